@@ -8,6 +8,8 @@ export class GerberViewer {
     this.fileInput = document.getElementById("file-input");
     this.selectFilesBtn = document.getElementById("select-files-btn");
     this.fitViewBtn = document.querySelector(".button-container button");
+    this.view3dBtn = document.getElementById("view3d-btn");
+    this.printScaleBtn = document.getElementById("print-scale-btn");
     this.selectAllBtn = document.getElementById("select-all-btn");
     this.unselectAllBtn = document.getElementById("unselect-all-btn");
     this.clearAllBtn = document.getElementById("clear-all-btn");
@@ -26,6 +28,17 @@ export class GerberViewer {
     // WASM module and single processor
     this.wasmModule = null;
     this.wasmProcessor = null;
+    this.gerberJob = null; // Last loaded Gerber job file (.gbrjob)
+    this.designBoard = null; // Last loaded board design (IPC-2581)
+    this.cutStart = null; // Cross-section cut line start while shift-dragging
+    this.orbitStart = null; // Pointer and orbit at the start of a 3D drag
+    this.printScaleDpi = null; // Display DPI while the 1:1 view is on
+
+    // Parse workers (created on first use)
+    this.parseWorkers = [];
+    this.nextParseWorker = 0;
+    this.parseRequests = new Map(); // Request ID -> { resolve, reject }
+    this.nextParseRequestId = 0;
 
     // Layers
     this.layers = [];
@@ -39,6 +52,7 @@ export class GerberViewer {
 
     // Interaction
     this.isPanning = false;
+    this.cameraAnimationRunning = false; // A requestAnimationFrame loop drives animate_to()
     this.lastMousePos = { x: 0, y: 0 };
 
     // Touch interaction
@@ -75,15 +89,14 @@ export class GerberViewer {
     await this.wasmModule.default();
     this.wasmModule.init_panic_hook();
 
-    // Create WebGL2 context
-    this.gl = this.canvas.getContext("webgl2");
-    if (!this.gl) {
-      throw new Error("WebGL2 not supported");
-    }
-
     // Initialize Gerber processor
     this.wasmProcessor = new this.wasmModule.GerberProcessor();
-    this.wasmProcessor.init(this.gl);
+    await this.initRenderer();
+    // Layers of one job mostly repeat the same aperture definitions
+    this.wasmProcessor.set_aperture_sharing(true);
+    this.wasmProcessor.set_event_callback((event) =>
+      this.handleViewerEvent(event),
+    );
 
     // Resize Canvas
     this.resizeCanvas();
@@ -95,6 +108,34 @@ export class GerberViewer {
     this.render();
   }
 
+  // Use WebGPU when the module was built with it and the browser supports
+  // it, otherwise WebGL2
+  async initRenderer() {
+    if (navigator.gpu && this.wasmProcessor.init_webgpu) {
+      try {
+        const adapter = await navigator.gpu.requestAdapter();
+        const device = adapter && (await adapter.requestDevice());
+        const context = device && this.canvas.getContext("webgpu");
+        if (context) {
+          this.wasmProcessor.init_webgpu(
+            device,
+            context,
+            navigator.gpu.getPreferredCanvasFormat(),
+          );
+          return;
+        }
+      } catch (error) {
+        console.warn("WebGPU unavailable, using WebGL2:", error);
+      }
+    }
+
+    this.gl = this.canvas.getContext("webgl2");
+    if (!this.gl) {
+      throw new Error("WebGL2 not supported");
+    }
+    this.wasmProcessor.init(this.gl);
+  }
+
   resizeCanvas() {
     const rect = this.canvas.getBoundingClientRect();
     this.canvas.width = rect.width;
@@ -104,6 +145,11 @@ export class GerberViewer {
       this.wasmProcessor.resize();
     }
 
+    // Keep 1 mm on screen equal to 1 mm of board at the new canvas size
+    if (this.printScaleDpi !== null) {
+      this.applyPrintScale();
+    }
+
     this.render();
   }
 
@@ -124,6 +170,16 @@ export class GerberViewer {
       this.fitView();
     });
 
+    // 3D view toggle
+    this.view3dBtn.addEventListener("click", () => {
+      this.toggle3dView();
+    });
+
+    // 1:1 print scale toggle
+    this.printScaleBtn.addEventListener("click", () => {
+      this.togglePrintScale();
+    });
+
     // Layer control buttons
     this.selectAllBtn.addEventListener("click", () => {
       this.selectAllLayerCheckboxes();
@@ -221,12 +277,42 @@ export class GerberViewer {
       this.showFileSizeWarning(oversizedFiles);
     }
 
+    // Gerber job files describe the other files instead of holding geometry
+    const jobFiles = validFiles.filter((file) =>
+      file.name.toLowerCase().endsWith(".gbrjob"),
+    );
+    // IPC-2581 and KiCad files hold a whole board and load as several layers
+    const designFiles = validFiles.filter((file) =>
+      /\.(xml|cvg|kicad_pcb)$/i.test(file.name),
+    );
+    const gerberFiles = validFiles.filter(
+      (file) => !jobFiles.includes(file) && !designFiles.includes(file),
+    );
+    for (const file of jobFiles) {
+      try {
+        const job = this.wasmModule.parse_gerber_job(await file.text());
+        if (this.gerberJob) this.gerberJob.free();
+        this.gerberJob = job;
+      } catch (error) {
+        console.error(`Failed to read job file ${file.name}:`, error);
+        this.showError(`Failed to read job file ${file.name}: ${error}`);
+      }
+    }
+
+    for (const file of designFiles) {
+      try {
+        this.addDesign(file.name, await file.text());
+      } catch (error) {
+        console.error(`Failed to load design ${file.name}:`, error);
+        this.showError(`Failed to load design ${file.name}: ${error}`);
+      }
+    }
+
     // Process valid files in parallel
-    if (validFiles.length > 0) {
-      const promises = validFiles.map(async (file) => {
+    if (gerberFiles.length > 0) {
+      const promises = gerberFiles.map(async (file) => {
         try {
-          const content = await file.text();
-          await this.addLayer(file.name, content);
+          await this.addLayerFromFile(file);
         } catch (error) {
           console.error(`Failed to load file ${file.name}:`, error);
           this.showError(`Failed to load file ${file.name}: ${error.message}`);
@@ -235,6 +321,12 @@ export class GerberViewer {
 
       await Promise.all(promises);
 
+      if (this.gerberJob) {
+        this.applyGerberJob(this.gerberJob);
+      }
+    }
+
+    if (gerberFiles.length > 0 || designFiles.length > 0) {
       // Render once after all layers are added
       this.renderLayerList();
       this.render();
@@ -245,6 +337,207 @@ export class GerberViewer {
     this.fileInput.value = "";
   }
 
+  /**
+   * Convert a client (mouse) position to board coordinates in mm.
+   */
+  screenToWorld(clientX, clientY) {
+    const rect = this.canvas.getBoundingClientRect();
+    const xNDC = ((clientX - rect.left) / rect.width) * 2 - 1;
+    const yNDC = -(((clientY - rect.top) / rect.height) * 2 - 1);
+    const aspect = this.canvas.width / this.canvas.height;
+
+    // Undo the aspect correction applied by the camera transform
+    const x = aspect > 1.0 ? xNDC * aspect : xNDC;
+    const y = aspect > 1.0 ? yNDC : yNDC / aspect;
+    return {
+      x: (x - this.camera.offsetX) / this.camera.zoom,
+      y: (y - this.camera.offsetY) / this.camera.zoom,
+    };
+  }
+
+  /**
+   * Build the board stackup for the cross-section and 3D views. A loaded
+   * design's stackup comes first, then the job's material stackup with layers
+   * named in the job linked to their stackup layer. Without either, every
+   * visible layer becomes a thin copper layer stacked in list order.
+   */
+  buildStackup() {
+    if (this.designBoard && this.designBoard.stackup_count > 0) {
+      return this.designBoard.stackup();
+    }
+
+    const job = this.gerberJob;
+    if (job && job.stackup_count > 0) {
+      const stackup = this.wasmModule.Stackup.from_job(job);
+      this.layers.forEach((layer) => {
+        const index = job.find_file(layer.name);
+        if (index === undefined) return;
+        const entry = stackup.layer_for_function(job.file_function(index));
+        if (entry !== undefined) {
+          stackup.assign_layer(entry, layer.layerId);
+        }
+      });
+      return stackup;
+    }
+
+    const stackup = new this.wasmModule.Stackup();
+    this.layers
+      .filter((layer) => layer.visible)
+      .forEach((layer) => {
+        const entry = stackup.add_layer("Copper", 0.035, "", layer.name);
+        stackup.assign_layer(entry, layer.layerId);
+      });
+    return stackup;
+  }
+
+  /**
+   * Whether a loaded design or job file describes the material stackup.
+   */
+  hasStackup() {
+    return (
+      (this.designBoard !== null && this.designBoard.stackup_count > 0) ||
+      (this.gerberJob !== null && this.gerberJob.stackup_count > 0)
+    );
+  }
+
+  /**
+   * Show the loaded stackup cut along a line. Linked layers make copper, mask
+   * and legend appear only where present.
+   */
+  showCrossSection(start, end) {
+    if (start.x === end.x && start.y === end.y) {
+      this.wasmProcessor.hide_cross_section();
+      this.render();
+      return;
+    }
+
+    if (!this.hasStackup()) {
+      this.showError("The loaded files do not describe a material stackup");
+      return;
+    }
+
+    const stackup = this.buildStackup();
+    try {
+      const section = this.wasmProcessor.cross_section(
+        stackup,
+        start.x,
+        start.y,
+        end.x,
+        end.y,
+      );
+      this.wasmProcessor.show_cross_section(section);
+      section.free();
+      this.render();
+    } catch (error) {
+      console.error("[CrossSection] Failed to show cross-section:", error);
+    } finally {
+      stackup.free();
+    }
+  }
+
+  /**
+   * Toggle the 1:1 view, where 1 mm on screen equals 1 mm of board.
+   * Asks for the display's physical DPI once and remembers it.
+   */
+  togglePrintScale() {
+    if (!this.wasmProcessor) return;
+
+    if (this.printScaleDpi !== null) {
+      this.exitPrintScale();
+      return;
+    }
+
+    const stored = localStorage.getItem("printScaleDpi");
+    const input = prompt(
+      "Display resolution in pixels per inch (measure a ruler on screen to calibrate):",
+      stored || String(Math.round(96 * window.devicePixelRatio)),
+    );
+    const dpi = parseFloat(input);
+    if (!(dpi > 0)) return;
+
+    localStorage.setItem("printScaleDpi", String(dpi));
+    this.printScaleDpi = dpi;
+    this.printScaleBtn.classList.add("active");
+    this.applyPrintScale();
+    this.render();
+  }
+
+  /**
+   * Set the 1:1 zoom, keeping the board point at the view center in place.
+   */
+  applyPrintScale() {
+    try {
+      // Canvas pixels are CSS pixels, each devicePixelRatio device pixels wide
+      const zoom = this.wasmProcessor.print_scale_zoom(
+        this.printScaleDpi / window.devicePixelRatio,
+        1.0,
+      );
+      const ratio = zoom / this.camera.zoom;
+      this.camera.offsetX *= ratio;
+      this.camera.offsetY *= ratio;
+      this.camera.zoom = zoom;
+    } catch (error) {
+      console.error("[PrintScale] Failed to set 1:1 zoom:", error);
+    }
+  }
+
+  exitPrintScale() {
+    this.printScaleDpi = null;
+    this.printScaleBtn.classList.remove("active");
+  }
+
+  /**
+   * Switch between the 2D view and an exploded 3D view of the stackup.
+   */
+  toggle3dView() {
+    if (!this.wasmProcessor) return;
+
+    if (this.wasmProcessor.is_3d()) {
+      this.wasmProcessor.hide_3d();
+      this.view3dBtn.classList.remove("active");
+      this.render();
+      return;
+    }
+
+    const stackup = this.buildStackup();
+    try {
+      if (stackup.count === 0) {
+        this.showError("Load a layer to view the board in 3D");
+        return;
+      }
+      // Spread layers apart so thin copper stays distinguishable
+      const separation = stackup.total_thickness / stackup.count;
+      this.wasmProcessor.show_3d(stackup, Math.max(separation, 0.1), undefined);
+      this.view3dBtn.classList.add("active");
+      this.render();
+    } catch (error) {
+      console.error("[View3D] Failed to show 3D view:", error);
+      this.showError(`Failed to show 3D view: ${error}`);
+    } finally {
+      stackup.free();
+    }
+  }
+
+  /**
+   * Order and color layers by the file functions listed in a Gerber job file.
+   * Layers named in the job are sorted top side first; others keep their
+   * color and follow in upload order.
+   */
+  applyGerberJob(job) {
+    const rank = (layer) => {
+      const index = job.find_file(layer.name);
+      return index === undefined ? job.file_count : index;
+    };
+
+    this.layers.forEach((layer) => {
+      const index = job.find_file(layer.name);
+      if (index !== undefined) {
+        layer.color = Array.from(job.file_color(index));
+      }
+    });
+    this.layers.sort((a, b) => rank(a) - rank(b));
+  }
+
   formatFileSize(bytes) {
     if (bytes === 0) return "0 Bytes";
     const k = 1024;
@@ -287,6 +580,20 @@ export class GerberViewer {
     }, 5000);
   }
 
+  handleViewerEvent(event) {
+    switch (event.type) {
+      case "context_lost":
+        this.showError("WebGL context lost. Reload the page to continue.");
+        break;
+      case "memory_warning":
+        this.showError(
+          `Loaded geometry uses ${this.formatFileSize(event.geometry_bytes)}. ` +
+            "Remove unused layers to avoid running out of memory.",
+        );
+        break;
+    }
+  }
+
   async addLayer(name, content) {
     try {
       // add layer to WASM processor and get layer ID
@@ -294,35 +601,157 @@ export class GerberViewer {
       if (layerId === undefined || layerId === null) {
         throw new Error("Failed to get layer ID from WASM processor");
       }
+      this.registerLayer(name, layerId);
+    } catch (error) {
+      console.error(`[Layer] Failed to add layer ${name}:`, error);
+      throw error;
+    }
+  }
 
-      // Get layer boundary from WASM
-      const bounds = this.wasmProcessor.get_boundary();
-
-      const color =
-        this.colorPalette[this.nextColorIndex % this.colorPalette.length];
-      this.nextColorIndex++;
-
-      const layer = {
-        id: `layer-${layerId}`,
-        layerId: layerId, // WASM layer_id
-        name: name,
-        visible: true,
-        color: color,
-        bounds: {
-          minX: bounds.min_x,
-          maxX: bounds.max_x,
-          minY: bounds.min_y,
-          maxY: bounds.max_y,
-        },
-      };
+  /**
+   * Parse a layer on the main thread a chunk per frame, rendering the
+   * geometry parsed so far so the board appears while it loads.
+   */
+  async addLayerProgressive(name, content) {
+    const LINES_PER_FRAME = 20000;
+    const layerId = this.wasmProcessor.add_layer_progressive(content);
+    if (!this.registerLayer(name, layerId)) return;
+    const layer = this.layers[this.layers.length - 1];
+    this.renderLayerList();
 
-      this.layers.push(layer);
+    try {
+      while (!this.wasmProcessor.continue_layer(layerId, LINES_PER_FRAME)) {
+        this.render();
+        await new Promise((resolve) => requestAnimationFrame(resolve));
+        // Stop if the layer was deleted while loading
+        if (!this.layers.includes(layer)) return;
+      }
     } catch (error) {
-      console.error(`[Layer] Failed to add layer ${name}:`, error);
+      const index = this.layers.indexOf(layer);
+      if (index !== -1) this.layers.splice(index, 1);
+      this.renderLayerList();
       throw error;
     }
   }
 
+  /**
+   * Parse a Gerber file in a worker and add the transferred geometry, keeping
+   * the UI responsive. Falls back to parsing on the main thread without
+   * worker support.
+   */
+  async addLayerFromFile(file) {
+    let bytes;
+    try {
+      bytes = await this.parseInWorker(file);
+    } catch (error) {
+      if (!error.workerUnavailable) throw new Error(error.message);
+      await this.addLayerProgressive(file.name, await file.text());
+      return;
+    }
+    const layerId = this.wasmProcessor.add_layer_from_bytes(bytes);
+    this.registerLayer(file.name, layerId);
+  }
+
+  /**
+   * Send a file to the next parse worker, resolving with its geometry bytes.
+   */
+  parseInWorker(file) {
+    if (this.parseWorkers.length === 0) {
+      try {
+        const count = Math.min(navigator.hardwareConcurrency || 2, 4);
+        for (let i = 0; i < count; i++) {
+          const worker = new Worker(
+            new URL("./parse_worker.js", import.meta.url),
+            { type: "module" },
+          );
+          worker.onmessage = (e) => this.handleParseResult(e.data);
+          worker.onerror = (e) => this.failParseWorkers(e);
+          this.parseWorkers.push(worker);
+        }
+      } catch (error) {
+        console.warn("[Worker] Parsing on the main thread:", error);
+        return Promise.reject({ workerUnavailable: true });
+      }
+    }
+
+    const id = this.nextParseRequestId++;
+    const worker = this.parseWorkers[this.nextParseWorker];
+    this.nextParseWorker = (this.nextParseWorker + 1) % this.parseWorkers.length;
+    return new Promise((resolve, reject) => {
+      this.parseRequests.set(id, { resolve, reject });
+      worker.postMessage({ id, file });
+    });
+  }
+
+  /**
+   * Drop the worker pool after a worker failed to load; pending files are
+   * parsed on the main thread instead.
+   */
+  failParseWorkers(error) {
+    console.warn("[Worker] Parse worker failed:", error.message || error);
+    for (const worker of this.parseWorkers) worker.terminate();
+    this.parseWorkers = [];
+    this.nextParseWorker = 0;
+    for (const request of this.parseRequests.values()) {
+      request.reject({ workerUnavailable: true });
+    }
+    this.parseRequests.clear();
+  }
+
+  handleParseResult({ id, bytes, error }) {
+    const request = this.parseRequests.get(id);
+    if (!request) return;
+    this.parseRequests.delete(id);
+    if (error !== undefined) {
+      request.reject({ message: error });
+    } else {
+      request.resolve(bytes);
+    }
+  }
+
+  /**
+   * Load every layer of a board design file (IPC-2581 or KiCad). Its stackup,
+   * linked to the new layers, is used by the cross-section and 3D views.
+   */
+  addDesign(fileName, content) {
+    const board = fileName.toLowerCase().endsWith(".kicad_pcb")
+      ? this.wasmProcessor.add_kicad_pcb(content)
+      : this.wasmProcessor.add_ipc2581(content);
+    board.layer_ids.forEach((layerId, index) => {
+      this.registerLayer(`${fileName}: ${board.layer_name(index)}`, layerId);
+    });
+    if (this.designBoard) this.designBoard.free();
+    this.designBoard = board;
+  }
+
+  /**
+   * Add a layer loaded into the WASM processor to the layer list. Returns
+   * false for a file that was already loaded (the processor hands back the
+   * existing layer instead of stacking a copy).
+   */
+  registerLayer(name, layerId) {
+    const existing = this.layers.find((layer) => layer.layerId === layerId);
+    if (existing) {
+      this.showError(`${name} is already loaded as ${existing.name}`);
+      return false;
+    }
+
+    const color =
+      this.colorPalette[this.nextColorIndex % this.colorPalette.length];
+    this.nextColorIndex++;
+
+    const layer = {
+      id: `layer-${layerId}`,
+      layerId: layerId, // WASM layer_id
+      name: name,
+      visible: true,
+      color: color,
+    };
+
+    this.layers.push(layer);
+    return true;
+  }
+
   render() {
     if (!this.wasmProcessor) return;
 
@@ -370,6 +799,8 @@ export class GerberViewer {
   }
 
   fitView() {
+    this.exitPrintScale();
+
     // Get selected layer IDs
     const selectedLayerIds = this.getSelectedLayerIds();
 
@@ -386,20 +817,16 @@ export class GerberViewer {
       return;
     }
 
-    // Calculate combined bounds from all selected layers
-    let minX = Infinity;
-    let maxX = -Infinity;
-    let minY = Infinity;
-    let maxY = -Infinity;
-
-    for (const layer of selectedLayers) {
-      if (layer.bounds) {
-        minX = Math.min(minX, layer.bounds.minX);
-        maxX = Math.max(maxX, layer.bounds.maxX);
-        minY = Math.min(minY, layer.bounds.minY);
-        maxY = Math.max(maxY, layer.bounds.maxY);
-      }
-    }
+    // Combined bounds of the visible layers only, so hidden far-off layers
+    // don't widen the view
+    const bounds = this.wasmProcessor.get_boundary_for(
+      Uint32Array.from(selectedLayers, (layer) => layer.layerId),
+    );
+    const minX = bounds.min_x;
+    const maxX = bounds.max_x;
+    const minY = bounds.min_y;
+    const maxY = bounds.max_y;
+    bounds.free();
 
     if (
       !isFinite(minX) ||
@@ -410,50 +837,74 @@ export class GerberViewer {
       return;
     }
 
-    const boundsWidth = maxX - minX;
-    const boundsHeight = maxY - minY;
-    const centerX = (minX + maxX) / 2;
-    const centerY = (minY + maxY) / 2;
-
     if (this.canvas.width === 0 || this.canvas.height === 0) {
       return;
     }
 
-    if (boundsWidth === 0 && boundsHeight === 0) {
-      // Can't fit to a single point, just center it
-      this.camera.zoom = 2.0;
-      this.camera.offsetX = -centerX * this.camera.zoom;
-      this.camera.offsetY = -centerY * this.camera.zoom;
-      this.render();
-      return;
+    try {
+      const view = this.wasmProcessor.zoom_to_bounds(minX, minY, maxX, maxY);
+      this.animateCamera(view, 250);
+      view.free();
+    } catch (error) {
+      console.error("[FitView] Failed to fit view:", error);
     }
+  }
 
-    const canvasAspect = this.canvas.width / this.canvas.height;
+  /**
+   * Tween the camera to a view, rendering every frame until it arrives.
+   */
+  animateCamera(view, durationMs) {
+    this.wasmProcessor.animate_to(view, durationMs);
+    if (this.cameraAnimationRunning) return;
 
-    let zoom;
-    if (boundsWidth === 0) {
-      zoom = (2.0 / boundsHeight) * 0.9;
-    } else if (boundsHeight === 0) {
-      zoom = (2.0 / boundsWidth) * 0.9;
-    } else {
-      const boundsAspect = boundsWidth / boundsHeight;
-      if (boundsAspect > canvasAspect) {
-        zoom = (2.0 / boundsWidth) * 0.9;
+    this.cameraAnimationRunning = true;
+    const step = () => {
+      this.render();
+      // Keep the camera in sync with the animated frames
+      const current = this.wasmProcessor.camera_view();
+      if (current) {
+        this.camera.zoom = current.zoom;
+        this.camera.offsetX = current.offset_x;
+        this.camera.offsetY = current.offset_y;
+        current.free();
+      }
+      if (this.wasmProcessor.is_animating()) {
+        requestAnimationFrame(step);
       } else {
-        zoom = (2.0 / boundsHeight) * 0.9;
+        this.cameraAnimationRunning = false;
       }
-    }
-
-    this.camera.zoom = zoom;
-    this.camera.offsetX = -centerX * zoom;
-    this.camera.offsetY = -centerY * zoom;
+    };
+    requestAnimationFrame(step);
+  }
 
-    this.render();
+  // User input takes over the camera from a running animation
+  stopCameraAnimation() {
+    if (this.wasmProcessor && this.wasmProcessor.is_animating()) {
+      this.wasmProcessor.stop_animation();
+    }
   }
 
   handleWheel(e) {
     e.preventDefault();
 
+    // In 3D the wheel moves the eye towards or away from the orbit center
+    if (this.wasmProcessor && this.wasmProcessor.is_3d()) {
+      const [yaw, pitch, distance, targetX, targetY] =
+        this.wasmProcessor.get_orbit();
+      const factor = e.deltaY > 0 ? 1.1 : 1 / 1.1;
+      this.wasmProcessor.set_orbit(
+        yaw,
+        pitch,
+        distance * factor,
+        targetX,
+        targetY,
+      );
+      this.render();
+      return;
+    }
+
+    this.stopCameraAnimation();
+
     const rect = this.canvas.getBoundingClientRect();
     if (rect.width === 0 || rect.height === 0) {
       return;
@@ -480,6 +931,7 @@ export class GerberViewer {
     }
 
     const zoomChange = 1 + (e.deltaY<0?1:-1) * Math.sqrt(Math.abs(e.deltaY)) * 0.02;
+    this.exitPrintScale();
 
     const prevZoom = this.camera.zoom;
     const newZoom = prevZoom * zoomChange;
@@ -497,12 +949,46 @@ export class GerberViewer {
 
   handleMouseDown(e) {
     if (e.button === 2) return; // Ignore right-click
+
+    // Dragging in 3D orbits the camera around the board
+    if (this.wasmProcessor && this.wasmProcessor.is_3d()) {
+      this.orbitStart = {
+        x: e.clientX,
+        y: e.clientY,
+        orbit: this.wasmProcessor.get_orbit(),
+      };
+      return;
+    }
+
+    // Shift+drag draws a cross-section cut line when a stackup is known
+    if (e.shiftKey && this.hasStackup()) {
+      this.cutStart = this.screenToWorld(e.clientX, e.clientY);
+      return;
+    }
+
+    this.stopCameraAnimation();
     this.isPanning = true;
     this.lastMousePos.x = e.clientX;
     this.lastMousePos.y = e.clientY;
   }
 
   handleMouseMove(e) {
+    if (this.orbitStart) {
+      const [yaw, pitch, distance, targetX, targetY] = this.orbitStart.orbit;
+      const deltaX = e.clientX - this.orbitStart.x;
+      const deltaY = e.clientY - this.orbitStart.y;
+      // Half a degree per pixel; dragging up tilts the board towards the viewer
+      this.wasmProcessor.set_orbit(
+        yaw - deltaX * 0.5,
+        pitch + deltaY * 0.5,
+        distance,
+        targetX,
+        targetY,
+      );
+      this.render();
+      return;
+    }
+
     if (!this.isPanning) return;
 
     const deltaX = e.clientX - this.lastMousePos.x;
@@ -513,6 +999,18 @@ export class GerberViewer {
   }
 
   handleMouseUp(e) {
+    if (this.orbitStart) {
+      this.orbitStart = null;
+      return;
+    }
+
+    if (this.cutStart) {
+      const end = this.screenToWorld(e.clientX, e.clientY);
+      this.showCrossSection(this.cutStart, end);
+      this.cutStart = null;
+      return;
+    }
+
     if (!this.isPanning) return;
 
     this.isPanning = false;
@@ -546,6 +1044,7 @@ export class GerberViewer {
   // Touch event handlers
   handleTouchStart(e) {
     e.preventDefault();
+    this.stopCameraAnimation();
 
     this.isTouching = true;
     this.touches = Array.from(e.touches);
@@ -614,6 +1113,7 @@ export class GerberViewer {
 
         // Apply zoom
         const zoomChange = currentDistance / this.lastPinchDistance;
+        this.exitPrintScale();
         const prevZoom = this.camera.zoom;
         const newZoom = prevZoom * zoomChange;
         const zoomRatio = newZoom / prevZoom;
@@ -760,6 +1260,15 @@ export class GerberViewer {
 
       this.layers = [];
       this.nextColorIndex = 0;
+      this.view3dBtn.classList.remove("active");
+      if (this.gerberJob) {
+        this.gerberJob.free();
+        this.gerberJob = null;
+      }
+      if (this.designBoard) {
+        this.designBoard.free();
+        this.designBoard = null;
+      }
       this.renderLayerList();
       this.render();
     } catch (error) {
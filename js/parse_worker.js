@@ -0,0 +1,16 @@
+// Parses Gerber files off the main thread. Replies with the geometry bytes
+// from parse_to_bytes(), transferring the buffer instead of copying it.
+import init, { parse_to_bytes } from "../wasm/pkg/wasm_gerber_processor.js";
+
+const ready = init();
+
+self.onmessage = async (e) => {
+  const { id, file } = e.data;
+  try {
+    await ready;
+    const bytes = parse_to_bytes(await file.text());
+    self.postMessage({ id, bytes }, [bytes.buffer]);
+  } catch (error) {
+    self.postMessage({ id, error: String(error) });
+  }
+};
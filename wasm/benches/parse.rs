@@ -0,0 +1,129 @@
+//! Parsing and tessellation timings on generated layers
+//!
+//! Native only: `cargo bench --bench parse`. Each case parses a fresh
+//! `GerberProcessor`, so aperture caches do not carry over between runs.
+//! Compare against a baseline with `-- --save-baseline before` and
+//! `-- --baseline before`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use std::fmt::Write;
+use std::hint::black_box;
+use wasm_gerber_processor::GerberProcessor;
+
+const HEADER: &str = "%FSLAX36Y36*%\n%MOMM*%\n";
+
+/// Routed board: long traces of straight and circular segments
+fn large_file() -> String {
+    let mut data = String::from(HEADER);
+    data.push_str("%ADD10C,0.150*%\n%ADD11C,0.250*%\n%ADD12C,0.500*%\nG75*\n");
+    for net in 0..5_000 {
+        let y = net as i64 * 200_000;
+        writeln!(data, "D{}*\nX0Y{}D02*", 10 + net % 3, y).unwrap();
+        for step in 1..=8 {
+            let x = step * 2_500_000;
+            if step % 4 == 0 {
+                writeln!(data, "G03X{}Y{}I1250000J0D01*\nG01*", x, y).unwrap();
+            } else {
+                writeln!(data, "X{}Y{}D01*", x, y + (step % 2) * 100_000).unwrap();
+            }
+        }
+    }
+    data.push_str("M02*\n");
+    data
+}
+
+/// Fine-pitch pads: every standard aperture flashed many times
+fn flash_heavy() -> String {
+    let mut data = String::from(HEADER);
+    data.push_str(
+        "%ADD10C,0.300*%\n%ADD11R,0.600X0.300*%\n%ADD12O,0.800X0.400*%\n\
+         %ADD13P,0.500X8*%\n%ADD14C,0.600X0.300*%\n",
+    );
+    for i in 0..100_000 {
+        let (x, y) = ((i % 400) * 1_000_000, (i / 400) * 1_000_000);
+        writeln!(data, "D{}*\nX{}Y{}D03*", 10 + i % 5, x, y).unwrap();
+    }
+    data.push_str("M02*\n");
+    data
+}
+
+/// Copper pours: regions with arc corners and cut-outs
+fn regions() -> String {
+    let mut data = String::from(HEADER);
+    for i in 0..1_000 {
+        let (x, y) = ((i % 40) * 6_000_000, (i / 40) * 6_000_000);
+        writeln!(
+            data,
+            "%LPD*%\nG36*\nX{x0}Y{y0}D02*\nG01X{x1}Y{y0}D01*\nG75*\nG03X{x2}Y{y1}I0J500000D01*\n\
+             G01X{x2}Y{y2}D01*\nX{x0}Y{y2}D01*\nX{x0}Y{y0}D01*\nG37*\n%LPC*%\nG36*\n\
+             X{x3}Y{y3}D02*\nG01X{x4}Y{y3}D01*\nX{x4}Y{y4}D01*\nX{x3}Y{y4}D01*\nX{x3}Y{y3}D01*\nG37*",
+            x0 = x,
+            x1 = x + 4_500_000,
+            x2 = x + 5_000_000,
+            x3 = x + 1_000_000,
+            x4 = x + 2_000_000,
+            y0 = y,
+            y1 = y + 500_000,
+            y2 = y + 5_000_000,
+            y3 = y + 1_000_000,
+            y4 = y + 2_000_000,
+        )
+        .unwrap();
+    }
+    data.push_str("M02*\n");
+    data
+}
+
+/// Macro apertures whose clear primitives go through boolean evaluation
+fn boolean_macros() -> String {
+    let mut data = String::from(HEADER);
+    data.push_str(
+        "%AMSLOTPAD*\n21,1,$1,$2,0,0,0*\n21,0,$3,0.2,0,0,0*\n21,0,0.2,$3,0,0,0*\n\
+         1,1,0.3,$1/2,0*\n7,0,0,$2,$2/2,0.1,45*%\n",
+    );
+    // Distinct parameters, so every aperture is evaluated on its own
+    for code in 0..500 {
+        let size = 1.0 + code as f32 * 0.002;
+        writeln!(
+            data,
+            "%ADD{}SLOTPAD,{:.3}X{:.3}X{:.3}*%",
+            10 + code,
+            size,
+            size * 0.6,
+            size * 0.4
+        )
+        .unwrap();
+    }
+    for code in 0..500 {
+        let (x, y) = ((code % 25) * 3_000_000, (code / 25) * 3_000_000);
+        writeln!(data, "D{}*\nX{}Y{}D03*", 10 + code, x, y).unwrap();
+    }
+    data.push_str("M02*\n");
+    data
+}
+
+fn parse(c: &mut Criterion) {
+    let cases = [
+        ("large_file", large_file()),
+        ("flash_heavy", flash_heavy()),
+        ("region_triangulation", regions()),
+        ("boolean_macros", boolean_macros()),
+    ];
+
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+    for (name, data) in &cases {
+        group.throughput(Throughput::Bytes(data.len() as u64));
+        group.bench_function(*name, |b| {
+            b.iter_batched(
+                || (GerberProcessor::new(), data.clone()),
+                |(mut processor, data)| black_box(processor.add_layer(data).unwrap()),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
@@ -0,0 +1,94 @@
+//! Vectorized geometry loops against their scalar versions
+//!
+//! Native builds have no SIMD path, so run this for wasm to compare:
+//! `cargo bench --bench simd --target wasm32-wasip1 --no-default-features`
+//! with a runner such as `CARGO_TARGET_WASM32_WASIP1_RUNNER=wasmtime`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use wasm_gerber_processor::simd;
+use wasm_gerber_processor::Boundary;
+
+const COUNT: usize = 1 << 16;
+const ROUNDS: u32 = 200;
+
+fn time(mut f: impl FnMut()) -> Duration {
+    f(); // Warm up
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        f();
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn report(name: &str, scalar: Duration, simd: Duration) {
+    println!(
+        "{:<20} scalar {:>9.1?}  simd {:>9.1?}  x{:.2}",
+        name,
+        scalar,
+        simd,
+        scalar.as_secs_f64() / simd.as_secs_f64()
+    );
+}
+
+fn empty_bounds() -> Boundary {
+    Boundary::new(
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+    )
+}
+
+fn main() {
+    let x: Vec<f32> = (0..COUNT)
+        .map(|i| (i as f32 * 0.37).sin() * 100.0)
+        .collect();
+    let y: Vec<f32> = (0..COUNT).map(|i| (i as f32 * 0.53).cos() * 80.0).collect();
+    let r: Vec<f32> = (0..COUNT).map(|i| 0.1 + (i % 7) as f32 * 0.05).collect();
+    let xy = simd::scalar::interleave_xy(&x, &y);
+
+    println!("SIMD enabled: {}", simd::ENABLED);
+
+    let mut points = xy.clone();
+    report(
+        "transform_points",
+        time(|| simd::scalar::transform_points(black_box(&mut points), 1.0, 0.5, -0.5)),
+        time(|| simd::transform_points(black_box(&mut points), 1.0, 0.5, -0.5)),
+    );
+    report(
+        "interleave_xy",
+        time(|| {
+            black_box(simd::scalar::interleave_xy(black_box(&x), black_box(&y)));
+        }),
+        time(|| {
+            black_box(simd::interleave_xy(black_box(&x), black_box(&y)));
+        }),
+    );
+    report(
+        "extend_point_bounds",
+        time(|| {
+            let mut bounds = empty_bounds();
+            simd::scalar::extend_point_bounds(&mut bounds, black_box(&xy));
+            black_box(bounds);
+        }),
+        time(|| {
+            let mut bounds = empty_bounds();
+            simd::extend_point_bounds(&mut bounds, black_box(&xy));
+            black_box(bounds);
+        }),
+    );
+    report(
+        "extend_disc_bounds",
+        time(|| {
+            let mut bounds = empty_bounds();
+            simd::scalar::extend_disc_bounds(&mut bounds, &x, &y, black_box(&r));
+            black_box(bounds);
+        }),
+        time(|| {
+            let mut bounds = empty_bounds();
+            simd::extend_disc_bounds(&mut bounds, &x, &y, black_box(&r));
+            black_box(bounds);
+        }),
+    );
+}
@@ -0,0 +1,14 @@
+//! Aperture macro bodies instantiated and flashed with fuzzed parameters
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wasm_gerber_processor::fuzzing::flash_macro;
+
+fuzz_target!(|input: (&str, &str)| {
+    let (body, params) = input;
+    // A '%' would end the macro definition early
+    if !body.contains('%') && !params.contains('%') {
+        let _ = flash_macro(body, params);
+    }
+});
@@ -0,0 +1,11 @@
+//! Aperture macro arithmetic, e.g. `$1X2+($2-$3)/2`
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wasm_gerber_processor::fuzzing::evaluate_expression;
+
+fuzz_target!(|input: (&str, [f32; 4])| {
+    let (expr, params) = input;
+    let _ = evaluate_expression(expr, &params);
+});
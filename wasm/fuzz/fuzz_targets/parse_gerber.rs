@@ -0,0 +1,13 @@
+//! Whole Gerber files, seeded from tests/corpus:
+//! `cargo fuzz run parse_gerber ../tests/corpus`
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wasm_gerber_processor::fuzzing::parse_gerber;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(text) = std::str::from_utf8(data) {
+        let _ = parse_gerber(text);
+    }
+});
@@ -0,0 +1,50 @@
+mod alignment;
+mod annular_ring;
+mod compare;
+mod connectivity;
+mod contours;
+#[cfg(feature = "renderer")]
+mod courtyards;
+mod coverage;
+mod cross_section;
+mod density;
+mod drills;
+mod exposed_copper;
+mod fiducials;
+mod flashes;
+mod histogram;
+mod inferred_drills;
+mod markers;
+mod mask_silk;
+mod outliers;
+mod smallest;
+mod test_points;
+mod traces;
+mod width_spacing;
+
+// Export only what's needed externally
+pub use alignment::{estimate_alignment, LayerAlignment};
+pub use annular_ring::{check_annular_ring, AnnularRingReport};
+pub use compare::{compare_layers, LayerComparison};
+pub use connectivity::{connectivity, Connectivity};
+pub use contours::{extract_contours, merged_shapes, Contours};
+#[cfg(feature = "renderer")]
+pub use courtyards::{courtyard_lines, ComponentSide, CourtyardLines};
+#[cfg(feature = "headless")]
+pub use coverage::CoverageIndex;
+pub use cross_section::{cross_section, CrossSection};
+pub use density::{copper_density, CopperDensity};
+pub use drills::{drill_summary, DrillSummary};
+pub use exposed_copper::{exposed_copper, ExposedCopperReport};
+pub use fiducials::{find_fiducials, FiducialList};
+pub use flashes::{collect_flashes, FlashList};
+pub use histogram::{size_histogram, SizeHistogram};
+pub use inferred_drills::{infer_drills, InferredDrills};
+#[cfg(feature = "renderer")]
+pub use markers::DrcMarkers;
+pub use mask_silk::{check_mask_silk, MaskSilkReport};
+pub use outliers::{find_outliers, BoundaryOutliers};
+pub use smallest::{smallest_features, SmallestFeatures};
+pub use test_points::{find_test_points, TestPointList};
+pub use traces::{collect_traces, TraceList};
+pub use width_spacing::{check_width_spacing, WidthSpacingReport};
@@ -0,0 +1,215 @@
+use crate::shape::GerberData;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+// Offset vote bin size in mm
+const VOTE_BIN: f32 = 0.1;
+// Flashes of each layer taken into the pairwise vote
+const MAX_VOTE_FLASHES: usize = 400;
+// Distance within which a moved flash counts as matching a reference flash (mm)
+const MATCH_TOLERANCE: f32 = 2.0 * VOTE_BIN;
+// Fewest matching flashes accepted as an alignment
+const MIN_MATCHES: usize = 3;
+
+/// Transform that moves a layer's flashes onto a reference layer's flashes
+///
+/// The layer is turned counterclockwise about the origin by `rotation`
+/// degrees, then moved by (dx, dy) mm.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct LayerAlignment {
+    pub(crate) dx: f32,
+    pub(crate) dy: f32,
+    pub(crate) quarter_turns: u32,
+    pub(crate) matched: usize,
+    pub(crate) flashes: usize,
+}
+
+#[wasm_bindgen]
+impl LayerAlignment {
+    #[wasm_bindgen(getter)]
+    pub fn dx(&self) -> f32 {
+        self.dx
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn dy(&self) -> f32 {
+        self.dy
+    }
+
+    /// Rotation in degrees counterclockwise: 0, 90, 180 or 270
+    #[wasm_bindgen(getter)]
+    pub fn rotation(&self) -> u32 {
+        self.quarter_turns * 90
+    }
+
+    /// Flashes of the layer that land on a reference flash after the transform
+    #[wasm_bindgen(getter)]
+    pub fn matched(&self) -> usize {
+        self.matched
+    }
+
+    /// Flashes of the layer considered
+    #[wasm_bindgen(getter)]
+    pub fn flashes(&self) -> usize {
+        self.flashes
+    }
+}
+
+/// Estimate the quarter turn and translation that best match the dark flashes
+/// of `layer` to those of `reference`
+///
+/// Every quarter turn is tried: pairs of sampled flashes vote for the offset
+/// between them, and the winning offset is refined by the mean residual of
+/// the flashes it matches. A turn is only chosen over no rotation if it
+/// matches more flashes. Returns an error message when either layer has no
+/// flashes or no offset matches enough of them.
+pub fn estimate_alignment(
+    layer: &[GerberData],
+    reference: &[GerberData],
+) -> Result<LayerAlignment, String> {
+    let points = dark_flashes(layer);
+    let targets = dark_flashes(reference);
+    if points.is_empty() {
+        return Err("Layer has no flashes to align".to_string());
+    }
+    if targets.is_empty() {
+        return Err("Reference layer has no flashes to align to".to_string());
+    }
+    let grid = PointGrid::new(&targets);
+
+    let mut best: Option<LayerAlignment> = None;
+    for quarter_turns in 0..4 {
+        let turned: Vec<[f32; 2]> = points
+            .iter()
+            .map(|&point| turn(point, quarter_turns))
+            .collect();
+        let (mut dx, mut dy) = vote_offset(&turned, &targets);
+
+        // Refine twice: the vote bin only places the offset to within a bin
+        let mut matched = 0;
+        for _ in 0..2 {
+            let (count, sum_x, sum_y) = grid.residuals(&turned, dx, dy);
+            matched = count;
+            if count > 0 {
+                dx += sum_x / count as f32;
+                dy += sum_y / count as f32;
+            }
+        }
+        if best.is_none_or(|best| matched > best.matched) {
+            best = Some(LayerAlignment {
+                dx,
+                dy,
+                quarter_turns,
+                matched,
+                flashes: points.len(),
+            });
+        }
+    }
+
+    match best {
+        Some(alignment) if alignment.matched >= MIN_MATCHES.min(points.len()) => Ok(alignment),
+        _ => Err("No offset matches the layer's flashes to the reference".to_string()),
+    }
+}
+
+/// Flash positions of the dark (even) sublayers
+fn dark_flashes(sublayers: &[GerberData]) -> Vec<[f32; 2]> {
+    sublayers
+        .iter()
+        .step_by(2)
+        .flat_map(|data| data.flashes.x.iter().zip(&data.flashes.y))
+        .map(|(&x, &y)| [x, y])
+        .collect()
+}
+
+/// Point turned counterclockwise about the origin by quarter turns
+fn turn([x, y]: [f32; 2], quarter_turns: u32) -> [f32; 2] {
+    match quarter_turns % 4 {
+        1 => [-y, x],
+        2 => [-x, -y],
+        3 => [y, -x],
+        _ => [x, y],
+    }
+}
+
+/// Up to `max` points spread evenly over `points`
+fn sample(points: &[[f32; 2]], max: usize) -> impl Iterator<Item = &[f32; 2]> {
+    points.iter().step_by(points.len().div_ceil(max).max(1))
+}
+
+/// Offset with the most flash pairs, to within a vote bin
+fn vote_offset(points: &[[f32; 2]], targets: &[[f32; 2]]) -> (f32, f32) {
+    let mut votes: HashMap<(i32, i32), u32> = HashMap::new();
+    for [x, y] in sample(points, MAX_VOTE_FLASHES) {
+        for [tx, ty] in sample(targets, MAX_VOTE_FLASHES) {
+            let bin = (
+                ((tx - x) / VOTE_BIN).round() as i32,
+                ((ty - y) / VOTE_BIN).round() as i32,
+            );
+            *votes.entry(bin).or_default() += 1;
+        }
+    }
+    // Ties go to the smaller offset, so the result does not depend on map order
+    let (bin_x, bin_y) = votes
+        .into_iter()
+        .max_by_key(|&((bx, by), count)| (count, std::cmp::Reverse(bx.abs() + by.abs()), bx, by))
+        .map_or((0, 0), |(bin, _)| bin);
+    (bin_x as f32 * VOTE_BIN, bin_y as f32 * VOTE_BIN)
+}
+
+/// Reference flashes bucketed by MATCH_TOLERANCE cells
+struct PointGrid<'a> {
+    points: &'a [[f32; 2]],
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl<'a> PointGrid<'a> {
+    fn new(points: &'a [[f32; 2]]) -> Self {
+        let mut cells: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+        for (index, &point) in points.iter().enumerate() {
+            cells.entry(Self::cell(point)).or_default().push(index);
+        }
+        PointGrid { points, cells }
+    }
+
+    fn cell([x, y]: [f32; 2]) -> (i32, i32) {
+        (
+            (x / MATCH_TOLERANCE).floor() as i32,
+            (y / MATCH_TOLERANCE).floor() as i32,
+        )
+    }
+
+    /// Nearest point within MATCH_TOLERANCE
+    fn nearest(&self, [x, y]: [f32; 2]) -> Option<[f32; 2]> {
+        let (cx, cy) = Self::cell([x, y]);
+        let mut best: Option<([f32; 2], f32)> = None;
+        for ny in cy - 1..=cy + 1 {
+            for nx in cx - 1..=cx + 1 {
+                for &index in self.cells.get(&(nx, ny)).into_iter().flatten() {
+                    let point = self.points[index];
+                    let distance = (point[0] - x).hypot(point[1] - y);
+                    if distance <= MATCH_TOLERANCE && best.is_none_or(|b| distance < b.1) {
+                        best = Some((point, distance));
+                    }
+                }
+            }
+        }
+        best.map(|(point, _)| point)
+    }
+
+    /// Number of points matched after moving by (dx, dy), and the summed
+    /// offsets from them to their matches
+    fn residuals(&self, points: &[[f32; 2]], dx: f32, dy: f32) -> (usize, f32, f32) {
+        let mut result = (0, 0.0, 0.0);
+        for &[x, y] in points {
+            let moved = [x + dx, y + dy];
+            if let Some(target) = self.nearest(moved) {
+                result.0 += 1;
+                result.1 += target[0] - moved[0];
+                result.2 += target[1] - moved[1];
+            }
+        }
+        result
+    }
+}
@@ -0,0 +1,151 @@
+use super::coverage::CoverageIndex;
+use super::markers::DrcMarkers;
+use crate::shape::GerberData;
+use wasm_bindgen::prelude::*;
+
+// Directions sampled around each drill hit
+const RAY_COUNT: usize = 32;
+// Rings wider than this are reported as this value (mm)
+const MAX_RING: f32 = 2.0;
+// Coarse march step along each ray before refinement (mm)
+const MARCH_STEP: f32 = 0.025;
+// Bisection steps refining the copper edge after the coarse march
+const REFINE_STEPS: usize = 6;
+
+/// Annular ring violations found between a drill layer and a copper layer
+#[wasm_bindgen]
+pub struct AnnularRingReport {
+    pub(crate) checked_hits: usize,
+    pub(crate) x: Vec<f32>,
+    pub(crate) y: Vec<f32>,
+    pub(crate) hole_diameter: Vec<f32>,
+    pub(crate) ring: Vec<f32>, // Minimum ring width, 0.0 = breakout
+}
+
+#[wasm_bindgen]
+impl AnnularRingReport {
+    /// Number of drill hits that were checked
+    #[wasm_bindgen(getter)]
+    pub fn checked_hits(&self) -> usize {
+        self.checked_hits
+    }
+
+    /// Number of violations below the threshold
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.x.len()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> Vec<f32> {
+        self.x.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> Vec<f32> {
+        self.y.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hole_diameter(&self) -> Vec<f32> {
+        self.hole_diameter.clone()
+    }
+
+    /// Minimum annular ring of each violation in mm (0.0 = hole breaks out of the pad)
+    #[wasm_bindgen(getter)]
+    pub fn ring(&self) -> Vec<f32> {
+        self.ring.clone()
+    }
+
+    /// Marker rings around each violating hit for the DRC overlay
+    pub fn markers(&self) -> DrcMarkers {
+        let mut markers = DrcMarkers::default();
+        for i in 0..self.x.len() {
+            markers.push(
+                self.x[i],
+                self.y[i],
+                self.hole_diameter[i] / 2.0 + MAX_RING / 4.0,
+            );
+        }
+        markers
+    }
+}
+
+/// Compute the annular ring of every drill hit and collect those below `min_ring`
+///
+/// Drill hits are the circles of the drill layer's positive sublayers. The ring is
+/// the shortest copper distance from the hole edge, sampled along rays around the hit.
+pub fn check_annular_ring(
+    drill_layer: &[GerberData],
+    copper_layer: &[GerberData],
+    min_ring: f32,
+) -> AnnularRingReport {
+    let copper = CoverageIndex::new(copper_layer);
+    let mut report = AnnularRingReport {
+        checked_hits: 0,
+        x: Vec::new(),
+        y: Vec::new(),
+        hole_diameter: Vec::new(),
+        ring: Vec::new(),
+    };
+
+    for data in drill_layer.iter().step_by(2) {
+        let circles = &data.circles;
+        for i in 0..circles.x.len() {
+            let (x, y, hole_radius) = (circles.x[i], circles.y[i], circles.radius[i]);
+            report.checked_hits += 1;
+
+            let ring = minimum_ring(&copper, x, y, hole_radius);
+            if ring < min_ring {
+                report.x.push(x);
+                report.y.push(y);
+                report.hole_diameter.push(hole_radius * 2.0);
+                report.ring.push(ring);
+            }
+        }
+    }
+
+    report
+}
+
+/// Shortest copper width from the hole edge outwards, over all sampled directions
+fn minimum_ring(copper: &CoverageIndex, x: f32, y: f32, hole_radius: f32) -> f32 {
+    let mut ring = MAX_RING;
+    for ray in 0..RAY_COUNT {
+        let angle = ray as f32 * 2.0 * std::f32::consts::PI / RAY_COUNT as f32;
+        let (dir_y, dir_x) = angle.sin_cos();
+        let covered = |distance: f32| copper.covered(x + dir_x * distance, y + dir_y * distance);
+
+        // Copper must start right at the hole edge
+        if !covered(hole_radius) {
+            return 0.0;
+        }
+
+        // March outwards until leaving copper, then bisect the edge
+        let mut inside = 0.0;
+        let mut outside = None;
+        let mut distance = MARCH_STEP;
+        while distance < ring {
+            if !covered(hole_radius + distance) {
+                outside = Some(distance);
+                break;
+            }
+            inside = distance;
+            distance += MARCH_STEP;
+        }
+        let mut outside = match outside {
+            Some(outside) => outside,
+            None => continue, // At least as wide as the current minimum
+        };
+        for _ in 0..REFINE_STEPS {
+            let middle = (inside + outside) / 2.0;
+            if covered(hole_radius + middle) {
+                inside = middle;
+            } else {
+                outside = middle;
+            }
+        }
+        ring = ring.min((inside + outside) / 2.0);
+    }
+    ring
+}
@@ -0,0 +1,91 @@
+use super::coverage::CoverageIndex;
+use crate::shape::{Boundary, GerberData};
+use wasm_bindgen::prelude::*;
+
+// Number of coverage samples along the longer side of the compared area
+const SAMPLES_ALONG_LONG_SIDE: usize = 1024;
+
+/// Area summary of a two-layer comparison (all areas in mm²)
+#[wasm_bindgen]
+pub struct LayerComparison {
+    pub(crate) a_only_area: f32,
+    pub(crate) b_only_area: f32,
+    pub(crate) common_area: f32,
+}
+
+#[wasm_bindgen]
+impl LayerComparison {
+    /// Area covered only by layer A
+    #[wasm_bindgen(getter)]
+    pub fn a_only_area(&self) -> f32 {
+        self.a_only_area
+    }
+
+    /// Area covered only by layer B
+    #[wasm_bindgen(getter)]
+    pub fn b_only_area(&self) -> f32 {
+        self.b_only_area
+    }
+
+    /// Area covered by both layers
+    #[wasm_bindgen(getter)]
+    pub fn common_area(&self) -> f32 {
+        self.common_area
+    }
+
+    /// Total area that differs between the layers (A-only + B-only)
+    #[wasm_bindgen(getter)]
+    pub fn differing_area(&self) -> f32 {
+        self.a_only_area + self.b_only_area
+    }
+}
+
+/// Compare the copper coverage of two layers by sampling both on a shared grid
+pub fn compare_layers(layer_a: &[GerberData], layer_b: &[GerberData]) -> LayerComparison {
+    let index_a = CoverageIndex::new(layer_a);
+    let index_b = CoverageIndex::new(layer_b);
+
+    // Sample over the union of both boundaries
+    let bounds = Boundary::new(
+        index_a.bounds().min_x.min(index_b.bounds().min_x),
+        index_a.bounds().max_x.max(index_b.bounds().max_x),
+        index_a.bounds().min_y.min(index_b.bounds().min_y),
+        index_a.bounds().max_y.max(index_b.bounds().max_y),
+    );
+    let width = bounds.max_x - bounds.min_x;
+    let height = bounds.max_y - bounds.min_y;
+    if width <= 0.0 || height <= 0.0 {
+        return LayerComparison {
+            a_only_area: 0.0,
+            b_only_area: 0.0,
+            common_area: 0.0,
+        };
+    }
+
+    let step = width.max(height) / SAMPLES_ALONG_LONG_SIDE as f32;
+    let columns = (width / step).ceil().max(1.0) as usize;
+    let rows = (height / step).ceil().max(1.0) as usize;
+
+    let mut a_only = 0usize;
+    let mut b_only = 0usize;
+    let mut common = 0usize;
+    for row in 0..rows {
+        let y = bounds.min_y + (row as f32 + 0.5) * step;
+        for column in 0..columns {
+            let x = bounds.min_x + (column as f32 + 0.5) * step;
+            match (index_a.covered(x, y), index_b.covered(x, y)) {
+                (true, true) => common += 1,
+                (true, false) => a_only += 1,
+                (false, true) => b_only += 1,
+                (false, false) => {}
+            }
+        }
+    }
+
+    let sample_area = step * step;
+    LayerComparison {
+        a_only_area: a_only as f32 * sample_area,
+        b_only_area: b_only as f32 * sample_area,
+        common_area: common as f32 * sample_area,
+    }
+}
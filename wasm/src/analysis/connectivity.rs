@@ -0,0 +1,460 @@
+use super::coverage::CoverageIndex;
+use crate::error::ViewerError;
+use crate::shape::{Boundary, GerberData};
+use wasm_bindgen::prelude::*;
+
+// Features closer than this count as touching (mm)
+const TOUCH_TOLERANCE: f32 = 0.001;
+// Target average number of features per grid cell
+const FEATURES_PER_CELL: usize = 4;
+// Upper bound on grid cells to keep the index memory bounded
+const MAX_GRID_CELLS: usize = 1 << 20;
+
+/// One dark primitive of a copper layer with its outline
+pub(crate) struct Feature {
+    pub(crate) rings: Vec<Vec<[f32; 2]>>, // Outer contours (thermals have one per segment)
+    bounds: [f32; 4],                     // min_x, min_y, max_x, max_y
+}
+
+impl Feature {
+    fn contains(&self, point: [f32; 2]) -> bool {
+        inside_bounds(&self.bounds, point)
+            && self.rings.iter().any(|ring| point_in_ring(point, ring))
+    }
+}
+
+/// Copper features of several layers grouped into nets by touching geometry
+///
+/// Features touching on a layer share a net, and drill hits join the features
+/// they hit on every layer. Nets are numbered from 0 in layer and feature
+/// order. Clear (negative) geometry does not separate features; it only keeps
+/// drill hits in clearances from connecting.
+#[wasm_bindgen]
+pub struct Connectivity {
+    pub(crate) layer_ids: Vec<u32>,
+    pub(crate) features: Vec<Vec<Feature>>, // Per copper layer
+    pub(crate) nets: Vec<Vec<u32>>,         // Net of each feature, per copper layer
+    pub(crate) net_count: u32,
+    pub(crate) hit_x: Vec<f32>,
+    pub(crate) hit_y: Vec<f32>,
+    pub(crate) hit_radius: Vec<f32>,
+    pub(crate) hit_net: Vec<u32>,
+    pub(crate) hit_copper: Vec<Vec<bool>>, // Whether each hit lands on copper, per copper layer // Net of each hit touching copper, u32::MAX otherwise
+}
+
+#[wasm_bindgen]
+impl Connectivity {
+    /// Number of nets
+    #[wasm_bindgen(getter)]
+    pub fn net_count(&self) -> u32 {
+        self.net_count
+    }
+
+    /// Copper layers the nets were computed for
+    #[wasm_bindgen(getter)]
+    pub fn layer_ids(&self) -> Vec<u32> {
+        self.layer_ids.clone()
+    }
+
+    /// Net of the copper at a point of a layer, e.g. under a click
+    ///
+    /// # Arguments
+    /// * `layer_id` - One of the copper layers the nets were computed for
+    /// * `x`, `y` - Point in mm
+    ///
+    /// # Returns
+    /// * Net number, or undefined where the layer has no copper
+    pub fn net_at(&self, layer_id: u32, x: f32, y: f32) -> Result<Option<u32>, JsValue> {
+        let layer = self.layer_position(layer_id)?;
+        Ok(self.features[layer]
+            .iter()
+            .position(|feature| feature.contains([x, y]))
+            .map(|index| self.nets[layer][index]))
+    }
+
+    /// Number of features of each copper layer in a net
+    pub fn net_feature_counts(&self, net: u32) -> Vec<u32> {
+        self.nets
+            .iter()
+            .map(|nets| nets.iter().filter(|&&n| n == net).count() as u32)
+            .collect()
+    }
+
+    /// Drill hit centers, x coordinates in mm
+    #[wasm_bindgen(getter)]
+    pub fn hit_x(&self) -> Vec<f32> {
+        self.hit_x.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn hit_y(&self) -> Vec<f32> {
+        self.hit_y.clone()
+    }
+
+    /// Drill hit radii in mm
+    #[wasm_bindgen(getter)]
+    pub fn hit_radius(&self) -> Vec<f32> {
+        self.hit_radius.clone()
+    }
+
+    /// Net of each drill hit (0xFFFFFFFF for hits through no copper)
+    #[wasm_bindgen(getter)]
+    pub fn hit_net(&self) -> Vec<u32> {
+        self.hit_net.clone()
+    }
+
+    /// Drill hits that connect two copper layers (via stitching map)
+    ///
+    /// # Arguments
+    /// * `layer_a`, `layer_b` - Two of the copper layers the nets were computed for
+    ///
+    /// # Returns
+    /// * Indices into the hit arrays of the hits landing on copper of both layers
+    pub fn stitching(&self, layer_a: u32, layer_b: u32) -> Result<Vec<u32>, JsValue> {
+        let (a, b) = (self.layer_position(layer_a)?, self.layer_position(layer_b)?);
+        Ok((0..self.hit_x.len())
+            .filter(|&hit| self.hit_copper[a][hit] && self.hit_copper[b][hit])
+            .map(|hit| hit as u32)
+            .collect())
+    }
+}
+
+impl Connectivity {
+    /// Position of a layer in layer_ids
+    pub(crate) fn layer_position(&self, layer_id: u32) -> Result<usize, JsValue> {
+        self.layer_ids
+            .iter()
+            .position(|&id| id == layer_id)
+            .ok_or_else(|| {
+                ViewerError::invalid_layer(
+                    "Layer is not part of the connectivity analysis",
+                    layer_id,
+                )
+                .into()
+            })
+    }
+}
+
+/// Group the copper of `copper_layers` into nets, joined across layers by the
+/// hits of `drill_layers`
+///
+/// Curves are flattened to within `chord_error` (mm). Drill hits are the
+/// circles of the drill layers' dark sublayers.
+pub fn connectivity(
+    copper_layers: &[(u32, &[GerberData])],
+    drill_layers: &[&[GerberData]],
+    chord_error: f32,
+) -> Connectivity {
+    let features: Vec<Vec<Feature>> = copper_layers
+        .iter()
+        .map(|(_, sublayers)| layer_features(sublayers, chord_error))
+        .collect();
+
+    // Features of all layers in one union-find, layer after layer
+    let offsets: Vec<usize> = features
+        .iter()
+        .scan(0, |total, layer| {
+            let offset = *total;
+            *total += layer.len();
+            Some(offset)
+        })
+        .collect();
+    let total = features.iter().map(Vec::len).sum();
+    let grids: Vec<FeatureGrid> = features.iter().map(|f| FeatureGrid::new(f)).collect();
+    let mut sets = UnionFind::new(total);
+    for (layer, layer_features) in features.iter().enumerate() {
+        join_touching(layer_features, &grids[layer], offsets[layer], &mut sets);
+    }
+
+    // Drill hits join whatever copper they pass through
+    let coverage: Vec<CoverageIndex> = copper_layers
+        .iter()
+        .map(|(_, sublayers)| CoverageIndex::new(sublayers))
+        .collect();
+    let mut hits: Vec<([f32; 2], f32, Option<usize>)> = Vec::new();
+    let mut hit_copper = vec![Vec::new(); features.len()];
+    for drill in drill_layers {
+        for data in drill.iter().step_by(2) {
+            let circles = &data.circles;
+            for i in 0..circles.x.len() {
+                let point = [circles.x[i], circles.y[i]];
+                let mut first: Option<usize> = None;
+                for (layer, layer_features) in features.iter().enumerate() {
+                    let mut on_copper = false;
+                    if coverage[layer].covered(point[0], point[1]) {
+                        for &index in grids[layer].candidates(point) {
+                            if layer_features[index as usize].contains(point) {
+                                let id = offsets[layer] + index as usize;
+                                match first {
+                                    Some(first) => sets.union(first, id),
+                                    None => first = Some(id),
+                                }
+                                on_copper = true;
+                            }
+                        }
+                    }
+                    hit_copper[layer].push(on_copper);
+                }
+                hits.push((point, circles.radius[i], first));
+            }
+        }
+    }
+
+    // Number nets by first appearance
+    let mut net_of_root = vec![u32::MAX; total];
+    let mut net_count = 0;
+    let mut net = |id: usize, sets: &mut UnionFind| {
+        let root = sets.find(id);
+        if net_of_root[root] == u32::MAX {
+            net_of_root[root] = net_count;
+            net_count += 1;
+        }
+        net_of_root[root]
+    };
+    let nets: Vec<Vec<u32>> = features
+        .iter()
+        .enumerate()
+        .map(|(layer, layer_features)| {
+            (0..layer_features.len())
+                .map(|index| net(offsets[layer] + index, &mut sets))
+                .collect()
+        })
+        .collect();
+    let hit_net = hits
+        .iter()
+        .map(|&(_, _, first)| first.map_or(u32::MAX, |id| net(id, &mut sets)))
+        .collect();
+
+    Connectivity {
+        layer_ids: copper_layers.iter().map(|(id, _)| *id).collect(),
+        features,
+        nets,
+        net_count,
+        hit_x: hits.iter().map(|h| h.0[0]).collect(),
+        hit_y: hits.iter().map(|h| h.0[1]).collect(),
+        hit_radius: hits.iter().map(|h| h.1).collect(),
+        hit_net,
+        hit_copper,
+    }
+}
+
+/// Outlines of the primitives of a layer's dark sublayers
+fn layer_features(sublayers: &[GerberData], chord_error: f32) -> Vec<Feature> {
+    let mut features = Vec::new();
+    for data in sublayers.iter().step_by(2) {
+        data.for_each_outline(chord_error, |_, _, shapes| {
+            let rings: Vec<Vec<[f32; 2]>> = shapes
+                .into_iter()
+                .filter_map(|shape| shape.into_iter().next())
+                .collect();
+            let mut bounds = [
+                f32::INFINITY,
+                f32::INFINITY,
+                f32::NEG_INFINITY,
+                f32::NEG_INFINITY,
+            ];
+            for point in rings.iter().flatten() {
+                bounds = [
+                    bounds[0].min(point[0]),
+                    bounds[1].min(point[1]),
+                    bounds[2].max(point[0]),
+                    bounds[3].max(point[1]),
+                ];
+            }
+            features.push(Feature { rings, bounds });
+        });
+    }
+    features
+}
+
+/// Uniform grid over the bounding boxes of one layer's features
+struct FeatureGrid {
+    bounds: Boundary,
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    cells: Vec<Vec<u32>>, // Features overlapping each cell
+}
+
+impl FeatureGrid {
+    fn new(features: &[Feature]) -> FeatureGrid {
+        let mut bounds = Boundary::new(0.0, 0.0, 0.0, 0.0);
+        if let Some(first) = features.first() {
+            bounds = Boundary::new(
+                first.bounds[0],
+                first.bounds[2],
+                first.bounds[1],
+                first.bounds[3],
+            );
+        }
+        for feature in features {
+            bounds.min_x = bounds.min_x.min(feature.bounds[0]);
+            bounds.min_y = bounds.min_y.min(feature.bounds[1]);
+            bounds.max_x = bounds.max_x.max(feature.bounds[2]);
+            bounds.max_y = bounds.max_y.max(feature.bounds[3]);
+        }
+        let width = (bounds.max_x - bounds.min_x).max(f32::EPSILON);
+        let height = (bounds.max_y - bounds.min_y).max(f32::EPSILON);
+        let target_cells = (features.len() / FEATURES_PER_CELL).clamp(1, MAX_GRID_CELLS);
+        let cell_size = ((width * height) / target_cells as f32)
+            .sqrt()
+            .max(f32::EPSILON);
+        let mut grid = FeatureGrid {
+            bounds,
+            cell_size,
+            columns: ((width / cell_size).ceil() as usize).max(1),
+            rows: ((height / cell_size).ceil() as usize).max(1),
+            cells: Vec::new(),
+        };
+
+        grid.cells = vec![Vec::new(); grid.columns * grid.rows];
+        for (index, feature) in features.iter().enumerate() {
+            let (c0, r0) = grid.cell(feature.bounds[0], feature.bounds[1]);
+            let (c1, r1) = grid.cell(feature.bounds[2], feature.bounds[3]);
+            for row in r0..=r1 {
+                for column in c0..=c1 {
+                    grid.cells[row * grid.columns + column].push(index as u32);
+                }
+            }
+        }
+        grid
+    }
+
+    /// Grid cell for a point, clamped to the grid
+    fn cell(&self, x: f32, y: f32) -> (usize, usize) {
+        let column = ((x - self.bounds.min_x) / self.cell_size).max(0.0) as usize;
+        let row = ((y - self.bounds.min_y) / self.cell_size).max(0.0) as usize;
+        (column.min(self.columns - 1), row.min(self.rows - 1))
+    }
+
+    /// Features whose bounding box may contain a point
+    fn candidates(&self, point: [f32; 2]) -> &[u32] {
+        let (column, row) = self.cell(point[0], point[1]);
+        &self.cells[row * self.columns + column]
+    }
+}
+
+/// Union the features of one layer that touch, testing pairs that share a
+/// grid cell
+fn join_touching(features: &[Feature], grid: &FeatureGrid, offset: usize, sets: &mut UnionFind) {
+    for members in &grid.cells {
+        for (k, &a) in members.iter().enumerate() {
+            for &b in &members[k + 1..] {
+                let (a, b) = (a as usize, b as usize);
+                if sets.find(offset + a) != sets.find(offset + b)
+                    && touching(&features[a], &features[b])
+                {
+                    sets.union(offset + a, offset + b);
+                }
+            }
+        }
+    }
+}
+
+/// Whether two features overlap or come within TOUCH_TOLERANCE
+fn touching(a: &Feature, b: &Feature) -> bool {
+    let t = TOUCH_TOLERANCE;
+    if a.bounds[0] > b.bounds[2] + t
+        || b.bounds[0] > a.bounds[2] + t
+        || a.bounds[1] > b.bounds[3] + t
+        || b.bounds[1] > a.bounds[3] + t
+    {
+        return false;
+    }
+    a.rings.iter().any(|ring_a| {
+        b.rings.iter().any(|ring_b| {
+            point_in_ring(ring_a[0], ring_b)
+                || point_in_ring(ring_b[0], ring_a)
+                || edges(ring_a)
+                    .any(|(p, q)| edges(ring_b).any(|(r, s)| segment_distance(p, q, r, s) <= t))
+        })
+    })
+}
+
+/// Edges of a closed ring
+fn edges(ring: &[[f32; 2]]) -> impl Iterator<Item = ([f32; 2], [f32; 2])> + '_ {
+    ring.iter()
+        .zip(ring.iter().cycle().skip(1))
+        .map(|(&p, &q)| (p, q))
+}
+
+fn inside_bounds(bounds: &[f32; 4], point: [f32; 2]) -> bool {
+    point[0] >= bounds[0] && point[0] <= bounds[2] && point[1] >= bounds[1] && point[1] <= bounds[3]
+}
+
+/// Even-odd point-in-polygon test
+fn point_in_ring(point: [f32; 2], ring: &[[f32; 2]]) -> bool {
+    let mut inside = false;
+    for (p, q) in edges(ring) {
+        if (p[1] > point[1]) != (q[1] > point[1])
+            && point[0] < p[0] + (point[1] - p[1]) / (q[1] - p[1]) * (q[0] - p[0])
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Shortest distance between segments pq and rs (0.0 if they cross)
+fn segment_distance(p: [f32; 2], q: [f32; 2], r: [f32; 2], s: [f32; 2]) -> f32 {
+    let cross = |a: [f32; 2], b: [f32; 2], c: [f32; 2]| {
+        (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+    };
+    let (d1, d2) = (cross(p, q, r), cross(p, q, s));
+    let (d3, d4) = (cross(r, s, p), cross(r, s, q));
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+    {
+        return 0.0;
+    }
+    point_segment_distance(p, r, s)
+        .min(point_segment_distance(q, r, s))
+        .min(point_segment_distance(r, p, q))
+        .min(point_segment_distance(s, p, q))
+}
+
+fn point_segment_distance(point: [f32; 2], a: [f32; 2], b: [f32; 2]) -> f32 {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq > 0.0 {
+        (((point[0] - a[0]) * dx + (point[1] - a[1]) * dy) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (point[0] - (a[0] + t * dx)).hypot(point[1] - (a[1] + t * dy))
+}
+
+/// Disjoint sets with path halving and union by size
+struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(count: usize) -> UnionFind {
+        UnionFind {
+            parent: (0..count).collect(),
+            size: vec![1; count],
+        }
+    }
+
+    fn find(&mut self, mut id: usize) -> usize {
+        while self.parent[id] != id {
+            self.parent[id] = self.parent[self.parent[id]];
+            id = self.parent[id];
+        }
+        id
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+}
@@ -0,0 +1,98 @@
+use crate::shape::GerberData;
+use i_overlay::core::fill_rule::FillRule;
+use i_overlay::core::overlay_rule::OverlayRule;
+use i_overlay::float::single::SingleFloatOverlay;
+use wasm_bindgen::prelude::*;
+
+/// Outline polygons of a layer's final image
+///
+/// Every shape is an outer boundary (counterclockwise) followed by its holes
+/// (clockwise). The points of all rings are stored back to back; ring i runs
+/// from `ring_start[i]` up to the next ring's start.
+#[wasm_bindgen]
+pub struct Contours {
+    pub(crate) points: Vec<f32>,     // x, y of every ring point in mm
+    pub(crate) ring_start: Vec<u32>, // First point of each ring
+    pub(crate) ring_shape: Vec<u32>, // Shape each ring belongs to
+    pub(crate) shapes: u32,
+}
+
+#[wasm_bindgen]
+impl Contours {
+    /// Number of shapes
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> u32 {
+        self.shapes
+    }
+
+    /// Number of rings (outer boundaries and holes)
+    #[wasm_bindgen(getter)]
+    pub fn ring_count(&self) -> usize {
+        self.ring_start.len()
+    }
+
+    /// Ring points as [x0, y0, x1, y1, ...] in mm
+    #[wasm_bindgen(getter)]
+    pub fn points(&self) -> Vec<f32> {
+        self.points.clone()
+    }
+
+    /// Index of the first point of each ring
+    #[wasm_bindgen(getter)]
+    pub fn ring_start(&self) -> Vec<u32> {
+        self.ring_start.clone()
+    }
+
+    /// Shape index of each ring; the first ring of a shape is its outer boundary
+    #[wasm_bindgen(getter)]
+    pub fn ring_shape(&self) -> Vec<u32> {
+        self.ring_shape.clone()
+    }
+}
+
+/// Merge a layer's geometry into outline polygons
+///
+/// Curves are flattened to within `chord_error` (mm).
+pub fn extract_contours(sublayers: &[GerberData], chord_error: f32) -> Contours {
+    let result = merged_shapes(sublayers, chord_error);
+    let mut contours = Contours {
+        points: Vec::new(),
+        ring_start: Vec::new(),
+        ring_shape: Vec::new(),
+        shapes: result.len() as u32,
+    };
+    for (shape_index, shape) in result.iter().enumerate() {
+        for ring in shape {
+            contours.ring_start.push((contours.points.len() / 2) as u32);
+            contours.ring_shape.push(shape_index as u32);
+            contours.points.extend(ring.iter().flatten());
+        }
+    }
+    contours
+}
+
+/// Shapes (outer contour, then holes) covered by a layer's final image
+///
+/// Sublayers are applied in drawing order: dark ones are united with the
+/// result and clear ones cut out of it, as are pad holes.
+pub fn merged_shapes(sublayers: &[GerberData], chord_error: f32) -> Vec<Vec<Vec<[f32; 2]>>> {
+    let mut result: Vec<Vec<Vec<[f32; 2]>>> = Vec::new();
+    for (index, data) in sublayers.iter().enumerate() {
+        let mut clip = Vec::new();
+        data.for_each_outline(chord_error, |_, _, shapes| clip.extend(shapes));
+        if clip.is_empty() {
+            continue;
+        }
+        let rule = if index % 2 == 0 {
+            OverlayRule::Union
+        } else if result.is_empty() {
+            continue;
+        } else {
+            OverlayRule::Difference
+        };
+        // Holes wind against their outline, so NonZero keeps them open
+        // unless another primitive covers them
+        result = result.overlay(&clip, rule, FillRule::NonZero);
+    }
+    result
+}
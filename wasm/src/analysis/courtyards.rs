@@ -0,0 +1,114 @@
+use crate::parser::ApertureFunction;
+use crate::shape::GerberData;
+
+// Arcs are split into segments of at most this sweep (radians)
+const ARC_STEP: f32 = 0.1;
+
+/// Board side of an X3 component layer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComponentSide {
+    Top = 0,
+    Bottom = 1,
+}
+
+impl ComponentSide {
+    /// Side named "top" or "bottom"
+    pub fn from_name(name: &str) -> Option<ComponentSide> {
+        match name {
+            "top" => Some(ComponentSide::Top),
+            "bottom" => Some(ComponentSide::Bottom),
+            _ => None,
+        }
+    }
+
+    /// Side of a component layer from its .FileFunction value, e.g.
+    /// "Component,L1,Top"; None for other layers
+    pub fn of_function(function: &str) -> Option<ComponentSide> {
+        let mut fields = function.split(',').map(str::trim);
+        if fields.next() != Some("Component") {
+            return None;
+        }
+        match fields.nth(1) {
+            Some("Top") => Some(ComponentSide::Top),
+            Some("Bot") => Some(ComponentSide::Bottom),
+            _ => None,
+        }
+    }
+}
+
+/// Courtyard outlines as line segments for the dashed overlay
+///
+/// Every segment has its own two vertices. Each vertex carries its distance
+/// along the outline, so dashes run on across corners.
+#[derive(Default)]
+pub struct CourtyardLines {
+    pub vertices: Vec<f32>,  // x, y of every vertex in mm
+    pub distances: Vec<f32>, // Distance along the outline of every vertex in mm
+}
+
+impl CourtyardLines {
+    /// Add the segment `from`-`to` starting `distance` along its outline and
+    /// return the distance at its end
+    fn push(&mut self, from: [f32; 2], to: [f32; 2], distance: f32) -> f32 {
+        let end = distance + (to[0] - from[0]).hypot(to[1] - from[1]);
+        self.vertices
+            .extend_from_slice(&[from[0], from[1], to[0], to[1]]);
+        self.distances.extend_from_slice(&[distance, end]);
+        end
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.distances.is_empty()
+    }
+}
+
+/// Collect the draws made with ComponentOutline,Courtyard apertures
+///
+/// Courtyards are drawn with zero-size apertures, so their recorded draws
+/// are used rather than the (empty) drawn area.
+pub fn courtyard_lines<'a>(sublayers: impl IntoIterator<Item = &'a GerberData>) -> CourtyardLines {
+    let mut lines = CourtyardLines::default();
+    for data in sublayers {
+        let d_codes = data.aperture_codes(ApertureFunction::Courtyard);
+        if d_codes.is_empty() {
+            continue;
+        }
+        let traces = &data.traces;
+        let point = |i: usize| [traces.points[i * 2], traces.points[i * 2 + 1]];
+        for polyline in 0..traces.len() {
+            if !d_codes.contains(&traces.d_code[polyline]) {
+                continue;
+            }
+            let end = traces
+                .start
+                .get(polyline + 1)
+                .map_or(traces.sweep.len(), |&s| s as usize);
+            let mut distance = 0.0;
+            for i in traces.start[polyline] as usize + 1..end {
+                let (from, to) = (point(i - 1), point(i));
+                let sweep = traces.sweep[i];
+                if sweep == 0.0 {
+                    distance = lines.push(from, to, distance);
+                    continue;
+                }
+                let center = [traces.center[i * 2], traces.center[i * 2 + 1]];
+                let radius = (from[0] - center[0]).hypot(from[1] - center[1]);
+                let start_angle = (from[1] - center[1]).atan2(from[0] - center[0]);
+                let steps = (sweep.abs() / ARC_STEP).ceil().max(1.0) as usize;
+                let mut previous = from;
+                for step in 1..=steps {
+                    let next = if step == steps {
+                        to
+                    } else {
+                        let (sin, cos) =
+                            (start_angle + sweep * step as f32 / steps as f32).sin_cos();
+                        [center[0] + radius * cos, center[1] + radius * sin]
+                    };
+                    distance = lines.push(previous, next, distance);
+                    previous = next;
+                }
+            }
+        }
+    }
+    lines
+}
@@ -0,0 +1,354 @@
+use crate::shape::{Boundary, GerberData};
+
+// Target average number of primitives per grid cell
+const PRIMITIVES_PER_CELL: usize = 4;
+// Upper bound on grid cells to keep the index memory bounded
+const MAX_GRID_CELLS: usize = 1 << 22;
+
+/// Kind of stored primitive referenced by an index entry
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ShapeKind {
+    Triangle,
+    Circle,
+    Arc,
+    Thermal,
+    Obround,
+}
+
+/// Reference to a single primitive inside one polarity sublayer
+#[derive(Clone, Copy)]
+struct Entry {
+    sublayer: u32,
+    kind: ShapeKind,
+    index: u32,
+}
+
+/// Uniform grid index answering "is this point covered by copper?" for one layer
+///
+/// Polarity sublayers are resolved in order (even index = positive, odd index = negative),
+/// so the result matches what the renderer draws into the layer FBO.
+pub struct CoverageIndex<'a> {
+    sublayers: &'a [GerberData],
+    bounds: Boundary,
+    cell_size: f32,
+    columns: usize,
+    rows: usize,
+    cell_start: Vec<u32>, // CSR offsets into entries (length = cells + 1)
+    entries: Vec<Entry>,
+}
+
+impl<'a> CoverageIndex<'a> {
+    /// Build the index for all polarity sublayers of a layer
+    pub fn new(sublayers: &'a [GerberData]) -> CoverageIndex<'a> {
+        let bounds = Boundary::combined(sublayers);
+        let primitive_count: usize = sublayers.iter().map(primitive_count).sum();
+
+        let width = (bounds.max_x - bounds.min_x).max(f32::EPSILON);
+        let height = (bounds.max_y - bounds.min_y).max(f32::EPSILON);
+        let target_cells = (primitive_count / PRIMITIVES_PER_CELL).clamp(1, MAX_GRID_CELLS);
+        let cell_size = ((width * height) / target_cells as f32)
+            .sqrt()
+            .max(f32::EPSILON);
+        let columns = ((width / cell_size).ceil() as usize).max(1);
+        let rows = ((height / cell_size).ceil() as usize).max(1);
+
+        let mut index = CoverageIndex {
+            sublayers,
+            bounds,
+            cell_size,
+            columns,
+            rows,
+            cell_start: vec![0; columns * rows + 1],
+            entries: Vec::new(),
+        };
+
+        // Pass 1: count entries per cell, pass 2: fill (keeps sublayer order per cell)
+        let mut counts = vec![0u32; columns * rows];
+        index.for_each_entry(|cell, _| counts[cell] += 1);
+
+        let mut total = 0u32;
+        for (cell, count) in counts.iter().enumerate() {
+            index.cell_start[cell] = total;
+            total += count;
+        }
+        index.cell_start[columns * rows] = total;
+
+        let placeholder = Entry {
+            sublayer: 0,
+            kind: ShapeKind::Triangle,
+            index: 0,
+        };
+        let mut entries = vec![placeholder; total as usize];
+        let mut cursor: Vec<u32> = index.cell_start[..columns * rows].to_vec();
+        index.for_each_entry(|cell, entry| {
+            entries[cursor[cell] as usize] = entry;
+            cursor[cell] += 1;
+        });
+        index.entries = entries;
+
+        index
+    }
+
+    /// Combined boundary of the indexed layer
+    pub fn bounds(&self) -> &Boundary {
+        &self.bounds
+    }
+
+    /// Check whether the point is covered after applying all polarity sublayers
+    pub fn covered(&self, x: f32, y: f32) -> bool {
+        let (column, row) = match self.cell_of(x, y) {
+            Some(cell) => cell,
+            None => return false,
+        };
+        let cell = row * self.columns + column;
+        let start = self.cell_start[cell] as usize;
+        let end = self.cell_start[cell + 1] as usize;
+
+        let mut covered = false;
+        let mut decided_sublayer = u32::MAX;
+        for entry in &self.entries[start..end] {
+            // Once a sublayer contains the point, later entries of the same sublayer don't matter
+            if entry.sublayer == decided_sublayer {
+                continue;
+            }
+            if self.entry_contains(entry, x, y) {
+                covered = entry.sublayer % 2 == 0;
+                decided_sublayer = entry.sublayer;
+            }
+        }
+        covered
+    }
+
+    /// Grid cell containing the point, if inside the layer bounds
+    fn cell_of(&self, x: f32, y: f32) -> Option<(usize, usize)> {
+        if x < self.bounds.min_x
+            || x > self.bounds.max_x
+            || y < self.bounds.min_y
+            || y > self.bounds.max_y
+        {
+            return None;
+        }
+        let column = (((x - self.bounds.min_x) / self.cell_size) as usize).min(self.columns - 1);
+        let row = (((y - self.bounds.min_y) / self.cell_size) as usize).min(self.rows - 1);
+        Some((column, row))
+    }
+
+    /// Visit every (cell, entry) pair covered by primitive bounding boxes, in sublayer order
+    fn for_each_entry<F: FnMut(usize, Entry)>(&self, mut visit: F) {
+        for (sublayer, data) in self.sublayers.iter().enumerate() {
+            let sublayer = sublayer as u32;
+            let mut emit = |kind: ShapeKind, index: usize, min: [f32; 2], max: [f32; 2]| {
+                let entry = Entry {
+                    sublayer,
+                    kind,
+                    index: index as u32,
+                };
+                let (c0, r0) = self.clamped_cell(min[0], min[1]);
+                let (c1, r1) = self.clamped_cell(max[0], max[1]);
+                for row in r0..=r1 {
+                    for column in c0..=c1 {
+                        visit(row * self.columns + column, entry);
+                    }
+                }
+            };
+
+            let triangles = &data.triangles;
+            for t in 0..triangles.indices.len() / 3 {
+                let mut min = [f32::INFINITY; 2];
+                let mut max = [f32::NEG_INFINITY; 2];
+                for k in 0..3 {
+                    let v = triangles.indices[t * 3 + k] as usize;
+                    let px = triangles.vertices[v * 2];
+                    let py = triangles.vertices[v * 2 + 1];
+                    min = [min[0].min(px), min[1].min(py)];
+                    max = [max[0].max(px), max[1].max(py)];
+                }
+                emit(ShapeKind::Triangle, t, min, max);
+            }
+
+            let circles = &data.circles;
+            for i in 0..circles.x.len() {
+                let r = circles.radius[i];
+                emit(
+                    ShapeKind::Circle,
+                    i,
+                    [circles.x[i] - r, circles.y[i] - r],
+                    [circles.x[i] + r, circles.y[i] + r],
+                );
+            }
+
+            let arcs = &data.arcs;
+            for i in 0..arcs.x.len() {
+                let r = arcs.radius[i] + arcs.thickness[i] / 2.0;
+                emit(
+                    ShapeKind::Arc,
+                    i,
+                    [arcs.x[i] - r, arcs.y[i] - r],
+                    [arcs.x[i] + r, arcs.y[i] + r],
+                );
+            }
+
+            let thermals = &data.thermals;
+            for i in 0..thermals.x.len() {
+                let r = thermals.outer_diameter[i] / 2.0;
+                emit(
+                    ShapeKind::Thermal,
+                    i,
+                    [thermals.x[i] - r, thermals.y[i] - r],
+                    [thermals.x[i] + r, thermals.y[i] + r],
+                );
+            }
+
+            let obrounds = &data.obrounds;
+            for i in 0..obrounds.x.len() {
+                let r = obrounds.width[i].max(obrounds.height[i]) / 2.0;
+                emit(
+                    ShapeKind::Obround,
+                    i,
+                    [obrounds.x[i] - r, obrounds.y[i] - r],
+                    [obrounds.x[i] + r, obrounds.y[i] + r],
+                );
+            }
+        }
+    }
+
+    /// Grid cell for a point, clamped to the grid
+    fn clamped_cell(&self, x: f32, y: f32) -> (usize, usize) {
+        let column = ((x - self.bounds.min_x) / self.cell_size).max(0.0) as usize;
+        let row = ((y - self.bounds.min_y) / self.cell_size).max(0.0) as usize;
+        (column.min(self.columns - 1), row.min(self.rows - 1))
+    }
+
+    /// Exact point-in-primitive test (mirrors the fragment shaders)
+    fn entry_contains(&self, entry: &Entry, x: f32, y: f32) -> bool {
+        let data = &self.sublayers[entry.sublayer as usize];
+        let i = entry.index as usize;
+        match entry.kind {
+            ShapeKind::Triangle => {
+                let triangles = &data.triangles;
+                let v0 = triangles.indices[i * 3] as usize;
+                let v1 = triangles.indices[i * 3 + 1] as usize;
+                let v2 = triangles.indices[i * 3 + 2] as usize;
+                let a = [triangles.vertices[v0 * 2], triangles.vertices[v0 * 2 + 1]];
+                let b = [triangles.vertices[v1 * 2], triangles.vertices[v1 * 2 + 1]];
+                let c = [triangles.vertices[v2 * 2], triangles.vertices[v2 * 2 + 1]];
+                point_in_triangle([x, y], a, b, c)
+                    && !in_hole(
+                        x,
+                        y,
+                        triangles.hole_x[v0],
+                        triangles.hole_y[v0],
+                        triangles.hole_radius[v0],
+                    )
+            }
+            ShapeKind::Circle => {
+                let circles = &data.circles;
+                let dx = x - circles.x[i];
+                let dy = y - circles.y[i];
+                let r = circles.radius[i];
+                dx * dx + dy * dy <= r * r
+                    && !in_hole(
+                        x,
+                        y,
+                        circles.hole_x[i],
+                        circles.hole_y[i],
+                        circles.hole_radius[i],
+                    )
+            }
+            ShapeKind::Arc => {
+                let arcs = &data.arcs;
+                let dx = x - arcs.x[i];
+                let dy = y - arcs.y[i];
+                let dist = (dx * dx + dy * dy).sqrt();
+                let half = arcs.thickness[i] / 2.0;
+                if dist < arcs.radius[i] - half || dist > arcs.radius[i] + half {
+                    return false;
+                }
+                angle_in_sweep(dy.atan2(dx), arcs.start_angle[i], arcs.sweep_angle[i])
+            }
+            ShapeKind::Thermal => {
+                let thermals = &data.thermals;
+                let (sin_r, cos_r) = thermals.rotation[i].sin_cos();
+                let dx = x - thermals.x[i];
+                let dy = y - thermals.y[i];
+                // Rotated back so the gaps lie along the axes
+                let rx = dx * cos_r + dy * sin_r;
+                let ry = -dx * sin_r + dy * cos_r;
+                let dist = (rx * rx + ry * ry).sqrt();
+                let half_gap = thermals.gap_thickness[i] / 2.0;
+                dist <= thermals.outer_diameter[i] / 2.0
+                    && dist >= thermals.inner_diameter[i] / 2.0
+                    && rx.abs() >= half_gap
+                    && ry.abs() >= half_gap
+            }
+            ShapeKind::Obround => {
+                let obrounds = &data.obrounds;
+                let (sin_r, cos_r) = obrounds.rotation[i].sin_cos();
+                let dx = x - obrounds.x[i];
+                let dy = y - obrounds.y[i];
+                // Rotate into the obround's local frame
+                let lx = dx * cos_r + dy * sin_r;
+                let ly = -dx * sin_r + dy * cos_r;
+                let half_w = obrounds.width[i] / 2.0;
+                let half_h = obrounds.height[i] / 2.0;
+                let radius = half_w.min(half_h);
+                let qx = (lx.abs() - (half_w - radius)).max(0.0);
+                let qy = (ly.abs() - (half_h - radius)).max(0.0);
+                qx * qx + qy * qy <= radius * radius
+                    && !in_hole(
+                        x,
+                        y,
+                        obrounds.hole_x[i],
+                        obrounds.hole_y[i],
+                        obrounds.hole_radius[i],
+                    )
+            }
+        }
+    }
+}
+
+/// Number of stored primitives in a sublayer
+fn primitive_count(data: &GerberData) -> usize {
+    data.triangles.indices.len() / 3
+        + data.circles.x.len()
+        + data.arcs.x.len()
+        + data.thermals.x.len()
+        + data.obrounds.x.len()
+}
+
+/// Check if a point falls inside an analytic hole
+#[inline]
+fn in_hole(x: f32, y: f32, hole_x: f32, hole_y: f32, hole_radius: f32) -> bool {
+    if hole_radius <= 0.0 {
+        return false;
+    }
+    let dx = x - hole_x;
+    let dy = y - hole_y;
+    dx * dx + dy * dy < hole_radius * hole_radius
+}
+
+/// Point-in-triangle test independent of winding order
+#[inline]
+fn point_in_triangle(p: [f32; 2], a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> bool {
+    let d1 = (p[0] - b[0]) * (a[1] - b[1]) - (a[0] - b[0]) * (p[1] - b[1]);
+    let d2 = (p[0] - c[0]) * (b[1] - c[1]) - (b[0] - c[0]) * (p[1] - c[1]);
+    let d3 = (p[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (p[1] - a[1]);
+    let has_negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let has_positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    !(has_negative && has_positive)
+}
+
+/// Check if an angle lies within a signed sweep starting at start_angle (radians)
+pub(crate) fn angle_in_sweep(angle: f32, start_angle: f32, sweep_angle: f32) -> bool {
+    const TWO_PI: f32 = 2.0 * std::f32::consts::PI;
+    if sweep_angle.abs() >= TWO_PI {
+        return true;
+    }
+    // Offset from the start angle measured in the sweep direction
+    let offset = if sweep_angle >= 0.0 {
+        (angle - start_angle).rem_euclid(TWO_PI)
+    } else {
+        (start_angle - angle).rem_euclid(TWO_PI)
+    };
+    offset <= sweep_angle.abs()
+}
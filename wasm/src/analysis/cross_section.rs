@@ -0,0 +1,177 @@
+use super::coverage::CoverageIndex;
+use crate::stackup::{LayerArtwork, Stackup, StackupKind};
+use wasm_bindgen::prelude::*;
+
+// Sample spacing along the cut line before edge refinement (mm)
+const SAMPLE_STEP: f32 = 0.02;
+// Upper bound on samples per layer for very long cut lines
+const MAX_SAMPLES: usize = 8192;
+// Bisection steps refining each material edge
+const REFINE_STEPS: usize = 8;
+
+/// Material present along a cut line through the board stackup
+///
+/// Positions along the cut are measured in mm from its start point, depths in
+/// mm from the top of the stackup.
+#[wasm_bindgen]
+pub struct CrossSection {
+    pub(crate) start_x: f32,
+    pub(crate) start_y: f32,
+    pub(crate) end_x: f32,
+    pub(crate) end_y: f32,
+    pub(crate) layer_kinds: Vec<StackupKind>,
+    pub(crate) layer_top: Vec<f32>, // Depth of each stackup layer
+    pub(crate) layer_bottom: Vec<f32>,
+    pub(crate) segment_layer: Vec<u32>, // Stackup layer of each material segment
+    pub(crate) segment_start: Vec<f32>,
+    pub(crate) segment_end: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl CrossSection {
+    /// Length of the cut line in mm
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> f32 {
+        (self.end_x - self.start_x).hypot(self.end_y - self.start_y)
+    }
+
+    /// Total stackup thickness in mm
+    #[wasm_bindgen(getter)]
+    pub fn total_thickness(&self) -> f32 {
+        self.layer_bottom.last().copied().unwrap_or(0.0)
+    }
+
+    /// Depth of the top of each stackup layer in mm
+    #[wasm_bindgen(getter)]
+    pub fn layer_top(&self) -> Vec<f32> {
+        self.layer_top.clone()
+    }
+
+    /// Depth of the bottom of each stackup layer in mm
+    #[wasm_bindgen(getter)]
+    pub fn layer_bottom(&self) -> Vec<f32> {
+        self.layer_bottom.clone()
+    }
+
+    /// Number of material segments
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.segment_layer.len()
+    }
+
+    /// Stackup layer index of each segment
+    #[wasm_bindgen(getter)]
+    pub fn segment_layer(&self) -> Vec<u32> {
+        self.segment_layer.clone()
+    }
+
+    /// Start of each segment along the cut line in mm
+    #[wasm_bindgen(getter)]
+    pub fn segment_start(&self) -> Vec<f32> {
+        self.segment_start.clone()
+    }
+
+    /// End of each segment along the cut line in mm
+    #[wasm_bindgen(getter)]
+    pub fn segment_end(&self) -> Vec<f32> {
+        self.segment_end.clone()
+    }
+}
+
+/// Cut the stackup along a line
+///
+/// `artwork` holds the sublayers linked to each stackup layer (None = not
+/// linked). Linked copper, legend and paste layers are present where their
+/// artwork is; solder mask artwork marks openings, so mask is present where it
+/// is not. Dielectrics and unlinked layers span the whole cut.
+pub fn cross_section(
+    stackup: &Stackup,
+    artwork: &[LayerArtwork],
+    start: (f32, f32),
+    end: (f32, f32),
+) -> CrossSection {
+    let length = (end.0 - start.0).hypot(end.1 - start.1);
+    let samples = ((length / SAMPLE_STEP).ceil() as usize).clamp(1, MAX_SAMPLES);
+    let point_at = |t: f32| {
+        (
+            start.0 + (end.0 - start.0) * t / length.max(f32::EPSILON),
+            start.1 + (end.1 - start.1) * t / length.max(f32::EPSILON),
+        )
+    };
+
+    let mut section = CrossSection {
+        start_x: start.0,
+        start_y: start.1,
+        end_x: end.0,
+        end_y: end.1,
+        layer_kinds: Vec::with_capacity(stackup.layers.len()),
+        layer_top: Vec::with_capacity(stackup.layers.len()),
+        layer_bottom: Vec::with_capacity(stackup.layers.len()),
+        segment_layer: Vec::new(),
+        segment_start: Vec::new(),
+        segment_end: Vec::new(),
+    };
+
+    let mut depth = 0.0;
+    for (index, layer) in stackup.layers.iter().enumerate() {
+        section.layer_kinds.push(layer.kind);
+        section.layer_top.push(depth);
+        depth += layer.thickness;
+        section.layer_bottom.push(depth);
+
+        let sublayers = match artwork.get(index).copied().flatten() {
+            Some(sublayers) if layer.kind != StackupKind::Dielectric => sublayers,
+            _ => {
+                section.push_segment(index, 0.0, length);
+                continue;
+            }
+        };
+
+        let coverage = CoverageIndex::new(sublayers);
+        let invert = layer.kind == StackupKind::SolderMask;
+        let present = |t: f32| {
+            let (x, y) = point_at(t);
+            coverage.covered(x, y) != invert
+        };
+
+        // Walk the samples and refine every change of state to an edge position
+        let step = length / samples as f32;
+        let mut inside = present(0.0);
+        let mut segment_start = 0.0;
+        for i in 1..=samples {
+            let t = i as f32 * step;
+            if present(t) == inside {
+                continue;
+            }
+            let (mut low, mut high) = (t - step, t);
+            for _ in 0..REFINE_STEPS {
+                let mid = (low + high) / 2.0;
+                if present(mid) == inside {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            let edge = (low + high) / 2.0;
+            if inside {
+                section.push_segment(index, segment_start, edge);
+            } else {
+                segment_start = edge;
+            }
+            inside = !inside;
+        }
+        if inside {
+            section.push_segment(index, segment_start, length);
+        }
+    }
+
+    section
+}
+
+impl CrossSection {
+    fn push_segment(&mut self, layer: usize, start: f32, end: f32) {
+        self.segment_layer.push(layer as u32);
+        self.segment_start.push(start);
+        self.segment_end.push(end);
+    }
+}
@@ -0,0 +1,123 @@
+use super::coverage::CoverageIndex;
+use crate::shape::GerberData;
+use wasm_bindgen::prelude::*;
+
+// Samples per cell along each axis (8x8 = 64 coverage samples per cell)
+const SAMPLES_PER_AXIS: usize = 8;
+// Largest grid accepted, in cells (256 x 256); each cell takes 64 samples
+const MAX_GRID_CELLS: u64 = 256 * 256;
+
+/// Copper coverage ratio per grid cell over the layer boundary
+#[wasm_bindgen]
+pub struct CopperDensity {
+    pub(crate) columns: u32,
+    pub(crate) rows: u32,
+    pub(crate) values: Vec<f32>, // Row-major, row 0 = min_y, 0.0 (bare) ..= 1.0 (full copper)
+    pub(crate) min_x: f32,
+    pub(crate) max_x: f32,
+    pub(crate) min_y: f32,
+    pub(crate) max_y: f32,
+}
+
+#[wasm_bindgen]
+impl CopperDensity {
+    #[wasm_bindgen(getter)]
+    pub fn columns(&self) -> u32 {
+        self.columns
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn rows(&self) -> u32 {
+        self.rows
+    }
+
+    /// Coverage ratio per cell (row-major, first row at min_y)
+    #[wasm_bindgen(getter)]
+    pub fn values(&self) -> Vec<f32> {
+        self.values.clone()
+    }
+
+    /// Copper coverage ratio of the whole layer boundary
+    #[wasm_bindgen(getter)]
+    pub fn total(&self) -> f32 {
+        if self.values.is_empty() {
+            return 0.0;
+        }
+        self.values.iter().sum::<f32>() / self.values.len() as f32
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn min_x(&self) -> f32 {
+        self.min_x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_x(&self) -> f32 {
+        self.max_x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn min_y(&self) -> f32 {
+        self.min_y
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn max_y(&self) -> f32 {
+        self.max_y
+    }
+}
+
+/// Rasterize copper coverage of a layer into a coarse columns x rows grid
+///
+/// Grids of more than MAX_GRID_CELLS cells are rejected.
+pub fn copper_density(
+    sublayers: &[GerberData],
+    columns: u32,
+    rows: u32,
+) -> Result<CopperDensity, String> {
+    let columns = columns.max(1);
+    let rows = rows.max(1);
+    if columns as u64 * rows as u64 > MAX_GRID_CELLS {
+        return Err(format!(
+            "Density grid of {} x {} cells exceeds {} cells",
+            columns, rows, MAX_GRID_CELLS
+        ));
+    }
+    let index = CoverageIndex::new(sublayers);
+    let bounds = index.bounds();
+
+    let cell_width = (bounds.max_x - bounds.min_x) / columns as f32;
+    let cell_height = (bounds.max_y - bounds.min_y) / rows as f32;
+    let sample_count = (SAMPLES_PER_AXIS * SAMPLES_PER_AXIS) as f32;
+
+    let mut values = Vec::with_capacity(columns as usize * rows as usize);
+    for row in 0..rows {
+        for column in 0..columns {
+            let cell_x = bounds.min_x + column as f32 * cell_width;
+            let cell_y = bounds.min_y + row as f32 * cell_height;
+
+            // Sample at sub-cell centers
+            let mut hits = 0;
+            for sy in 0..SAMPLES_PER_AXIS {
+                for sx in 0..SAMPLES_PER_AXIS {
+                    let x = cell_x + (sx as f32 + 0.5) / SAMPLES_PER_AXIS as f32 * cell_width;
+                    let y = cell_y + (sy as f32 + 0.5) / SAMPLES_PER_AXIS as f32 * cell_height;
+                    if index.covered(x, y) {
+                        hits += 1;
+                    }
+                }
+            }
+            values.push(hits as f32 / sample_count);
+        }
+    }
+
+    Ok(CopperDensity {
+        columns,
+        rows,
+        values,
+        min_x: bounds.min_x,
+        max_x: bounds.max_x,
+        min_y: bounds.min_y,
+        max_y: bounds.max_y,
+    })
+}
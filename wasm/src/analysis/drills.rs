@@ -0,0 +1,117 @@
+use crate::shape::GerberData;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+// Tool diameters are grouped in 1 um steps, so inch tools converted to mm
+// land on one tool
+const STEPS_PER_MM: f64 = 1000.0;
+
+/// Drill tools of a board with their hit and slot counts
+///
+/// One row per tool, sorted by diameter with plated tools before
+/// non-plated ones of the same size, as listed in fab quote tables.
+#[wasm_bindgen]
+pub struct DrillSummary {
+    pub(crate) diameter: Vec<f32>,
+    pub(crate) plated: Vec<bool>,
+    pub(crate) hits: Vec<u32>,
+    pub(crate) slots: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl DrillSummary {
+    /// Number of tools
+    #[wasm_bindgen(getter)]
+    pub fn tools(&self) -> usize {
+        self.diameter.len()
+    }
+
+    /// Diameter of each tool in mm, ascending
+    #[wasm_bindgen(getter)]
+    pub fn diameter(&self) -> Vec<f32> {
+        self.diameter.clone()
+    }
+
+    /// 1 for each plated tool, 0 for a non-plated one
+    #[wasm_bindgen(getter)]
+    pub fn plated(&self) -> Vec<u8> {
+        self.plated.iter().map(|&p| p as u8).collect()
+    }
+
+    /// Number of drill hits (flashes) of each tool
+    #[wasm_bindgen(getter)]
+    pub fn hits(&self) -> Vec<u32> {
+        self.hits.clone()
+    }
+
+    /// Number of routed slots (draws) of each tool
+    #[wasm_bindgen(getter)]
+    pub fn slots(&self) -> Vec<u32> {
+        self.slots.clone()
+    }
+
+    /// Hits of all tools
+    #[wasm_bindgen(getter)]
+    pub fn total_hits(&self) -> u32 {
+        self.hits.iter().sum()
+    }
+
+    /// Slots of all tools
+    #[wasm_bindgen(getter)]
+    pub fn total_slots(&self) -> u32 {
+        self.slots.iter().sum()
+    }
+
+    /// The summary as CSV with a header row, one tool per line
+    ///
+    /// Tools are numbered T1, T2, ... in table order.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("Tool,Diameter (mm),Plated,Hits,Slots\n");
+        for i in 0..self.diameter.len() {
+            csv.push_str(&format!(
+                "T{},{:.3},{},{},{}\n",
+                i + 1,
+                self.diameter[i],
+                if self.plated[i] { "PTH" } else { "NPTH" },
+                self.hits[i],
+                self.slots[i]
+            ));
+        }
+        csv
+    }
+}
+
+/// Count the hits and slots of drill layers per tool
+///
+/// `layers` holds each drill layer with whether its holes are plated. Hits
+/// are the flashes and slots the draws of the dark sublayers; tools of the
+/// same diameter and plating in different layers are merged.
+pub fn drill_summary(layers: &[(&[GerberData], bool)]) -> DrillSummary {
+    // (diameter step, non-plated) -> (hits, slots); plated sorts first
+    let mut tools: BTreeMap<(i64, bool), (u32, u32)> = BTreeMap::new();
+    let step = |diameter: f32| (diameter as f64 * STEPS_PER_MM).round() as i64;
+    for &(sublayers, plated) in layers {
+        for data in sublayers.iter().step_by(2) {
+            for &width in &data.flashes.width {
+                tools.entry((step(width), !plated)).or_default().0 += 1;
+            }
+            for &width in &data.traces.width {
+                tools.entry((step(width), !plated)).or_default().1 += 1;
+            }
+        }
+    }
+
+    let mut summary = DrillSummary {
+        diameter: Vec::with_capacity(tools.len()),
+        plated: Vec::with_capacity(tools.len()),
+        hits: Vec::with_capacity(tools.len()),
+        slots: Vec::with_capacity(tools.len()),
+    };
+    for ((step, non_plated), (hits, slots)) in tools {
+        summary.diameter.push((step as f64 / STEPS_PER_MM) as f32);
+        summary.plated.push(!non_plated);
+        summary.hits.push(hits);
+        summary.slots.push(slots);
+    }
+    summary
+}
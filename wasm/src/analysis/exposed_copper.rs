@@ -0,0 +1,130 @@
+use super::contours::merged_shapes;
+use super::markers::DrcMarkers;
+use crate::shape::GerberData;
+use i_overlay::core::fill_rule::FillRule;
+use i_overlay::core::overlay_rule::OverlayRule;
+use i_overlay::float::single::SingleFloatOverlay;
+use wasm_bindgen::prelude::*;
+
+// Regions listed individually, largest first
+const MAX_REGIONS: usize = 20;
+
+/// Copper left bare by the solder mask, e.g. for surface finish cost estimates
+#[wasm_bindgen]
+pub struct ExposedCopperReport {
+    pub(crate) total_area: f32,
+    pub(crate) region_count: usize,
+    pub(crate) region_x: Vec<f32>, // Centroid of each listed region
+    pub(crate) region_y: Vec<f32>,
+    pub(crate) region_radius: Vec<f32>, // Distance from centroid to the farthest outline point
+    pub(crate) region_area: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl ExposedCopperReport {
+    /// Total exposed copper area in mm²
+    #[wasm_bindgen(getter)]
+    pub fn total_area(&self) -> f32 {
+        self.total_area
+    }
+
+    /// Number of separate exposed regions
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.region_count
+    }
+
+    /// Centroids of the largest exposed regions, largest first
+    #[wasm_bindgen(getter)]
+    pub fn region_x(&self) -> Vec<f32> {
+        self.region_x.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn region_y(&self) -> Vec<f32> {
+        self.region_y.clone()
+    }
+
+    /// Extent of each listed region around its centroid in mm
+    #[wasm_bindgen(getter)]
+    pub fn region_radius(&self) -> Vec<f32> {
+        self.region_radius.clone()
+    }
+
+    /// Area of each listed region in mm²
+    #[wasm_bindgen(getter)]
+    pub fn region_area(&self) -> Vec<f32> {
+        self.region_area.clone()
+    }
+
+    /// Marker rings around the listed regions for the DRC overlay
+    pub fn markers(&self) -> DrcMarkers {
+        let mut markers = DrcMarkers::default();
+        for i in 0..self.region_x.len() {
+            markers.push(self.region_x[i], self.region_y[i], self.region_radius[i]);
+        }
+        markers
+    }
+}
+
+/// Measure the copper inside the solder mask openings
+///
+/// The mask layer's dark geometry marks openings, so exposed copper is the
+/// overlap of both layers' final images. Curves are flattened to within
+/// `chord_error` (mm).
+pub fn exposed_copper(
+    copper: &[GerberData],
+    mask: &[GerberData],
+    chord_error: f32,
+) -> ExposedCopperReport {
+    let shapes = merged_shapes(copper, chord_error).overlay(
+        &merged_shapes(mask, chord_error),
+        OverlayRule::Intersect,
+        FillRule::NonZero,
+    );
+
+    // (area, centroid, radius) of every region
+    let mut regions: Vec<(f32, [f32; 2], f32)> = shapes
+        .iter()
+        .filter(|shape| !shape.is_empty())
+        .map(|shape| {
+            // Holes run clockwise, so their signed areas subtract
+            let (mut area, mut cx, mut cy) = (0.0, 0.0, 0.0);
+            for ring in shape {
+                for (a, b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+                    let cross = a[0] * b[1] - b[0] * a[1];
+                    area += cross / 2.0;
+                    cx += (a[0] + b[0]) * cross / 6.0;
+                    cy += (a[1] + b[1]) * cross / 6.0;
+                }
+            }
+            let centroid = if area.abs() > f32::EPSILON {
+                [cx / area, cy / area]
+            } else {
+                shape[0][0]
+            };
+            let radius = shape[0]
+                .iter()
+                .map(|p| (p[0] - centroid[0]).hypot(p[1] - centroid[1]))
+                .fold(0.0, f32::max);
+            (area.abs(), centroid, radius)
+        })
+        .collect();
+    regions.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut report = ExposedCopperReport {
+        total_area: regions.iter().map(|region| region.0).sum(),
+        region_count: regions.len(),
+        region_x: Vec::new(),
+        region_y: Vec::new(),
+        region_radius: Vec::new(),
+        region_area: Vec::new(),
+    };
+    for &(area, [x, y], radius) in regions.iter().take(MAX_REGIONS) {
+        report.region_x.push(x);
+        report.region_y.push(y);
+        report.region_radius.push(radius);
+        report.region_area.push(area);
+    }
+    report
+}
@@ -0,0 +1,203 @@
+use super::markers::DrcMarkers;
+use crate::parser::{ApertureFunction, ApertureShape};
+use crate::shape::GerberData;
+use wasm_bindgen::prelude::*;
+
+// Diameter range of round pads taken for fiducials without an attribute (mm)
+const MIN_DIAMETER: f32 = 0.5;
+const MAX_DIAMETER: f32 = 3.0;
+// Mask opening beyond the pad edge that sets a fiducial apart from a pad (mm)
+const MIN_CLEARANCE: f32 = 0.25;
+// Largest offset between a pad and its mask opening (mm)
+pub(super) const CENTER_TOLERANCE: f32 = 0.05;
+
+/// Fiducials found on a copper layer
+///
+/// Pads flashed with a FiducialPad aperture are always listed. Other pads
+/// count when they are round, 0.5 to 3 mm across, sit in a mask opening
+/// at least 0.25 mm wider all around, have no drill hit and no draw
+/// ending on them.
+#[wasm_bindgen]
+pub struct FiducialList {
+    pub(crate) x: Vec<f32>,
+    pub(crate) y: Vec<f32>,
+    pub(crate) diameter: Vec<f32>,
+    pub(crate) opening: Vec<f32>, // Mask opening diameter, 0.0 when unknown
+    pub(crate) attributed: Vec<bool>,
+}
+
+#[wasm_bindgen]
+impl FiducialList {
+    /// Number of fiducials
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.x.len()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> Vec<f32> {
+        self.x.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> Vec<f32> {
+        self.y.clone()
+    }
+
+    /// Pad diameter of each fiducial in mm (width of non-round attributed pads)
+    #[wasm_bindgen(getter)]
+    pub fn diameter(&self) -> Vec<f32> {
+        self.diameter.clone()
+    }
+
+    /// Mask opening diameter of each fiducial in mm, 0 when no mask layer
+    /// was given or the pad has no round opening
+    #[wasm_bindgen(getter)]
+    pub fn opening(&self) -> Vec<f32> {
+        self.opening.clone()
+    }
+
+    /// 1 for each fiducial marked with .AperFunction,FiducialPad, 0 for one
+    /// found by its shape
+    #[wasm_bindgen(getter)]
+    pub fn attributed(&self) -> Vec<u8> {
+        self.attributed.iter().map(|&a| a as u8).collect()
+    }
+
+    /// Marker rings around each fiducial for the overlay
+    pub fn markers(&self) -> DrcMarkers {
+        let mut markers = DrcMarkers::default();
+        for i in 0..self.x.len() {
+            let radius = self.opening[i].max(self.diameter[i] * 1.5) / 2.0;
+            markers.push(self.x[i], self.y[i], radius);
+        }
+        markers
+    }
+}
+
+/// Circles (x, y, radius) sorted by x for neighbourhood lookups
+pub(super) struct Points(Vec<[f32; 3]>);
+
+impl Points {
+    pub(super) fn new(mut points: Vec<[f32; 3]>) -> Points {
+        points.sort_by(|a, b| a[0].total_cmp(&b[0]));
+        Points(points)
+    }
+
+    /// Points within `distance` of (x, y)
+    pub(super) fn near(&self, x: f32, y: f32, distance: f32) -> impl Iterator<Item = &[f32; 3]> {
+        let start = self.0.partition_point(|p| p[0] < x - distance);
+        self.0[start..]
+            .iter()
+            .take_while(move |p| p[0] <= x + distance)
+            .filter(move |p| (p[0] - x).hypot(p[1] - y) <= distance)
+    }
+}
+
+/// What surrounds the pads of a copper layer: the round mask openings and
+/// drill hits of the dark sublayers, and the points of every copper draw
+pub(super) struct PadContext {
+    pub(super) openings: Points,
+    pub(super) hits: Points,
+    pub(super) draw_points: Points,
+}
+
+impl PadContext {
+    pub(super) fn new(
+        copper: &[GerberData],
+        mask: Option<&[GerberData]>,
+        drill: Option<&[GerberData]>,
+    ) -> PadContext {
+        let openings = Points::new(
+            mask.unwrap_or_default()
+                .iter()
+                .step_by(2)
+                .flat_map(round_flashes)
+                .collect(),
+        );
+        let hits = Points::new(
+            drill
+                .unwrap_or_default()
+                .iter()
+                .step_by(2)
+                .flat_map(|data| {
+                    let c = &data.circles;
+                    (0..c.x.len()).map(|i| [c.x[i], c.y[i], c.radius[i]])
+                })
+                .collect(),
+        );
+        // Draw points tell pads with a trace attached apart
+        let draw_points = Points::new(
+            copper
+                .iter()
+                .flat_map(|data| data.traces.points.chunks_exact(2))
+                .map(|p| [p[0], p[1], 0.0])
+                .collect(),
+        );
+        PadContext {
+            openings,
+            hits,
+            draw_points,
+        }
+    }
+}
+
+/// Find the fiducials of a copper layer
+///
+/// `mask` is the solder mask layer of the same side and `drill` a drill
+/// layer; without a mask layer only attributed fiducials are found.
+pub fn find_fiducials(
+    copper: &[GerberData],
+    mask: Option<&[GerberData]>,
+    drill: Option<&[GerberData]>,
+) -> FiducialList {
+    let context = PadContext::new(copper, mask, drill);
+
+    let mut list = FiducialList {
+        x: Vec::new(),
+        y: Vec::new(),
+        diameter: Vec::new(),
+        opening: Vec::new(),
+        attributed: Vec::new(),
+    };
+    for data in copper.iter().step_by(2) {
+        let fiducial_codes = data.aperture_codes(ApertureFunction::FiducialPad);
+        let flashes = &data.flashes;
+        for i in 0..flashes.len() {
+            let (x, y) = (flashes.x[i], flashes.y[i]);
+            let attributed = fiducial_codes.contains(&flashes.d_code[i]);
+            let round = flashes.shape[i] == ApertureShape::Circle as u8;
+            let diameter = flashes.width[i];
+            let opening = context
+                .openings
+                .near(x, y, CENTER_TOLERANCE)
+                .map(|p| p[2] * 2.0)
+                .fold(0.0, f32::max);
+            if !attributed {
+                let radius = diameter / 2.0;
+                if !round
+                    || !(MIN_DIAMETER..=MAX_DIAMETER).contains(&diameter)
+                    || opening < diameter + 2.0 * MIN_CLEARANCE
+                    || context.hits.near(x, y, radius).next().is_some()
+                    || context.draw_points.near(x, y, radius).next().is_some()
+                {
+                    continue;
+                }
+            }
+            list.x.push(x);
+            list.y.push(y);
+            list.diameter.push(diameter);
+            list.opening.push(opening);
+            list.attributed.push(attributed);
+        }
+    }
+    list
+}
+
+/// Position and radius of the round flashes of a sublayer
+pub(super) fn round_flashes(data: &GerberData) -> impl Iterator<Item = [f32; 3]> + '_ {
+    let flashes = &data.flashes;
+    (0..flashes.len())
+        .filter(|&i| flashes.shape[i] == ApertureShape::Circle as u8)
+        .map(|i| [flashes.x[i], flashes.y[i], flashes.width[i] / 2.0])
+}
@@ -0,0 +1,82 @@
+use crate::shape::{Flashes, GerberData};
+use wasm_bindgen::prelude::*;
+
+/// Every aperture flash (D03) of a layer as a pad list
+///
+/// Flashes keep file order within each sublayer; sublayers follow in drawing
+/// order. Positions are the flash points in mm, sizes the width and height of
+/// the aperture in mm.
+#[wasm_bindgen]
+pub struct FlashList {
+    pub(crate) flashes: Flashes,
+    pub(crate) sublayer: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl FlashList {
+    /// Number of flashes
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.flashes.len()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> Vec<f32> {
+        self.flashes.x.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> Vec<f32> {
+        self.flashes.y.clone()
+    }
+
+    /// Aperture D-code of each flash
+    #[wasm_bindgen(getter)]
+    pub fn d_code(&self) -> Vec<u16> {
+        self.flashes.d_code.clone()
+    }
+
+    /// Aperture template of each flash: 0 circle, 1 rectangle, 2 obround,
+    /// 3 polygon, 4 macro, 5 block
+    #[wasm_bindgen(getter)]
+    pub fn shape(&self) -> Vec<u8> {
+        self.flashes.shape.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> Vec<f32> {
+        self.flashes.width.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> Vec<f32> {
+        self.flashes.height.clone()
+    }
+
+    /// %LR rotation in effect for each flash, degrees counterclockwise
+    #[wasm_bindgen(getter)]
+    pub fn rotation(&self) -> Vec<f32> {
+        self.flashes.rotation.clone()
+    }
+
+    /// Sublayer of each flash; even sublayers are dark, odd ones clear
+    #[wasm_bindgen(getter)]
+    pub fn sublayer(&self) -> Vec<u32> {
+        self.sublayer.clone()
+    }
+}
+
+/// Collect the flashes recorded for all sublayers of a layer
+pub fn collect_flashes(sublayers: &[GerberData]) -> FlashList {
+    let mut list = FlashList {
+        flashes: Flashes::default(),
+        sublayer: Vec::new(),
+    };
+    for (index, data) in sublayers.iter().enumerate() {
+        let mut flashes = data.flashes.clone();
+        list.sublayer
+            .resize(list.sublayer.len() + flashes.len(), index as u32);
+        list.flashes.append(&mut flashes);
+    }
+    list
+}
@@ -0,0 +1,69 @@
+use crate::parser::ApertureShape;
+use crate::shape::GerberData;
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+
+// Diameters are grouped in 1 um steps, so inch apertures converted to mm
+// land in one bin
+const STEPS_PER_MM: f64 = 1000.0;
+
+/// Circular flashes of a layer counted per diameter
+///
+/// Bins are sorted by diameter, e.g. the via and drill sizes of a board with
+/// how often each is used.
+#[wasm_bindgen]
+pub struct SizeHistogram {
+    pub(crate) diameter: Vec<f32>,
+    pub(crate) count: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl SizeHistogram {
+    /// Number of distinct diameters
+    #[wasm_bindgen(getter)]
+    pub fn bins(&self) -> usize {
+        self.diameter.len()
+    }
+
+    /// Diameter of each bin in mm, ascending
+    #[wasm_bindgen(getter)]
+    pub fn diameter(&self) -> Vec<f32> {
+        self.diameter.clone()
+    }
+
+    /// Number of flashes in each bin
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> Vec<u32> {
+        self.count.clone()
+    }
+
+    /// Number of circular flashes in all bins
+    #[wasm_bindgen(getter)]
+    pub fn total(&self) -> u32 {
+        self.count.iter().sum()
+    }
+}
+
+/// Count the circular flashes of a layer's dark sublayers by diameter
+///
+/// Flashes in clear sublayers cut copper away and are not counted.
+pub fn size_histogram(sublayers: &[GerberData]) -> SizeHistogram {
+    let mut bins: BTreeMap<i64, u32> = BTreeMap::new();
+    for data in sublayers.iter().step_by(2) {
+        let flashes = &data.flashes;
+        for (&shape, &width) in flashes.shape.iter().zip(&flashes.width) {
+            if shape == ApertureShape::Circle as u8 {
+                *bins
+                    .entry((width as f64 * STEPS_PER_MM).round() as i64)
+                    .or_default() += 1;
+            }
+        }
+    }
+    SizeHistogram {
+        diameter: bins
+            .keys()
+            .map(|&step| (step as f64 / STEPS_PER_MM) as f32)
+            .collect(),
+        count: bins.into_values().collect(),
+    }
+}
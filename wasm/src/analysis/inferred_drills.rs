@@ -0,0 +1,140 @@
+use super::fiducials::{round_flashes, Points, CENTER_TOLERANCE};
+use super::markers::DrcMarkers;
+use crate::export::write_excellon;
+use crate::shape::GerberData;
+use std::collections::HashSet;
+use wasm_bindgen::prelude::*;
+
+// Hole diameter guessed for pads without a hole, as a fraction of the
+// smaller pad diameter, rounded to a drill size step (mm)
+const ESTIMATED_HOLE_RATIO: f32 = 0.5;
+const DRILL_SIZE_STEP: f32 = 0.05;
+
+/// Drill hits inferred from coincident round pads on both copper sides
+///
+/// Only a best guess for jobs that lack a drill file: hole diameters come
+/// from pad apertures with a hole where given, otherwise they are estimated
+/// from the pad size.
+#[wasm_bindgen]
+pub struct InferredDrills {
+    pub(crate) x: Vec<f32>,
+    pub(crate) y: Vec<f32>,
+    pub(crate) diameter: Vec<f32>,
+    pub(crate) estimated: Vec<bool>,
+}
+
+#[wasm_bindgen]
+impl InferredDrills {
+    /// Number of inferred hits
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.x.len()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> Vec<f32> {
+        self.x.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> Vec<f32> {
+        self.y.clone()
+    }
+
+    /// Hole diameter of each hit in mm
+    #[wasm_bindgen(getter)]
+    pub fn diameter(&self) -> Vec<f32> {
+        self.diameter.clone()
+    }
+
+    /// 1 for each hit whose diameter was estimated from the pad size, 0 for
+    /// one taken from a pad hole
+    #[wasm_bindgen(getter)]
+    pub fn estimated(&self) -> Vec<u8> {
+        self.estimated.iter().map(|&e| e as u8).collect()
+    }
+
+    /// The hits as an Excellon drill file (mm, plated)
+    ///
+    /// The header says the file was inferred from copper pads and is no
+    /// fabrication drill file.
+    pub fn to_excellon(&self) -> String {
+        write_excellon(
+            &[
+                "Inferred from coincident top and bottom copper pads",
+                "Best effort only - not a fabrication drill file",
+            ],
+            &self.x,
+            &self.y,
+            &self.diameter,
+        )
+    }
+
+    /// Marker rings at the size of each hole for the overlay
+    pub fn markers(&self) -> DrcMarkers {
+        let mut markers = DrcMarkers::default();
+        for i in 0..self.x.len() {
+            markers.push(self.x[i], self.y[i], self.diameter[i] / 2.0);
+        }
+        markers
+    }
+}
+
+/// Infer drill hits from round pads flashed at the same place on both sides
+///
+/// A hit is placed at the top pad center; its diameter is the largest pad
+/// hole at that place, or half the smaller pad rounded to 0.05 mm.
+pub fn infer_drills(top: &[GerberData], bottom: &[GerberData]) -> InferredDrills {
+    let bottom_pads = Points::new(bottom.iter().step_by(2).flat_map(round_flashes).collect());
+    // Holes of pad apertures on either side
+    let holes = Points::new(
+        top.iter()
+            .step_by(2)
+            .chain(bottom.iter().step_by(2))
+            .flat_map(|data| {
+                let c = &data.circles;
+                (0..c.x.len())
+                    .filter(|&i| c.hole_radius[i] > 0.0)
+                    .map(|i| [c.hole_x[i], c.hole_y[i], c.hole_radius[i]])
+            })
+            .collect(),
+    );
+
+    let mut drills = InferredDrills {
+        x: Vec::new(),
+        y: Vec::new(),
+        diameter: Vec::new(),
+        estimated: Vec::new(),
+    };
+    let mut placed = HashSet::new(); // Hit positions in um
+    for [x, y, radius] in top.iter().step_by(2).flat_map(round_flashes) {
+        let bottom_radius = match bottom_pads
+            .near(x, y, CENTER_TOLERANCE)
+            .map(|p| p[2])
+            .reduce(f32::max)
+        {
+            Some(radius) => radius,
+            None => continue,
+        };
+        // Pads flashed twice at one place give one hit
+        if !placed.insert(((x * 1000.0).round() as i64, (y * 1000.0).round() as i64)) {
+            continue;
+        }
+        let hole = holes
+            .near(x, y, CENTER_TOLERANCE)
+            .map(|p| p[2] * 2.0)
+            .fold(0.0, f32::max);
+        let estimated = hole <= 0.0;
+        let diameter = if estimated {
+            let guess = radius.min(bottom_radius) * 2.0 * ESTIMATED_HOLE_RATIO;
+            ((guess / DRILL_SIZE_STEP).round() * DRILL_SIZE_STEP).max(DRILL_SIZE_STEP)
+        } else {
+            hole
+        };
+        drills.x.push(x);
+        drills.y.push(y);
+        drills.diameter.push(diameter);
+        drills.estimated.push(estimated);
+    }
+    drills
+}
@@ -0,0 +1,55 @@
+use crate::error::ViewerError;
+use wasm_bindgen::prelude::*;
+
+/// Marker positions for the DRC overlay (ring markers in world coordinates)
+#[wasm_bindgen]
+#[derive(Clone, Default)]
+pub struct DrcMarkers {
+    pub(crate) x: Vec<f32>,
+    pub(crate) y: Vec<f32>,
+    pub(crate) radius: Vec<f32>,
+}
+
+impl DrcMarkers {
+    /// Add a marker ring centered at (x, y)
+    pub(crate) fn push(&mut self, x: f32, y: f32, radius: f32) {
+        self.x.push(x);
+        self.y.push(y);
+        self.radius.push(radius);
+    }
+}
+
+#[wasm_bindgen]
+impl DrcMarkers {
+    /// Create a marker set from flat coordinate arrays
+    #[wasm_bindgen(constructor)]
+    pub fn new(x: Vec<f32>, y: Vec<f32>, radius: Vec<f32>) -> Result<DrcMarkers, JsValue> {
+        if x.len() != y.len() || x.len() != radius.len() {
+            return Err(ViewerError::invalid_argument(
+                "Marker arrays x, y and radius must have the same length",
+            )
+            .into());
+        }
+        Ok(DrcMarkers { x, y, radius })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.x.len()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> Vec<f32> {
+        self.x.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> Vec<f32> {
+        self.y.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn radius(&self) -> Vec<f32> {
+        self.radius.clone()
+    }
+}
@@ -0,0 +1,218 @@
+use super::coverage::CoverageIndex;
+use super::markers::DrcMarkers;
+use super::width_spacing::check_width_spacing;
+use crate::shape::{Boundary, GerberData};
+use wasm_bindgen::prelude::*;
+
+// Raster samples along the longer side of the silkscreen, used for overlap search
+const SAMPLES_ALONG_LONG_SIDE: usize = 1024;
+// Marker ring margin around each violation (mm)
+const MARKER_MARGIN: f32 = 0.1;
+
+/// Solder mask slivers and silkscreen-over-pad violations
+#[wasm_bindgen]
+pub struct MaskSilkReport {
+    pub(crate) sliver_x: Vec<f32>,
+    pub(crate) sliver_y: Vec<f32>,
+    pub(crate) sliver_width: Vec<f32>,
+    pub(crate) overlap_x: Vec<f32>, // Centroid of each overlap region
+    pub(crate) overlap_y: Vec<f32>,
+    pub(crate) overlap_radius: Vec<f32>, // Distance from centroid to the farthest sample
+    pub(crate) overlap_area: Vec<f32>,
+}
+
+#[wasm_bindgen]
+impl MaskSilkReport {
+    /// Locations of mask webs narrower than the sliver threshold
+    #[wasm_bindgen(getter)]
+    pub fn sliver_x(&self) -> Vec<f32> {
+        self.sliver_x.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn sliver_y(&self) -> Vec<f32> {
+        self.sliver_y.clone()
+    }
+
+    /// Measured width of each mask sliver in mm
+    #[wasm_bindgen(getter)]
+    pub fn sliver_width(&self) -> Vec<f32> {
+        self.sliver_width.clone()
+    }
+
+    /// Centroids of silkscreen regions printed on exposed pads
+    #[wasm_bindgen(getter)]
+    pub fn overlap_x(&self) -> Vec<f32> {
+        self.overlap_x.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn overlap_y(&self) -> Vec<f32> {
+        self.overlap_y.clone()
+    }
+
+    /// Extent of each overlap region around its centroid in mm
+    #[wasm_bindgen(getter)]
+    pub fn overlap_radius(&self) -> Vec<f32> {
+        self.overlap_radius.clone()
+    }
+
+    /// Area of each overlap region in mm²
+    #[wasm_bindgen(getter)]
+    pub fn overlap_area(&self) -> Vec<f32> {
+        self.overlap_area.clone()
+    }
+
+    /// Total number of violations
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.sliver_x.len() + self.overlap_x.len()
+    }
+
+    /// Marker rings around every sliver and overlap for the DRC overlay
+    pub fn markers(&self) -> DrcMarkers {
+        let mut markers = DrcMarkers::default();
+        for i in 0..self.sliver_x.len() {
+            markers.push(
+                self.sliver_x[i],
+                self.sliver_y[i],
+                self.sliver_width[i] / 2.0 + MARKER_MARGIN,
+            );
+        }
+        for i in 0..self.overlap_x.len() {
+            markers.push(
+                self.overlap_x[i],
+                self.overlap_y[i],
+                self.overlap_radius[i] + MARKER_MARGIN,
+            );
+        }
+        markers
+    }
+}
+
+/// Check a solder mask layer for slivers and a silkscreen layer for ink on exposed pads
+///
+/// Mask slivers are the gaps between mask openings narrower than `min_sliver`. A pad is
+/// exposed where copper and a mask opening overlap; silkscreen over it is reported per
+/// connected overlap region.
+pub fn check_mask_silk(
+    mask_layer: &[GerberData],
+    silk_layer: &[GerberData],
+    copper_layer: &[GerberData],
+    min_sliver: f32,
+) -> MaskSilkReport {
+    // Mask slivers are the narrow gaps between openings of the mask layer
+    let slivers = check_width_spacing(mask_layer, 0.0, min_sliver).spacing;
+    let mut report = MaskSilkReport {
+        sliver_x: slivers.x,
+        sliver_y: slivers.y,
+        sliver_width: slivers.value,
+        overlap_x: Vec::new(),
+        overlap_y: Vec::new(),
+        overlap_radius: Vec::new(),
+        overlap_area: Vec::new(),
+    };
+
+    let mask = CoverageIndex::new(mask_layer);
+    let silk = CoverageIndex::new(silk_layer);
+    let copper = CoverageIndex::new(copper_layer);
+
+    // Overlaps can only occur where all three layers overlap
+    let bounds = Boundary::new(
+        silk.bounds()
+            .min_x
+            .max(mask.bounds().min_x)
+            .max(copper.bounds().min_x),
+        silk.bounds()
+            .max_x
+            .min(mask.bounds().max_x)
+            .min(copper.bounds().max_x),
+        silk.bounds()
+            .min_y
+            .max(mask.bounds().min_y)
+            .max(copper.bounds().min_y),
+        silk.bounds()
+            .max_y
+            .min(mask.bounds().max_y)
+            .min(copper.bounds().max_y),
+    );
+    let width = bounds.max_x - bounds.min_x;
+    let height = bounds.max_y - bounds.min_y;
+    if width <= 0.0 || height <= 0.0 {
+        return report;
+    }
+
+    let step = width.max(height) / SAMPLES_ALONG_LONG_SIDE as f32;
+    let columns = (width / step).ceil().max(1.0) as usize;
+    let rows = (height / step).ceil().max(1.0) as usize;
+    let sample = |column: usize, row: usize| {
+        (
+            bounds.min_x + (column as f32 + 0.5) * step,
+            bounds.min_y + (row as f32 + 0.5) * step,
+        )
+    };
+
+    let mut overlap = vec![false; columns * rows];
+    for row in 0..rows {
+        for column in 0..columns {
+            let (x, y) = sample(column, row);
+            overlap[row * columns + column] =
+                silk.covered(x, y) && mask.covered(x, y) && copper.covered(x, y);
+        }
+    }
+
+    // Group overlapping samples into 4-connected regions
+    let mut visited = vec![false; columns * rows];
+    let mut stack = Vec::new();
+    for start in 0..overlap.len() {
+        if !overlap[start] || visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        stack.push(start);
+        let mut region = Vec::new();
+        while let Some(cell) = stack.pop() {
+            region.push(cell);
+            let (column, row) = (cell % columns, cell / columns);
+            let mut neighbors = Vec::with_capacity(4);
+            if column > 0 {
+                neighbors.push(cell - 1);
+            }
+            if column + 1 < columns {
+                neighbors.push(cell + 1);
+            }
+            if row > 0 {
+                neighbors.push(cell - columns);
+            }
+            if row + 1 < rows {
+                neighbors.push(cell + columns);
+            }
+            for neighbor in neighbors {
+                if overlap[neighbor] && !visited[neighbor] {
+                    visited[neighbor] = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+
+        let points: Vec<(f32, f32)> = region
+            .iter()
+            .map(|&cell| sample(cell % columns, cell / columns))
+            .collect();
+        let count = points.len() as f32;
+        let center_x = points.iter().map(|p| p.0).sum::<f32>() / count;
+        let center_y = points.iter().map(|p| p.1).sum::<f32>() / count;
+        let radius = points
+            .iter()
+            .map(|p| (p.0 - center_x).hypot(p.1 - center_y))
+            .fold(0.0f32, f32::max)
+            + step;
+
+        report.overlap_x.push(center_x);
+        report.overlap_y.push(center_y);
+        report.overlap_radius.push(radius);
+        report.overlap_area.push(count * step * step);
+    }
+
+    report
+}
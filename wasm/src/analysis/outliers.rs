@@ -0,0 +1,169 @@
+use crate::shape::{Boundary, GerberData};
+use wasm_bindgen::prelude::*;
+
+// Fraction of primitive centers trimmed from each side for the core box
+const CORE_TRIM: f32 = 0.02;
+// Primitives may reach this far beyond the core box, as a fraction of its longer side
+const CORE_MARGIN: f32 = 0.5;
+// Smallest margin around the core box in mm
+const MIN_MARGIN: f32 = 1.0;
+// Layers with fewer primitives are too small to tell outliers apart
+const MIN_PRIMITIVES: usize = 8;
+
+/// Primitives lying far outside the bulk of the board
+///
+/// A primitive is an outlier when its extent reaches beyond the box holding
+/// the middle 96% of all primitive centers, grown by half its longer side,
+/// e.g. a stray flash at (0, 0) or a frame drawn far around the board.
+#[wasm_bindgen]
+pub struct BoundaryOutliers {
+    pub(crate) layer_id: Vec<u32>,
+    pub(crate) extents: Vec<[f32; 4]>, // min_x, min_y, max_x, max_y
+    pub(crate) boundary: Boundary,
+}
+
+#[wasm_bindgen]
+impl BoundaryOutliers {
+    /// Number of outlier primitives
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.layer_id.len()
+    }
+
+    /// Layer ID of each outlier
+    #[wasm_bindgen(getter)]
+    pub fn layer_id(&self) -> Vec<u32> {
+        self.layer_id.clone()
+    }
+
+    /// Center x of each outlier's bounding box
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> Vec<f32> {
+        self.extents.iter().map(|e| (e[0] + e[2]) / 2.0).collect()
+    }
+
+    /// Center y of each outlier's bounding box
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> Vec<f32> {
+        self.extents.iter().map(|e| (e[1] + e[3]) / 2.0).collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> Vec<f32> {
+        self.extents.iter().map(|e| e[2] - e[0]).collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> Vec<f32> {
+        self.extents.iter().map(|e| e[3] - e[1]).collect()
+    }
+
+    /// Boundary of the primitives that are not outliers
+    #[wasm_bindgen(getter)]
+    pub fn boundary(&self) -> Boundary {
+        self.boundary
+    }
+}
+
+/// Find the outlier primitives of some layers and the boundary without them
+///
+/// `boundary` is the plain combined boundary, returned unchanged when there
+/// are no outliers.
+pub fn find_outliers(layers: &[(u32, &[GerberData])], boundary: Boundary) -> BoundaryOutliers {
+    let mut primitives: Vec<(u32, [f32; 4])> = Vec::new();
+    for &(layer_id, sublayers) in layers {
+        for data in sublayers {
+            for_each_extent(data, |extent| primitives.push((layer_id, extent)));
+        }
+    }
+    let mut outliers = BoundaryOutliers {
+        layer_id: Vec::new(),
+        extents: Vec::new(),
+        boundary,
+    };
+    if primitives.len() < MIN_PRIMITIVES {
+        return outliers;
+    }
+
+    // Core box from trimmed center percentiles, then grown by the margin
+    let (x0, x1) = trimmed_range(primitives.iter().map(|(_, e)| (e[0] + e[2]) / 2.0));
+    let (y0, y1) = trimmed_range(primitives.iter().map(|(_, e)| (e[1] + e[3]) / 2.0));
+    let margin = ((x1 - x0).max(y1 - y0) * CORE_MARGIN).max(MIN_MARGIN);
+    let allowed = [x0 - margin, y0 - margin, x1 + margin, y1 + margin];
+
+    let mut inside = [
+        f32::INFINITY,
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+        f32::NEG_INFINITY,
+    ];
+    for (layer_id, e) in primitives {
+        if e[0] >= allowed[0] && e[1] >= allowed[1] && e[2] <= allowed[2] && e[3] <= allowed[3] {
+            inside = [
+                inside[0].min(e[0]),
+                inside[1].min(e[1]),
+                inside[2].max(e[2]),
+                inside[3].max(e[3]),
+            ];
+        } else {
+            outliers.layer_id.push(layer_id);
+            outliers.extents.push(e);
+        }
+    }
+    if !outliers.extents.is_empty() && inside[0] <= inside[2] {
+        outliers.boundary = Boundary::new(inside[0], inside[2], inside[1], inside[3]);
+    }
+    outliers
+}
+
+/// Values at CORE_TRIM and 1 - CORE_TRIM, dropping at least one value per side
+fn trimmed_range(values: impl Iterator<Item = f32>) -> (f32, f32) {
+    let mut values: Vec<f32> = values.collect();
+    values.sort_by(f32::total_cmp);
+    let trim = ((values.len() as f32 * CORE_TRIM) as usize).max(1);
+    (values[trim], values[values.len() - 1 - trim])
+}
+
+/// Call `f` with the bounding box of every primitive
+fn for_each_extent(data: &GerberData, mut f: impl FnMut([f32; 4])) {
+    let t = &data.triangles;
+    for triangle in t.indices.chunks_exact(3) {
+        let mut extent = [
+            f32::INFINITY,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::NEG_INFINITY,
+        ];
+        for &index in triangle {
+            let (x, y) = (
+                t.vertices[index as usize * 2],
+                t.vertices[index as usize * 2 + 1],
+            );
+            extent = [
+                extent[0].min(x),
+                extent[1].min(y),
+                extent[2].max(x),
+                extent[3].max(y),
+            ];
+        }
+        f(extent);
+    }
+
+    let around = |x: f32, y: f32, r: f32| [x - r, y - r, x + r, y + r];
+    let c = &data.circles;
+    for i in 0..c.x.len() {
+        f(around(c.x[i], c.y[i], c.radius[i]));
+    }
+    let a = &data.arcs;
+    for i in 0..a.x.len() {
+        f(around(a.x[i], a.y[i], a.radius[i] + a.thickness[i] / 2.0));
+    }
+    let th = &data.thermals;
+    for i in 0..th.x.len() {
+        f(around(th.x[i], th.y[i], th.outer_diameter[i] / 2.0));
+    }
+    let o = &data.obrounds;
+    for i in 0..o.x.len() {
+        f(around(o.x[i], o.y[i], o.width[i].max(o.height[i]) / 2.0));
+    }
+}
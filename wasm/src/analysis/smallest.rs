@@ -0,0 +1,147 @@
+use crate::shape::GerberData;
+use wasm_bindgen::prelude::*;
+
+/// One smallest feature: its size, where it is and what drew it
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SmallestFeature {
+    pub(crate) layer_id: u32,
+    pub(crate) d_code: u16, // 0 for drill hits, which have no aperture
+    pub(crate) size: f32,
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+}
+
+#[wasm_bindgen]
+impl SmallestFeature {
+    #[wasm_bindgen(getter)]
+    pub fn layer_id(&self) -> u32 {
+        self.layer_id
+    }
+
+    /// Aperture D-code (0 for drill hits)
+    #[wasm_bindgen(getter)]
+    pub fn d_code(&self) -> u16 {
+        self.d_code
+    }
+
+    /// Line width, smaller pad side or hole diameter in mm
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> f32 {
+        self.size
+    }
+
+    /// Position in mm: the start of a draw, the center of a pad or hole
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> f32 {
+        self.x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> f32 {
+        self.y
+    }
+}
+
+/// Smallest draw aperture, flash and drill hit of a set of layers
+///
+/// Each is None when the layers have no such feature.
+#[wasm_bindgen]
+pub struct SmallestFeatures {
+    pub(crate) draw: Option<SmallestFeature>,
+    pub(crate) flash: Option<SmallestFeature>,
+    pub(crate) drill: Option<SmallestFeature>,
+}
+
+#[wasm_bindgen]
+impl SmallestFeatures {
+    /// Narrowest draw (D01) aperture, a proxy for the minimum trace width
+    #[wasm_bindgen(getter)]
+    pub fn draw(&self) -> Option<SmallestFeature> {
+        self.draw
+    }
+
+    /// Flash (D03) whose aperture has the smallest side
+    #[wasm_bindgen(getter)]
+    pub fn flash(&self) -> Option<SmallestFeature> {
+        self.flash
+    }
+
+    /// Drill hit with the smallest diameter
+    #[wasm_bindgen(getter)]
+    pub fn drill(&self) -> Option<SmallestFeature> {
+        self.drill
+    }
+}
+
+/// Find the smallest features of the dark sublayers of `layers` and
+/// `drill_layers`, each given with its layer ID
+///
+/// Draws and flashes come from the recorded apertures of `layers`, drill hits
+/// are the circles of `drill_layers`. Features of zero size, such as outlines
+/// drawn with a zero-width aperture, are skipped. Ties keep the first feature
+/// in layer and file order.
+pub fn smallest_features(
+    layers: &[(u32, &[GerberData])],
+    drill_layers: &[(u32, &[GerberData])],
+) -> SmallestFeatures {
+    let mut report = SmallestFeatures {
+        draw: None,
+        flash: None,
+        drill: None,
+    };
+
+    for &(layer_id, sublayers) in layers {
+        for data in sublayers.iter().step_by(2) {
+            let traces = &data.traces;
+            for (i, (&d_code, &width)) in traces.d_code.iter().zip(&traces.width).enumerate() {
+                let start = traces.start[i] as usize * 2;
+                let feature = SmallestFeature {
+                    layer_id,
+                    d_code,
+                    size: width,
+                    x: traces.points[start],
+                    y: traces.points[start + 1],
+                };
+                keep_smaller(&mut report.draw, feature);
+            }
+
+            let flashes = &data.flashes;
+            for i in 0..flashes.len() {
+                let feature = SmallestFeature {
+                    layer_id,
+                    d_code: flashes.d_code[i],
+                    size: flashes.width[i].min(flashes.height[i]),
+                    x: flashes.x[i],
+                    y: flashes.y[i],
+                };
+                keep_smaller(&mut report.flash, feature);
+            }
+        }
+    }
+
+    for &(layer_id, sublayers) in drill_layers {
+        for data in sublayers.iter().step_by(2) {
+            let circles = &data.circles;
+            for i in 0..circles.x.len() {
+                let feature = SmallestFeature {
+                    layer_id,
+                    d_code: 0,
+                    size: circles.radius[i] * 2.0,
+                    x: circles.x[i],
+                    y: circles.y[i],
+                };
+                keep_smaller(&mut report.drill, feature);
+            }
+        }
+    }
+
+    report
+}
+
+/// Replace `smallest` with `feature` if it is smaller and not empty
+fn keep_smaller(smallest: &mut Option<SmallestFeature>, feature: SmallestFeature) {
+    if feature.size > 0.0 && smallest.is_none_or(|s| feature.size < s.size) {
+        *smallest = Some(feature);
+    }
+}
@@ -0,0 +1,157 @@
+use super::fiducials::{PadContext, Points, CENTER_TOLERANCE};
+use super::markers::DrcMarkers;
+use crate::parser::{ApertureFunction, ApertureShape};
+use crate::shape::GerberData;
+use wasm_bindgen::prelude::*;
+
+// Diameter range of round pads taken for test points without an attribute (mm)
+const MIN_DIAMETER: f32 = 0.6;
+const MAX_DIAMETER: f32 = 2.0;
+// Smallest edge-to-edge gap to any other pad, telling probe targets apart
+// from the pads of fine-pitch parts (mm)
+const MIN_SPACING: f32 = 1.0;
+// Functions of pads that are never taken for test points by their shape
+const OTHER_PAD_FUNCTIONS: [ApertureFunction; 6] = [
+    ApertureFunction::ViaPad,
+    ApertureFunction::SmdPad,
+    ApertureFunction::BgaPad,
+    ApertureFunction::ComponentPad,
+    ApertureFunction::FiducialPad,
+    ApertureFunction::Other,
+];
+
+/// Test points found on a copper layer
+///
+/// Pads flashed with a TestPad aperture are always listed. Other pads
+/// count when they are round, 0.6 to 2 mm across, exposed by a mask
+/// opening, have a draw ending on them, no drill hit and at least 1 mm to
+/// the next pad.
+#[wasm_bindgen]
+pub struct TestPointList {
+    pub(crate) x: Vec<f32>,
+    pub(crate) y: Vec<f32>,
+    pub(crate) width: Vec<f32>,
+    pub(crate) height: Vec<f32>,
+    pub(crate) attributed: Vec<bool>,
+}
+
+#[wasm_bindgen]
+impl TestPointList {
+    /// Number of test points
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.x.len()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x(&self) -> Vec<f32> {
+        self.x.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y(&self) -> Vec<f32> {
+        self.y.clone()
+    }
+
+    /// Pad width of each test point in mm (diameter of round pads)
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> Vec<f32> {
+        self.width.clone()
+    }
+
+    /// Pad height of each test point in mm (diameter of round pads)
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> Vec<f32> {
+        self.height.clone()
+    }
+
+    /// 1 for each test point marked with .AperFunction,TestPad, 0 for one
+    /// found by its shape
+    #[wasm_bindgen(getter)]
+    pub fn attributed(&self) -> Vec<u8> {
+        self.attributed.iter().map(|&a| a as u8).collect()
+    }
+
+    /// Marker rings around each test point for the overlay
+    pub fn markers(&self) -> DrcMarkers {
+        let mut markers = DrcMarkers::default();
+        for i in 0..self.x.len() {
+            let radius = self.width[i].max(self.height[i]) * 0.75;
+            markers.push(self.x[i], self.y[i], radius);
+        }
+        markers
+    }
+}
+
+/// Find the test points of a copper layer
+///
+/// `mask` is the solder mask layer of the same side and `drill` a drill
+/// layer; without a mask layer only attributed test points are found.
+pub fn find_test_points(
+    copper: &[GerberData],
+    mask: Option<&[GerberData]>,
+    drill: Option<&[GerberData]>,
+) -> TestPointList {
+    let context = PadContext::new(copper, mask, drill);
+    // Every copper pad, by its enclosing circle
+    let pads: Vec<[f32; 3]> = copper
+        .iter()
+        .step_by(2)
+        .flat_map(|data| {
+            let f = &data.flashes;
+            (0..f.len()).map(|i| [f.x[i], f.y[i], f.width[i].max(f.height[i]) / 2.0])
+        })
+        .collect();
+    let largest_pad = pads.iter().map(|p| p[2]).fold(0.0, f32::max);
+    let pads = Points::new(pads);
+
+    let mut list = TestPointList {
+        x: Vec::new(),
+        y: Vec::new(),
+        width: Vec::new(),
+        height: Vec::new(),
+        attributed: Vec::new(),
+    };
+    for data in copper.iter().step_by(2) {
+        let test_codes = data.aperture_codes(ApertureFunction::TestPad);
+        let other_codes: Vec<u16> = OTHER_PAD_FUNCTIONS
+            .iter()
+            .flat_map(|&function| data.aperture_codes(function))
+            .collect();
+        let flashes = &data.flashes;
+        for i in 0..flashes.len() {
+            let (x, y) = (flashes.x[i], flashes.y[i]);
+            let attributed = test_codes.contains(&flashes.d_code[i]);
+            if !attributed {
+                let diameter = flashes.width[i];
+                let radius = diameter / 2.0;
+                let exposed = context
+                    .openings
+                    .near(x, y, CENTER_TOLERANCE)
+                    .any(|p| p[2] >= radius);
+                let crowded = pads
+                    .near(x, y, radius + MIN_SPACING + largest_pad)
+                    .any(|p| {
+                        let distance = (p[0] - x).hypot(p[1] - y);
+                        distance > CENTER_TOLERANCE && distance - p[2] - radius < MIN_SPACING
+                    });
+                if other_codes.contains(&flashes.d_code[i])
+                    || flashes.shape[i] != ApertureShape::Circle as u8
+                    || !(MIN_DIAMETER..=MAX_DIAMETER).contains(&diameter)
+                    || !exposed
+                    || context.hits.near(x, y, radius).next().is_some()
+                    || context.draw_points.near(x, y, radius).next().is_none()
+                    || crowded
+                {
+                    continue;
+                }
+            }
+            list.x.push(x);
+            list.y.push(y);
+            list.width.push(flashes.width[i]);
+            list.height.push(flashes.height[i]);
+            list.attributed.push(attributed);
+        }
+    }
+    list
+}
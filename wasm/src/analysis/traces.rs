@@ -0,0 +1,87 @@
+use crate::shape::{GerberData, Traces};
+use wasm_bindgen::prelude::*;
+
+/// Draw sequences (D01) of a layer as polylines with width and D-code
+///
+/// Points of all polylines are stored back to back; polyline i runs from
+/// `start[i]` up to the next polyline's start. Each point after the first of
+/// a polyline ends one segment, straight or, with a non-zero sweep, an arc
+/// around its center. Sublayers follow in drawing order.
+#[wasm_bindgen]
+pub struct TraceList {
+    pub(crate) traces: Traces,
+    pub(crate) sublayer: Vec<u32>,
+}
+
+#[wasm_bindgen]
+impl TraceList {
+    /// Number of polylines
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.traces.len()
+    }
+
+    /// Index of the first point of each polyline
+    #[wasm_bindgen(getter)]
+    pub fn start(&self) -> Vec<u32> {
+        self.traces.start.clone()
+    }
+
+    /// Polyline points as [x0, y0, x1, y1, ...] in mm
+    #[wasm_bindgen(getter)]
+    pub fn points(&self) -> Vec<f32> {
+        self.traces.points.clone()
+    }
+
+    /// Sweep in radians of the arc ending at each point (counterclockwise
+    /// positive), 0.0 for straight segments and first points
+    #[wasm_bindgen(getter)]
+    pub fn sweep(&self) -> Vec<f32> {
+        self.traces.sweep.clone()
+    }
+
+    /// Arc center of the segment ending at each point as [x0, y0, ...] in mm
+    #[wasm_bindgen(getter)]
+    pub fn center(&self) -> Vec<f32> {
+        self.traces.center.clone()
+    }
+
+    /// Aperture D-code of each polyline
+    #[wasm_bindgen(getter)]
+    pub fn d_code(&self) -> Vec<u16> {
+        self.traces.d_code.clone()
+    }
+
+    /// Line width of each polyline in mm
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> Vec<f32> {
+        self.traces.width.clone()
+    }
+
+    /// Routed length of each polyline in mm, arcs measured along the arc
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> Vec<f32> {
+        self.traces.lengths()
+    }
+
+    /// Sublayer of each polyline; even sublayers are dark, odd ones clear
+    #[wasm_bindgen(getter)]
+    pub fn sublayer(&self) -> Vec<u32> {
+        self.sublayer.clone()
+    }
+}
+
+/// Collect the draws recorded for all sublayers of a layer
+pub fn collect_traces(sublayers: &[GerberData]) -> TraceList {
+    let mut list = TraceList {
+        traces: Traces::default(),
+        sublayer: Vec::new(),
+    };
+    for (index, data) in sublayers.iter().enumerate() {
+        let mut traces = data.traces.clone();
+        list.sublayer
+            .resize(list.sublayer.len() + traces.len(), index as u32);
+        list.traces.append(&mut traces);
+    }
+    list
+}
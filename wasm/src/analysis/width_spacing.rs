@@ -0,0 +1,328 @@
+use super::coverage::CoverageIndex;
+use super::markers::DrcMarkers;
+use crate::shape::GerberData;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+// Raster samples along the longer side of the layer, used to find copper edges
+const SAMPLES_ALONG_LONG_SIDE: usize = 2048;
+// Widths and gaps longer than this are not measured (mm)
+const MAX_MEASURE: f32 = 1.0;
+// Bisection steps refining each copper edge crossing
+const REFINE_STEPS: usize = 8;
+// Violations closer than this are reported once (mm)
+const DEDUP_CELL: f32 = 1.0;
+// Marker ring margin around the measured segment (mm)
+const MARKER_MARGIN: f32 = 0.1;
+
+/// Measured copper widths and gaps found below the requested minimums
+#[derive(Default)]
+pub(super) struct Measurements {
+    pub(super) x: Vec<f32>,
+    pub(super) y: Vec<f32>,
+    pub(super) value: Vec<f32>,
+    pub(super) min: Option<(f32, f32, f32)>, // Smallest measurement (value, x, y)
+}
+
+impl Measurements {
+    fn record(&mut self, value: f32, x: f32, y: f32) {
+        if self.min.is_none_or(|(min, _, _)| value < min) {
+            self.min = Some((value, x, y));
+        }
+    }
+}
+
+/// Minimum conductor width and clearance of a copper layer
+#[wasm_bindgen]
+pub struct WidthSpacingReport {
+    pub(super) width: Measurements,
+    pub(super) spacing: Measurements,
+}
+
+#[wasm_bindgen]
+impl WidthSpacingReport {
+    /// Smallest conductor width in mm (undefined if none under the measuring limit)
+    #[wasm_bindgen(getter)]
+    pub fn min_width(&self) -> Option<f32> {
+        self.width.min.map(|(value, _, _)| value)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn min_width_x(&self) -> Option<f32> {
+        self.width.min.map(|(_, x, _)| x)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn min_width_y(&self) -> Option<f32> {
+        self.width.min.map(|(_, _, y)| y)
+    }
+
+    /// Smallest copper-to-copper gap in mm (undefined if none under the measuring limit)
+    #[wasm_bindgen(getter)]
+    pub fn min_spacing(&self) -> Option<f32> {
+        self.spacing.min.map(|(value, _, _)| value)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn min_spacing_x(&self) -> Option<f32> {
+        self.spacing.min.map(|(_, x, _)| x)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn min_spacing_y(&self) -> Option<f32> {
+        self.spacing.min.map(|(_, _, y)| y)
+    }
+
+    /// Locations of conductors narrower than the width threshold
+    #[wasm_bindgen(getter)]
+    pub fn width_violation_x(&self) -> Vec<f32> {
+        self.width.x.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width_violation_y(&self) -> Vec<f32> {
+        self.width.y.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width_violation_value(&self) -> Vec<f32> {
+        self.width.value.clone()
+    }
+
+    /// Locations of gaps narrower than the spacing threshold
+    #[wasm_bindgen(getter)]
+    pub fn spacing_violation_x(&self) -> Vec<f32> {
+        self.spacing.x.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn spacing_violation_y(&self) -> Vec<f32> {
+        self.spacing.y.clone()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn spacing_violation_value(&self) -> Vec<f32> {
+        self.spacing.value.clone()
+    }
+
+    /// Marker rings around every width and spacing violation for the DRC overlay
+    pub fn markers(&self) -> DrcMarkers {
+        let mut markers = DrcMarkers::default();
+        for found in [&self.width, &self.spacing] {
+            for i in 0..found.x.len() {
+                markers.push(found.x[i], found.y[i], found.value[i] / 2.0 + MARKER_MARGIN);
+            }
+        }
+        markers
+    }
+}
+
+/// Estimate minimum conductor width and clearance of a copper layer
+///
+/// Copper edges are located on a raster of the layer. From every edge sample a ray is
+/// cast along the edge normal, inwards across the copper for the width and outwards
+/// across the gap for the spacing; both crossings are refined on the exact geometry.
+pub fn check_width_spacing(
+    sublayers: &[GerberData],
+    min_width: f32,
+    min_spacing: f32,
+) -> WidthSpacingReport {
+    let index = CoverageIndex::new(sublayers);
+    let bounds = *index.bounds();
+    let mut report = WidthSpacingReport {
+        width: Measurements::default(),
+        spacing: Measurements::default(),
+    };
+
+    let width = bounds.max_x - bounds.min_x;
+    let height = bounds.max_y - bounds.min_y;
+    if width <= 0.0 || height <= 0.0 {
+        return report;
+    }
+
+    // Raster with a one-sample bare margin so edges on the boundary are found
+    let step = width.max(height) / SAMPLES_ALONG_LONG_SIDE as f32;
+    let columns = (width / step).ceil() as usize + 2;
+    let rows = (height / step).ceil() as usize + 2;
+    let origin_x = bounds.min_x - step * 0.5;
+    let origin_y = bounds.min_y - step * 0.5;
+    let mut raster = vec![false; columns * rows];
+    for row in 1..rows - 1 {
+        let y = origin_y + row as f32 * step;
+        for column in 1..columns - 1 {
+            raster[row * columns + column] = index.covered(origin_x + column as f32 * step, y);
+        }
+    }
+    let is_copper = |column: isize, row: isize| {
+        column >= 0
+            && row >= 0
+            && (column as usize) < columns
+            && (row as usize) < rows
+            && raster[row as usize * columns + column as usize]
+    };
+
+    let mut width_cells = HashMap::new();
+    let mut spacing_cells = HashMap::new();
+    for row in 1..rows as isize - 1 {
+        for column in 1..columns as isize - 1 {
+            if !is_copper(column, row) {
+                continue;
+            }
+            let on_edge = !is_copper(column - 1, row)
+                || !is_copper(column + 1, row)
+                || !is_copper(column, row - 1)
+                || !is_copper(column, row + 1);
+            if !on_edge {
+                continue;
+            }
+
+            // Outward normal points towards the bare samples around the edge
+            let (mut normal_x, mut normal_y) = (0.0f32, 0.0f32);
+            for dy in -2..=2 {
+                for dx in -2..=2 {
+                    if !is_copper(column + dx, row + dy) {
+                        normal_x += dx as f32;
+                        normal_y += dy as f32;
+                    }
+                }
+            }
+            let length = normal_x.hypot(normal_y);
+            if length == 0.0 {
+                continue;
+            }
+            let direction = (normal_x / length, normal_y / length);
+            let start = (
+                origin_x + column as f32 * step,
+                origin_y + row as f32 * step,
+            );
+
+            let ray = Ray {
+                index: &index,
+                start,
+                direction,
+                step,
+            };
+            // Exact copper edge near this sample
+            let edge = match ray.crossing(0.0, step, true) {
+                Some(edge) => edge,
+                None => continue,
+            };
+
+            // Width: across the copper, against the normal
+            if let Some(far) = ray.march(edge, -1.0, true) {
+                let value = edge - far;
+                let (x, y) = ray.point((edge + far) / 2.0);
+                report.width.record(value, x, y);
+                if value < min_width {
+                    keep_smallest(&mut width_cells, value, x, y);
+                }
+            }
+
+            // Spacing: across the gap, along the normal
+            if let Some(far) = ray.march(edge, 1.0, false) {
+                let value = far - edge;
+                let (x, y) = ray.point((edge + far) / 2.0);
+                report.spacing.record(value, x, y);
+                if value < min_spacing {
+                    keep_smallest(&mut spacing_cells, value, x, y);
+                }
+            }
+        }
+    }
+
+    for (cells, found) in [
+        (width_cells, &mut report.width),
+        (spacing_cells, &mut report.spacing),
+    ] {
+        let mut violations: Vec<_> = cells.into_values().collect();
+        violations.sort_by(|a, b| a.0.total_cmp(&b.0));
+        for (value, x, y) in violations {
+            found.x.push(x);
+            found.y.push(y);
+            found.value.push(value);
+        }
+    }
+
+    report
+}
+
+/// Keep the smallest violation among those within the dedup distance
+fn keep_smallest(cells: &mut HashMap<(i32, i32), (f32, f32, f32)>, value: f32, x: f32, y: f32) {
+    let key = (
+        (x / DEDUP_CELL).floor() as i32,
+        (y / DEDUP_CELL).floor() as i32,
+    );
+    // A nearby violation may sit in a neighboring cell
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let neighbor = (key.0 + dx, key.1 + dy);
+            if let Some(entry) = cells.get_mut(&neighbor) {
+                if (entry.1 - x).abs().max((entry.2 - y).abs()) < DEDUP_CELL {
+                    if value < entry.0 {
+                        *entry = (value, x, y);
+                    }
+                    return;
+                }
+            }
+        }
+    }
+    cells.insert(key, (value, x, y));
+}
+
+/// Measuring ray through an edge sample along its outward normal
+struct Ray<'a> {
+    index: &'a CoverageIndex<'a>,
+    start: (f32, f32),
+    direction: (f32, f32),
+    step: f32,
+}
+
+impl Ray<'_> {
+    fn point(&self, t: f32) -> (f32, f32) {
+        (
+            self.start.0 + self.direction.0 * t,
+            self.start.1 + self.direction.1 * t,
+        )
+    }
+
+    fn covered(&self, t: f32) -> bool {
+        let (x, y) = self.point(t);
+        self.index.covered(x, y)
+    }
+
+    /// Bisect the edge between `inside` (covered == `copper`) and `outside` (the opposite)
+    fn crossing(&self, mut inside: f32, mut outside: f32, copper: bool) -> Option<f32> {
+        if self.covered(inside) != copper || self.covered(outside) == copper {
+            return None;
+        }
+        for _ in 0..REFINE_STEPS {
+            let middle = (inside + outside) / 2.0;
+            if self.covered(middle) == copper {
+                inside = middle;
+            } else {
+                outside = middle;
+            }
+        }
+        Some((inside + outside) / 2.0)
+    }
+
+    /// March from `edge` in `sign` direction through copper (`copper`) or bare space
+    /// and return the position of the next edge, if within the measuring limit
+    fn march(&self, edge: f32, sign: f32, copper: bool) -> Option<f32> {
+        // Skip the half step around the starting edge
+        let mut inside = edge + sign * self.step * 0.5;
+        if self.covered(inside) != copper {
+            return None;
+        }
+        let mut distance = self.step;
+        while distance <= MAX_MEASURE {
+            let next = edge + sign * distance;
+            if self.covered(next) != copper {
+                return self.crossing(inside, next, copper);
+            }
+            inside = next;
+            distance += self.step;
+        }
+        None
+    }
+}
@@ -0,0 +1,483 @@
+use crate::layer_info::{LayerInfo, Unit, UnitSource};
+use crate::parser::{
+    LintFinding, LintRule, Polarity, PrimitiveKind, APERTURE_FUNCTION_COUNT, APERTURE_SHAPE_COUNT,
+};
+use crate::shape::{
+    Arcs, Boundary, Circles, Flashes, GerberData, Obrounds, PrimitiveValues, Thermals, Traces,
+    Triangles,
+};
+
+// File signature and format version of the geometry cache
+const MAGIC: &[u8; 4] = b"GBRC";
+const CACHE_VERSION: u32 = 10;
+
+/// Encode parsed layer geometry into the binary cache format
+///
+/// Layout (little endian): magic, version, content hash (length-prefixed UTF-8),
+/// layer info (image polarity, unit, unit source, %FS digits, whether %FS was
+/// present, .FileFunction value, lint findings), sublayer count, then per sublayer its boundary and
+/// length-prefixed arrays.
+pub fn encode_layer(sublayers: &[GerberData], hash: &str, info: &LayerInfo) -> Vec<u8> {
+    let mut writer = Writer::default();
+    writer.bytes.extend_from_slice(MAGIC);
+    writer.u32(CACHE_VERSION);
+    writer.u32(hash.len() as u32);
+    writer.bytes.extend_from_slice(hash.as_bytes());
+    writer.u32((info.image_polarity == Polarity::Negative) as u32);
+    writer.u32((info.unit == Unit::Inch) as u32);
+    writer.u32(match info.unit_source {
+        UnitSource::Default => 0,
+        UnitSource::File => 1,
+        UnitSource::Forced => 2,
+    });
+    for digits in info.format {
+        writer.u32(digits);
+    }
+    writer.u32(info.format_declared as u32);
+    writer.u8s(info.file_function.as_bytes());
+    let lint = &info.lint;
+    writer.u8s(&lint.iter().map(|f| f.rule as u8).collect::<Vec<_>>());
+    writer.u32s(&lint.iter().map(|f| f.line).collect::<Vec<_>>());
+    writer.u32s(&lint.iter().map(|f| f.occurrences).collect::<Vec<_>>());
+    writer.u32(sublayers.len() as u32);
+
+    for data in sublayers {
+        let b = &data.boundary;
+        for value in [b.min_x, b.max_x, b.min_y, b.max_y] {
+            writer.f32(value);
+        }
+
+        let t = &data.triangles;
+        writer.f32s(&t.vertices);
+        writer.u32s(&t.indices);
+        writer.f32s(&t.hole_x);
+        writer.f32s(&t.hole_y);
+        writer.f32s(&t.hole_radius);
+
+        let c = &data.circles;
+        for array in [&c.x, &c.y, &c.radius, &c.hole_x, &c.hole_y, &c.hole_radius] {
+            writer.f32s(array);
+        }
+
+        let a = &data.arcs;
+        for array in [
+            &a.x,
+            &a.y,
+            &a.radius,
+            &a.start_angle,
+            &a.sweep_angle,
+            &a.thickness,
+        ] {
+            writer.f32s(array);
+        }
+
+        let th = &data.thermals;
+        for array in [
+            &th.x,
+            &th.y,
+            &th.outer_diameter,
+            &th.inner_diameter,
+            &th.gap_thickness,
+            &th.rotation,
+        ] {
+            writer.f32s(array);
+        }
+
+        let o = &data.obrounds;
+        for array in [
+            &o.x,
+            &o.y,
+            &o.width,
+            &o.height,
+            &o.rotation,
+            &o.hole_x,
+            &o.hole_y,
+            &o.hole_radius,
+        ] {
+            writer.f32s(array);
+        }
+
+        let f = &data.functions;
+        for array in [&f.triangles, &f.circles, &f.arcs, &f.thermals, &f.obrounds] {
+            writer.u8s(array);
+        }
+        let d = &data.apertures;
+        for array in [&d.triangles, &d.circles, &d.arcs, &d.thermals, &d.obrounds] {
+            writer.u16s(array);
+        }
+        let k = &data.kinds;
+        for array in [&k.triangles, &k.circles, &k.arcs, &k.thermals, &k.obrounds] {
+            writer.u8s(array);
+        }
+
+        let fl = &data.flashes;
+        writer.f32s(&fl.x);
+        writer.f32s(&fl.y);
+        writer.u16s(&fl.d_code);
+        writer.u8s(&fl.shape);
+        for array in [&fl.width, &fl.height, &fl.rotation] {
+            writer.f32s(array);
+        }
+
+        let tr = &data.traces;
+        writer.u32s(&tr.start);
+        writer.u16s(&tr.d_code);
+        for array in [&tr.width, &tr.points, &tr.sweep, &tr.center] {
+            writer.f32s(array);
+        }
+    }
+
+    writer.bytes
+}
+
+/// Decode a binary cache back into layer geometry, content hash and layer info
+pub fn decode_layer(bytes: &[u8]) -> Result<(Vec<GerberData>, String, LayerInfo), String> {
+    let mut reader = Reader { bytes, position: 0 };
+    if reader.take(4)? != MAGIC {
+        return Err("Not a geometry cache (bad signature)".to_string());
+    }
+    let version = reader.u32()?;
+    if version != CACHE_VERSION {
+        return Err(format!(
+            "Unsupported geometry cache version: {} (expected {})",
+            version, CACHE_VERSION
+        ));
+    }
+    let hash_len = reader.u32()? as usize;
+    let hash = String::from_utf8(reader.take(hash_len)?.to_vec())
+        .map_err(|_| "Invalid content hash in geometry cache".to_string())?;
+    let image_polarity = match reader.u32()? {
+        0 => Polarity::Positive,
+        1 => Polarity::Negative,
+        _ => return Err("Invalid image polarity in geometry cache".to_string()),
+    };
+    let unit = match reader.u32()? {
+        0 => Unit::Millimeter,
+        1 => Unit::Inch,
+        _ => return Err("Invalid unit in geometry cache".to_string()),
+    };
+    let unit_source = match reader.u32()? {
+        0 => UnitSource::Default,
+        1 => UnitSource::File,
+        2 => UnitSource::Forced,
+        _ => return Err("Invalid unit source in geometry cache".to_string()),
+    };
+    let format = [reader.u32()?, reader.u32()?, reader.u32()?, reader.u32()?];
+    let format_declared = reader.u32()? != 0;
+    let file_function = String::from_utf8(reader.u8s()?)
+        .map_err(|_| "Invalid file function in geometry cache".to_string())?;
+    let (rules, lines, occurrences) = (reader.u8s()?, reader.u32s()?, reader.u32s()?);
+    if lines.len() != rules.len() || occurrences.len() != rules.len() {
+        return Err("Corrupt lint data in geometry cache".to_string());
+    }
+    let lint = rules
+        .iter()
+        .zip(lines.iter().zip(&occurrences))
+        .map(|(&rule, (&line, &occurrences))| {
+            Some(LintFinding {
+                rule: LintRule::from_u8(rule)?,
+                line,
+                occurrences,
+            })
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| "Invalid lint rule in geometry cache".to_string())?;
+    let info = LayerInfo {
+        unit,
+        unit_source,
+        format,
+        format_declared,
+        file_function,
+        image_polarity,
+        lint,
+    };
+
+    let count = reader.u32()? as usize;
+    let mut sublayers = Vec::new();
+    for _ in 0..count {
+        let boundary = Boundary::new(reader.f32()?, reader.f32()?, reader.f32()?, reader.f32()?);
+
+        let triangles = Triangles::new(
+            reader.f32s()?,
+            reader.u32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+        );
+        let vertex_count = triangles.vertices.len() / 2;
+        if !triangles.vertices.len().is_multiple_of(2)
+            || !triangles.indices.len().is_multiple_of(3)
+            || triangles
+                .indices
+                .iter()
+                .any(|&i| i as usize >= vertex_count)
+            || triangles.hole_x.len() != vertex_count
+            || triangles.hole_y.len() != vertex_count
+            || triangles.hole_radius.len() != vertex_count
+        {
+            return Err("Corrupt triangle data in geometry cache".to_string());
+        }
+
+        let circles = Circles::new(
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+        );
+        let arcs = Arcs::new(
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+        );
+        let thermals = Thermals::new(
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+        );
+        let obrounds = Obrounds::new(
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+            reader.f32s()?,
+        );
+
+        // Instance arrays of one primitive type must all have the same length
+        let consistent = [
+            &circles.y,
+            &circles.radius,
+            &circles.hole_x,
+            &circles.hole_y,
+            &circles.hole_radius,
+        ]
+        .iter()
+        .all(|a| a.len() == circles.x.len())
+            && [
+                &arcs.y,
+                &arcs.radius,
+                &arcs.start_angle,
+                &arcs.sweep_angle,
+                &arcs.thickness,
+            ]
+            .iter()
+            .all(|a| a.len() == arcs.x.len())
+            && [
+                &thermals.y,
+                &thermals.outer_diameter,
+                &thermals.inner_diameter,
+                &thermals.gap_thickness,
+                &thermals.rotation,
+            ]
+            .iter()
+            .all(|a| a.len() == thermals.x.len())
+            && [
+                &obrounds.y,
+                &obrounds.width,
+                &obrounds.height,
+                &obrounds.rotation,
+                &obrounds.hole_x,
+                &obrounds.hole_y,
+                &obrounds.hole_radius,
+            ]
+            .iter()
+            .all(|a| a.len() == obrounds.x.len());
+        if !consistent {
+            return Err("Corrupt instance data in geometry cache".to_string());
+        }
+
+        // Aperture functions, D-codes and kinds are either absent or parallel to the primitives
+        let functions = PrimitiveValues {
+            triangles: reader.u8s()?,
+            circles: reader.u8s()?,
+            arcs: reader.u8s()?,
+            thermals: reader.u8s()?,
+            obrounds: reader.u8s()?,
+        };
+        let apertures = PrimitiveValues {
+            triangles: reader.u16s()?,
+            circles: reader.u16s()?,
+            arcs: reader.u16s()?,
+            thermals: reader.u16s()?,
+            obrounds: reader.u16s()?,
+        };
+        let kinds = PrimitiveValues {
+            triangles: reader.u8s()?,
+            circles: reader.u8s()?,
+            arcs: reader.u8s()?,
+            thermals: reader.u8s()?,
+            obrounds: reader.u8s()?,
+        };
+        let data = GerberData::new(triangles, circles, arcs, thermals, obrounds, boundary);
+        let f = &functions;
+        let valid_functions = [&f.triangles, &f.circles, &f.arcs, &f.thermals, &f.obrounds]
+            .iter()
+            .all(|a| a.iter().all(|&v| (v as usize) < APERTURE_FUNCTION_COUNT));
+        let k = &kinds;
+        let valid_kinds = [&k.triangles, &k.circles, &k.arcs, &k.thermals, &k.obrounds]
+            .iter()
+            .all(|a| a.iter().all(|&v| v <= PrimitiveKind::Flash as u8));
+        if !functions.fits(&data)
+            || !apertures.fits(&data)
+            || !kinds.fits(&data)
+            || !valid_functions
+            || !valid_kinds
+        {
+            return Err("Corrupt aperture data in geometry cache".to_string());
+        }
+
+        let flashes = Flashes {
+            x: reader.f32s()?,
+            y: reader.f32s()?,
+            d_code: reader.u16s()?,
+            shape: reader.u8s()?,
+            width: reader.f32s()?,
+            height: reader.f32s()?,
+            rotation: reader.f32s()?,
+        };
+        if !flashes.is_consistent()
+            || flashes
+                .shape
+                .iter()
+                .any(|&s| s as usize >= APERTURE_SHAPE_COUNT)
+        {
+            return Err("Corrupt flash data in geometry cache".to_string());
+        }
+
+        let traces = Traces {
+            start: reader.u32s()?,
+            d_code: reader.u16s()?,
+            width: reader.f32s()?,
+            points: reader.f32s()?,
+            sweep: reader.f32s()?,
+            center: reader.f32s()?,
+        };
+        if !traces.is_consistent() {
+            return Err("Corrupt trace data in geometry cache".to_string());
+        }
+
+        sublayers.push(
+            data.with_apertures(functions, apertures)
+                .with_kinds(kinds)
+                .with_flashes(flashes, traces),
+        );
+    }
+
+    if reader.position != bytes.len() {
+        return Err("Trailing data in geometry cache".to_string());
+    }
+    Ok((sublayers, hash, info))
+}
+
+#[derive(Default)]
+struct Writer {
+    bytes: Vec<u8>,
+}
+
+impl Writer {
+    fn u32(&mut self, value: u32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn f32s(&mut self, values: &[f32]) {
+        self.u32(values.len() as u32);
+        self.bytes.reserve(values.len() * 4);
+        for &value in values {
+            self.f32(value);
+        }
+    }
+
+    fn u32s(&mut self, values: &[u32]) {
+        self.u32(values.len() as u32);
+        self.bytes.reserve(values.len() * 4);
+        for &value in values {
+            self.u32(value);
+        }
+    }
+
+    fn u8s(&mut self, values: &[u8]) {
+        self.u32(values.len() as u32);
+        self.bytes.extend_from_slice(values);
+    }
+
+    fn u16s(&mut self, values: &[u16]) {
+        self.u32(values.len() as u32);
+        self.bytes.reserve(values.len() * 2);
+        for &value in values {
+            self.bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| "Truncated geometry cache".to_string())?;
+        let slice = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(slice)
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn f32(&mut self) -> Result<f32, String> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    fn f32s(&mut self) -> Result<Vec<f32>, String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len.checked_mul(4).ok_or("Truncated geometry cache")?)?;
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect())
+    }
+
+    fn u32s(&mut self) -> Result<Vec<u32>, String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len.checked_mul(4).ok_or("Truncated geometry cache")?)?;
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect())
+    }
+
+    fn u8s(&mut self) -> Result<Vec<u8>, String> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn u16s(&mut self) -> Result<Vec<u16>, String> {
+        let len = self.u32()? as usize;
+        let bytes = self.take(len.checked_mul(2).ok_or("Truncated geometry cache")?)?;
+        Ok(bytes
+            .chunks_exact(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .collect())
+    }
+}
@@ -0,0 +1,140 @@
+use std::fmt;
+use wasm_bindgen::prelude::*;
+
+/// Category of a ViewerError, exposed to JS as a string code
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ErrorCode {
+    NotInitialized,  // init() has not been called
+    InvalidLayer,    // Unknown, removed or still loading layer ID
+    InvalidArgument, // Argument out of range or malformed
+    InvalidState,    // Call not valid in the current viewer state
+    ParseFailed,     // Input file or data could not be parsed
+    ResourceLimit,   // Input exceeds a security limit
+    ShaderCompile,   // Shader compile or link failure
+    WebGl,           // WebGL object creation failed or context lost
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::NotInitialized => "not_initialized",
+            ErrorCode::InvalidLayer => "invalid_layer",
+            ErrorCode::InvalidArgument => "invalid_argument",
+            ErrorCode::InvalidState => "invalid_state",
+            ErrorCode::ParseFailed => "parse_failed",
+            ErrorCode::ResourceLimit => "resource_limit",
+            ErrorCode::ShaderCompile => "shader_compile",
+            ErrorCode::WebGl => "webgl",
+        }
+    }
+}
+
+/// Error thrown by the viewer
+///
+/// `code` identifies the kind of failure so callers can handle it without
+/// matching on message text. `line` and `layer_id` are set when known.
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ViewerError {
+    pub(crate) code: ErrorCode,
+    pub(crate) message: String,
+    pub(crate) line: Option<u32>,
+    pub(crate) layer_id: Option<u32>,
+}
+
+impl ViewerError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        ViewerError {
+            code,
+            message: message.into(),
+            line: None,
+            layer_id: None,
+        }
+    }
+
+    /// Renderer calls made before init()
+    pub fn not_initialized() -> Self {
+        Self::new(
+            ErrorCode::NotInitialized,
+            "Renderer not initialized. Call init() first.",
+        )
+    }
+
+    pub fn invalid_layer(message: impl Into<String>, layer_id: u32) -> Self {
+        Self::new(ErrorCode::InvalidLayer, message).with_layer(layer_id)
+    }
+
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidArgument, message)
+    }
+
+    pub fn invalid_state(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidState, message)
+    }
+
+    pub fn parse_failed(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::ParseFailed, message)
+    }
+
+    pub fn webgl(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::WebGl, message)
+    }
+
+    /// Set the 1-based source line the error refers to
+    pub fn with_line(mut self, line: usize) -> Self {
+        self.line = Some(line as u32);
+        self
+    }
+
+    /// Set the layer the error refers to, unless already set
+    pub fn with_layer(mut self, layer_id: u32) -> Self {
+        self.layer_id.get_or_insert(layer_id);
+        self
+    }
+}
+
+#[wasm_bindgen]
+impl ViewerError {
+    /// Error kind: "not_initialized", "invalid_layer", "invalid_argument",
+    /// "invalid_state", "parse_failed", "resource_limit", "shader_compile"
+    /// or "webgl"
+    #[wasm_bindgen(getter)]
+    pub fn code(&self) -> String {
+        self.code.as_str().to_string()
+    }
+
+    /// Human readable description
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+
+    /// 1-based line in the source file, if the error refers to one
+    #[wasm_bindgen(getter)]
+    pub fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    /// Layer ID, if the error refers to a layer
+    #[wasm_bindgen(getter)]
+    pub fn layer_id(&self) -> Option<u32> {
+        self.layer_id
+    }
+
+    /// Message with the line number, for string interpolation in JS
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_js_string(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for ViewerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{} (line {})", self.message, line),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for ViewerError {}
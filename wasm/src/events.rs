@@ -0,0 +1,162 @@
+use crate::shape::{Boundary, GerberData};
+use serde_json::json;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+
+// Stored geometry above which a memory_warning event is emitted (bytes)
+pub const MEMORY_WARNING_BYTES: usize = 1 << 30;
+
+#[wasm_bindgen]
+extern "C" {
+    /// JS function called with each viewer event object
+    #[wasm_bindgen(typescript_type = "(event: any) => void")]
+    pub type EventCallback;
+
+    #[wasm_bindgen(method, catch, js_name = call)]
+    fn call(this: &EventCallback, this_arg: &JsValue, event: &JsValue) -> Result<JsValue, JsValue>;
+
+    #[wasm_bindgen(js_namespace = JSON, js_name = parse)]
+    pub(crate) fn json_parse(text: &str) -> JsValue;
+
+    #[wasm_bindgen(js_namespace = performance, js_name = now)]
+    pub(crate) fn performance_now() -> f64;
+}
+
+/// Sends structured events to the host's callback
+///
+/// Every event is a plain object with a `type` field. Nothing is measured
+/// or sent while no callback is set.
+#[derive(Default)]
+pub struct EventEmitter {
+    callback: Option<EventCallback>,
+    parse_started: HashMap<u32, f64>, // Start time of progressive parses by layer ID
+    memory_warned: bool,              // memory_warning sent, until usage drops again
+    #[cfg(feature = "renderer")]
+    context_lost: bool, // context_lost sent, until the next init()
+}
+
+impl EventEmitter {
+    pub fn set_callback(&mut self, callback: Option<EventCallback>) {
+        self.callback = callback;
+    }
+
+    /// Current time in ms (0 without a callback)
+    pub fn now(&self) -> f64 {
+        if self.callback.is_some() {
+            performance_now()
+        } else {
+            0.0
+        }
+    }
+
+    fn emit(&self, event: serde_json::Value) {
+        if let Some(callback) = &self.callback {
+            // Exceptions thrown by the host's handler must not abort the viewer
+            let _ = callback.call(&JsValue::NULL, &json_parse(&event.to_string()));
+        }
+    }
+
+    /// Remember when a progressive parse started
+    pub fn parse_started(&mut self, layer_id: u32) {
+        let now = self.now();
+        self.parse_started.insert(layer_id, now);
+    }
+
+    /// Progress of a progressive parse in bytes of the source file
+    pub fn parse_progress(&self, layer_id: u32, parsed_bytes: usize, total_bytes: usize) {
+        if self.callback.is_none() {
+            return;
+        }
+        self.emit(json!({
+            "type": "parse_progress",
+            "layer_id": layer_id,
+            "parsed_bytes": parsed_bytes,
+            "total_bytes": total_bytes,
+        }));
+    }
+
+    /// A layer finished parsing, with counts describing the result
+    ///
+    /// `warnings` lists problems that did not fail the parse. `started` is the
+    /// now() value when parsing began; progressive parses pass None and use
+    /// the time recorded by parse_started().
+    pub fn parse_complete(
+        &mut self,
+        layer_id: u32,
+        sublayers: &[GerberData],
+        warnings: &[String],
+        started: Option<f64>,
+    ) {
+        let started = started.or_else(|| self.parse_started.remove(&layer_id));
+        if self.callback.is_none() {
+            return;
+        }
+        let count = |f: fn(&GerberData) -> usize| sublayers.iter().map(f).sum::<usize>();
+        let bounds = Boundary::combined(sublayers);
+        self.emit(json!({
+            "type": "parse_complete",
+            "layer_id": layer_id,
+            "duration_ms": started.map(|t| self.now() - t),
+            "diagnostics": {
+                "sublayers": sublayers.len(),
+                "triangles": count(|d| d.triangles.indices.len() / 3),
+                "circles": count(|d| d.circles.x.len()),
+                "arcs": count(|d| d.arcs.x.len()),
+                "thermals": count(|d| d.thermals.x.len()),
+                "obrounds": count(|d| d.obrounds.x.len()),
+                "geometry_bytes": count(GerberData::byte_size),
+                "bounds": [bounds.min_x, bounds.max_x, bounds.min_y, bounds.max_y],
+                "warnings": warnings,
+            },
+        }));
+    }
+
+    /// Forget a progressive parse that ended without a result
+    pub fn parse_cancelled(&mut self, layer_id: u32) {
+        self.parse_started.remove(&layer_id);
+    }
+
+    #[cfg(feature = "renderer")]
+    /// A frame was drawn; `started` is the now() value before drawing
+    ///
+    /// The duration is CPU time spent issuing GPU work, not GPU time.
+    pub fn render_complete(&self, layer_count: usize, started: f64) {
+        if self.callback.is_none() {
+            return;
+        }
+        self.emit(json!({
+            "type": "render_complete",
+            "layers": layer_count,
+            "duration_ms": self.now() - started,
+        }));
+    }
+
+    #[cfg(feature = "renderer")]
+    /// The WebGL context was lost (sent once until the next init())
+    pub fn context_lost(&mut self) {
+        if !self.context_lost {
+            self.context_lost = true;
+            self.emit(json!({ "type": "context_lost" }));
+        }
+    }
+
+    #[cfg(feature = "renderer")]
+    /// Rendering works again after init() with a new context
+    pub fn context_restored(&mut self) {
+        self.context_lost = false;
+    }
+
+    /// Warn once when stored geometry grows beyond MEMORY_WARNING_BYTES
+    pub fn check_memory(&mut self, geometry_bytes: usize) {
+        if geometry_bytes <= MEMORY_WARNING_BYTES {
+            self.memory_warned = false;
+        } else if !self.memory_warned {
+            self.memory_warned = true;
+            self.emit(json!({
+                "type": "memory_warning",
+                "geometry_bytes": geometry_bytes,
+                "threshold_bytes": MEMORY_WARNING_BYTES,
+            }));
+        }
+    }
+}
@@ -0,0 +1,18 @@
+mod boolean;
+mod excellon;
+mod geojson;
+mod gerber;
+mod gltf;
+mod offset;
+mod panel;
+mod pdf;
+
+// Export only what's needed externally
+pub use boolean::{combine_layers, LayerOperation};
+pub use excellon::write_excellon;
+pub use geojson::write_geojson;
+pub use gerber::write_gerber;
+pub use gltf::{write_gltf, GltfOptions};
+pub use offset::offset_layer;
+pub use panel::{panelize, PanelLayout};
+pub use pdf::{write_pdf, PdfLayer};
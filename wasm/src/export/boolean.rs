@@ -0,0 +1,44 @@
+use super::gerber::GerberWriter;
+use crate::analysis::merged_shapes;
+use crate::shape::GerberData;
+use i_overlay::core::fill_rule::FillRule;
+use i_overlay::core::overlay_rule::OverlayRule;
+use i_overlay::float::single::SingleFloatOverlay;
+
+/// Boolean operation between the images of two layers
+#[derive(Clone, Copy, Debug)]
+pub enum LayerOperation {
+    Union,
+    Subtract, // First layer minus the second
+    Intersect,
+}
+
+/// Combine the images of two layers into a Gerber file of regions
+///
+/// Both layers are merged into their final outlines first, so clear polarity
+/// and pad holes are respected. Curves are flattened to within `chord_error`
+/// (mm).
+pub fn combine_layers(
+    a: &[GerberData],
+    b: &[GerberData],
+    operation: LayerOperation,
+    chord_error: f32,
+) -> Result<String, String> {
+    let rule = match operation {
+        LayerOperation::Union => OverlayRule::Union,
+        LayerOperation::Subtract => OverlayRule::Difference,
+        LayerOperation::Intersect => OverlayRule::Intersect,
+    };
+    let shapes = merged_shapes(a, chord_error).overlay(
+        &merged_shapes(b, chord_error),
+        rule,
+        FillRule::NonZero,
+    );
+    if shapes.is_empty() {
+        return Err(format!("{:?} of the layers is empty", operation));
+    }
+
+    let mut writer = GerberWriter::default();
+    writer.shapes(&shapes);
+    Ok(writer.finish())
+}
@@ -0,0 +1,42 @@
+use std::collections::BTreeMap;
+
+// Tool diameters are grouped in 1 um steps
+const STEPS_PER_MM: f64 = 1000.0;
+
+/// Write drill hits as an Excellon file
+///
+/// Coordinates and tool sizes are in mm with explicit decimal points, so no
+/// zero suppression applies. Tools are numbered T1, T2, ... by ascending
+/// diameter; `comments` go into the header as ";" lines.
+pub fn write_excellon(comments: &[&str], x: &[f32], y: &[f32], diameter: &[f32]) -> String {
+    // Diameter step -> hit indices
+    let mut tools: BTreeMap<i64, Vec<usize>> = BTreeMap::new();
+    for (i, &d) in diameter.iter().enumerate() {
+        tools
+            .entry((d as f64 * STEPS_PER_MM).round() as i64)
+            .or_default()
+            .push(i);
+    }
+
+    let mut lines = vec!["M48".to_string()];
+    lines.extend(comments.iter().map(|comment| format!("; {}", comment)));
+    lines.push("FMAT,2".to_string());
+    lines.push("METRIC".to_string());
+    for (tool, step) in tools.keys().enumerate() {
+        lines.push(format!("T{}C{:.3}", tool + 1, *step as f64 / STEPS_PER_MM));
+    }
+    lines.push("%".to_string());
+    lines.push("G90".to_string());
+    lines.push("G05".to_string());
+    for (tool, hits) in tools.values().enumerate() {
+        lines.push(format!("T{}", tool + 1));
+        for &i in hits {
+            lines.push(format!("X{:.3}Y{:.3}", x[i], y[i]));
+        }
+    }
+    lines.push("M30".to_string());
+
+    let mut file = lines.join("\n");
+    file.push('\n');
+    file
+}
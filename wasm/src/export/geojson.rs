@@ -0,0 +1,61 @@
+use crate::shape::GerberData;
+use serde_json::{json, Value};
+
+/// Write the primitives of a layer as a GeoJSON FeatureCollection
+///
+/// Every primitive becomes one feature: a Polygon (with an inner ring for a
+/// hole) or, for thermals, a MultiPolygon of the four segments. Coordinates
+/// are board millimeters rather than WGS84 longitude and latitude; curves are
+/// flattened to within `chord_error` (mm). Each feature's properties name the
+/// source layer, sublayer, polarity, primitive kind and index within that
+/// kind, so external scripts can trace and diff them.
+pub fn write_geojson(layer_id: u32, sublayers: &[GerberData], chord_error: f32) -> String {
+    let mut features: Vec<Value> = Vec::new();
+    for (sublayer, data) in sublayers.iter().enumerate() {
+        let polarity = if sublayer % 2 == 0 { "dark" } else { "clear" };
+        data.for_each_outline(chord_error, |kind, index, shapes| {
+            let polygons: Vec<Vec<Vec<[f64; 2]>>> = shapes
+                .into_iter()
+                .map(|shape| shape.into_iter().map(ring).collect())
+                .collect();
+            let geometry = if polygons.len() == 1 {
+                json!({ "type": "Polygon", "coordinates": polygons[0] })
+            } else {
+                json!({ "type": "MultiPolygon", "coordinates": polygons })
+            };
+            features.push(json!({
+                "type": "Feature",
+                "geometry": geometry,
+                "properties": {
+                    "layer_id": layer_id,
+                    "sublayer": sublayer,
+                    "polarity": polarity,
+                    "primitive": kind,
+                    "index": index,
+                },
+            }));
+        });
+    }
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+    .to_string()
+}
+
+/// Closed linear ring
+///
+/// Outlines already run counterclockwise and holes clockwise, as RFC 7946
+/// recommends. Coordinates are rounded to 1 nm so f32 noise does not spell
+/// out as seventeen digits.
+fn ring(mut points: Vec<[f32; 2]>) -> Vec<[f64; 2]> {
+    if points.first() != points.last() {
+        points.push(points[0]);
+    }
+    let round = |value: f32| (value as f64 * 1e6).round() / 1e6 + 0.0;
+    points
+        .into_iter()
+        .map(|[x, y]| [round(x), round(y)])
+        .collect()
+}
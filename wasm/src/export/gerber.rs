@@ -0,0 +1,341 @@
+use crate::shape::GerberData;
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+// Coordinate format written to every file: %FSLAX46Y46*% in millimeters
+const COORDINATE_SCALE: f32 = 1_000_000.0;
+// First D code available for user apertures
+const FIRST_APERTURE_CODE: u32 = 10;
+// Thermal macro written when thermals are present: outer, inner, gap, rotation (degrees)
+const THERMAL_MACRO: &str = "%AMTHERMAL*7,0,0,$1,$2,$3,$4*%";
+
+/// RS-274X writer for parsed layer geometry
+///
+/// Writes one command per line so the output can be read back by the parser.
+/// Triangles become one region each; strokes and flashes keep their aperture form.
+pub struct GerberWriter {
+    apertures: HashMap<String, u32>, // Aperture definition -> D code
+    aperture_lines: Vec<String>,
+    body: Vec<String>,
+    current_aperture: Option<u32>,
+    dark: bool,
+    uses_thermal_macro: bool,
+}
+
+impl Default for GerberWriter {
+    fn default() -> Self {
+        GerberWriter {
+            apertures: HashMap::new(),
+            aperture_lines: Vec::new(),
+            body: Vec::new(),
+            current_aperture: None,
+            dark: true,
+            uses_thermal_macro: false,
+        }
+    }
+}
+
+impl GerberWriter {
+    /// Write all polarity sublayers of a layer (even = dark, odd = clear)
+    ///
+    /// Holes that an aperture cannot express (off-center or inside triangles)
+    /// are written as clear circles right after their sublayer.
+    pub fn write_sublayers(&mut self, sublayers: &[GerberData]) {
+        for (index, data) in sublayers.iter().enumerate() {
+            let dark = index % 2 == 0;
+            self.set_polarity(dark);
+            let holes = self.write_geometry(data);
+
+            // Holes in clear features would need dark fills and are dropped
+            if dark && !holes.is_empty() {
+                self.set_polarity(false);
+                for (x, y, radius) in holes {
+                    self.flash_circle(x, y, radius * 2.0);
+                }
+            }
+        }
+    }
+
+    /// Switch between dark (%LPD*%) and clear (%LPC*%) polarity
+    pub fn set_polarity(&mut self, dark: bool) {
+        if self.dark != dark {
+            self.dark = dark;
+            self.body
+                .push(if dark { "%LPD*%" } else { "%LPC*%" }.to_string());
+        }
+    }
+
+    /// Open a step and repeat block; geometry written until `end_step_repeat` is repeated
+    pub fn begin_step_repeat(&mut self, nx: u32, ny: u32, step_x: f32, step_y: f32) {
+        self.body
+            .push(format!("%SRX{}Y{}I{:.6}J{:.6}*%", nx, ny, step_x, step_y));
+    }
+
+    /// Close the current step and repeat block
+    pub fn end_step_repeat(&mut self) {
+        self.body.push("%SR*%".to_string());
+    }
+
+    /// Write a filled polygon as a G36/G37 region
+    pub fn region(&mut self, points: &[[f32; 2]]) {
+        if points.len() < 3 {
+            return;
+        }
+        self.body.push("G36*".to_string());
+        self.body
+            .push(format!("{}D02*", coordinate(points[0][0], points[0][1])));
+        self.body.push("G01*".to_string());
+        for point in &points[1..] {
+            self.body
+                .push(format!("{}D01*", coordinate(point[0], point[1])));
+        }
+        self.body
+            .push(format!("{}D01*", coordinate(points[0][0], points[0][1])));
+        self.body.push("G37*".to_string());
+    }
+
+    /// Write polygons with holes, e.g. boolean results, as regions
+    ///
+    /// Each shape (outer contour, then holes) becomes a dark region with its
+    /// holes cleared right after it. Larger shapes go first, so an island
+    /// inside a hole is drawn after the hole is cleared.
+    pub fn shapes(&mut self, shapes: &[Vec<Vec<[f32; 2]>>]) {
+        let mut order: Vec<&Vec<Vec<[f32; 2]>>> =
+            shapes.iter().filter(|shape| !shape.is_empty()).collect();
+        order.sort_by(|a, b| contour_area(&b[0]).total_cmp(&contour_area(&a[0])));
+        for shape in order {
+            self.set_polarity(true);
+            self.region(&shape[0]);
+            if shape.len() > 1 {
+                self.set_polarity(false);
+                for hole in &shape[1..] {
+                    self.region(hole);
+                }
+            }
+        }
+    }
+
+    /// Flash a round aperture
+    pub fn flash_circle(&mut self, x: f32, y: f32, diameter: f32) {
+        self.select_aperture(format!("C,{:.6}", diameter));
+        self.body.push(format!("{}D03*", coordinate(x, y)));
+    }
+
+    /// Finish the file: header, aperture definitions, body and M02
+    pub fn finish(self) -> String {
+        let mut lines = vec![
+            "G04 Written by wasm_gerber_viewer*".to_string(),
+            "%FSLAX46Y46*%".to_string(),
+            "%MOMM*%".to_string(),
+            "G75*".to_string(),
+        ];
+        if self.uses_thermal_macro {
+            lines.push(THERMAL_MACRO.to_string());
+        }
+        lines.extend(self.aperture_lines);
+        lines.push("%LPD*%".to_string());
+        lines.extend(self.body);
+        lines.push("M02*".to_string());
+        lines.join("\n") + "\n"
+    }
+
+    /// Write the geometry of one sublayer, returning the holes left to clear
+    fn write_geometry(&mut self, data: &GerberData) -> Vec<(f32, f32, f32)> {
+        let mut holes = Vec::new();
+
+        // Triangles, one region each
+        let triangles = &data.triangles;
+        for corners in triangles.indices.chunks_exact(3) {
+            let points: Vec<[f32; 2]> = corners
+                .iter()
+                .map(|&i| {
+                    let i = i as usize * 2;
+                    [triangles.vertices[i], triangles.vertices[i + 1]]
+                })
+                .collect();
+            self.region(&points);
+
+            let v = corners[0] as usize;
+            if triangles.hole_radius.get(v).is_some_and(|&r| r > 0.0) {
+                push_hole(
+                    &mut holes,
+                    triangles.hole_x[v],
+                    triangles.hole_y[v],
+                    triangles.hole_radius[v],
+                );
+            }
+        }
+
+        // Circles, flashed with a centered hole where possible
+        let circles = &data.circles;
+        for i in 0..circles.x.len() {
+            let (x, y) = (circles.x[i], circles.y[i]);
+            let hole_radius = circles.hole_radius[i];
+            if hole_radius > 0.0 && is_centered(circles.hole_x[i], circles.hole_y[i], x, y) {
+                self.select_aperture(format!(
+                    "C,{:.6}X{:.6}",
+                    circles.radius[i] * 2.0,
+                    hole_radius * 2.0
+                ));
+                self.body.push(format!("{}D03*", coordinate(x, y)));
+            } else {
+                self.flash_circle(x, y, circles.radius[i] * 2.0);
+                if hole_radius > 0.0 {
+                    push_hole(
+                        &mut holes,
+                        circles.hole_x[i],
+                        circles.hole_y[i],
+                        hole_radius,
+                    );
+                }
+            }
+        }
+
+        // Arcs, stroked with a round aperture of their thickness
+        let arcs = &data.arcs;
+        for i in 0..arcs.x.len() {
+            self.select_aperture(format!("C,{:.6}", arcs.thickness[i]));
+            // Split so no single draw reaches a full circle
+            let sweep = arcs.sweep_angle[i];
+            let parts = if sweep.abs() > PI { 2 } else { 1 };
+            for part in 0..parts {
+                let start = arcs.start_angle[i] + sweep * part as f32 / parts as f32;
+                let end = start + sweep / parts as f32;
+                let (cx, cy, r) = (arcs.x[i], arcs.y[i], arcs.radius[i]);
+                let (start_x, start_y) = (cx + r * start.cos(), cy + r * start.sin());
+                let (end_x, end_y) = (cx + r * end.cos(), cy + r * end.sin());
+                self.body
+                    .push(format!("{}D02*", coordinate(start_x, start_y)));
+                self.body.push(format!(
+                    "{}{}I{}J{}D01*",
+                    if sweep < 0.0 { "G02" } else { "G03" },
+                    coordinate(end_x, end_y),
+                    to_units(cx - start_x),
+                    to_units(cy - start_y)
+                ));
+            }
+            self.body.push("G01*".to_string());
+        }
+
+        // Thermals, flashed through the thermal macro
+        let thermals = &data.thermals;
+        for i in 0..thermals.x.len() {
+            self.uses_thermal_macro = true;
+            self.select_aperture(format!(
+                "THERMAL,{:.6}X{:.6}X{:.6}X{:.4}",
+                thermals.outer_diameter[i],
+                thermals.inner_diameter[i],
+                thermals.gap_thickness[i],
+                thermals.rotation[i].to_degrees()
+            ));
+            self.body
+                .push(format!("{}D03*", coordinate(thermals.x[i], thermals.y[i])));
+        }
+
+        // Obrounds, flashed when axis-aligned, otherwise stroked along their long axis
+        let obrounds = &data.obrounds;
+        for i in 0..obrounds.x.len() {
+            let (x, y) = (obrounds.x[i], obrounds.y[i]);
+            let (width, height) = (obrounds.width[i], obrounds.height[i]);
+            let rotation = obrounds.rotation[i];
+            let quarter_turns = rotation / (PI / 2.0);
+            let hole_radius = obrounds.hole_radius[i];
+
+            if (quarter_turns - quarter_turns.round()).abs() < 1e-4 {
+                let (width, height) = if quarter_turns.round() as i64 % 2 == 0 {
+                    (width, height)
+                } else {
+                    (height, width)
+                };
+                if hole_radius > 0.0 && is_centered(obrounds.hole_x[i], obrounds.hole_y[i], x, y) {
+                    self.select_aperture(format!(
+                        "O,{:.6}X{:.6}X{:.6}",
+                        width,
+                        height,
+                        hole_radius * 2.0
+                    ));
+                    self.body.push(format!("{}D03*", coordinate(x, y)));
+                    continue;
+                }
+                self.select_aperture(format!("O,{:.6}X{:.6}", width, height));
+                self.body.push(format!("{}D03*", coordinate(x, y)));
+            } else {
+                // A stadium is a round stroke between its two end-cap centers
+                let diameter = width.min(height);
+                let half_length = (width.max(height) - diameter) / 2.0;
+                let axis = if width >= height {
+                    rotation
+                } else {
+                    rotation + PI / 2.0
+                };
+                let (dx, dy) = (axis.cos() * half_length, axis.sin() * half_length);
+                self.select_aperture(format!("C,{:.6}", diameter));
+                self.body
+                    .push(format!("{}D02*", coordinate(x - dx, y - dy)));
+                self.body
+                    .push(format!("{}D01*", coordinate(x + dx, y + dy)));
+            }
+            if hole_radius > 0.0 {
+                push_hole(
+                    &mut holes,
+                    obrounds.hole_x[i],
+                    obrounds.hole_y[i],
+                    hole_radius,
+                );
+            }
+        }
+
+        holes
+    }
+
+    /// Select (defining if needed) the aperture with the given definition
+    fn select_aperture(&mut self, definition: String) {
+        let next_code = FIRST_APERTURE_CODE + self.apertures.len() as u32;
+        let code = *self.apertures.entry(definition.clone()).or_insert_with(|| {
+            self.aperture_lines
+                .push(format!("%ADD{}{}*%", next_code, definition));
+            next_code
+        });
+        if self.current_aperture != Some(code) {
+            self.current_aperture = Some(code);
+            self.body.push(format!("D{}*", code));
+        }
+    }
+}
+
+/// Write parsed layer geometry back out as a Gerber file
+pub fn write_gerber(sublayers: &[GerberData]) -> String {
+    let mut writer = GerberWriter::default();
+    writer.write_sublayers(sublayers);
+    writer.finish()
+}
+
+/// Add a hole unless the same hole was already recorded (triangles share their hole)
+fn push_hole(holes: &mut Vec<(f32, f32, f32)>, x: f32, y: f32, radius: f32) {
+    if holes.last() != Some(&(x, y, radius)) {
+        holes.push((x, y, radius));
+    }
+}
+
+fn is_centered(hole_x: f32, hole_y: f32, x: f32, y: f32) -> bool {
+    (hole_x - x).abs() < 1e-5 && (hole_y - y).abs() < 1e-5
+}
+
+fn to_units(value: f32) -> i64 {
+    (value * COORDINATE_SCALE).round() as i64
+}
+
+fn coordinate(x: f32, y: f32) -> String {
+    format!("X{}Y{}", to_units(x), to_units(y))
+}
+
+/// Unsigned area of a contour
+fn contour_area(points: &[[f32; 2]]) -> f32 {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+        .sum::<f32>()
+        .abs()
+        / 2.0
+}
@@ -0,0 +1,208 @@
+use crate::extrude::{extrude_stackup, LayerMesh};
+use crate::shape::Boundary;
+use crate::stackup::{LayerArtwork, Stackup, StackupKind};
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+
+// glTF uses meters, layer geometry is in millimeters
+const MM_TO_METERS: f32 = 0.001;
+// GLB container constants
+const GLB_MAGIC: u32 = 0x4654_6C67; // "glTF"
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+const ARRAY_BUFFER_TARGET: u32 = 34962;
+const COMPONENT_FLOAT: u32 = 5126;
+
+/// Options for the 3D board export
+#[wasm_bindgen]
+pub struct GltfOptions {
+    pub(crate) stackup: Stackup,
+    pub(crate) outline_layer_id: Option<u32>,
+}
+
+#[wasm_bindgen]
+impl GltfOptions {
+    /// Create export options for a stackup
+    ///
+    /// Stackup layers linked to loaded layers (Stackup.assign_layer) are
+    /// extruded from their artwork; the stackup is copied, later changes to it
+    /// are not seen by the options.
+    #[wasm_bindgen(constructor)]
+    pub fn new(stackup: &Stackup) -> GltfOptions {
+        GltfOptions {
+            stackup: stackup.clone(),
+            outline_layer_id: None,
+        }
+    }
+
+    /// Layer whose extent gives the board outline (default: all linked layers)
+    #[wasm_bindgen(getter)]
+    pub fn outline_layer_id(&self) -> Option<u32> {
+        self.outline_layer_id
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_outline_layer_id(&mut self, layer_id: Option<u32>) {
+        self.outline_layer_id = layer_id;
+    }
+}
+
+/// Extrude a board stackup into a binary glTF (GLB) file
+///
+/// One node is written per extruded stackup layer, top layer first
+/// (see extrude_stackup for how each layer kind is built). Curves are
+/// flattened to within `chord_error` (mm).
+pub fn write_gltf(
+    stackup: &Stackup,
+    artwork: &[LayerArtwork],
+    board: &Boundary,
+    chord_error: f32,
+) -> Result<Vec<u8>, String> {
+    let mut document = Document::default();
+    for mesh in extrude_stackup(stackup, artwork, board, 0.0, chord_error)? {
+        document.add_mesh(&mesh);
+    }
+    Ok(document.into_glb())
+}
+
+/// glTF document under construction: one mesh and node per layer
+#[derive(Default)]
+struct Document {
+    binary: Vec<u8>,
+    buffer_views: Vec<serde_json::Value>,
+    accessors: Vec<serde_json::Value>,
+    materials: Vec<serde_json::Value>,
+    meshes: Vec<serde_json::Value>,
+    nodes: Vec<serde_json::Value>,
+}
+
+impl Document {
+    fn add_mesh(&mut self, mesh: &LayerMesh) {
+        // Board space (mm, z up) to glTF space (meters, y up)
+        let positions: Vec<f32> = mesh
+            .positions
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[2], -p[1]].map(|v| v * MM_TO_METERS))
+            .collect();
+        let normals: Vec<f32> = mesh
+            .normals
+            .chunks_exact(3)
+            .flat_map(|n| [n[0], n[2], -n[1]])
+            .collect();
+
+        let count = positions.len() / 3;
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        for vertex in positions.chunks_exact(3) {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(vertex[axis]);
+                max[axis] = max[axis].max(vertex[axis]);
+            }
+        }
+
+        let position = self.add_accessor(&positions, count, Some((min, max)));
+        let normal = self.add_accessor(&normals, count, None);
+
+        let material = self.materials.len();
+        self.materials.push(material_for(mesh.kind));
+        self.meshes.push(json!({
+            "name": mesh.name,
+            "primitives": [{
+                "attributes": { "POSITION": position, "NORMAL": normal },
+                "material": material,
+            }],
+        }));
+        self.nodes
+            .push(json!({ "name": mesh.name, "mesh": self.meshes.len() - 1 }));
+    }
+
+    fn add_accessor(
+        &mut self,
+        values: &[f32],
+        count: usize,
+        bounds: Option<([f32; 3], [f32; 3])>,
+    ) -> usize {
+        let offset = self.binary.len();
+        for value in values {
+            self.binary.extend_from_slice(&value.to_le_bytes());
+        }
+        self.buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": offset,
+            "byteLength": values.len() * 4,
+            "target": ARRAY_BUFFER_TARGET,
+        }));
+
+        let mut accessor = json!({
+            "bufferView": self.buffer_views.len() - 1,
+            "componentType": COMPONENT_FLOAT,
+            "count": count,
+            "type": "VEC3",
+        });
+        // POSITION accessors must declare their bounds
+        if let Some((min, max)) = bounds {
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn into_glb(mut self) -> Vec<u8> {
+        while !self.binary.len().is_multiple_of(4) {
+            self.binary.push(0);
+        }
+        let node_indices: Vec<usize> = (0..self.nodes.len()).collect();
+        let gltf = json!({
+            "asset": { "version": "2.0", "generator": "wasm_gerber_viewer" },
+            "scene": 0,
+            "scenes": [{ "nodes": node_indices }],
+            "nodes": self.nodes,
+            "meshes": self.meshes,
+            "materials": self.materials,
+            "accessors": self.accessors,
+            "bufferViews": self.buffer_views,
+            "buffers": [{ "byteLength": self.binary.len() }],
+        });
+        let mut json_chunk = gltf.to_string().into_bytes();
+        while !json_chunk.len().is_multiple_of(4) {
+            json_chunk.push(b' ');
+        }
+
+        let total = 12 + 8 + json_chunk.len() + 8 + self.binary.len();
+        let mut glb = Vec::with_capacity(total);
+        for word in [GLB_MAGIC, GLB_VERSION, total as u32] {
+            glb.extend_from_slice(&word.to_le_bytes());
+        }
+        glb.extend_from_slice(&(json_chunk.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&CHUNK_JSON.to_le_bytes());
+        glb.extend_from_slice(&json_chunk);
+        glb.extend_from_slice(&(self.binary.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&CHUNK_BIN.to_le_bytes());
+        glb.extend_from_slice(&self.binary);
+        glb
+    }
+}
+
+/// PBR material for a stackup material kind
+fn material_for(kind: StackupKind) -> serde_json::Value {
+    let color = kind.color();
+    let (metallic, roughness) = match kind {
+        StackupKind::Copper => (0.9, 0.35),
+        StackupKind::Paste => (0.6, 0.5),
+        StackupKind::SolderMask => (0.0, 0.4),
+        _ => (0.0, 0.85),
+    };
+    let mut material = json!({
+        "pbrMetallicRoughness": {
+            "baseColorFactor": color,
+            "metallicFactor": metallic,
+            "roughnessFactor": roughness,
+        },
+    });
+    if color[3] < 1.0 {
+        material["alphaMode"] = json!("BLEND");
+    }
+    material
+}
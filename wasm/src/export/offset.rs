@@ -0,0 +1,42 @@
+use super::gerber::GerberWriter;
+use crate::analysis::merged_shapes;
+use crate::shape::GerberData;
+use i_overlay::mesh::outline::offset::OutlineOffset;
+use i_overlay::mesh::style::{LineJoin, OutlineStyle};
+use std::f32::consts::FRAC_PI_2;
+
+/// Grow (positive `delta`) or shrink (negative) a layer's image by `delta` mm
+///
+/// The layer is merged into its final outlines first, so clear polarity and
+/// pad holes are respected, then every outline is offset with rounded
+/// corners. Returns the result as a Gerber file of regions.
+pub fn offset_layer(
+    sublayers: &[GerberData],
+    delta: f32,
+    chord_error: f32,
+) -> Result<String, String> {
+    if !delta.is_finite() {
+        return Err("Offset must be a finite number".to_string());
+    }
+
+    let shapes = merged_shapes(sublayers, chord_error);
+    let shapes = if delta == 0.0 {
+        shapes
+    } else {
+        // Joint arcs as fine as the curves of the layer
+        let radius = delta.abs();
+        let step = if radius > chord_error {
+            2.0 * (1.0 - chord_error / radius).acos()
+        } else {
+            FRAC_PI_2
+        };
+        shapes.outline(&OutlineStyle::new(delta).line_join(LineJoin::Round(step)))
+    };
+    if shapes.is_empty() {
+        return Err("Offset leaves no geometry".to_string());
+    }
+
+    let mut writer = GerberWriter::default();
+    writer.shapes(&shapes);
+    Ok(writer.finish())
+}
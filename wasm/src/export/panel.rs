@@ -0,0 +1,97 @@
+use super::gerber::GerberWriter;
+use crate::shape::{Boundary, GerberData};
+
+// Width of the top and bottom panel rails (mm)
+const RAIL_WIDTH: f32 = 5.0;
+// Fiducial copper dot and clearance diameters (mm)
+const FIDUCIAL_DIAMETER: f32 = 1.0;
+const FIDUCIAL_CLEARANCE: f32 = 2.0;
+// Distance of fiducials from the rail ends (mm)
+const FIDUCIAL_INSET: f32 = 5.0;
+
+/// Panel layout parameters
+pub struct PanelLayout {
+    pub nx: u32,
+    pub ny: u32,
+    pub gap_x: f32,
+    pub gap_y: f32,
+    pub add_rails: bool,
+}
+
+/// Step-repeat a set of layers into a panel, one Gerber file per input layer
+///
+/// All layers share the pitch of their combined boundary so they stay aligned.
+/// The board copies are written as a %SR% block; rails and fiducials follow it.
+pub fn panelize(layers: &[&[GerberData]], layout: &PanelLayout) -> Result<Vec<String>, String> {
+    if layout.nx == 0 || layout.ny == 0 {
+        return Err("Panel must have at least one column and one row".to_string());
+    }
+    if layout.gap_x < 0.0 || layout.gap_y < 0.0 {
+        return Err("Panel gaps must not be negative".to_string());
+    }
+
+    let all: Vec<Boundary> = layers.iter().map(|l| Boundary::combined(l)).collect();
+    let board = Boundary::new(
+        all.iter().map(|b| b.min_x).fold(f32::INFINITY, f32::min),
+        all.iter()
+            .map(|b| b.max_x)
+            .fold(f32::NEG_INFINITY, f32::max),
+        all.iter().map(|b| b.min_y).fold(f32::INFINITY, f32::min),
+        all.iter()
+            .map(|b| b.max_y)
+            .fold(f32::NEG_INFINITY, f32::max),
+    );
+    if !(board.max_x > board.min_x && board.max_y > board.min_y) {
+        return Err("Layers have no geometry to panelize".to_string());
+    }
+
+    let pitch_x = board.max_x - board.min_x + layout.gap_x;
+    let pitch_y = board.max_y - board.min_y + layout.gap_y;
+    let panel_max_x = board.min_x + pitch_x * layout.nx as f32 - layout.gap_x;
+    let panel_max_y = board.min_y + pitch_y * layout.ny as f32 - layout.gap_y;
+
+    let mut files = Vec::with_capacity(layers.len());
+    for sublayers in layers {
+        let mut writer = GerberWriter::default();
+        writer.begin_step_repeat(layout.nx, layout.ny, pitch_x, pitch_y);
+        writer.write_sublayers(sublayers);
+        writer.end_step_repeat();
+
+        if layout.add_rails {
+            let bottom = board.min_y - layout.gap_y - RAIL_WIDTH;
+            let top = panel_max_y + layout.gap_y;
+            write_rail(&mut writer, board.min_x, panel_max_x, bottom);
+            write_rail(&mut writer, board.min_x, panel_max_x, top);
+
+            // Three fiducials, asymmetric so the panel orientation is unambiguous
+            let fiducials = [
+                (board.min_x + FIDUCIAL_INSET, bottom + RAIL_WIDTH / 2.0),
+                (panel_max_x - FIDUCIAL_INSET, bottom + RAIL_WIDTH / 2.0),
+                (board.min_x + FIDUCIAL_INSET, top + RAIL_WIDTH / 2.0),
+            ];
+            writer.set_polarity(false);
+            for &(x, y) in &fiducials {
+                writer.flash_circle(x, y, FIDUCIAL_CLEARANCE);
+            }
+            writer.set_polarity(true);
+            for &(x, y) in &fiducials {
+                writer.flash_circle(x, y, FIDUCIAL_DIAMETER);
+            }
+        }
+
+        files.push(writer.finish());
+    }
+
+    Ok(files)
+}
+
+/// Write a full-width rail strip starting at `bottom`
+fn write_rail(writer: &mut GerberWriter, min_x: f32, max_x: f32, bottom: f32) {
+    writer.set_polarity(true);
+    writer.region(&[
+        [min_x, bottom],
+        [max_x, bottom],
+        [max_x, bottom + RAIL_WIDTH],
+        [min_x, bottom + RAIL_WIDTH],
+    ]);
+}
@@ -0,0 +1,375 @@
+use crate::shape::{Boundary, GerberData};
+use crate::tessellation::bezier_segments;
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+use std::fmt::Write;
+
+// PDF user space units (points) per millimeter
+const POINTS_PER_MM: f32 = 72.0 / 25.4;
+// A4 page in portrait orientation (mm)
+const PAGE_WIDTH: f32 = 210.0;
+const PAGE_HEIGHT: f32 = 297.0;
+// Blank border around the plot (mm)
+const PAGE_MARGIN: f32 = 10.0;
+
+/// A layer to plot, with the color of its dark polarity
+pub struct PdfLayer<'a> {
+    pub sublayers: &'a [GerberData],
+    pub color: [f32; 3],
+}
+
+/// Plot layers onto a single-page vector PDF
+///
+/// Layers are painted in order, later layers on top. Curves are written as
+/// Bezier arcs rather than flattened, so the plot stays exact when zoomed or
+/// printed. `scale` is the plot scale (1 = 1:1); 0 or less fits the board on
+/// an A4 page. At a fixed scale the page grows beyond A4 when the board does
+/// not fit. Clear polarity is painted in white, the page color. Arcs are split
+/// into enough Bezier segments to stay within `chord_error` mm of the true
+/// curve on paper, so enlarged plots get finer arcs.
+pub fn write_pdf(layers: &[PdfLayer], scale: f32, chord_error: f32) -> Vec<u8> {
+    let board = layers.iter().fold(
+        Boundary::new(
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+            f32::INFINITY,
+            f32::NEG_INFINITY,
+        ),
+        |b, layer| {
+            let l = Boundary::combined(layer.sublayers);
+            Boundary::new(
+                b.min_x.min(l.min_x),
+                b.max_x.max(l.max_x),
+                b.min_y.min(l.min_y),
+                b.max_y.max(l.max_y),
+            )
+        },
+    );
+    let (width, height) = if board.min_x <= board.max_x && board.min_y <= board.max_y {
+        (board.max_x - board.min_x, board.max_y - board.min_y)
+    } else {
+        (0.0, 0.0)
+    };
+
+    // Page turned to the board's orientation
+    let (mut page_w, mut page_h) = if width > height {
+        (PAGE_HEIGHT, PAGE_WIDTH)
+    } else {
+        (PAGE_WIDTH, PAGE_HEIGHT)
+    };
+    let scale = if scale > 0.0 {
+        page_w = page_w.max(width * scale + 2.0 * PAGE_MARGIN);
+        page_h = page_h.max(height * scale + 2.0 * PAGE_MARGIN);
+        scale
+    } else {
+        let fit_x = (page_w - 2.0 * PAGE_MARGIN) / width.max(f32::EPSILON);
+        let fit_y = (page_h - 2.0 * PAGE_MARGIN) / height.max(f32::EPSILON);
+        fit_x.min(fit_y)
+    };
+
+    // Board millimeters to page points, board centered on the page
+    let (offset_x, offset_y) = if width > 0.0 || height > 0.0 {
+        (
+            (page_w - width * scale) / 2.0 - board.min_x * scale,
+            (page_h - height * scale) / 2.0 - board.min_y * scale,
+        )
+    } else {
+        (0.0, 0.0)
+    };
+    // Arc tolerance on paper, in board millimeters
+    let board_error = chord_error / scale.max(f32::EPSILON);
+    let mut content = String::new();
+    let _ = writeln!(
+        content,
+        "q\n{} 0 0 {} {} {} cm",
+        num(scale * POINTS_PER_MM),
+        num(scale * POINTS_PER_MM),
+        num(offset_x * POINTS_PER_MM),
+        num(offset_y * POINTS_PER_MM)
+    );
+    for layer in layers {
+        for (index, sublayer) in layer.sublayers.iter().enumerate() {
+            // Even sublayers are dark, odd sublayers clear
+            let [r, g, b] = if index % 2 == 0 {
+                layer.color
+            } else {
+                [1.0, 1.0, 1.0]
+            };
+            let mut path = Path::new(board_error);
+            path.add_geometry(sublayer);
+            if path.ops.is_empty() {
+                continue;
+            }
+            let _ = writeln!(content, "{} {} {} rg", num(r), num(g), num(b));
+            content.push_str(&path.ops);
+            // Nonzero fill: shapes wind counterclockwise, holes clockwise
+            content.push_str("f\n");
+        }
+    }
+    content.push_str("Q\n");
+
+    let page = format!(
+        "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Contents 4 0 R /Resources << >> >>",
+        num(page_w * POINTS_PER_MM),
+        num(page_h * POINTS_PER_MM)
+    );
+    let stream = format!(
+        "<< /Length {} >>\nstream\n{}\nendstream",
+        content.len(),
+        content
+    );
+    write_document(&[
+        "<< /Type /Catalog /Pages 2 0 R >>",
+        "<< /Type /Pages /Kids [3 0 R] /Count 1 >>",
+        &page,
+        &stream,
+    ])
+}
+
+/// Assemble numbered objects into a PDF file with its cross-reference table
+fn write_document(objects: &[&str]) -> Vec<u8> {
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        let _ = write!(pdf, "{} 0 obj\n{}\nendobj\n", index + 1, object);
+    }
+
+    let xref = pdf.len();
+    let _ = write!(pdf, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(pdf, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        pdf,
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref
+    );
+    pdf.into_bytes()
+}
+
+/// Path construction operators for one sublayer
+struct Path {
+    ops: String,
+    chord_error: f32, // Maximum deviation of Bezier arcs (mm)
+}
+
+impl Path {
+    fn new(chord_error: f32) -> Self {
+        Path {
+            ops: String::new(),
+            chord_error,
+        }
+    }
+
+    fn add_geometry(&mut self, data: &GerberData) {
+        // Triangles, holes punched once per flash
+        let triangles = &data.triangles;
+        let mut last_hole = None;
+        for corners in triangles.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| {
+                let i = corners[k] as usize * 2;
+                [triangles.vertices[i], triangles.vertices[i + 1]]
+            });
+            let cross = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+            let (b, c) = if cross < 0.0 { (c, b) } else { (b, c) };
+            self.move_to(a);
+            self.line_to(b);
+            self.line_to(c);
+            self.close();
+
+            let v = corners[0] as usize;
+            if triangles.hole_radius.get(v).is_some_and(|&r| r > 0.0) {
+                let hole = (
+                    triangles.hole_x[v],
+                    triangles.hole_y[v],
+                    triangles.hole_radius[v],
+                );
+                if last_hole != Some(hole) {
+                    self.circle([hole.0, hole.1], hole.2, false);
+                    last_hole = Some(hole);
+                }
+            }
+        }
+
+        let circles = &data.circles;
+        for i in 0..circles.x.len() {
+            self.circle([circles.x[i], circles.y[i]], circles.radius[i], true);
+            if circles.hole_radius[i] > 0.0 {
+                let hole = [circles.hole_x[i], circles.hole_y[i]];
+                self.circle(hole, circles.hole_radius[i], false);
+            }
+        }
+
+        // Arcs, as the annular sector swept by the line width
+        let arcs = &data.arcs;
+        for i in 0..arcs.x.len() {
+            let (mut start, mut sweep) = (arcs.start_angle[i], arcs.sweep_angle[i]);
+            if sweep < 0.0 {
+                start += sweep;
+                sweep = -sweep;
+            }
+            let half = arcs.thickness[i] / 2.0;
+            self.sector(
+                [arcs.x[i], arcs.y[i]],
+                arcs.radius[i] + half,
+                (arcs.radius[i] - half).max(0.0),
+                (start, start + sweep),
+                (start, start + sweep),
+            );
+        }
+
+        // Thermals, four ring sectors between the gap bars
+        let thermals = &data.thermals;
+        for i in 0..thermals.x.len() {
+            let center = [thermals.x[i], thermals.y[i]];
+            let outer = thermals.outer_diameter[i] / 2.0;
+            let inner = thermals.inner_diameter[i] / 2.0;
+            let half_gap = thermals.gap_thickness[i] / 2.0;
+            // Angle of the gap edge where it meets a circle
+            let edge = |radius: f32| (half_gap / radius.max(f32::EPSILON)).min(1.0).asin();
+            let (outer_edge, inner_edge) = (edge(outer), edge(inner).min(FRAC_PI_4));
+            if outer_edge >= FRAC_PI_4 {
+                continue;
+            }
+            for quadrant in 0..4 {
+                let base = thermals.rotation[i] + quadrant as f32 * FRAC_PI_2;
+                self.sector(
+                    center,
+                    outer,
+                    inner,
+                    (base + outer_edge, base + FRAC_PI_2 - outer_edge),
+                    (base + inner_edge, base + FRAC_PI_2 - inner_edge),
+                );
+            }
+        }
+
+        // Obrounds, as two half circles joined by straight sides
+        let obrounds = &data.obrounds;
+        for i in 0..obrounds.x.len() {
+            let (width, height) = (obrounds.width[i], obrounds.height[i]);
+            let radius = width.min(height) / 2.0;
+            let half_length = (width.max(height) - width.min(height)) / 2.0;
+            let axis = obrounds.rotation[i] + if height > width { FRAC_PI_2 } else { 0.0 };
+            let (dx, dy) = (axis.cos() * half_length, axis.sin() * half_length);
+            let (x, y) = (obrounds.x[i], obrounds.y[i]);
+            self.arc([x + dx, y + dy], radius, axis - FRAC_PI_2, PI, true);
+            self.arc([x - dx, y - dy], radius, axis + FRAC_PI_2, PI, false);
+            self.close();
+            if obrounds.hole_radius[i] > 0.0 {
+                let hole = [obrounds.hole_x[i], obrounds.hole_y[i]];
+                self.circle(hole, obrounds.hole_radius[i], false);
+            }
+        }
+    }
+
+    /// Ring sector, counterclockwise along the outer edge
+    ///
+    /// Outer and inner edges take their own angle ranges so that straight
+    /// gaps (thermal spokes) can be cut exactly.
+    fn sector(
+        &mut self,
+        center: [f32; 2],
+        outer: f32,
+        inner: f32,
+        outer_angles: (f32, f32),
+        inner_angles: (f32, f32),
+    ) {
+        self.arc(
+            center,
+            outer,
+            outer_angles.0,
+            outer_angles.1 - outer_angles.0,
+            true,
+        );
+        if inner > 0.0 {
+            self.arc(
+                center,
+                inner,
+                inner_angles.1,
+                inner_angles.0 - inner_angles.1,
+                false,
+            );
+        } else {
+            self.line_to(center);
+        }
+        self.close();
+    }
+
+    fn circle(&mut self, center: [f32; 2], radius: f32, counterclockwise: bool) {
+        if radius <= 0.0 {
+            return;
+        }
+        let sweep = if counterclockwise {
+            2.0 * PI
+        } else {
+            -2.0 * PI
+        };
+        self.arc(center, radius, 0.0, sweep, true);
+        self.close();
+    }
+
+    /// Arc as cubic Bezier segments of at most a quarter turn, more when the
+    /// chord error requires it
+    ///
+    /// Starts a new subpath when `new_subpath` is set, otherwise continues the
+    /// current one with a line to the arc's start.
+    fn arc(&mut self, center: [f32; 2], radius: f32, start: f32, sweep: f32, new_subpath: bool) {
+        let point = |angle: f32| {
+            [
+                center[0] + radius * angle.cos(),
+                center[1] + radius * angle.sin(),
+            ]
+        };
+        if new_subpath {
+            self.move_to(point(start));
+        } else {
+            self.line_to(point(start));
+        }
+
+        let segments = bezier_segments(radius, sweep, self.chord_error);
+        let step = sweep / segments as f32;
+        // Control point distance for a circular Bezier arc
+        let handle = 4.0 / 3.0 * (step / 4.0).tan() * radius;
+        for i in 0..segments {
+            let a0 = start + step * i as f32;
+            let a1 = a0 + step;
+            let (p0, p1) = (point(a0), point(a1));
+            let c0 = [p0[0] - handle * a0.sin(), p0[1] + handle * a0.cos()];
+            let c1 = [p1[0] + handle * a1.sin(), p1[1] - handle * a1.cos()];
+            let _ = writeln!(
+                self.ops,
+                "{} {} {} {} {} {} c",
+                num(c0[0]),
+                num(c0[1]),
+                num(c1[0]),
+                num(c1[1]),
+                num(p1[0]),
+                num(p1[1])
+            );
+        }
+    }
+
+    fn move_to(&mut self, p: [f32; 2]) {
+        let _ = writeln!(self.ops, "{} {} m", num(p[0]), num(p[1]));
+    }
+
+    fn line_to(&mut self, p: [f32; 2]) {
+        let _ = writeln!(self.ops, "{} {} l", num(p[0]), num(p[1]));
+    }
+
+    fn close(&mut self) {
+        self.ops.push_str("h\n");
+    }
+}
+
+/// Number in PDF syntax, without exponent or trailing zeros
+fn num(value: f32) -> String {
+    let text = format!("{:.4}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" || text.is_empty() {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
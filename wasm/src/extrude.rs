@@ -0,0 +1,350 @@
+use crate::parser::geometry::{triangulate_shape_with_holes, Primitive};
+use crate::shape::{Boundary, GerberData};
+use crate::stackup::{LayerArtwork, Stackup, StackupKind};
+use crate::tessellation::arc_segments;
+use i_overlay::core::fill_rule::FillRule;
+use i_overlay::core::overlay_rule::OverlayRule;
+use i_overlay::float::single::SingleFloatOverlay;
+use std::f32::consts::PI;
+
+/// Extrude each stackup layer at its height in the stack
+///
+/// The board body is a box over the board extent. Copper, legend and paste
+/// are extruded from the dark sublayers of their linked artwork; solder mask
+/// covers the board except for its openings. Unlinked copper, legend and paste
+/// layers produce no mesh. `separation` adds a gap (mm) between consecutive
+/// layers for an exploded view. Curves are flattened to within `chord_error`
+/// (mm). Meshes are returned top layer first.
+pub fn extrude_stackup(
+    stackup: &Stackup,
+    artwork: &[LayerArtwork],
+    board: &Boundary,
+    separation: f32,
+    chord_error: f32,
+) -> Result<Vec<LayerMesh>, String> {
+    if !(board.max_x > board.min_x && board.max_y > board.min_y) {
+        return Err("Board outline is empty".to_string());
+    }
+
+    let mut meshes = Vec::new();
+    let gaps = stackup.layers.len().saturating_sub(1) as f32 * separation;
+    let mut top = stackup.layers.iter().map(|l| l.thickness).sum::<f32>() + gaps;
+    for (index, layer) in stackup.layers.iter().enumerate() {
+        let bottom = top - layer.thickness;
+        let mut mesh = LayerMesh {
+            name: if layer.name.is_empty() {
+                layer.type_name.clone()
+            } else {
+                layer.name.clone()
+            },
+            kind: layer.kind,
+            layer_id: layer.layer_id,
+            positions: Vec::new(),
+            normals: Vec::new(),
+        };
+        match (layer.kind, artwork.get(index).copied().flatten()) {
+            (StackupKind::Dielectric, _) | (StackupKind::Other, None) => {
+                mesh.push_box(board, bottom, top)
+            }
+            (StackupKind::SolderMask, openings) => {
+                let openings = openings
+                    .map(|openings| dark_triangles(openings, chord_error))
+                    .unwrap_or_default();
+                mesh.push_prisms(&mask_triangles(board, &openings), bottom, top);
+            }
+            (_, Some(sublayers)) => {
+                mesh.push_prisms(&dark_triangles(sublayers, chord_error), bottom, top)
+            }
+            (_, None) => {}
+        }
+        if !mesh.positions.is_empty() {
+            meshes.push(mesh);
+        }
+        top = bottom - separation;
+    }
+
+    if meshes.is_empty() {
+        return Err("Stackup has no layers to extrude".to_string());
+    }
+    Ok(meshes)
+}
+
+/// Triangles of one extruded stackup layer in board space (mm, z up)
+pub struct LayerMesh {
+    pub name: String,
+    pub kind: StackupKind,
+    #[cfg_attr(not(feature = "renderer"), allow(dead_code))]
+    pub layer_id: Option<u32>, // Loaded layer the artwork came from
+    pub positions: Vec<f32>, // x, y, z per vertex
+    pub normals: Vec<f32>,   // Flat normal per vertex
+}
+
+impl LayerMesh {
+    fn push_triangle(&mut self, corners: [[f32; 3]; 3], normal: [f32; 3]) {
+        for corner in corners {
+            self.positions.extend_from_slice(&corner);
+            self.normals.extend_from_slice(&normal);
+        }
+    }
+
+    /// Top and bottom faces of each 2D triangle (thin layers need no side walls)
+    fn push_prisms(&mut self, triangles: &[[[f32; 2]; 3]], bottom: f32, top: f32) {
+        for &[a, b, c] in triangles {
+            let [a, b, c] = if signed_area(a, b, c) < 0.0 {
+                [a, c, b]
+            } else {
+                [a, b, c]
+            };
+            self.push_triangle(
+                [point(a, top), point(b, top), point(c, top)],
+                [0.0, 0.0, 1.0],
+            );
+            self.push_triangle(
+                [point(a, bottom), point(c, bottom), point(b, bottom)],
+                [0.0, 0.0, -1.0],
+            );
+        }
+    }
+
+    /// Closed box over the board extent
+    fn push_box(&mut self, board: &Boundary, bottom: f32, top: f32) {
+        let corners = [
+            [board.min_x, board.min_y],
+            [board.max_x, board.min_y],
+            [board.max_x, board.max_y],
+            [board.min_x, board.max_y],
+        ];
+        self.push_prisms(
+            &[
+                [corners[0], corners[1], corners[2]],
+                [corners[0], corners[2], corners[3]],
+            ],
+            bottom,
+            top,
+        );
+
+        // Side walls, outward facing for the counter-clockwise corner order
+        for i in 0..4 {
+            let (a, b) = (corners[i], corners[(i + 1) % 4]);
+            let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+            let length = dx.hypot(dy);
+            let normal = [dy / length, -dx / length, 0.0];
+            let quad = [
+                point(a, bottom),
+                point(b, bottom),
+                point(b, top),
+                point(a, top),
+            ];
+            self.push_triangle([quad[0], quad[1], quad[2]], normal);
+            self.push_triangle([quad[0], quad[2], quad[3]], normal);
+        }
+    }
+}
+
+/// Board coordinates and height in mm
+fn point(p: [f32; 2], z: f32) -> [f32; 3] {
+    [p[0], p[1], z]
+}
+
+fn signed_area(a: [f32; 2], b: [f32; 2], c: [f32; 2]) -> f32 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])
+}
+
+/// Board extent minus the mask openings, triangulated
+fn mask_triangles(board: &Boundary, openings: &[[[f32; 2]; 3]]) -> Vec<[[f32; 2]; 3]> {
+    let outline = vec![vec![
+        [board.min_x, board.min_y],
+        [board.max_x, board.min_y],
+        [board.max_x, board.max_y],
+        [board.min_x, board.max_y],
+    ]];
+    if openings.is_empty() {
+        return vec![
+            [outline[0][0], outline[0][1], outline[0][2]],
+            [outline[0][0], outline[0][2], outline[0][3]],
+        ];
+    }
+
+    // Same winding for every opening so overlapping ones merge under NonZero
+    let clip: Vec<Vec<Vec<[f32; 2]>>> = openings
+        .iter()
+        .map(|&[a, b, c]| {
+            if signed_area(a, b, c) < 0.0 {
+                vec![vec![a, c, b]]
+            } else {
+                vec![vec![a, b, c]]
+            }
+        })
+        .collect();
+    let shapes = vec![outline].overlay(&clip, OverlayRule::Difference, FillRule::NonZero);
+
+    let mut triangles = Vec::new();
+    for shape in shapes {
+        if let Ok(primitives) = triangulate_shape_with_holes(&shape, 1.0) {
+            for primitive in primitives {
+                if let Primitive::Triangle { vertices, .. } = primitive {
+                    triangles.push(vertices);
+                }
+            }
+        }
+    }
+    triangles
+}
+
+/// Tessellate the dark (even) sublayers of a layer into triangles
+///
+/// Clear sublayers and holes other than centered circle holes are not cut out.
+fn dark_triangles(sublayers: &[GerberData], chord_error: f32) -> Vec<[[f32; 2]; 3]> {
+    let mut triangles = Vec::new();
+    for data in sublayers.iter().step_by(2) {
+        let t = &data.triangles;
+        for corners in t.indices.chunks_exact(3) {
+            let vertex = |i: u32| [t.vertices[i as usize * 2], t.vertices[i as usize * 2 + 1]];
+            triangles.push([vertex(corners[0]), vertex(corners[1]), vertex(corners[2])]);
+        }
+
+        let c = &data.circles;
+        for i in 0..c.x.len() {
+            let centered =
+                (c.hole_x[i] - c.x[i]).abs() < 1e-5 && (c.hole_y[i] - c.y[i]).abs() < 1e-5;
+            let inner = if centered { c.hole_radius[i] } else { 0.0 };
+            push_ring(
+                &mut triangles,
+                [c.x[i], c.y[i]],
+                inner,
+                c.radius[i],
+                0.0,
+                2.0 * PI,
+                chord_error,
+            );
+        }
+
+        let a = &data.arcs;
+        for i in 0..a.x.len() {
+            let inner = (a.radius[i] - a.thickness[i] / 2.0).max(0.0);
+            let outer = a.radius[i] + a.thickness[i] / 2.0;
+            push_ring(
+                &mut triangles,
+                [a.x[i], a.y[i]],
+                inner,
+                outer,
+                a.start_angle[i],
+                a.sweep_angle[i],
+                chord_error,
+            );
+        }
+
+        // Thermals: four ring sectors between the gaps, gaps follow the shader's rotation
+        let th = &data.thermals;
+        for i in 0..th.x.len() {
+            let inner = th.inner_diameter[i] / 2.0;
+            let outer = th.outer_diameter[i] / 2.0;
+            let half_gap = th.gap_thickness[i] / 2.0;
+            if half_gap >= outer {
+                continue;
+            }
+            // Angular half width of the gap at a radius
+            let gap_angle = |r: f32| {
+                if r > half_gap {
+                    (half_gap / r).asin()
+                } else {
+                    PI / 4.0
+                }
+            };
+            let (gap_inner, gap_outer) = (gap_angle(inner), gap_angle(outer));
+            for quarter in 0..4 {
+                let axis = th.rotation[i] + quarter as f32 * PI / 2.0;
+                push_thermal_sector(
+                    &mut triangles,
+                    [th.x[i], th.y[i]],
+                    [inner, outer],
+                    [axis + gap_inner, axis + PI / 2.0 - gap_inner],
+                    [axis + gap_outer, axis + PI / 2.0 - gap_outer],
+                    chord_error,
+                );
+            }
+        }
+
+        // Obrounds: convex stadium, fanned from the center
+        let o = &data.obrounds;
+        for i in 0..o.x.len() {
+            let radius = o.width[i].min(o.height[i]) / 2.0;
+            let half_length = (o.width[i].max(o.height[i]) - 2.0 * radius) / 2.0;
+            let axis = if o.width[i] >= o.height[i] {
+                o.rotation[i]
+            } else {
+                o.rotation[i] + PI / 2.0
+            };
+            let (ux, uy) = (axis.cos(), axis.sin());
+            let half_segments = arc_segments(radius, PI, chord_error);
+            let mut outline = Vec::with_capacity(half_segments * 2 + 2);
+            for (sign, start) in [(1.0, axis - PI / 2.0), (-1.0, axis + PI / 2.0)] {
+                let (cx, cy) = (
+                    o.x[i] + sign * ux * half_length,
+                    o.y[i] + sign * uy * half_length,
+                );
+                for step in 0..=half_segments {
+                    let angle = start + step as f32 * PI / half_segments as f32;
+                    outline.push([cx + radius * angle.cos(), cy + radius * angle.sin()]);
+                }
+            }
+            let center = [o.x[i], o.y[i]];
+            for k in 0..outline.len() {
+                triangles.push([center, outline[k], outline[(k + 1) % outline.len()]]);
+            }
+        }
+    }
+    triangles
+}
+
+/// Annular sector (or full disc for a zero inner radius) as a triangle strip
+fn push_ring(
+    triangles: &mut Vec<[[f32; 2]; 3]>,
+    center: [f32; 2],
+    inner: f32,
+    outer: f32,
+    start: f32,
+    sweep: f32,
+    chord_error: f32,
+) {
+    let segments = arc_segments(outer, sweep, chord_error);
+    let at = |r: f32, angle: f32| [center[0] + r * angle.cos(), center[1] + r * angle.sin()];
+    for k in 0..segments {
+        let a0 = start + sweep * k as f32 / segments as f32;
+        let a1 = start + sweep * (k + 1) as f32 / segments as f32;
+        if inner > 0.0 {
+            triangles.push([at(inner, a0), at(outer, a0), at(outer, a1)]);
+            triangles.push([at(inner, a0), at(outer, a1), at(inner, a1)]);
+        } else {
+            triangles.push([center, at(outer, a0), at(outer, a1)]);
+        }
+    }
+}
+
+/// Thermal spoke sector whose edges are straight gap walls, not radial lines
+fn push_thermal_sector(
+    triangles: &mut Vec<[[f32; 2]; 3]>,
+    center: [f32; 2],
+    radii: [f32; 2],
+    inner_span: [f32; 2],
+    outer_span: [f32; 2],
+    chord_error: f32,
+) {
+    let segments = arc_segments(radii[1], outer_span[1] - outer_span[0], chord_error);
+    let at = |r: f32, angle: f32| [center[0] + r * angle.cos(), center[1] + r * angle.sin()];
+    for k in 0..segments {
+        let t0 = k as f32 / segments as f32;
+        let t1 = (k + 1) as f32 / segments as f32;
+        let lerp = |span: [f32; 2], t: f32| span[0] + (span[1] - span[0]) * t;
+        let inner0 = at(radii[0], lerp(inner_span, t0));
+        let inner1 = at(radii[0], lerp(inner_span, t1));
+        let outer0 = at(radii[1], lerp(outer_span, t0));
+        let outer1 = at(radii[1], lerp(outer_span, t1));
+        if radii[0] > 0.0 {
+            triangles.push([inner0, outer0, outer1]);
+            triangles.push([inner0, outer1, inner1]);
+        } else {
+            triangles.push([center, outer0, outer1]);
+        }
+    }
+}
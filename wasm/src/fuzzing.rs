@@ -0,0 +1,162 @@
+//! Entry points for the cargo-fuzz targets in `fuzz/` (`fuzzing` feature)
+//!
+//! These expose parser internals so the fuzzers, the corpus tests and the
+//! golden-file tests can drive them natively. Not a stable API.
+
+use crate::error::ViewerError;
+use crate::parser::{evaluate_expression as evaluate, parse_gerber as parse};
+use crate::shape::Boundary;
+use std::collections::HashMap;
+
+/// Parse Gerber text, returning the number of polarity sublayers
+pub fn parse_gerber(data: &str) -> Result<usize, ViewerError> {
+    parse(data).map(|layers| layers.len())
+}
+
+/// Evaluate an aperture macro expression with `params` as $1, $2, ...
+pub fn evaluate_expression(expr: &str, params: &[f32]) -> Result<f32, String> {
+    let variables: HashMap<String, f32> = params
+        .iter()
+        .enumerate()
+        .map(|(i, &value)| (format!("${}", i + 1), value))
+        .collect();
+    evaluate(expr, &variables)
+}
+
+/// Define an aperture macro from its statements and flash it once
+///
+/// `body` is the text between `%AMNAME*` and the closing `%`; `params` is
+/// the modifier list of the %ADD that instantiates it, e.g. "1.0X0.5".
+pub fn flash_macro(body: &str, params: &str) -> Result<usize, ViewerError> {
+    parse_gerber(&format!(
+        "%FSLAX26Y26*%\n%MOMM*%\n%AMFUZZ*{}%\n%ADD10FUZZ,{}*%\nD10*\nX0Y0D03*\nM02*\n",
+        body, params
+    ))
+}
+
+/// Primitive counts and a geometry hash of one polarity sublayer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SublayerSummary {
+    pub triangles: usize,
+    pub circles: usize,
+    pub arcs: usize,
+    pub thermals: usize,
+    pub obrounds: usize,
+    /// FNV-1a over every coordinate rounded to 0.1 µm, so float noise far
+    /// below manufacturing resolution does not change it
+    pub hash: u64,
+}
+
+/// Summary of a parsed layer for the golden-file tests
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeometrySummary {
+    pub sublayers: Vec<SublayerSummary>,
+    /// min_x, max_x, min_y, max_y in mm
+    pub bounds: [f32; 4],
+}
+
+/// Parse Gerber text and summarize the resulting geometry
+pub fn summarize_gerber(data: &str) -> Result<GeometrySummary, ViewerError> {
+    let layers = parse(data)?;
+    let boundary = Boundary::combined(&layers);
+    let sublayers = layers
+        .iter()
+        .map(|layer| {
+            let mut hash = Fnv::new();
+            let t = &layer.triangles;
+            hash.floats(&t.vertices);
+            hash.indices(&t.indices);
+            for values in [&t.hole_x, &t.hole_y, &t.hole_radius] {
+                hash.floats(values);
+            }
+            let c = &layer.circles;
+            for values in [&c.x, &c.y, &c.radius, &c.hole_x, &c.hole_y, &c.hole_radius] {
+                hash.floats(values);
+            }
+            let a = &layer.arcs;
+            for values in [
+                &a.x,
+                &a.y,
+                &a.radius,
+                &a.start_angle,
+                &a.sweep_angle,
+                &a.thickness,
+            ] {
+                hash.floats(values);
+            }
+            let th = &layer.thermals;
+            for values in [
+                &th.x,
+                &th.y,
+                &th.outer_diameter,
+                &th.inner_diameter,
+                &th.gap_thickness,
+                &th.rotation,
+            ] {
+                hash.floats(values);
+            }
+            let o = &layer.obrounds;
+            for values in [
+                &o.x,
+                &o.y,
+                &o.width,
+                &o.height,
+                &o.rotation,
+                &o.hole_x,
+                &o.hole_y,
+                &o.hole_radius,
+            ] {
+                hash.floats(values);
+            }
+            SublayerSummary {
+                triangles: t.indices.len() / 3,
+                circles: c.x.len(),
+                arcs: a.x.len(),
+                thermals: th.x.len(),
+                obrounds: o.x.len(),
+                hash: hash.0,
+            }
+        })
+        .collect();
+    Ok(GeometrySummary {
+        sublayers,
+        bounds: [
+            boundary.min_x,
+            boundary.max_x,
+            boundary.min_y,
+            boundary.max_y,
+        ],
+    })
+}
+
+/// 64-bit FNV-1a
+struct Fnv(u64);
+
+impl Fnv {
+    fn new() -> Fnv {
+        Fnv(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn floats(&mut self, values: &[f32]) {
+        // Length first, so values cannot shift between arrays
+        self.bytes(&(values.len() as u64).to_le_bytes());
+        for &value in values {
+            let quantized = (value as f64 * 1e4).round() as i64;
+            self.bytes(&quantized.to_le_bytes());
+        }
+    }
+
+    fn indices(&mut self, values: &[u32]) {
+        self.bytes(&(values.len() as u64).to_le_bytes());
+        for &value in values {
+            self.bytes(&value.to_le_bytes());
+        }
+    }
+}
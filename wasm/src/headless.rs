@@ -0,0 +1,172 @@
+mod png;
+
+// Export only what's needed externally
+pub use png::encode_png;
+
+use crate::analysis::CoverageIndex;
+use crate::parser::{GerberParser, Polarity};
+use crate::shape::{Boundary, GerberData};
+
+// Coverage samples per pixel along each axis (antialiasing)
+const SAMPLES_PER_AXIS: usize = 4;
+
+/// Software renderer drawing parsed layers without WebGL
+///
+/// Produces the same layer compositing as the WebGL renderer (polarity
+/// sublayers resolved per layer, layers added in their colors) on the CPU, so
+/// images can be rendered natively, e.g. for golden-image tests in CI.
+#[derive(Default)]
+pub struct HeadlessRenderer {
+    layers: Vec<Vec<GerberData>>,
+    inverted: Vec<bool>, // Per layer: negative image polarity (%IPNEG)
+}
+
+impl HeadlessRenderer {
+    pub fn new() -> HeadlessRenderer {
+        HeadlessRenderer::default()
+    }
+
+    /// Parse Gerber text and add it as a new layer, returning its layer ID
+    pub fn add_layer(&mut self, content: &str) -> Result<usize, String> {
+        let mut parser = GerberParser::new();
+        let sublayers = parser.parse(content).map_err(|e| e.to_string())?;
+        let layer_id = self.add_gerber_data(sublayers);
+        self.inverted[layer_id] = parser.layer_info().image_polarity == Polarity::Negative;
+        Ok(layer_id)
+    }
+
+    /// Add already parsed polarity sublayers as a new layer
+    pub fn add_gerber_data(&mut self, sublayers: Vec<GerberData>) -> usize {
+        self.layers.push(sublayers);
+        self.inverted.push(false);
+        self.layers.len() - 1
+    }
+
+    /// Combined boundary of all layers
+    pub fn boundary(&self) -> Boundary {
+        let bounds: Vec<Boundary> = self.layers.iter().map(|l| Boundary::combined(l)).collect();
+        Boundary::new(
+            bounds.iter().map(|b| b.min_x).fold(f32::INFINITY, f32::min),
+            bounds
+                .iter()
+                .map(|b| b.max_x)
+                .fold(f32::NEG_INFINITY, f32::max),
+            bounds.iter().map(|b| b.min_y).fold(f32::INFINITY, f32::min),
+            bounds
+                .iter()
+                .map(|b| b.max_y)
+                .fold(f32::NEG_INFINITY, f32::max),
+        )
+    }
+
+    /// Render a board window to RGBA pixels
+    ///
+    /// Same layout as GerberProcessor.render_region_to_image(): top row first,
+    /// straight alpha, layers combined additively at full opacity.
+    pub fn render(
+        &self,
+        region: &Boundary,
+        width: u32,
+        height: u32,
+        layer_ids: &[usize],
+        colors: &[[f32; 3]],
+    ) -> Result<Vec<u8>, String> {
+        if colors.len() < layer_ids.len() {
+            return Err("One color is needed per layer".to_string());
+        }
+        let region_width = region.max_x - region.min_x;
+        let region_height = region.max_y - region.min_y;
+        if width == 0 || height == 0 || region_width <= 0.0 || region_height <= 0.0 {
+            return Err("Image and region must have a positive size".to_string());
+        }
+
+        let (width, height) = (width as usize, height as usize);
+        let pixel_width = region_width / width as f32;
+        let pixel_height = region_height / height as f32;
+        // Premultiplied color and alpha per pixel, accumulated over layers
+        let mut accumulated = vec![[0.0f32; 4]; width * height];
+
+        for (&layer_id, color) in layer_ids.iter().zip(colors) {
+            let sublayers = self
+                .layers
+                .get(layer_id)
+                .ok_or_else(|| format!("Invalid layer_id: {}", layer_id))?;
+            let index = CoverageIndex::new(sublayers);
+            let bounds = index.bounds();
+            // A negative image covers everything except its dark geometry
+            let inverted = self.inverted[layer_id];
+
+            for row in 0..height {
+                let top = region.max_y - row as f32 * pixel_height;
+                if !inverted && (top < bounds.min_y || top - pixel_height > bounds.max_y) {
+                    continue;
+                }
+                for column in 0..width {
+                    let left = region.min_x + column as f32 * pixel_width;
+                    let outside = left > bounds.max_x || left + pixel_width < bounds.min_x;
+                    if outside && !inverted {
+                        continue;
+                    }
+                    let mut coverage = if outside {
+                        0.0
+                    } else {
+                        pixel_coverage(&index, left, top, pixel_width, pixel_height)
+                    };
+                    if inverted {
+                        coverage = 1.0 - coverage;
+                    }
+                    if coverage > 0.0 {
+                        let pixel = &mut accumulated[row * width + column];
+                        for (value, &c) in pixel[..3].iter_mut().zip(color) {
+                            *value += c * coverage;
+                        }
+                        pixel[3] += coverage;
+                    }
+                }
+            }
+        }
+
+        let mut image = Vec::with_capacity(width * height * 4);
+        for pixel in accumulated {
+            let alpha = pixel[3].min(1.0);
+            for &value in &pixel[..3] {
+                let value = if alpha > 0.0 {
+                    value.min(1.0) / alpha
+                } else {
+                    0.0
+                };
+                image.push((value.min(1.0) * 255.0).round() as u8);
+            }
+            image.push((alpha * 255.0).round() as u8);
+        }
+        Ok(image)
+    }
+
+    /// Render a board window to a PNG file
+    pub fn render_png(
+        &self,
+        region: &Boundary,
+        width: u32,
+        height: u32,
+        layer_ids: &[usize],
+        colors: &[[f32; 3]],
+    ) -> Result<Vec<u8>, String> {
+        let pixels = self.render(region, width, height, layer_ids, colors)?;
+        Ok(encode_png(width, height, &pixels))
+    }
+}
+
+/// Fraction of a pixel covered by the layer, from a regular sample grid
+fn pixel_coverage(index: &CoverageIndex, left: f32, top: f32, width: f32, height: f32) -> f32 {
+    let mut covered = 0;
+    for sy in 0..SAMPLES_PER_AXIS {
+        let y = top - (sy as f32 + 0.5) / SAMPLES_PER_AXIS as f32 * height;
+        for sx in 0..SAMPLES_PER_AXIS {
+            let x = left + (sx as f32 + 0.5) / SAMPLES_PER_AXIS as f32 * width;
+            if index.covered(x, y) {
+                covered += 1;
+            }
+        }
+    }
+    covered as f32 / (SAMPLES_PER_AXIS * SAMPLES_PER_AXIS) as f32
+}
@@ -0,0 +1,220 @@
+mod ipc2581;
+mod kicad;
+
+// Export only what's needed externally
+pub use ipc2581::parse_ipc2581;
+pub use kicad::parse_kicad_pcb;
+
+use crate::parser::build_gerber_data;
+use crate::parser::geometry::{line_to_triangles, Primitive};
+use crate::shape::GerberData;
+use crate::stackup::Stackup;
+use std::f32::consts::{FRAC_PI_2, PI};
+use wasm_bindgen::prelude::*;
+
+// Segments per full turn when flattening curves into polygons
+const CURVE_SEGMENTS: usize = 72;
+
+/// One layer read from a board design file
+pub struct ImportedLayer {
+    pub name: String,
+    pub function: String, // .FileFunction style value, e.g. "Copper,L1,Top"
+    pub sublayers: Vec<GerberData>, // Polarity sublayers, dark first
+    pub stackup_index: Option<usize>, // Stackup layer holding this layer
+}
+
+/// Layers and stackup read from a board design file
+pub struct ImportedDesign {
+    pub layers: Vec<ImportedLayer>,
+    pub stackup: Stackup,
+}
+
+/// Primitives of one layer, split into a new sublayer at every polarity change
+///
+/// Sublayers alternate dark and clear like those of parsed Gerber files.
+#[derive(Default)]
+pub struct Artwork {
+    sublayers: Vec<Vec<Primitive>>,
+}
+
+impl Artwork {
+    /// Add a primitive with dark (`clear` = false) or clear polarity
+    pub fn push(&mut self, primitive: Primitive, clear: bool) {
+        // Clear before any dark material removes nothing
+        if clear && self.sublayers.is_empty() {
+            return;
+        }
+        // Odd sublayers are clear, so the last one is clear at even lengths
+        let current_clear = self.sublayers.len().is_multiple_of(2);
+        if self.sublayers.is_empty() || current_clear != clear {
+            self.sublayers.push(Vec::new());
+        }
+        if let Some(sublayer) = self.sublayers.last_mut() {
+            sublayer.push(primitive);
+        }
+    }
+
+    pub fn extend(&mut self, primitives: Vec<Primitive>, clear: bool) {
+        for primitive in primitives {
+            self.push(primitive, clear);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sublayers.is_empty()
+    }
+
+    /// All primitives, for artwork drawn with dark polarity only
+    pub fn into_primitives(self) -> Vec<Primitive> {
+        self.sublayers.into_iter().flatten().collect()
+    }
+
+    pub fn into_gerber_data(self) -> Vec<GerberData> {
+        self.sublayers
+            .iter()
+            .map(|primitives| build_gerber_data(primitives))
+            .collect()
+    }
+}
+
+/// Layers loaded from a board design file (IPC-2581, KiCad)
+#[wasm_bindgen]
+pub struct ImportedBoard {
+    pub(crate) layer_ids: Vec<u32>,
+    pub(crate) names: Vec<String>,
+    pub(crate) functions: Vec<String>,
+    pub(crate) stackup: Stackup,
+}
+
+#[wasm_bindgen]
+impl ImportedBoard {
+    /// Number of loaded layers
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.layer_ids.len()
+    }
+
+    /// Layer IDs of the loaded layers, top side first
+    #[wasm_bindgen(getter)]
+    pub fn layer_ids(&self) -> Vec<u32> {
+        self.layer_ids.clone()
+    }
+
+    /// Layer name from the design file of the layer at `index`
+    pub fn layer_name(&self, index: usize) -> Option<String> {
+        self.names.get(index).cloned()
+    }
+
+    /// .FileFunction style function of the layer at `index` (e.g. "Copper,L1,Top")
+    pub fn layer_function(&self, index: usize) -> Option<String> {
+        self.functions.get(index).cloned()
+    }
+
+    /// Number of layers in the design's stackup (0 if it has none)
+    #[wasm_bindgen(getter)]
+    pub fn stackup_count(&self) -> usize {
+        self.stackup.layers.len()
+    }
+
+    /// Board stackup with the loaded layers already linked
+    pub fn stackup(&self) -> Stackup {
+        self.stackup.clone()
+    }
+}
+
+/// Flatten an arc into points, start and end included
+///
+/// Coincident start and end points give a full circle.
+fn arc_points(start: [f32; 2], end: [f32; 2], center: [f32; 2], clockwise: bool) -> Vec<[f32; 2]> {
+    let radius = (start[0] - center[0]).hypot(start[1] - center[1]);
+    let start_angle = (start[1] - center[1]).atan2(start[0] - center[0]);
+    let end_angle = (end[1] - center[1]).atan2(end[0] - center[0]);
+    let mut sweep = end_angle - start_angle;
+    if clockwise && sweep >= 0.0 {
+        sweep -= 2.0 * PI;
+    } else if !clockwise && sweep <= 0.0 {
+        sweep += 2.0 * PI;
+    }
+
+    let segments = ((sweep.abs() / (2.0 * PI) * CURVE_SEGMENTS as f32).ceil() as usize).max(1);
+    let mut points: Vec<[f32; 2]> = (0..segments)
+        .map(|i| {
+            let angle = start_angle + sweep * i as f32 / segments as f32;
+            [
+                center[0] + radius * angle.cos(),
+                center[1] + radius * angle.sin(),
+            ]
+        })
+        .collect();
+    points.push(end);
+    points
+}
+
+/// Stroke a path with a line of the given width
+fn stroke(path: &[[f32; 2]], width: f32, round: bool, clear: bool, artwork: &mut Artwork) {
+    if width <= 0.0 {
+        return;
+    }
+    for segment in path.windows(2) {
+        let ([x0, y0], [x1, y1]) = (segment[0], segment[1]);
+        artwork.extend(line_to_triangles(x0, y0, x1, y1, width, 1.0), clear);
+    }
+    // Round joints and ends, like a circular aperture
+    if round {
+        for &[x, y] in path {
+            artwork.push(circle(x, y, width / 2.0), clear);
+        }
+    }
+}
+
+/// Rectangle outline centered on the origin with rounded or chamfered corners
+fn rectangle(width: f32, height: f32, corner: f32, chamfer: bool) -> Vec<[f32; 2]> {
+    let (w, h) = (width / 2.0, height / 2.0);
+    let corner = corner.clamp(0.0, w.min(h));
+    if corner <= 0.0 {
+        return vec![[w, -h], [w, h], [-w, h], [-w, -h]];
+    }
+
+    let steps = if chamfer { 1 } else { CURVE_SEGMENTS / 4 };
+    let centers = [
+        [w - corner, -h + corner],
+        [w - corner, h - corner],
+        [-w + corner, h - corner],
+        [-w + corner, -h + corner],
+    ];
+    let mut outline = Vec::with_capacity(4 * (steps + 1));
+    for (quadrant, c) in centers.iter().enumerate() {
+        let start = -FRAC_PI_2 + quadrant as f32 * FRAC_PI_2;
+        for i in 0..=steps {
+            let angle = start + FRAC_PI_2 * i as f32 / steps as f32;
+            outline.push([c[0] + corner * angle.cos(), c[1] + corner * angle.sin()]);
+        }
+    }
+    outline
+}
+
+fn circle(x: f32, y: f32, radius: f32) -> Primitive {
+    Primitive::Circle {
+        x,
+        y,
+        radius,
+        exposure: 1.0,
+        hole_x: x,
+        hole_y: y,
+        hole_radius: 0.0,
+    }
+}
+
+/// Path with the requested winding (counterclockwise or clockwise)
+fn oriented(mut path: Vec<[f32; 2]>, counterclockwise: bool) -> Vec<[f32; 2]> {
+    let area: f32 = (0..path.len())
+        .map(|i| {
+            let (a, b) = (path[i], path[(i + 1) % path.len()]);
+            a[0] * b[1] - b[0] * a[1]
+        })
+        .sum();
+    if (area > 0.0) != counterclockwise {
+        path.reverse();
+    }
+    path
+}
@@ -0,0 +1,612 @@
+use super::{
+    arc_points, circle, oriented, rectangle, stroke, Artwork, ImportedDesign, ImportedLayer,
+    CURVE_SEGMENTS,
+};
+use crate::parser::geometry::{
+    rotate_point, triangulate_outline, triangulate_shape_with_holes, Primitive,
+};
+use crate::stackup::Stackup;
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+use std::f32::consts::PI;
+
+// Width of the lines drawing the board profile (mm)
+const PROFILE_WIDTH: f32 = 0.1;
+
+/// Stroke settings from a LineDesc element
+#[derive(Clone, Copy)]
+struct LineDesc {
+    width: f32,
+    round: bool, // Round line ends, otherwise square or flat
+}
+
+/// Placement of a flashed primitive
+#[derive(Clone, Copy)]
+struct Placement {
+    x: f32,
+    y: f32,
+    rotation: f32, // Radians, counterclockwise
+    mirror: bool,  // Mirrored across the local Y axis before rotation
+    scale: f32,
+}
+
+impl Placement {
+    fn apply(&self, point: [f32; 2]) -> [f32; 2] {
+        let mut p = [point[0] * self.scale, point[1] * self.scale];
+        if self.mirror {
+            p[0] = -p[0];
+        }
+        rotate_point(&mut p, self.rotation, 0.0, 0.0);
+        [self.x + p[0], self.y + p[1]]
+    }
+}
+
+/// Dictionaries and units shared by all features of the file
+struct Context<'a, 'input> {
+    scale: f32,                                            // CAD units to mm
+    primitives: HashMap<&'a str, (Node<'a, 'input>, f32)>, // Shape and its dictionary scale
+    line_descs: HashMap<&'a str, LineDesc>,
+}
+
+/// Parse an IPC-2581 file (revision B or C)
+///
+/// Every layer of the first step that carries features becomes one imported
+/// layer; the board profile becomes a "Profile" layer. Layer functions are
+/// mapped to .FileFunction values and the stackup is read from the CAD data.
+pub fn parse_ipc2581(xml: &str) -> Result<ImportedDesign, String> {
+    let document = Document::parse(xml).map_err(|e| format!("Invalid IPC-2581 file: {}", e))?;
+    let root = document.root_element();
+    if root.tag_name().name() != "IPC-2581" {
+        return Err("Not an IPC-2581 file (missing IPC-2581 root element)".to_string());
+    }
+
+    let scale = find(root, "CadHeader")
+        .map(|header| unit_scale(header.attribute("units")))
+        .unwrap_or(1.0);
+    let context = Context {
+        scale,
+        primitives: read_primitives(root, scale),
+        line_descs: read_line_descs(root, scale),
+    };
+
+    let cad_data = find(root, "CadData").ok_or("IPC-2581 file has no CadData section")?;
+    let layer_defs: Vec<Node> = children(cad_data, "Layer").collect();
+    let stackup_refs = read_stackup_order(cad_data, scale);
+
+    // Copper layers are numbered in stackup order when a stackup is given
+    let copper_order: Vec<&str> = if stackup_refs.is_empty() {
+        layer_defs
+            .iter()
+            .filter(|l| is_copper(l.attribute("layerFunction").unwrap_or("")))
+            .filter_map(|l| l.attribute("name"))
+            .collect()
+    } else {
+        stackup_refs
+            .iter()
+            .filter(|(name, _)| {
+                layer_defs.iter().any(|l| {
+                    l.attribute("name") == Some(name.as_str())
+                        && is_copper(l.attribute("layerFunction").unwrap_or(""))
+                })
+            })
+            .map(|(name, _)| name.as_str())
+            .collect()
+    };
+    let function_of = |name: &str| -> String {
+        let def = layer_defs
+            .iter()
+            .find(|l| l.attribute("name") == Some(name));
+        let layer_function = def.and_then(|l| l.attribute("layerFunction")).unwrap_or("");
+        let side = def.and_then(|l| l.attribute("side")).unwrap_or("");
+        file_function(layer_function, side, name, &copper_order)
+    };
+
+    let mut stackup = Stackup::default();
+    let mut stackup_index: HashMap<&str, usize> = HashMap::new();
+    for (name, thickness) in &stackup_refs {
+        let def = layer_defs
+            .iter()
+            .find(|l| l.attribute("name") == Some(name.as_str()));
+        let layer_function = def.and_then(|l| l.attribute("layerFunction")).unwrap_or("");
+        let index = stackup.add_layer(stackup_type(layer_function), *thickness, "", name);
+        stackup_index.insert(name.as_str(), index);
+    }
+
+    // Only the first step is loaded; panels reference it through StepRepeat
+    let step = find(root, "Step").ok_or("IPC-2581 file has no Step")?;
+    let mut artwork: Vec<(&str, Artwork)> = Vec::new();
+    for feature in children(step, "LayerFeature") {
+        let name = feature.attribute("layerRef").unwrap_or("");
+        let index = match artwork.iter().position(|(n, _)| *n == name) {
+            Some(index) => index,
+            None => {
+                artwork.push((name, Artwork::default()));
+                artwork.len() - 1
+            }
+        };
+        for set in children(feature, "Set") {
+            let clear = set.attribute("polarity") == Some("NEGATIVE");
+            read_set(&context, set, clear, &mut artwork[index].1);
+        }
+    }
+
+    // Keep the stackup order (top side first) for layers that are part of it
+    artwork.sort_by_key(|(name, _)| {
+        stackup_refs
+            .iter()
+            .position(|(n, _)| n == name)
+            .unwrap_or(stackup_refs.len())
+    });
+
+    let mut layers: Vec<ImportedLayer> = artwork
+        .into_iter()
+        .filter(|(_, artwork)| !artwork.is_empty())
+        .map(|(name, artwork)| ImportedLayer {
+            name: name.to_string(),
+            function: function_of(name),
+            sublayers: artwork.into_gerber_data(),
+            stackup_index: stackup_index.get(name).copied(),
+        })
+        .collect();
+
+    if let Some(profile) = children(step, "Profile").next() {
+        let mut outline = Artwork::default();
+        let line = LineDesc {
+            width: PROFILE_WIDTH,
+            round: true,
+        };
+        for polygon in profile.children().filter(|n| n.is_element()) {
+            let path = read_path(polygon, context.scale);
+            stroke(&path, line.width, line.round, false, &mut outline);
+        }
+        if !outline.is_empty() {
+            layers.push(ImportedLayer {
+                name: "Profile".to_string(),
+                function: "Profile,NP".to_string(),
+                sublayers: outline.into_gerber_data(),
+                stackup_index: None,
+            });
+        }
+    }
+
+    if layers.is_empty() {
+        return Err("IPC-2581 file does not contain any layer features".to_string());
+    }
+    Ok(ImportedDesign { layers, stackup })
+}
+
+/// Read the features of one Set element into the layer artwork
+fn read_set(context: &Context, set: Node, clear: bool, artwork: &mut Artwork) {
+    for child in set.children().filter(|n| n.is_element()) {
+        match child.tag_name().name() {
+            "Pad" | "Features" => read_features(context, child, clear, artwork),
+            "Hole" => {
+                let x = attr(child, "x") * context.scale;
+                let y = attr(child, "y") * context.scale;
+                let radius = attr(child, "diameter") * context.scale / 2.0;
+                artwork.push(circle(x, y, radius), clear);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Read a Pad or Features element
+///
+/// A Location child makes it a flash of the referenced or inline primitive;
+/// otherwise its children are drawn as lines, arcs, polylines and contours.
+fn read_features(context: &Context, node: Node, clear: bool, artwork: &mut Artwork) {
+    if let Some(location) = children(node, "Location").next() {
+        let xform = children(node, "Xform").next();
+        let placement = Placement {
+            x: attr(location, "x") * context.scale,
+            y: attr(location, "y") * context.scale,
+            rotation: xform.map_or(0.0, |x| attr(x, "rotation").to_radians()),
+            mirror: xform.and_then(|x| x.attribute("mirror")) == Some("true"),
+            scale: xform
+                .and_then(|x| x.attribute("scale"))
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(1.0),
+        };
+        for shape in node.children().filter(|n| n.is_element()) {
+            match shape.tag_name().name() {
+                "Location" | "Xform" | "PinRef" => {}
+                "StandardPrimitiveRef" | "UserPrimitiveRef" => {
+                    let id = shape.attribute("id").unwrap_or("");
+                    if let Some((definition, scale)) = context.primitives.get(id) {
+                        flash(context, *definition, *scale, placement, clear, artwork);
+                    }
+                }
+                _ => flash(context, shape, context.scale, placement, clear, artwork),
+            }
+        }
+        return;
+    }
+
+    for feature in node.children().filter(|n| n.is_element()) {
+        draw_feature(context, feature, context.scale, None, clear, artwork);
+    }
+}
+
+/// Draw a line, arc, polyline or contour given in absolute coordinates
+/// (`placement` = None) or relative to a flash position
+fn draw_feature(
+    context: &Context,
+    feature: Node,
+    scale: f32,
+    placement: Option<Placement>,
+    clear: bool,
+    artwork: &mut Artwork,
+) {
+    let place = |p: [f32; 2]| placement.map_or(p, |pl| pl.apply(p));
+    let point = |x: &str, y: &str| place([attr(feature, x) * scale, attr(feature, y) * scale]);
+    match feature.tag_name().name() {
+        "Line" => {
+            let line = line_desc(context, feature);
+            let path = vec![point("startX", "startY"), point("endX", "endY")];
+            stroke(&path, line.width, line.round, clear, artwork);
+        }
+        "Arc" => {
+            let line = line_desc(context, feature);
+            let start = point("startX", "startY");
+            let end = point("endX", "endY");
+            let center = point("centerX", "centerY");
+            // Mirroring reverses the direction of the arc
+            let clockwise = (feature.attribute("clockwise") == Some("true"))
+                != placement.is_some_and(|p| p.mirror);
+            let path = arc_points(start, end, center, clockwise);
+            stroke(&path, line.width, line.round, clear, artwork);
+        }
+        "Polyline" => {
+            let line = line_desc(context, feature);
+            let path: Vec<[f32; 2]> = read_path(feature, scale).into_iter().map(place).collect();
+            stroke(&path, line.width, line.round, clear, artwork);
+        }
+        "Contour" => {
+            let mut contours = Vec::new();
+            for (i, polygon) in feature.children().filter(|n| n.is_element()).enumerate() {
+                let path: Vec<[f32; 2]> =
+                    read_path(polygon, scale).into_iter().map(place).collect();
+                // Outer boundary counterclockwise, cutouts clockwise
+                contours.push(oriented(path, i == 0));
+            }
+            if let Ok(triangles) = triangulate_shape_with_holes(&contours, 1.0) {
+                artwork.extend(triangles, clear);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Flash a standard or user primitive at a placement
+fn flash(
+    context: &Context,
+    shape: Node,
+    scale: f32,
+    placement: Placement,
+    clear: bool,
+    artwork: &mut Artwork,
+) {
+    let dimension = |name: &str| attr(shape, name) * scale * placement.scale;
+    let center = placement.apply([0.0, 0.0]);
+    match shape.tag_name().name() {
+        "Circle" => artwork.push(
+            circle(center[0], center[1], dimension("diameter") / 2.0),
+            clear,
+        ),
+        "Donut" if shape.attribute("shape") != Some("SQUARE") => {
+            let mut ring = circle(center[0], center[1], dimension("outerDiameter") / 2.0);
+            if let Primitive::Circle { hole_radius, .. } = &mut ring {
+                *hole_radius = dimension("innerDiameter") / 2.0;
+            }
+            artwork.push(ring, clear);
+        }
+        "Thermal" => artwork.push(
+            Primitive::Thermal {
+                x: center[0],
+                y: center[1],
+                outer_diameter: dimension("outerDiameter"),
+                inner_diameter: dimension("innerDiameter"),
+                gap_thickness: dimension("gap"),
+                rotation: attr(shape, "spokeStartAngle").to_radians() + placement.rotation,
+                exposure: 1.0,
+            },
+            clear,
+        ),
+        "Oval" => artwork.push(
+            Primitive::Obround {
+                x: center[0],
+                y: center[1],
+                width: dimension("width"),
+                height: dimension("height"),
+                rotation: placement.rotation,
+                exposure: 1.0,
+                hole_x: center[0],
+                hole_y: center[1],
+                hole_radius: 0.0,
+            },
+            clear,
+        ),
+        // User primitives group several shapes and features
+        "EntryUser" | "UserSpecial" => {
+            for child in shape.children().filter(|n| n.is_element()) {
+                flash(context, child, scale, placement, clear, artwork);
+            }
+        }
+        "Line" | "Arc" | "Polyline" | "Contour" => {
+            draw_feature(context, shape, scale, Some(placement), clear, artwork);
+        }
+        _ => {
+            if let Some(outline) = shape_outline(shape, scale) {
+                let points: Vec<[f32; 2]> =
+                    outline.into_iter().map(|p| placement.apply(p)).collect();
+                if let Ok(triangles) = triangulate_outline(&points, 1.0) {
+                    artwork.extend(triangles, clear);
+                }
+            }
+        }
+    }
+}
+
+/// Outline of a polygonal standard primitive around its own origin
+fn shape_outline(shape: Node, scale: f32) -> Option<Vec<[f32; 2]>> {
+    let dimension = |name: &str| attr(shape, name) * scale;
+    let outline = match shape.tag_name().name() {
+        "RectCenter" => rectangle(dimension("width"), dimension("height"), 0.0, false),
+        "RectRound" => rectangle(
+            dimension("width"),
+            dimension("height"),
+            dimension("radius"),
+            false,
+        ),
+        "RectCham" => rectangle(
+            dimension("width"),
+            dimension("height"),
+            dimension("chamfer"),
+            true,
+        ),
+        "Diamond" => {
+            let (w, h) = (dimension("width") / 2.0, dimension("height") / 2.0);
+            vec![[w, 0.0], [0.0, h], [-w, 0.0], [0.0, -h]]
+        }
+        "Ellipse" => {
+            let (w, h) = (dimension("width") / 2.0, dimension("height") / 2.0);
+            (0..CURVE_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 * 2.0 * PI / CURVE_SEGMENTS as f32;
+                    [w * angle.cos(), h * angle.sin()]
+                })
+                .collect()
+        }
+        "Hexagon" | "Octagon" => {
+            let sides = if shape.tag_name().name() == "Hexagon" {
+                6
+            } else {
+                8
+            };
+            let radius = dimension("length") / 2.0;
+            // Octagons have flat sides on the axes, hexagons points on the X axis
+            let offset = if sides == 8 { PI / 8.0 } else { 0.0 };
+            (0..sides)
+                .map(|i| {
+                    let angle = offset + i as f32 * 2.0 * PI / sides as f32;
+                    [radius * angle.cos(), radius * angle.sin()]
+                })
+                .collect()
+        }
+        "Triangle" => {
+            let (b, h) = (dimension("base") / 2.0, dimension("height") / 2.0);
+            vec![[-b, -h], [b, -h], [0.0, h]]
+        }
+        _ => return None,
+    };
+    Some(outline)
+}
+
+/// Points of a PolyBegin / PolyStepSegment / PolyStepCurve sequence
+fn read_path(node: Node, scale: f32) -> Vec<[f32; 2]> {
+    let mut path: Vec<[f32; 2]> = Vec::new();
+    for step in node.children().filter(|n| n.is_element()) {
+        let point = [attr(step, "x") * scale, attr(step, "y") * scale];
+        match step.tag_name().name() {
+            "PolyBegin" | "PolyStepSegment" => path.push(point),
+            "PolyStepCurve" => {
+                let start = path.last().copied().unwrap_or(point);
+                let center = [attr(step, "centerX") * scale, attr(step, "centerY") * scale];
+                let clockwise = step.attribute("clockwise") == Some("true");
+                path.extend(
+                    arc_points(start, point, center, clockwise)
+                        .into_iter()
+                        .skip(1),
+                );
+            }
+            _ => {}
+        }
+    }
+    path
+}
+
+/// Line description of a feature, inline or by reference
+fn line_desc(context: &Context, feature: Node) -> LineDesc {
+    for child in feature.children().filter(|n| n.is_element()) {
+        match child.tag_name().name() {
+            "LineDesc" => return parse_line_desc(child, context.scale),
+            "LineDescRef" => {
+                if let Some(line) = child
+                    .attribute("id")
+                    .and_then(|id| context.line_descs.get(id))
+                {
+                    return *line;
+                }
+            }
+            _ => {}
+        }
+    }
+    LineDesc {
+        width: 0.0,
+        round: true,
+    }
+}
+
+fn parse_line_desc(node: Node, scale: f32) -> LineDesc {
+    LineDesc {
+        width: attr(node, "lineWidth") * scale,
+        round: node.attribute("lineEnd").is_none_or(|end| end == "ROUND"),
+    }
+}
+
+/// Standard and user primitives by id, with their dictionary's unit scale
+fn read_primitives<'a, 'input>(
+    root: Node<'a, 'input>,
+    scale: f32,
+) -> HashMap<&'a str, (Node<'a, 'input>, f32)> {
+    let mut primitives = HashMap::new();
+    for dictionary in root
+        .descendants()
+        .filter(|n| matches!(n.tag_name().name(), "DictionaryStandard" | "DictionaryUser"))
+    {
+        let dictionary_scale = dictionary
+            .attribute("units")
+            .map_or(scale, |u| unit_scale(Some(u)));
+        for entry in dictionary.children().filter(|n| n.is_element()) {
+            let id = match entry.attribute("id") {
+                Some(id) => id,
+                None => continue,
+            };
+            let shape = if entry.tag_name().name() == "EntryUser" {
+                Some(entry)
+            } else {
+                entry.children().find(|n| n.is_element())
+            };
+            if let Some(shape) = shape {
+                primitives.insert(id, (shape, dictionary_scale));
+            }
+        }
+    }
+    primitives
+}
+
+/// Line descriptions by id
+fn read_line_descs<'a>(root: Node<'a, '_>, scale: f32) -> HashMap<&'a str, LineDesc> {
+    let mut line_descs = HashMap::new();
+    for dictionary in root
+        .descendants()
+        .filter(|n| n.tag_name().name() == "DictionaryLineDesc")
+    {
+        let dictionary_scale = dictionary
+            .attribute("units")
+            .map_or(scale, |u| unit_scale(Some(u)));
+        for entry in children(dictionary, "EntryLineDesc") {
+            let line = children(entry, "LineDesc").next();
+            if let (Some(id), Some(line)) = (entry.attribute("id"), line) {
+                line_descs.insert(id, parse_line_desc(line, dictionary_scale));
+            }
+        }
+    }
+    line_descs
+}
+
+/// Layer names and thicknesses (mm) of the stackup, top layer first
+fn read_stackup_order(cad_data: Node, scale: f32) -> Vec<(String, f32)> {
+    let stackup = match children(cad_data, "Stackup").next() {
+        Some(stackup) => stackup,
+        None => return Vec::new(),
+    };
+    let mut layers: Vec<(f32, String, f32)> = stackup
+        .descendants()
+        .filter(|n| n.tag_name().name() == "StackupLayer")
+        .enumerate()
+        .filter_map(|(i, layer)| {
+            let name = layer.attribute("layerOrGroupRef")?;
+            let sequence = layer
+                .attribute("sequence")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(i as f32);
+            Some((sequence, name.to_string(), attr(layer, "thickness") * scale))
+        })
+        .collect();
+    layers.sort_by(|a, b| a.0.total_cmp(&b.0));
+    layers
+        .into_iter()
+        .map(|(_, name, thickness)| (name, thickness))
+        .collect()
+}
+
+fn is_copper(layer_function: &str) -> bool {
+    matches!(
+        layer_function,
+        "CONDUCTOR" | "SIGNAL" | "PLANE" | "MIXED" | "CONDFILM" | "CONDFOIL"
+    )
+}
+
+/// .FileFunction value for an IPC-2581 layer function
+fn file_function(layer_function: &str, side: &str, name: &str, copper_order: &[&str]) -> String {
+    let side = match side {
+        "BOTTOM" => "Bot",
+        _ => "Top",
+    };
+    if is_copper(layer_function) {
+        let number = copper_order.iter().position(|n| *n == name).unwrap_or(0) + 1;
+        let position = if number == 1 {
+            "Top"
+        } else if number == copper_order.len() {
+            "Bot"
+        } else {
+            "Inr"
+        };
+        return format!("Copper,L{},{}", number, position);
+    }
+    match layer_function {
+        "SOLDERMASK" => format!("Soldermask,{}", side),
+        "SILKSCREEN" | "LEGEND" => format!("Legend,{}", side),
+        "SOLDERPASTE" | "PASTEMASK" => format!("Paste,{}", side),
+        "DRILL" => format!("Plated,1,{},PTH", copper_order.len().max(2)),
+        "ROUTE" | "BOARD_OUTLINE" => "Profile,NP".to_string(),
+        _ => format!("Other,{}", layer_function),
+    }
+}
+
+/// Stackup type name for an IPC-2581 layer function
+fn stackup_type(layer_function: &str) -> &'static str {
+    if is_copper(layer_function) {
+        return "Copper";
+    }
+    match layer_function {
+        "DIELCORE" => "Core",
+        "DIELPREG" | "DIELPREPREG" => "Prepreg",
+        "DIELBASE" | "DIELADHV" | "DIELCOVERLAY" => "Dielectric",
+        "SOLDERMASK" => "SolderMask",
+        "SILKSCREEN" | "LEGEND" => "Legend",
+        "SOLDERPASTE" | "PASTEMASK" => "SolderPaste",
+        _ => "Other",
+    }
+}
+
+/// Factor converting a units attribute value to mm
+fn unit_scale(units: Option<&str>) -> f32 {
+    match units {
+        Some("INCH") => 25.4,
+        Some("MICRON") => 0.001,
+        _ => 1.0,
+    }
+}
+
+/// First descendant element with the given name
+fn find<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.descendants().find(|n| n.tag_name().name() == name)
+}
+
+/// Child elements with the given name
+fn children<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children().filter(move |n| n.tag_name().name() == name)
+}
+
+/// Numeric attribute, 0 when missing or malformed
+fn attr(node: Node, name: &str) -> f32 {
+    node.attribute(name)
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0.0)
+}
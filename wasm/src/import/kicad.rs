@@ -0,0 +1,613 @@
+use super::{
+    arc_points, circle, oriented, rectangle, stroke, Artwork, ImportedDesign, ImportedLayer,
+};
+use crate::parser::geometry::{rotate_point, triangulate_outline, Primitive};
+use crate::stackup::Stackup;
+
+// Non-copper layers imported from the board, top side first
+const TOP_LAYERS: [(&str, &str); 3] = [
+    ("F.SilkS", "Legend,Top"),
+    ("F.Paste", "Paste,Top"),
+    ("F.Mask", "Soldermask,Top"),
+];
+const BOTTOM_LAYERS: [(&str, &str); 4] = [
+    ("B.Mask", "Soldermask,Bot"),
+    ("B.Paste", "Paste,Bot"),
+    ("B.SilkS", "Legend,Bot"),
+    ("Edge.Cuts", "Profile,NP"),
+];
+
+/// Node of a KiCad s-expression: an atom or a parenthesized list
+enum Sexp {
+    Atom(String),
+    List(Vec<Sexp>),
+}
+
+impl Sexp {
+    /// Keyword at the head of a list ("" for atoms)
+    fn name(&self) -> &str {
+        self.atom(0).unwrap_or("")
+    }
+
+    /// Item `index` of a list as text
+    fn atom(&self, index: usize) -> Option<&str> {
+        match self {
+            Sexp::List(items) => match items.get(index) {
+                Some(Sexp::Atom(text)) => Some(text),
+                _ => None,
+            },
+            Sexp::Atom(_) => None,
+        }
+    }
+
+    /// Item `index` of a list as a number, 0 when missing
+    fn number(&self, index: usize) -> f32 {
+        self.atom(index).and_then(|v| v.parse().ok()).unwrap_or(0.0)
+    }
+
+    fn items(&self) -> &[Sexp] {
+        match self {
+            Sexp::List(items) => items,
+            Sexp::Atom(_) => &[],
+        }
+    }
+
+    /// First child list with the given keyword
+    fn child(&self, name: &str) -> Option<&Sexp> {
+        self.items().iter().find(|item| item.name() == name)
+    }
+
+    /// Child lists with the given keyword
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Sexp> + 'a {
+        self.items().iter().filter(move |item| item.name() == name)
+    }
+
+    /// Point of a child such as (start x y), in board coordinates (Y up)
+    fn point(&self, name: &str) -> Option<[f32; 2]> {
+        self.child(name).map(|p| [p.number(1), -p.number(2)])
+    }
+}
+
+/// Placement of footprint or pad local coordinates on the board
+#[derive(Clone, Copy, Default)]
+struct Transform {
+    x: f32,
+    y: f32,
+    rotation: f32, // Radians, counterclockwise
+}
+
+impl Transform {
+    /// Placement from an (at x y [angle]) child
+    fn at(node: &Sexp) -> Transform {
+        node.child("at")
+            .map_or_else(Transform::default, |at| Transform {
+                x: at.number(1),
+                y: -at.number(2),
+                rotation: at.number(3).to_radians(),
+            })
+    }
+
+    fn apply(&self, mut point: [f32; 2]) -> [f32; 2] {
+        rotate_point(&mut point, self.rotation, 0.0, 0.0);
+        [self.x + point[0], self.y + point[1]]
+    }
+}
+
+/// Artwork of the imported layers, in board order
+struct Board {
+    layers: Vec<(String, String, Artwork)>, // Name, .FileFunction, artwork
+}
+
+impl Board {
+    /// Add primitives to every layer matched by `pattern` ("F.Cu", "*.Cu", "F&B.Cu")
+    fn add(&mut self, pattern: &str, primitives: &[Primitive]) {
+        for (name, _, artwork) in &mut self.layers {
+            if layer_matches(pattern, name) {
+                artwork.extend(primitives.to_vec(), false);
+            }
+        }
+    }
+}
+
+/// Parse a KiCad board file (.kicad_pcb, KiCad 6 or later)
+///
+/// Tracks, vias, pads, filled zones and board graphics are imported for the
+/// copper, silkscreen, mask and paste layers and the Edge.Cuts outline. Text
+/// is not imported. The stackup is read from the board setup when present.
+pub fn parse_kicad_pcb(text: &str) -> Result<ImportedDesign, String> {
+    let root = parse_sexp(text)?;
+    if root.name() != "kicad_pcb" {
+        return Err("Not a KiCad board file (missing kicad_pcb)".to_string());
+    }
+
+    // Copper layers sorted physically: F.Cu, In1.Cu, In2.Cu, ..., B.Cu
+    let mut copper: Vec<&str> = root
+        .child("layers")
+        .map(|layers| {
+            layers
+                .items()
+                .iter()
+                .filter_map(|layer| layer.atom(1))
+                .filter(|name| name.ends_with(".Cu"))
+                .collect()
+        })
+        .unwrap_or_default();
+    copper.sort_by_key(|name| copper_rank(name));
+
+    let mut board = Board { layers: Vec::new() };
+    for (name, function) in TOP_LAYERS {
+        board
+            .layers
+            .push((name.to_string(), function.to_string(), Artwork::default()));
+    }
+    for (index, name) in copper.iter().enumerate() {
+        let position = if index == 0 {
+            "Top"
+        } else if index + 1 == copper.len() {
+            "Bot"
+        } else {
+            "Inr"
+        };
+        let function = format!("Copper,L{},{}", index + 1, position);
+        board
+            .layers
+            .push((name.to_string(), function, Artwork::default()));
+    }
+    for (name, function) in BOTTOM_LAYERS {
+        board
+            .layers
+            .push((name.to_string(), function.to_string(), Artwork::default()));
+    }
+
+    for item in root.items() {
+        match item.name() {
+            "segment" => {
+                if let (Some(start), Some(end)) = (item.point("start"), item.point("end")) {
+                    let mut track = Artwork::default();
+                    stroke(
+                        &[start, end],
+                        item.child("width").map_or(0.0, |w| w.number(1)),
+                        true,
+                        false,
+                        &mut track,
+                    );
+                    add_artwork(&mut board, item, track);
+                }
+            }
+            "arc" => {
+                if let Some(path) = three_point_arc(item, &Transform::default()) {
+                    let mut track = Artwork::default();
+                    stroke(
+                        &path,
+                        item.child("width").map_or(0.0, |w| w.number(1)),
+                        true,
+                        false,
+                        &mut track,
+                    );
+                    add_artwork(&mut board, item, track);
+                }
+            }
+            "via" => add_via(&mut board, item, &copper),
+            "zone" => add_zone(&mut board, item),
+            "footprint" | "module" => add_footprint(&mut board, item),
+            name if name.starts_with("gr_") => {
+                let mut graphic = Artwork::default();
+                draw_graphic(item, &Transform::default(), &mut graphic);
+                add_artwork(&mut board, item, graphic);
+            }
+            _ => {}
+        }
+    }
+
+    let stackup = read_stackup(&root);
+    let layers: Vec<ImportedLayer> = board
+        .layers
+        .into_iter()
+        .filter(|(_, _, artwork)| !artwork.is_empty())
+        .map(|(name, function, artwork)| ImportedLayer {
+            stackup_index: stackup.layers.iter().position(|l| l.name == name),
+            name,
+            function,
+            sublayers: artwork.into_gerber_data(),
+        })
+        .collect();
+    if layers.is_empty() {
+        return Err("KiCad board does not contain any importable geometry".to_string());
+    }
+    Ok(ImportedDesign { layers, stackup })
+}
+
+/// Add artwork to the layer(s) named by the item's (layer) or (layers) child
+fn add_artwork(board: &mut Board, item: &Sexp, artwork: Artwork) {
+    let primitives: Vec<Primitive> = artwork.into_primitives();
+    if primitives.is_empty() {
+        return;
+    }
+    if let Some(layer) = item.child("layer").and_then(|l| l.atom(1)) {
+        board.add(layer, &primitives);
+    }
+    if let Some(layers) = item.child("layers") {
+        for index in 1..layers.items().len() {
+            if let Some(layer) = layers.atom(index) {
+                board.add(layer, &primitives);
+            }
+        }
+    }
+}
+
+/// Add a via to every copper layer between its two end layers
+fn add_via(board: &mut Board, via: &Sexp, copper: &[&str]) {
+    let center = match via.point("at") {
+        Some(center) => center,
+        None => return,
+    };
+    let mut ring = circle(
+        center[0],
+        center[1],
+        via.child("size").map_or(0.0, |s| s.number(1)) / 2.0,
+    );
+    if let Primitive::Circle { hole_radius, .. } = &mut ring {
+        *hole_radius = via.child("drill").map_or(0.0, |d| d.number(1)) / 2.0;
+    }
+
+    let ends = via.child("layers");
+    let rank = |index: usize| {
+        ends.and_then(|l| l.atom(index))
+            .and_then(|name| copper.iter().position(|c| *c == name))
+    };
+    let (first, last) = match (rank(1), rank(2)) {
+        (Some(a), Some(b)) => (a.min(b), a.max(b)),
+        _ => (0, copper.len().saturating_sub(1)),
+    };
+    for name in copper.iter().take(last + 1).skip(first) {
+        board.add(name, std::slice::from_ref(&ring));
+    }
+}
+
+/// Add the filled areas of a zone (as computed by KiCad's last zone fill)
+fn add_zone(board: &mut Board, zone: &Sexp) {
+    let zone_layer = zone.child("layer").and_then(|l| l.atom(1));
+    for fill in zone.children("filled_polygon") {
+        let layer = fill.child("layer").and_then(|l| l.atom(1)).or(zone_layer);
+        let points = read_points(fill, &Transform::default());
+        if let (Some(layer), Ok(triangles)) =
+            (layer, triangulate_outline(&oriented(points, true), 1.0))
+        {
+            board.add(layer, &triangles);
+        }
+    }
+}
+
+/// Add the pads and graphics of a footprint
+fn add_footprint(board: &mut Board, footprint: &Sexp) {
+    let placement = Transform::at(footprint);
+    for item in footprint.items() {
+        match item.name() {
+            "pad" => {
+                let primitives = pad_primitives(item, &placement);
+                if let Some(layers) = item.child("layers") {
+                    for index in 1..layers.items().len() {
+                        if let Some(layer) = layers.atom(index) {
+                            board.add(layer, &primitives);
+                        }
+                    }
+                }
+            }
+            name if name.starts_with("fp_") => {
+                let mut graphic = Artwork::default();
+                draw_graphic(item, &placement, &mut graphic);
+                add_artwork(board, item, graphic);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Primitives of a pad's copper shape
+///
+/// Pad angles in the file already include the footprint rotation.
+fn pad_primitives(pad: &Sexp, footprint: &Transform) -> Vec<Primitive> {
+    let at = pad.child("at");
+    let local = [
+        at.map_or(0.0, |a| a.number(1)),
+        -at.map_or(0.0, |a| a.number(2)),
+    ];
+    let center = footprint.apply(local);
+    let placement = Transform {
+        x: center[0],
+        y: center[1],
+        rotation: at.map_or(0.0, |a| a.number(3)).to_radians(),
+    };
+    let size = pad.child("size");
+    let (width, height) = (
+        size.map_or(0.0, |s| s.number(1)),
+        size.map_or(0.0, |s| s.number(2)),
+    );
+
+    let polygon = |outline: Vec<[f32; 2]>| {
+        let points: Vec<[f32; 2]> = outline.into_iter().map(|p| placement.apply(p)).collect();
+        triangulate_outline(&points, 1.0).unwrap_or_default()
+    };
+    let mut shape = pad.atom(3).unwrap_or("");
+    // Custom pads: the anchor shape plus their graphic primitives
+    let mut primitives = Vec::new();
+    if shape == "custom" {
+        shape = pad
+            .child("options")
+            .and_then(|o| o.child("anchor"))
+            .and_then(|a| a.atom(1))
+            .unwrap_or("circle");
+        if let Some(custom) = pad.child("primitives") {
+            let mut artwork = Artwork::default();
+            for graphic in custom.items() {
+                draw_graphic(graphic, &placement, &mut artwork);
+            }
+            primitives.extend(artwork.into_primitives());
+        }
+    }
+
+    match shape {
+        "circle" => {
+            let mut pad_circle = circle(center[0], center[1], width / 2.0);
+            // Round holes are shown through the pad
+            let drill = pad.child("drill");
+            if drill.is_some_and(|d| d.atom(1) != Some("oval")) {
+                if let Primitive::Circle { hole_radius, .. } = &mut pad_circle {
+                    *hole_radius = drill.map_or(0.0, |d| d.number(1)) / 2.0;
+                }
+            }
+            primitives.push(pad_circle);
+        }
+        "oval" => primitives.push(Primitive::Obround {
+            x: center[0],
+            y: center[1],
+            width,
+            height,
+            rotation: placement.rotation,
+            exposure: 1.0,
+            hole_x: center[0],
+            hole_y: center[1],
+            hole_radius: 0.0,
+        }),
+        "roundrect" => {
+            let ratio = pad.child("roundrect_rratio").map_or(0.25, |r| r.number(1));
+            primitives.extend(polygon(rectangle(
+                width,
+                height,
+                ratio * width.min(height),
+                false,
+            )));
+        }
+        // Rectangles; trapezoids are drawn as their bounding rectangle
+        _ => primitives.extend(polygon(rectangle(width, height, 0.0, false))),
+    }
+    primitives
+}
+
+/// Draw a gr_* or fp_* graphic (line, arc, circle, rect, poly)
+fn draw_graphic(graphic: &Sexp, placement: &Transform, artwork: &mut Artwork) {
+    let width = graphic
+        .child("stroke")
+        .and_then(|s| s.child("width"))
+        .or_else(|| graphic.child("width"))
+        .map_or(0.0, |w| w.number(1));
+    let filled = graphic
+        .child("fill")
+        .and_then(|f| f.atom(1))
+        .is_some_and(|f| f == "solid" || f == "yes");
+    let point = |name: &str| graphic.point(name).map(|p| placement.apply(p));
+    let kind = graphic
+        .name()
+        .trim_start_matches("gr_")
+        .trim_start_matches("fp_");
+
+    let outline: Vec<[f32; 2]> = match kind {
+        "line" => {
+            if let (Some(start), Some(end)) = (point("start"), point("end")) {
+                stroke(&[start, end], width, true, false, artwork);
+            }
+            return;
+        }
+        "arc" => {
+            if let Some(path) = three_point_arc(graphic, placement) {
+                stroke(&path, width, true, false, artwork);
+            }
+            return;
+        }
+        "circle" => match (point("center"), point("end")) {
+            (Some(center), Some(end)) => arc_points(end, end, center, false),
+            _ => return,
+        },
+        "rect" => match (graphic.point("start"), graphic.point("end")) {
+            (Some(a), Some(b)) => [[a[0], a[1]], [b[0], a[1]], [b[0], b[1]], [a[0], b[1]]]
+                .into_iter()
+                .map(|p| placement.apply(p))
+                .collect(),
+            _ => return,
+        },
+        "poly" => graphic
+            .child("pts")
+            .map(|pts| read_points(pts, placement))
+            .unwrap_or_default(),
+        _ => return,
+    };
+    if outline.len() < 3 {
+        return;
+    }
+
+    if filled {
+        if let Ok(triangles) = triangulate_outline(&oriented(outline.clone(), true), 1.0) {
+            artwork.extend(triangles, false);
+        }
+    }
+    let mut closed = outline;
+    closed.push(closed[0]);
+    stroke(&closed, width, true, false, artwork);
+}
+
+/// Points of an arc given by (start) (mid) (end) children
+fn three_point_arc(arc: &Sexp, placement: &Transform) -> Option<Vec<[f32; 2]>> {
+    let start = placement.apply(arc.point("start")?);
+    let mid = placement.apply(arc.point("mid")?);
+    let end = placement.apply(arc.point("end")?);
+
+    // Circumcenter of the three points
+    let d = 2.0
+        * (start[0] * (mid[1] - end[1])
+            + mid[0] * (end[1] - start[1])
+            + end[0] * (start[1] - mid[1]));
+    if d.abs() < f32::EPSILON {
+        return Some(vec![start, end]);
+    }
+    let square = |p: [f32; 2]| p[0] * p[0] + p[1] * p[1];
+    let center = [
+        (square(start) * (mid[1] - end[1])
+            + square(mid) * (end[1] - start[1])
+            + square(end) * (start[1] - mid[1]))
+            / d,
+        (square(start) * (end[0] - mid[0])
+            + square(mid) * (start[0] - end[0])
+            + square(end) * (mid[0] - start[0]))
+            / d,
+    ];
+    // The arc turns clockwise when the midpoint lies right of start -> end
+    let turn =
+        (mid[0] - start[0]) * (end[1] - start[1]) - (mid[1] - start[1]) * (end[0] - start[0]);
+    Some(arc_points(start, end, center, turn > 0.0))
+}
+
+/// Points of a (pts (xy x y) ...) list; (arc ...) entries are flattened
+fn read_points(pts: &Sexp, placement: &Transform) -> Vec<[f32; 2]> {
+    let list = pts.child("pts").unwrap_or(pts);
+    let mut points = Vec::new();
+    for item in list.items() {
+        match item.name() {
+            "xy" => points.push(placement.apply([item.number(1), -item.number(2)])),
+            "arc" => {
+                if let Some(path) = three_point_arc(item, placement) {
+                    points.extend(path);
+                }
+            }
+            _ => {}
+        }
+    }
+    points
+}
+
+/// Board stackup from (setup (stackup ...)), top layer first
+fn read_stackup(root: &Sexp) -> Stackup {
+    let mut stackup = Stackup::default();
+    let layers = root.child("setup").and_then(|s| s.child("stackup"));
+    for layer in layers.iter().flat_map(|l| l.children("layer")) {
+        let name = layer.atom(1).unwrap_or("");
+        let kind = layer
+            .child("type")
+            .and_then(|t| t.atom(1))
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        let type_name = if kind.contains("silk") {
+            "Legend"
+        } else if kind.contains("paste") {
+            "SolderPaste"
+        } else if kind.contains("mask") {
+            "SolderMask"
+        } else if kind == "copper" {
+            "Copper"
+        } else if kind == "core" {
+            "Core"
+        } else if kind == "prepreg" {
+            "Prepreg"
+        } else {
+            "Other"
+        };
+        let thickness = layer.child("thickness").map_or(0.0, |t| t.number(1));
+        let material = layer
+            .child("material")
+            .and_then(|m| m.atom(1))
+            .unwrap_or("");
+        stackup.add_layer(type_name, thickness, material, name);
+    }
+    stackup
+}
+
+/// Whether a pad or via layer pattern includes a board layer
+fn layer_matches(pattern: &str, layer: &str) -> bool {
+    if pattern == layer {
+        return true;
+    }
+    // "*.Cu" and "F&B.Cu" style wildcards
+    let (sides, suffix) = match pattern.split_once('.') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let (side, layer_suffix) = match layer.split_once('.') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    suffix == layer_suffix && (sides == "*" || (sides == "F&B" && (side == "F" || side == "B")))
+}
+
+/// Physical position of a copper layer: F.Cu, In1.Cu ... InN.Cu, B.Cu
+fn copper_rank(name: &str) -> u32 {
+    match name {
+        "F.Cu" => 0,
+        "B.Cu" => u32::MAX,
+        _ => name
+            .trim_start_matches("In")
+            .trim_end_matches(".Cu")
+            .parse()
+            .unwrap_or(u32::MAX - 1),
+    }
+}
+
+/// Parse s-expression text into its top-level list
+fn parse_sexp(text: &str) -> Result<Sexp, String> {
+    let mut stack: Vec<Vec<Sexp>> = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '(' => stack.push(Vec::new()),
+            ')' => {
+                let list = stack.pop().ok_or("Unbalanced ')' in KiCad file")?;
+                match stack.last_mut() {
+                    Some(parent) => parent.push(Sexp::List(list)),
+                    None => return Ok(Sexp::List(list)),
+                }
+            }
+            '"' => {
+                let mut value = String::new();
+                let mut escaped = false;
+                for (_, c) in chars.by_ref() {
+                    match (escaped, c) {
+                        (false, '\\') => escaped = true,
+                        (false, '"') => break,
+                        _ => {
+                            value.push(c);
+                            escaped = false;
+                        }
+                    }
+                }
+                stack
+                    .last_mut()
+                    .ok_or("Text outside of a list in KiCad file")?
+                    .push(Sexp::Atom(value));
+            }
+            c if c.is_whitespace() => {}
+            _ => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                stack
+                    .last_mut()
+                    .ok_or("Text outside of a list in KiCad file")?
+                    .push(Sexp::Atom(text[start..end].to_string()));
+            }
+        }
+    }
+    Err("Unexpected end of KiCad file".to_string())
+}
@@ -0,0 +1,242 @@
+use crate::shape::Boundary;
+use crate::view::CameraView;
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{AddEventListenerOptions, Event, HtmlCanvasElement, PointerEvent, WheelEvent};
+
+// Zoom change per square root of wheel delta, as in the demo page
+const WHEEL_ZOOM_RATE: f32 = 0.02;
+// Zoom limits so the camera never degenerates
+const MIN_ZOOM: f32 = 1e-6;
+const MAX_ZOOM: f32 = 1e6;
+
+// Event name and the closure registered for it
+type Listener = (&'static str, Closure<dyn FnMut(Event)>);
+
+/// Camera and gesture state shared with the event listeners
+struct InputState {
+    canvas: HtmlCanvasElement,
+    camera: CameraView,
+    pointers: Vec<(i32, f32, f32)>, // Pressed pointers: id, client x, client y
+    fit_bounds: Boundary,           // Board area shown on double-click
+    on_change: Option<js_sys::Function>,
+}
+
+impl InputState {
+    /// Canvas point in camera offset units (shorter side spans -1..1) from client pixels
+    fn to_view(&self, client_x: f32, client_y: f32) -> (f32, f32) {
+        let rect = self.canvas.get_bounding_client_rect();
+        let width = (rect.width() as f32).max(1.0);
+        let height = (rect.height() as f32).max(1.0);
+        let x = (client_x - rect.left() as f32) / width * 2.0 - 1.0;
+        let y = 1.0 - (client_y - rect.top() as f32) / height * 2.0;
+        let aspect = self.canvas.width().max(1) as f32 / self.canvas.height().max(1) as f32;
+        if aspect > 1.0 {
+            (x * aspect, y)
+        } else {
+            (x, y / aspect)
+        }
+    }
+
+    /// Scale the zoom by `factor`, keeping the board point under `(x, y)` in place
+    fn zoom_at(&mut self, factor: f32, (x, y): (f32, f32)) {
+        let zoom = (self.camera.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let ratio = zoom / self.camera.zoom;
+        self.camera = CameraView::new(
+            zoom,
+            (self.camera.offset_x - x) * ratio + x,
+            (self.camera.offset_y - y) * ratio + y,
+        );
+    }
+
+    fn pan(&mut self, (dx, dy): (f32, f32)) {
+        self.camera.offset_x += dx;
+        self.camera.offset_y += dy;
+    }
+
+    fn pointer_down(&mut self, event: &PointerEvent) {
+        // Primary or middle button pans; right-click stays with the page
+        if event.pointer_type() == "mouse" && event.button() > 1 {
+            return;
+        }
+        let _ = self.canvas.set_pointer_capture(event.pointer_id());
+        self.pointers.retain(|p| p.0 != event.pointer_id());
+        self.pointers.push((
+            event.pointer_id(),
+            event.client_x() as f32,
+            event.client_y() as f32,
+        ));
+    }
+
+    /// Pan with one pointer, pinch-zoom and pan with two
+    fn pointer_move(&mut self, event: &PointerEvent) -> bool {
+        let Some(index) = self.pointers.iter().position(|p| p.0 == event.pointer_id()) else {
+            return false;
+        };
+        let before: Vec<(f32, f32)> = self.pointers.iter().map(|p| (p.1, p.2)).collect();
+        self.pointers[index].1 = event.client_x() as f32;
+        self.pointers[index].2 = event.client_y() as f32;
+        let after: Vec<(f32, f32)> = self.pointers.iter().map(|p| (p.1, p.2)).collect();
+
+        if after.len() == 1 {
+            let from = self.to_view(before[0].0, before[0].1);
+            let to = self.to_view(after[0].0, after[0].1);
+            self.pan((to.0 - from.0, to.1 - from.1));
+            return true;
+        }
+        if after.len() == 2 {
+            let (old_mid, old_distance) = self.pinch(&before);
+            let (new_mid, new_distance) = self.pinch(&after);
+            self.pan((new_mid.0 - old_mid.0, new_mid.1 - old_mid.1));
+            if old_distance > 0.0 {
+                self.zoom_at(new_distance / old_distance, new_mid);
+            }
+            return true;
+        }
+        false
+    }
+
+    /// Midpoint (view units) and distance (pixels) of the first two pointers
+    fn pinch(&self, points: &[(f32, f32)]) -> ((f32, f32), f32) {
+        let (a, b) = (points[0], points[1]);
+        let mid = self.to_view((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0);
+        (mid, (a.0 - b.0).hypot(a.1 - b.1))
+    }
+
+    fn pointer_up(&mut self, event: &PointerEvent) {
+        self.pointers.retain(|p| p.0 != event.pointer_id());
+    }
+
+    fn wheel(&mut self, event: &WheelEvent) {
+        event.prevent_default();
+        let delta = event.delta_y() as f32;
+        if delta == 0.0 {
+            return;
+        }
+        let factor = 1.0 - delta.signum() * delta.abs().sqrt() * WHEEL_ZOOM_RATE;
+        let cursor = self.to_view(event.client_x() as f32, event.client_y() as f32);
+        self.zoom_at(factor.max(0.1), cursor);
+    }
+
+    fn fit(&mut self) {
+        self.camera = CameraView::fit(&self.fit_bounds, self.canvas.width(), self.canvas.height());
+    }
+}
+
+/// Pan, zoom and fit gestures handled on a canvas
+///
+/// Dragging pans, the wheel zooms at the cursor, two fingers pinch-zoom and
+/// a double-click fits the board. The listeners are removed on drop.
+pub struct InputHandler {
+    state: Rc<RefCell<InputState>>,
+    listeners: Vec<Listener>,
+}
+
+impl InputHandler {
+    /// Register the listeners on `canvas`, starting from `camera`
+    ///
+    /// `on_change` is called without arguments after every camera change.
+    pub fn attach(
+        canvas: HtmlCanvasElement,
+        camera: CameraView,
+        fit_bounds: Boundary,
+        on_change: Option<js_sys::Function>,
+    ) -> Result<InputHandler, JsValue> {
+        // Keep the browser from scrolling or zooming the page on touch
+        canvas.style().set_property("touch-action", "none")?;
+
+        let state = Rc::new(RefCell::new(InputState {
+            canvas: canvas.clone(),
+            camera,
+            pointers: Vec::new(),
+            fit_bounds,
+            on_change,
+        }));
+        let mut handler = InputHandler {
+            state,
+            listeners: Vec::new(),
+        };
+
+        handler.listen("pointerdown", |state, event| {
+            state.pointer_down(event.unchecked_ref());
+            false
+        })?;
+        handler.listen("pointermove", |state, event| {
+            state.pointer_move(event.unchecked_ref())
+        })?;
+        for name in ["pointerup", "pointercancel"] {
+            handler.listen(name, |state, event| {
+                state.pointer_up(event.unchecked_ref());
+                false
+            })?;
+        }
+        handler.listen("wheel", |state, event| {
+            state.wheel(event.unchecked_ref());
+            true
+        })?;
+        handler.listen("dblclick", |state, _| {
+            state.fit();
+            true
+        })?;
+        Ok(handler)
+    }
+
+    /// Register one listener; `handle` returns whether the camera changed
+    fn listen(
+        &mut self,
+        name: &'static str,
+        handle: impl Fn(&mut InputState, &Event) -> bool + 'static,
+    ) -> Result<(), JsValue> {
+        let state = self.state.clone();
+        let closure = Closure::<dyn FnMut(Event)>::new(move |event: Event| {
+            let changed = handle(&mut state.borrow_mut(), &event);
+            // The host re-renders from on_change, which reads the camera again
+            let on_change = state.borrow().on_change.clone();
+            if let (true, Some(on_change)) = (changed, on_change) {
+                let _ = on_change.call0(&JsValue::NULL);
+            }
+        });
+
+        // Wheel listeners must not be passive to prevent page scrolling
+        let options = AddEventListenerOptions::new();
+        options.set_passive(false);
+        self.state
+            .borrow()
+            .canvas
+            .add_event_listener_with_callback_and_add_event_listener_options(
+                name,
+                closure.as_ref().unchecked_ref(),
+                &options,
+            )?;
+        self.listeners.push((name, closure));
+        Ok(())
+    }
+
+    /// Camera after the latest gestures
+    pub fn camera(&self) -> CameraView {
+        self.state.borrow().camera
+    }
+
+    /// Continue gestures from a camera set elsewhere, e.g. an animation
+    pub fn set_camera(&self, camera: CameraView) {
+        self.state.borrow_mut().camera = camera;
+    }
+
+    /// Board area a double-click fits into the canvas
+    pub fn set_fit_bounds(&self, bounds: Boundary) {
+        self.state.borrow_mut().fit_bounds = bounds;
+    }
+}
+
+impl Drop for InputHandler {
+    fn drop(&mut self) {
+        let state = self.state.borrow();
+        for (name, closure) in &self.listeners {
+            let _ = state
+                .canvas
+                .remove_event_listener_with_callback(name, closure.as_ref().unchecked_ref());
+        }
+    }
+}
@@ -0,0 +1,264 @@
+use serde::Deserialize;
+use wasm_bindgen::prelude::*;
+
+// Display colors (RGB) per file function
+const COLOR_COPPER_TOP: [f32; 3] = [0.85, 0.25, 0.2];
+const COLOR_COPPER_BOTTOM: [f32; 3] = [0.2, 0.4, 0.9];
+const COLOR_COPPER_INNER: [[f32; 3]; 4] = [
+    [0.9, 0.6, 0.1],
+    [0.6, 0.3, 0.8],
+    [0.2, 0.7, 0.7],
+    [0.7, 0.7, 0.2],
+];
+const COLOR_SOLDER_MASK: [f32; 3] = [0.1, 0.55, 0.25];
+const COLOR_LEGEND: [f32; 3] = [0.9, 0.9, 0.9];
+const COLOR_PASTE: [f32; 3] = [0.6, 0.6, 0.65];
+const COLOR_PROFILE: [f32; 3] = [0.95, 0.85, 0.2];
+const COLOR_DRILL: [f32; 3] = [0.75, 0.75, 0.75];
+const COLOR_OTHER: [f32; 3] = [0.5, 0.5, 0.5];
+
+/// Top level of a Gerber job file (.gbrjob)
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JobFile {
+    #[serde(default)]
+    general_specs: GeneralSpecs,
+    #[serde(default)]
+    files_attributes: Vec<FileAttributes>,
+    #[serde(default)]
+    material_stackup: Vec<StackupEntry>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "PascalCase")]
+struct GeneralSpecs {
+    size: Option<Size>,
+    layer_number: Option<u32>,
+    board_thickness: Option<f32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Size {
+    x: f32,
+    y: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct FileAttributes {
+    path: String,
+    file_function: String,
+    #[serde(default)]
+    file_polarity: String,
+}
+
+/// One layer of the material stackup, listed from top to bottom
+#[derive(Deserialize, Clone)]
+#[serde(rename_all = "PascalCase")]
+pub struct StackupEntry {
+    #[serde(rename = "Type")]
+    pub kind: String, // Copper, Dielectric, SolderMask, Legend, SolderPaste, ...
+    #[serde(default)]
+    pub thickness: f32, // mm
+    #[serde(default)]
+    pub material: String,
+    #[serde(default)]
+    pub name: String,
+}
+
+/// A fabrication file listed in the job, with its suggested display settings
+struct JobLayer {
+    path: String,
+    function: String,
+    polarity: String,
+    rank: u32, // Position in the physical stack, top side first
+    color: [f32; 3],
+}
+
+/// Parsed Gerber job file: board size, stackup and file functions
+#[wasm_bindgen]
+pub struct GerberJob {
+    pub(crate) board_width: f32,
+    pub(crate) board_height: f32,
+    pub(crate) board_thickness: f32,
+    pub(crate) layer_count: u32,
+    files: Vec<JobLayer>, // Sorted top to bottom
+    pub(crate) stackup: Vec<StackupEntry>,
+}
+
+#[wasm_bindgen]
+impl GerberJob {
+    /// Board outline width in mm (0 if not specified)
+    #[wasm_bindgen(getter)]
+    pub fn board_width(&self) -> f32 {
+        self.board_width
+    }
+
+    /// Board outline height in mm (0 if not specified)
+    #[wasm_bindgen(getter)]
+    pub fn board_height(&self) -> f32 {
+        self.board_height
+    }
+
+    /// Finished board thickness in mm (0 if not specified)
+    #[wasm_bindgen(getter)]
+    pub fn board_thickness(&self) -> f32 {
+        self.board_thickness
+    }
+
+    /// Number of copper layers
+    #[wasm_bindgen(getter)]
+    pub fn layer_count(&self) -> u32 {
+        self.layer_count
+    }
+
+    /// Number of files listed in the job, sorted from the top side down
+    #[wasm_bindgen(getter)]
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+
+    /// File name of the file at `index`, as referenced by the job
+    pub fn file_path(&self, index: usize) -> Option<String> {
+        self.files.get(index).map(|f| f.path.clone())
+    }
+
+    /// .FileFunction attribute of the file at `index` (e.g. "Copper,L1,Top")
+    pub fn file_function(&self, index: usize) -> Option<String> {
+        self.files.get(index).map(|f| f.function.clone())
+    }
+
+    /// .FilePolarity attribute of the file at `index` ("Positive" or "Negative")
+    pub fn file_polarity(&self, index: usize) -> Option<String> {
+        self.files.get(index).map(|f| f.polarity.clone())
+    }
+
+    /// Suggested RGB display color of the file at `index`
+    pub fn file_color(&self, index: usize) -> Option<Vec<f32>> {
+        self.files.get(index).map(|f| f.color.to_vec())
+    }
+
+    /// Index of the file whose job path matches `name`
+    ///
+    /// Directories are ignored and the comparison is case-insensitive, so
+    /// uploaded file names match paths like "gerbers/Board-F_Cu.gbr".
+    pub fn find_file(&self, name: &str) -> Option<usize> {
+        let name = base_name(name).to_lowercase();
+        self.files
+            .iter()
+            .position(|f| base_name(&f.path).to_lowercase() == name)
+    }
+
+    /// Number of entries in the material stackup
+    #[wasm_bindgen(getter)]
+    pub fn stackup_count(&self) -> usize {
+        self.stackup.len()
+    }
+
+    /// Type of the stackup entry at `index` (Copper, Dielectric, SolderMask, ...)
+    pub fn stackup_type(&self, index: usize) -> Option<String> {
+        self.stackup.get(index).map(|s| s.kind.clone())
+    }
+
+    /// Thickness of the stackup entry at `index` in mm
+    pub fn stackup_thickness(&self, index: usize) -> Option<f32> {
+        self.stackup.get(index).map(|s| s.thickness)
+    }
+
+    /// Material of the stackup entry at `index` (e.g. "FR4")
+    pub fn stackup_material(&self, index: usize) -> Option<String> {
+        self.stackup.get(index).map(|s| s.material.clone())
+    }
+
+    /// Name of the stackup entry at `index`
+    pub fn stackup_name(&self, index: usize) -> Option<String> {
+        self.stackup.get(index).map(|s| s.name.clone())
+    }
+}
+
+/// Parse a Gerber job file (.gbrjob, JSON)
+pub fn parse_job(json: &str) -> Result<GerberJob, String> {
+    let job: JobFile =
+        serde_json::from_str(json).map_err(|e| format!("Invalid Gerber job file: {}", e))?;
+
+    let specs = job.general_specs;
+    let copper_files = job
+        .files_attributes
+        .iter()
+        .filter(|f| f.file_function.to_ascii_lowercase().starts_with("copper"))
+        .count() as u32;
+    let layer_count = specs.layer_number.unwrap_or(copper_files);
+
+    let mut files: Vec<JobLayer> = job
+        .files_attributes
+        .into_iter()
+        .map(|f| {
+            let (rank, color) = classify(&f.file_function, layer_count);
+            JobLayer {
+                path: f.path,
+                function: f.file_function,
+                polarity: f.file_polarity,
+                rank,
+                color,
+            }
+        })
+        .collect();
+    // Stable sort keeps the job's own order for files of the same function
+    files.sort_by_key(|f| f.rank);
+
+    let (board_width, board_height) = specs.size.map_or((0.0, 0.0), |s| (s.x, s.y));
+    Ok(GerberJob {
+        board_width,
+        board_height,
+        board_thickness: specs.board_thickness.unwrap_or(0.0),
+        layer_count,
+        files,
+        stackup: job.material_stackup,
+    })
+}
+
+/// Stack position and display color for a .FileFunction value
+///
+/// Ranks run from the top legend down through the copper layers to the bottom
+/// legend; outline and drill files come last.
+fn classify(function: &str, layer_count: u32) -> (u32, [f32; 3]) {
+    let fields: Vec<&str> = function.split(',').map(str::trim).collect();
+    let side = fields
+        .iter()
+        .find(|f| **f == "Top" || **f == "Bot")
+        .copied();
+    let copper_top = 10;
+    let copper_bottom = copper_top + layer_count.max(1);
+    // Mirror a top-side rank around the copper block
+    let sided = |top_rank: u32| match side {
+        Some("Bot") => copper_bottom + (copper_top - top_rank),
+        _ => top_rank,
+    };
+
+    match fields[0].to_ascii_lowercase().as_str() {
+        "copper" => {
+            let number = fields
+                .get(1)
+                .and_then(|f| f.strip_prefix('L'))
+                .and_then(|n| n.parse::<u32>().ok())
+                .unwrap_or(1);
+            let color = match side {
+                Some("Top") => COLOR_COPPER_TOP,
+                Some("Bot") => COLOR_COPPER_BOTTOM,
+                _ => COLOR_COPPER_INNER[(number as usize) % COLOR_COPPER_INNER.len()],
+            };
+            (copper_top + number - 1, color)
+        }
+        "legend" => (sided(1), COLOR_LEGEND),
+        "paste" => (sided(2), COLOR_PASTE),
+        "soldermask" => (sided(3), COLOR_SOLDER_MASK),
+        "profile" => (copper_bottom + 20, COLOR_PROFILE),
+        "plated" | "nonplated" => (copper_bottom + 21, COLOR_DRILL),
+        _ => (copper_bottom + 30, COLOR_OTHER),
+    }
+}
+
+fn base_name(path: &str) -> &str {
+    path.rsplit(['/', '\\']).next().unwrap_or(path)
+}
@@ -0,0 +1,139 @@
+use crate::parser::{LintFinding, Polarity};
+use wasm_bindgen::prelude::*;
+
+/// Coordinate unit of a Gerber file
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Unit {
+    Millimeter,
+    Inch,
+}
+
+impl Unit {
+    /// Unit from its API name, "mm" or "inch"
+    pub fn from_name(name: &str) -> Option<Unit> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "mm" => Some(Unit::Millimeter),
+            "in" | "inch" => Some(Unit::Inch),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Unit::Millimeter => "mm",
+            Unit::Inch => "inch",
+        }
+    }
+
+    /// Millimeters per unit
+    pub fn multiplier(self) -> f32 {
+        match self {
+            Unit::Millimeter => 1.0,
+            Unit::Inch => 25.4,
+        }
+    }
+}
+
+/// Where the unit of a layer came from
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnitSource {
+    Default, // No %MO (or G70/G71) in the file, millimeters assumed
+    File,    // Declared by the file
+    Forced,  // Chosen when loading, file declarations ignored
+}
+
+impl UnitSource {
+    pub fn name(self) -> &'static str {
+        match self {
+            UnitSource::Default => "default",
+            UnitSource::File => "file",
+            UnitSource::Forced => "forced",
+        }
+    }
+}
+
+/// Unit, coordinate format, image polarity and file function a layer was
+/// parsed with
+///
+/// A layer that looks 25.4 times too small or too large usually has no
+/// %MO command; reload it with add_layer_with_units().
+#[wasm_bindgen]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayerInfo {
+    pub(crate) unit: Unit,
+    pub(crate) unit_source: UnitSource,
+    pub(crate) format: [u32; 4], // %FS integer and decimal digits of X, then of Y
+    pub(crate) format_declared: bool,
+    pub(crate) file_function: String, // %TF.FileFunction value, empty when not given
+    pub(crate) image_polarity: Polarity,
+    pub(crate) lint: Vec<LintFinding>, // Deprecated constructs of the file
+}
+
+impl Default for LayerInfo {
+    fn default() -> Self {
+        LayerInfo {
+            unit: Unit::Millimeter,
+            unit_source: UnitSource::Default,
+            format: [2, 4, 2, 4],
+            format_declared: false,
+            file_function: String::new(),
+            image_polarity: Polarity::Positive,
+            lint: Vec::new(),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl LayerInfo {
+    /// Coordinate unit: "mm" or "inch"
+    #[wasm_bindgen(getter)]
+    pub fn unit(&self) -> String {
+        self.unit.name().to_string()
+    }
+
+    /// "file" when the file declared its unit, "default" when millimeters
+    /// were assumed, "forced" when the unit was chosen at load time
+    #[wasm_bindgen(getter)]
+    pub fn unit_source(&self) -> String {
+        self.unit_source.name().to_string()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x_integer_digits(&self) -> u32 {
+        self.format[0]
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn x_decimal_digits(&self) -> u32 {
+        self.format[1]
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y_integer_digits(&self) -> u32 {
+        self.format[2]
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn y_decimal_digits(&self) -> u32 {
+        self.format[3]
+    }
+
+    /// Whether the file has a %FS command; otherwise the format is the 2.4 default
+    #[wasm_bindgen(getter)]
+    pub fn format_declared(&self) -> bool {
+        self.format_declared
+    }
+
+    /// .FileFunction attribute of the file (e.g. "Component,L1,Top"), empty
+    /// when the file has none
+    #[wasm_bindgen(getter)]
+    pub fn file_function(&self) -> String {
+        self.file_function.clone()
+    }
+
+    /// Whether the file sets %IPNEG, so the layer renders inverted
+    #[wasm_bindgen(getter)]
+    pub fn image_negative(&self) -> bool {
+        self.image_polarity == Polarity::Negative
+    }
+}
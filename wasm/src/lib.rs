@@ -1,11 +1,84 @@
+mod analysis;
+mod cache;
+mod error;
+mod events;
+mod export;
+mod extrude;
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+#[cfg(feature = "headless")]
+pub mod headless;
+mod import;
+#[cfg(feature = "input")]
+mod input;
+mod job;
+mod layer_info;
+mod options;
 mod parser;
+#[cfg(feature = "renderer")]
 mod renderer;
+#[cfg(feature = "webgpu")]
+mod renderer_webgpu;
+mod session;
 mod shape;
+pub mod simd;
+mod stackup;
+mod store;
+mod tessellation;
+mod view;
 
-use crate::parser::parse_gerber;
+use crate::analysis::{
+    check_annular_ring, check_mask_silk, check_width_spacing, collect_flashes, collect_traces,
+    compare_layers, connectivity, copper_density, cross_section, drill_summary, estimate_alignment,
+    exposed_copper, extract_contours, find_fiducials, find_outliers, find_test_points,
+    infer_drills, size_histogram, smallest_features, AnnularRingReport, BoundaryOutliers,
+    Connectivity, Contours, CopperDensity, CrossSection, DrillSummary, ExposedCopperReport,
+    FiducialList, FlashList, InferredDrills, LayerAlignment, LayerComparison, MaskSilkReport,
+    SizeHistogram, SmallestFeatures, TestPointList, TraceList, WidthSpacingReport,
+};
+#[cfg(feature = "renderer")]
+use crate::analysis::{courtyard_lines, ComponentSide, DrcMarkers};
+use crate::cache::{decode_layer, encode_layer};
+pub use crate::error::ViewerError;
+#[cfg(feature = "renderer")]
+use crate::events::performance_now;
+use crate::events::{EventCallback, EventEmitter};
+use crate::export::{
+    combine_layers, offset_layer, panelize, write_geojson, write_gerber, write_gltf, write_pdf,
+    GltfOptions, LayerOperation, PanelLayout, PdfLayer,
+};
+#[cfg(feature = "renderer")]
+use crate::extrude::extrude_stackup;
+use crate::import::{parse_ipc2581, parse_kicad_pcb, ImportedBoard, ImportedDesign};
+#[cfg(feature = "input")]
+use crate::input::InputHandler;
+use crate::job::{parse_job, GerberJob};
+use crate::layer_info::{LayerInfo, Unit};
+#[cfg(feature = "renderer")]
+use crate::options::PrimitiveFilter;
+use crate::options::{ParseOptions, RenderOptions};
+use crate::parser::{
+    decode_gerber_bytes, ApertureCache, GerberParser, LintReport, ProgressiveParse, Replay,
+};
+#[cfg(feature = "renderer")]
 use crate::renderer::Renderer;
-use crate::shape::Boundary;
+#[cfg(feature = "webgpu")]
+use crate::renderer_webgpu::{GpuCanvasContext, GpuDevice, WebGpuRenderer};
+use crate::session::{content_hash, SessionLayer, SessionState, ViewState};
+pub use crate::shape::Boundary;
+use crate::shape::GerberData;
+#[cfg(feature = "renderer")]
+use crate::stackup::StackupKind;
+use crate::stackup::{LayerArtwork, Stackup};
+use crate::store::LayerStore;
+use crate::view::CameraView;
+#[cfg(feature = "renderer")]
+use crate::view::{CameraAnimation, CameraState};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
 use wasm_bindgen::prelude::*;
+#[cfg(feature = "renderer")]
 use web_sys::WebGl2RenderingContext;
 
 /// Initialize panic hook for better error messages in browser console
@@ -15,13 +88,73 @@ pub fn init_panic_hook() {
     console_error_panic_hook::set_once();
 }
 
+/// Parse a Gerber job file (.gbrjob) accompanying X2 fabrication outputs
+///
+/// # Arguments
+/// * `json` - Job file content as string
+///
+/// # Returns
+/// * GerberJob with board size, stackup and the files sorted top to bottom,
+///   each with a suggested display color
+#[wasm_bindgen]
+pub fn parse_gerber_job(json: String) -> Result<GerberJob, JsValue> {
+    parse_job(&json).map_err(|e| ViewerError::parse_failed(e).into())
+}
+
+/// Parse Gerber text into transferable geometry bytes
+///
+/// Runs without a GerberProcessor, so it can be called from a Web Worker.
+/// Post the bytes' buffer to the main thread as a transferable and load them
+/// with GerberProcessor.add_layer_from_bytes(), keeping long parses off the
+/// UI thread.
+///
+/// # Arguments
+/// * `content` - Gerber file content as string
+///
+/// # Returns
+/// * Geometry bytes (Uint8Array) in the serialize_layer() cache format
+#[wasm_bindgen]
+pub fn parse_to_bytes(content: String) -> Result<Vec<u8>, JsValue> {
+    let mut parser = GerberParser::new();
+    let gerber_data_layers = parser.parse(&content)?;
+    let hash = content_hash(content.as_bytes());
+    Ok(encode_layer(
+        &gerber_data_layers,
+        &hash,
+        &parser.layer_info(),
+    ))
+}
+
 /// Main Gerber processor with stateful WebGL renderer
+///
+/// Instances share no state: each has its own WebGL context, layers, caches,
+/// settings and event callback, so several viewers can run on one page.
 #[wasm_bindgen]
 #[derive(Default)]
 pub struct GerberProcessor {
+    layers: LayerStore, // Parsed geometry by layer ID
+    #[cfg(feature = "renderer")]
     gl: Option<WebGl2RenderingContext>,
+    #[cfg(feature = "renderer")]
     renderer: Option<Renderer>,
-    next_layer_id: u32, // Layer ID generator
+    #[cfg(feature = "webgpu")]
+    webgpu: Option<WebGpuRenderer>, // Replaces the WebGL renderer after init_webgpu()
+    next_layer_id: u32,                      // Layer ID generator
+    layer_hashes: HashMap<u32, String>,      // Content hash per layer for session restore
+    loading: HashMap<u32, ProgressiveParse>, // Layers still being parsed progressively
+    replays: HashMap<u32, Replay>,           // Command lists of layers from add_layer_replay()
+    // Apertures shared across layers
+    aperture_cache: Option<Rc<RefCell<ApertureCache>>>,
+    parse_options: ParseOptions,   // Quality settings for new layers
+    render_options: RenderOptions, // Quality settings of the renderer
+    events: EventEmitter,          // Host event callback
+    view: Option<ViewState>,       // Settings of the last render() call
+    #[cfg(feature = "renderer")]
+    animation: Option<CameraAnimation>, // Camera tween applied by render() (None = host camera)
+    #[cfg(feature = "input")]
+    input: Option<InputHandler>, // Built-in canvas gestures (None = host handles input)
+    annotations: serde_json::Value, // Page annotations saved with the session
+    robust_boundary: bool,         // Leave outlier primitives out of get_boundary()
 }
 
 #[wasm_bindgen]
@@ -34,55 +167,417 @@ impl GerberProcessor {
 
     /// Initialize with WebGL 2.0 context
     ///
+    /// The context may come from an OffscreenCanvas, so rendering can run
+    /// entirely inside a worker. Set the OffscreenCanvas size there and call
+    /// resize() when the displayed canvas changes size.
+    ///
     /// # Arguments
-    /// * `gl` - WebGL 2.0 rendering context from canvas
+    /// * `gl` - WebGL 2.0 rendering context from a canvas or OffscreenCanvas
     ///
     /// # Returns
     /// * `"init_done"` signal on success
+    #[cfg(feature = "renderer")]
     pub fn init(&mut self, gl: WebGl2RenderingContext) -> Result<String, JsValue> {
-        // Create renderer with WebGL context (initially no layers)
-        self.renderer = Some(Renderer::new(gl.clone())?);
+        // Create renderer with WebGL context and GPU resources for loaded layers
+        let mut renderer = Renderer::new(gl.clone())?;
+        renderer.set_options(&self.render_options)?;
+        for (layer_id, _) in self.layers.iter() {
+            renderer.add_layer(layer_id)?;
+        }
+        if let Some(previous) = self.renderer.replace(renderer) {
+            previous.dispose();
+        }
+        #[cfg(feature = "webgpu")]
+        if let Some(webgpu) = self.webgpu.take() {
+            webgpu.dispose();
+        }
         self.gl = Some(gl);
+        self.events.context_restored();
         Ok("init_done".to_string())
     }
 
+    /// Initialize with a WebGPU device instead of WebGL
+    ///
+    /// Layers are drawn and composited the same way as with init(). Only
+    /// render(), the level of detail setting and resizing use WebGPU; the
+    /// overlays, comparison, 3D view and render_region_to_image() need a
+    /// WebGL context from init(). Request the adapter and device in JS, as
+    /// that is asynchronous.
+    ///
+    /// # Arguments
+    /// * `device` - GPUDevice from adapter.requestDevice()
+    /// * `context` - GPUCanvasContext from canvas.getContext("webgpu")
+    /// * `format` - Canvas format from navigator.gpu.getPreferredCanvasFormat()
+    ///
+    /// # Returns
+    /// * `"init_webgpu_done"` signal on success
+    #[cfg(feature = "webgpu")]
+    pub fn init_webgpu(
+        &mut self,
+        device: GpuDevice,
+        context: GpuCanvasContext,
+        format: String,
+    ) -> Result<String, JsValue> {
+        let mut webgpu = WebGpuRenderer::new(device, context, &format)?;
+        webgpu.set_min_feature_pixels(self.render_options.min_feature_pixels);
+        if let Some(previous) = self.webgpu.replace(webgpu) {
+            previous.dispose();
+        }
+        if let Some(renderer) = self.renderer.take() {
+            renderer.dispose();
+        }
+        self.gl = None;
+        Ok("init_webgpu_done".to_string())
+    }
+
+    /// Set a function that receives viewer events
+    ///
+    /// The function is called with one object per event, its `type` being:
+    /// * `parse_progress` - `layer_id`, `parsed_bytes`, `total_bytes` (progressive loading)
+    /// * `parse_complete` - `layer_id`, `duration_ms`, `diagnostics` (primitive counts,
+    ///   `sublayers`, `geometry_bytes`, `bounds` as [min_x, max_x, min_y, max_y],
+    ///   `warnings` such as a missing M02 or an aperture definition that could not be read)
+    /// * `render_complete` - `layers`, `duration_ms` (CPU time to issue the frame)
+    /// * `context_lost` - The WebGL context was lost; call init() with a new one
+    /// * `memory_warning` - `geometry_bytes`, `threshold_bytes` (stored geometry is large)
+    ///
+    /// # Arguments
+    /// * `callback` - Event handler function, or null to stop sending events
+    ///
+    /// # Returns
+    /// * `"event_callback_done"` signal
+    pub fn set_event_callback(&mut self, callback: Option<EventCallback>) -> String {
+        self.events.set_callback(callback);
+        "event_callback_done".to_string()
+    }
+
     /// Add a new layer to the renderer
     ///
+    /// A file identical to an already loaded layer is not added again; the
+    /// existing layer's ID is returned instead (see find_duplicate_layer()).
+    ///
     /// # Arguments
     /// * `content` - Gerber file content as string
     ///
     /// # Returns
     /// * Layer ID (u32) for tracking this layer
     pub fn add_layer(&mut self, content: String) -> Result<u32, JsValue> {
-        // Parse Gerber content to get Vec<GerberData> (one per polarity layer)
-        let gerber_data_layers = parse_gerber(&content)?;
+        self.add_layer_text(&content, None, None)
+    }
+
+    /// Add a new layer with simplified region outlines
+    ///
+    /// Region (G36/G37) contours are reduced with Douglas-Peucker before
+    /// triangulation, so large copper pours load and render faster at the
+    /// cost of outline deviations up to `tolerance`. Other shapes are exact.
+    /// Like add_layer(), an already loaded identical file is not added again.
+    ///
+    /// # Arguments
+    /// * `content` - Gerber file content as string
+    /// * `tolerance` - Maximum outline deviation in mm (0 = no simplification)
+    ///
+    /// # Returns
+    /// * Layer ID (u32) for tracking this layer
+    pub fn add_layer_simplified(
+        &mut self,
+        content: String,
+        tolerance: f32,
+    ) -> Result<u32, JsValue> {
+        if !tolerance.is_finite() || tolerance < 0.0 {
+            return Err(
+                ViewerError::invalid_argument("Tolerance must be a non-negative number").into(),
+            );
+        }
+        self.add_layer_text(&content, Some(tolerance), None)
+    }
+
+    /// Add a new layer read in the given unit
+    ///
+    /// For files without a %MO command that load 25.4 times too small or
+    /// too large; %MO and G70/G71 in the file are ignored. To correct a
+    /// loaded layer, remove it first: like add_layer(), an already loaded
+    /// identical file is returned as is.
+    ///
+    /// # Arguments
+    /// * `content` - Gerber file content as string
+    /// * `force_unit` - `"mm"` or `"inch"`
+    ///
+    /// # Returns
+    /// * Layer ID (u32) for tracking this layer
+    pub fn add_layer_with_units(
+        &mut self,
+        content: String,
+        force_unit: String,
+    ) -> Result<u32, JsValue> {
+        let unit = Unit::from_name(&force_unit).ok_or_else(|| {
+            ViewerError::invalid_argument(format!(
+                "Unknown unit '{}' (expected \"mm\" or \"inch\")",
+                force_unit
+            ))
+        })?;
+        self.add_layer_text(&content, None, Some(unit))
+    }
+
+    /// Add a new layer from raw file bytes
+    ///
+    /// Avoids decoding the file into a JS string first. UTF-8 (with or without
+    /// BOM), UTF-16 with BOM and Latin-1 content is detected automatically.
+    ///
+    /// # Arguments
+    /// * `data` - Gerber file content as bytes (Uint8Array)
+    ///
+    /// # Returns
+    /// * Layer ID (u32) for tracking this layer
+    pub fn add_layer_bytes(&mut self, data: &[u8]) -> Result<u32, JsValue> {
+        let content = decode_gerber_bytes(data);
+        self.add_layer_text(&content, None, None)
+    }
+
+    /// Serialize a parsed layer into a compact binary geometry cache
+    ///
+    /// Store the bytes (e.g. in IndexedDB) and pass them to
+    /// add_layer_from_cache() on the next load to skip parsing.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    ///
+    /// # Returns
+    /// * Cache bytes (Uint8Array)
+    pub fn serialize_layer(&self, layer_id: u32) -> Result<Vec<u8>, JsValue> {
+        let data = self.layers.get(layer_id as usize)?;
+        let hash = self.layer_hashes.get(&layer_id).map_or("", String::as_str);
+        let info = self.layers.info(layer_id as usize)?;
+        Ok(encode_layer(data, hash, info))
+    }
+
+    /// Add a layer from a geometry cache created by serialize_layer()
+    ///
+    /// # Arguments
+    /// * `bytes` - Cache bytes (Uint8Array)
+    ///
+    /// # Returns
+    /// * Layer ID (u32) for tracking this layer
+    pub fn add_layer_from_cache(&mut self, bytes: &[u8]) -> Result<u32, JsValue> {
+        let (gerber_data_layers, hash, info) =
+            decode_layer(bytes).map_err(ViewerError::parse_failed)?;
+        self.insert_layer(gerber_data_layers, hash, info)
+    }
+
+    /// Add a layer parsed in a worker by parse_to_bytes()
+    ///
+    /// # Arguments
+    /// * `bytes` - Geometry bytes (Uint8Array)
+    ///
+    /// # Returns
+    /// * Layer ID (u32) for tracking this layer
+    pub fn add_layer_from_bytes(&mut self, bytes: &[u8]) -> Result<u32, JsValue> {
+        self.add_layer_from_cache(bytes)
+    }
+
+    /// Start adding a layer that is parsed a chunk at a time
+    ///
+    /// The layer is created empty; call continue_layer() repeatedly (e.g. once
+    /// per animation frame) to parse it. Each call updates the layer with the
+    /// geometry parsed so far, so the board appears while it loads.
+    ///
+    /// # Arguments
+    /// * `content` - Gerber file content as string
+    ///
+    /// # Returns
+    /// * Layer ID (u32) for tracking this layer
+    pub fn add_layer_progressive(&mut self, content: String) -> Result<u32, JsValue> {
+        let hash = content_hash(content.as_bytes());
+        if let Some(layer_id) = self.layer_with_hash(&hash) {
+            return Ok(layer_id);
+        }
+        let layer_index = self.layers.add(Vec::new());
+        #[cfg(feature = "renderer")]
+        if let Some(renderer) = &mut self.renderer {
+            if let Err(e) = renderer.add_layer(layer_index) {
+                let _ = self.layers.remove(layer_index);
+                return Err(e);
+            }
+        }
+        self.next_layer_id += 1;
+        self.layer_hashes.insert(layer_index as u32, hash);
+        self.loading.insert(
+            layer_index as u32,
+            ProgressiveParse::new(content, self.parser()),
+        );
+        self.events.parse_started(layer_index as u32);
+        Ok(layer_index as u32)
+    }
+
+    /// Parse the next chunk of a layer started with add_layer_progressive()
+    ///
+    /// The layer is removed if parsing fails or the file has no geometry.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer_progressive()
+    /// * `max_lines` - Number of lines to parse in this call
+    ///
+    /// # Returns
+    /// * `true` when the layer is completely loaded
+    pub fn continue_layer(&mut self, layer_id: u32, max_lines: u32) -> Result<bool, JsValue> {
+        let parse = self.loading.get_mut(&layer_id).ok_or_else(|| {
+            ViewerError::invalid_layer(format!("Layer {} is not loading", layer_id), layer_id)
+        })?;
+
+        let mut warnings = Vec::new();
+        let result = match parse.step(max_lines.max(1) as usize) {
+            Ok(false) => {
+                let (parsed_bytes, total_bytes) = parse.progress();
+                self.events
+                    .parse_progress(layer_id, parsed_bytes, total_bytes);
+                parse
+                    .snapshot()
+                    .map(|layers| (layers, false, parse.layer_info()))
+            }
+            Ok(true) => {
+                let mut parse = self.loading.remove(&layer_id).unwrap();
+                let finished = parse.finish();
+                warnings = parse.warnings().to_vec();
+                finished.map(|layers| (layers, true, parse.layer_info()))
+            }
+            Err(e) => Err(e),
+        };
+        let (gerber_data_layers, done, info) = match result {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.remove_layer(layer_id)?;
+                return Err(e.with_layer(layer_id).into());
+            }
+        };
 
         // Filter out empty layers (layers with no geometry)
         let non_empty_layers: Vec<_> = gerber_data_layers
             .into_iter()
             .filter(|layer| layer.has_geometry())
             .collect();
-
-        // If no non-empty layers found, reject the file as invalid Gerber
-        if non_empty_layers.is_empty() {
-            return Err(JsValue::from_str(
+        if done && non_empty_layers.is_empty() {
+            self.remove_layer(layer_id)?;
+            return Err(ViewerError::parse_failed(
                 "File does not contain valid Gerber data (no geometry found)",
-            ));
+            )
+            .with_layer(layer_id)
+            .into());
         }
 
-        // Add to renderer
+        self.layers.replace(layer_id as usize, non_empty_layers)?;
+        self.layers.set_info(layer_id as usize, info)?;
+        #[cfg(feature = "renderer")]
         if let Some(renderer) = &mut self.renderer {
-            let layer_index = renderer.add_layer(non_empty_layers)?;
-            self.next_layer_id += 1;
+            renderer.reset_layer_buffers(layer_id as usize)?;
+        }
+        #[cfg(feature = "webgpu")]
+        if let Some(webgpu) = &mut self.webgpu {
+            webgpu.reset_layer_buffers(layer_id as usize);
+        }
+        if done {
+            let sublayers = self.layers.get(layer_id as usize)?;
+            self.events
+                .parse_complete(layer_id, sublayers, &warnings, None);
+            self.events.check_memory(self.layers.byte_size());
+            #[cfg(feature = "renderer")]
+            self.fit_first_layer(layer_id)?;
+        }
+        Ok(done)
+    }
 
-            // For now, layer_id matches layer_index
-            // In a more complex implementation, we could maintain a mapping
-            Ok(layer_index as u32)
-        } else {
-            Err(JsValue::from_str(
-                "Renderer not initialized. Call init() first.",
-            ))
+    /// Add a layer that can be stepped through command by command
+    ///
+    /// The layer is loaded complete like with add_layer() and keeps its
+    /// command list, so set_replay_position() can show the image after any
+    /// number of commands, e.g. to teach Gerber or find the command that
+    /// breaks a file.
+    ///
+    /// # Arguments
+    /// * `content` - Gerber file content as string
+    ///
+    /// # Returns
+    /// * Layer ID (u32) for tracking this layer
+    pub fn add_layer_replay(&mut self, content: String) -> Result<u32, JsValue> {
+        let replay = Replay::new(content.clone(), self.parser())?;
+        let layer_id = self.add_layer(content)?;
+        self.replays.insert(layer_id, replay);
+        Ok(layer_id)
+    }
+
+    /// Get the commands of a layer added with add_layer_replay()
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer_replay()
+    ///
+    /// # Returns
+    /// * Command text in file order, a whole multi-line % command per entry
+    pub fn get_replay_commands(&self, layer_id: u32) -> Result<Vec<String>, JsValue> {
+        Ok(self.replay(layer_id)?.commands())
+    }
+
+    /// Show a replay layer as drawn by its first `count` commands
+    ///
+    /// Regions appear once their G37 is reached. A count of at least the
+    /// number of commands shows the complete layer again.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer_replay()
+    /// * `count` - Number of commands to apply
+    ///
+    /// # Returns
+    /// * `"replay_position_done"` signal on success
+    pub fn set_replay_position(&mut self, layer_id: u32, count: u32) -> Result<String, JsValue> {
+        let (sublayers, info) = self
+            .replay(layer_id)?
+            .parse_first(count as usize, self.parser())
+            .map_err(|e| e.with_layer(layer_id))?;
+        let sublayers: Vec<_> = sublayers
+            .into_iter()
+            .filter(|layer| layer.has_geometry())
+            .collect();
+
+        self.layers.replace(layer_id as usize, sublayers)?;
+        self.layers.set_info(layer_id as usize, info)?;
+        #[cfg(feature = "renderer")]
+        if let Some(renderer) = &mut self.renderer {
+            renderer.reset_layer_buffers(layer_id as usize)?;
+        }
+        #[cfg(feature = "webgpu")]
+        if let Some(webgpu) = &mut self.webgpu {
+            webgpu.reset_layer_buffers(layer_id as usize);
         }
+        Ok("replay_position_done".to_string())
+    }
+
+    /// Load all layers of an IPC-2581 file
+    ///
+    /// Each layer with features becomes a separate layer, plus a "Profile"
+    /// layer for the board outline. The returned stackup is linked to the
+    /// loaded layers.
+    ///
+    /// # Arguments
+    /// * `content` - IPC-2581 XML content as string
+    ///
+    /// # Returns
+    /// * ImportedBoard with the layer IDs, names and functions, top side first
+    pub fn add_ipc2581(&mut self, content: String) -> Result<ImportedBoard, JsValue> {
+        let design = parse_ipc2581(&content).map_err(ViewerError::parse_failed)?;
+        self.insert_design(design, &content)
+    }
+
+    /// Load the copper, silkscreen, mask, paste and outline layers of a KiCad board
+    ///
+    /// Experimental: reads tracks, vias, pads, filled zones and graphics from a
+    /// .kicad_pcb file (KiCad 6 or later) without plotting Gerbers first. Text
+    /// is not imported, and zones show KiCad's last fill.
+    ///
+    /// # Arguments
+    /// * `content` - .kicad_pcb file content as string
+    ///
+    /// # Returns
+    /// * ImportedBoard with the layer IDs, names and functions, top side first
+    pub fn add_kicad_pcb(&mut self, content: String) -> Result<ImportedBoard, JsValue> {
+        let design = parse_kicad_pcb(&content).map_err(ViewerError::parse_failed)?;
+        self.insert_design(design, &content)
     }
 
     /// Remove a layer from the renderer
@@ -93,14 +588,22 @@ impl GerberProcessor {
     /// # Returns
     /// * `"remove_done"` signal on success
     pub fn remove_layer(&mut self, layer_id: u32) -> Result<String, JsValue> {
+        self.layers.remove(layer_id as usize)?;
+        #[cfg(feature = "renderer")]
         if let Some(renderer) = &mut self.renderer {
             renderer.remove_layer(layer_id as usize)?;
-            Ok("remove_done".to_string())
-        } else {
-            Err(JsValue::from_str(
-                "Renderer not initialized. Call init() first.",
-            ))
         }
+        #[cfg(feature = "webgpu")]
+        if let Some(webgpu) = &mut self.webgpu {
+            webgpu.remove_layer(layer_id as usize);
+        }
+        self.layer_hashes.remove(&layer_id);
+        self.replays.remove(&layer_id);
+        if self.loading.remove(&layer_id).is_some() {
+            self.events.parse_cancelled(layer_id);
+        }
+        self.events.check_memory(self.layers.byte_size());
+        Ok("remove_done".to_string())
     }
 
     /// Clear all layers
@@ -108,15 +611,55 @@ impl GerberProcessor {
     /// # Returns
     /// * `"clear_done"` signal on success
     pub fn clear(&mut self) -> Result<String, JsValue> {
+        self.layers.clear();
+        #[cfg(feature = "renderer")]
         if let Some(renderer) = &mut self.renderer {
             renderer.clear_all();
-            self.next_layer_id = 0;
-            Ok("clear_done".to_string())
-        } else {
-            Err(JsValue::from_str(
-                "Renderer not initialized. Call init() first.",
-            ))
         }
+        #[cfg(feature = "webgpu")]
+        if let Some(webgpu) = &mut self.webgpu {
+            webgpu.clear_all();
+        }
+        self.next_layer_id = 0;
+        self.layer_hashes.clear();
+        self.replays.clear();
+        for layer_id in std::mem::take(&mut self.loading).into_keys() {
+            self.events.parse_cancelled(layer_id);
+        }
+        self.events.check_memory(0);
+        if self.aperture_cache.is_some() {
+            self.aperture_cache = Some(Rc::default());
+        }
+        self.view = None;
+        #[cfg(feature = "renderer")]
+        {
+            self.animation = None;
+        }
+        self.annotations = serde_json::Value::Null;
+        Ok("clear_done".to_string())
+    }
+
+    /// Release all resources held by the processor
+    ///
+    /// Deletes the layers, the WebGL programs, buffers and framebuffers, the
+    /// shared aperture cache and the event callback, and resets all settings.
+    /// Call it before free() when tearing down a viewer so GPU memory is
+    /// released right away instead of when the context is garbage collected.
+    /// The processor can be reused by calling init() again.
+    ///
+    /// # Returns
+    /// * `"dispose_done"` signal on success
+    pub fn dispose(&mut self) -> Result<String, JsValue> {
+        #[cfg(feature = "renderer")]
+        if let Some(renderer) = self.renderer.take() {
+            renderer.dispose();
+        }
+        #[cfg(feature = "webgpu")]
+        if let Some(webgpu) = self.webgpu.take() {
+            webgpu.dispose();
+        }
+        *self = GerberProcessor::default();
+        Ok("dispose_done".to_string())
     }
 
     /// DEPRECATED: Use add_layer() instead
@@ -133,8 +676,202 @@ impl GerberProcessor {
         Ok("parse_done".to_string())
     }
 
+    /// Export a layer as a Gerber file
+    ///
+    /// The file is written from the parsed geometry: areas become regions,
+    /// strokes and flashes are written with equivalent apertures.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    ///
+    /// # Returns
+    /// * Gerber file content (mm, 4.6 format)
+    pub fn export_gerber(&self, layer_id: u32) -> Result<String, JsValue> {
+        Ok(write_gerber(self.layers.get(layer_id as usize)?))
+    }
+
+    /// Export a layer's primitives as GeoJSON
+    ///
+    /// Each primitive becomes a polygon feature (thermals a multipolygon) with
+    /// its polarity, sublayer and primitive kind as properties. Coordinates are
+    /// in mm and curves are flattened with the parse options' chord error.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    ///
+    /// # Returns
+    /// * GeoJSON FeatureCollection as a string
+    pub fn export_geojson(&self, layer_id: u32) -> Result<String, JsValue> {
+        Ok(write_geojson(
+            layer_id,
+            self.layers.get(layer_id as usize)?,
+            self.parse_options.chord_error,
+        ))
+    }
+
+    /// Export the board as an extruded 3D model (binary glTF)
+    ///
+    /// Each stackup layer becomes one node at its height in the stack: the
+    /// board body spans the outline extent, linked copper, legend and paste are
+    /// extruded from their artwork and the solder mask keeps its openings.
+    ///
+    /// # Arguments
+    /// * `options` - Stackup and outline settings (GltfOptions)
+    ///
+    /// # Returns
+    /// * GLB file bytes (Uint8Array), e.g. for <model-viewer>
+    pub fn export_gltf(&self, options: &GltfOptions) -> Result<Vec<u8>, JsValue> {
+        let (artwork, board) =
+            Self::stackup_artwork(&self.layers, &options.stackup, options.outline_layer_id)?;
+        write_gltf(
+            &options.stackup,
+            &artwork,
+            &board,
+            self.parse_options.chord_error,
+        )
+        .map_err(|e| ViewerError::invalid_argument(e).into())
+    }
+
+    /// Export layers as a vector PDF check plot
+    ///
+    /// Layers are drawn in the given order with the colors of the last
+    /// render() (black for layers that were not visible). Regions and flashes
+    /// are written as filled paths with true arcs, within the parse options'
+    /// chord error on paper; clear polarity is painted white.
+    ///
+    /// # Arguments
+    /// * `layer_ids` - Layers to plot, bottom first
+    /// * `scale` - Plot scale (1.0 = 1:1, page enlarged to fit), or 0 to fit the board on A4
+    ///
+    /// # Returns
+    /// * PDF file bytes (Uint8Array)
+    pub fn export_pdf(&self, layer_ids: Vec<u32>, scale: f32) -> Result<Vec<u8>, JsValue> {
+        let layers = layer_ids
+            .iter()
+            .map(|&id| {
+                Ok(PdfLayer {
+                    sublayers: self.layers.get(id as usize)?,
+                    color: self.layer_color(id),
+                })
+            })
+            .collect::<Result<Vec<_>, ViewerError>>()?;
+        Ok(write_pdf(&layers, scale, self.parse_options.chord_error))
+    }
+
+    /// Step-repeat layers into a panel and add the panel as new layers
+    ///
+    /// All layers are repeated on the pitch of their combined boundary plus the
+    /// gaps, so a copper, mask and outline set stays aligned. Use
+    /// export_gerber() on the returned layers to save the panel.
+    ///
+    /// # Arguments
+    /// * `layer_ids` - Layers to panelize
+    /// * `nx` - Number of board copies along X
+    /// * `ny` - Number of board copies along Y
+    /// * `gap_x` - Gap between copies along X in mm
+    /// * `gap_y` - Gap between copies along Y in mm
+    /// * `add_rails` - Add top and bottom rails with three fiducials
+    ///
+    /// # Returns
+    /// * New layer IDs, one per input layer in the same order
+    pub fn panelize(
+        &mut self,
+        layer_ids: Vec<u32>,
+        nx: u32,
+        ny: u32,
+        gap_x: f32,
+        gap_y: f32,
+        add_rails: bool,
+    ) -> Result<Vec<u32>, JsValue> {
+        let layers = layer_ids
+            .iter()
+            .map(|&id| self.layers.get(id as usize))
+            .collect::<Result<Vec<_>, _>>()?;
+        let layout = PanelLayout {
+            nx,
+            ny,
+            gap_x,
+            gap_y,
+            add_rails,
+        };
+        let files = panelize(&layers, &layout).map_err(ViewerError::invalid_argument)?;
+
+        files
+            .into_iter()
+            .map(|content| self.add_layer(content))
+            .collect()
+    }
+
+    /// Grow or shrink a layer by a fixed distance and add the result as a new layer
+    ///
+    /// The layer's final image (clear polarity applied) is offset outward for
+    /// positive and inward for negative distances, e.g. -0.05 to shrink paste
+    /// stencil apertures. Features narrower than twice a negative offset
+    /// disappear. Use export_gerber() on the returned layer to save it.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    /// * `delta_mm` - Signed offset in mm
+    ///
+    /// # Returns
+    /// * Layer ID of the offset layer
+    pub fn offset_layer(&mut self, layer_id: u32, delta_mm: f32) -> Result<u32, JsValue> {
+        let content = offset_layer(
+            self.layers.get(layer_id as usize)?,
+            delta_mm,
+            self.parse_options.chord_error,
+        )
+        .map_err(ViewerError::invalid_argument)?;
+        self.add_layer(content)
+    }
+
+    /// Add the union of two layers as a new layer
+    ///
+    /// # Arguments
+    /// * `a` - First layer ID
+    /// * `b` - Second layer ID
+    ///
+    /// # Returns
+    /// * Layer ID of the combined layer
+    pub fn union_layers(&mut self, a: u32, b: u32) -> Result<u32, JsValue> {
+        self.add_combined_layer(a, b, LayerOperation::Union)
+    }
+
+    /// Add a layer minus another as a new layer
+    ///
+    /// E.g. a copper layer minus a keep-out layer.
+    ///
+    /// # Arguments
+    /// * `a` - Layer ID to subtract from
+    /// * `b` - Layer ID to subtract
+    ///
+    /// # Returns
+    /// * Layer ID of the combined layer
+    pub fn subtract_layers(&mut self, a: u32, b: u32) -> Result<u32, JsValue> {
+        self.add_combined_layer(a, b, LayerOperation::Subtract)
+    }
+
+    /// Add the overlap of two layers as a new layer
+    ///
+    /// E.g. copper intersected with solder mask openings gives the exposed
+    /// copper.
+    ///
+    /// # Arguments
+    /// * `a` - First layer ID
+    /// * `b` - Second layer ID
+    ///
+    /// # Returns
+    /// * Layer ID of the combined layer
+    pub fn intersect_layers(&mut self, a: u32, b: u32) -> Result<u32, JsValue> {
+        self.add_combined_layer(a, b, LayerOperation::Intersect)
+    }
+
     /// Render geometry to FBOs and composite to canvas
     ///
+    /// While a camera animation from animate_to() runs, its current view
+    /// replaces the zoom and offsets passed in; so does the gesture camera
+    /// after attach_input(). Read the camera drawn back with camera_view().
+    ///
     /// # Arguments
     /// * `active_layer_ids` - Array of layer IDs to render (in order)
     /// * `color_data` - Flat array of [r, g, b] for each active layer (NO alpha)
@@ -146,6 +883,7 @@ impl GerberProcessor {
     ///
     /// # Returns
     /// * `"render_done"` signal on success
+    #[cfg(feature = "renderer")]
     #[allow(clippy::too_many_arguments)]
     pub fn render(
         &mut self,
@@ -157,8 +895,46 @@ impl GerberProcessor {
         offset_y: f32,
         alpha: f32,
     ) -> Result<String, JsValue> {
-        if let Some(renderer) = &mut self.renderer {
+        // Advance the animation first so it ends even when drawing fails
+        let camera = self.animation_frame();
+        #[cfg(feature = "input")]
+        let camera = camera.or_else(|| self.input.as_ref().map(InputHandler::camera));
+        let (zoom_x, zoom_y, offset_x, offset_y) = match camera {
+            Some(view) => (view.zoom, view.zoom, view.offset_x, view.offset_y),
+            None => (zoom_x, zoom_y, offset_x, offset_y),
+        };
+        if self.gl.as_ref().is_some_and(|gl| gl.is_context_lost()) {
+            self.events.context_lost();
+            return Err(
+                ViewerError::webgl("WebGL context lost. Call init() with a new context.").into(),
+            );
+        }
+        let started = self.events.now();
+        #[cfg(feature = "webgpu")]
+        let rendered = match &mut self.webgpu {
+            Some(webgpu) => {
+                webgpu.render(
+                    &self.layers,
+                    active_layer_ids,
+                    color_data,
+                    zoom_x,
+                    offset_x,
+                    offset_y,
+                    alpha,
+                )?;
+                true
+            }
+            None => false,
+        };
+        #[cfg(not(feature = "webgpu"))]
+        let rendered = false;
+        if !rendered {
+            let renderer = self
+                .renderer
+                .as_mut()
+                .ok_or_else(ViewerError::not_initialized)?;
             renderer.render(
+                &self.layers,
                 active_layer_ids,
                 color_data,
                 zoom_x,
@@ -167,48 +943,2355 @@ impl GerberProcessor {
                 offset_y,
                 alpha,
             )?;
-            Ok("render_done".to_string())
-        } else {
-            Err(JsValue::from_str(
-                "Renderer not initialized. Call init() first.",
-            ))
         }
+        self.events.render_complete(active_layer_ids.len(), started);
+        #[cfg(feature = "input")]
+        if let Some(input) = &self.input {
+            input.set_camera(CameraView::new(zoom_x, offset_x, offset_y));
+            input.set_fit_bounds(self.layers.boundary());
+        }
+        self.view = Some(ViewState {
+            active_layer_ids: active_layer_ids.to_vec(),
+            colors: color_data.to_vec(),
+            zoom_x,
+            zoom_y,
+            offset_x,
+            offset_y,
+            alpha,
+        });
+        Ok("render_done".to_string())
     }
 
-    /// Get the boundary of the parsed Gerber data for fitToView
+    /// Get the content hash identifying a layer's source file in session state
     ///
-    /// # Returns
-    /// * `Boundary` containing min/max x/y coordinates
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
     ///
-    /// # Errors
-    /// * Returns error if parse() has not been called yet
-    pub fn get_boundary(&self) -> Result<Boundary, JsValue> {
-        if let Some(renderer) = &self.renderer {
-            Ok(renderer.get_boundary())
-        } else {
-            Err(JsValue::from_str(
-                "No data available. Call parse() first to parse Gerber content.",
-            ))
-        }
+    /// # Returns
+    /// * 16-digit hex hash of the layer content
+    pub fn layer_hash(&self, layer_id: u32) -> Result<String, JsValue> {
+        self.layer_hashes
+            .get(&layer_id)
+            .cloned()
+            .ok_or_else(|| ViewerError::invalid_layer("Layer not found", layer_id).into())
     }
 
-    /// Resize framebuffers when canvas dimensions change (e.g., fullscreen)
+    /// Get the unit, coordinate format and image polarity of a layer's file
     ///
-    /// # Returns
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    ///
+    /// # Returns
+    /// * `LayerInfo` with the unit and where it came from, the %FS digits and %IP polarity
+    pub fn get_layer_info(&self, layer_id: u32) -> Result<LayerInfo, JsValue> {
+        Ok(self.layers.info(layer_id as usize)?.clone())
+    }
+
+    /// List the deprecated constructs of a layer's Gerber file
+    ///
+    /// Flags G54 aperture selects, G70/G71 unit codes, G90/G91 coordinate
+    /// modes, single-quadrant arc mode (G74), trailing zero omission (%FST)
+    /// and files without %TF attributes, each with the modern replacement and
+    /// the section of the Gerber specification, to help modernize CAM output.
+    /// Layers imported from IPC-2581 or KiCad boards have no findings.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    ///
+    /// # Returns
+    /// * `LintReport` with one finding per broken rule
+    pub fn lint_layer(&self, layer_id: u32) -> Result<LintReport, JsValue> {
+        Ok(LintReport::new(&self.layers.info(layer_id as usize)?.lint))
+    }
+
+    /// Find a loaded layer with exactly the given content
+    ///
+    /// add_layer() and the other add methods return such a layer's ID instead
+    /// of stacking the same file twice; compare the returned ID with this to
+    /// tell a duplicate apart from a new layer beforehand.
+    ///
+    /// # Arguments
+    /// * `content` - Gerber file content as string
+    ///
+    /// # Returns
+    /// * Layer ID of the identical layer, or undefined if there is none
+    pub fn find_duplicate_layer(&self, content: String) -> Option<u32> {
+        self.layer_with_hash(&content_hash(content.as_bytes()))
+    }
+
+    /// Share aperture definitions between the layers parsed from now on
+    ///
+    /// Layers of one job mostly define identical apertures and macros; with
+    /// sharing on, a repeated definition reuses the primitives instantiated
+    /// for an earlier layer. Turning it off drops the shared cache (layers
+    /// keep their apertures).
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether to share apertures
+    ///
+    /// # Returns
+    /// * `"aperture_sharing_done"` signal on success
+    pub fn set_aperture_sharing(&mut self, enabled: bool) -> String {
+        if !enabled {
+            self.aperture_cache = None;
+        } else if self.aperture_cache.is_none() {
+            self.aperture_cache = Some(Rc::default());
+        }
+        "aperture_sharing_done".to_string()
+    }
+
+    /// Set quality settings for parsing
+    ///
+    /// Applies to layers added afterwards, and the chord error also to 3D
+    /// models (show_3d(), export_gltf()) and PDF arcs.
+    ///
+    /// # Arguments
+    /// * `options` - Parse settings (ParseOptions)
+    ///
+    /// # Returns
+    /// * `"parse_options_done"` signal on success
+    pub fn set_parse_options(&mut self, options: &ParseOptions) -> Result<String, JsValue> {
+        options.validate()?;
+        self.parse_options = *options;
+        Ok("parse_options_done".to_string())
+    }
+
+    /// Current parse settings (a copy; use set_parse_options() to change them)
+    pub fn parse_options(&self) -> ParseOptions {
+        self.parse_options
+    }
+
+    /// Set quality and camera settings for rendering
+    ///
+    /// # Arguments
+    /// * `options` - Render settings (RenderOptions)
+    ///
+    /// # Returns
+    /// * `"render_options_done"` signal on success
+    pub fn set_render_options(&mut self, options: &RenderOptions) -> Result<String, JsValue> {
+        options.validate()?;
+        #[cfg(feature = "renderer")]
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_options(options)?;
+        }
+        #[cfg(feature = "webgpu")]
+        if let Some(webgpu) = &mut self.webgpu {
+            webgpu.set_min_feature_pixels(options.min_feature_pixels);
+        }
+        self.render_options = *options;
+        Ok("render_options_done".to_string())
+    }
+
+    /// Current render settings (a copy; use set_render_options() to change them)
+    pub fn render_options(&self) -> RenderOptions {
+        self.render_options
+    }
+
+    /// Number of distinct shared apertures and of definitions that reused one
+    ///
+    /// # Returns
+    /// * `[distinct, reused]` (both 0 with sharing off)
+    pub fn aperture_sharing_stats(&self) -> Vec<u32> {
+        match &self.aperture_cache {
+            Some(cache) => {
+                let cache = cache.borrow();
+                vec![cache.len() as u32, cache.hits() as u32]
+            }
+            None => vec![0, 0],
+        }
+    }
+
+    /// Store page annotations (comments, measurements, ...) with the session
+    ///
+    /// # Arguments
+    /// * `json` - Any JSON value; saved and restored verbatim
+    ///
+    /// # Returns
+    /// * `"annotations_done"` signal on success
+    pub fn set_annotations(&mut self, json: String) -> Result<String, JsValue> {
+        self.annotations = serde_json::from_str(&json).map_err(|e| {
+            ViewerError::invalid_argument(format!("Invalid annotations JSON: {}", e))
+        })?;
+        Ok("annotations_done".to_string())
+    }
+
+    /// Get the stored page annotations as JSON (`null` if none)
+    pub fn get_annotations(&self) -> String {
+        self.annotations.to_string()
+    }
+
+    /// Save the review session as JSON
+    ///
+    /// Layers are recorded by content hash together with the colors, visible
+    /// layers and camera of the last render() call and the stored annotations.
+    ///
+    /// # Returns
+    /// * Session state JSON
+    pub fn export_state(&self) -> String {
+        let mut layers: Vec<SessionLayer> = self
+            .layer_hashes
+            .iter()
+            .map(|(&id, hash)| SessionLayer {
+                id,
+                hash: hash.clone(),
+            })
+            .collect();
+        layers.sort_by_key(|layer| layer.id);
+
+        SessionState::new(layers, self.view.clone(), self.annotations.clone()).to_json()
+    }
+
+    /// Restore a review session saved with export_state()
+    ///
+    /// Load the session's files with add_layer() first; saved layers are matched
+    /// to loaded layers by content hash. Annotations are restored, and the view
+    /// settings are returned with layer IDs rewritten for the next render() call.
+    ///
+    /// # Arguments
+    /// * `json` - Session state JSON from export_state()
+    ///
+    /// # Returns
+    /// * Restored session state JSON with current layer IDs
+    ///
+    /// # Errors
+    /// * Returns error listing the hashes of saved layers that are not loaded
+    pub fn import_state(&mut self, json: String) -> Result<String, JsValue> {
+        let state = SessionState::from_json(&json).map_err(ViewerError::parse_failed)?;
+        let loaded: Vec<SessionLayer> = self
+            .layer_hashes
+            .iter()
+            .map(|(&id, hash)| SessionLayer {
+                id,
+                hash: hash.clone(),
+            })
+            .collect();
+
+        let state = state.remap(&loaded).map_err(|missing| {
+            ViewerError::invalid_argument(format!(
+                "Session layers not loaded: {}",
+                missing.join(", ")
+            ))
+        })?;
+
+        self.view = state.view.clone();
+        self.annotations = state.annotations.clone();
+        Ok(state.to_json())
+    }
+
+    /// Get the boundary of the parsed Gerber data for fitToView
+    ///
+    /// With set_robust_boundary(true), outlier primitives are left out.
+    ///
+    /// # Returns
+    /// * `Boundary` containing min/max x/y coordinates (all zero without layers)
+    pub fn get_boundary(&self) -> Result<Boundary, JsValue> {
+        Ok(self.fit_boundary(None)?)
+    }
+
+    /// Get the combined boundary of some layers, e.g. the visible ones
+    ///
+    /// With set_robust_boundary(true), outlier primitives are left out.
+    ///
+    /// # Arguments
+    /// * `layer_ids` - Layer IDs to include (Uint32Array)
+    ///
+    /// # Returns
+    /// * `Boundary` containing min/max x/y coordinates (all zero when none has geometry)
+    pub fn get_boundary_for(&self, layer_ids: &[u32]) -> Result<Boundary, JsValue> {
+        Ok(self.fit_boundary(Some(layer_ids))?)
+    }
+
+    /// Leave outlier primitives out of get_boundary() and get_boundary_for()
+    ///
+    /// Stray flashes at (0, 0) or frames drawn far around the board then no
+    /// longer shrink the board when fitting the view. See
+    /// get_boundary_outliers() for which primitives are left out.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether to leave outliers out
+    ///
+    /// # Returns
+    /// * `"robust_boundary_done"` signal on success
+    pub fn set_robust_boundary(&mut self, enabled: bool) -> String {
+        self.robust_boundary = enabled;
+        "robust_boundary_done".to_string()
+    }
+
+    /// Find primitives lying far outside the bulk of the board
+    ///
+    /// A primitive is an outlier when it reaches beyond the box around the
+    /// middle 96% of primitive centers, grown by half that box's longer side.
+    ///
+    /// # Arguments
+    /// * `layer_ids` - Layer IDs to check together (all layers when omitted)
+    ///
+    /// # Returns
+    /// * `BoundaryOutliers` with the layer and box of every outlier and the boundary without them
+    pub fn get_boundary_outliers(
+        &self,
+        layer_ids: Option<Vec<u32>>,
+    ) -> Result<BoundaryOutliers, JsValue> {
+        Ok(self.boundary_outliers(layer_ids.as_deref())?)
+    }
+
+    /// Get the parsed geometry of a layer for host-side analysis or rendering
+    ///
+    /// Each polarity sublayer becomes an object with `polarity` ("dark" or
+    /// "clear") and the primitive groups `triangles`, `circles`, `arcs`,
+    /// `thermals` and `obrounds`, whose fields are Float32Arrays (Uint32Array
+    /// for triangle indices) in mm and radians. The arrays are views into WASM
+    /// memory, not copies: they go stale on the next call into the processor,
+    /// so copy them with slice() to keep them.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    ///
+    /// # Returns
+    /// * Array of sublayer geometry objects, in drawing order
+    #[cfg(feature = "renderer")]
+    pub fn get_layer_geometry(&self, layer_id: u32) -> Result<js_sys::Array, JsValue> {
+        let sublayers = self.layers.get(layer_id as usize)?;
+        let result = js_sys::Array::new();
+        for (index, sublayer) in sublayers.iter().enumerate() {
+            // SAFETY: nothing below allocates in WASM memory, and the host is
+            // told to copy the views before calling back into the processor
+            let geometry = unsafe { sublayer.js_views()? };
+            let polarity = if index % 2 == 0 { "dark" } else { "clear" };
+            js_sys::Reflect::set(&geometry, &"polarity".into(), &polarity.into())?;
+            result.push(&geometry);
+        }
+        Ok(result)
+    }
+
+    /// Render a window of the board to an RGBA image
+    ///
+    /// The window is drawn offscreen at the requested size, independent of the
+    /// canvas and camera, for tiling, thumbnails or automated comparisons.
+    /// Layers are combined additively at full opacity. For a 1:1 print image
+    /// use px_width = (max_x - min_x) / 25.4 * dpi.
+    ///
+    /// # Arguments
+    /// * `min_x` - Window left edge in mm
+    /// * `min_y` - Window bottom edge in mm
+    /// * `max_x` - Window right edge in mm
+    /// * `max_y` - Window top edge in mm
+    /// * `px_width` - Image width in pixels
+    /// * `px_height` - Image height in pixels
+    /// * `layer_ids` - Layers to draw (in order)
+    /// * `colors` - Flat array of [r, g, b] for each layer
+    ///
+    /// # Returns
+    /// * RGBA pixels, top row first with straight alpha (for ImageData)
+    #[cfg(feature = "renderer")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_region_to_image(
+        &mut self,
+        min_x: f32,
+        min_y: f32,
+        max_x: f32,
+        max_y: f32,
+        px_width: u32,
+        px_height: u32,
+        layer_ids: &[u32],
+        colors: &[f32],
+    ) -> Result<Vec<u8>, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            let region = Boundary::new(min_x, max_x, min_y, max_y);
+            renderer.render_region(
+                &self.layers,
+                &region,
+                px_width,
+                px_height,
+                layer_ids,
+                colors,
+            )
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Zoom factor that shows the board at a true physical scale
+    ///
+    /// With the returned zoom passed to render(), `scale` mm on screen equal
+    /// 1 mm of board (1.0 = 1:1), e.g. to lay parts on a screen for a fit
+    /// check. Recompute after resize(); export_pdf() with a scale of 1.0 gives
+    /// the same check on paper.
+    ///
+    /// # Arguments
+    /// * `dpi` - Display resolution in canvas pixels per inch (physical DPI / devicePixelRatio)
+    /// * `scale` - Screen millimeters per board millimeter
+    ///
+    /// # Returns
+    /// * Zoom value for render()
+    #[cfg(feature = "renderer")]
+    pub fn print_scale_zoom(&self, dpi: f32, scale: f32) -> Result<f32, JsValue> {
+        if dpi <= 0.0 || scale <= 0.0 {
+            return Err(ViewerError::invalid_argument("DPI and scale must be positive").into());
+        }
+        if let Some(renderer) = &self.renderer {
+            renderer.zoom_for_pixel_scale(dpi / 25.4 * scale)
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Camera view centered on a board point, e.g. a position from a DRC report
+    ///
+    /// # Arguments
+    /// * `world_x`, `world_y` - Point in mm to show at the canvas center
+    /// * `zoom_level` - Zoom factor as passed to render()
+    ///
+    /// # Returns
+    /// * `CameraView` whose zoom and offsets go to render()
+    pub fn zoom_to(
+        &self,
+        world_x: f32,
+        world_y: f32,
+        zoom_level: f32,
+    ) -> Result<CameraView, JsValue> {
+        if !(zoom_level.is_finite() && zoom_level > 0.0) {
+            return Err(ViewerError::invalid_argument("Zoom must be a positive number").into());
+        }
+        if !(world_x.is_finite() && world_y.is_finite()) {
+            return Err(ViewerError::invalid_argument("Point must be finite").into());
+        }
+        Ok(CameraView::centered_on(world_x, world_y, zoom_level))
+    }
+
+    /// Camera view fitting a window of the board into the canvas
+    ///
+    /// The window is centered with a small border; windows smaller than 1 mm
+    /// (e.g. a single marker point) are shown 1 mm wide. Recompute after
+    /// resize().
+    ///
+    /// # Arguments
+    /// * `min_x` - Window left edge in mm
+    /// * `min_y` - Window bottom edge in mm
+    /// * `max_x` - Window right edge in mm
+    /// * `max_y` - Window top edge in mm
+    ///
+    /// # Returns
+    /// * `CameraView` whose zoom and offsets go to render()
+    #[cfg(feature = "renderer")]
+    pub fn zoom_to_bounds(
+        &self,
+        min_x: f32,
+        min_y: f32,
+        max_x: f32,
+        max_y: f32,
+    ) -> Result<CameraView, JsValue> {
+        let finite = [min_x, min_y, max_x, max_y].iter().all(|v| v.is_finite());
+        if !(finite && min_x <= max_x && min_y <= max_y) {
+            return Err(
+                ViewerError::invalid_argument("Bounds must be finite with min <= max").into(),
+            );
+        }
+        let bounds = Boundary::new(min_x, max_x, min_y, max_y);
+        #[cfg(feature = "webgpu")]
+        if let Some(webgpu) = &self.webgpu {
+            return webgpu.view_for_bounds(&bounds);
+        }
+        if let Some(renderer) = &self.renderer {
+            renderer.view_for_bounds(&bounds)
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Move the camera smoothly to a view over the next render() calls
+    ///
+    /// The animation starts from the camera of the last render() and runs
+    /// in real time: keep calling render() each frame (e.g. from
+    /// requestAnimationFrame) while is_animating() is true, then take over the
+    /// final camera from camera_view(). A new animation replaces a running one.
+    ///
+    /// # Arguments
+    /// * `view` - Target view, e.g. from zoom_to() or zoom_to_bounds()
+    /// * `duration_ms` - Animation length in milliseconds (0 = jump on the next render())
+    ///
+    /// # Returns
+    /// * `"animate_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn animate_to(&mut self, view: &CameraView, duration_ms: f64) -> Result<String, JsValue> {
+        if !(duration_ms.is_finite() && duration_ms >= 0.0) {
+            return Err(
+                ViewerError::invalid_argument("Duration must be a non-negative number").into(),
+            );
+        }
+        if !(view.zoom.is_finite() && view.zoom > 0.0) {
+            return Err(ViewerError::invalid_argument("Zoom must be a positive number").into());
+        }
+        let from = self.camera_view().unwrap_or(*view);
+        self.animation = Some(CameraAnimation::new(
+            from,
+            *view,
+            performance_now(),
+            duration_ms,
+        ));
+        Ok("animate_done".to_string())
+    }
+
+    /// Whether a camera animation is still running
+    #[cfg(feature = "renderer")]
+    pub fn is_animating(&self) -> bool {
+        self.animation.is_some()
+    }
+
+    /// Stop a running camera animation where the last render() left it
+    ///
+    /// # Returns
+    /// * `"animation_stop_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn stop_animation(&mut self) -> String {
+        self.animation = None;
+        "animation_stop_done".to_string()
+    }
+
+    /// Handle pan, zoom and fit gestures on the canvas inside the viewer
+    ///
+    /// Dragging pans, the wheel zooms at the cursor, two fingers pinch-zoom
+    /// and a double-click fits all layers. From then on render() draws with
+    /// the gesture camera instead of the zoom and offsets passed in, so the
+    /// host only needs to call render() from `on_change`. Replaces handlers
+    /// attached before.
+    ///
+    /// # Arguments
+    /// * `canvas` - Canvas to listen on, usually the one rendered to
+    /// * `on_change` - Called without arguments after every camera change
+    ///
+    /// # Returns
+    /// * `"attach_input_done"` signal on success
+    #[cfg(feature = "input")]
+    pub fn attach_input(
+        &mut self,
+        canvas: web_sys::HtmlCanvasElement,
+        on_change: Option<js_sys::Function>,
+    ) -> Result<String, JsValue> {
+        // Remove the old listeners before adding new ones
+        self.input = None;
+        let camera = self
+            .camera_view()
+            .unwrap_or_else(|| CameraView::new(1.0, 0.0, 0.0));
+        self.input = Some(InputHandler::attach(
+            canvas,
+            camera,
+            self.layers.boundary(),
+            on_change,
+        )?);
+        Ok("attach_input_done".to_string())
+    }
+
+    /// Remove the gesture handlers; render() uses the passed camera again
+    ///
+    /// # Returns
+    /// * `"detach_input_done"` signal on success
+    #[cfg(feature = "input")]
+    pub fn detach_input(&mut self) -> String {
+        self.input = None;
+        "detach_input_done".to_string()
+    }
+
+    /// Camera of the last render() call, including animated frames
+    ///
+    /// # Returns
+    /// * `CameraView`, or undefined before the first render()
+    pub fn camera_view(&self) -> Option<CameraView> {
+        self.view
+            .as_ref()
+            .map(|view| CameraView::new(view.zoom_x, view.offset_x, view.offset_y))
+    }
+
+    /// Camera of the last render() call with its rotation and view matrices
+    ///
+    /// Store the zoom and offsets to restore a view later, pass them to a
+    /// second viewer's render() to keep both in sync, or use pixel_matrix to
+    /// place DOM overlays over board coordinates.
+    ///
+    /// # Returns
+    /// * `CameraState`, or undefined before the first render()
+    ///
+    /// # Errors
+    /// * Returns error if renderer is not initialized
+    #[cfg(feature = "renderer")]
+    pub fn get_camera(&self) -> Result<Option<CameraState>, JsValue> {
+        let (canvas_width, canvas_height) = self.canvas_size()?;
+        Ok(self.camera_view().map(|view| CameraState {
+            view,
+            canvas_width,
+            canvas_height,
+        }))
+    }
+
+    /// Take over another viewer's camera on the next render()
+    ///
+    /// The board point at the other canvas's center is centered here at the
+    /// same pixels per mm, so two viewers of different canvas sizes show the
+    /// same region. Call it from the leading viewer's input callback to keep
+    /// two board revisions side by side in lock-step. Replaces a running
+    /// animation.
+    ///
+    /// # Arguments
+    /// * `state` - Camera from get_camera() of another viewer (or this one)
+    ///
+    /// # Returns
+    /// * `"apply_camera_done"` signal on success
+    ///
+    /// # Errors
+    /// * Returns error if renderer is not initialized
+    #[cfg(feature = "renderer")]
+    pub fn apply_camera(&mut self, state: &CameraState) -> Result<String, JsValue> {
+        if !(state.view.zoom.is_finite() && state.view.zoom > 0.0) {
+            return Err(ViewerError::invalid_argument("Zoom must be a positive number").into());
+        }
+        let (width, height) = self.canvas_size()?;
+        let view = state.view_for_canvas(width, height);
+        self.animation = Some(CameraAnimation::new(view, view, performance_now(), 0.0));
+        Ok("apply_camera_done".to_string())
+    }
+
+    /// Pixel size of the canvas of the active renderer
+    #[cfg(feature = "renderer")]
+    fn canvas_size(&self) -> Result<(u32, u32), JsValue> {
+        #[cfg(feature = "webgpu")]
+        if let Some(webgpu) = &self.webgpu {
+            return webgpu.canvas_size();
+        }
+        if let Some(renderer) = &self.renderer {
+            renderer.get_canvas_size()
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Mark a canvas rectangle to be redrawn by render_dirty()
+    ///
+    /// # Arguments
+    /// * `x` - Left edge in canvas pixels
+    /// * `y` - Top edge in canvas pixels (y down, like the DOM)
+    /// * `width` - Width in canvas pixels
+    /// * `height` - Height in canvas pixels
+    ///
+    /// # Returns
+    /// * `"mark_dirty_done"` signal on success
+    ///
+    /// # Errors
+    /// * Returns error if renderer is not initialized
+    #[cfg(feature = "renderer")]
+    pub fn mark_dirty(
+        &mut self,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    ) -> Result<String, JsValue> {
+        if ![x, y, width, height].iter().all(|v| v.is_finite()) || width < 0.0 || height < 0.0 {
+            return Err(ViewerError::invalid_argument(
+                "Rectangle must be finite with a non-negative size",
+            )
+            .into());
+        }
+        #[cfg(feature = "webgpu")]
+        if self.webgpu.is_some() {
+            return Ok("mark_dirty_done".to_string());
+        }
+        let renderer = self
+            .renderer
+            .as_mut()
+            .ok_or_else(ViewerError::not_initialized)?;
+        renderer.mark_dirty(x, y, width, height)?;
+        Ok("mark_dirty_done".to_string())
+    }
+
+    /// Redraw only the rectangles marked with mark_dirty() since the last render
+    ///
+    /// For small overlay changes (e.g. a DRC marker selection) with the
+    /// camera and layers of the last render() unchanged: the layer images of
+    /// that render() are recomposited inside the rectangles, without drawing
+    /// the geometry again. Needs the double_buffer render option to keep the
+    /// rest of the frame; otherwise the whole canvas is recomposited. With
+    /// WebGPU the last render() is repeated.
+    ///
+    /// # Returns
+    /// * `"render_dirty_done"` signal on success (nothing is drawn before the first render())
+    ///
+    /// # Errors
+    /// * Returns error if renderer is not initialized
+    #[cfg(feature = "renderer")]
+    pub fn render_dirty(&mut self) -> Result<String, JsValue> {
+        let Some(view) = self.view.clone() else {
+            return Ok("render_dirty_done".to_string());
+        };
+        #[cfg(feature = "webgpu")]
+        if self.webgpu.is_some() {
+            self.render(
+                &view.active_layer_ids,
+                &view.colors,
+                view.zoom_x,
+                view.zoom_y,
+                view.offset_x,
+                view.offset_y,
+                view.alpha,
+            )?;
+            return Ok("render_dirty_done".to_string());
+        }
+        let renderer = self
+            .renderer
+            .as_mut()
+            .ok_or_else(ViewerError::not_initialized)?;
+        renderer.render_dirty(
+            &self.layers,
+            &view.active_layer_ids,
+            &view.colors,
+            view.alpha,
+        )?;
+        Ok("render_dirty_done".to_string())
+    }
+
+    /// Resize framebuffers when canvas dimensions change (e.g., fullscreen)
+    ///
+    /// # Returns
     /// * `"resize_done"` signal on success
     ///
     /// # Errors
     /// * Returns error if renderer is not initialized
+    #[cfg(feature = "renderer")]
     pub fn resize(&mut self) -> Result<String, JsValue> {
+        // WebGPU layer textures follow the canvas size on the next render()
+        #[cfg(feature = "webgpu")]
+        if self.webgpu.is_some() {
+            return Ok("resize_done".to_string());
+        }
         if let Some(renderer) = &mut self.renderer {
             renderer.resize()?;
             Ok("resize_done".to_string())
         } else {
-            Err(JsValue::from_str(
-                "Renderer not initialized. Call init() and parse() first.",
-            ))
+            Err(ViewerError::not_initialized().into())
         }
     }
+
+    /// Extract the outline polygons of a layer
+    ///
+    /// Dark geometry is merged and clear geometry cut out in drawing order, so
+    /// the shapes describe the layer as it is plotted, e.g. for paste stencils,
+    /// keep-out areas or isolation milling paths. Curves are flattened with the
+    /// parse options' chord error.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    ///
+    /// # Returns
+    /// * `Contours` with the outer boundary and holes of every shape
+    pub fn extract_contours(&self, layer_id: u32) -> Result<Contours, JsValue> {
+        Ok(extract_contours(
+            self.layers.get(layer_id as usize)?,
+            self.parse_options.chord_error,
+        ))
+    }
+
+    /// List every aperture flash (D03) of a layer as a pad
+    ///
+    /// Flashes are recorded while parsing, one per flash and step-and-repeat
+    /// copy, independent of the triangles they render as, e.g. for pad-count
+    /// audits or cross-checks against pick-and-place data.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    ///
+    /// # Returns
+    /// * `FlashList` with position, D-code, aperture shape, size and rotation of every flash
+    pub fn get_flashes(&self, layer_id: u32) -> Result<FlashList, JsValue> {
+        Ok(collect_flashes(self.layers.get(layer_id as usize)?))
+    }
+
+    /// Count the circular flashes of a layer by diameter
+    ///
+    /// Answers e.g. how many 0.2 mm vias a board has, or how many holes of
+    /// each size a drill layer needs. Diameters within 1 um share a bin.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    ///
+    /// # Returns
+    /// * `SizeHistogram` with the ascending diameters and their flash counts
+    pub fn get_size_histogram(&self, layer_id: u32) -> Result<SizeHistogram, JsValue> {
+        Ok(size_histogram(self.layers.get(layer_id as usize)?))
+    }
+
+    /// Find the smallest draw aperture, flash and drill hit of a board
+    ///
+    /// One call to check a design against a fab's minimum trace width, pad
+    /// size and drill diameter; each feature comes with its position so it
+    /// can be located on the board. Clear (negative) features are ignored.
+    ///
+    /// # Arguments
+    /// * `layer_ids` - Layers whose draws and flashes are checked, e.g. the copper layers
+    /// * `drill_layer_ids` - Drill layers whose hits are checked
+    ///
+    /// # Returns
+    /// * `SmallestFeatures` with the smallest draw, flash and drill hit, each undefined if there is none
+    pub fn get_smallest_features(
+        &self,
+        layer_ids: Vec<u32>,
+        drill_layer_ids: Vec<u32>,
+    ) -> Result<SmallestFeatures, JsValue> {
+        let layers = |ids: &[u32]| {
+            ids.iter()
+                .map(|&id| Ok((id, self.layers.get(id as usize)?)))
+                .collect::<Result<Vec<_>, ViewerError>>()
+        };
+        Ok(smallest_features(
+            &layers(&layer_ids)?,
+            &layers(&drill_layer_ids)?,
+        ))
+    }
+
+    /// Summarize the drill tools of a board for fab quotes
+    ///
+    /// Lists every tool diameter with its plating and how many hits (flashes)
+    /// and routed slots (draws) it makes; to_csv() gives the table as text.
+    /// Holes of a layer whose %TF.FileFunction is NonPlated count as
+    /// non-plated, those of any other layer as plated.
+    ///
+    /// # Arguments
+    /// * `layer_ids` - Drill layers, e.g. the plated and the non-plated drill file
+    ///
+    /// # Returns
+    /// * `DrillSummary` with the diameter, plating, hit and slot count of each tool
+    pub fn get_drill_summary(&self, layer_ids: Vec<u32>) -> Result<DrillSummary, JsValue> {
+        let layers = layer_ids
+            .iter()
+            .map(|&id| {
+                let plated = !self
+                    .layers
+                    .info(id as usize)?
+                    .file_function
+                    .starts_with("NonPlated");
+                Ok((self.layers.get(id as usize)?, plated))
+            })
+            .collect::<Result<Vec<_>, ViewerError>>()?;
+        Ok(drill_summary(&layers))
+    }
+
+    /// List the draws (D01) of a layer as polylines
+    ///
+    /// Consecutive draws with the same aperture that each start where the
+    /// previous one ended form one polyline of straight segments and arcs,
+    /// recorded while parsing alongside the rendered geometry, e.g. to measure
+    /// routed lengths for net-length analysis. Regions are not included.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    ///
+    /// # Returns
+    /// * `TraceList` with the points, arcs, width, D-code and length of every polyline
+    pub fn get_traces(&self, layer_id: u32) -> Result<TraceList, JsValue> {
+        Ok(collect_traces(self.layers.get(layer_id as usize)?))
+    }
+
+    /// Compute the copper coverage of a layer on a coarse grid
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    /// * `columns` - Number of grid cells across the layer width
+    /// * `rows` - Number of grid cells across the layer height; at most
+    ///   65536 (256 x 256) cells in total
+    ///
+    /// # Returns
+    /// * `CopperDensity` with the coverage ratio (0.0 - 1.0) of every cell
+    pub fn compute_copper_density(
+        &self,
+        layer_id: u32,
+        columns: u32,
+        rows: u32,
+    ) -> Result<CopperDensity, JsValue> {
+        let data = self.layers.get(layer_id as usize)?;
+        copper_density(data, columns, rows).map_err(|e| ViewerError::invalid_argument(e).into())
+    }
+
+    /// Show the copper density of a layer as a translucent heatmap overlay
+    ///
+    /// The overlay stays visible on subsequent render() calls until hidden
+    /// or until the layer is removed.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    /// * `columns` - Number of grid cells across the layer width
+    /// * `rows` - Number of grid cells across the layer height; at most
+    ///   65536 (256 x 256) cells in total
+    ///
+    /// # Returns
+    /// * `"heatmap_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn show_density_heatmap(
+        &mut self,
+        layer_id: u32,
+        columns: u32,
+        rows: u32,
+    ) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            let density = copper_density(self.layers.get(layer_id as usize)?, columns, rows)
+                .map_err(ViewerError::invalid_argument)?;
+            renderer.set_heatmap(layer_id as usize, &density)?;
+            Ok("heatmap_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Hide the copper density heatmap overlay
+    ///
+    /// # Returns
+    /// * `"heatmap_hide_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn hide_density_heatmap(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_heatmap();
+            Ok("heatmap_hide_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Compare two layers and switch rendering to compare mode
+    ///
+    /// While compare mode is active, render() draws regions only in layer A,
+    /// only in layer B, and common to both in three distinct colors.
+    ///
+    /// # Arguments
+    /// * `layer_a` - First layer ID
+    /// * `layer_b` - Second layer ID
+    /// * `colors` - Optional flat array of [r, g, b] for A-only, B-only and common regions
+    ///
+    /// # Returns
+    /// * `LayerComparison` with the A-only, B-only and common areas in mm²
+    pub fn compare_layers(
+        &mut self,
+        layer_a: u32,
+        layer_b: u32,
+        colors: Option<Vec<f32>>,
+    ) -> Result<LayerComparison, JsValue> {
+        let comparison = compare_layers(
+            self.layers.get(layer_a as usize)?,
+            self.layers.get(layer_b as usize)?,
+        );
+        #[cfg(feature = "renderer")]
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_compare(
+                layer_a as usize,
+                layer_b as usize,
+                colors.as_deref().unwrap_or(&[]),
+            )?;
+        }
+        #[cfg(not(feature = "renderer"))]
+        let _ = colors;
+        Ok(comparison)
+    }
+
+    /// Move a layer so its flashes line up with a reference layer's flashes
+    ///
+    /// Estimates the translation, and a 90° rotation if that matches more
+    /// flashes, e.g. to put an offset drill file onto its pads, and applies
+    /// it to the layer's geometry. Only flashes on dark polarity are compared.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID of the layer to move
+    /// * `reference_layer_id` - Layer ID of the layer to align to
+    ///
+    /// # Returns
+    /// * `LayerAlignment` with the applied offset and rotation and the number of matched flashes
+    pub fn auto_align(
+        &mut self,
+        layer_id: u32,
+        reference_layer_id: u32,
+    ) -> Result<LayerAlignment, JsValue> {
+        self.check_loaded(layer_id)?;
+        let alignment = estimate_alignment(
+            self.layers.get(layer_id as usize)?,
+            self.layers.get(reference_layer_id as usize)?,
+        )
+        .map_err(|message| ViewerError::invalid_layer(message, layer_id))?;
+
+        self.transform_layer(layer_id, |data| {
+            data.transform(alignment.quarter_turns, alignment.dx, alignment.dy)
+        })?;
+        Ok(alignment)
+    }
+
+    /// Mirror a layer left to right about a vertical axis
+    ///
+    /// Fixes drill files written mirrored, e.g. from the bottom side. Flip
+    /// about the board center to keep the layer in place.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID of the layer to mirror
+    /// * `axis_x` - X coordinate of the mirror axis in mm
+    ///
+    /// # Returns
+    /// * `"layer_flip_done"` signal on success
+    pub fn flip_layer_horizontal(&mut self, layer_id: u32, axis_x: f32) -> Result<String, JsValue> {
+        if !axis_x.is_finite() {
+            return Err(ViewerError::invalid_argument("Mirror axis must be finite").into());
+        }
+        self.check_loaded(layer_id)?;
+        self.transform_layer(layer_id, |data| data.mirror_x(axis_x))?;
+        Ok("layer_flip_done".to_string())
+    }
+
+    /// Scale a layer's coordinates and sizes about the origin
+    ///
+    /// Fixes layers read in the wrong unit: 25.4 turns inch values read as
+    /// mm into mm, 1 / 25.4 the other way round.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID of the layer to scale
+    /// * `factor` - Scale factor (> 0)
+    ///
+    /// # Returns
+    /// * `"layer_rescale_done"` signal on success
+    pub fn rescale_layer(&mut self, layer_id: u32, factor: f32) -> Result<String, JsValue> {
+        if !(factor > 0.0 && factor.is_finite()) {
+            return Err(ViewerError::invalid_argument("Scale factor must be positive").into());
+        }
+        self.check_loaded(layer_id)?;
+        self.transform_layer(layer_id, |data| data.scale(factor))?;
+        Ok("layer_rescale_done".to_string())
+    }
+
+    /// Leave compare mode and return to normal layer rendering
+    ///
+    /// # Returns
+    /// * `"compare_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn clear_compare(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_compare();
+            Ok("compare_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Draw the copper layers of a stackup like an X-ray image
+    ///
+    /// The copper layers linked to loaded layers (Stackup.assign_layer) are
+    /// composited at reduced alpha with "over" blending from the bottom up,
+    /// so the top layer lies in front and overlapping copper reads darker
+    /// with depth instead of adding up to white. Only active layers are
+    /// drawn, in their render() colors; other active layers keep additive
+    /// blending beneath them. Stays on for subsequent render() calls until
+    /// cleared.
+    ///
+    /// # Arguments
+    /// * `stackup` - Board stackup with linked copper layers
+    /// * `alpha` - Opacity of each copper layer, in (0, 1]
+    ///
+    /// # Returns
+    /// * `"xray_done"` signal on success
+    ///
+    /// # Errors
+    /// * When no copper layer of the stackup is linked or alpha is out of range
+    #[cfg(feature = "renderer")]
+    pub fn show_xray(&mut self, stackup: &Stackup, alpha: f32) -> Result<String, JsValue> {
+        let layer_ids: Vec<usize> = stackup
+            .layers
+            .iter()
+            .filter(|l| l.kind == StackupKind::Copper)
+            .filter_map(|l| l.layer_id)
+            .map(|id| id as usize)
+            .collect();
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_xray(&layer_ids, alpha)?;
+            Ok("xray_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Return to additive compositing of all layers
+    ///
+    /// # Returns
+    /// * `"xray_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn clear_xray(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_xray();
+            Ok("xray_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Color a layer's primitives by their X2 aperture function
+    ///
+    /// Uses the .AperFunction attributes of the layer's Gerber file; the
+    /// layer's own color is replaced by one color per function while its
+    /// alpha still applies. Stays on for subsequent render() calls until
+    /// hidden or until the layer is removed. Only one layer is colored by
+    /// function at a time.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    /// * `colors` - Optional flat array of [r, g, b] for primitives without a
+    ///   function, Conductor, ViaPad, SMDPad, BGAPad, ComponentPad, any
+    ///   other function, ComponentOutline,Courtyard, FiducialPad and TestPad
+    ///
+    /// # Returns
+    /// * `"aperture_functions_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn show_aperture_functions(
+        &mut self,
+        layer_id: u32,
+        colors: Option<Vec<f32>>,
+    ) -> Result<String, JsValue> {
+        let tagged = self
+            .layers
+            .get(layer_id as usize)?
+            .iter()
+            .any(|data| !data.functions.is_empty());
+        if !tagged {
+            return Err(ViewerError::invalid_layer(
+                "Layer has no .AperFunction attributes",
+                layer_id,
+            )
+            .into());
+        }
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_function_colors(layer_id as usize, colors.as_deref().unwrap_or(&[]))?;
+            Ok("aperture_functions_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Return to drawing the layer colored by aperture function in its own color
+    ///
+    /// # Returns
+    /// * `"aperture_functions_hide_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn hide_aperture_functions(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_function_colors();
+            Ok("aperture_functions_hide_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Emphasize the features a layer drew with one aperture
+    ///
+    /// Flashes and draws of the aperture keep full strength while the rest of
+    /// the layer is dimmed, so all uses of e.g. one pad size stand out. Stays
+    /// on for subsequent render() calls until cleared or until the layer is
+    /// removed. Only one aperture is highlighted at a time.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    /// * `d_code` - Aperture number, e.g. 10 for D10
+    ///
+    /// # Returns
+    /// * `"highlight_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn highlight_aperture(&mut self, layer_id: u32, d_code: u32) -> Result<String, JsValue> {
+        let sublayers = self.layers.get(layer_id as usize)?;
+        let used = u16::try_from(d_code).is_ok_and(|code| {
+            code > 0 && sublayers.iter().any(|data| data.apertures.contains(code))
+        });
+        if !used {
+            return Err(ViewerError::invalid_argument(format!(
+                "Aperture D{} is not used in layer {}",
+                d_code, layer_id
+            ))
+            .into());
+        }
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_highlight(layer_id as usize, d_code as u16)?;
+            Ok("highlight_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Draw every feature at full strength again
+    ///
+    /// # Returns
+    /// * `"highlight_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn clear_highlight(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_highlight();
+            Ok("highlight_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Draw only the primitives of a layer that pass a filter
+    ///
+    /// Shows e.g. only flashes, only draws, only apertures of at least some
+    /// size or only Conductor features. The filter is applied from the stored
+    /// geometry without parsing the layer again and stays on for subsequent
+    /// render() calls until cleared or until the layer is removed.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    /// * `filter` - Primitives to draw
+    ///
+    /// # Returns
+    /// * `"filter_done"` signal on success
+    ///
+    /// # Errors
+    /// Fails for a negative minimum aperture size, or for conductors_only on
+    /// a layer without .AperFunction attributes.
+    #[cfg(feature = "renderer")]
+    pub fn set_filter(
+        &mut self,
+        layer_id: u32,
+        filter: &PrimitiveFilter,
+    ) -> Result<String, JsValue> {
+        filter.validate()?;
+        let sublayers = self.layers.get(layer_id as usize)?;
+        if filter.conductors_only && sublayers.iter().all(|data| data.functions.is_empty()) {
+            return Err(ViewerError::invalid_layer(
+                "Layer has no .AperFunction attributes",
+                layer_id,
+            )
+            .into());
+        }
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_filter(layer_id as usize, *filter)?;
+            Ok("filter_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Draw every primitive of a layer again
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    ///
+    /// # Returns
+    /// * `"filter_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn clear_filter(&mut self, layer_id: u32) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_filter(layer_id as usize, PrimitiveFilter::default())?;
+            Ok("filter_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Check the annular ring of every drill hit against a copper layer
+    ///
+    /// The ring is measured from the hole edge to the edge of the surrounding
+    /// copper; hits without copper at the hole edge report a ring of 0.
+    ///
+    /// # Arguments
+    /// * `drill_layer_id` - Layer ID of the drill layer (hits are its flashed circles)
+    /// * `copper_layer_id` - Layer ID of the copper layer to check against
+    /// * `min_ring` - Minimum allowed annular ring in mm
+    ///
+    /// # Returns
+    /// * `AnnularRingReport` listing the hits below `min_ring`
+    pub fn check_annular_ring(
+        &self,
+        drill_layer_id: u32,
+        copper_layer_id: u32,
+        min_ring: f32,
+    ) -> Result<AnnularRingReport, JsValue> {
+        Ok(check_annular_ring(
+            self.layers.get(drill_layer_id as usize)?,
+            self.layers.get(copper_layer_id as usize)?,
+            min_ring,
+        ))
+    }
+
+    /// Estimate minimum conductor width and clearance of a copper layer
+    ///
+    /// Widths and gaps are measured across the copper edges of the whole layer;
+    /// values above 1 mm are not measured.
+    ///
+    /// # Arguments
+    /// * `layer_id` - Layer ID returned from add_layer()
+    /// * `min_width` - Minimum allowed conductor width in mm
+    /// * `min_spacing` - Minimum allowed copper-to-copper clearance in mm
+    ///
+    /// # Returns
+    /// * `WidthSpacingReport` with the minima, their locations and all violations
+    pub fn check_width_spacing(
+        &self,
+        layer_id: u32,
+        min_width: f32,
+        min_spacing: f32,
+    ) -> Result<WidthSpacingReport, JsValue> {
+        Ok(check_width_spacing(
+            self.layers.get(layer_id as usize)?,
+            min_width,
+            min_spacing,
+        ))
+    }
+
+    /// Check a solder mask layer for slivers and a silkscreen layer for ink on pads
+    ///
+    /// Pads are exposed where the copper layer and a mask opening overlap;
+    /// silkscreen over an exposed pad is reported once per overlap region.
+    ///
+    /// # Arguments
+    /// * `mask_layer_id` - Layer ID of the solder mask (openings drawn dark)
+    /// * `silk_layer_id` - Layer ID of the silkscreen on the same side
+    /// * `copper_layer_id` - Layer ID of the outer copper on the same side
+    /// * `min_sliver` - Minimum allowed mask web width between openings in mm
+    ///
+    /// # Returns
+    /// * `MaskSilkReport` with the slivers and silkscreen overlaps
+    pub fn check_mask_silk(
+        &self,
+        mask_layer_id: u32,
+        silk_layer_id: u32,
+        copper_layer_id: u32,
+        min_sliver: f32,
+    ) -> Result<MaskSilkReport, JsValue> {
+        Ok(check_mask_silk(
+            self.layers.get(mask_layer_id as usize)?,
+            self.layers.get(silk_layer_id as usize)?,
+            self.layers.get(copper_layer_id as usize)?,
+            min_sliver,
+        ))
+    }
+
+    /// Measure the copper left bare by the solder mask openings
+    ///
+    /// Exposed copper is where the copper layer and a mask opening overlap,
+    /// e.g. the area plated by ENIG. The largest regions are listed with
+    /// their location.
+    ///
+    /// # Arguments
+    /// * `copper_layer_id` - Layer ID of the outer copper
+    /// * `mask_layer_id` - Layer ID of the solder mask on the same side (openings drawn dark)
+    ///
+    /// # Returns
+    /// * `ExposedCopperReport` with the total area and the largest regions
+    pub fn exposed_copper(
+        &self,
+        copper_layer_id: u32,
+        mask_layer_id: u32,
+    ) -> Result<ExposedCopperReport, JsValue> {
+        Ok(exposed_copper(
+            self.layers.get(copper_layer_id as usize)?,
+            self.layers.get(mask_layer_id as usize)?,
+            self.parse_options.chord_error,
+        ))
+    }
+
+    /// Show DRC marker rings on top of the layers
+    ///
+    /// Replaces any markers shown before; they stay visible until cleared.
+    ///
+    /// # Arguments
+    /// * `markers` - Marker set, e.g. from `AnnularRingReport.markers()`
+    ///
+    /// # Returns
+    /// * `"markers_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn show_drc_markers(&mut self, markers: &DrcMarkers) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_markers(markers)?;
+            Ok("markers_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Remove all DRC marker rings
+    ///
+    /// # Returns
+    /// * `"markers_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn clear_drc_markers(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_markers();
+            Ok("markers_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Find the fiducials of a copper layer for assembly setup checks
+    ///
+    /// Pads flashed with a FiducialPad aperture are always found. Without
+    /// the attribute, a pad counts when it is round, 0.5 to 3 mm across,
+    /// sits in a round mask opening at least 0.25 mm wider all around and
+    /// has neither a drill hit nor a draw ending on it.
+    ///
+    /// # Arguments
+    /// * `copper_layer_id` - Layer ID of an outer copper layer
+    /// * `mask_layer_id` - Layer ID of the solder mask of the same side;
+    ///   without it only attributed fiducials are found
+    /// * `drill_layer_id` - Layer ID of a drill layer, if any
+    ///
+    /// # Returns
+    /// * `FiducialList` with the position, pad and opening size of each fiducial
+    pub fn get_fiducials(
+        &self,
+        copper_layer_id: u32,
+        mask_layer_id: Option<u32>,
+        drill_layer_id: Option<u32>,
+    ) -> Result<FiducialList, JsValue> {
+        let mask = match mask_layer_id {
+            Some(id) => Some(self.layers.get(id as usize)?),
+            None => None,
+        };
+        let drill = match drill_layer_id {
+            Some(id) => Some(self.layers.get(id as usize)?),
+            None => None,
+        };
+        Ok(find_fiducials(
+            self.layers.get(copper_layer_id as usize)?,
+            mask,
+            drill,
+        ))
+    }
+
+    /// Mark fiducials with rings on top of the layers
+    ///
+    /// Shown apart from the DRC markers, in their own color; replaces any
+    /// fiducials shown before.
+    ///
+    /// # Arguments
+    /// * `fiducials` - Fiducials from get_fiducials()
+    ///
+    /// # Returns
+    /// * `"fiducials_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn show_fiducials(&mut self, fiducials: &FiducialList) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_fiducials(&fiducials.markers())?;
+            Ok("fiducials_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Remove the fiducial markers
+    ///
+    /// # Returns
+    /// * `"fiducials_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn hide_fiducials(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_fiducials();
+            Ok("fiducials_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Find the test points of a copper layer for test-engineering review
+    ///
+    /// Pads flashed with a TestPad aperture (.AperFunction,TestPad or
+    /// TestPoint) are always found. Without the attribute, a pad counts when
+    /// it is round, 0.6 to 2 mm across, exposed by a mask opening at least
+    /// its size, has a draw ending on it, no drill hit and at least 1 mm
+    /// clearance to the next pad. Pads attributed as any other kind of pad
+    /// are skipped.
+    ///
+    /// # Arguments
+    /// * `copper_layer_id` - Layer ID of an outer copper layer
+    /// * `mask_layer_id` - Layer ID of the solder mask of the same side;
+    ///   without it only attributed test points are found
+    /// * `drill_layer_id` - Layer ID of a drill layer, if any
+    ///
+    /// # Returns
+    /// * `TestPointList` with the position and pad size of each test point
+    pub fn get_test_points(
+        &self,
+        copper_layer_id: u32,
+        mask_layer_id: Option<u32>,
+        drill_layer_id: Option<u32>,
+    ) -> Result<TestPointList, JsValue> {
+        let mask = match mask_layer_id {
+            Some(id) => Some(self.layers.get(id as usize)?),
+            None => None,
+        };
+        let drill = match drill_layer_id {
+            Some(id) => Some(self.layers.get(id as usize)?),
+            None => None,
+        };
+        Ok(find_test_points(
+            self.layers.get(copper_layer_id as usize)?,
+            mask,
+            drill,
+        ))
+    }
+
+    /// Mark test points with rings on top of the layers
+    ///
+    /// Shown apart from the DRC and fiducial markers, in their own color;
+    /// replaces any test points shown before.
+    ///
+    /// # Arguments
+    /// * `test_points` - Test points from get_test_points()
+    ///
+    /// # Returns
+    /// * `"test_points_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn show_test_points(&mut self, test_points: &TestPointList) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_test_points(&test_points.markers())?;
+            Ok("test_points_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Remove the test point markers
+    ///
+    /// # Returns
+    /// * `"test_points_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn hide_test_points(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_test_points();
+            Ok("test_points_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Infer drill hits for a job that lacks a drill file
+    ///
+    /// Round pads flashed at the same place on both outer copper layers are
+    /// taken for plated holes. A hole takes the diameter of a pad aperture's
+    /// hole where one is given, otherwise half the smaller pad rounded to
+    /// 0.05 mm, flagged as estimated. The result is a best guess: export it
+    /// with to_excellon() and preview it with show_inferred_drills().
+    ///
+    /// # Arguments
+    /// * `copper_top` - Layer ID of the top copper layer
+    /// * `copper_bottom` - Layer ID of the bottom copper layer
+    ///
+    /// # Returns
+    /// * `InferredDrills` with the position and diameter of each inferred hit
+    pub fn infer_drills(
+        &self,
+        copper_top: u32,
+        copper_bottom: u32,
+    ) -> Result<InferredDrills, JsValue> {
+        Ok(infer_drills(
+            self.layers.get(copper_top as usize)?,
+            self.layers.get(copper_bottom as usize)?,
+        ))
+    }
+
+    /// Preview inferred drill hits as translucent holes on top of the layers
+    ///
+    /// Drawn in their own color so they are not mistaken for a loaded drill
+    /// layer; replaces any inferred drills shown before.
+    ///
+    /// # Arguments
+    /// * `drills` - Drill hits from infer_drills()
+    ///
+    /// # Returns
+    /// * `"inferred_drills_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn show_inferred_drills(&mut self, drills: &InferredDrills) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_inferred_drills(&drills.markers())?;
+            Ok("inferred_drills_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Remove the inferred drill preview
+    ///
+    /// # Returns
+    /// * `"inferred_drills_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn hide_inferred_drills(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_inferred_drills();
+            Ok("inferred_drills_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Group the copper of layers into nets by touching geometry
+    ///
+    /// Flashes, draws and regions that overlap or touch on a layer share a
+    /// net; drill hits join the copper they pass through on every layer. No
+    /// X2 netlist attributes are needed, so this works for legacy files too.
+    ///
+    /// # Arguments
+    /// * `copper_layer_ids` - Copper layers to analyze
+    /// * `drill_layer_ids` - Drill layers whose hits connect the copper layers
+    ///
+    /// # Returns
+    /// * `Connectivity` with the net of every feature; use `net_at()` to find the net under a click
+    pub fn compute_connectivity(
+        &self,
+        copper_layer_ids: Vec<u32>,
+        drill_layer_ids: Vec<u32>,
+    ) -> Result<Connectivity, JsValue> {
+        let copper = copper_layer_ids
+            .iter()
+            .map(|&id| Ok((id, self.layers.get(id as usize)?)))
+            .collect::<Result<Vec<_>, ViewerError>>()?;
+        let drills = drill_layer_ids
+            .iter()
+            .map(|&id| self.layers.get(id as usize))
+            .collect::<Result<Vec<_>, ViewerError>>()?;
+        Ok(connectivity(
+            &copper,
+            &drills,
+            self.parse_options.chord_error,
+        ))
+    }
+
+    /// Highlight all copper of one net on top of the layers
+    ///
+    /// Replaces any net shown before. The highlight is dropped when one of
+    /// the analyzed layers is removed.
+    ///
+    /// # Arguments
+    /// * `connectivity` - Nets from compute_connectivity()
+    /// * `net` - Net number, e.g. from `Connectivity.net_at()`
+    ///
+    /// # Returns
+    /// * `"net_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn show_net(&mut self, connectivity: &Connectivity, net: u32) -> Result<String, JsValue> {
+        if net >= connectivity.net_count {
+            return Err(ViewerError::invalid_argument(format!(
+                "Net {} does not exist ({} nets)",
+                net, connectivity.net_count
+            ))
+            .into());
+        }
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_net(connectivity, net)?;
+            Ok("net_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Remove the net highlight
+    ///
+    /// # Returns
+    /// * `"net_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn hide_net(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_net();
+            Ok("net_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Show which drill hits connect two copper layers (via stitching map)
+    ///
+    /// Every drill hit of the analysis is drawn as a dot; hits landing on
+    /// copper of both layers are drawn bright, the others dimmed. Replaces
+    /// any map shown before and is dropped when either layer is removed.
+    ///
+    /// # Arguments
+    /// * `connectivity` - Nets from compute_connectivity()
+    /// * `layer_a`, `layer_b` - Two of the analyzed copper layers
+    ///
+    /// # Returns
+    /// * `"via_stitching_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn show_via_stitching(
+        &mut self,
+        connectivity: &Connectivity,
+        layer_a: u32,
+        layer_b: u32,
+    ) -> Result<String, JsValue> {
+        let mut connected = vec![false; connectivity.hit_x.len()];
+        for hit in connectivity.stitching(layer_a, layer_b)? {
+            connected[hit as usize] = true;
+        }
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_via_stitching(
+                [layer_a as usize, layer_b as usize],
+                connectivity,
+                &connected,
+            )?;
+            Ok("via_stitching_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Remove the via stitching map
+    ///
+    /// # Returns
+    /// * `"via_stitching_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn hide_via_stitching(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_via_stitching();
+            Ok("via_stitching_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Outline the component courtyards of one board side with dashed lines
+    ///
+    /// Uses the draws of ComponentOutline,Courtyard apertures in the loaded
+    /// X3 component layers of that side (%TF.FileFunction,Component,L1,Top
+    /// or Component,L2,Bot). Each side is shown and hidden on its own;
+    /// showing a side again picks up layers loaded since. A side is hidden
+    /// when one of its component layers is removed.
+    ///
+    /// # Arguments
+    /// * `side` - "top" or "bottom"
+    ///
+    /// # Returns
+    /// * `"courtyards_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn show_courtyards(&mut self, side: &str) -> Result<String, JsValue> {
+        let side = Self::component_side(side)?;
+        let layer_ids: Vec<usize> = self
+            .layers
+            .iter()
+            .map(|(id, _)| id)
+            .filter(|&id| {
+                self.layers
+                    .info(id)
+                    .is_ok_and(|info| ComponentSide::of_function(&info.file_function) == Some(side))
+            })
+            .collect();
+        let layers = layer_ids
+            .iter()
+            .map(|&id| self.layers.get(id))
+            .collect::<Result<Vec<_>, _>>()?;
+        let lines = courtyard_lines(layers.into_iter().flatten());
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_courtyards(side, &layer_ids, &lines)?;
+            Ok("courtyards_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Remove the courtyard outlines of one board side
+    ///
+    /// # Arguments
+    /// * `side` - "top" or "bottom"
+    ///
+    /// # Returns
+    /// * `"courtyards_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn hide_courtyards(&mut self, side: &str) -> Result<String, JsValue> {
+        let side = Self::component_side(side)?;
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_courtyards(side);
+            Ok("courtyards_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Annotate the board with its overall width and height
+    ///
+    /// Dimension lines with arrowheads and the size in mm are drawn below
+    /// and to the right of the board outline's extent, so captured images
+    /// carry the board size. The annotation is hidden when the outline layer
+    /// is removed.
+    ///
+    /// # Arguments
+    /// * `outline_layer_id` - Board outline layer (None = the loaded layer
+    ///   with %TF.FileFunction,Profile)
+    ///
+    /// # Returns
+    /// * `"dimensions_done"` signal on success
+    ///
+    /// # Errors
+    /// * No outline layer was given or found, or it has no features
+    #[cfg(feature = "renderer")]
+    pub fn show_board_dimensions(
+        &mut self,
+        outline_layer_id: Option<u32>,
+    ) -> Result<String, JsValue> {
+        let layer_id = match outline_layer_id {
+            Some(id) => id as usize,
+            None => self
+                .layers
+                .iter()
+                .map(|(id, _)| id)
+                .find(|&id| {
+                    self.layers
+                        .info(id)
+                        .is_ok_and(|info| info.file_function.starts_with("Profile"))
+                })
+                .ok_or_else(|| {
+                    ViewerError::invalid_argument(
+                        "No board outline layer (%TF.FileFunction,Profile) is loaded",
+                    )
+                })?,
+        };
+        let board = Boundary::combined(self.layers.get(layer_id)?);
+        if !(board.max_x > board.min_x && board.max_y > board.min_y) {
+            return Err(ViewerError::invalid_layer(
+                "Board outline layer is empty",
+                layer_id as u32,
+            )
+            .into());
+        }
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_dimensions(layer_id, &board)?;
+            Ok("dimensions_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Remove the board width and height annotation
+    ///
+    /// # Returns
+    /// * `"dimensions_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn hide_board_dimensions(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_dimensions();
+            Ok("dimensions_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Show a minimap of the whole board in a canvas corner
+    ///
+    /// The minimap always fits all loaded layers in their render colors and
+    /// frames the area the camera currently shows. Calling it again moves or
+    /// resizes the minimap.
+    ///
+    /// # Arguments
+    /// * `corner` - 0 bottom-left, 1 bottom-right, 2 top-left, 3 top-right
+    /// * `size` - Side length as a fraction of the shorter canvas side (0..1]
+    ///
+    /// # Returns
+    /// * `"minimap_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn show_minimap(&mut self, corner: u32, size: f32) -> Result<String, JsValue> {
+        if corner > 3 {
+            return Err(ViewerError::invalid_argument("Minimap corner must be 0 to 3").into());
+        }
+        if !(size > 0.0 && size <= 1.0) {
+            return Err(ViewerError::invalid_argument("Minimap size must be in (0, 1]").into());
+        }
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_minimap(corner, size);
+            Ok("minimap_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Remove the minimap
+    ///
+    /// # Returns
+    /// * `"minimap_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn hide_minimap(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_minimap();
+            Ok("minimap_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Cut the board stackup along a line
+    ///
+    /// Stackup layers linked to loaded layers (Stackup.assign_layer) show where
+    /// their material is present along the cut; other layers span all of it.
+    ///
+    /// # Arguments
+    /// * `stackup` - Board stackup, e.g. from `Stackup.from_job()`
+    /// * `x0`, `y0` - Start of the cut line in mm
+    /// * `x1`, `y1` - End of the cut line in mm
+    ///
+    /// # Returns
+    /// * `CrossSection` with the material segments of each stackup layer
+    pub fn cross_section(
+        &self,
+        stackup: &Stackup,
+        x0: f32,
+        y0: f32,
+        x1: f32,
+        y1: f32,
+    ) -> Result<CrossSection, JsValue> {
+        let (artwork, _) = Self::stackup_artwork(&self.layers, stackup, None)?;
+        Ok(cross_section(stackup, &artwork, (x0, y0), (x1, y1)))
+    }
+
+    /// Show a cross-section as a strip along the bottom of the canvas
+    ///
+    /// The cut line is drawn on the board. Replaces any cross-section shown before.
+    ///
+    /// # Arguments
+    /// * `section` - Cross-section from cross_section()
+    ///
+    /// # Returns
+    /// * `"cross_section_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn show_cross_section(&mut self, section: &CrossSection) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_cross_section(section)?;
+            Ok("cross_section_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Remove the cross-section strip and cut line
+    ///
+    /// # Returns
+    /// * `"cross_section_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn hide_cross_section(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_cross_section();
+            Ok("cross_section_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Switch render() to an interactive 3D view of the extruded stackup
+    ///
+    /// Layers are extruded as in export_gltf() and drawn with a perspective
+    /// camera orbiting the board; move it with set_orbit(). Extruded layers
+    /// follow the visibility of their linked layer in render().
+    ///
+    /// # Arguments
+    /// * `stackup` - Board stackup with linked layers
+    /// * `layer_separation` - Extra gap between stackup layers in mm (exploded view)
+    /// * `outline_layer_id` - Layer giving the board extent (None = all linked layers)
+    ///
+    /// # Returns
+    /// * `"view3d_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn show_3d(
+        &mut self,
+        stackup: &Stackup,
+        layer_separation: f32,
+        outline_layer_id: Option<u32>,
+    ) -> Result<String, JsValue> {
+        let chord_error = self.parse_options.chord_error;
+        if let Some(renderer) = &mut self.renderer {
+            let (meshes, board) = {
+                let (artwork, board) =
+                    Self::stackup_artwork(&self.layers, stackup, outline_layer_id)?;
+                let meshes = extrude_stackup(
+                    stackup,
+                    &artwork,
+                    &board,
+                    layer_separation.max(0.0),
+                    chord_error,
+                )
+                .map_err(ViewerError::invalid_argument)?;
+                (meshes, board)
+            };
+            renderer.set_3d_view(&meshes, &board)?;
+            Ok("view3d_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Return render() to the normal 2D view
+    ///
+    /// # Returns
+    /// * `"view3d_clear_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn hide_3d(&mut self) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.clear_3d_view();
+            Ok("view3d_clear_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Whether render() draws the 3D view
+    #[cfg(feature = "renderer")]
+    pub fn is_3d(&self) -> bool {
+        self.renderer.as_ref().is_some_and(|r| r.is_3d_view())
+    }
+
+    /// Move the 3D orbit camera (takes effect on the next render())
+    ///
+    /// # Arguments
+    /// * `yaw` - Angle around the board normal in degrees (0 = eye on the +X side)
+    /// * `pitch` - Eye elevation in degrees, negative to look from below
+    /// * `distance` - Eye distance from the orbit center in mm
+    /// * `target_x`, `target_y` - Orbit center on the board in mm
+    ///
+    /// # Returns
+    /// * `"orbit_done"` signal on success
+    #[cfg(feature = "renderer")]
+    pub fn set_orbit(
+        &mut self,
+        yaw: f32,
+        pitch: f32,
+        distance: f32,
+        target_x: f32,
+        target_y: f32,
+    ) -> Result<String, JsValue> {
+        if let Some(renderer) = &mut self.renderer {
+            renderer.set_orbit(yaw, pitch, distance, target_x, target_y)?;
+            Ok("orbit_done".to_string())
+        } else {
+            Err(ViewerError::not_initialized().into())
+        }
+    }
+
+    /// Current 3D orbit as [yaw, pitch, distance, target_x, target_y]
+    ///
+    /// # Returns
+    /// * Orbit values (Float32Array), empty when the 3D view is not active
+    #[cfg(feature = "renderer")]
+    pub fn get_orbit(&self) -> Vec<f32> {
+        self.renderer
+            .as_ref()
+            .and_then(|r| r.orbit())
+            .map_or_else(Vec::new, |orbit| orbit.to_vec())
+    }
+}
+
+impl GerberProcessor {
+    /// Parser configured with the processor's shared settings
+    /// Replay of a layer added with add_layer_replay()
+    fn replay(&self, layer_id: u32) -> Result<&Replay, ViewerError> {
+        self.replays.get(&layer_id).ok_or_else(|| {
+            ViewerError::invalid_layer(
+                format!("Layer {} was not added with add_layer_replay()", layer_id),
+                layer_id,
+            )
+        })
+    }
+
+    fn parser(&self) -> GerberParser {
+        let mut parser = GerberParser::new();
+        parser.set_aperture_cache(self.aperture_cache.clone());
+        parser.set_options(&self.parse_options);
+        parser
+    }
+
+    /// Outliers of some layers (all layers for None)
+    fn boundary_outliers(
+        &self,
+        layer_ids: Option<&[u32]>,
+    ) -> Result<BoundaryOutliers, ViewerError> {
+        let layers = match layer_ids {
+            Some(layer_ids) => layer_ids
+                .iter()
+                .map(|&id| Ok((id, self.layers.get(id as usize)?)))
+                .collect::<Result<Vec<_>, ViewerError>>()?,
+            None => self
+                .layers
+                .iter()
+                .map(|(id, sublayers)| (id as u32, sublayers))
+                .collect(),
+        };
+        Ok(find_outliers(&layers, self.plain_boundary(layer_ids)?))
+    }
+
+    /// Combined boundary of some layers (all layers for None), without
+    /// outliers when robust boundaries are on
+    fn fit_boundary(&self, layer_ids: Option<&[u32]>) -> Result<Boundary, ViewerError> {
+        if self.robust_boundary {
+            Ok(self.boundary_outliers(layer_ids)?.boundary)
+        } else {
+            self.plain_boundary(layer_ids)
+        }
+    }
+
+    fn plain_boundary(&self, layer_ids: Option<&[u32]>) -> Result<Boundary, ViewerError> {
+        match layer_ids {
+            Some(layer_ids) => {
+                let layer_ids: Vec<usize> = layer_ids.iter().map(|&id| id as usize).collect();
+                self.layers.boundary_of(&layer_ids)
+            }
+            None => Ok(self.layers.boundary()),
+        }
+    }
+
+    /// Parse Gerber text and add it as a new layer
+    ///
+    /// `simplify_tolerance` overrides the tolerance of the parse options,
+    /// `unit` the unit declared by the file.
+    fn add_layer_text(
+        &mut self,
+        content: &str,
+        simplify_tolerance: Option<f32>,
+        unit: Option<Unit>,
+    ) -> Result<u32, JsValue> {
+        let hash = content_hash(content.as_bytes());
+        if let Some(layer_id) = self.layer_with_hash(&hash) {
+            return Ok(layer_id);
+        }
+
+        // Parse Gerber content to get Vec<GerberData> (one per polarity layer)
+        let started = self.events.now();
+        let mut parser = self.parser();
+        if let Some(tolerance) = simplify_tolerance {
+            parser.set_simplify_tolerance(tolerance);
+        }
+        if let Some(unit) = unit {
+            parser.force_unit(unit);
+        }
+        let gerber_data_layers = parser.parse(content)?;
+        let layer_id = self.insert_layer(gerber_data_layers, hash, parser.layer_info())?;
+        let sublayers = self.layers.get(layer_id as usize)?;
+        self.events
+            .parse_complete(layer_id, sublayers, parser.warnings(), Some(started));
+        Ok(layer_id)
+    }
+
+    /// Combine the images of two layers and add the result as a new layer
+    ///
+    /// Like panelize(), the result goes through Gerber text, so it gets a
+    /// content hash and is exported like any loaded file.
+    fn add_combined_layer(
+        &mut self,
+        a: u32,
+        b: u32,
+        operation: LayerOperation,
+    ) -> Result<u32, JsValue> {
+        let content = combine_layers(
+            self.layers.get(a as usize)?,
+            self.layers.get(b as usize)?,
+            operation,
+            self.parse_options.chord_error,
+        )
+        .map_err(ViewerError::invalid_argument)?;
+        self.add_layer(content)
+    }
+
+    /// Artwork linked to each stackup layer, and the board extent
+    ///
+    /// The extent comes from the outline layer when given, otherwise from all
+    /// linked layers.
+    fn stackup_artwork<'a>(
+        layers: &'a LayerStore,
+        stackup: &Stackup,
+        outline_layer_id: Option<u32>,
+    ) -> Result<(Vec<LayerArtwork<'a>>, Boundary), JsValue> {
+        let artwork = stackup
+            .layers
+            .iter()
+            .map(|layer| layer.layer_id.map(|id| layers.get(id as usize)).transpose())
+            .collect::<Result<Vec<_>, ViewerError>>()?;
+
+        let board = match outline_layer_id {
+            Some(id) => Boundary::combined(layers.get(id as usize)?),
+            None => {
+                let linked: Vec<Boundary> = artwork
+                    .iter()
+                    .flatten()
+                    .map(|l| Boundary::combined(l))
+                    .collect();
+                Boundary::new(
+                    linked.iter().map(|b| b.min_x).fold(f32::INFINITY, f32::min),
+                    linked
+                        .iter()
+                        .map(|b| b.max_x)
+                        .fold(f32::NEG_INFINITY, f32::max),
+                    linked.iter().map(|b| b.min_y).fold(f32::INFINITY, f32::min),
+                    linked
+                        .iter()
+                        .map(|b| b.max_y)
+                        .fold(f32::NEG_INFINITY, f32::max),
+                )
+            }
+        };
+        Ok((artwork, board))
+    }
+
+    /// Fail for layers still loading with add_layer_progressive()
+    fn check_loaded(&self, layer_id: u32) -> Result<(), ViewerError> {
+        if self.loading.contains_key(&layer_id) {
+            return Err(ViewerError::invalid_layer(
+                format!("Layer {} is still loading", layer_id),
+                layer_id,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Apply `f` to every sublayer of a layer and rebuild its GPU buffers
+    fn transform_layer(
+        &mut self,
+        layer_id: u32,
+        f: impl Fn(&mut GerberData),
+    ) -> Result<(), JsValue> {
+        let mut sublayers = self.layers.get(layer_id as usize)?.to_vec();
+        sublayers.iter_mut().for_each(f);
+        self.layers.replace(layer_id as usize, sublayers)?;
+        #[cfg(feature = "renderer")]
+        if let Some(renderer) = &mut self.renderer {
+            renderer.reset_layer_buffers(layer_id as usize)?;
+        }
+        #[cfg(feature = "webgpu")]
+        if let Some(webgpu) = &mut self.webgpu {
+            webgpu.reset_layer_buffers(layer_id as usize);
+        }
+        Ok(())
+    }
+
+    /// Component side named by a `side` argument
+    #[cfg(feature = "renderer")]
+    fn component_side(side: &str) -> Result<ComponentSide, ViewerError> {
+        ComponentSide::from_name(side).ok_or_else(|| {
+            ViewerError::invalid_argument(format!(
+                "Unknown component side: {} (expected \"top\" or \"bottom\")",
+                side
+            ))
+        })
+    }
+
+    /// Color of a layer in the last render(), black if it was not visible
+    fn layer_color(&self, layer_id: u32) -> [f32; 3] {
+        self.view
+            .as_ref()
+            .and_then(|view| {
+                let index = view
+                    .active_layer_ids
+                    .iter()
+                    .position(|&id| id == layer_id)?;
+                let rgb = view.colors.get(index * 3..index * 3 + 3)?;
+                Some([rgb[0], rgb[1], rgb[2]])
+            })
+            .unwrap_or([0.0, 0.0, 0.0])
+    }
+
+    /// Jump to a view fitting `layer_id` on the next render() when auto_fit
+    /// is on and no other layer has geometry
+    #[cfg(feature = "renderer")]
+    fn fit_first_layer(&mut self, layer_id: u32) -> Result<(), ViewerError> {
+        let first = self
+            .layers
+            .iter()
+            .all(|(id, sublayers)| id == layer_id as usize || sublayers.is_empty());
+        if !(self.render_options.auto_fit && first) {
+            return Ok(());
+        }
+        let b = self.fit_boundary(Some(&[layer_id]))?;
+        // Without a renderer there is no canvas size to fit to yet
+        if let Ok(view) = self.zoom_to_bounds(b.min_x, b.min_y, b.max_x, b.max_y) {
+            self.animation = Some(CameraAnimation::new(view, view, performance_now(), 0.0));
+        }
+        Ok(())
+    }
+
+    /// Current view of the camera animation, dropping it once it has arrived
+    #[cfg(feature = "renderer")]
+    fn animation_frame(&mut self) -> Option<CameraView> {
+        let (view, arrived) = self.animation.as_ref()?.view_at(performance_now());
+        if arrived {
+            self.animation = None;
+        }
+        Some(view)
+    }
+
+    /// Add the layers of an imported design, linking them into its stackup
+    ///
+    /// Layers are hashed by name and source content for session restore.
+    fn insert_design(
+        &mut self,
+        design: ImportedDesign,
+        content: &str,
+    ) -> Result<ImportedBoard, JsValue> {
+        let mut board = ImportedBoard {
+            layer_ids: Vec::new(),
+            names: Vec::new(),
+            functions: Vec::new(),
+            stackup: design.stackup,
+        };
+        for layer in design.layers {
+            if !layer.sublayers.iter().any(|l| l.has_geometry()) {
+                continue;
+            }
+            let hash = content_hash(format!("{}\n{}", layer.name, content).as_bytes());
+            let layer_id = self.insert_layer(layer.sublayers, hash, LayerInfo::default())?;
+            if let Some(index) = layer.stackup_index {
+                board.stackup.assign_layer(index, layer_id)?;
+            }
+            board.layer_ids.push(layer_id);
+            board.names.push(layer.name);
+            board.functions.push(layer.function);
+        }
+        Ok(board)
+    }
+
+    /// Loaded layer whose source has the given content hash
+    fn layer_with_hash(&self, hash: &str) -> Option<u32> {
+        if hash.is_empty() {
+            return None; // Unknown source (cache without hash)
+        }
+        self.layer_hashes
+            .iter()
+            .filter(|(_, h)| h.as_str() == hash)
+            .map(|(&id, _)| id)
+            .min()
+    }
+
+    /// Add parsed polarity sublayers to the renderer as a new layer
+    ///
+    /// Returns the existing layer instead if one has the same content hash.
+    fn insert_layer(
+        &mut self,
+        gerber_data_layers: Vec<GerberData>,
+        hash: String,
+        info: LayerInfo,
+    ) -> Result<u32, JsValue> {
+        if let Some(layer_id) = self.layer_with_hash(&hash) {
+            return Ok(layer_id);
+        }
+
+        // Filter out empty layers (layers with no geometry)
+        let non_empty_layers: Vec<_> = gerber_data_layers
+            .into_iter()
+            .filter(|layer| layer.has_geometry())
+            .collect();
+
+        // If no non-empty layers found, reject the file as invalid Gerber
+        if non_empty_layers.is_empty() {
+            return Err(ViewerError::parse_failed(
+                "File does not contain valid Gerber data (no geometry found)",
+            )
+            .into());
+        }
+
+        // Store the geometry, then allocate GPU resources under the same ID
+        let layer_index = self.layers.add(non_empty_layers);
+        self.layers.set_info(layer_index, info)?;
+        #[cfg(feature = "renderer")]
+        if let Some(renderer) = &mut self.renderer {
+            if let Err(e) = renderer.add_layer(layer_index) {
+                let _ = self.layers.remove(layer_index);
+                return Err(e);
+            }
+        }
+        self.next_layer_id += 1;
+        self.layer_hashes.insert(layer_index as u32, hash);
+        self.events.check_memory(self.layers.byte_size());
+        #[cfg(feature = "renderer")]
+        self.fit_first_layer(layer_index as u32)?;
+
+        // For now, layer_id matches layer_index
+        // In a more complex implementation, we could maintain a mapping
+        Ok(layer_index as u32)
+    }
 }
 
 // triangulate_polygon is accessed through parser module
@@ -0,0 +1,305 @@
+use crate::error::ViewerError;
+use crate::tessellation::DEFAULT_CHORD_ERROR;
+use wasm_bindgen::prelude::*;
+
+/// Quality settings for parsing layers
+///
+/// Applied to layers added after GerberProcessor.set_parse_options(); layers
+/// already loaded keep the geometry they were parsed with.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParseOptions {
+    pub(crate) chord_error: f32,
+    pub(crate) simplify_tolerance: f32,
+    pub(crate) high_precision: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            chord_error: DEFAULT_CHORD_ERROR,
+            simplify_tolerance: 0.0,
+            high_precision: false,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl ParseOptions {
+    /// Create parse options with the default settings
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> ParseOptions {
+        ParseOptions::default()
+    }
+
+    /// Maximum deviation (mm) of curves flattened for aperture macros,
+    /// boolean operations and 3D models (default 0.005)
+    ///
+    /// Sets the tessellation density: circles get as many segments as needed
+    /// to stay within it, from 8 for tiny pads to 1024 for huge arcs. PDF
+    /// export applies it on paper, so arcs get finer with the plot scale.
+    #[wasm_bindgen(getter)]
+    pub fn chord_error(&self) -> f32 {
+        self.chord_error
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_chord_error(&mut self, max_error: f32) {
+        self.chord_error = max_error;
+    }
+
+    /// Douglas-Peucker tolerance (mm) for region outlines (default 0 = exact)
+    #[wasm_bindgen(getter)]
+    pub fn simplify_tolerance(&self) -> f32 {
+        self.simplify_tolerance
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_simplify_tolerance(&mut self, tolerance: f32) {
+        self.simplify_tolerance = tolerance;
+    }
+
+    /// Convert and accumulate coordinates in f64 (default false)
+    ///
+    /// Avoids rounding drift in long incremental (G91) paths and on very
+    /// large boards; geometry is still stored as f32.
+    #[wasm_bindgen(getter)]
+    pub fn high_precision(&self) -> bool {
+        self.high_precision
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_high_precision(&mut self, enabled: bool) {
+        self.high_precision = enabled;
+    }
+}
+
+impl ParseOptions {
+    pub fn validate(&self) -> Result<(), JsValue> {
+        if !(self.chord_error.is_finite() && self.chord_error > 0.0) {
+            return Err(
+                ViewerError::invalid_argument("Chord error must be a positive number").into(),
+            );
+        }
+        if !(self.simplify_tolerance.is_finite() && self.simplify_tolerance >= 0.0) {
+            return Err(ViewerError::invalid_argument(
+                "Simplify tolerance must be a non-negative number",
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Quality and camera settings for the WebGL renderer
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct RenderOptions {
+    pub(crate) msaa_samples: u32,
+    pub(crate) min_feature_pixels: f32,
+    pub(crate) min_stroke_pixels: f32,
+    pub(crate) auto_fit: bool,
+    pub(crate) double_buffer: bool,
+}
+
+#[wasm_bindgen]
+impl RenderOptions {
+    /// Create render options with the default settings
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> RenderOptions {
+        RenderOptions::default()
+    }
+
+    /// Antialiasing: multisample count for layer rendering (default 0 = off)
+    ///
+    /// Clamped to what the GPU supports.
+    #[wasm_bindgen(getter)]
+    pub fn msaa_samples(&self) -> u32 {
+        self.msaa_samples
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_msaa_samples(&mut self, samples: u32) {
+        self.msaa_samples = samples;
+    }
+
+    /// Level of detail: flashes and arcs smaller than this many pixels on
+    /// screen are skipped (default 0 = draw everything)
+    #[wasm_bindgen(getter)]
+    pub fn min_feature_pixels(&self) -> f32 {
+        self.min_feature_pixels
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_min_feature_pixels(&mut self, pixels: f32) {
+        self.min_feature_pixels = pixels;
+    }
+
+    /// Thin features: draws, arcs and pads narrower than this many pixels on
+    /// screen are widened to it, so hairline silkscreen strokes do not break
+    /// up into dashes when zoomed out (default 0 = true width)
+    ///
+    /// Only the WebGL renderer widens features; exported images keep their
+    /// true size.
+    #[wasm_bindgen(getter)]
+    pub fn min_stroke_pixels(&self) -> f32 {
+        self.min_stroke_pixels
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_min_stroke_pixels(&mut self, pixels: f32) {
+        self.min_stroke_pixels = pixels;
+    }
+
+    /// Fit the camera to the first layer added to an empty viewer on the
+    /// next render(), like animate_to() with no duration (default false)
+    #[wasm_bindgen(getter)]
+    pub fn auto_fit(&self) -> bool {
+        self.auto_fit
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_auto_fit(&mut self, enabled: bool) {
+        self.auto_fit = enabled;
+    }
+
+    /// Composite each frame off screen and copy it to the canvas once it is
+    /// complete, so a failed render() leaves the previous frame on screen
+    /// instead of a partial one (default false)
+    ///
+    /// Costs one canvas-sized texture. WebGPU frames are always presented
+    /// whole; the 3D view draws to the canvas directly.
+    #[wasm_bindgen(getter)]
+    pub fn double_buffer(&self) -> bool {
+        self.double_buffer
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_double_buffer(&mut self, enabled: bool) {
+        self.double_buffer = enabled;
+    }
+}
+
+impl RenderOptions {
+    pub fn validate(&self) -> Result<(), JsValue> {
+        if !(self.min_feature_pixels.is_finite() && self.min_feature_pixels >= 0.0) {
+            return Err(ViewerError::invalid_argument(
+                "Minimum feature size must be a non-negative number",
+            )
+            .into());
+        }
+        if !(self.min_stroke_pixels.is_finite() && self.min_stroke_pixels >= 0.0) {
+            return Err(ViewerError::invalid_argument(
+                "Minimum stroke width must be a non-negative number",
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+/// Which primitives of a layer are drawn
+///
+/// Set with GerberProcessor.set_filter(); the default shows everything.
+/// Predicates combine: a primitive is drawn only if it passes all of them.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PrimitiveFilter {
+    pub(crate) flashes: bool,
+    pub(crate) draws: bool,
+    pub(crate) regions: bool,
+    pub(crate) min_aperture: f32,
+    pub(crate) conductors_only: bool,
+}
+
+impl Default for PrimitiveFilter {
+    fn default() -> Self {
+        PrimitiveFilter {
+            flashes: true,
+            draws: true,
+            regions: true,
+            min_aperture: 0.0,
+            conductors_only: false,
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl PrimitiveFilter {
+    /// Create a filter that shows every primitive
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> PrimitiveFilter {
+        PrimitiveFilter::default()
+    }
+
+    /// Draw D03 flashes, including block apertures (default true)
+    #[wasm_bindgen(getter)]
+    pub fn flashes(&self) -> bool {
+        self.flashes
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_flashes(&mut self, enabled: bool) {
+        self.flashes = enabled;
+    }
+
+    /// Draw D01 lines and arcs (default true)
+    #[wasm_bindgen(getter)]
+    pub fn draws(&self) -> bool {
+        self.draws
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_draws(&mut self, enabled: bool) {
+        self.draws = enabled;
+    }
+
+    /// Draw G36/G37 regions (default true)
+    #[wasm_bindgen(getter)]
+    pub fn regions(&self) -> bool {
+        self.regions
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_regions(&mut self, enabled: bool) {
+        self.regions = enabled;
+    }
+
+    /// Hide flashes and draws whose aperture is smaller than this (mm,
+    /// default 0 = no limit)
+    ///
+    /// A flashed aperture measures its larger side, a draw its line width.
+    /// Regions have no aperture and are not affected.
+    #[wasm_bindgen(getter)]
+    pub fn min_aperture(&self) -> f32 {
+        self.min_aperture
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_min_aperture(&mut self, size: f32) {
+        self.min_aperture = size;
+    }
+
+    /// Draw only primitives with the Conductor .AperFunction (default false)
+    #[wasm_bindgen(getter)]
+    pub fn conductors_only(&self) -> bool {
+        self.conductors_only
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_conductors_only(&mut self, enabled: bool) {
+        self.conductors_only = enabled;
+    }
+}
+
+impl PrimitiveFilter {
+    pub fn validate(&self) -> Result<(), JsValue> {
+        if !(self.min_aperture.is_finite() && self.min_aperture >= 0.0) {
+            return Err(ViewerError::invalid_argument(
+                "Minimum aperture size must be a non-negative number",
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
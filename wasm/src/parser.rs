@@ -1,84 +1,166 @@
 mod aperture;
 mod aperture_macro;
+mod attributes;
+mod builder;
+mod encoding;
 pub mod geometry;
+mod lint;
 mod state;
 
 // Export only what's needed externally
-pub use aperture::Aperture;
+pub use aperture::{Aperture, ApertureCache, ApertureShape, APERTURE_SHAPE_COUNT};
+#[cfg(feature = "fuzzing")]
+pub use aperture_macro::evaluate_expression;
+pub use attributes::{ApertureFunction, PrimitiveKind, PrimitiveTag, APERTURE_FUNCTION_COUNT};
+pub use builder::{GerberDataBuilder, PolarityLayers};
+pub use encoding::decode_gerber_bytes;
+pub use lint::{LintFinding, LintReport, LintRule};
 pub use state::{FormatSpec, ParserState, Polarity};
 
 // Internal use only
-use aperture::parse_aperture;
+use aperture::{parse_ab, parse_aperture};
 use aperture_macro::{parse_macro, ApertureMacro};
-use state::{parse_format_spec, parse_if, parse_lm, parse_lp, parse_ls, parse_mo, parse_sr};
-
-use self::geometry::{parse_graphic_command, Primitive};
-use crate::shape::{Arcs, Boundary, Circles, GerberData, Thermals, Triangles};
+use attributes::{
+    current_tag, parse_attribute_comment, parse_ta, parse_td, parse_tf, record_aperture_function,
+};
+use lint::{lint_findings, lint_line};
+use state::{
+    parse_format_spec, parse_ip, parse_lm, parse_lp, parse_lr, parse_ls, parse_mo, parse_sr,
+};
+
+use self::geometry::{close_region, parse_graphic_command, Primitive};
+use crate::error::{ErrorCode, ViewerError};
+use crate::layer_info::{LayerInfo, Unit, UnitSource};
+use crate::options::ParseOptions;
+use crate::shape::GerberData;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::mem::take;
-use wasm_bindgen::prelude::*;
+use std::rc::Rc;
 
 // Security limits for resource consumption
 const MAX_TOTAL_PRIMITIVES: usize = 70_000_000; // 70 million total primitives max
 
+// Primitives collected before they are packed into GerberData arrays
+pub const DEFAULT_BATCH_SIZE: usize = 16_384;
+
 /// Gerber parser with stateful aperture and macro storage
 pub struct GerberParser {
-    pub apertures: HashMap<String, Aperture>,
+    // Apertures by D-code as defined so far. Objects look their aperture up
+    // when drawn, so a D-code may be selected before its %ADD and a later
+    // %ADD for the same D-code replaces it for the objects that follow.
+    pub apertures: HashMap<String, Rc<Aperture>>,
     pub macros: HashMap<String, ApertureMacro>,
     pub current_state: ParserState,
-    // Store layers by polarity - [pos, neg, pos, neg, ...]
-    pub positive_layers: Vec<Vec<Primitive>>,
-    pub negative_layers: Vec<Vec<Primitive>>,
-    pub current_primitives: Vec<Primitive>, // Accumulating primitives for current polarity
+    // Sublayers by polarity, packed as parsing goes - [pos, neg, pos, neg, ...]
+    pub layers: PolarityLayers,
     pub region_contours: Vec<Vec<[f32; 2]>>, // Contour points collected in Region mode
-    total_primitives: usize, // Track total primitives for security limit
+    batch_size: usize,                       // Pending primitives that trigger packing
+    aperture_cache: Option<Rc<RefCell<ApertureCache>>>, // Apertures shared with other layers
 }
 
 impl GerberParser {
     /// Create new parser instance
     pub fn new() -> Self {
+        Self::with_batch_size(DEFAULT_BATCH_SIZE)
+    }
+
+    /// Create a parser that packs primitives every `batch_size` primitives
+    ///
+    /// Smaller batches lower peak memory and call on_batch more often;
+    /// larger batches pack with less per-batch overhead.
+    pub fn with_batch_size(batch_size: usize) -> Self {
         GerberParser {
             apertures: HashMap::new(),
             macros: HashMap::new(),
             current_state: ParserState::default(),
-            positive_layers: Vec::new(),
-            negative_layers: Vec::new(),
-            current_primitives: Vec::new(),
+            layers: PolarityLayers::default(),
             region_contours: Vec::new(),
-            total_primitives: 0,
+            batch_size: batch_size.max(1),
+            aperture_cache: None,
         }
     }
 
+    /// Share instantiated apertures with other parsers through `cache`
+    pub fn set_aperture_cache(&mut self, cache: Option<Rc<RefCell<ApertureCache>>>) {
+        self.aperture_cache = cache;
+    }
+
+    /// Simplify region outlines with Douglas-Peucker before triangulation
+    ///
+    /// Points closer than `tolerance` (mm) to the simplified outline are
+    /// dropped, which keeps huge copper pours cheap to triangulate and draw.
+    /// 0.0 (default) keeps outlines exact.
+    pub fn set_simplify_tolerance(&mut self, tolerance: f32) {
+        self.current_state.simplify_tolerance = tolerance.max(0.0);
+    }
+
+    /// Apply quality settings (chord error, simplification, f64 coordinates)
+    pub fn set_options(&mut self, options: &ParseOptions) {
+        self.current_state.chord_error = options.chord_error;
+        self.set_simplify_tolerance(options.simplify_tolerance);
+        self.current_state.high_precision = options.high_precision;
+    }
+
     /// Parse Gerber file content and return Vec of GerberData (one per polarity layer)
     /// Order: [pos_layer1, neg_layer1, pos_layer2, neg_layer2, ...]
-    pub fn parse(&mut self, data: &str) -> Result<Vec<GerberData>, JsValue> {
-        let lines: Vec<&str> = data.split('\n').collect();
-        let length = lines.len();
-        let mut i = 0;
+    pub fn parse(&mut self, data: &str) -> Result<Vec<GerberData>, ViewerError> {
+        self.parse_with(data, |_| {})
+    }
+
+    /// Parse Gerber file content, calling `on_batch` after each packed batch
+    pub fn parse_with(
+        &mut self,
+        data: &str,
+        mut on_batch: impl FnMut(&GerberParser),
+    ) -> Result<Vec<GerberData>, ViewerError> {
+        let mut lines = LineCursor::new(data);
+        self.parse_lines(&mut lines, usize::MAX, &mut on_batch)?;
+        self.finish()
+    }
 
-        while i < length {
-            let line_ref = lines[i].trim();
+    /// Parse up to `max_lines` lines from the cursor
+    ///
+    /// A multi-line extended command is always read to its end, even when it
+    /// runs past `max_lines`.
+    pub fn parse_lines(
+        &mut self,
+        lines: &mut LineCursor,
+        max_lines: usize,
+        on_batch: &mut impl FnMut(&GerberParser),
+    ) -> Result<(), ViewerError> {
+        for _ in 0..max_lines {
+            let Some(line) = lines.next() else {
+                break;
+            };
+            let line_ref = line.trim();
 
             if line_ref.is_empty() {
-                i += 1;
                 continue;
             }
+            lint_line(line_ref, &mut self.current_state, || lines.line());
+            // Stray control characters (NUL padding, DOS EOF) may trail the file
+            if self.current_state.end_of_file && !line_ref.chars().all(char::is_control) {
+                return Err(ViewerError::parse_failed("Content after M02 end of file")
+                    .with_line(lines.line()));
+            }
 
             if line_ref.starts_with('%') {
                 parse_command(
                     line_ref,
-                    &mut i,
-                    length,
-                    &lines,
+                    lines,
                     &mut self.current_state,
                     &mut self.apertures,
                     &mut self.macros,
-                    &mut self.current_primitives,
-                    &mut self.positive_layers,
-                    &mut self.negative_layers,
+                    &mut self.layers,
+                    &mut self.region_contours,
+                    self.aperture_cache.as_deref(),
                 );
             } else if line_ref.starts_with("G04") {
-                // Comment line, skip
+                // Comment line; `G04 #@! TA...*` carries a legacy X2 attribute
+                parse_attribute_comment(line_ref, &mut self.current_state);
+            } else if line_ref.starts_with("M02") {
+                self.current_state.end_of_file = true;
             } else if line_ref.starts_with('G')
                 || line_ref.starts_with('D')
                 || line_ref.starts_with('X')
@@ -86,290 +168,149 @@ impl GerberParser {
                 || line_ref.starts_with('I')
                 || line_ref.starts_with('J')
             {
+                let in_region = self.current_state.region_mode;
                 parse_graphic_command(
                     line_ref,
                     &mut self.current_state,
                     &self.apertures,
-                    &mut self.current_primitives,
+                    &mut self.layers.pending,
+                    &mut self.layers.flashes,
+                    &mut self.layers.traces,
                     &mut self.region_contours,
                 );
+                self.layers
+                    .tag_pending(current_tag(&self.current_state, in_region));
             }
 
-            i += 1;
+            // Block aperture contents stay pending until the block closes
+            if self.layers.pending.len() >= self.batch_size && self.current_state.blocks.is_empty()
+            {
+                self.layers.flush();
+                self.check_primitive_limit()
+                    .map_err(|e| e.with_line(lines.line()))?;
+                on_batch(self);
+            }
         }
+        Ok(())
+    }
 
-        // Save last accumulated primitives by polarity
-        if !self.current_primitives.is_empty() {
-            self.total_primitives += self.current_primitives.len();
-            if self.total_primitives > MAX_TOTAL_PRIMITIVES {
-                return Err(JsValue::from_str(&format!(
-                    "Too many total primitives: {} (max: {})",
-                    self.total_primitives,
-                    MAX_TOTAL_PRIMITIVES
-                )));
-            }
+    /// Pack the remaining primitives and return all polarity layers
+    /// Order: [pos_layer1, neg_layer1, pos_layer2, neg_layer2, ...]
+    pub fn finish(&mut self) -> Result<Vec<GerberData>, ViewerError> {
+        self.layers.flush();
+        self.check_primitive_limit()?;
+        let warnings = self.end_of_file_warnings();
+        self.current_state.warnings.extend(warnings);
+        let layers = take(&mut self.layers);
+        Ok(layers.finish(self.current_state.polarity))
+    }
 
-            if self.current_state.polarity == Polarity::Positive {
-                self.positive_layers
-                    .push(take(&mut self.current_primitives));
-            } else {
-                self.negative_layers
-                    .push(take(&mut self.current_primitives));
-            }
+    /// Copy of the layers parsed so far, including the unfinished one
+    /// Order: [pos_layer1, neg_layer1, pos_layer2, neg_layer2, ...]
+    pub fn snapshot(&mut self) -> Result<Vec<GerberData>, ViewerError> {
+        if self.current_state.blocks.is_empty() {
+            self.layers.flush();
         }
+        self.check_primitive_limit()?;
+        Ok(self.layers.snapshot(self.current_state.polarity))
+    }
 
-        // Convert each layer to individual GerberData
-        // Order: [pos_layer1, neg_layer1, pos_layer2, neg_layer2, ...]
-        let mut gerber_data_layers: Vec<GerberData> = Vec::new();
+    /// Problems that did not fail the parse, reported in the parse diagnostics
+    ///
+    /// Aperture definitions that can't be understood are listed as they are
+    /// read; a missing M02, an unclosed G36 region or unbalanced %AB blocks
+    /// are added by finish().
+    pub fn warnings(&self) -> &[String] {
+        &self.current_state.warnings
+    }
 
-        let max_layers = self.positive_layers.len().max(self.negative_layers.len());
-        for idx in 0..max_layers {
-            // Add positive layer
-            if idx < self.positive_layers.len() {
-                let gerber_data = Self::primitives_to_gerber_data(&self.positive_layers[idx]);
-                gerber_data_layers.push(gerber_data);
-            }
-            // Add negative layer
-            if idx < self.negative_layers.len() {
-                let gerber_data = Self::primitives_to_gerber_data(&self.negative_layers[idx]);
-                gerber_data_layers.push(gerber_data);
-            }
+    fn end_of_file_warnings(&self) -> Vec<String> {
+        let state = &self.current_state;
+        let mut warnings = Vec::new();
+        if !state.end_of_file {
+            warnings.push("Missing M02 end of file".to_string());
         }
-
-        Ok(gerber_data_layers)
-    }
-
-    /// Convert a vector of primitives to GerberData
-    fn primitives_to_gerber_data(primitives: &[Primitive]) -> GerberData {
-        let mut triangle_vertices: Vec<f32> = Vec::new();
-        let mut triangle_indices: Vec<u32> = Vec::new();
-        let mut triangle_hole_x: Vec<f32> = Vec::new();
-        let mut triangle_hole_y: Vec<f32> = Vec::new();
-        let mut triangle_hole_radius: Vec<f32> = Vec::new();
-        let mut circles_x: Vec<f32> = Vec::new();
-        let mut circles_y: Vec<f32> = Vec::new();
-        let mut circles_radius: Vec<f32> = Vec::new();
-        let mut circles_hole_x: Vec<f32> = Vec::new();
-        let mut circles_hole_y: Vec<f32> = Vec::new();
-        let mut circles_hole_radius: Vec<f32> = Vec::new();
-        let mut arcs_x: Vec<f32> = Vec::new();
-        let mut arcs_y: Vec<f32> = Vec::new();
-        let mut arcs_radius: Vec<f32> = Vec::new();
-        let mut arcs_start_angle: Vec<f32> = Vec::new();
-        let mut arcs_sweep_angle: Vec<f32> = Vec::new();
-        let mut arcs_thickness: Vec<f32> = Vec::new();
-        let mut thermals_x: Vec<f32> = Vec::new();
-        let mut thermals_y: Vec<f32> = Vec::new();
-        let mut thermals_outer_diameter: Vec<f32> = Vec::new();
-        let mut thermals_inner_diameter: Vec<f32> = Vec::new();
-        let mut thermals_gap_thickness: Vec<f32> = Vec::new();
-        let mut thermals_rotation: Vec<f32> = Vec::new();
-
-        let mut vertex_offset: u32 = 0;
-
-        // Unit conversion: aperture.rs already converts to mm using unit_multiplier
-        // No additional conversion needed
-        const TO_MM: f32 = 1.0;
-
-        for primitive in primitives {
-            match primitive {
-                Primitive::Triangle {
-                    vertices,
-                    hole_x,
-                    hole_y,
-                    hole_radius,
-                    ..
-                } => {
-                    // Add triangle vertices to array (convert to mm units)
-                    for vertex in vertices {
-                        triangle_vertices.push(vertex[0] * TO_MM);
-                        triangle_vertices.push(vertex[1] * TO_MM);
-                    }
-                    // Add index for every 3 vertices (one triangle)
-                    triangle_indices.push(vertex_offset);
-                    triangle_indices.push(vertex_offset + 1);
-                    triangle_indices.push(vertex_offset + 2);
-                    vertex_offset += 3;
-
-                    // Add hole data for each vertex (3 times per triangle)
-                    for _ in 0..3 {
-                        triangle_hole_x.push(*hole_x * TO_MM);
-                        triangle_hole_y.push(*hole_y * TO_MM);
-                        triangle_hole_radius.push(*hole_radius * TO_MM);
-                    }
-                }
-                Primitive::Circle {
-                    x,
-                    y,
-                    radius,
-                    hole_x,
-                    hole_y,
-                    hole_radius,
-                    ..
-                } => {
-                    circles_x.push(*x * TO_MM);
-                    circles_y.push(*y * TO_MM);
-                    circles_radius.push(*radius * TO_MM);
-                    circles_hole_x.push(*hole_x * TO_MM);
-                    circles_hole_y.push(*hole_y * TO_MM);
-                    circles_hole_radius.push(*hole_radius * TO_MM);
-                }
-                Primitive::Arc {
-                    x,
-                    y,
-                    radius,
-                    start_angle,
-                    end_angle,
-                    thickness,
-                    ..
-                } => {
-                    arcs_x.push(*x * TO_MM);
-                    arcs_y.push(*y * TO_MM);
-                    arcs_radius.push(*radius * TO_MM);
-                    arcs_start_angle.push(*start_angle);
-                    // sweep_angle = end_angle - start_angle
-                    arcs_sweep_angle.push(*end_angle - *start_angle);
-                    arcs_thickness.push(*thickness * TO_MM);
-                }
-                Primitive::Thermal {
-                    x,
-                    y,
-                    outer_diameter,
-                    inner_diameter,
-                    gap_thickness,
-                    rotation,
-                    ..
-                } => {
-                    thermals_x.push(*x * TO_MM);
-                    thermals_y.push(*y * TO_MM);
-                    thermals_outer_diameter.push(*outer_diameter * TO_MM);
-                    thermals_inner_diameter.push(*inner_diameter * TO_MM);
-                    thermals_gap_thickness.push(*gap_thickness * TO_MM);
-                    thermals_rotation.push(*rotation);
-                }
-            }
+        if state.region_mode {
+            warnings.push("G36 region not closed with G37".to_string());
         }
-
-        // Calculate boundary from all geometry
-        let mut min_x = f32::INFINITY;
-        let mut max_x = f32::NEG_INFINITY;
-        let mut min_y = f32::INFINITY;
-        let mut max_y = f32::NEG_INFINITY;
-
-        // Include triangle vertices in boundary
-        for i in (0..triangle_vertices.len()).step_by(2) {
-            let x = triangle_vertices[i];
-            let y = triangle_vertices[i + 1];
-            min_x = min_x.min(x);
-            max_x = max_x.max(x);
-            min_y = min_y.min(y);
-            max_y = max_y.max(y);
+        if !state.blocks.is_empty() {
+            warnings.push(format!("{} %AB block(s) not closed", state.blocks.len()));
         }
-
-        // Include circles in boundary (center +/- radius)
-        for i in 0..circles_x.len() {
-            let x = circles_x[i];
-            let y = circles_y[i];
-            let r = circles_radius[i];
-            min_x = min_x.min(x - r);
-            max_x = max_x.max(x + r);
-            min_y = min_y.min(y - r);
-            max_y = max_y.max(y + r);
+        if state.unmatched_block_ends > 0 {
+            warnings.push(format!(
+                "{} %AB block end(s) without a matching start",
+                state.unmatched_block_ends
+            ));
         }
+        warnings
+    }
 
-        // Include arcs in boundary (center +/- radius + thickness/2)
-        for i in 0..arcs_x.len() {
-            let x = arcs_x[i];
-            let y = arcs_y[i];
-            let r = arcs_radius[i];
-            let t = arcs_thickness[i];
-            let outer = r + t / 2.0;
-            min_x = min_x.min(x - outer);
-            max_x = max_x.max(x + outer);
-            min_y = min_y.min(y - outer);
-            max_y = max_y.max(y + outer);
-        }
+    /// Read coordinates in `unit`, ignoring %MO and G70/G71 in the file
+    pub fn force_unit(&mut self, unit: Unit) {
+        self.current_state.unit_multiplier = unit.multiplier();
+        self.current_state.unit_source = UnitSource::Forced;
+    }
 
-        // Include thermals in boundary (center +/- outer_diameter/2)
-        for i in 0..thermals_x.len() {
-            let x = thermals_x[i];
-            let y = thermals_y[i];
-            let r = thermals_outer_diameter[i] / 2.0;
-            min_x = min_x.min(x - r);
-            max_x = max_x.max(x + r);
-            min_y = min_y.min(y - r);
-            max_y = max_y.max(y + r);
+    /// Unit, coordinate format and image polarity (%IP) read so far
+    pub fn layer_info(&self) -> LayerInfo {
+        let state = &self.current_state;
+        let format = &state.format_spec;
+        LayerInfo {
+            unit: if state.unit_multiplier == Unit::Inch.multiplier() {
+                Unit::Inch
+            } else {
+                Unit::Millimeter
+            },
+            unit_source: state.unit_source,
+            format: [
+                format.x_integer_digits,
+                format.x_decimal_digits,
+                format.y_integer_digits,
+                format.y_decimal_digits,
+            ],
+            format_declared: state.format_declared,
+            file_function: state.file_function.clone(),
+            image_polarity: state.image_polarity,
+            lint: lint_findings(state),
         }
+    }
 
-        // Handle empty geometry case
-        if min_x == f32::INFINITY {
-            min_x = 0.0;
-            max_x = 0.0;
-            min_y = 0.0;
-            max_y = 0.0;
+    fn check_primitive_limit(&self) -> Result<(), ViewerError> {
+        if self.layers.packed() > MAX_TOTAL_PRIMITIVES {
+            return Err(ViewerError::new(
+                ErrorCode::ResourceLimit,
+                format!(
+                    "Too many total primitives: {} (max: {})",
+                    self.layers.packed(),
+                    MAX_TOTAL_PRIMITIVES
+                ),
+            ));
         }
-
-        GerberData::new(
-            Triangles::new(
-                triangle_vertices,
-                triangle_indices,
-                triangle_hole_x,
-                triangle_hole_y,
-                triangle_hole_radius,
-            ),
-            Circles::new(
-                circles_x,
-                circles_y,
-                circles_radius,
-                circles_hole_x,
-                circles_hole_y,
-                circles_hole_radius,
-            ),
-            Arcs::new(
-                arcs_x,
-                arcs_y,
-                arcs_radius,
-                arcs_start_angle,
-                arcs_sweep_angle,
-                arcs_thickness,
-            ),
-            Thermals::new(
-                thermals_x,
-                thermals_y,
-                thermals_outer_diameter,
-                thermals_inner_diameter,
-                thermals_gap_thickness,
-                thermals_rotation,
-            ),
-            Boundary::new(min_x, max_x, min_y, max_y),
-        )
+        Ok(())
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn parse_command(
     line_ref: &str,
-    i: &mut usize,
-    length: usize,
-    lines: &[&str],
+    lines: &mut LineCursor,
     state: &mut ParserState,
-    apertures: &mut HashMap<String, Aperture>,
+    apertures: &mut HashMap<String, Rc<Aperture>>,
     macros: &mut HashMap<String, ApertureMacro>,
-    current_primitives: &mut Vec<Primitive>,
-    positive_layers: &mut Vec<Vec<Primitive>>,
-    negative_layers: &mut Vec<Vec<Primitive>>,
+    layers: &mut PolarityLayers,
+    region_contours: &mut Vec<Vec<[f32; 2]>>,
+    aperture_cache: Option<&RefCell<ApertureCache>>,
 ) {
     let line = if !line_ref.ends_with('%') {
         let mut buffer = vec![line_ref.to_string()];
-        *i += 1;
 
-        while *i < length {
-            let next_line = lines[*i].trim();
+        for next_line in lines.by_ref() {
+            let next_line = next_line.trim();
             buffer.push(next_line.to_string());
 
             if next_line.ends_with('%') {
                 break;
             }
-            *i += 1;
         }
 
         buffer.join("")
@@ -380,13 +321,18 @@ fn parse_command(
     if line.starts_with("%AM") {
         parse_macro(&line, macros);
     } else if line.starts_with("%ADD") {
-        parse_aperture(
-            &line,
+        // Whitespace in a definition (e.g. one split across lines) is not significant
+        let definition: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+        record_aperture_function(&definition, state);
+        if let Err(warning) = parse_aperture(
+            &definition,
             apertures,
             macros,
             state.unit_multiplier,
-            state.layer_scale,
-        );
+            aperture_cache,
+        ) {
+            state.warnings.push(warning);
+        }
     } else if line.starts_with("%MO") {
         // Unit mode: %MOMM* or %MOIN*
         parse_mo(&line, state);
@@ -395,37 +341,219 @@ fn parse_command(
         parse_format_spec(&line, state);
     } else if line.starts_with("%LP") {
         // Polarity: %LPD* (dark/positive) or %LPC* (clear/negative)
-        parse_lp(
-            &line,
-            state,
-            current_primitives,
-            positive_layers,
-            negative_layers,
-        );
+        if state.region_mode {
+            // Inside G36/G37 the region is split: the contours so far are
+            // filled with the old polarity, later ones with the new
+            close_region(state, &mut layers.pending, region_contours);
+            layers.tag_pending(current_tag(state, true));
+            region_contours.push(Vec::new());
+        }
+        parse_lp(&line, state, layers);
     } else if line.starts_with("%SR") {
         // Step and repeat: %SRX3Y2I10J20*%
         parse_sr(&line, state);
-    } else if line.starts_with("%IF") {
-        // Image polarity: %IFPOS*% or %IFNEG*%
-        parse_if(&line, state);
+    } else if line.starts_with("%IP") {
+        // Image polarity: %IPPOS*% or %IPNEG*%
+        parse_ip(&line, state);
     } else if line.starts_with("%AB") {
         // Block Aperture: %ABD##*% ... %AB*%
-        // TODO: Implement full block aperture support
+        record_aperture_function(&line, state);
+        parse_ab(&line, state, layers, apertures);
     } else if line.starts_with("%LM") {
         // Layer mirroring: %LMN*, %LMX*, %LMY*, %LMXY*
         parse_lm(&line, state);
     } else if line.starts_with("%LR") {
         // Layer rotation: %LR45.0*
-        // TODO: Implement rotation transformation
+        // TODO: Implement rotation transformation (only flashes record it)
+        parse_lr(&line, state);
     } else if line.starts_with("%LS") {
         // Layer scaling: %LS0.8*
         parse_ls(&line, state);
+    } else if line.starts_with("%TA") {
+        // Aperture attribute: %TA.AperFunction,SMDPad,CuDef*%
+        parse_ta(&line, state);
+    } else if line.starts_with("%TF") {
+        // File attribute: %TF.FileFunction,Component,L1,Top*%
+        parse_tf(&line, state);
+    } else if line.starts_with("%TD") {
+        // Delete attribute: %TD*% or %TD.AperFunction*%
+        parse_td(&line, state);
     } else {
         // Unknown or unsupported command
     }
 }
 
-pub fn parse_gerber(data: &str) -> Result<Vec<GerberData>, JsValue> {
+/// Lines of Gerber text with the byte position of the next line
+pub struct LineCursor<'a> {
+    data: &'a str,
+    position: usize,
+}
+
+impl<'a> LineCursor<'a> {
+    pub fn new(data: &'a str) -> Self {
+        Self::at(data, 0)
+    }
+
+    /// Cursor resuming at a byte position returned by position()
+    pub fn at(data: &'a str, position: usize) -> Self {
+        LineCursor { data, position }
+    }
+
+    /// Byte position of the next line (past the end when done)
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.position > self.data.len()
+    }
+
+    /// 1-based number of the line returned last (0 before the first)
+    pub fn line(&self) -> usize {
+        let end = self.position.min(self.data.len());
+        self.data[..end].matches('\n').count() + usize::from(self.is_done())
+    }
+}
+
+impl<'a> Iterator for LineCursor<'a> {
+    type Item = &'a str;
+
+    // Same lines as data.split('\n')
+    fn next(&mut self) -> Option<&'a str> {
+        let rest = self.data.get(self.position..)?;
+        match rest.find('\n') {
+            Some(end) => {
+                self.position += end + 1;
+                Some(&rest[..end])
+            }
+            None => {
+                self.position = self.data.len() + 1;
+                Some(rest)
+            }
+        }
+    }
+}
+
+/// Gerber text parsed a chunk of lines at a time
+///
+/// Lets callers show a partially parsed layer between chunks instead of
+/// blocking until the whole file is parsed.
+pub struct ProgressiveParse {
+    content: String,
+    position: usize, // Byte position of the next line
+    parser: GerberParser,
+}
+
+impl ProgressiveParse {
+    /// Parse `content` with an already configured parser
+    pub fn new(content: String, parser: GerberParser) -> Self {
+        ProgressiveParse {
+            content,
+            position: 0,
+            parser,
+        }
+    }
+
+    /// Parse up to `max_lines` more lines and return whether the end was reached
+    pub fn step(&mut self, max_lines: usize) -> Result<bool, ViewerError> {
+        let mut lines = LineCursor::at(&self.content, self.position);
+        self.parser
+            .parse_lines(&mut lines, max_lines, &mut |_| {})?;
+        self.position = lines.position();
+        Ok(lines.is_done())
+    }
+
+    /// Bytes of the content parsed so far and in total
+    pub fn progress(&self) -> (usize, usize) {
+        (self.position, self.content.len())
+    }
+
+    /// Copy of the layers parsed so far
+    pub fn snapshot(&mut self) -> Result<Vec<GerberData>, ViewerError> {
+        self.parser.snapshot()
+    }
+
+    /// Unit, coordinate format and image polarity read so far
+    pub fn layer_info(&self) -> LayerInfo {
+        self.parser.layer_info()
+    }
+
+    pub fn finish(&mut self) -> Result<Vec<GerberData>, ViewerError> {
+        self.parser.finish()
+    }
+
+    /// Problems that did not fail the parse, complete after finish()
+    pub fn warnings(&self) -> &[String] {
+        self.parser.warnings()
+    }
+}
+
+/// Gerber text split into commands for step-through replay
+///
+/// A command is what the parser reads in one step: a line, or a whole
+/// multi-line extended (%) command. Blank lines are not commands.
+pub struct Replay {
+    content: String,
+    commands: Vec<(usize, usize)>, // Byte range of each command
+}
+
+impl Replay {
+    /// Split `content` into commands, parsing it once with `parser`
+    pub fn new(content: String, mut parser: GerberParser) -> Result<Self, ViewerError> {
+        let mut commands = Vec::new();
+        let mut lines = LineCursor::new(&content);
+        while !lines.is_done() {
+            let start = lines.position();
+            parser.parse_lines(&mut lines, 1, &mut |_| {})?;
+            let end = lines.position().min(content.len());
+            if !content[start..end].trim().is_empty() {
+                commands.push((start, end));
+            }
+        }
+        Ok(Replay { content, commands })
+    }
+
+    /// Text of every command in file order
+    pub fn commands(&self) -> Vec<String> {
+        self.commands
+            .iter()
+            .map(|&(start, end)| self.content[start..end].trim().to_string())
+            .collect()
+    }
+
+    /// Parse the first `count` commands with a fresh `parser`
+    ///
+    /// Returns the layers drawn so far and the layer info read so far. The
+    /// end-of-file checks only run once every command is included.
+    pub fn parse_first(
+        &self,
+        count: usize,
+        mut parser: GerberParser,
+    ) -> Result<(Vec<GerberData>, LayerInfo), ViewerError> {
+        let end = match count.min(self.commands.len()) {
+            0 => 0,
+            n => self.commands[n - 1].1,
+        };
+        let mut lines = LineCursor::new(&self.content[..end]);
+        parser.parse_lines(&mut lines, usize::MAX, &mut |_| {})?;
+        let layers = if count >= self.commands.len() {
+            parser.finish()?
+        } else {
+            parser.snapshot()?
+        };
+        Ok((layers, parser.layer_info()))
+    }
+}
+
+#[cfg(feature = "fuzzing")]
+pub fn parse_gerber(data: &str) -> Result<Vec<GerberData>, ViewerError> {
     let mut parser = GerberParser::new();
     parser.parse(data)
 }
+
+/// Build render data from primitives produced outside the Gerber parser
+pub fn build_gerber_data(primitives: &[Primitive]) -> GerberData {
+    let mut builder = GerberDataBuilder::default();
+    builder.push_batch(primitives, &[]);
+    builder.build()
+}
@@ -1,6 +1,39 @@
 use super::aperture_macro::ApertureMacro;
-use super::geometry::Primitive;
+use super::geometry::{primitive_to_polygon, Primitive};
+use super::state::{mark_polarity, OpenBlock, ParserState, Polarity};
+use super::PolarityLayers;
+use crate::tessellation::DEFAULT_CHORD_ERROR;
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::rc::Rc;
+
+/// Aperture template an aperture was defined with
+///
+/// Stored as one byte per flash.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ApertureShape {
+    Circle = 0,
+    Rectangle = 1,
+    Obround = 2,
+    Polygon = 3,
+    #[default]
+    Macro = 4,
+    Block = 5,
+}
+
+/// Number of aperture shape values
+pub const APERTURE_SHAPE_COUNT: usize = 6;
+
+/// Aperture map key for D-code digits
+///
+/// Leading zeros are dropped so %ADD010 and D10 name the same aperture.
+pub fn aperture_key(digits: &str) -> String {
+    match digits.trim_start_matches('0') {
+        "" => "0".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
 
 /// Aperture definition (Circle, Rectangle, Obround, Polygon, or Macro reference)
 #[derive(Clone, Debug)]
@@ -8,6 +41,8 @@ pub struct Aperture {
     pub radius: f32,
     pub primitives: Vec<Primitive>, // Aperture contains multiple basic primitives
     pub has_negative: bool,         // true if primitives contain exposure=0
+    pub shape: ApertureShape,
+    pub size: [f32; 2], // Width and height in mm
 }
 
 impl Aperture {
@@ -16,344 +51,341 @@ impl Aperture {
             radius,
             primitives: Vec::new(),
             has_negative: false,
+            shape: ApertureShape::default(),
+            size: [0.0, 0.0],
+        }
+    }
+}
+
+/// Width and height of the dark primitives of an aperture
+fn extent(primitives: &[Primitive]) -> [f32; 2] {
+    let mut min = [f32::INFINITY; 2];
+    let mut max = [f32::NEG_INFINITY; 2];
+    for primitive in primitives.iter().filter(|p| p.exposure() >= 0.5) {
+        for point in primitive_to_polygon(primitive, DEFAULT_CHORD_ERROR)
+            .iter()
+            .flatten()
+        {
+            for axis in 0..2 {
+                min[axis] = min[axis].min(point[axis]);
+                max[axis] = max[axis].max(point[axis]);
+            }
         }
     }
+    if min[0] > max[0] {
+        return [0.0, 0.0];
+    }
+    [max[0] - min[0], max[1] - min[1]]
+}
+
+/// Instantiated apertures shared between layers, keyed by definition
+///
+/// Layers of one job usually define the same apertures. With a shared cache,
+/// a definition seen before (same shape, parameters, macro body, units and
+/// scale) reuses the already instantiated primitives instead of a new copy.
+#[derive(Default)]
+pub struct ApertureCache {
+    apertures: HashMap<String, Rc<Aperture>>,
+    hits: usize, // Definitions served from the cache
+}
+
+impl ApertureCache {
+    /// Number of distinct cached apertures
+    pub fn len(&self) -> usize {
+        self.apertures.len()
+    }
+
+    /// Number of aperture definitions that reused a cached aperture
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
 }
 
 /// Parse Aperture definition - %ADD{code}{shape}{params}*%
 /// Example: %ADD10C,0.20*% (circle), %ADD20R,0.5X0.3*% (rectangle), %ADD30TESTMACRO*1.5*%
+///
+/// A definition that can't be understood returns a message naming its
+/// D-code; the D-code is then defined as an empty aperture.
 pub fn parse_aperture(
     data: &str,
-    apertures: &mut HashMap<String, Aperture>,
+    apertures: &mut HashMap<String, Rc<Aperture>>,
     macros: &HashMap<String, ApertureMacro>,
     unit_multiplier: f32,
-    layer_scale: f32,
-) {
-    // Format: %ADD{code}{shape},{params}*%
-    // Remove %ADD and %
+    cache: Option<&RefCell<ApertureCache>>,
+) -> Result<(), String> {
     let content = data
         .trim_start_matches('%')
         .trim_start_matches("ADD")
         .trim_end_matches('%');
 
-    // Split code and shape/params by first letter that's not a digit
-    let mut code_end = 0;
-    for (i, ch) in content.chars().enumerate() {
-        if !ch.is_ascii_digit() {
-            code_end = i;
-            break;
-        }
-    }
-
+    // D-code digits, then the template name
+    let code_end = content
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(content.len());
     if code_end == 0 {
-        return;
+        return Err(format!(
+            "Aperture definition without a D-code: {}",
+            data.trim()
+        ));
     }
-
-    let code = content[..code_end].to_string();
+    let code = aperture_key(&content[..code_end]);
     let rest = &content[code_end..];
 
-    // Split shape and parameters by comma or *
-    let shape_and_params: Vec<&str> = rest.split([',', '*']).collect();
-    if shape_and_params.is_empty() {
-        return;
+    // Template name up to the first separator, then the parameters
+    let shape_end = rest.find([',', '*']).unwrap_or(rest.len());
+    let shape = rest[..shape_end].to_string();
+    if shape.is_empty() {
+        apertures.insert(code.clone(), Rc::new(Aperture::new(0.0)));
+        return Err(format!("D{}: aperture definition has no template", code));
+    }
+    let params = match definition_params(&rest[shape_end..]) {
+        Ok(params) => params,
+        Err(param) => {
+            apertures.insert(code.clone(), Rc::new(Aperture::new(0.0)));
+            return Err(format!("D{}: invalid aperture parameter '{}'", code, param));
+        }
+    };
+    // Aperture parameters are dimensions, except polygon vertices and rotation.
+    // %LS is not applied here but when the aperture is flashed or drawn.
+    let mm = |value: f32| value * unit_multiplier;
+
+    // The same definition text only instantiates the same primitives under
+    // the same units and macro body
+    let cache_key = cache.map(|_| {
+        let macro_body = macros
+            .get(&shape)
+            .map(|m| m.statements.join("*"))
+            .unwrap_or_default();
+        format!("{}|{}|{}", rest, macro_body, unit_multiplier)
+    });
+    if let (Some(cache), Some(key)) = (cache, &cache_key) {
+        let mut cache = cache.borrow_mut();
+        if let Some(shared) = cache.apertures.get(key).cloned() {
+            cache.hits += 1;
+            apertures.insert(code, shared);
+            return Ok(());
+        }
     }
 
-    let shape = shape_and_params[0].trim().to_string();
     let mut aperture = Aperture::new(0.0);
+    let required = match shape.as_str() {
+        "C" => 1,
+        "R" | "O" | "P" => 2,
+        _ => 0,
+    };
+    if params.len() < required {
+        apertures.insert(code.clone(), Rc::new(aperture));
+        return Err(format!(
+            "D{}: {} aperture needs {} parameter(s), got {}",
+            code,
+            shape,
+            required,
+            params.len()
+        ));
+    }
+    let param = |index: usize| params.get(index).copied().unwrap_or(0.0);
 
     // Process basic Aperture formats (C, R, O, P)
     match shape.as_str() {
         "C" => {
             // Circle: %ADD10C,0.20*% or with hole: %ADD10C,0.20X0.10*%
-            if shape_and_params.len() > 1 {
-                let params: Vec<&str> = shape_and_params[1].split('X').collect();
-                if let Ok(diameter) = params[0].trim().parse::<f32>() {
-                    let diameter_mm = diameter * unit_multiplier * layer_scale;
-                    let hole_diameter_mm = if params.len() > 1 {
-                        params[1].trim().parse::<f32>().unwrap_or(0.0) * unit_multiplier * layer_scale
-                    } else {
-                        0.0
-                    };
-
-                    aperture.radius = diameter_mm / 2.0;
-                    aperture.primitives.push(Primitive::Circle {
-                        x: 0.0,
-                        y: 0.0,
-                        radius: diameter_mm / 2.0,
-                        exposure: 1.0,
-                        hole_x: 0.0,
-                        hole_y: 0.0,
-                        hole_radius: hole_diameter_mm / 2.0,
-                    });
-                }
-            }
+            let diameter_mm = mm(param(0));
+            let hole_diameter_mm = mm(param(1));
+
+            aperture.radius = diameter_mm / 2.0;
+            aperture.shape = ApertureShape::Circle;
+            aperture.size = [diameter_mm, diameter_mm];
+            aperture.primitives.push(Primitive::Circle {
+                x: 0.0,
+                y: 0.0,
+                radius: diameter_mm / 2.0,
+                exposure: 1.0,
+                hole_x: 0.0,
+                hole_y: 0.0,
+                hole_radius: hole_diameter_mm / 2.0,
+            });
         }
         "R" => {
             // Rectangle: %ADD20R,0.5X0.3*% or with hole: %ADD20R,0.5X0.3X0.1*%
-            if shape_and_params.len() > 1 {
-                let params: Vec<&str> = shape_and_params[1].split('X').collect();
-                if params.len() >= 2 {
-                    if let (Ok(width), Ok(height)) = (
-                        params[0].trim().parse::<f32>(),
-                        params[1].trim().parse::<f32>(),
-                    ) {
-                        let width_mm = width * unit_multiplier * layer_scale;
-                        let height_mm = height * unit_multiplier * layer_scale;
-                        let hole_diameter_mm = if params.len() > 2 {
-                            params[2].trim().parse::<f32>().unwrap_or(0.0) * unit_multiplier * layer_scale
-                        } else {
-                            0.0
-                        };
-
-                        aperture.radius = width_mm.max(height_mm) / 2.0;
-                        // Split Rectangle into two triangles
-                        let half_width = width_mm / 2.0;
-                        let half_height = height_mm / 2.0;
-
-                        let v1 = [-half_width, -half_height];
-                        let v2 = [half_width, -half_height];
-                        let v3 = [half_width, half_height];
-                        let v4 = [-half_width, half_height];
-
-                        aperture.primitives.push(Primitive::Triangle {
-                            vertices: [v1, v2, v3],
-                            exposure: 1.0,
-                            hole_x: 0.0,
-                            hole_y: 0.0,
-                            hole_radius: hole_diameter_mm / 2.0,
-                        });
-                        aperture.primitives.push(Primitive::Triangle {
-                            vertices: [v1, v3, v4],
-                            exposure: 1.0,
-                            hole_x: 0.0,
-                            hole_y: 0.0,
-                            hole_radius: hole_diameter_mm / 2.0,
-                        });
-                    }
-                }
-            }
+            let width_mm = mm(param(0));
+            let height_mm = mm(param(1));
+            let hole_diameter_mm = mm(param(2));
+
+            aperture.radius = width_mm.max(height_mm) / 2.0;
+            aperture.shape = ApertureShape::Rectangle;
+            aperture.size = [width_mm, height_mm];
+            // Split Rectangle into two triangles
+            let half_width = width_mm / 2.0;
+            let half_height = height_mm / 2.0;
+
+            let v1 = [-half_width, -half_height];
+            let v2 = [half_width, -half_height];
+            let v3 = [half_width, half_height];
+            let v4 = [-half_width, half_height];
+
+            aperture.primitives.push(Primitive::Triangle {
+                vertices: [v1, v2, v3],
+                exposure: 1.0,
+                hole_x: 0.0,
+                hole_y: 0.0,
+                hole_radius: hole_diameter_mm / 2.0,
+            });
+            aperture.primitives.push(Primitive::Triangle {
+                vertices: [v1, v3, v4],
+                exposure: 1.0,
+                hole_x: 0.0,
+                hole_y: 0.0,
+                hole_radius: hole_diameter_mm / 2.0,
+            });
         }
         "O" => {
             // Obround (rounded rectangle): %ADD30O,0.5X0.3*% or with hole: %ADD30O,0.5X0.3X0.1*%
-            if shape_and_params.len() > 1 {
-                let params: Vec<&str> = shape_and_params[1].split('X').collect();
-                if params.len() >= 2 {
-                    if let (Ok(width), Ok(height)) = (
-                        params[0].trim().parse::<f32>(),
-                        params[1].trim().parse::<f32>(),
-                    ) {
-                        let width_mm = width * unit_multiplier * layer_scale;
-                        let height_mm = height * unit_multiplier * layer_scale;
-                        let hole_diameter_mm = if params.len() > 2 {
-                            params[2].trim().parse::<f32>().unwrap_or(0.0) * unit_multiplier * layer_scale
-                        } else {
-                            0.0
-                        };
-
-                        let short_side = width_mm.min(height_mm);
-                        let long_side = width_mm.max(height_mm);
-                        let radius = short_side / 2.0;
-
-                        aperture.radius = radius;
-
-                        if width_mm > height_mm {
-                            // If width is greater - circles on the left and right, rectangle in the middle
-                            let rect_width = long_side - short_side;
-                            let half_rect_width = rect_width / 2.0;
-                            let half_height = height_mm / 2.0;
-
-                            // Left circle (center: -half_rect_width, 0), hole at aperture center (0, 0)
-                            aperture.primitives.push(Primitive::Circle {
-                                x: -half_rect_width,
-                                y: 0.0,
-                                radius,
-                                exposure: 1.0,
-                                hole_x: 0.0,
-                                hole_y: 0.0,
-                                hole_radius: hole_diameter_mm / 2.0,
-                            });
-
-                            // Right circle (center: half_rect_width, 0), hole at aperture center (0, 0)
-                            aperture.primitives.push(Primitive::Circle {
-                                x: half_rect_width,
-                                y: 0.0,
-                                radius,
-                                exposure: 1.0,
-                                hole_x: 0.0,
-                                hole_y: 0.0,
-                                hole_radius: hole_diameter_mm / 2.0,
-                            });
-
-                            // Central rectangle (2 triangles)
-                            let x1 = -half_rect_width;
-                            let x2 = half_rect_width;
-                            let y1 = -half_height;
-                            let y2 = half_height;
-
-                            aperture.primitives.push(Primitive::Triangle {
-                                vertices: [[x1, y1], [x2, y1], [x1, y2]],
-                                exposure: 1.0,
-                                hole_x: 0.0,
-                                hole_y: 0.0,
-                                hole_radius: hole_diameter_mm / 2.0,
-                            });
-                            aperture.primitives.push(Primitive::Triangle {
-                                vertices: [[x2, y1], [x2, y2], [x1, y2]],
-                                exposure: 1.0,
-                                hole_x: 0.0,
-                                hole_y: 0.0,
-                                hole_radius: hole_diameter_mm / 2.0,
-                            });
-                        } else {
-                            // If height is greater - circles on the top and bottom, rectangle in the middle
-                            let rect_height = long_side - short_side;
-                            let half_rect_height = rect_height / 2.0;
-                            let half_width = width_mm / 2.0;
-
-                            // Bottom circle (center: 0, -half_rect_height), hole at aperture center (0, 0)
-                            aperture.primitives.push(Primitive::Circle {
-                                x: 0.0,
-                                y: -half_rect_height,
-                                radius,
-                                exposure: 1.0,
-                                hole_x: 0.0,
-                                hole_y: 0.0,
-                                hole_radius: hole_diameter_mm / 2.0,
-                            });
-
-                            // Top circle (center: 0, half_rect_height), hole at aperture center (0, 0)
-                            aperture.primitives.push(Primitive::Circle {
-                                x: 0.0,
-                                y: half_rect_height,
-                                radius,
-                                exposure: 1.0,
-                                hole_x: 0.0,
-                                hole_y: 0.0,
-                                hole_radius: hole_diameter_mm / 2.0,
-                            });
-
-                            // Central rectangle (2 triangles)
-                            let x1 = -half_width;
-                            let x2 = half_width;
-                            let y1 = -half_rect_height;
-                            let y2 = half_rect_height;
-
-                            aperture.primitives.push(Primitive::Triangle {
-                                vertices: [[x1, y1], [x2, y1], [x1, y2]],
-                                exposure: 1.0,
-                                hole_x: 0.0,
-                                hole_y: 0.0,
-                                hole_radius: hole_diameter_mm / 2.0,
-                            });
-                            aperture.primitives.push(Primitive::Triangle {
-                                vertices: [[x2, y1], [x2, y2], [x1, y2]],
-                                exposure: 1.0,
-                                hole_x: 0.0,
-                                hole_y: 0.0,
-                                hole_radius: hole_diameter_mm / 2.0,
-                            });
-                        }
-                    }
-                }
-            }
+            let width_mm = mm(param(0));
+            let height_mm = mm(param(1));
+            let hole_diameter_mm = mm(param(2));
+
+            // Line width of an obround draw is its short side
+            aperture.radius = width_mm.min(height_mm) / 2.0;
+            aperture.shape = ApertureShape::Obround;
+            aperture.size = [width_mm, height_mm];
+            aperture.primitives.push(Primitive::Obround {
+                x: 0.0,
+                y: 0.0,
+                width: width_mm,
+                height: height_mm,
+                rotation: 0.0,
+                exposure: 1.0,
+                hole_x: 0.0,
+                hole_y: 0.0,
+                hole_radius: hole_diameter_mm / 2.0,
+            });
         }
         "P" => {
             // Polygon: %ADD40P,0.5X5*% or with rotation: %ADD40P,0.5X5X45.0*% or with hole: %ADD40P,0.5X5X0X0.1*%
             // Parameters: diameter X vertices [X rotation] [X hole_diameter]
-            if shape_and_params.len() > 1 {
-                let params: Vec<&str> = shape_and_params[1].split('X').collect();
-                if params.len() >= 2 {
-                    if let (Ok(diameter), Ok(num_vertices)) = (
-                        params[0].trim().parse::<f32>(),
-                        params[1].trim().parse::<f32>(),
-                    ) {
-                        let diameter_mm = diameter * unit_multiplier * layer_scale;
-
-                        // Parse rotation (degrees, defaults to 0)
-                        // 3 parameters: rotation (NOT hole!)
-                        // 4+ parameters: rotation AND hole
-                        let rotation_degrees = if params.len() > 2 {
-                            params[2].trim().parse::<f32>().unwrap_or(0.0)
-                        } else {
-                            0.0
-                        };
-                        let rotation_radians = rotation_degrees * std::f32::consts::PI / 180.0;
-
-                        // Parse hole (only if 4+ parameters)
-                        let hole_diameter_mm = if params.len() > 3 {
-                            params[3].trim().parse::<f32>().unwrap_or(0.0) * unit_multiplier * layer_scale
-                        } else {
-                            0.0
-                        };
-
-                        aperture.radius = diameter_mm / 2.0;
-                        let radius = diameter_mm / 2.0;
-                        let num_vertices = num_vertices as u32;
-                        let angle_step = 2.0 * std::f32::consts::PI / num_vertices as f32;
-
-                        // Fan triangulation with rotation
-                        for i in 0..(num_vertices as usize) {
-                            let next_i = (i + 1) % (num_vertices as usize);
-                            let angle_i = angle_step * i as f32 + rotation_radians;
-                            let angle_next = angle_step * next_i as f32 + rotation_radians;
-
-                            let x1 = radius * angle_i.cos();
-                            let y1 = radius * angle_i.sin();
-                            let x2 = radius * angle_next.cos();
-                            let y2 = radius * angle_next.sin();
-
-                            aperture.primitives.push(Primitive::Triangle {
-                                vertices: [[0.0, 0.0], [x1, y1], [x2, y2]],
-                                exposure: 1.0,
-                                hole_x: 0.0,
-                                hole_y: 0.0,
-                                hole_radius: hole_diameter_mm / 2.0,
-                            });
-                        }
-                    }
-                }
+            let diameter_mm = mm(param(0));
+            let num_vertices = param(1);
+            let rotation_radians = param(2) * std::f32::consts::PI / 180.0;
+            let hole_diameter_mm = mm(param(3));
+
+            aperture.radius = diameter_mm / 2.0;
+            aperture.shape = ApertureShape::Polygon;
+            aperture.size = [diameter_mm, diameter_mm];
+            let radius = diameter_mm / 2.0;
+            let num_vertices = num_vertices as u32;
+            let angle_step = 2.0 * std::f32::consts::PI / num_vertices as f32;
+
+            // Fan triangulation with rotation
+            for i in 0..(num_vertices as usize) {
+                let next_i = (i + 1) % (num_vertices as usize);
+                let angle_i = angle_step * i as f32 + rotation_radians;
+                let angle_next = angle_step * next_i as f32 + rotation_radians;
+
+                let x1 = radius * angle_i.cos();
+                let y1 = radius * angle_i.sin();
+                let x2 = radius * angle_next.cos();
+                let y2 = radius * angle_next.sin();
+
+                aperture.primitives.push(Primitive::Triangle {
+                    vertices: [[0.0, 0.0], [x1, y1], [x2, y2]],
+                    exposure: 1.0,
+                    hole_x: 0.0,
+                    hole_y: 0.0,
+                    hole_radius: hole_diameter_mm / 2.0,
+                });
             }
         }
         _ => {
             // Macro reference: %ADD30TESTMACRO,1.5*% or %ADD11RoundRect,0.250000X0.600000X...
-            // Check if shape is a macro name
-            if let Some(macro_def) = macros.get(&shape) {
-                // Collect parameters - also handle parameters separated by X
-                let mut params = Vec::new();
-                for param_str in shape_and_params.iter().skip(1) {
-                    let param_str = param_str.trim();
-                    if param_str.is_empty() {
-                        continue;
-                    }
-
-                    // There can be multiple parameters separated by X
-                    if param_str.contains('X') {
-                        for sub_param in param_str.split('X') {
-                            if let Ok(param) = sub_param.trim().parse::<f32>() {
-                                // Convert dimension parameters (aperture macro params are dimensions)
-                                params.push(param * unit_multiplier * layer_scale);
-                            }
-                        }
-                    } else if let Ok(param) = param_str.parse::<f32>() {
-                        // Convert dimension parameters (aperture macro params are dimensions)
-                        params.push(param * unit_multiplier * layer_scale);
-                    }
-                }
-
-                // Call Macro instantiate
-                aperture.primitives = macro_def.instantiate(&params);
-                aperture.radius = 0.0; // For macros, the radius depends on the parameters
-            }
+            let Some(macro_def) = macros.get(&shape) else {
+                apertures.insert(code.clone(), Rc::new(aperture));
+                return Err(format!("D{}: unknown aperture template '{}'", code, shape));
+            };
+            let params: Vec<f32> = params.iter().map(|&value| mm(value)).collect();
+
+            // Call Macro instantiate
+            aperture.primitives = macro_def.instantiate(&params);
+            aperture.radius = 0.0; // For macros, the radius depends on the parameters
+            aperture.shape = ApertureShape::Macro;
+            aperture.size = extent(&aperture.primitives);
         }
     }
 
     // Calculate has_negative based on actual primitives
-    aperture.has_negative = aperture.primitives.iter().any(|p| match p {
-        Primitive::Circle { exposure, .. } => *exposure < 0.5,
-        Primitive::Triangle { exposure, .. } => *exposure < 0.5,
-        Primitive::Arc { exposure, .. } => *exposure < 0.5,
-        Primitive::Thermal { exposure, .. } => *exposure < 0.5,
-    });
-
+    aperture.has_negative = aperture.primitives.iter().any(|p| p.exposure() < 0.5);
+
+    let aperture = Rc::new(aperture);
+    if let (Some(cache), Some(key)) = (cache, cache_key) {
+        cache
+            .borrow_mut()
+            .apertures
+            .insert(key, Rc::clone(&aperture));
+    }
     apertures.insert(code, aperture);
+    Ok(())
+}
+
+/// Parameters of an aperture definition after its template name
+///
+/// Parameters are separated by X; commas and asterisks between them are
+/// tolerated. Returns the first parameter that is not a number as the error.
+fn definition_params(text: &str) -> Result<Vec<f32>, &str> {
+    text.split([',', 'X', '*'])
+        .filter(|param| !param.is_empty())
+        .map(|param| param.parse::<f32>().map_err(|_| param))
+        .collect()
+}
+
+/// Parse Block Aperture - %ABD{code}*% opens a block, %AB*% closes the innermost one
+///
+/// Objects drawn inside the block become the aperture's primitives, relative
+/// to the block origin. Objects drawn under %LPC* keep exposure 0, so a
+/// flash erases them like the negative primitives of a macro aperture.
+pub fn parse_ab(
+    line: &str,
+    state: &mut ParserState,
+    layers: &mut PolarityLayers,
+    apertures: &mut HashMap<String, Rc<Aperture>>,
+) {
+    let spec_str = line
+        .trim_start_matches('%')
+        .trim_end_matches('%')
+        .trim_end_matches('*');
+    let Some(code) = spec_str.strip_prefix("AB") else {
+        return;
+    };
+
+    if let Some(code) = code.strip_prefix('D') {
+        if code.is_empty() || !code.bytes().all(|b| b.is_ascii_digit()) {
+            return;
+        }
+        let start = layers.pending.len();
+        state.blocks.push(OpenBlock {
+            code: aperture_key(code),
+            start,
+            segment_start: start,
+            outer_polarity: state.polarity,
+        });
+        // Block contents start dark
+        state.polarity = Polarity::Positive;
+    } else if let Some(block) = state.blocks.pop() {
+        mark_polarity(&mut layers.pending[block.segment_start..], state.polarity);
+        let primitives = layers.take_pending(block.start);
+        state.polarity = block.outer_polarity;
+
+        let has_negative = primitives.iter().any(|p| p.exposure() < 0.5);
+        let size = extent(&primitives);
+        apertures.insert(
+            block.code,
+            Rc::new(Aperture {
+                radius: 0.0, // Blocks can only be flashed
+                primitives,
+                has_negative,
+                shape: ApertureShape::Block,
+                size,
+            }),
+        );
+    } else {
+        state.unmatched_block_ends += 1;
+    }
 }
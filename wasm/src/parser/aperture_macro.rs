@@ -405,6 +405,10 @@ pub fn parse_primitive_statement(
             let exposure: f32 = evaluate_expression(parts[1], variables).ok()?;
             let num_vertices: u32 =
                 evaluate_expression(parts[2], variables).ok()? as u32;
+            // Every vertex needs its two coordinates in the statement
+            if num_vertices as usize > (parts.len() - 3) / 2 {
+                return None;
+            }
             let rotation: f32 = if parts.len() > 3 + (num_vertices as usize) * 2 {
                 evaluate_expression(
                     parts[3 + (num_vertices as usize) * 2],
@@ -461,6 +465,10 @@ pub fn parse_primitive_statement(
             let exposure: f32 = evaluate_expression(parts[1], variables).ok()?;
             let num_vertices: u32 =
                 evaluate_expression(parts[2], variables).ok()? as u32;
+            // The specification allows 3 to 12 vertices
+            if !(3..=12).contains(&num_vertices) {
+                return None;
+            }
             let center_x: f32 = evaluate_expression(parts[3], variables).ok()?;
             let center_y: f32 = evaluate_expression(parts[4], variables).ok()?;
             let diameter: f32 = evaluate_expression(parts[5], variables).ok()?;
@@ -515,24 +523,28 @@ pub fn parse_primitive_statement(
             if parts.len() < 6 {
                 return None;
             }
-            let center_x: f32 = evaluate_expression(parts[1], variables).ok()?;
-            let center_y: f32 = evaluate_expression(parts[2], variables).ok()?;
+            let mut center = [
+                evaluate_expression(parts[1], variables).ok()?,
+                evaluate_expression(parts[2], variables).ok()?,
+            ];
             let outer_diameter: f32 =
                 evaluate_expression(parts[3], variables).ok()?;
             let inner_diameter: f32 =
                 evaluate_expression(parts[4], variables).ok()?;
             let gap_thickness: f32 =
                 evaluate_expression(parts[5], variables).ok()?;
+            // Degrees in the macro, radians in the primitive; the rotation
+            // is about the macro origin, so it also moves the center
             let rotation: f32 = if parts.len() > 6 {
-                evaluate_expression(parts[6], variables).ok()?
-                    * (std::f32::consts::PI / 180.0)
+                evaluate_expression(parts[6], variables).ok()?.to_radians()
             } else {
                 0.0
             };
+            rotate_point(&mut center, rotation, 0.0, 0.0);
 
             primitives.push(Primitive::Thermal {
-                x: center_x,
-                y: center_y,
+                x: center[0],
+                y: center[1],
                 outer_diameter,
                 inner_diameter,
                 gap_thickness,
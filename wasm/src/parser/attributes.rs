@@ -0,0 +1,195 @@
+use super::aperture::aperture_key;
+use super::state::ParserState;
+
+/// X2 aperture function (.AperFunction) of a primitive
+///
+/// Stored as one byte per primitive; `None` (0) marks primitives of files or
+/// apertures without the attribute.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum ApertureFunction {
+    #[default]
+    None = 0,
+    Conductor = 1,
+    ViaPad = 2,
+    SmdPad = 3,
+    BgaPad = 4,
+    ComponentPad = 5,
+    Other = 6,       // Any other function value (ComponentDrill, HeatsinkPad, ...)
+    Courtyard = 7,   // ComponentOutline,Courtyard of an X3 component layer
+    FiducialPad = 8, // Local, global or panel fiducial
+    TestPad = 9,     // Probe target for in-circuit test
+}
+
+/// Number of aperture function values, including None
+pub const APERTURE_FUNCTION_COUNT: usize = 10;
+
+impl ApertureFunction {
+    /// Function named by an .AperFunction value (its first field, and the
+    /// second for component outlines)
+    fn from_value(value: &str) -> ApertureFunction {
+        let mut fields = value.split(',');
+        match fields.next().unwrap_or("") {
+            "Conductor" => ApertureFunction::Conductor,
+            "ViaPad" => ApertureFunction::ViaPad,
+            "SMDPad" => ApertureFunction::SmdPad,
+            "BGAPad" => ApertureFunction::BgaPad,
+            "ComponentPad" => ApertureFunction::ComponentPad,
+            "FiducialPad" => ApertureFunction::FiducialPad,
+            "TestPad" | "TestPoint" => ApertureFunction::TestPad,
+            "ComponentOutline" if fields.next() == Some("Courtyard") => ApertureFunction::Courtyard,
+            _ => ApertureFunction::Other,
+        }
+    }
+}
+
+/// Parse Aperture attribute - %TA.AperFunction,SMDPad,CuDef*%
+/// Sets the function given to apertures defined from here on; other
+/// aperture attributes are ignored
+pub fn parse_ta(line: &str, state: &mut ParserState) {
+    let spec_str = line
+        .trim_start_matches('%')
+        .trim_end_matches('%')
+        .trim_end_matches('*');
+
+    if let Some(value) = spec_str.strip_prefix("TA.AperFunction,") {
+        state.aperture_function = ApertureFunction::from_value(value);
+    }
+}
+
+/// Parse File attribute - %TF.FileFunction,Component,L1,Top*%
+/// Keeps the .FileFunction value; other file attributes are ignored
+pub fn parse_tf(line: &str, state: &mut ParserState) {
+    let spec_str = line
+        .trim_start_matches('%')
+        .trim_end_matches('%')
+        .trim_end_matches('*');
+
+    if let Some(value) = spec_str.strip_prefix("TF.FileFunction,") {
+        state.file_function = value.to_string();
+    }
+}
+
+/// Parse Delete attribute - %TD*% (all) or %TD.AperFunction*%
+pub fn parse_td(line: &str, state: &mut ParserState) {
+    let spec_str = line
+        .trim_start_matches('%')
+        .trim_end_matches('%')
+        .trim_end_matches('*');
+
+    if matches!(spec_str, "TD" | "TD.AperFunction") {
+        state.aperture_function = ApertureFunction::None;
+    }
+}
+
+/// Parse an attribute written as a structured comment - G04 #@! TA.AperFunction,SMDPad,CuDef*
+///
+/// Older CAM output carries X2 attributes in this legacy form; they apply as
+/// if written as extended commands. Plain comments are ignored.
+pub fn parse_attribute_comment(line: &str, state: &mut ParserState) {
+    let Some(attribute) = line
+        .strip_prefix("G04")
+        .map(str::trim_start)
+        .and_then(|rest| rest.strip_prefix("#@!"))
+        .map(str::trim_start)
+    else {
+        return;
+    };
+
+    if attribute.starts_with("TA") {
+        parse_ta(attribute, state);
+    } else if attribute.starts_with("TF") {
+        parse_tf(attribute, state);
+    } else if attribute.starts_with("TD") {
+        parse_td(attribute, state);
+    }
+}
+
+/// Attach the current function to an aperture being defined - %ADD10C,0.5*%
+/// or %ABD12*%
+pub fn record_aperture_function(line: &str, state: &mut ParserState) {
+    let spec_str = line.trim_start_matches('%');
+    let Some(rest) = spec_str
+        .strip_prefix("ADD")
+        .or_else(|| spec_str.strip_prefix("ABD"))
+    else {
+        return;
+    };
+    let code_end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    if code_end == 0 {
+        return;
+    }
+    // A redefinition replaces the function of the earlier definition
+    let key = aperture_key(&rest[..code_end]);
+    if state.aperture_function == ApertureFunction::None {
+        state.aperture_functions.remove(&key);
+    } else {
+        state
+            .aperture_functions
+            .insert(key, state.aperture_function);
+    }
+}
+
+/// Graphics operation that produced a primitive
+///
+/// Stored as one byte per primitive; `Unknown` (0) marks geometry that did
+/// not come from a Gerber parse, such as imports and boolean results.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum PrimitiveKind {
+    #[default]
+    Unknown = 0,
+    Region = 1,
+    Draw = 2,  // D01 line or arc
+    Flash = 3, // D03, including block apertures
+}
+
+impl PrimitiveKind {
+    #[cfg(feature = "renderer")]
+    pub fn from_u8(value: u8) -> PrimitiveKind {
+        match value {
+            1 => PrimitiveKind::Region,
+            2 => PrimitiveKind::Draw,
+            3 => PrimitiveKind::Flash,
+            _ => PrimitiveKind::Unknown,
+        }
+    }
+}
+
+/// Aperture function, D-code and operation a primitive was drawn with
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PrimitiveTag {
+    pub function: ApertureFunction,
+    pub d_code: u16, // 0 for regions
+    pub kind: PrimitiveKind,
+}
+
+/// Tag of the primitives a graphics command just produced
+///
+/// Regions take the function current when they are drawn and have no
+/// D-code; draws and flashes take the function and D-code of their aperture.
+pub fn current_tag(state: &ParserState, in_region: bool) -> PrimitiveTag {
+    if in_region {
+        PrimitiveTag {
+            function: state.aperture_function,
+            d_code: 0,
+            kind: PrimitiveKind::Region,
+        }
+    } else {
+        PrimitiveTag {
+            function: state
+                .aperture_functions
+                .get(&state.current_aperture)
+                .copied()
+                .unwrap_or_default(),
+            d_code: state.current_d_code,
+            kind: if state.flashing {
+                PrimitiveKind::Flash
+            } else {
+                PrimitiveKind::Draw
+            },
+        }
+    }
+}
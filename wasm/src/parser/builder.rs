@@ -0,0 +1,418 @@
+use super::geometry::Primitive;
+use super::{ApertureFunction, Polarity, PrimitiveTag};
+use crate::shape::{
+    Arcs, Boundary, Circles, Flashes, GerberData, Obrounds, PrimitiveValues, Thermals, Traces,
+    Triangles,
+};
+use crate::simd;
+use std::mem::take;
+
+/// Packs primitives into GerberData arrays batch by batch
+///
+/// The boundary is extended per batch, so finishing a layer needs no further
+/// pass over the geometry.
+pub struct GerberDataBuilder {
+    triangles: Triangles,
+    circles: Circles,
+    arcs: Arcs,
+    thermals: Thermals,
+    obrounds: Obrounds,
+    boundary: Boundary,
+    functions: PrimitiveValues<u8>, // Empty until the first primitive with a function
+    apertures: PrimitiveValues<u16>, // D-code of every primitive
+    kinds: PrimitiveValues<u8>,     // PrimitiveKind of every primitive
+    flashes: Flashes,
+    traces: Traces,
+    primitive_count: usize,
+}
+
+impl Default for GerberDataBuilder {
+    fn default() -> Self {
+        GerberDataBuilder {
+            triangles: Triangles::default(),
+            circles: Circles::default(),
+            arcs: Arcs::default(),
+            thermals: Thermals::default(),
+            obrounds: Obrounds::default(),
+            boundary: Boundary::new(
+                f32::INFINITY,
+                f32::NEG_INFINITY,
+                f32::INFINITY,
+                f32::NEG_INFINITY,
+            ),
+            functions: PrimitiveValues::default(),
+            apertures: PrimitiveValues::default(),
+            kinds: PrimitiveValues::default(),
+            flashes: Flashes::default(),
+            traces: Traces::default(),
+            primitive_count: 0,
+        }
+    }
+}
+
+impl GerberDataBuilder {
+    pub fn is_empty(&self) -> bool {
+        self.primitive_count == 0
+    }
+
+    /// Append a batch of primitives to the packed arrays
+    ///
+    /// `tags` holds the aperture function, D-code and operation of each
+    /// primitive, or is empty for primitives that have none.
+    pub fn push_batch(&mut self, primitives: &[Primitive], tags: &[PrimitiveTag]) {
+        let triangle_start = self.triangles.vertices.len();
+        let circle_start = self.circles.x.len();
+        let arc_start = self.arcs.x.len();
+        let thermal_start = self.thermals.x.len();
+        let obround_start = self.obrounds.x.len();
+
+        self.push_tags(primitives, tags);
+        for primitive in primitives {
+            self.push(primitive);
+        }
+        self.primitive_count += primitives.len();
+
+        // Extend the boundary with the new geometry only
+        let boundary = &mut self.boundary;
+
+        // Include triangle vertices in boundary
+        simd::extend_point_bounds(boundary, &self.triangles.vertices[triangle_start..]);
+
+        // Include circles in boundary (center +/- radius)
+        let circles = &self.circles;
+        simd::extend_disc_bounds(
+            boundary,
+            &circles.x[circle_start..],
+            &circles.y[circle_start..],
+            &circles.radius[circle_start..],
+        );
+
+        // Include arcs in boundary (center +/- radius + thickness/2)
+        let arcs = &self.arcs;
+        let arcs_outer: Vec<f32> = arcs.radius[arc_start..]
+            .iter()
+            .zip(&arcs.thickness[arc_start..])
+            .map(|(r, t)| r + t / 2.0)
+            .collect();
+        simd::extend_disc_bounds(
+            boundary,
+            &arcs.x[arc_start..],
+            &arcs.y[arc_start..],
+            &arcs_outer,
+        );
+
+        // Include thermals in boundary (center +/- outer_diameter/2)
+        let thermals = &self.thermals;
+        let thermals_outer: Vec<f32> = thermals.outer_diameter[thermal_start..]
+            .iter()
+            .map(|d| d / 2.0)
+            .collect();
+        simd::extend_disc_bounds(
+            boundary,
+            &thermals.x[thermal_start..],
+            &thermals.y[thermal_start..],
+            &thermals_outer,
+        );
+
+        // Include obrounds in boundary (center +/- half of the longer side, any rotation)
+        let obrounds = &self.obrounds;
+        let obrounds_outer: Vec<f32> = obrounds.width[obround_start..]
+            .iter()
+            .zip(&obrounds.height[obround_start..])
+            .map(|(w, h)| w.max(*h) / 2.0)
+            .collect();
+        simd::extend_disc_bounds(
+            boundary,
+            &obrounds.x[obround_start..],
+            &obrounds.y[obround_start..],
+            &obrounds_outer,
+        );
+    }
+
+    fn push(&mut self, primitive: &Primitive) {
+        match primitive {
+            Primitive::Triangle {
+                vertices,
+                hole_x,
+                hole_y,
+                hole_radius,
+                ..
+            } => {
+                let triangles = &mut self.triangles;
+                let vertex_offset = (triangles.vertices.len() / 2) as u32;
+                // Add triangle vertices to array
+                triangles
+                    .vertices
+                    .extend_from_slice(vertices.as_flattened());
+                // Add index for every 3 vertices (one triangle)
+                triangles.indices.extend_from_slice(&[
+                    vertex_offset,
+                    vertex_offset + 1,
+                    vertex_offset + 2,
+                ]);
+
+                // Add hole data for each vertex (3 times per triangle)
+                triangles.hole_x.extend_from_slice(&[*hole_x; 3]);
+                triangles.hole_y.extend_from_slice(&[*hole_y; 3]);
+                triangles.hole_radius.extend_from_slice(&[*hole_radius; 3]);
+            }
+            Primitive::Circle {
+                x,
+                y,
+                radius,
+                hole_x,
+                hole_y,
+                hole_radius,
+                ..
+            } => {
+                let circles = &mut self.circles;
+                circles.x.push(*x);
+                circles.y.push(*y);
+                circles.radius.push(*radius);
+                circles.hole_x.push(*hole_x);
+                circles.hole_y.push(*hole_y);
+                circles.hole_radius.push(*hole_radius);
+            }
+            Primitive::Arc {
+                x,
+                y,
+                radius,
+                start_angle,
+                end_angle,
+                thickness,
+                ..
+            } => {
+                let arcs = &mut self.arcs;
+                arcs.x.push(*x);
+                arcs.y.push(*y);
+                arcs.radius.push(*radius);
+                arcs.start_angle.push(*start_angle);
+                // sweep_angle = end_angle - start_angle
+                arcs.sweep_angle.push(*end_angle - *start_angle);
+                arcs.thickness.push(*thickness);
+            }
+            Primitive::Thermal {
+                x,
+                y,
+                outer_diameter,
+                inner_diameter,
+                gap_thickness,
+                rotation,
+                ..
+            } => {
+                let thermals = &mut self.thermals;
+                thermals.x.push(*x);
+                thermals.y.push(*y);
+                thermals.outer_diameter.push(*outer_diameter);
+                thermals.inner_diameter.push(*inner_diameter);
+                thermals.gap_thickness.push(*gap_thickness);
+                thermals.rotation.push(*rotation);
+            }
+            Primitive::Obround {
+                x,
+                y,
+                width,
+                height,
+                rotation,
+                hole_x,
+                hole_y,
+                hole_radius,
+                ..
+            } => {
+                let obrounds = &mut self.obrounds;
+                obrounds.x.push(*x);
+                obrounds.y.push(*y);
+                obrounds.width.push(*width);
+                obrounds.height.push(*height);
+                obrounds.rotation.push(*rotation);
+                obrounds.hole_x.push(*hole_x);
+                obrounds.hole_y.push(*hole_y);
+                obrounds.hole_radius.push(*hole_radius);
+            }
+        }
+    }
+
+    /// Append aperture flashes and draws that produced primitives of this sublayer
+    pub fn push_flashes(&mut self, flashes: &mut Flashes, traces: &mut Traces) {
+        self.flashes.append(flashes);
+        // A draw sequence may continue across batches
+        self.traces.join(traces);
+    }
+
+    /// Record the aperture functions, D-codes and operations of a batch
+    /// before packing it
+    fn push_tags(&mut self, primitives: &[Primitive], tags: &[PrimitiveTag]) {
+        let tag = |i: usize| tags.get(i).copied().unwrap_or_default();
+        let lengths = [
+            self.triangles.vertices.len() / 2,
+            self.circles.x.len(),
+            self.arcs.x.len(),
+            self.thermals.x.len(),
+            self.obrounds.x.len(),
+        ];
+
+        self.apertures.resize(lengths, 0);
+        self.kinds.resize(lengths, 0);
+        for (i, primitive) in primitives.iter().enumerate() {
+            self.apertures.push(primitive, tag(i).d_code);
+            self.kinds.push(primitive, tag(i).kind as u8);
+        }
+
+        let tagged = tags.iter().any(|t| t.function != ApertureFunction::None);
+        if !tagged && self.functions.is_empty() {
+            return;
+        }
+        // Primitives packed before the first one with a function get None
+        self.functions.resize(lengths, 0);
+        for (i, primitive) in primitives.iter().enumerate() {
+            self.functions.push(primitive, tag(i).function as u8);
+        }
+    }
+
+    /// Boundary of the packed geometry (all zero when empty)
+    fn boundary(&self) -> Boundary {
+        if self.boundary.min_x == f32::INFINITY {
+            Boundary::new(0.0, 0.0, 0.0, 0.0)
+        } else {
+            self.boundary
+        }
+    }
+
+    /// Copy of the geometry packed so far
+    pub fn snapshot(&self) -> GerberData {
+        GerberData::new(
+            self.triangles.clone(),
+            self.circles.clone(),
+            self.arcs.clone(),
+            self.thermals.clone(),
+            self.obrounds.clone(),
+            self.boundary(),
+        )
+        .with_apertures(self.functions.clone(), self.apertures.clone())
+        .with_kinds(self.kinds.clone())
+        .with_flashes(self.flashes.clone(), self.traces.clone())
+    }
+
+    pub fn build(self) -> GerberData {
+        let boundary = self.boundary();
+        GerberData::new(
+            self.triangles,
+            self.circles,
+            self.arcs,
+            self.thermals,
+            self.obrounds,
+            boundary,
+        )
+        .with_apertures(self.functions, self.apertures)
+        .with_kinds(self.kinds)
+        .with_flashes(self.flashes, self.traces)
+    }
+}
+
+/// Polarity sublayers packed while parsing
+///
+/// Primitives collect in `pending` and are packed into the current sublayer
+/// once per batch, so a file's full primitive list never exists at once.
+#[derive(Default)]
+pub struct PolarityLayers {
+    pub pending: Vec<Primitive>,     // Primitives not packed yet
+    pending_tags: Vec<PrimitiveTag>, // Aperture function and D-code of each pending primitive
+    pub flashes: Flashes,            // Flashes not packed yet
+    pub traces: Traces,              // Draws not packed yet
+    current: GerberDataBuilder,      // Packed primitives of the current polarity
+    positive: Vec<GerberDataBuilder>,
+    negative: Vec<GerberDataBuilder>,
+    packed: usize, // Total packed primitives (security limit)
+}
+
+impl PolarityLayers {
+    /// Pack the pending primitives into the current sublayer
+    pub fn flush(&mut self) {
+        self.packed += self.pending.len();
+        self.pending_tags
+            .resize(self.pending.len(), PrimitiveTag::default());
+        self.current.push_batch(&self.pending, &self.pending_tags);
+        self.current
+            .push_flashes(&mut self.flashes, &mut self.traces);
+        self.pending.clear();
+        self.pending_tags.clear();
+    }
+
+    /// Give the primitives added to `pending` since the last call `tag`
+    pub fn tag_pending(&mut self, tag: PrimitiveTag) {
+        self.pending_tags.resize(self.pending.len(), tag);
+    }
+
+    /// Remove and return the pending primitives from `start` on
+    pub fn take_pending(&mut self, start: usize) -> Vec<Primitive> {
+        self.pending_tags.truncate(start);
+        self.pending.drain(start..).collect()
+    }
+
+    /// Total number of primitives packed so far
+    pub fn packed(&self) -> usize {
+        self.packed
+    }
+
+    /// Finish the current sublayer, e.g. when the polarity changes
+    pub fn close_layer(&mut self, polarity: Polarity) {
+        self.flush();
+        if self.current.is_empty() {
+            return;
+        }
+        let layer = take(&mut self.current);
+        if polarity == Polarity::Positive {
+            self.positive.push(layer);
+        } else {
+            self.negative.push(layer);
+        }
+    }
+
+    /// Copy of the sublayers packed so far, including the unfinished one
+    /// Order: [pos_layer1, neg_layer1, pos_layer2, neg_layer2, ...]
+    pub fn snapshot(&self, polarity: Polarity) -> Vec<GerberData> {
+        let current = (!self.current.is_empty()).then_some(&self.current);
+        let (open_positive, open_negative) = if polarity == Polarity::Positive {
+            (current, None)
+        } else {
+            (None, current)
+        };
+        interleave(
+            self.positive
+                .iter()
+                .chain(open_positive)
+                .map(GerberDataBuilder::snapshot),
+            self.negative
+                .iter()
+                .chain(open_negative)
+                .map(GerberDataBuilder::snapshot),
+        )
+    }
+
+    /// Finish parsing and return all sublayers
+    /// Order: [pos_layer1, neg_layer1, pos_layer2, neg_layer2, ...]
+    pub fn finish(mut self, polarity: Polarity) -> Vec<GerberData> {
+        self.close_layer(polarity);
+        interleave(
+            self.positive.into_iter().map(GerberDataBuilder::build),
+            self.negative.into_iter().map(GerberDataBuilder::build),
+        )
+    }
+}
+
+/// Alternate positive and negative sublayers, pairing them by index
+fn interleave(
+    positive: impl Iterator<Item = GerberData>,
+    negative: impl Iterator<Item = GerberData>,
+) -> Vec<GerberData> {
+    let (mut positive, mut negative) = (positive.fuse(), negative.fuse());
+    let mut layers = Vec::new();
+    loop {
+        let (pos, neg) = (positive.next(), negative.next());
+        if pos.is_none() && neg.is_none() {
+            return layers;
+        }
+        layers.extend(pos);
+        layers.extend(neg);
+    }
+}
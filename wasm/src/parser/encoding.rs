@@ -0,0 +1,31 @@
+use std::borrow::Cow;
+
+/// Decode raw Gerber file bytes into text
+///
+/// Gerber is 7-bit ASCII, but files in the wild carry a UTF-8 or UTF-16 BOM or
+/// Latin-1 text in comments. Valid UTF-8 is borrowed without copying; anything
+/// else falls back to Latin-1, which maps every byte to a character.
+pub fn decode_gerber_bytes(data: &[u8]) -> Cow<'_, str> {
+    // UTF-8 BOM
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+
+    // UTF-16 BOM (little or big endian)
+    if let Some(rest) = data.strip_prefix(b"\xFF\xFE") {
+        return Cow::Owned(decode_utf16(rest, u16::from_le_bytes));
+    }
+    if let Some(rest) = data.strip_prefix(b"\xFE\xFF") {
+        return Cow::Owned(decode_utf16(rest, u16::from_be_bytes));
+    }
+
+    match std::str::from_utf8(data) {
+        Ok(text) => Cow::Borrowed(text),
+        Err(_) => Cow::Owned(data.iter().map(|&b| b as char).collect()),
+    }
+}
+
+fn decode_utf16(data: &[u8], to_unit: fn([u8; 2]) -> u16) -> String {
+    let units = data.chunks_exact(2).map(|pair| to_unit([pair[0], pair[1]]));
+    char::decode_utf16(units)
+        .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect()
+}
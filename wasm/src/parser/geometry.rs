@@ -1,9 +1,14 @@
+use crate::parser::state::set_file_unit;
 use crate::parser::{Aperture, FormatSpec, ParserState};
+use crate::shape::{Flashes, Traces};
+use crate::simd;
+use crate::tessellation::arc_segments;
 use i_overlay::core::fill_rule::FillRule;
 use i_overlay::core::overlay_rule::OverlayRule;
 use i_overlay::float::single::SingleFloatOverlay;
 use i_triangle::float::triangulatable::Triangulatable;
 use std::collections::HashMap;
+use std::rc::Rc;
 
 /// Basic primitive shape - created directly by parser
 #[derive(Clone, Debug)]
@@ -39,9 +44,43 @@ pub enum Primitive {
         outer_diameter: f32,
         inner_diameter: f32,
         gap_thickness: f32,
-        rotation: f32,
+        rotation: f32, // Radians counterclockwise
         exposure: f32, // 1.0 = positive, 0.0 = negative
     },
+    Obround {
+        x: f32,
+        y: f32,
+        width: f32,        // Full extent along the local X axis
+        height: f32,       // Full extent along the local Y axis
+        rotation: f32,     // Rotation in radians (counterclockwise)
+        exposure: f32,     // 1.0 = positive, 0.0 = negative
+        hole_x: f32,       // Hole center X (absolute position)
+        hole_y: f32,       // Hole center Y (absolute position)
+        hole_radius: f32,  // Hole radius (0.0 = no hole)
+    },
+}
+
+impl Primitive {
+    /// Exposure of the primitive (1.0 = positive, 0.0 = negative)
+    pub fn exposure(&self) -> f32 {
+        match self {
+            Primitive::Triangle { exposure, .. }
+            | Primitive::Circle { exposure, .. }
+            | Primitive::Arc { exposure, .. }
+            | Primitive::Thermal { exposure, .. }
+            | Primitive::Obround { exposure, .. } => *exposure,
+        }
+    }
+
+    pub fn set_exposure(&mut self, value: f32) {
+        match self {
+            Primitive::Triangle { exposure, .. }
+            | Primitive::Circle { exposure, .. }
+            | Primitive::Arc { exposure, .. }
+            | Primitive::Thermal { exposure, .. }
+            | Primitive::Obround { exposure, .. } => *exposure = value,
+        }
+    }
 }
 
 /// Rotate point around given center
@@ -55,7 +94,7 @@ pub fn rotate_point(point: &mut [f32; 2], angle: f32, center_x: f32, center_y: f
     point[1] = center_y + x * sin_a + y * cos_a;
 }
 
-/// Scale a primitive by a given factor
+/// Scale a primitive by a given factor, anchored at the aperture origin
 pub fn scale_primitive(primitive: &mut Primitive, scale: f32) {
     if scale == 1.0 {
         return; // No scaling needed
@@ -63,42 +102,77 @@ pub fn scale_primitive(primitive: &mut Primitive, scale: f32) {
 
     match primitive {
         Primitive::Circle {
+            x,
+            y,
             radius,
+            hole_x,
+            hole_y,
             hole_radius,
             ..
         } => {
+            *x *= scale;
+            *y *= scale;
             *radius *= scale;
+            *hole_x *= scale;
+            *hole_y *= scale;
             *hole_radius *= scale;
         }
         Primitive::Triangle {
             vertices,
+            hole_x,
+            hole_y,
             hole_radius,
             ..
         } => {
-            for vertex in vertices.iter_mut() {
-                vertex[0] *= scale;
-                vertex[1] *= scale;
-            }
+            simd::transform_points(vertices.as_flattened_mut(), scale, 0.0, 0.0);
+            *hole_x *= scale;
+            *hole_y *= scale;
             *hole_radius *= scale;
         }
         Primitive::Arc {
+            x,
+            y,
             radius,
             thickness,
             ..
         } => {
+            *x *= scale;
+            *y *= scale;
             *radius *= scale;
             *thickness *= scale;
         }
         Primitive::Thermal {
+            x,
+            y,
             outer_diameter,
             inner_diameter,
             gap_thickness,
             ..
         } => {
+            *x *= scale;
+            *y *= scale;
             *outer_diameter *= scale;
             *inner_diameter *= scale;
             *gap_thickness *= scale;
         }
+        Primitive::Obround {
+            x,
+            y,
+            width,
+            height,
+            hole_x,
+            hole_y,
+            hole_radius,
+            ..
+        } => {
+            *x *= scale;
+            *y *= scale;
+            *width *= scale;
+            *height *= scale;
+            *hole_x *= scale;
+            *hole_y *= scale;
+            *hole_radius *= scale;
+        }
     }
 }
 
@@ -145,6 +219,68 @@ pub fn triangulate_outline(vertices: &[[f32; 2]], exposure: f32) -> Result<Vec<P
     }
 }
 
+/// Simplify a contour with the Douglas-Peucker algorithm
+///
+/// Drops points closer than `tolerance` to the simplified outline. The end
+/// points are kept, so closed contours stay closed. Returns the contour
+/// unchanged if simplification would leave fewer than 3 points.
+pub fn simplify_contour(points: &[[f32; 2]], tolerance: f32) -> Vec<[f32; 2]> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    // Explicit stack of index ranges: pours can have huge contours
+    let mut ranges = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let (a, b) = (points[first], points[last]);
+        let mut farthest = first;
+        let mut max_distance = tolerance;
+        for (offset, p) in points[first + 1..last].iter().enumerate() {
+            let distance = distance_to_segment(*p, a, b);
+            if distance > max_distance {
+                max_distance = distance;
+                farthest = first + 1 + offset;
+            }
+        }
+        if farthest != first {
+            keep[farthest] = true;
+            ranges.push((first, farthest));
+            ranges.push((farthest, last));
+        }
+    }
+
+    let simplified: Vec<[f32; 2]> = points
+        .iter()
+        .zip(&keep)
+        .filter(|(_, &keep)| keep)
+        .map(|(p, _)| *p)
+        .collect();
+    // A closed contour repeats its first point, so it needs 4 to keep an area
+    let closed = points[0] == points[points.len() - 1];
+    if simplified.len() < if closed { 4 } else { 3 } {
+        points.to_vec()
+    } else {
+        simplified
+    }
+}
+
+/// Distance from a point to the segment a-b
+fn distance_to_segment(p: [f32; 2], a: [f32; 2], b: [f32; 2]) -> f32 {
+    let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq > 0.0 {
+        (((p[0] - a[0]) * dx + (p[1] - a[1]) * dy) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let (cx, cy) = (a[0] + t * dx - p[0], a[1] + t * dy - p[1]);
+    (cx * cx + cy * cy).sqrt()
+}
+
 /// Split line into two triangles (including width)
 pub fn line_to_triangles(
     start_x: f32,
@@ -193,23 +329,26 @@ pub fn line_to_triangles(
     ]
 }
 
-/// Convert a primitive to a polygon (outer boundary as Vec<[f32; 2]>)
-pub fn primitive_to_polygon(primitive: &Primitive) -> Vec<[f32; 2]> {
+/// Convert a primitive to polygons (shape format: one outline per contour)
+///
+/// Most primitives are a single outline; a thermal is its four segments.
+/// Curves are flattened so that no edge deviates more than `max_error` from
+/// the true outline.
+pub fn primitive_to_polygon(primitive: &Primitive, max_error: f32) -> Vec<Vec<[f32; 2]>> {
     match primitive {
         Primitive::Circle { x, y, radius, .. } => {
-            // 36-sided polygon (10 degree increments)
-            let segments = 36;
+            let segments = arc_segments(*radius, 2.0 * std::f32::consts::PI, max_error);
             let mut vertices = Vec::with_capacity(segments);
             for i in 0..segments {
                 let angle = (i as f32) * (2.0 * std::f32::consts::PI / segments as f32);
                 vertices.push([x + radius * angle.cos(), y + radius * angle.sin()]);
             }
-            vertices
+            vec![vertices]
         }
 
         Primitive::Triangle { vertices, .. } => {
             // Already a polygon
-            vertices.to_vec()
+            vec![vertices.to_vec()]
         }
 
         Primitive::Arc {
@@ -218,93 +357,203 @@ pub fn primitive_to_polygon(primitive: &Primitive) -> Vec<[f32; 2]> {
             radius,
             start_angle,
             end_angle,
+            thickness,
             ..
         } => {
-            // Subdivide arc into 10-degree segments
-            let start_rad = start_angle.to_radians();
-            let end_rad = end_angle.to_radians();
-            let mut sweep = end_rad - start_rad;
-            if sweep < 0.0 {
-                sweep += 2.0 * std::f32::consts::PI;
+            // Annular sector covered by the stroke (angles in radians, signed
+            // sweep); the round ends are flashed separately by the parser
+            let sweep = end_angle - start_angle;
+            // Trace counterclockwise whatever the drawing direction
+            let start = if sweep < 0.0 {
+                *end_angle
+            } else {
+                *start_angle
+            };
+            let sweep = sweep.abs();
+            let outer_radius = radius + thickness / 2.0;
+            let inner_radius = (radius - thickness / 2.0).max(0.0);
+
+            let outer_segments = arc_segments(outer_radius, sweep, max_error);
+            let inner_segments = arc_segments(inner_radius, sweep, max_error);
+            let mut vertices = Vec::with_capacity(outer_segments + inner_segments + 2);
+            // Outer arc forward, then the end cap to the inner arc going back
+            for i in 0..=outer_segments {
+                let angle = start + sweep * i as f32 / outer_segments as f32;
+                vertices.push([
+                    x + outer_radius * angle.cos(),
+                    y + outer_radius * angle.sin(),
+                ]);
             }
-
-            let segment_angle = 10.0_f32.to_radians();
-            let num_segments = (sweep / segment_angle).ceil() as usize;
-
-            let mut vertices = Vec::with_capacity(num_segments + 1);
-            for i in 0..=num_segments {
-                let t = (i as f32) / (num_segments as f32);
-                let angle = start_rad + sweep * t;
-                vertices.push([x + radius * angle.cos(), y + radius * angle.sin()]);
+            if inner_radius > 0.0 {
+                for i in (0..=inner_segments).rev() {
+                    let angle = start + sweep * i as f32 / inner_segments as f32;
+                    vertices.push([
+                        x + inner_radius * angle.cos(),
+                        y + inner_radius * angle.sin(),
+                    ]);
+                }
+            } else {
+                // Stroke wider than the arc diameter: a pie slice
+                vertices.push([*x, *y]);
             }
-            vertices
+            vec![vertices]
         }
 
         Primitive::Thermal {
             x,
             y,
             outer_diameter,
+            inner_diameter,
+            gap_thickness,
+            rotation,
+            ..
+        } => thermal_segments(
+            [*x, *y],
+            outer_diameter / 2.0,
+            inner_diameter / 2.0,
+            gap_thickness / 2.0,
+            *rotation,
+            max_error,
+        ),
+
+        Primitive::Obround {
+            x,
+            y,
+            width,
+            height,
+            rotation,
             ..
         } => {
-            // Convert thermal to polygon
-            // For now, simplified to outer circle (can be refined later)
-            let outer_radius = outer_diameter / 2.0;
-            let segments = 36;
-
-            let mut vertices = Vec::with_capacity(segments);
-            for i in 0..segments {
-                let angle = (i as f32) * (2.0 * std::f32::consts::PI / segments as f32);
-                vertices.push([
-                    x + outer_radius * angle.cos(),
-                    y + outer_radius * angle.sin(),
-                ]);
+            // Two half circles joined by straight sides
+            let radius = width.min(*height) / 2.0;
+            let half_length = (width.max(*height) - width.min(*height)) / 2.0;
+            let along_x = width >= height;
+            let half_segments = arc_segments(radius, std::f32::consts::PI, max_error);
+
+            let mut vertices = Vec::with_capacity((half_segments + 1) * 2);
+            for end in 0..2 {
+                let sign = if end == 0 { 1.0 } else { -1.0 };
+                let (cx, cy) = if along_x {
+                    (sign * half_length, 0.0)
+                } else {
+                    (0.0, sign * half_length)
+                };
+                // Half circle facing away from the obround center
+                let base_angle = if along_x {
+                    -std::f32::consts::FRAC_PI_2
+                } else {
+                    0.0
+                } + end as f32 * std::f32::consts::PI;
+                for i in 0..=half_segments {
+                    let angle =
+                        base_angle + (i as f32) * std::f32::consts::PI / half_segments as f32;
+                    let mut point = [cx + radius * angle.cos(), cy + radius * angle.sin()];
+                    rotate_point(&mut point, *rotation, 0.0, 0.0);
+                    vertices.push([x + point[0], y + point[1]]);
+                }
             }
-            vertices
+            vec![vertices]
         }
     }
 }
 
-/// Apply sequential boolean operations to shapes (new version using Shape format)
-/// Input: Vec<(Shape, exposure)> where Shape is Vec<Contour> and Contour is Vec<Point>
-/// Returns: Vec<Primitive::Triangle> with all triangulated results
-pub fn apply_boolean_operations(shapes: &[(Vec<Vec<[f32; 2]>>, f32)]) -> Vec<Primitive> {
-    if shapes.is_empty() {
-        return Vec::new();
+/// Outlines of the four segments of a thermal
+///
+/// Each segment is the part of the ring between `inner_radius` and
+/// `outer_radius` in one quadrant, minus the gap strips of `half_gap` on
+/// either side of the (rotated) axes. The inner edge degenerates to the gap
+/// corner when the inner circle fits inside the crossing of the gaps.
+fn thermal_segments(
+    center: [f32; 2],
+    outer_radius: f32,
+    inner_radius: f32,
+    half_gap: f32,
+    rotation: f32,
+    max_error: f32,
+) -> Vec<Vec<[f32; 2]>> {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, SQRT_2};
+
+    let corner = half_gap * SQRT_2;
+    if outer_radius <= corner {
+        return Vec::new(); // The gaps cover the whole ring
     }
 
-    // Find first positive shape
-    let first_positive_idx = shapes.iter().position(|(_, exposure)| *exposure > 0.5);
-
-    let first_idx = match first_positive_idx {
-        Some(idx) => idx,
-        None => return Vec::new(), // No positive shapes to start with
+    let outer_margin = (half_gap / outer_radius).asin();
+    let inner_margin = if inner_radius > corner {
+        Some((half_gap / inner_radius).asin())
+    } else {
+        None
     };
 
-    // Start with first positive shape
-    let mut result_shapes: Vec<Vec<Vec<[f32; 2]>>> = vec![shapes[first_idx].0.clone()];
+    (0..4)
+        .map(|quadrant| {
+            let base = quadrant as f32 * FRAC_PI_2;
+            let mut points = Vec::new();
+
+            // Outer edge counterclockwise
+            let sweep = FRAC_PI_2 - 2.0 * outer_margin;
+            let segments = arc_segments(outer_radius, sweep, max_error);
+            for i in 0..=segments {
+                let angle = base + outer_margin + sweep * i as f32 / segments as f32;
+                points.push([outer_radius * angle.cos(), outer_radius * angle.sin()]);
+            }
 
-    // Apply boolean operations sequentially
-    for (i, (shape, exposure)) in shapes.iter().enumerate() {
-        if i == first_idx {
-            continue; // Skip the first shape we already added
-        }
+            // Inner edge back clockwise, or the corner where the gaps cross
+            match inner_margin {
+                Some(margin) => {
+                    let sweep = FRAC_PI_2 - 2.0 * margin;
+                    let segments = arc_segments(inner_radius, sweep, max_error);
+                    for i in 0..=segments {
+                        let angle = base + FRAC_PI_2 - margin - sweep * i as f32 / segments as f32;
+                        points.push([inner_radius * angle.cos(), inner_radius * angle.sin()]);
+                    }
+                }
+                None => {
+                    let angle = base + FRAC_PI_4;
+                    points.push([corner * angle.cos(), corner * angle.sin()]);
+                }
+            }
+
+            for point in &mut points {
+                rotate_point(point, rotation, 0.0, 0.0);
+                point[0] += center[0];
+                point[1] += center[1];
+            }
+            points
+        })
+        .collect()
+}
 
+/// Apply sequential boolean operations to shapes (new version using Shape format)
+/// Input: Vec<(Shape, exposure)> where Shape is Vec<Contour> and Contour is Vec<Point>
+/// Returns: Vec<Primitive::Triangle> with all triangulated results
+///
+/// Shapes are evaluated strictly in order starting from nothing: a negative
+/// shape only erases what the shapes before it exposed.
+pub fn apply_boolean_operations(shapes: &[(Vec<Vec<[f32; 2]>>, f32)]) -> Vec<Primitive> {
+    let mut result_shapes: Vec<Vec<Vec<[f32; 2]>>> = Vec::new();
+
+    // Apply boolean operations sequentially
+    for (shape, exposure) in shapes {
         if *exposure > 0.5 {
             // Positive: UNION
-            result_shapes =
-                result_shapes.overlay(&vec![shape.clone()], OverlayRule::Union, FillRule::NonZero);
-        } else {
-            // Negative: DIFFERENCE
+            if result_shapes.is_empty() {
+                result_shapes = vec![shape.clone()];
+            } else {
+                result_shapes = result_shapes.overlay(
+                    &vec![shape.clone()],
+                    OverlayRule::Union,
+                    FillRule::NonZero,
+                );
+            }
+        } else if !result_shapes.is_empty() {
+            // Negative: DIFFERENCE (nothing to erase before the first exposure)
             result_shapes = result_shapes.overlay(
                 &vec![shape.clone()],
                 OverlayRule::Difference,
                 FillRule::NonZero,
             );
         }
-
-        if result_shapes.is_empty() {
-            return Vec::new();
-        }
     }
 
     // Triangulate all result shapes (preserving holes)
@@ -465,6 +714,27 @@ pub fn offset_primitive_by(primitive: &Primitive, dx: f32, dy: f32) -> Primitive
             rotation: *rotation,
             exposure: *exposure,
         },
+        Primitive::Obround {
+            x,
+            y,
+            width,
+            height,
+            rotation,
+            exposure,
+            hole_x,
+            hole_y,
+            hole_radius,
+        } => Primitive::Obround {
+            x: x + dx,
+            y: y + dy,
+            width: *width,
+            height: *height,
+            rotation: *rotation,
+            exposure: *exposure,
+            hole_x: hole_x + dx,
+            hole_y: hole_y + dy,
+            hole_radius: *hole_radius,
+        },
     }
 }
 
@@ -506,20 +776,9 @@ pub fn convert_coordinate(
     format_spec: &FormatSpec,
     unit_multiplier: f32,
 ) -> f32 {
-    if let Ok(val) = coord_str.parse::<i64>() {
-        let divisor = match axis {
-            'x' => format_spec.x_divisor,
-            'y' => format_spec.y_divisor,
-            _ => 10000.0,
-        };
-
-        // Check for division by zero
-        if divisor == 0.0 || !divisor.is_finite() {
-            return 0.0;
-        }
-
-        // Divide by decimal point position (no padding) and then convert units (1.0 for mm, 25.4 for inch)
-        let result = (val as f64 / divisor) as f32 * unit_multiplier;
+    if let Some(value) = coordinate_value(coord_str, axis, format_spec) {
+        // Convert units (1.0 for mm, 25.4 for inch)
+        let result = value as f32 * unit_multiplier;
 
         // Check for numeric overflow
         if !result.is_finite() {
@@ -532,37 +791,176 @@ pub fn convert_coordinate(
     }
 }
 
+/// Coordinate in mm computed entirely in f64, including the coordinate scale
+fn exact_coordinate(coord_str: &str, axis: char, state: &ParserState) -> f64 {
+    match coordinate_value(coord_str, axis, &state.format_spec) {
+        Some(value) => {
+            // 25.4 is not exact in f32; use the exact inch factor
+            let unit = if state.unit_multiplier == 25.4 {
+                25.4
+            } else {
+                state.unit_multiplier as f64
+            };
+            let result = value * unit * state.scale as f64;
+            if result.is_finite() {
+                result
+            } else {
+                0.0
+            }
+        }
+        None => 0.0,
+    }
+}
+
+/// Coordinate digits divided by the decimal point position of the format spec
+///
+/// With trailing zero omission (%FST) the digits are the leading ones of the
+/// full-width number, so "15" in format 24 is padded to "150000" (15.0).
+fn coordinate_value(coord_str: &str, axis: char, format_spec: &FormatSpec) -> Option<f64> {
+    let val = coord_str.parse::<i64>().ok()?;
+    let (divisor, total_digits) = match axis {
+        'x' => (format_spec.x_divisor, format_spec.x_total_digits),
+        'y' => (format_spec.y_divisor, format_spec.y_total_digits),
+        _ => (10000.0, 6),
+    };
+
+    // Check for division by zero
+    if divisor == 0.0 || !divisor.is_finite() {
+        return None;
+    }
+    if format_spec.trailing_zeros {
+        let digits = coord_str.trim_start_matches('-').len() as i32;
+        return Some(val as f64 * 10_f64.powi(total_digits - digits) / divisor);
+    }
+    Some(val as f64 / divisor)
+}
+
+/// Cut a trailing negative circle into the positive primitives as an analytic hole
+///
+/// Applies when the circle is the only negative primitive and comes last, and
+/// every positive primitive it may touch is a circle, obround or triangle
+/// without a hole of its own. Since (A ∪ B) - hole = (A - hole) ∪ (B - hole),
+/// giving each of them the hole matches the boolean result exactly.
+fn cut_analytic_hole(primitives: &[Primitive]) -> Option<Vec<Primitive>> {
+    let (last, positives) = primitives.split_last()?;
+    let Primitive::Circle {
+        x: cx,
+        y: cy,
+        radius: r,
+        exposure,
+        hole_radius: 0.0,
+        ..
+    } = *last
+    else {
+        return None;
+    };
+    if exposure > 0.5 || positives.iter().any(|p| p.exposure() <= 0.5) {
+        return None;
+    }
+
+    // Conservative overlap test against a disk around the primitive
+    let near = |x: f32, y: f32, reach: f32| (x - cx).hypot(y - cy) < reach + r;
+    let mut cut = positives.to_vec();
+    for primitive in &mut cut {
+        let touches = match &*primitive {
+            Primitive::Circle { x, y, radius, .. } => near(*x, *y, *radius),
+            Primitive::Obround {
+                x,
+                y,
+                width,
+                height,
+                ..
+            } => near(*x, *y, width.hypot(*height) / 2.0),
+            Primitive::Triangle { vertices, .. } => {
+                // Bounding boxes overlap
+                let (min_x, max_x) = vertices.iter().fold((f32::MAX, f32::MIN), |(lo, hi), v| {
+                    (lo.min(v[0]), hi.max(v[0]))
+                });
+                let (min_y, max_y) = vertices.iter().fold((f32::MAX, f32::MIN), |(lo, hi), v| {
+                    (lo.min(v[1]), hi.max(v[1]))
+                });
+                cx + r > min_x && cx - r < max_x && cy + r > min_y && cy - r < max_y
+            }
+            Primitive::Arc {
+                x,
+                y,
+                radius,
+                thickness,
+                ..
+            } => near(*x, *y, radius + thickness / 2.0),
+            Primitive::Thermal {
+                x,
+                y,
+                outer_diameter,
+                ..
+            } => near(*x, *y, outer_diameter / 2.0),
+        };
+        if !touches {
+            continue;
+        }
+        match primitive {
+            Primitive::Circle {
+                hole_x,
+                hole_y,
+                hole_radius,
+                ..
+            }
+            | Primitive::Obround {
+                hole_x,
+                hole_y,
+                hole_radius,
+                ..
+            }
+            | Primitive::Triangle {
+                hole_x,
+                hole_y,
+                hole_radius,
+                ..
+            } if *hole_radius == 0.0 => {
+                *hole_x = cx;
+                *hole_y = cy;
+                *hole_radius = r;
+            }
+            _ => return None,
+        }
+    }
+    Some(cut)
+}
+
 /// Flash aperture at given position without Step and Repeat
 fn flash_aperture_no_sr(
     aperture: &Aperture,
     primitives: &mut Vec<Primitive>,
     x: f32,
     y: f32,
-    layer_scale: f32,
+    state: &ParserState,
 ) {
+    let layer_scale = state.aperture_scale();
     // Use pre-calculated has_negative field for performance
     if aperture.has_negative {
-        // Boolean operations with hole preservation
-        // Convert offset primitives to shapes
-        let shapes_with_exposure: Vec<(Vec<Vec<[f32; 2]>>, f32)> = aperture
+        let placed: Vec<Primitive> = aperture
             .primitives
             .iter()
             .map(|p| {
                 let mut scaled_primitive = p.clone();
                 scale_primitive(&mut scaled_primitive, layer_scale);
-                let offset_p = offset_primitive_by(&scaled_primitive, x, y);
-                let poly = primitive_to_polygon(&offset_p);
-                let exposure = match &offset_p {
-                    Primitive::Circle { exposure, .. } => *exposure,
-                    Primitive::Triangle { exposure, .. } => *exposure,
-                    Primitive::Arc { exposure, .. } => *exposure,
-                    Primitive::Thermal { exposure, .. } => *exposure,
-                };
-                // Wrap polygon in shape format (single contour)
-                (vec![poly], exposure)
+                offset_primitive_by(&scaled_primitive, x, y)
             })
             .collect();
 
+        // A plain round hole stays analytic instead of being triangulated
+        if let Some(cut) = cut_analytic_hole(&placed) {
+            primitives.extend(cut);
+            return;
+        }
+
+        // Boolean operations with hole preservation
+        // Convert offset primitives to shapes
+        let shapes_with_exposure: Vec<(Vec<Vec<[f32; 2]>>, f32)> = placed
+            .iter()
+            .map(|p| (primitive_to_polygon(p, state.chord_error), p.exposure()))
+            .collect();
+
         // Apply boolean operations with hole preservation
         let result_primitives = apply_boolean_operations(&shapes_with_exposure);
         primitives.extend(result_primitives);
@@ -579,10 +977,7 @@ fn flash_aperture_no_sr(
                     *hy += y;
                 }
                 Primitive::Triangle { vertices, hole_x, hole_y, .. } => {
-                    for vertex in vertices.iter_mut() {
-                        vertex[0] += x;
-                        vertex[1] += y;
-                    }
+                    simd::transform_points(vertices.as_flattened_mut(), 1.0, x, y);
                     *hole_x += x;
                     *hole_y += y;
                 }
@@ -594,6 +989,12 @@ fn flash_aperture_no_sr(
                     *tx += x;
                     *ty += y;
                 }
+                Primitive::Obround { x: ox, y: oy, hole_x: hx, hole_y: hy, .. } => {
+                    *ox += x;
+                    *oy += y;
+                    *hx += x;
+                    *hy += y;
+                }
             }
             primitives.push(new_primitive);
         }
@@ -603,7 +1004,7 @@ fn flash_aperture_no_sr(
 /// Flash aperture at given position - add all primitives of the aperture to the position
 pub fn flash_aperture(
     state: &ParserState,
-    apertures: &HashMap<String, Aperture>,
+    apertures: &HashMap<String, Rc<Aperture>>,
     primitives: &mut Vec<Primitive>,
     x: f32,
     y: f32,
@@ -614,17 +1015,73 @@ pub fn flash_aperture(
             for sx in 0..state.sr_x {
                 let flash_x = x + sx as f32 * state.sr_i;
                 let flash_y = y + sy as f32 * state.sr_j;
-                flash_aperture_no_sr(aperture, primitives, flash_x, flash_y, state.layer_scale);
+                flash_aperture_no_sr(aperture, primitives, flash_x, flash_y, state);
+            }
+        }
+    }
+}
+
+/// Warn once per D-code drawn or flashed while it has no definition
+///
+/// Such objects are skipped; a later %ADD for the D-code only applies to
+/// objects after it.
+fn warn_undefined_aperture(state: &mut ParserState, apertures: &HashMap<String, Rc<Aperture>>) {
+    let d_code = state.current_d_code;
+    if d_code == 0
+        || apertures.contains_key(&state.current_aperture)
+        || state.undefined_apertures.contains(&d_code)
+    {
+        return;
+    }
+    state.undefined_apertures.push(d_code);
+    state.warnings.push(format!(
+        "D{}: used before it was defined, objects skipped",
+        d_code
+    ));
+}
+
+/// Record a flash of the current aperture for pad queries
+///
+/// Flashes inside a block aperture definition are part of that aperture, not
+/// of the image, so they are not recorded.
+pub fn record_flash(
+    state: &ParserState,
+    apertures: &HashMap<String, Rc<Aperture>>,
+    flashes: &mut Flashes,
+    x: f32,
+    y: f32,
+) {
+    if !state.blocks.is_empty() {
+        return;
+    }
+    if let Some(aperture) = apertures.get(&state.current_aperture) {
+        for sy in 0..state.sr_y {
+            for sx in 0..state.sr_x {
+                flashes.x.push(x + sx as f32 * state.sr_i);
+                flashes.y.push(y + sy as f32 * state.sr_j);
+                flashes.d_code.push(state.current_d_code);
+                flashes.shape.push(aperture.shape as u8);
+                flashes
+                    .width
+                    .push(aperture.size[0] * state.aperture_scale());
+                flashes
+                    .height
+                    .push(aperture.size[1] * state.aperture_scale());
+                flashes.rotation.push(state.rotation);
             }
         }
     }
 }
 
 /// Execute interpolation (draw line or arc)
+///
+/// Draws outside block aperture definitions are also recorded in `traces`.
+#[allow(clippy::too_many_arguments)]
 pub fn execute_interpolation(
     state: &mut ParserState,
-    apertures: &HashMap<String, Aperture>,
+    apertures: &HashMap<String, Rc<Aperture>>,
     primitives: &mut Vec<Primitive>,
+    traces: &mut Traces,
     end_x: f32,
     end_y: f32,
     i: f32,
@@ -632,6 +1089,7 @@ pub fn execute_interpolation(
 ) {
     let start_x = state.x;
     let start_y = state.y;
+    let record = state.blocks.is_empty();
 
     // Get current aperture
     if let Some(_aperture) = apertures.get(&state.current_aperture) {
@@ -654,11 +1112,11 @@ pub fn execute_interpolation(
                                 primitives,
                                 sr_start_x,
                                 sr_start_y,
-                                state.layer_scale,
+                                state,
                             );
 
                             // Convert vector line with width of aperture diameter to triangle
-                            let diameter = aperture.radius * 2.0 * state.layer_scale;
+                            let diameter = aperture.radius * 2.0 * state.aperture_scale();
                             let line_triangles = line_to_triangles(
                                 sr_start_x,
                                 sr_start_y,
@@ -670,6 +1128,15 @@ pub fn execute_interpolation(
                             for triangle in line_triangles {
                                 primitives.push(triangle);
                             }
+                            if record {
+                                traces.push_segment(
+                                    [sr_start_x, sr_start_y],
+                                    [sr_end_x, sr_end_y],
+                                    None,
+                                    state.current_d_code,
+                                    diameter,
+                                );
+                            }
 
                             // Flash aperture at end point (no SR since we're already in SR loop)
                             flash_aperture_no_sr(
@@ -677,7 +1144,7 @@ pub fn execute_interpolation(
                                 primitives,
                                 sr_end_x,
                                 sr_end_y,
-                                state.layer_scale,
+                                state,
                             );
                         }
                     }
@@ -701,7 +1168,7 @@ pub fn execute_interpolation(
                                 primitives,
                                 sr_start_x,
                                 sr_start_y,
-                                state.layer_scale,
+                                state,
                             );
 
                             // Find the correct arc center
@@ -756,14 +1223,23 @@ pub fn execute_interpolation(
                             .sqrt();
                             let start_angle = (sr_start_y - center_y).atan2(sr_start_x - center_x);
                             let end_angle = (sr_end_y - center_y).atan2(sr_end_x - center_x);
-                            let thickness = aperture.radius * 2.0 * state.layer_scale;
+                            let thickness = aperture.radius * 2.0 * state.aperture_scale();
 
                             // Calculate sweep_angle considering direction
                             let mut sweep_angle = end_angle - start_angle;
                             let is_clockwise = state.interpolation_mode == "clockwise";
 
-                            // Normalize sweep angle based on direction
-                            if is_clockwise && sweep_angle > 0.0 {
+                            // Normalize sweep angle based on direction; in
+                            // multi-quadrant mode coinciding start and end
+                            // points make a full circle
+                            if sweep_angle == 0.0 && state.quadrant_mode == "multi" && radius > 0.0
+                            {
+                                sweep_angle = if is_clockwise {
+                                    -2.0 * std::f32::consts::PI
+                                } else {
+                                    2.0 * std::f32::consts::PI
+                                };
+                            } else if is_clockwise && sweep_angle > 0.0 {
                                 sweep_angle -= 2.0 * std::f32::consts::PI;
                             } else if !is_clockwise && sweep_angle < 0.0 {
                                 sweep_angle += 2.0 * std::f32::consts::PI;
@@ -790,6 +1266,15 @@ pub fn execute_interpolation(
                                 thickness,
                                 exposure: 1.0,
                             });
+                            if record {
+                                traces.push_segment(
+                                    [sr_start_x, sr_start_y],
+                                    [sr_end_x, sr_end_y],
+                                    Some(([center_x, center_y], sweep_angle)),
+                                    state.current_d_code,
+                                    thickness,
+                                );
+                            }
 
                             // Flash aperture at end point (no SR since we're already in SR loop)
                             flash_aperture_no_sr(
@@ -797,7 +1282,7 @@ pub fn execute_interpolation(
                                 primitives,
                                 sr_end_x,
                                 sr_end_y,
-                                state.layer_scale,
+                                state,
                             );
                         }
                     }
@@ -808,13 +1293,61 @@ pub fn execute_interpolation(
     }
 }
 
+/// Fill the contours collected since G36 and clear them
+///
+/// Called by G37 and by a polarity change inside the region, which splits
+/// the region: the contours so far are filled with the old polarity.
+pub fn close_region(
+    state: &ParserState,
+    primitives: &mut Vec<Primitive>,
+    region_contours: &mut Vec<Vec<[f32; 2]>>,
+) {
+    // Triangulate region and add to primitives with Step and Repeat
+    // Regions are always positive (add material)
+    for contour in region_contours.iter() {
+        let simplified;
+        let contour = if state.simplify_tolerance > 0.0 {
+            simplified = simplify_contour(contour, state.simplify_tolerance);
+            &simplified
+        } else {
+            contour
+        };
+        if contour.len() >= 3 {
+            match triangulate_outline(contour, 1.0) {
+                Ok(triangles) => {
+                    // Apply Step and Repeat to region triangles
+                    for sy in 0..state.sr_y {
+                        for sx in 0..state.sr_x {
+                            let offset_x = sx as f32 * state.sr_i;
+                            let offset_y = sy as f32 * state.sr_j;
+
+                            for triangle in &triangles {
+                                let offset_triangle =
+                                    offset_primitive_by(triangle, offset_x, offset_y);
+                                primitives.push(offset_triangle);
+                            }
+                        }
+                    }
+                }
+                Err(_e) => {
+                    // Triangulation failed, skip this contour
+                }
+            }
+        }
+    }
+
+    region_contours.clear();
+}
+
 /// Parse graphic commands - process G/D/XY codes
 /// Example: G01X1000Y2000D01* (draw line), X1000Y2000D03* (flash), etc.
 pub fn parse_graphic_command(
     line: &str,
     state: &mut ParserState,
-    apertures: &HashMap<String, Aperture>,
+    apertures: &HashMap<String, Rc<Aperture>>,
     primitives: &mut Vec<Primitive>,
+    flashes: &mut Flashes,
+    traces: &mut Traces,
     region_contours: &mut Vec<Vec<[f32; 2]>>,
 ) {
     let clean_line = line.trim_end_matches('*');
@@ -860,42 +1393,15 @@ pub fn parse_graphic_command(
                 37 => {
                     // G37: End region fill mode
                     state.region_mode = false;
-
-                    // Triangulate region and add to primitives with Step and Repeat
-                    // Regions are always positive (add material)
-                    for contour in region_contours.iter() {
-                        if contour.len() >= 3 {
-                            match triangulate_outline(contour, 1.0) {
-                                Ok(triangles) => {
-                                    // Apply Step and Repeat to region triangles
-                                    for sy in 0..state.sr_y {
-                                        for sx in 0..state.sr_x {
-                                            let offset_x = sx as f32 * state.sr_i;
-                                            let offset_y = sy as f32 * state.sr_j;
-
-                                            for triangle in &triangles {
-                                                let offset_triangle = offset_primitive_by(triangle, offset_x, offset_y);
-                                                primitives.push(offset_triangle);
-                                            }
-                                        }
-                                    }
-                                }
-                                Err(_e) => {
-                                    // Triangulation failed, skip this contour
-                                }
-                            }
-                        }
-                    }
-
-                    region_contours.clear();
+                    close_region(state, primitives, region_contours);
                 }
                 70 => {
                     // G70: Unit mode - Inches
-                    state.unit_multiplier = 25.4;
+                    set_file_unit(state, 25.4);
                 }
                 71 => {
                     // G71: Unit mode - Millimeters
-                    state.unit_multiplier = 1.0;
+                    set_file_unit(state, 1.0);
                 }
                 74 => {
                     // G74: Single quadrant mode
@@ -929,13 +1435,26 @@ pub fn parse_graphic_command(
 
     let mut x = state.x;
     let mut y = state.y;
+    let mut exact_x = state.exact_x;
+    let mut exact_y = state.exact_y;
     let mut i = 0.0;
     let mut j = 0.0;
 
     // Process X coordinate
-    if let Some(x_val) = x_match.as_ref() {
+    if let Some(x_val) = x_match.as_ref().filter(|_| state.high_precision) {
+        let mut new_x = exact_coordinate(x_val, 'x', state);
+        if state.mirror_x {
+            new_x = -new_x;
+        }
+        exact_x = if state.coordinate_mode == "absolute" {
+            new_x
+        } else {
+            state.exact_x + new_x
+        };
+        x = exact_x as f32;
+    } else if let Some(x_val) = x_match.as_ref() {
         let mut new_x =
-            convert_coordinate(x_val, 'x', &state.format_spec, state.unit_multiplier) * state.scale * state.layer_scale;
+            convert_coordinate(x_val, 'x', &state.format_spec, state.unit_multiplier) * state.scale;
         // Apply X mirroring
         if state.mirror_x {
             new_x = -new_x;
@@ -948,9 +1467,20 @@ pub fn parse_graphic_command(
     }
 
     // Process Y coordinate
-    if let Some(y_val) = y_match.as_ref() {
+    if let Some(y_val) = y_match.as_ref().filter(|_| state.high_precision) {
+        let mut new_y = exact_coordinate(y_val, 'y', state);
+        if state.mirror_y {
+            new_y = -new_y;
+        }
+        exact_y = if state.coordinate_mode == "absolute" {
+            new_y
+        } else {
+            state.exact_y + new_y
+        };
+        y = exact_y as f32;
+    } else if let Some(y_val) = y_match.as_ref() {
         let mut new_y =
-            convert_coordinate(y_val, 'y', &state.format_spec, state.unit_multiplier) * state.scale * state.layer_scale;
+            convert_coordinate(y_val, 'y', &state.format_spec, state.unit_multiplier) * state.scale;
         // Apply Y mirroring
         if state.mirror_y {
             new_y = -new_y;
@@ -965,7 +1495,7 @@ pub fn parse_graphic_command(
     // Process I coordinate (arc center X offset)
     if let Some(i_val) = i_match.as_ref() {
         let mut raw_i =
-            convert_coordinate(i_val, 'x', &state.format_spec, state.unit_multiplier) * state.scale * state.layer_scale;
+            convert_coordinate(i_val, 'x', &state.format_spec, state.unit_multiplier) * state.scale;
         // Apply X mirroring to I offset
         if state.mirror_x {
             raw_i = -raw_i;
@@ -980,7 +1510,7 @@ pub fn parse_graphic_command(
     // Process J coordinate (arc center Y offset)
     if let Some(j_val) = j_match.as_ref() {
         let mut raw_j =
-            convert_coordinate(j_val, 'y', &state.format_spec, state.unit_multiplier) * state.scale * state.layer_scale;
+            convert_coordinate(j_val, 'y', &state.format_spec, state.unit_multiplier) * state.scale;
         // Apply Y mirroring to J offset
         if state.mirror_y {
             raw_j = -raw_j;
@@ -999,6 +1529,7 @@ pub fn parse_graphic_command(
                 1 => {
                     // D01: Pen down (draw)
                     state.pen_state = "down".to_string();
+                    state.flashing = false;
 
                     // If in region mode, add coordinates to contour
                     if state.region_mode {
@@ -1007,7 +1538,8 @@ pub fn parse_graphic_command(
                             last_contour.push([x, y]);
                         }
                     } else {
-                        execute_interpolation(state, apertures, primitives, x, y, i, j);
+                        warn_undefined_aperture(state, apertures);
+                        execute_interpolation(state, apertures, primitives, traces, x, y, i, j);
                     }
                 }
                 2 => {
@@ -1024,28 +1556,40 @@ pub fn parse_graphic_command(
                         }
                     }
                 }
+                3 if state.region_mode => {
+                    // Regions are built from D01/D02 contours only
+                    let warning = "D03 flash inside a G36/G37 region ignored".to_string();
+                    if !state.warnings.contains(&warning) {
+                        state.warnings.push(warning);
+                    }
+                }
                 3 => {
                     // D03: Flash aperture at current position
-                    if !state.region_mode {
-                        flash_aperture(state, apertures, primitives, x, y);
-                    }
+                    state.flashing = true;
+                    warn_undefined_aperture(state, apertures);
+                    flash_aperture(state, apertures, primitives, x, y);
+                    record_flash(state, apertures, flashes, x, y);
                 }
                 10..=9999 => {
-                    // D10+: Aperture selection
-                    state.current_aperture = d_val;
+                    // D10+: Aperture selection, resolved when it is used
+                    // so it may come before the definition
+                    state.current_aperture = d_code.to_string();
+                    state.current_d_code = d_code as u16;
                 }
                 _ => {}
             }
         }
     } else if (x_match.is_some() || y_match.is_some()) && state.pen_state == "down" {
         // If there is only X/Y without D-code and the pen is down, execute interpolation
+        state.flashing = false;
         if state.region_mode {
             if !region_contours.is_empty() {
                 let last_contour = region_contours.last_mut().unwrap();
                 last_contour.push([x, y]);
             }
         } else {
-            execute_interpolation(state, apertures, primitives, x, y, i, j);
+            warn_undefined_aperture(state, apertures);
+            execute_interpolation(state, apertures, primitives, traces, x, y, i, j);
         }
     } else {
         // No drawing operation
@@ -1054,6 +1598,8 @@ pub fn parse_graphic_command(
     // Update state
     state.x = x;
     state.y = y;
+    state.exact_x = exact_x;
+    state.exact_y = exact_y;
     state.i = i;
     state.j = j;
 }
@@ -0,0 +1,213 @@
+use super::state::ParserState;
+use wasm_bindgen::prelude::*;
+
+/// Deprecated or missing construct of a Gerber file
+///
+/// Stored as one byte per finding in the geometry cache.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum LintRule {
+    SelectAperture = 0,        // G54 before a D-code
+    UnitCode = 1,              // G70/G71
+    CoordinateModeCode = 2,    // G90/G91
+    SingleQuadrant = 3,        // G74
+    TrailingZeros = 4,         // %FST
+    MissingFileAttributes = 5, // No %TF
+}
+
+impl LintRule {
+    pub fn from_u8(value: u8) -> Option<LintRule> {
+        match value {
+            0 => Some(LintRule::SelectAperture),
+            1 => Some(LintRule::UnitCode),
+            2 => Some(LintRule::CoordinateModeCode),
+            3 => Some(LintRule::SingleQuadrant),
+            4 => Some(LintRule::TrailingZeros),
+            5 => Some(LintRule::MissingFileAttributes),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            LintRule::SelectAperture => "g54",
+            LintRule::UnitCode => "unit_code",
+            LintRule::CoordinateModeCode => "coordinate_mode_code",
+            LintRule::SingleQuadrant => "single_quadrant",
+            LintRule::TrailingZeros => "trailing_zeros",
+            LintRule::MissingFileAttributes => "missing_file_attributes",
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            LintRule::SelectAperture => {
+                "G54 before an aperture select is deprecated; write the D-code alone (D10*)"
+            }
+            LintRule::UnitCode => "G70/G71 unit codes are deprecated; use %MOIN*% or %MOMM*%",
+            LintRule::CoordinateModeCode => {
+                "G90/G91 coordinate mode codes are deprecated; use absolute coordinates set by %FSLA..*%"
+            }
+            LintRule::SingleQuadrant => {
+                "Single-quadrant arc mode (G74) is deprecated; use multi-quadrant mode (G75)"
+            }
+            LintRule::TrailingZeros => {
+                "Trailing zero omission (%FST) is deprecated; use leading zero omission (%FSL)"
+            }
+            LintRule::MissingFileAttributes => {
+                "No %TF file attributes; the layer's function (e.g. .FileFunction) has to be guessed"
+            }
+        }
+    }
+
+    fn reference(self) -> &'static str {
+        match self {
+            LintRule::SelectAperture
+            | LintRule::UnitCode
+            | LintRule::CoordinateModeCode
+            | LintRule::SingleQuadrant => {
+                "Gerber Layer Format Specification 8.1 Deprecated Commands"
+            }
+            LintRule::TrailingZeros => {
+                "Gerber Layer Format Specification 8.2 Deprecated Command Options"
+            }
+            LintRule::MissingFileAttributes => {
+                "Gerber Layer Format Specification 5.2 File Attributes (TF)"
+            }
+        }
+    }
+}
+
+/// A rule broken by a file: where first and how often
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LintFinding {
+    pub rule: LintRule,
+    pub line: u32, // 1-based line of the first occurrence, 0 for the whole file
+    pub occurrences: u32,
+}
+
+/// Check one line for deprecated constructs and note file attributes
+///
+/// `line_number` is only called for the first occurrence of a rule, as it
+/// may have to count lines.
+pub fn lint_line(line: &str, state: &mut ParserState, line_number: impl Fn() -> usize) {
+    if line.contains("TF.") && (line.starts_with("%TF") || line.starts_with("G04")) {
+        // %TF.FileFunction,...*% or its G04 #@! comment form
+        state.file_attributes = true;
+    }
+    if line.starts_with("%FST") {
+        flag(state, LintRule::TrailingZeros, &line_number);
+    }
+    if !line.starts_with('G') || line.starts_with("G04") {
+        return;
+    }
+
+    // Every G code of the line, e.g. G90 and G70 in G90G70*
+    let mut rest = line;
+    while let Some(start) = rest.find('G') {
+        let digits = &rest[start + 1..];
+        let end = digits
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(digits.len());
+        let rule = match digits[..end].parse::<u32>() {
+            Ok(54) => Some(LintRule::SelectAperture),
+            Ok(70 | 71) => Some(LintRule::UnitCode),
+            Ok(90 | 91) => Some(LintRule::CoordinateModeCode),
+            Ok(74) => Some(LintRule::SingleQuadrant),
+            _ => None,
+        };
+        if let Some(rule) = rule {
+            flag(state, rule, &line_number);
+        }
+        rest = &digits[end..];
+    }
+}
+
+/// Findings of a parse, with missing file attributes last
+pub fn lint_findings(state: &ParserState) -> Vec<LintFinding> {
+    let mut findings = state.lint.clone();
+    if !state.file_attributes {
+        findings.push(LintFinding {
+            rule: LintRule::MissingFileAttributes,
+            line: 0,
+            occurrences: 1,
+        });
+    }
+    findings
+}
+
+fn flag(state: &mut ParserState, rule: LintRule, line_number: &impl Fn() -> usize) {
+    match state.lint.iter_mut().find(|f| f.rule == rule) {
+        Some(finding) => finding.occurrences += 1,
+        None => state.lint.push(LintFinding {
+            rule,
+            line: line_number() as u32,
+            occurrences: 1,
+        }),
+    }
+}
+
+/// Deprecated constructs of a layer's Gerber file, one entry per rule
+///
+/// Each entry has the rule name, an explanation with the modern
+/// replacement, the Gerber specification section, the line of the first
+/// occurrence (0 for findings about the whole file) and how often the
+/// construct occurs.
+#[wasm_bindgen]
+pub struct LintReport {
+    pub(crate) findings: Vec<LintFinding>,
+}
+
+impl LintReport {
+    pub fn new(findings: &[LintFinding]) -> LintReport {
+        LintReport {
+            findings: findings.to_vec(),
+        }
+    }
+}
+
+#[wasm_bindgen]
+impl LintReport {
+    /// Number of rules the file breaks
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.findings.len()
+    }
+
+    /// Rule of each finding: "g54", "unit_code", "coordinate_mode_code",
+    /// "single_quadrant", "trailing_zeros" or "missing_file_attributes"
+    #[wasm_bindgen(getter)]
+    pub fn rule(&self) -> Vec<String> {
+        self.findings
+            .iter()
+            .map(|f| f.rule.name().to_string())
+            .collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> Vec<String> {
+        self.findings
+            .iter()
+            .map(|f| f.rule.message().to_string())
+            .collect()
+    }
+
+    /// Section of the Gerber Layer Format Specification for each finding
+    #[wasm_bindgen(getter)]
+    pub fn reference(&self) -> Vec<String> {
+        self.findings
+            .iter()
+            .map(|f| f.rule.reference().to_string())
+            .collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn line(&self) -> Vec<u32> {
+        self.findings.iter().map(|f| f.line).collect()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn occurrences(&self) -> Vec<u32> {
+        self.findings.iter().map(|f| f.occurrences).collect()
+    }
+}
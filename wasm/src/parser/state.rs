@@ -1,5 +1,10 @@
+use super::attributes::ApertureFunction;
 use super::geometry::Primitive;
-use std::mem::take;
+use super::lint::LintFinding;
+use super::PolarityLayers;
+use crate::layer_info::UnitSource;
+use crate::tessellation::DEFAULT_CHORD_ERROR;
+use std::collections::HashMap;
 
 /// Polarity - Dark (positive) or Clear (negative)
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -16,10 +21,11 @@ pub struct FormatSpec {
     pub y_integer_digits: u32,
     pub y_decimal_digits: u32,
     // Cached calculation values - performance optimization
-    pub x_divisor: f64,      // 10^(x_decimal_digits)
-    pub y_divisor: f64,      // 10^(y_decimal_digits)
-    pub x_total_digits: i32, // x_integer_digits + x_decimal_digits
-    pub y_total_digits: i32, // y_integer_digits + y_decimal_digits
+    pub x_divisor: f64,       // 10^(x_decimal_digits)
+    pub y_divisor: f64,       // 10^(y_decimal_digits)
+    pub x_total_digits: i32,  // x_integer_digits + x_decimal_digits
+    pub y_total_digits: i32,  // y_integer_digits + y_decimal_digits
+    pub trailing_zeros: bool, // %FST: trailing zeros omitted instead of leading
 }
 
 impl Default for FormatSpec {
@@ -33,35 +39,71 @@ impl Default for FormatSpec {
             y_divisor: 10000.0, // 10^4
             x_total_digits: 6,  // 2 + 4
             y_total_digits: 6,  // 2 + 4
+            trailing_zeros: false,
         }
     }
 }
 
+/// Block aperture being defined, from its %ABD..*% up to the closing %AB*%
+pub struct OpenBlock {
+    pub code: String,             // Aperture code the block defines
+    pub start: usize,             // First pending primitive of the block
+    pub segment_start: usize,     // First pending primitive of the current polarity
+    pub outer_polarity: Polarity, // Polarity to restore when the block closes
+}
+
 pub struct ParserState {
     pub x: f32,
     pub y: f32,
-    pub current_aperture: String,
+    pub current_aperture: String, // Aperture key, D-code without leading zeros
+    pub current_d_code: u16,      // current_aperture as a number (0 = none selected)
     pub interpolation_mode: String,
     pub quadrant_mode: String,
     pub region_mode: bool,
     pub coordinate_mode: String,
     pub scale: f32,
     pub unit_multiplier: f32, // 1.0 for mm, 25.4 for inch
+    pub unit_source: UnitSource,
     pub i: f32,
     pub j: f32,
     pub pen_state: String,
+    pub flashing: bool, // Last operation was a D03 flash, not a D01 draw
     pub polarity: Polarity,
+    pub image_polarity: Polarity, // %IP: Negative inverts the rendered image
     pub format_spec: FormatSpec,
+    pub format_declared: bool, // %FS seen, otherwise format_spec is the default
     // Step and Repeat settings
     pub sr_x: u32,
     pub sr_y: u32,
     pub sr_i: f32,
     pub sr_j: f32,
-    // Layer Scaling
+    // Layer Scaling, applied to aperture dimensions but never to coordinates
     pub layer_scale: f32,
     // Layer Mirroring
     pub mirror_x: bool,
     pub mirror_y: bool,
+    // Layer rotation in degrees (recorded with flashes, geometry is not rotated)
+    pub rotation: f32,
+    // Douglas-Peucker tolerance for region contours in mm (0.0 = off)
+    pub simplify_tolerance: f32,
+    // Maximum deviation of flattened curves in mm (boolean operations)
+    pub chord_error: f32,
+    // f64 coordinate conversion; exact_x/exact_y hold the unrounded position
+    pub high_precision: bool,
+    pub exact_x: f64,
+    pub exact_y: f64,
+    // Block apertures being defined, innermost last
+    pub blocks: Vec<OpenBlock>,
+    pub unmatched_block_ends: usize,   // %AB*% without an open block
+    pub end_of_file: bool,             // M02 reached; only blank lines may follow
+    pub warnings: Vec<String>,         // Problems that did not fail the parse
+    pub undefined_apertures: Vec<u16>, // D-codes already warned about as undefined
+    pub lint: Vec<LintFinding>,        // Deprecated constructs, one per rule
+    pub file_attributes: bool,         // %TF seen
+    pub file_function: String,         // %TF.FileFunction value, e.g. "Copper,L1,Top"
+    // X2 .AperFunction in the attribute dictionary and per aperture code
+    pub aperture_function: ApertureFunction,
+    pub aperture_functions: HashMap<String, ApertureFunction>,
 }
 
 impl Default for ParserState {
@@ -70,17 +112,22 @@ impl Default for ParserState {
             x: 0.0,
             y: 0.0,
             current_aperture: String::new(),
+            current_d_code: 0,
             interpolation_mode: "linear".to_string(),
             quadrant_mode: "single".to_string(),
             region_mode: false,
             coordinate_mode: "absolute".to_string(),
             scale: 1.0,
             unit_multiplier: 1.0, // Default to mm
+            unit_source: UnitSource::Default,
             i: 0.0,
             j: 0.0,
             pen_state: "up".to_string(),
+            flashing: false,
             polarity: Polarity::Positive,
+            image_polarity: Polarity::Positive,
             format_spec: FormatSpec::default(),
+            format_declared: false,
             sr_x: 1,
             sr_y: 1,
             sr_i: 0.0,
@@ -88,6 +135,36 @@ impl Default for ParserState {
             layer_scale: 1.0,
             mirror_x: false,
             mirror_y: false,
+            rotation: 0.0,
+            simplify_tolerance: 0.0,
+            chord_error: DEFAULT_CHORD_ERROR,
+            high_precision: false,
+            exact_x: 0.0,
+            exact_y: 0.0,
+            blocks: Vec::new(),
+            unmatched_block_ends: 0,
+            end_of_file: false,
+            warnings: Vec::new(),
+            undefined_apertures: Vec::new(),
+            lint: Vec::new(),
+            file_attributes: false,
+            file_function: String::new(),
+            aperture_function: ApertureFunction::None,
+            aperture_functions: HashMap::new(),
+        }
+    }
+}
+
+impl ParserState {
+    /// Scale for apertures flashed or drawn now
+    ///
+    /// Inside a block aperture definition the contents stay unscaled, as the
+    /// %LS in effect when the block is flashed scales the block as a whole.
+    pub fn aperture_scale(&self) -> f32 {
+        if self.blocks.is_empty() {
+            self.layer_scale
+        } else {
+            1.0
         }
     }
 }
@@ -116,8 +193,8 @@ pub fn parse_format_spec(line: &str, state: &mut ParserState) {
         return;
     }
 
-    // L/T: Leading (L) or Trailing (T) zeros
-    let _leading_type = chars[pos];
+    // L/T: Leading (L) or Trailing (T) zeros omitted
+    state.format_spec.trailing_zeros = chars[pos] == 'T';
     pos += 1;
 
     if pos >= chars.len() {
@@ -157,6 +234,8 @@ pub fn parse_format_spec(line: &str, state: &mut ParserState) {
         }
     }
 
+    state.format_declared = true;
+
     // Save mode
     if mode == 'I' {
         state.coordinate_mode = "incremental".to_string();
@@ -244,14 +323,8 @@ pub fn parse_sr(line: &str, state: &mut ParserState) {
 }
 
 /// Parse Polarity - %LPD* (positive) or %LPC* (negative)
-/// Save the primitives accumulated so far each time the polarity changes
-pub fn parse_lp(
-    line: &str,
-    state: &mut ParserState,
-    current_primitives: &mut Vec<Primitive>,
-    positive_layers: &mut Vec<Vec<Primitive>>,
-    negative_layers: &mut Vec<Vec<Primitive>>,
-) {
+/// Close the current sublayer each time the polarity changes
+pub fn parse_lp(line: &str, state: &mut ParserState, layers: &mut PolarityLayers) {
     // Extract D or C from %LPD* or %LPC* format
     let spec_str = line
         .trim_start_matches('%')
@@ -271,12 +344,14 @@ pub fn parse_lp(
     };
 
     // Check if polarity has changed
-    if state.polarity != new_polarity && !current_primitives.is_empty() {
-        // Save to layer according to current polarity
-        if state.polarity == Polarity::Positive {
-            positive_layers.push(take(current_primitives));
+    if state.polarity != new_polarity {
+        if let Some(block) = state.blocks.last_mut() {
+            // Inside a block the polarity goes into the block's primitives
+            mark_polarity(&mut layers.pending[block.segment_start..], state.polarity);
+            block.segment_start = layers.pending.len();
         } else {
-            negative_layers.push(take(current_primitives));
+            // Save to layer according to current polarity
+            layers.close_layer(state.polarity);
         }
     }
 
@@ -284,28 +359,30 @@ pub fn parse_lp(
     state.polarity = new_polarity;
 }
 
-pub fn parse_if(line: &str, state: &mut ParserState) {
-    // %IFPOS*% or %IFNEG*% format
+/// Give primitives drawn under clear polarity exposure 0
+pub fn mark_polarity(primitives: &mut [Primitive], polarity: Polarity) {
+    if polarity == Polarity::Negative {
+        for primitive in primitives {
+            primitive.set_exposure(0.0);
+        }
+    }
+}
+
+/// Parse Image Polarity - %IPPOS*% (dark image) or %IPNEG*% (inverted image)
+///
+/// A negative image does not change how objects are parsed: the whole image
+/// is inverted when the layer is rendered.
+pub fn parse_ip(line: &str, state: &mut ParserState) {
     let spec_str = line
         .trim_start_matches('%')
         .trim_end_matches('%')
         .trim_end_matches('*');
 
-    if !spec_str.starts_with("IF") {
-        return;
-    }
-
-    let polarity_str = &spec_str[2..]; // "POS" or "NEG" part
-
-    let new_polarity = if polarity_str == "POS" {
-        Polarity::Positive
-    } else if polarity_str == "NEG" {
-        Polarity::Negative
-    } else {
-        return;
+    state.image_polarity = match spec_str.strip_prefix("IP") {
+        Some("POS") => Polarity::Positive,
+        Some("NEG") => Polarity::Negative,
+        _ => return,
     };
-
-    state.polarity = new_polarity;
 }
 
 /// Parse Unit mode - %MOMM* (millimeters) or %MOIN* (inches)
@@ -322,18 +399,28 @@ pub fn parse_mo(line: &str, state: &mut ParserState) {
 
     let unit_str = &spec_str[2..]; // "MM" or "IN" part
 
-    state.unit_multiplier = if unit_str == "MM" {
+    let unit_multiplier = if unit_str == "MM" {
         1.0 // mm
     } else if unit_str == "IN" {
         25.4 // inch to mm
     } else {
         return;
     };
+    set_file_unit(state, unit_multiplier);
+}
+
+/// Apply a unit declared by the file (%MO, G70/G71) unless one was forced
+pub fn set_file_unit(state: &mut ParserState, unit_multiplier: f32) {
+    if state.unit_source != UnitSource::Forced {
+        state.unit_multiplier = unit_multiplier;
+        state.unit_source = UnitSource::File;
+    }
 }
 
 /// Parse Layer Scaling - %LS0.8*
 /// Format: %LS[scale_factor]*%
-/// Example: %LS0.5* scales all subsequent coordinates by 0.5
+/// Example: %LS0.5* halves the apertures of all subsequent flashes and draws;
+/// coordinates are not scaled
 pub fn parse_ls(line: &str, state: &mut ParserState) {
     // Extract scale value from %LS0.8*% format
     let spec_str = line
@@ -348,15 +435,26 @@ pub fn parse_ls(line: &str, state: &mut ParserState) {
     let scale_str = &spec_str[2..]; // "0.8" part
 
     if let Ok(new_scale) = scale_str.parse::<f32>() {
-        if state.layer_scale != 0.0 && new_scale != state.layer_scale {
-            let ratio = new_scale / state.layer_scale;
-            state.x *= ratio;
-            state.y *= ratio;
-        }
         state.layer_scale = new_scale;
     }
 }
 
+/// Parse Layer Rotation - %LR45.0*%
+/// Format: %LR[degrees]*%, counterclockwise around each object's origin
+pub fn parse_lr(line: &str, state: &mut ParserState) {
+    let spec_str = line
+        .trim_start_matches('%')
+        .trim_end_matches('%')
+        .trim_end_matches('*');
+
+    if let Some(degrees) = spec_str
+        .strip_prefix("LR")
+        .and_then(|value| value.parse::<f32>().ok())
+    {
+        state.rotation = degrees;
+    }
+}
+
 /// Parse Layer Mirroring - %LMN*%, %LMX*%, %LMY*%, %LMXY*%
 /// Format: %LM[N|X|Y|XY]*%
 /// - N: No mirroring (default)
@@ -1,36 +1,92 @@
 mod buffer;
-mod camera;
+pub(crate) mod camera;
+mod collapse;
+mod compare;
+mod dimensions;
+mod dirty;
+mod filter;
+mod functions;
+mod highlight;
+mod minimap;
+mod overlay;
+mod quantize;
+mod region;
 mod shader;
+mod view3d;
+mod xray;
 
 // Internal use only
-use buffer::{BufferCache, Fbo};
+use buffer::{BufferCache, Fbo, MultisampleTarget};
 use camera::Camera;
+use collapse::CollapsedLayer;
+use compare::CompareMode;
+use dimensions::DimensionOverlay;
+use functions::FunctionColors;
+use highlight::ApertureHighlight;
+use minimap::Minimap;
+use overlay::{
+    CourtyardOverlay, CrossSectionOverlay, Heatmap, MarkerOverlay, NetOverlay, ViaOverlay,
+};
+use quantize::QuantizedCircles;
 use shader::{
-    ShaderProgram, ShaderPrograms, ARRAY_BUFFER, BLEND, COLOR_BUFFER_BIT, ELEMENT_ARRAY_BUFFER,
-    FLOAT, FUNC_ADD, ONE, ONE_MINUS_SRC_ALPHA, STATIC_DRAW, TRIANGLES, UNSIGNED_INT, ZERO,
+    ShaderProgram, ShaderPrograms, ARRAY_BUFFER, BLEND, COLOR_BUFFER_BIT, DEPTH_BUFFER_BIT,
+    DEPTH_TEST, ELEMENT_ARRAY_BUFFER, FLOAT, FUNC_ADD, LEQUAL, LESS, ONE, STATIC_DRAW, TRIANGLES,
+    UNSIGNED_BYTE, UNSIGNED_INT, UNSIGNED_SHORT,
 };
-
-use crate::shape::{Boundary, GerberData};
+use view3d::View3d;
+use xray::XrayMode;
+
+use crate::analysis::ComponentSide;
+use crate::error::ViewerError;
+use crate::options::{PrimitiveFilter, RenderOptions};
+use crate::parser::Polarity;
+use crate::shape::{Boundary, Triangles};
+use crate::simd::interleave_xy;
+use crate::store::LayerStore;
+use crate::view::CameraView;
 use js_sys::Float32Array;
 use wasm_bindgen::prelude::*;
-use web_sys::{WebGl2RenderingContext, WebGlBuffer, WebGlTexture};
+use web_sys::{
+    WebGl2RenderingContext, WebGlBuffer, WebGlFramebuffer, WebGlRenderbuffer, WebGlTexture,
+};
 
-/// Metadata for a single user layer (may contain multiple polarity sublayers)
+/// GPU resources of a single user layer (may contain multiple polarity sublayers)
+///
+/// The geometry itself lives in the LayerStore under the same layer ID.
 pub struct LayerMetadata {
-    gerber_data: Vec<GerberData>,    // Polarity sublayers for this layer
     fbo: Fbo,                        // FBO for rendering this layer
-    buffer_caches: Vec<BufferCache>, // Buffer cache per polarity sublayer
-    boundary: Boundary,              // Combined boundary
+    buffers: BufferCache,            // Buffers of all polarity sublayers
+    filter: Option<PrimitiveFilter>, // Primitives drawn (None = all)
 }
 
 /// WebGL renderer for Gerber graphics with multi-layer support
 pub struct Renderer {
     gl: WebGl2RenderingContext,
     layers: Vec<Option<LayerMetadata>>, // Sparse vec (None = deallocated slot)
-    layer_count: usize,                 // Active layer count
     programs: ShaderPrograms,
     camera: Camera,
-    quad_buffer: WebGlBuffer, // Shared quad buffer for all layers
+    quad_buffer: WebGlBuffer,           // Shared quad buffer for all layers
+    heatmap: Option<Heatmap>,           // Copper density overlay (None = hidden)
+    markers: Option<MarkerOverlay>,     // DRC marker overlay (None = hidden)
+    fiducials: Option<MarkerOverlay>,   // Fiducial marker overlay (None = hidden)
+    test_points: Option<MarkerOverlay>, // Test point marker overlay (None = hidden)
+    inferred_drills: Option<MarkerOverlay>, // Inferred drill preview (None = hidden)
+    cross_section: Option<CrossSectionOverlay>, // Stackup cut view (None = hidden)
+    net: Option<NetOverlay>,            // Highlighted connectivity net (None = hidden)
+    vias: Option<ViaOverlay>,           // Via stitching map (None = hidden)
+    courtyards: [Option<CourtyardOverlay>; 2], // Per ComponentSide (None = hidden)
+    dimensions: Option<DimensionOverlay>, // Board width and height annotation (None = hidden)
+    minimap: Option<Minimap>,           // Board overview in a corner (None = hidden)
+    view3d: Option<View3d>,             // Extruded 3D view (None = normal 2D view)
+    compare: Option<CompareMode>,       // Two-layer compare display (None = normal compositing)
+    xray: Option<XrayMode>,             // Translucent copper stack (None = additive compositing)
+    function_colors: Option<FunctionColors>, // Layer colored by aperture function (None = off)
+    highlight: Option<ApertureHighlight>, // Aperture emphasized in one layer (None = off)
+    msaa_samples: i32,                  // Multisamples of layer FBOs (0 = no antialiasing)
+    min_feature_pixels: f32,            // Level of detail: smallest drawn flash or arc on screen
+    min_stroke_pixels: f32,             // Narrower features are widened to this on screen (0 = off)
+    frame: Option<Fbo>, // Back buffer composited into before the canvas (None = off)
+    dirty: Vec<[i32; 4]>, // Canvas rectangles queued for render_dirty() (x, y, w, h)
 }
 
 impl Renderer {
@@ -45,244 +101,352 @@ impl Renderer {
         Ok(Renderer {
             gl,
             layers: Vec::new(),
-            layer_count: 0,
             programs,
             camera: Camera::new(),
             quad_buffer,
+            heatmap: None,
+            markers: None,
+            fiducials: None,
+            test_points: None,
+            inferred_drills: None,
+            cross_section: None,
+            net: None,
+            vias: None,
+            courtyards: [None, None],
+            dimensions: None,
+            minimap: None,
+            view3d: None,
+            compare: None,
+            xray: None,
+            function_colors: None,
+            highlight: None,
+            msaa_samples: 0,
+            min_feature_pixels: 0.0,
+            min_stroke_pixels: 0.0,
+            frame: None,
+            dirty: Vec::new(),
         })
     }
 
-    /// Add a new layer with parsed Gerber data
-    /// Returns the layer index (layer_id)
-    pub fn add_layer(&mut self, gerber_data: Vec<GerberData>) -> Result<usize, JsValue> {
+    /// Allocate GPU resources for a layer stored under `layer_id`
+    pub fn add_layer(&mut self, layer_id: usize) -> Result<(), JsValue> {
         let (width, height) = self.get_canvas_size()?;
 
-        // Calculate combined boundary from all polarity sublayers
-        let mut min_x = f32::INFINITY;
-        let mut max_x = f32::NEG_INFINITY;
-        let mut min_y = f32::INFINITY;
-        let mut max_y = f32::NEG_INFINITY;
-
-        for data in &gerber_data {
-            let b = &data.boundary;
-            min_x = min_x.min(b.min_x);
-            max_x = max_x.max(b.max_x);
-            min_y = min_y.min(b.min_y);
-            max_y = max_y.max(b.max_y);
-        }
-
-        let boundary = Boundary::new(min_x, max_x, min_y, max_y);
-
         // Create FBO for this layer
-        let fbo = Self::create_fbo(&self.gl, width, height)?;
-
-        // Create buffer caches for each polarity sublayer
-        let mut buffer_caches = Vec::new();
-        for _ in 0..gerber_data.len() {
-            buffer_caches.push(BufferCache {
-                triangle_vao: None,
-                triangle_vertex_buffer: None,
-                triangle_index_buffer: None,
-                triangle_hole_center_buffer: None,
-                triangle_hole_radius_buffer: None,
-                circle_vao: None,
-                circle_center_buffer: None,
-                circle_radius_buffer: None,
-                circle_hole_center_buffer: None,
-                circle_hole_radius_buffer: None,
-                arc_vao: None,
-                arc_center_buffer: None,
-                arc_radius_buffer: None,
-                arc_start_angle_buffer: None,
-                arc_sweep_angle_buffer: None,
-                arc_thickness_buffer: None,
-                thermal_vao: None,
-                thermal_center_buffer: None,
-                thermal_outer_diameter_buffer: None,
-                thermal_inner_diameter_buffer: None,
-                thermal_gap_thickness_buffer: None,
-                thermal_rotation_buffer: None,
-            });
-        }
-
-        let layer_metadata = LayerMetadata {
-            gerber_data,
-            fbo,
-            buffer_caches,
-            boundary,
-        };
+        let fbo = self.create_layer_fbo(width, height)?;
 
-        // Find next free slot or extend vec
-        if let Some(free_slot) = self.layers.iter().position(|layer| layer.is_none()) {
-            self.layers[free_slot] = Some(layer_metadata);
-            self.layer_count += 1;
-            Ok(free_slot)
-        } else {
-            self.layers.push(Some(layer_metadata));
-            self.layer_count += 1;
-            Ok(self.layers.len() - 1)
+        // Use the same slot as the layer store
+        if layer_id >= self.layers.len() {
+            self.layers.resize_with(layer_id + 1, || None);
         }
+        self.layers[layer_id] = Some(LayerMetadata {
+            fbo,
+            buffers: BufferCache::default(),
+            filter: None,
+        });
+        Ok(())
     }
 
     /// Remove a layer by index
     pub fn remove_layer(&mut self, layer_id: usize) -> Result<(), JsValue> {
         if layer_id >= self.layers.len() || self.layers[layer_id].is_none() {
-            return Err(JsValue::from_str(&format!(
-                "Invalid layer_id: {}",
-                layer_id
-            )));
+            return Err(ViewerError::invalid_layer(
+                format!("Invalid layer_id: {}", layer_id),
+                layer_id as u32,
+            )
+            .into());
         }
 
         // Remove layer metadata (which will drop cached WebGL resources)
         if let Some(layer) = self.layers[layer_id].take() {
-            // Delete framebuffer and texture
-            self.gl.delete_framebuffer(Some(&layer.fbo.framebuffer));
-            self.gl.delete_texture(Some(&layer.fbo.texture));
+            Self::delete_fbo(&self.gl, layer.fbo);
 
             // Delete all cached buffers and VAOs
-            for cache in layer.buffer_caches {
-                // Delete triangle cache
-                if let Some(vao) = cache.triangle_vao {
-                    self.gl.delete_vertex_array(Some(&vao));
-                }
-                if let Some(buf) = cache.triangle_vertex_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.triangle_index_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-
-                // Delete circle cache
-                if let Some(vao) = cache.circle_vao {
-                    self.gl.delete_vertex_array(Some(&vao));
-                }
-                if let Some(buf) = cache.circle_center_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.circle_radius_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-
-                // Delete arc cache
-                if let Some(vao) = cache.arc_vao {
-                    self.gl.delete_vertex_array(Some(&vao));
-                }
-                if let Some(buf) = cache.arc_center_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.arc_radius_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.arc_start_angle_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.arc_sweep_angle_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.arc_thickness_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
+            Self::delete_buffer_cache(&self.gl, layer.buffers);
+        }
 
-                // Delete thermal cache
-                if let Some(vao) = cache.thermal_vao {
-                    self.gl.delete_vertex_array(Some(&vao));
-                }
-                if let Some(buf) = cache.thermal_center_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.thermal_outer_diameter_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.thermal_inner_diameter_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.thermal_gap_thickness_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.thermal_rotation_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
+        // Drop overlays computed from the removed layer
+        if self
+            .heatmap
+            .as_ref()
+            .is_some_and(|h| h.layer_id == layer_id)
+        {
+            self.clear_heatmap();
+        }
+        if self
+            .compare
+            .as_ref()
+            .is_some_and(|c| c.layer_a == layer_id || c.layer_b == layer_id)
+        {
+            self.clear_compare();
+        }
+        if let Some(xray) = &mut self.xray {
+            xray.layer_ids.retain(|&id| id != layer_id);
+            if xray.layer_ids.is_empty() {
+                self.clear_xray();
             }
         }
+        if self.colors_by_function(layer_id) {
+            self.clear_function_colors();
+        }
+        if self
+            .net
+            .as_ref()
+            .is_some_and(|n| n.layer_ids.contains(&layer_id))
+        {
+            self.clear_net();
+        }
+        if self
+            .vias
+            .as_ref()
+            .is_some_and(|v| v.layer_ids.contains(&layer_id))
+        {
+            self.clear_via_stitching();
+        }
+        for side in [ComponentSide::Top, ComponentSide::Bottom] {
+            if self.courtyards[side as usize]
+                .as_ref()
+                .is_some_and(|c| c.layer_ids.contains(&layer_id))
+            {
+                self.clear_courtyards(side);
+            }
+        }
+        if self
+            .dimensions
+            .as_ref()
+            .is_some_and(|d| d.layer_id == layer_id)
+        {
+            self.clear_dimensions();
+        }
+        if self
+            .highlight
+            .as_ref()
+            .is_some_and(|h| h.layer_id == layer_id)
+        {
+            self.clear_highlight();
+        }
 
-        self.layer_count -= 1;
         Ok(())
     }
 
+    /// Replace a layer's buffer cache after its geometry changed
+    ///
+    /// Buffers are rebuilt from the layer store on the next render.
+    pub fn reset_layer_buffers(&mut self, layer_id: usize) -> Result<(), JsValue> {
+        let layer = self
+            .layers
+            .get_mut(layer_id)
+            .and_then(Option::as_mut)
+            .ok_or_else(|| {
+                ViewerError::invalid_layer(
+                    format!("Invalid layer_id: {}", layer_id),
+                    layer_id as u32,
+                )
+            })?;
+        let cache = std::mem::take(&mut layer.buffers);
+        Self::delete_buffer_cache(&self.gl, cache);
+        Ok(())
+    }
+
+    /// Delete the GPU buffers and VAOs of a buffer cache
+    fn delete_buffer_cache(gl: &WebGl2RenderingContext, cache: BufferCache) {
+        // Delete triangle cache
+        if let Some(vao) = cache.triangle_vao {
+            gl.delete_vertex_array(Some(&vao));
+        }
+        if let Some(buf) = cache.triangle_vertex_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.triangle_index_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.triangle_hole_center_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.triangle_hole_radius_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.triangle_function_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.triangle_aperture_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.triangle_hidden_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.triangle_sublayer_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.triangle_corner_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+
+        // Delete circle cache
+        if let Some(vao) = cache.circle_vao {
+            gl.delete_vertex_array(Some(&vao));
+        }
+        if let Some(buf) = cache.circle_center_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.circle_radius_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.circle_hole_center_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.circle_hole_radius_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.circle_function_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.circle_aperture_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.circle_hidden_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.circle_sublayer_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+
+        // Delete arc cache
+        if let Some(vao) = cache.arc_vao {
+            gl.delete_vertex_array(Some(&vao));
+        }
+        if let Some(buf) = cache.arc_center_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.arc_radius_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.arc_start_angle_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.arc_sweep_angle_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.arc_thickness_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.arc_function_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.arc_aperture_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.arc_hidden_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.arc_sublayer_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+
+        // Delete thermal cache
+        if let Some(vao) = cache.thermal_vao {
+            gl.delete_vertex_array(Some(&vao));
+        }
+        if let Some(buf) = cache.thermal_center_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.thermal_outer_diameter_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.thermal_inner_diameter_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.thermal_gap_thickness_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.thermal_rotation_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.thermal_function_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.thermal_aperture_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.thermal_hidden_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.thermal_sublayer_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+
+        // Delete obround cache
+        if let Some(vao) = cache.obround_vao {
+            gl.delete_vertex_array(Some(&vao));
+        }
+        if let Some(buf) = cache.obround_center_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.obround_size_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.obround_rotation_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.obround_hole_center_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.obround_hole_radius_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.obround_function_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.obround_aperture_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.obround_hidden_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+        if let Some(buf) = cache.obround_sublayer_buffer {
+            gl.delete_buffer(Some(&buf));
+        }
+    }
+
     /// Clear all layers and clean up WebGL resources
     pub fn clear_all(&mut self) {
         // Delete all cached resources for each layer
         for layer in self.layers.drain(..).flatten() {
-            // Delete framebuffer and texture
-            self.gl.delete_framebuffer(Some(&layer.fbo.framebuffer));
-            self.gl.delete_texture(Some(&layer.fbo.texture));
+            Self::delete_fbo(&self.gl, layer.fbo);
 
             // Delete all cached buffers and VAOs
-            for cache in layer.buffer_caches {
-                // Delete triangle cache
-                if let Some(vao) = cache.triangle_vao {
-                    self.gl.delete_vertex_array(Some(&vao));
-                }
-                if let Some(buf) = cache.triangle_vertex_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.triangle_index_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-
-                // Delete circle cache
-                if let Some(vao) = cache.circle_vao {
-                    self.gl.delete_vertex_array(Some(&vao));
-                }
-                if let Some(buf) = cache.circle_center_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.circle_radius_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-
-                // Delete arc cache
-                if let Some(vao) = cache.arc_vao {
-                    self.gl.delete_vertex_array(Some(&vao));
-                }
-                if let Some(buf) = cache.arc_center_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.arc_radius_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.arc_start_angle_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.arc_sweep_angle_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.arc_thickness_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
+            Self::delete_buffer_cache(&self.gl, layer.buffers);
+        }
+        self.clear_heatmap();
+        self.clear_markers();
+        self.clear_fiducials();
+        self.clear_test_points();
+        self.clear_inferred_drills();
+        self.clear_cross_section();
+        self.clear_net();
+        self.clear_via_stitching();
+        self.clear_courtyards(ComponentSide::Top);
+        self.clear_courtyards(ComponentSide::Bottom);
+        self.clear_dimensions();
+        self.clear_minimap();
+        self.clear_3d_view();
+        self.clear_compare();
+        self.clear_xray();
+        self.clear_function_colors();
+        self.clear_highlight();
+    }
 
-                // Delete thermal cache
-                if let Some(vao) = cache.thermal_vao {
-                    self.gl.delete_vertex_array(Some(&vao));
-                }
-                if let Some(buf) = cache.thermal_center_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.thermal_outer_diameter_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.thermal_inner_diameter_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.thermal_gap_thickness_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-                if let Some(buf) = cache.thermal_rotation_buffer {
-                    self.gl.delete_buffer(Some(&buf));
-                }
-            }
+    /// Delete every WebGL object owned by the renderer
+    ///
+    /// Besides the layers and overlays cleared by clear_all(), this frees the
+    /// shader programs and the shared quad buffer.
+    pub fn dispose(mut self) {
+        self.clear_all();
+        if let Some(frame) = self.frame.take() {
+            Self::delete_fbo(&self.gl, frame);
         }
-        self.layer_count = 0;
+        self.programs.delete(&self.gl);
+        self.gl.delete_buffer(Some(&self.quad_buffer));
     }
 
     fn create_fbo(gl: &WebGl2RenderingContext, width: u32, height: u32) -> Result<Fbo, JsValue> {
@@ -331,9 +495,152 @@ impl Renderer {
         Ok(Fbo {
             framebuffer,
             texture,
+            multisample: None,
+            depth: None,
         })
     }
 
+    /// Create an FBO with a depth buffer for drawing layer geometry
+    fn create_geometry_fbo(
+        gl: &WebGl2RenderingContext,
+        width: u32,
+        height: u32,
+    ) -> Result<Fbo, JsValue> {
+        let mut fbo = Self::create_fbo(gl, width, height)?;
+        fbo.depth = Some(Self::attach_depth_buffer(
+            gl,
+            &fbo.framebuffer,
+            width,
+            height,
+            0,
+        )?);
+        Ok(fbo)
+    }
+
+    /// Create a depth renderbuffer (multisampled unless `samples` is 0) and
+    /// attach it to `framebuffer`
+    fn attach_depth_buffer(
+        gl: &WebGl2RenderingContext,
+        framebuffer: &WebGlFramebuffer,
+        width: u32,
+        height: u32,
+        samples: i32,
+    ) -> Result<WebGlRenderbuffer, JsValue> {
+        let renderbuffer = gl
+            .create_renderbuffer()
+            .ok_or("Failed to create renderbuffer")?;
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, Some(&renderbuffer));
+        gl.renderbuffer_storage_multisample(
+            WebGl2RenderingContext::RENDERBUFFER,
+            samples,
+            WebGl2RenderingContext::DEPTH_COMPONENT24,
+            width as i32,
+            height as i32,
+        );
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(framebuffer));
+        gl.framebuffer_renderbuffer(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            WebGl2RenderingContext::DEPTH_ATTACHMENT,
+            WebGl2RenderingContext::RENDERBUFFER,
+            Some(&renderbuffer),
+        );
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, None);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        Ok(renderbuffer)
+    }
+
+    /// Create a layer FBO, multisampled when antialiasing is on
+    fn create_layer_fbo(&self, width: u32, height: u32) -> Result<Fbo, JsValue> {
+        if self.msaa_samples == 0 {
+            return Self::create_geometry_fbo(&self.gl, width, height);
+        }
+        let mut fbo = Self::create_fbo(&self.gl, width, height)?;
+
+        let gl = &self.gl;
+        let renderbuffer = gl
+            .create_renderbuffer()
+            .ok_or("Failed to create renderbuffer")?;
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, Some(&renderbuffer));
+        gl.renderbuffer_storage_multisample(
+            WebGl2RenderingContext::RENDERBUFFER,
+            self.msaa_samples,
+            WebGl2RenderingContext::RGBA8,
+            width as i32,
+            height as i32,
+        );
+        let framebuffer = gl.create_framebuffer().ok_or("Failed to create FBO")?;
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&framebuffer));
+        gl.framebuffer_renderbuffer(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            WebGl2RenderingContext::COLOR_ATTACHMENT0,
+            WebGl2RenderingContext::RENDERBUFFER,
+            Some(&renderbuffer),
+        );
+        gl.bind_renderbuffer(WebGl2RenderingContext::RENDERBUFFER, None);
+        gl.bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        let depth = Self::attach_depth_buffer(gl, &framebuffer, width, height, self.msaa_samples)?;
+
+        fbo.multisample = Some(MultisampleTarget {
+            framebuffer,
+            renderbuffer,
+            depth,
+        });
+        Ok(fbo)
+    }
+
+    /// Delete the framebuffers, texture and renderbuffers of an FBO
+    fn delete_fbo(gl: &WebGl2RenderingContext, fbo: Fbo) {
+        gl.delete_framebuffer(Some(&fbo.framebuffer));
+        gl.delete_texture(Some(&fbo.texture));
+        if let Some(multisample) = fbo.multisample {
+            gl.delete_framebuffer(Some(&multisample.framebuffer));
+            gl.delete_renderbuffer(Some(&multisample.renderbuffer));
+            gl.delete_renderbuffer(Some(&multisample.depth));
+        }
+        if let Some(depth) = fbo.depth {
+            gl.delete_renderbuffer(Some(&depth));
+        }
+    }
+
+    /// Apply quality settings, recreating layer FBOs when antialiasing changed
+    pub fn set_options(&mut self, options: &RenderOptions) -> Result<(), JsValue> {
+        let max_samples = self
+            .gl
+            .get_parameter(WebGl2RenderingContext::MAX_SAMPLES)?
+            .as_f64()
+            .unwrap_or(0.0) as u32;
+        let samples = options.msaa_samples.min(max_samples) as i32;
+        self.min_feature_pixels = options.min_feature_pixels;
+        // Triangles carry their corners only while thin features are widened
+        let widen = options.min_stroke_pixels > 0.0;
+        let widened = self.min_stroke_pixels > 0.0;
+        self.min_stroke_pixels = options.min_stroke_pixels;
+        if widen != widened {
+            for layer_id in 0..self.layers.len() {
+                if self.layers[layer_id].is_some() {
+                    self.reset_layer_buffers(layer_id)?;
+                }
+            }
+        }
+        if options.double_buffer != self.frame.is_some() {
+            self.frame = match self.frame.take() {
+                Some(frame) => {
+                    Self::delete_fbo(&self.gl, frame);
+                    None
+                }
+                None => {
+                    let (width, height) = self.get_canvas_size()?;
+                    Some(Self::create_fbo(&self.gl, width, height)?)
+                }
+            };
+        }
+        if samples != self.msaa_samples {
+            self.msaa_samples = samples;
+            self.resize()?;
+        }
+        Ok(())
+    }
+
     /// Create and bind a single-channel instance buffer
     fn create_instance_buffer(
         gl: &WebGl2RenderingContext,
@@ -344,20 +651,44 @@ impl Renderer {
     ) -> Result<WebGlBuffer, JsValue> {
         let buffer = gl
             .create_buffer()
-            .ok_or_else(|| JsValue::from_str("Failed to create buffer"))?;
+            .ok_or_else(|| ViewerError::webgl("Failed to create buffer"))?;
         gl.bind_buffer(ARRAY_BUFFER, Some(&buffer));
         unsafe {
             let array = Float32Array::view(data);
             gl.buffer_data_with_array_buffer_view(ARRAY_BUFFER, &array, STATIC_DRAW);
         }
-        let loc = program.attributes.get(attr_name)
-            .ok_or_else(|| JsValue::from_str(&format!("Missing shader attribute: {}", attr_name)))?;
+        let loc = program.attributes.get(attr_name).ok_or_else(|| {
+            ViewerError::webgl(format!("Missing shader attribute: {}", attr_name))
+        })?;
         gl.enable_vertex_attrib_array(*loc);
         gl.vertex_attrib_pointer_with_i32(*loc, 1, FLOAT, false, 0, 0);
         gl.vertex_attrib_divisor(*loc, divisor);
         Ok(buffer)
     }
 
+    /// Create and bind a four-channel per-vertex buffer
+    fn create_vertex_buffer_4d(
+        gl: &WebGl2RenderingContext,
+        data: &[f32],
+        program: &ShaderProgram,
+        attr_name: &str,
+    ) -> Result<WebGlBuffer, JsValue> {
+        let buffer = gl
+            .create_buffer()
+            .ok_or_else(|| ViewerError::webgl("Failed to create buffer"))?;
+        gl.bind_buffer(ARRAY_BUFFER, Some(&buffer));
+        unsafe {
+            let array = Float32Array::view(data);
+            gl.buffer_data_with_array_buffer_view(ARRAY_BUFFER, &array, STATIC_DRAW);
+        }
+        let loc = program.attributes.get(attr_name).ok_or_else(|| {
+            ViewerError::webgl(format!("Missing shader attribute: {}", attr_name))
+        })?;
+        gl.enable_vertex_attrib_array(*loc);
+        gl.vertex_attrib_pointer_with_i32(*loc, 4, FLOAT, false, 0, 0);
+        Ok(buffer)
+    }
+
     /// Create and bind a dual-channel (2D) instance buffer
     fn create_instance_buffer_2d(
         gl: &WebGl2RenderingContext,
@@ -368,28 +699,110 @@ impl Renderer {
     ) -> Result<WebGlBuffer, JsValue> {
         let buffer = gl
             .create_buffer()
-            .ok_or_else(|| JsValue::from_str("Failed to create buffer"))?;
+            .ok_or_else(|| ViewerError::webgl("Failed to create buffer"))?;
         gl.bind_buffer(ARRAY_BUFFER, Some(&buffer));
         unsafe {
             let array = Float32Array::view(data);
             gl.buffer_data_with_array_buffer_view(ARRAY_BUFFER, &array, STATIC_DRAW);
         }
-        let loc = program.attributes.get(attr_name)
-            .ok_or_else(|| JsValue::from_str(&format!("Missing shader attribute: {}", attr_name)))?;
+        let loc = program.attributes.get(attr_name).ok_or_else(|| {
+            ViewerError::webgl(format!("Missing shader attribute: {}", attr_name))
+        })?;
         gl.enable_vertex_attrib_array(*loc);
         gl.vertex_attrib_pointer_with_i32(*loc, 2, FLOAT, false, 0, 0);
         gl.vertex_attrib_divisor(*loc, divisor);
         Ok(buffer)
     }
 
-    /// Interleave x,y arrays into a single flat array
-    fn interleave_xy(x: &[f32], y: &[f32]) -> Vec<f32> {
-        let mut result = Vec::with_capacity(x.len() * 2);
-        for i in 0..x.len() {
-            result.push(x[i]);
-            result.push(y[i]);
+    /// Create and bind an instance buffer of 16-bit normalized values
+    ///
+    /// The shader reads them as 0.0 to 1.0; see QuantizedCircles.
+    fn create_quantized_buffer(
+        gl: &WebGl2RenderingContext,
+        data: &[u16],
+        components: i32,
+        program: &ShaderProgram,
+        attr_name: &str,
+    ) -> Result<WebGlBuffer, JsValue> {
+        let buffer = gl
+            .create_buffer()
+            .ok_or_else(|| ViewerError::webgl("Failed to create buffer"))?;
+        gl.bind_buffer(ARRAY_BUFFER, Some(&buffer));
+        unsafe {
+            let array = js_sys::Uint16Array::view(data);
+            gl.buffer_data_with_array_buffer_view(ARRAY_BUFFER, &array, STATIC_DRAW);
+        }
+        let loc = program.attributes.get(attr_name).ok_or_else(|| {
+            ViewerError::webgl(format!("Missing shader attribute: {}", attr_name))
+        })?;
+        gl.enable_vertex_attrib_array(*loc);
+        gl.vertex_attrib_pointer_with_i32(*loc, components, UNSIGNED_SHORT, true, 0, 0);
+        gl.vertex_attrib_divisor(*loc, 1);
+        Ok(buffer)
+    }
+
+    /// Create and bind an aperture function buffer (one byte per element)
+    ///
+    /// Returns None for untagged geometry; the disabled attribute then reads
+    /// as 0 (ApertureFunction::None).
+    fn create_function_buffer(
+        gl: &WebGl2RenderingContext,
+        functions: &[u8],
+        count: usize,
+        program: &ShaderProgram,
+        divisor: u32,
+    ) -> Result<Option<WebGlBuffer>, JsValue> {
+        if functions.len() != count {
+            return Ok(None);
+        }
+        let buffer = gl
+            .create_buffer()
+            .ok_or_else(|| ViewerError::webgl("Failed to create function buffer"))?;
+        gl.bind_buffer(ARRAY_BUFFER, Some(&buffer));
+        unsafe {
+            let array = js_sys::Uint8Array::view(functions);
+            gl.buffer_data_with_array_buffer_view(ARRAY_BUFFER, &array, STATIC_DRAW);
+        }
+        let loc = program
+            .attributes
+            .get("function_instance")
+            .ok_or_else(|| ViewerError::webgl("Missing shader attribute: function_instance"))?;
+        gl.enable_vertex_attrib_array(*loc);
+        gl.vertex_attrib_pointer_with_i32(*loc, 1, UNSIGNED_BYTE, false, 0, 0);
+        gl.vertex_attrib_divisor(*loc, divisor);
+        Ok(Some(buffer))
+    }
+
+    /// Create and bind an aperture D-code buffer (one u16 per element)
+    ///
+    /// Returns None for geometry without D-codes; the disabled attribute then
+    /// reads as 0, like a region.
+    fn create_aperture_buffer(
+        gl: &WebGl2RenderingContext,
+        d_codes: &[u16],
+        count: usize,
+        program: &ShaderProgram,
+        divisor: u32,
+    ) -> Result<Option<WebGlBuffer>, JsValue> {
+        if d_codes.len() != count {
+            return Ok(None);
         }
-        result
+        let buffer = gl
+            .create_buffer()
+            .ok_or_else(|| ViewerError::webgl("Failed to create aperture buffer"))?;
+        gl.bind_buffer(ARRAY_BUFFER, Some(&buffer));
+        unsafe {
+            let array = js_sys::Uint16Array::view(d_codes);
+            gl.buffer_data_with_array_buffer_view(ARRAY_BUFFER, &array, STATIC_DRAW);
+        }
+        let loc = program
+            .attributes
+            .get("aperture_instance")
+            .ok_or_else(|| ViewerError::webgl("Missing shader attribute: aperture_instance"))?;
+        gl.enable_vertex_attrib_array(*loc);
+        gl.vertex_attrib_pointer_with_i32(*loc, 1, UNSIGNED_SHORT, false, 0, 0);
+        gl.vertex_attrib_divisor(*loc, divisor);
+        Ok(Some(buffer))
     }
 
     /// Create quad buffer for instanced rendering
@@ -400,7 +813,7 @@ impl Renderer {
 
         let buffer = gl
             .create_buffer()
-            .ok_or_else(|| JsValue::from_str("Failed to create quad buffer"))?;
+            .ok_or_else(|| ViewerError::webgl("Failed to create quad buffer"))?;
 
         gl.bind_buffer(ARRAY_BUFFER, Some(&buffer));
 
@@ -413,26 +826,35 @@ impl Renderer {
     }
 
     fn get_canvas_size_from_gl(gl: &WebGl2RenderingContext) -> Result<(u32, u32), JsValue> {
-        let canvas = gl
-            .canvas()
-            .ok_or_else(|| JsValue::from_str("No canvas"))?
-            .dyn_into::<web_sys::HtmlCanvasElement>()?;
-        Ok((canvas.width(), canvas.height()))
+        let canvas = gl.canvas().ok_or_else(|| ViewerError::webgl("No canvas"))?;
+        canvas_size(&canvas)
     }
 
     /// Get canvas dimensions
-    fn get_canvas_size(&self) -> Result<(u32, u32), JsValue> {
+    pub fn get_canvas_size(&self) -> Result<(u32, u32), JsValue> {
         Self::get_canvas_size_from_gl(&self.gl)
     }
 
+    /// Camera zoom showing one board millimeter as `pixels_per_mm` canvas pixels
+    pub fn zoom_for_pixel_scale(&self, pixels_per_mm: f32) -> Result<f32, JsValue> {
+        let (width, height) = self.get_canvas_size()?;
+        Ok(Camera::zoom_for_pixel_scale(pixels_per_mm, width, height))
+    }
+
+    /// Camera view fitting `bounds` into the canvas
+    pub fn view_for_bounds(&self, bounds: &Boundary) -> Result<CameraView, JsValue> {
+        let (width, height) = self.get_canvas_size()?;
+        Ok(CameraView::fit(bounds, width, height))
+    }
+
     /// Get layer reference with error handling
     fn get_layer(&self, layer_id: usize) -> Result<&LayerMetadata, JsValue> {
         if layer_id >= self.layers.len() {
-            return Err(JsValue::from_str("Invalid layer index"));
+            return Err(ViewerError::invalid_layer("Invalid layer index", layer_id as u32).into());
         }
         self.layers[layer_id]
             .as_ref()
-            .ok_or_else(|| JsValue::from_str("Layer deallocated"))
+            .ok_or_else(|| ViewerError::invalid_layer("Layer deallocated", layer_id as u32).into())
     }
 
     /// Update camera state
@@ -443,7 +865,15 @@ impl Renderer {
     }
 
     /// Draw a specific FBO texture to the current framebuffer
-    fn draw_fbo_texture(&self, texture: &WebGlTexture, color: &[f32; 4]) -> Result<(), JsValue> {
+    ///
+    /// `by_function` takes the color from the texture (aperture function
+    /// colors) instead of `color`; the alpha still comes from `color`.
+    fn draw_fbo_texture(
+        &self,
+        texture: &WebGlTexture,
+        color: &[f32; 4],
+        by_function: bool,
+    ) -> Result<(), JsValue> {
         let program = &self.programs.texture;
         self.gl.use_program(Some(&program.program));
 
@@ -460,6 +890,8 @@ impl Renderer {
         self.gl.uniform1i(program.uniforms.get("u_texture"), 0);
         self.gl
             .uniform4fv_with_f32_array(program.uniforms.get("u_color"), color);
+        self.gl
+            .uniform1i(program.uniforms.get("u_by_function"), by_function as i32);
 
         self.gl.draw_arrays(TRIANGLES, 0, 6);
 
@@ -471,53 +903,50 @@ impl Renderer {
         &mut self,
         transform: &[f32; 9],
         color: &[f32; 4],
+        collapsed: Option<&CollapsedLayer>,
         layer_id: usize,
-        sublayer_idx: usize,
     ) -> Result<(), JsValue> {
         // Validate layer exists
         if layer_id >= self.layers.len() {
-            return Err(JsValue::from_str("Invalid layer index"));
+            return Err(ViewerError::invalid_layer("Invalid layer index", layer_id as u32).into());
         }
 
-        // Check if data is empty (short-lived borrow)
-        {
-            let layer = if let Some(l) = &self.layers[layer_id] {
-                l
-            } else {
-                return Err(JsValue::from_str("Layer deallocated"));
-            };
-            if layer.gerber_data[sublayer_idx].triangles.indices.is_empty() {
-                return Ok(());
-            }
+        let index_count = self.get_layer(layer_id)?.buffers.triangle_index_count;
+        if index_count == 0 {
+            return Ok(());
         }
 
         let program = &self.programs.triangle;
         self.gl.use_program(Some(&program.program));
 
         // Buffer creation/update phase (scoped to end borrow early)
-        let index_count = {
+        {
             let layer = if let Some(l) = &mut self.layers[layer_id] {
                 l
             } else {
-                return Err(JsValue::from_str("Layer deallocated"));
+                return Err(
+                    ViewerError::invalid_layer("Layer deallocated", layer_id as u32).into(),
+                );
             };
-            let triangles = &layer.gerber_data[sublayer_idx].triangles;
-            let buffer_cache = &mut layer.buffer_caches[sublayer_idx];
+            let buffer_cache = &mut layer.buffers;
 
-            // Check if VAO is cached for this sublayer
-            if buffer_cache.triangle_vao.is_none() {
+            // Upload the sublayers unless their VAO is cached
+            if let Some(CollapsedLayer { data, sublayers }) =
+                collapsed.filter(|_| buffer_cache.triangle_vao.is_none())
+            {
+                let triangles = &data.triangles;
                 // Create VAO
                 let vao = self
                     .gl
                     .create_vertex_array()
-                    .ok_or_else(|| JsValue::from_str("Failed to create VAO"))?;
+                    .ok_or_else(|| ViewerError::webgl("Failed to create VAO"))?;
                 self.gl.bind_vertex_array(Some(&vao));
 
                 // Create and bind vertex buffer
                 let vertex_buffer = self
                     .gl
                     .create_buffer()
-                    .ok_or_else(|| JsValue::from_str("Failed to create vertex buffer"))?;
+                    .ok_or_else(|| ViewerError::webgl("Failed to create vertex buffer"))?;
                 self.gl.bind_buffer(ARRAY_BUFFER, Some(&vertex_buffer));
                 unsafe {
                     let array = Float32Array::view(&triangles.vertices);
@@ -529,7 +958,7 @@ impl Renderer {
                 let index_buffer = self
                     .gl
                     .create_buffer()
-                    .ok_or_else(|| JsValue::from_str("Failed to create index buffer"))?;
+                    .ok_or_else(|| ViewerError::webgl("Failed to create index buffer"))?;
                 self.gl
                     .bind_buffer(ELEMENT_ARRAY_BUFFER, Some(&index_buffer));
                 unsafe {
@@ -548,11 +977,11 @@ impl Renderer {
                     .vertex_attrib_pointer_with_i32(position_loc, 2, FLOAT, false, 0, 0);
 
                 // Create regular attribute buffers for hole data (per-vertex)
-                let hole_centers = Self::interleave_xy(&triangles.hole_x, &triangles.hole_y);
+                let hole_centers = interleave_xy(&triangles.hole_x, &triangles.hole_y);
                 let hole_center_buffer = self
                     .gl
                     .create_buffer()
-                    .ok_or_else(|| JsValue::from_str("Failed to create hole center buffer"))?;
+                    .ok_or_else(|| ViewerError::webgl("Failed to create hole center buffer"))?;
                 self.gl.bind_buffer(ARRAY_BUFFER, Some(&hole_center_buffer));
                 unsafe {
                     let array = Float32Array::view(&hole_centers);
@@ -567,7 +996,7 @@ impl Renderer {
                 let hole_radius_buffer = self
                     .gl
                     .create_buffer()
-                    .ok_or_else(|| JsValue::from_str("Failed to create hole radius buffer"))?;
+                    .ok_or_else(|| ViewerError::webgl("Failed to create hole radius buffer"))?;
                 self.gl.bind_buffer(ARRAY_BUFFER, Some(&hole_radius_buffer));
                 unsafe {
                     let array = Float32Array::view(&triangles.hole_radius);
@@ -579,25 +1008,77 @@ impl Renderer {
                 self.gl
                     .vertex_attrib_pointer_with_i32(hole_radius_loc, 1, FLOAT, false, 0, 0);
 
+                // Aperture function and D-code per vertex
+                let vertex_count = triangles.vertices.len() / 2;
+                let function_buffer = Self::create_function_buffer(
+                    &self.gl,
+                    &data.functions.triangles,
+                    vertex_count,
+                    program,
+                    0,
+                )?;
+                let aperture_buffer = Self::create_aperture_buffer(
+                    &self.gl,
+                    &data.apertures.triangles,
+                    vertex_count,
+                    program,
+                    0,
+                )?;
+                let hidden_buffer = Self::create_hidden_buffer(
+                    &self.gl,
+                    layer.filter.map(|filter| {
+                        filter.hidden(
+                            data,
+                            &data.kinds.triangles,
+                            &data.apertures.triangles,
+                            &data.functions.triangles,
+                            vertex_count,
+                        )
+                    }),
+                    program,
+                    0,
+                )?;
+                let sublayer_buffer = Self::create_sublayer_buffer(
+                    &self.gl,
+                    &sublayers.triangles,
+                    vertex_count,
+                    program,
+                    0,
+                )?;
+                let corner_buffer = if self.min_stroke_pixels > 0.0 {
+                    let corners = triangle_corners(triangles);
+                    Some(Self::create_vertex_buffer_4d(
+                        &self.gl,
+                        &corners,
+                        program,
+                        "corners_instance",
+                    )?)
+                } else {
+                    None
+                };
+
                 // Unbind VAO
                 self.gl.bind_vertex_array(None);
 
-                // Cache VAO and buffers for this sublayer
+                // Cache VAO and buffers
                 buffer_cache.triangle_vao = Some(vao);
                 buffer_cache.triangle_vertex_buffer = Some(vertex_buffer);
                 buffer_cache.triangle_index_buffer = Some(index_buffer);
                 buffer_cache.triangle_hole_center_buffer = Some(hole_center_buffer);
                 buffer_cache.triangle_hole_radius_buffer = Some(hole_radius_buffer);
+                buffer_cache.triangle_function_buffer = function_buffer;
+                buffer_cache.triangle_aperture_buffer = aperture_buffer;
+                buffer_cache.triangle_hidden_buffer = hidden_buffer;
+                buffer_cache.triangle_sublayer_buffer = sublayer_buffer;
+                buffer_cache.triangle_corner_buffer = corner_buffer;
             }
-
-            triangles.indices.len()
-        }; // Borrow ends here
+        } // Borrow ends here
 
         // Rendering phase (new borrow)
         let layer = self.get_layer(layer_id)?;
-        let buffer_cache = &layer.buffer_caches[sublayer_idx];
+        let buffer_cache = &layer.buffers;
 
-        // Bind cached VAO for this sublayer
+        // Bind cached VAO
         self.gl
             .bind_vertex_array(buffer_cache.triangle_vao.as_ref());
 
@@ -609,6 +1090,8 @@ impl Renderer {
         if let Some(loc) = program.uniforms.get("color") {
             self.gl.uniform4fv_with_f32_array(Some(loc), color);
         }
+        self.set_function_uniforms(program, layer_id);
+        self.set_highlight_uniform(program, layer_id);
 
         // Draw
         self.gl
@@ -625,14 +1108,16 @@ impl Renderer {
         &mut self,
         transform: &[f32; 9],
         color: &[f32; 4],
+        min_size: f32,
+        collapsed: Option<&CollapsedLayer>,
         layer_id: usize,
-        sublayer_idx: usize,
     ) -> Result<(), JsValue> {
-        // Check if data is empty (short-lived borrow)
-        let instance_count = {
-            let layer = self.get_layer(layer_id)?;
-            layer.gerber_data[sublayer_idx].circles.x.len()
-        };
+        // Get mutable reference to buffer cache
+        let layer = self.layers[layer_id]
+            .as_mut()
+            .ok_or_else(|| ViewerError::invalid_layer("Layer not found", layer_id as u32))?;
+        let buffer_cache = &mut layer.buffers;
+        let instance_count = buffer_cache.circle_count;
         if instance_count == 0 {
             return Ok(());
         }
@@ -640,21 +1125,16 @@ impl Renderer {
         let program = &self.programs.circle;
         self.gl.use_program(Some(&program.program));
 
-        // Get mutable reference to buffer cache and immutable reference to data
-        // Split borrowing: gerber_data and buffer_caches are different fields
-        let layer = self.layers[layer_id]
-            .as_mut()
-            .ok_or_else(|| JsValue::from_str("Layer not found"))?;
-        let circles = &layer.gerber_data[sublayer_idx].circles;
-        let buffer_cache = &mut layer.buffer_caches[sublayer_idx];
-
-        // Check if VAO is cached for this sublayer
-        if buffer_cache.circle_vao.is_none() {
+        // Upload the sublayers unless their VAO is cached
+        if let Some(CollapsedLayer { data, sublayers }) =
+            collapsed.filter(|_| buffer_cache.circle_vao.is_none())
+        {
+            let circles = &data.circles;
             // Create VAO
             let vao = self
                 .gl
                 .create_vertex_array()
-                .ok_or_else(|| JsValue::from_str("Failed to create VAO"))?;
+                .ok_or_else(|| ViewerError::webgl("Failed to create VAO"))?;
             self.gl.bind_vertex_array(Some(&vao));
 
             // Bind shared quad buffer for position attribute
@@ -664,50 +1144,143 @@ impl Renderer {
             self.gl
                 .vertex_attrib_pointer_with_i32(position_loc, 2, FLOAT, false, 0, 0);
 
-            // Create instance buffers
-            let centers = Self::interleave_xy(&circles.x, &circles.y);
-            let center_buffer =
-                Self::create_instance_buffer_2d(&self.gl, &centers, program, "center_instance", 1)?;
-            let radius_buffer = Self::create_instance_buffer(
-                &self.gl,
+            // Create instance buffers, 16-bit when the layer's range allows it
+            let quantized = QuantizedCircles::new(
+                &circles.x,
+                &circles.y,
                 &circles.radius,
+                &circles.hole_x,
+                &circles.hole_y,
+                &circles.hole_radius,
+            );
+            let (center_buffer, radius_buffer, hole_center_buffer, hole_radius_buffer) =
+                match &quantized {
+                    Some(q) => (
+                        Self::create_quantized_buffer(
+                            &self.gl,
+                            &q.centers,
+                            2,
+                            program,
+                            "center_instance",
+                        )?,
+                        Self::create_quantized_buffer(
+                            &self.gl,
+                            &q.radii,
+                            1,
+                            program,
+                            "radius_instance",
+                        )?,
+                        Self::create_quantized_buffer(
+                            &self.gl,
+                            &q.hole_centers,
+                            2,
+                            program,
+                            "hole_center_instance",
+                        )?,
+                        Self::create_quantized_buffer(
+                            &self.gl,
+                            &q.hole_radii,
+                            1,
+                            program,
+                            "hole_radius_instance",
+                        )?,
+                    ),
+                    None => {
+                        let centers = interleave_xy(&circles.x, &circles.y);
+                        let hole_centers = interleave_xy(&circles.hole_x, &circles.hole_y);
+                        (
+                            Self::create_instance_buffer_2d(
+                                &self.gl,
+                                &centers,
+                                program,
+                                "center_instance",
+                                1,
+                            )?,
+                            Self::create_instance_buffer(
+                                &self.gl,
+                                &circles.radius,
+                                program,
+                                "radius_instance",
+                                1,
+                            )?,
+                            Self::create_instance_buffer_2d(
+                                &self.gl,
+                                &hole_centers,
+                                program,
+                                "hole_center_instance",
+                                1,
+                            )?,
+                            Self::create_instance_buffer(
+                                &self.gl,
+                                &circles.hole_radius,
+                                program,
+                                "hole_radius_instance",
+                                1,
+                            )?,
+                        )
+                    }
+                };
+            let function_buffer = Self::create_function_buffer(
+                &self.gl,
+                &data.functions.circles,
+                instance_count,
                 program,
-                "radius_instance",
                 1,
             )?;
-            let hole_centers = Self::interleave_xy(&circles.hole_x, &circles.hole_y);
-            let hole_center_buffer = Self::create_instance_buffer_2d(
+            let aperture_buffer = Self::create_aperture_buffer(
                 &self.gl,
-                &hole_centers,
+                &data.apertures.circles,
+                instance_count,
                 program,
-                "hole_center_instance",
                 1,
             )?;
-            let hole_radius_buffer = Self::create_instance_buffer(
+            let hidden_buffer = Self::create_hidden_buffer(
                 &self.gl,
-                &circles.hole_radius,
+                layer.filter.map(|filter| {
+                    filter.hidden(
+                        data,
+                        &data.kinds.circles,
+                        &data.apertures.circles,
+                        &data.functions.circles,
+                        instance_count,
+                    )
+                }),
+                program,
+                1,
+            )?;
+            let sublayer_buffer = Self::create_sublayer_buffer(
+                &self.gl,
+                &sublayers.circles,
+                instance_count,
                 program,
-                "hole_radius_instance",
                 1,
             )?;
 
             // Unbind VAO
             self.gl.bind_vertex_array(None);
 
-            // Cache VAO and buffers for this sublayer
+            // Cache VAO and buffers
             buffer_cache.circle_vao = Some(vao);
             buffer_cache.circle_center_buffer = Some(center_buffer);
             buffer_cache.circle_radius_buffer = Some(radius_buffer);
             buffer_cache.circle_hole_center_buffer = Some(hole_center_buffer);
             buffer_cache.circle_hole_radius_buffer = Some(hole_radius_buffer);
+            buffer_cache.circle_function_buffer = function_buffer;
+            buffer_cache.circle_aperture_buffer = aperture_buffer;
+            buffer_cache.circle_hidden_buffer = hidden_buffer;
+            buffer_cache.circle_sublayer_buffer = sublayer_buffer;
+            buffer_cache.circle_ranges = quantized.map(|q| (q.center_range(), q.radius_range()));
         }
 
         // Re-get immutable reference for rendering
         let layer = self.get_layer(layer_id)?;
-        let buffer_cache = &layer.buffer_caches[sublayer_idx];
+        let buffer_cache = &layer.buffers;
 
-        // Bind cached VAO for this sublayer
+        // Bind cached VAO
         self.gl.bind_vertex_array(buffer_cache.circle_vao.as_ref());
+        let (center_range, radius_range) = buffer_cache
+            .circle_ranges
+            .unwrap_or(([0.0, 0.0, 1.0, 1.0], [0.0, 1.0]));
 
         // Set uniforms (only these change per frame)
         if let Some(loc) = program.uniforms.get("transform") {
@@ -717,6 +1290,17 @@ impl Renderer {
         if let Some(loc) = program.uniforms.get("color") {
             self.gl.uniform4fv_with_f32_array(Some(loc), color);
         }
+        self.set_function_uniforms(program, layer_id);
+        self.set_highlight_uniform(program, layer_id);
+        if let Some(loc) = program.uniforms.get("u_min_size") {
+            self.gl.uniform1f(Some(loc), min_size);
+        }
+        if let Some(loc) = program.uniforms.get("u_center_range") {
+            self.gl.uniform4fv_with_f32_array(Some(loc), &center_range);
+        }
+        if let Some(loc) = program.uniforms.get("u_radius_range") {
+            self.gl.uniform2fv_with_f32_array(Some(loc), &radius_range);
+        }
 
         // Draw
         self.gl
@@ -733,14 +1317,16 @@ impl Renderer {
         &mut self,
         transform: &[f32; 9],
         color: &[f32; 4],
+        min_size: f32,
+        collapsed: Option<&CollapsedLayer>,
         layer_id: usize,
-        sublayer_idx: usize,
     ) -> Result<(), JsValue> {
-        // Check if data is empty (short-lived borrow)
-        let instance_count = {
-            let layer = self.get_layer(layer_id)?;
-            layer.gerber_data[sublayer_idx].arcs.x.len()
-        };
+        // Get mutable reference to buffer cache
+        let layer = self.layers[layer_id]
+            .as_mut()
+            .ok_or_else(|| ViewerError::invalid_layer("Layer not found", layer_id as u32))?;
+        let buffer_cache = &mut layer.buffers;
+        let instance_count = buffer_cache.arc_count;
         if instance_count == 0 {
             return Ok(());
         }
@@ -748,21 +1334,16 @@ impl Renderer {
         let program = &self.programs.arc;
         self.gl.use_program(Some(&program.program));
 
-        // Get mutable reference to buffer cache and immutable reference to data
-        // Split borrowing: gerber_data and buffer_caches are different fields
-        let layer = self.layers[layer_id]
-            .as_mut()
-            .ok_or_else(|| JsValue::from_str("Layer not found"))?;
-        let arcs = &layer.gerber_data[sublayer_idx].arcs;
-        let buffer_cache = &mut layer.buffer_caches[sublayer_idx];
-
-        // Check if VAO is cached for this sublayer
-        if buffer_cache.arc_vao.is_none() {
+        // Upload the sublayers unless their VAO is cached
+        if let Some(CollapsedLayer { data, sublayers }) =
+            collapsed.filter(|_| buffer_cache.arc_vao.is_none())
+        {
+            let arcs = &data.arcs;
             // Create VAO
             let vao = self
                 .gl
                 .create_vertex_array()
-                .ok_or_else(|| JsValue::from_str("Failed to create VAO"))?;
+                .ok_or_else(|| ViewerError::webgl("Failed to create VAO"))?;
             self.gl.bind_vertex_array(Some(&vao));
 
             // Bind shared quad buffer for position attribute
@@ -773,7 +1354,7 @@ impl Renderer {
                 .vertex_attrib_pointer_with_i32(position_loc, 2, FLOAT, false, 0, 0);
 
             // Create instance buffers
-            let centers = Self::interleave_xy(&arcs.x, &arcs.y);
+            let centers = interleave_xy(&arcs.x, &arcs.y);
             let center_buffer =
                 Self::create_instance_buffer_2d(&self.gl, &centers, program, "center_instance", 1)?;
             let radius_buffer = Self::create_instance_buffer(
@@ -804,24 +1385,63 @@ impl Renderer {
                 "thickness_instance",
                 1,
             )?;
+            let function_buffer = Self::create_function_buffer(
+                &self.gl,
+                &data.functions.arcs,
+                instance_count,
+                program,
+                1,
+            )?;
+            let aperture_buffer = Self::create_aperture_buffer(
+                &self.gl,
+                &data.apertures.arcs,
+                instance_count,
+                program,
+                1,
+            )?;
+            let hidden_buffer = Self::create_hidden_buffer(
+                &self.gl,
+                layer.filter.map(|filter| {
+                    filter.hidden(
+                        data,
+                        &data.kinds.arcs,
+                        &data.apertures.arcs,
+                        &data.functions.arcs,
+                        instance_count,
+                    )
+                }),
+                program,
+                1,
+            )?;
+            let sublayer_buffer = Self::create_sublayer_buffer(
+                &self.gl,
+                &sublayers.arcs,
+                instance_count,
+                program,
+                1,
+            )?;
 
             // Unbind VAO
             self.gl.bind_vertex_array(None);
 
-            // Cache VAO and buffers for this sublayer
+            // Cache VAO and buffers
             buffer_cache.arc_vao = Some(vao);
             buffer_cache.arc_center_buffer = Some(center_buffer);
             buffer_cache.arc_radius_buffer = Some(radius_buffer);
             buffer_cache.arc_start_angle_buffer = Some(start_angle_buffer);
             buffer_cache.arc_sweep_angle_buffer = Some(sweep_angle_buffer);
             buffer_cache.arc_thickness_buffer = Some(thickness_buffer);
+            buffer_cache.arc_function_buffer = function_buffer;
+            buffer_cache.arc_aperture_buffer = aperture_buffer;
+            buffer_cache.arc_hidden_buffer = hidden_buffer;
+            buffer_cache.arc_sublayer_buffer = sublayer_buffer;
         }
 
         // Re-get immutable reference for rendering
         let layer = self.get_layer(layer_id)?;
-        let buffer_cache = &layer.buffer_caches[sublayer_idx];
+        let buffer_cache = &layer.buffers;
 
-        // Bind cached VAO for this sublayer
+        // Bind cached VAO
         self.gl.bind_vertex_array(buffer_cache.arc_vao.as_ref());
 
         // Set uniforms (only these change per frame)
@@ -832,6 +1452,11 @@ impl Renderer {
         if let Some(loc) = program.uniforms.get("color") {
             self.gl.uniform4fv_with_f32_array(Some(loc), color);
         }
+        self.set_function_uniforms(program, layer_id);
+        self.set_highlight_uniform(program, layer_id);
+        if let Some(loc) = program.uniforms.get("u_min_size") {
+            self.gl.uniform1f(Some(loc), min_size);
+        }
 
         // Draw
         self.gl
@@ -848,14 +1473,16 @@ impl Renderer {
         &mut self,
         transform: &[f32; 9],
         color: &[f32; 4],
+        min_size: f32,
+        collapsed: Option<&CollapsedLayer>,
         layer_id: usize,
-        sublayer_idx: usize,
     ) -> Result<(), JsValue> {
-        // Check if data is empty (short-lived borrow)
-        let instance_count = {
-            let layer = self.get_layer(layer_id)?;
-            layer.gerber_data[sublayer_idx].thermals.x.len()
-        };
+        // Get mutable reference to buffer cache
+        let layer = self.layers[layer_id]
+            .as_mut()
+            .ok_or_else(|| ViewerError::invalid_layer("Layer not found", layer_id as u32))?;
+        let buffer_cache = &mut layer.buffers;
+        let instance_count = buffer_cache.thermal_count;
         if instance_count == 0 {
             return Ok(());
         }
@@ -863,21 +1490,16 @@ impl Renderer {
         let program = &self.programs.thermal;
         self.gl.use_program(Some(&program.program));
 
-        // Get mutable reference to buffer cache and immutable reference to data
-        // Split borrowing: gerber_data and buffer_caches are different fields
-        let layer = self.layers[layer_id]
-            .as_mut()
-            .ok_or_else(|| JsValue::from_str("Layer not found"))?;
-        let thermals = &layer.gerber_data[sublayer_idx].thermals;
-        let buffer_cache = &mut layer.buffer_caches[sublayer_idx];
-
-        // Check if VAO is cached for this sublayer
-        if buffer_cache.thermal_vao.is_none() {
+        // Upload the sublayers unless their VAO is cached
+        if let Some(CollapsedLayer { data, sublayers }) =
+            collapsed.filter(|_| buffer_cache.thermal_vao.is_none())
+        {
+            let thermals = &data.thermals;
             // Create VAO
             let vao = self
                 .gl
                 .create_vertex_array()
-                .ok_or_else(|| JsValue::from_str("Failed to create VAO"))?;
+                .ok_or_else(|| ViewerError::webgl("Failed to create VAO"))?;
             self.gl.bind_vertex_array(Some(&vao));
 
             // Bind shared quad buffer for position attribute
@@ -888,7 +1510,7 @@ impl Renderer {
                 .vertex_attrib_pointer_with_i32(position_loc, 2, FLOAT, false, 0, 0);
 
             // Create instance buffers
-            let centers = Self::interleave_xy(&thermals.x, &thermals.y);
+            let centers = interleave_xy(&thermals.x, &thermals.y);
             let center_buffer =
                 Self::create_instance_buffer_2d(&self.gl, &centers, program, "center_instance", 1)?;
             let outer_diameter_buffer = Self::create_instance_buffer(
@@ -919,24 +1541,63 @@ impl Renderer {
                 "rotation_instance",
                 1,
             )?;
+            let function_buffer = Self::create_function_buffer(
+                &self.gl,
+                &data.functions.thermals,
+                instance_count,
+                program,
+                1,
+            )?;
+            let aperture_buffer = Self::create_aperture_buffer(
+                &self.gl,
+                &data.apertures.thermals,
+                instance_count,
+                program,
+                1,
+            )?;
+            let hidden_buffer = Self::create_hidden_buffer(
+                &self.gl,
+                layer.filter.map(|filter| {
+                    filter.hidden(
+                        data,
+                        &data.kinds.thermals,
+                        &data.apertures.thermals,
+                        &data.functions.thermals,
+                        instance_count,
+                    )
+                }),
+                program,
+                1,
+            )?;
+            let sublayer_buffer = Self::create_sublayer_buffer(
+                &self.gl,
+                &sublayers.thermals,
+                instance_count,
+                program,
+                1,
+            )?;
 
             // Unbind VAO
             self.gl.bind_vertex_array(None);
 
-            // Cache VAO and buffers for this sublayer
+            // Cache VAO and buffers
             buffer_cache.thermal_vao = Some(vao);
             buffer_cache.thermal_center_buffer = Some(center_buffer);
             buffer_cache.thermal_outer_diameter_buffer = Some(outer_diameter_buffer);
             buffer_cache.thermal_inner_diameter_buffer = Some(inner_diameter_buffer);
             buffer_cache.thermal_gap_thickness_buffer = Some(gap_thickness_buffer);
             buffer_cache.thermal_rotation_buffer = Some(rotation_buffer);
+            buffer_cache.thermal_function_buffer = function_buffer;
+            buffer_cache.thermal_aperture_buffer = aperture_buffer;
+            buffer_cache.thermal_hidden_buffer = hidden_buffer;
+            buffer_cache.thermal_sublayer_buffer = sublayer_buffer;
         }
 
         // Re-get immutable reference for rendering
         let layer = self.get_layer(layer_id)?;
-        let buffer_cache = &layer.buffer_caches[sublayer_idx];
+        let buffer_cache = &layer.buffers;
 
-        // Bind cached VAO for this sublayer
+        // Bind cached VAO
         self.gl.bind_vertex_array(buffer_cache.thermal_vao.as_ref());
 
         // Set uniforms (only transform and color)
@@ -947,6 +1608,164 @@ impl Renderer {
         if let Some(loc) = program.uniforms.get("color") {
             self.gl.uniform4fv_with_f32_array(Some(loc), color);
         }
+        self.set_function_uniforms(program, layer_id);
+        self.set_highlight_uniform(program, layer_id);
+        if let Some(loc) = program.uniforms.get("u_min_size") {
+            self.gl.uniform1f(Some(loc), min_size);
+        }
+
+        // Draw
+        self.gl
+            .draw_arrays_instanced(TRIANGLES, 0, 6, instance_count as i32);
+
+        // Unbind VAO to prevent state leakage
+        self.gl.bind_vertex_array(None);
+
+        Ok(())
+    }
+
+    /// Draw instanced obrounds
+    fn draw_instanced_obrounds(
+        &mut self,
+        transform: &[f32; 9],
+        color: &[f32; 4],
+        min_size: f32,
+        collapsed: Option<&CollapsedLayer>,
+        layer_id: usize,
+    ) -> Result<(), JsValue> {
+        // Get mutable reference to buffer cache
+        let layer = self.layers[layer_id]
+            .as_mut()
+            .ok_or_else(|| ViewerError::invalid_layer("Layer not found", layer_id as u32))?;
+        let buffer_cache = &mut layer.buffers;
+        let instance_count = buffer_cache.obround_count;
+        if instance_count == 0 {
+            return Ok(());
+        }
+
+        let program = &self.programs.obround;
+        self.gl.use_program(Some(&program.program));
+
+        // Upload the sublayers unless their VAO is cached
+        if let Some(CollapsedLayer { data, sublayers }) =
+            collapsed.filter(|_| buffer_cache.obround_vao.is_none())
+        {
+            let obrounds = &data.obrounds;
+            // Create VAO
+            let vao = self
+                .gl
+                .create_vertex_array()
+                .ok_or_else(|| ViewerError::webgl("Failed to create VAO"))?;
+            self.gl.bind_vertex_array(Some(&vao));
+
+            // Bind shared quad buffer for position attribute
+            self.gl.bind_buffer(ARRAY_BUFFER, Some(&self.quad_buffer));
+            let position_loc = *program.attributes.get("position").unwrap();
+            self.gl.enable_vertex_attrib_array(position_loc);
+            self.gl
+                .vertex_attrib_pointer_with_i32(position_loc, 2, FLOAT, false, 0, 0);
+
+            // Create instance buffers
+            let centers = interleave_xy(&obrounds.x, &obrounds.y);
+            let center_buffer =
+                Self::create_instance_buffer_2d(&self.gl, &centers, program, "center_instance", 1)?;
+            let sizes = interleave_xy(&obrounds.width, &obrounds.height);
+            let size_buffer =
+                Self::create_instance_buffer_2d(&self.gl, &sizes, program, "size_instance", 1)?;
+            let rotation_buffer = Self::create_instance_buffer(
+                &self.gl,
+                &obrounds.rotation,
+                program,
+                "rotation_instance",
+                1,
+            )?;
+            let hole_centers = interleave_xy(&obrounds.hole_x, &obrounds.hole_y);
+            let hole_center_buffer = Self::create_instance_buffer_2d(
+                &self.gl,
+                &hole_centers,
+                program,
+                "hole_center_instance",
+                1,
+            )?;
+            let hole_radius_buffer = Self::create_instance_buffer(
+                &self.gl,
+                &obrounds.hole_radius,
+                program,
+                "hole_radius_instance",
+                1,
+            )?;
+            let function_buffer = Self::create_function_buffer(
+                &self.gl,
+                &data.functions.obrounds,
+                instance_count,
+                program,
+                1,
+            )?;
+            let aperture_buffer = Self::create_aperture_buffer(
+                &self.gl,
+                &data.apertures.obrounds,
+                instance_count,
+                program,
+                1,
+            )?;
+            let hidden_buffer = Self::create_hidden_buffer(
+                &self.gl,
+                layer.filter.map(|filter| {
+                    filter.hidden(
+                        data,
+                        &data.kinds.obrounds,
+                        &data.apertures.obrounds,
+                        &data.functions.obrounds,
+                        instance_count,
+                    )
+                }),
+                program,
+                1,
+            )?;
+            let sublayer_buffer = Self::create_sublayer_buffer(
+                &self.gl,
+                &sublayers.obrounds,
+                instance_count,
+                program,
+                1,
+            )?;
+
+            // Unbind VAO
+            self.gl.bind_vertex_array(None);
+
+            // Cache VAO and buffers
+            buffer_cache.obround_vao = Some(vao);
+            buffer_cache.obround_center_buffer = Some(center_buffer);
+            buffer_cache.obround_size_buffer = Some(size_buffer);
+            buffer_cache.obround_rotation_buffer = Some(rotation_buffer);
+            buffer_cache.obround_hole_center_buffer = Some(hole_center_buffer);
+            buffer_cache.obround_hole_radius_buffer = Some(hole_radius_buffer);
+            buffer_cache.obround_function_buffer = function_buffer;
+            buffer_cache.obround_aperture_buffer = aperture_buffer;
+            buffer_cache.obround_hidden_buffer = hidden_buffer;
+            buffer_cache.obround_sublayer_buffer = sublayer_buffer;
+        }
+
+        // Re-get immutable reference for rendering
+        let layer = self.get_layer(layer_id)?;
+        let buffer_cache = &layer.buffers;
+
+        // Bind cached VAO
+        self.gl.bind_vertex_array(buffer_cache.obround_vao.as_ref());
+
+        // Set uniforms (only transform and color)
+        if let Some(loc) = program.uniforms.get("transform") {
+            self.gl
+                .uniform_matrix3fv_with_f32_array(Some(loc), false, transform);
+        }
+        if let Some(loc) = program.uniforms.get("color") {
+            self.gl.uniform4fv_with_f32_array(Some(loc), color);
+        }
+        self.set_function_uniforms(program, layer_id);
+        self.set_highlight_uniform(program, layer_id);
+        if let Some(loc) = program.uniforms.get("u_min_size") {
+            self.gl.uniform1f(Some(loc), min_size);
+        }
 
         // Draw
         self.gl
@@ -961,43 +1780,66 @@ impl Renderer {
     /// Render all geometry from a specific user layer (with polarity sublayers)
     fn render_layer_geometry(
         &mut self,
+        store: &LayerStore,
         layer_id: usize,
         transform: &[f32; 9],
+        min_size: f32,
+        min_width: f32,
     ) -> Result<(), JsValue> {
         if layer_id >= self.layers.len() || self.layers[layer_id].is_none() {
             return Ok(());
         }
 
         let white_color = [1.0, 1.0, 1.0, 1.0];
+        let sublayers = store.get(layer_id)?;
 
-        // Get sublayer count
-        let sublayer_count = self.get_layer(layer_id)?.gerber_data.len();
-
-        // Render each polarity sublayer with appropriate blending
-        for sublayer_idx in 0..sublayer_count {
-            // Check polarity: even index = positive, odd index = negative
-            let is_negative = (sublayer_idx % 2) == 1;
-
-            // Set polarity blending mode
-            self.gl.enable(BLEND);
-            if is_negative {
-                // Negative polarity: erase alpha
-                self.gl
-                    .blend_func_separate(ZERO, ONE, ZERO, ONE_MINUS_SRC_ALPHA);
-            } else {
-                // Positive polarity: add alpha
-                self.gl.blend_func_separate(ZERO, ONE, ONE, ONE);
-            }
-            self.gl.blend_equation(FUNC_ADD);
+        // A negative image (%IPNEG) starts fully dark and swaps the polarities
+        let inverted = store.image_polarity(layer_id) == Polarity::Negative;
+        if inverted {
+            self.gl.clear_color(1.0, 1.0, 1.0, 1.0);
+            self.gl.clear(COLOR_BUFFER_BIT);
+        }
 
-            // Render all shapes (empty checks done inside draw methods)
-            self.draw_instanced_triangles(transform, &white_color, layer_id, sublayer_idx)?;
-            self.draw_instanced_circles(transform, &white_color, layer_id, sublayer_idx)?;
-            self.draw_instanced_arcs(transform, &white_color, layer_id, sublayer_idx)?;
-            self.draw_instanced_thermals(transform, &white_color, layer_id, sublayer_idx)?;
+        // Merge the polarity sublayers once, when the buffers are uploaded
+        let collapsed = match &self.layers[layer_id] {
+            Some(layer) if !layer.buffers.uploaded => CollapsedLayer::new(sublayers),
+            _ => None,
+        };
+        if let (Some(collapsed), Some(layer)) = (&collapsed, &mut self.layers[layer_id]) {
+            let data = &collapsed.data;
+            let buffers = &mut layer.buffers;
+            buffers.triangle_index_count = data.triangles.indices.len();
+            buffers.circle_count = data.circles.x.len();
+            buffers.arc_count = data.arcs.x.len();
+            buffers.thermal_count = data.thermals.x.len();
+            buffers.obround_count = data.obrounds.x.len();
         }
 
-        self.gl.disable(BLEND);
+        // Every feature replaces what lies below it: dark ones draw, clear
+        // ones erase, and the depth test keeps the latest sublayer on top
+        self.set_sublayer_uniforms(sublayers.len(), inverted);
+        for program in self.programs.primitives() {
+            self.gl.use_program(Some(&program.program));
+            self.gl
+                .uniform1f(program.uniforms.get("u_min_width"), min_width);
+        }
+        self.gl.clear(DEPTH_BUFFER_BIT);
+        self.gl.enable(DEPTH_TEST);
+        self.gl.depth_func(LEQUAL);
+
+        // Render all shapes (empty checks done inside draw methods)
+        let collapsed = collapsed.as_ref();
+        self.draw_instanced_triangles(transform, &white_color, collapsed, layer_id)?;
+        self.draw_instanced_circles(transform, &white_color, min_size, collapsed, layer_id)?;
+        self.draw_instanced_arcs(transform, &white_color, min_size, collapsed, layer_id)?;
+        self.draw_instanced_thermals(transform, &white_color, min_size, collapsed, layer_id)?;
+        self.draw_instanced_obrounds(transform, &white_color, min_size, collapsed, layer_id)?;
+
+        self.gl.depth_func(LESS);
+        self.gl.disable(DEPTH_TEST);
+        if let Some(layer) = &mut self.layers[layer_id] {
+            layer.buffers.uploaded = true;
+        }
         Ok(())
     }
 
@@ -1006,6 +1848,7 @@ impl Renderer {
     #[allow(clippy::too_many_arguments)]
     pub fn render(
         &mut self,
+        store: &LayerStore,
         active_layer_ids: &[u32],
         color_data: &[f32],
         zoom_x: f32,
@@ -1017,30 +1860,57 @@ impl Renderer {
         // Update camera state
         self.update_camera(zoom_x, offset_x, offset_y);
 
+        // The 3D view draws its meshes directly and ignores the 2D camera
+        if self.view3d.is_some() {
+            return self.render_3d(active_layer_ids, alpha);
+        }
+
         // Get canvas dimensions
         let (width, height) = self.get_canvas_size()?;
 
         // Get transform matrix
         let transform = self.camera.get_transform_matrix(width, height);
+        // Level of detail threshold in board units (clip space spans 2 units)
+        let min_size = if self.min_feature_pixels > 0.0 {
+            self.min_feature_pixels * 2.0 / (transform[0] * width as f32)
+        } else {
+            0.0
+        };
+        let min_width = self.min_stroke_pixels * 2.0 / (transform[0] * width as f32);
+
+        // In compare mode only the two compared layers are drawn
+        let compared_layer_ids = self
+            .compare
+            .as_ref()
+            .map(|c| [c.layer_a as u32, c.layer_b as u32]);
+        let layer_ids = match &compared_layer_ids {
+            Some(ids) => &ids[..],
+            None => active_layer_ids,
+        };
 
         // STEP 1: Render each active layer's geometry to its FBO (white)
-        for &layer_id in active_layer_ids {
+        for &layer_id in layer_ids {
             let layer_idx = layer_id as usize;
 
             // Validate layer exists and get FBO
             let layer = if layer_idx >= self.layers.len() || self.layers[layer_idx].is_none() {
-                return Err(JsValue::from_str(&format!(
-                    "Invalid layer_id: {}",
-                    layer_id
-                )));
+                return Err(ViewerError::invalid_layer(
+                    format!("Invalid layer_id: {}", layer_id),
+                    layer_id,
+                )
+                .into());
             } else {
                 self.layers[layer_idx].as_ref().unwrap()
             };
             let fbo = &layer.fbo;
 
-            // Bind layer FBO
+            // Bind layer FBO (its multisampled target when antialiasing)
+            let target = fbo
+                .multisample
+                .as_ref()
+                .map_or(&fbo.framebuffer, |m| &m.framebuffer);
             self.gl
-                .bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(&fbo.framebuffer));
+                .bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, Some(target));
             self.gl.viewport(0, 0, width as i32, height as i32);
 
             // Clear layer FBO
@@ -1048,15 +1918,93 @@ impl Renderer {
             self.gl.clear(COLOR_BUFFER_BIT);
 
             // Render layer geometry (with polarity blending handled internally)
-            self.render_layer_geometry(layer_idx, &transform)?;
+            self.render_layer_geometry(store, layer_idx, &transform, min_size, min_width)?;
+            self.resolve_multisample(layer_idx, width, height);
         }
 
-        // STEP 2: Composite FBOs to canvas
-        self.composite_layers(active_layer_ids, color_data, alpha)?;
+        // STEP 2: Composite FBOs to canvas, then draw analysis overlays on top
+        self.dirty.clear();
+        self.composite(active_layer_ids, color_data, alpha, &transform)?;
+
+        // STEP 3: Board minimap with the visible window
+        self.draw_minimap(store, active_layer_ids, color_data, (width, height))?;
+
+        // STEP 4: Show the finished frame
+        self.present(width, height);
 
         Ok(())
     }
 
+    /// Bind the framebuffer frames are composited into: the back buffer when
+    /// double buffering, otherwise the canvas
+    fn bind_output(&self) {
+        self.gl.bind_framebuffer(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            self.frame.as_ref().map(|frame| &frame.framebuffer),
+        );
+    }
+
+    /// Copy the finished back buffer to the canvas
+    fn present(&self, width: u32, height: u32) {
+        let Some(frame) = &self.frame else {
+            return;
+        };
+        let (w, h) = (width as i32, height as i32);
+        self.gl.bind_framebuffer(
+            WebGl2RenderingContext::READ_FRAMEBUFFER,
+            Some(&frame.framebuffer),
+        );
+        self.gl
+            .bind_framebuffer(WebGl2RenderingContext::DRAW_FRAMEBUFFER, None);
+        self.gl.blit_framebuffer(
+            0,
+            0,
+            w,
+            h,
+            0,
+            0,
+            w,
+            h,
+            COLOR_BUFFER_BIT,
+            WebGl2RenderingContext::NEAREST,
+        );
+        self.gl
+            .bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+    }
+
+    /// Resolve a layer's multisampled target into its texture
+    fn resolve_multisample(&self, layer_id: usize, width: u32, height: u32) {
+        let Some(layer) = self.layers.get(layer_id).and_then(Option::as_ref) else {
+            return;
+        };
+        let Some(multisample) = &layer.fbo.multisample else {
+            return;
+        };
+        let (w, h) = (width as i32, height as i32);
+        self.gl.bind_framebuffer(
+            WebGl2RenderingContext::READ_FRAMEBUFFER,
+            Some(&multisample.framebuffer),
+        );
+        self.gl.bind_framebuffer(
+            WebGl2RenderingContext::DRAW_FRAMEBUFFER,
+            Some(&layer.fbo.framebuffer),
+        );
+        self.gl.blit_framebuffer(
+            0,
+            0,
+            w,
+            h,
+            0,
+            0,
+            w,
+            h,
+            COLOR_BUFFER_BIT,
+            WebGl2RenderingContext::NEAREST,
+        );
+        self.gl
+            .bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+    }
+
     fn composite_layers(
         &mut self,
         active_layer_ids: &[u32],
@@ -1066,9 +2014,8 @@ impl Renderer {
         // Get canvas dimensions
         let (width, height) = self.get_canvas_size()?;
 
-        // Bind canvas framebuffer
-        self.gl
-            .bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        // Bind canvas (or back buffer) framebuffer
+        self.bind_output();
         self.gl.viewport(0, 0, width as i32, height as i32);
 
         // Clear canvas
@@ -1083,6 +2030,9 @@ impl Renderer {
         // Render each active layer's FBO to canvas with its color/alpha
         for (color_index, &layer_id) in active_layer_ids.iter().enumerate() {
             let layer_idx = layer_id as usize;
+            if self.in_xray(layer_idx) {
+                continue;
+            }
 
             if let Some(layer) = &self.layers[layer_idx] {
                 // Get RGB color from array (3 floats per layer)
@@ -1094,37 +2044,16 @@ impl Renderer {
                         color_data[color_offset + 2],
                         alpha, // Use provided alpha
                     ];
-                    self.draw_fbo_texture(&layer.fbo.texture, &color)?;
+                    let by_function = self.colors_by_function(layer_idx);
+                    self.draw_fbo_texture(&layer.fbo.texture, &color, by_function)?;
                 }
             }
         }
 
         self.gl.disable(BLEND);
 
-        Ok(())
-    }
-
-    /// Get the combined boundary from all layers
-    pub fn get_boundary(&self) -> Boundary {
-        if self.layer_count == 0 {
-            return Boundary::new(0.0, 0.0, 0.0, 0.0);
-        }
-
-        // Combine boundaries from all active layers
-        let mut min_x = f32::INFINITY;
-        let mut max_x = f32::NEG_INFINITY;
-        let mut min_y = f32::INFINITY;
-        let mut max_y = f32::NEG_INFINITY;
-
-        for layer in self.layers.iter().flatten() {
-            let b = &layer.boundary;
-            min_x = min_x.min(b.min_x);
-            max_x = max_x.max(b.max_x);
-            min_y = min_y.min(b.min_y);
-            max_y = max_y.max(b.max_y);
-        }
-
-        Boundary::new(min_x, max_x, min_y, max_y)
+        // X-ray layers go over the additive ones in stackup order
+        self.composite_xray(active_layer_ids, color_data)
     }
 
     /// Resize framebuffers when canvas size changes
@@ -1132,10 +2061,52 @@ impl Renderer {
         let (width, height) = self.get_canvas_size()?;
 
         // Recreate FBO for each active layer
-        for layer in self.layers.iter_mut().flatten() {
-            layer.fbo = Self::create_fbo(&self.gl, width, height)?;
+        for layer_id in 0..self.layers.len() {
+            if self.layers[layer_id].is_some() {
+                let fbo = self.create_layer_fbo(width, height)?;
+                if let Some(layer) = &mut self.layers[layer_id] {
+                    Self::delete_fbo(&self.gl, std::mem::replace(&mut layer.fbo, fbo));
+                }
+            }
+        }
+        if let Some(frame) = self.frame.take() {
+            Self::delete_fbo(&self.gl, frame);
+            self.frame = Some(Self::create_fbo(&self.gl, width, height)?);
         }
 
         Ok(())
     }
 }
+
+/// Size of an HtmlCanvasElement or OffscreenCanvas
+pub(crate) fn canvas_size(canvas: &js_sys::Object) -> Result<(u32, u32), JsValue> {
+    // Contexts created in a worker belong to an OffscreenCanvas
+    if let Some(canvas) = canvas.dyn_ref::<web_sys::HtmlCanvasElement>() {
+        Ok((canvas.width(), canvas.height()))
+    } else if let Some(canvas) = canvas.dyn_ref::<web_sys::OffscreenCanvas>() {
+        Ok((canvas.width(), canvas.height()))
+    } else {
+        Err(ViewerError::webgl("Unsupported canvas type").into())
+    }
+}
+
+/// The other two corners of every triangle vertex (x, y, x, y), in winding
+/// order, for widening thin triangles in the shader
+fn triangle_corners(triangles: &Triangles) -> Vec<f32> {
+    let vertices = &triangles.vertices;
+    let mut corners = vec![0.0; vertices.len() * 2];
+    for triangle in triangles.indices.chunks_exact(3) {
+        for k in 0..3 {
+            let vertex = triangle[k] as usize;
+            for (slot, other) in [triangle[(k + 1) % 3], triangle[(k + 2) % 3]]
+                .into_iter()
+                .enumerate()
+            {
+                let other = other as usize;
+                corners[vertex * 4 + slot * 2] = vertices[other * 2];
+                corners[vertex * 4 + slot * 2 + 1] = vertices[other * 2 + 1];
+            }
+        }
+    }
+    corners
+}
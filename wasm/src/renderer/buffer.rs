@@ -1,20 +1,43 @@
-use web_sys::{WebGlBuffer, WebGlFramebuffer, WebGlTexture, WebGlVertexArrayObject};
+use web_sys::{
+    WebGlBuffer, WebGlFramebuffer, WebGlRenderbuffer, WebGlTexture, WebGlVertexArrayObject,
+};
 
 /// Frame buffer object for off-screen rendering
 pub struct Fbo {
     pub framebuffer: WebGlFramebuffer,
     pub texture: WebGlTexture,
+    pub multisample: Option<MultisampleTarget>, // Drawn into first, then resolved into texture
+    pub depth: Option<WebGlRenderbuffer>,       // Orders polarity sublayers (layer geometry only)
 }
 
-/// Buffer cache for geometry rendering (per polarity sublayer)
+/// Multisampled color target of an FBO (antialiasing)
+pub struct MultisampleTarget {
+    pub framebuffer: WebGlFramebuffer,
+    pub renderbuffer: WebGlRenderbuffer,
+    pub depth: WebGlRenderbuffer,
+}
+
+/// Buffer cache for geometry rendering (all polarity sublayers of a layer)
 #[derive(Default)]
 pub struct BufferCache {
+    pub uploaded: bool, // Buffers and counts hold the layer's geometry
+    pub triangle_index_count: usize,
+    pub circle_count: usize,
+    pub arc_count: usize,
+    pub thermal_count: usize,
+    pub obround_count: usize,
+
     // Triangles cache
     pub triangle_vao: Option<WebGlVertexArrayObject>,
     pub triangle_vertex_buffer: Option<WebGlBuffer>,
     pub triangle_index_buffer: Option<WebGlBuffer>,
     pub triangle_hole_center_buffer: Option<WebGlBuffer>,
     pub triangle_hole_radius_buffer: Option<WebGlBuffer>,
+    pub triangle_function_buffer: Option<WebGlBuffer>,
+    pub triangle_aperture_buffer: Option<WebGlBuffer>,
+    pub triangle_hidden_buffer: Option<WebGlBuffer>,
+    pub triangle_sublayer_buffer: Option<WebGlBuffer>,
+    pub triangle_corner_buffer: Option<WebGlBuffer>, // Only while thin features are widened
 
     // Circles cache
     pub circle_vao: Option<WebGlVertexArrayObject>,
@@ -22,6 +45,11 @@ pub struct BufferCache {
     pub circle_radius_buffer: Option<WebGlBuffer>,
     pub circle_hole_center_buffer: Option<WebGlBuffer>,
     pub circle_hole_radius_buffer: Option<WebGlBuffer>,
+    pub circle_function_buffer: Option<WebGlBuffer>,
+    pub circle_aperture_buffer: Option<WebGlBuffer>,
+    pub circle_hidden_buffer: Option<WebGlBuffer>,
+    pub circle_sublayer_buffer: Option<WebGlBuffer>,
+    pub circle_ranges: Option<([f32; 4], [f32; 2])>, // Center and radius ranges of 16-bit data
 
     // Arcs cache
     pub arc_vao: Option<WebGlVertexArrayObject>,
@@ -30,6 +58,10 @@ pub struct BufferCache {
     pub arc_start_angle_buffer: Option<WebGlBuffer>,
     pub arc_sweep_angle_buffer: Option<WebGlBuffer>,
     pub arc_thickness_buffer: Option<WebGlBuffer>,
+    pub arc_function_buffer: Option<WebGlBuffer>,
+    pub arc_aperture_buffer: Option<WebGlBuffer>,
+    pub arc_hidden_buffer: Option<WebGlBuffer>,
+    pub arc_sublayer_buffer: Option<WebGlBuffer>,
 
     // Thermals cache
     pub thermal_vao: Option<WebGlVertexArrayObject>,
@@ -38,4 +70,20 @@ pub struct BufferCache {
     pub thermal_inner_diameter_buffer: Option<WebGlBuffer>,
     pub thermal_gap_thickness_buffer: Option<WebGlBuffer>,
     pub thermal_rotation_buffer: Option<WebGlBuffer>,
+    pub thermal_function_buffer: Option<WebGlBuffer>,
+    pub thermal_aperture_buffer: Option<WebGlBuffer>,
+    pub thermal_hidden_buffer: Option<WebGlBuffer>,
+    pub thermal_sublayer_buffer: Option<WebGlBuffer>,
+
+    // Obrounds cache
+    pub obround_vao: Option<WebGlVertexArrayObject>,
+    pub obround_center_buffer: Option<WebGlBuffer>,
+    pub obround_size_buffer: Option<WebGlBuffer>,
+    pub obround_rotation_buffer: Option<WebGlBuffer>,
+    pub obround_hole_center_buffer: Option<WebGlBuffer>,
+    pub obround_hole_radius_buffer: Option<WebGlBuffer>,
+    pub obround_function_buffer: Option<WebGlBuffer>,
+    pub obround_aperture_buffer: Option<WebGlBuffer>,
+    pub obround_hidden_buffer: Option<WebGlBuffer>,
+    pub obround_sublayer_buffer: Option<WebGlBuffer>,
 }
@@ -1,3 +1,5 @@
+use crate::view::CameraView;
+
 /// Camera transformation for viewport control
 pub struct Camera {
     pub zoom: f32,
@@ -24,23 +26,15 @@ impl Camera {
     /// # Returns
     /// A 3x3 transformation matrix as [f32; 9]
     pub fn get_transform_matrix(&self, canvas_width: u32, canvas_height: u32) -> [f32; 9] {
-        let aspect = canvas_width as f32 / canvas_height as f32;
-
-        let (scale_x, scale_y) = if aspect > 1.0 {
-            (self.zoom / aspect, self.zoom)
-        } else {
-            (self.zoom, self.zoom * aspect)
-        };
-
-        let (offset_x, offset_y) = if aspect > 1.0 {
-            (self.offset_x / aspect, self.offset_y)
-        } else {
-            (self.offset_x, self.offset_y * aspect)
-        };
+        CameraView::new(self.zoom, self.offset_x, self.offset_y)
+            .transform_matrix(canvas_width, canvas_height)
+    }
 
-        [
-            scale_x, 0.0, 0.0, 0.0, scale_y, 0.0, offset_x, offset_y, 1.0,
-        ]
+    /// Zoom at which one board millimeter spans `pixels_per_mm` canvas pixels
+    ///
+    /// The shorter canvas side spans two clip space units.
+    pub fn zoom_for_pixel_scale(pixels_per_mm: f32, canvas_width: u32, canvas_height: u32) -> f32 {
+        2.0 * pixels_per_mm / canvas_width.min(canvas_height).max(1) as f32
     }
 }
 
@@ -0,0 +1,193 @@
+use super::shader::{ShaderProgram, ARRAY_BUFFER, FLOAT, STATIC_DRAW};
+use super::Renderer;
+use crate::error::ViewerError;
+use crate::shape::{Boundary, GerberData, PrimitiveValues};
+use js_sys::Float32Array;
+use std::borrow::Cow;
+use wasm_bindgen::prelude::*;
+use web_sys::{WebGl2RenderingContext, WebGlBuffer};
+
+/// The polarity sublayers of a layer merged into one set of arrays
+///
+/// Every element keeps the index of its sublayer. The shaders turn it into
+/// the exposure (even = dark, odd = clear) and a depth that puts later
+/// sublayers in front, so a file toggling %LPD/%LPC hundreds of times is
+/// still drawn with one buffer set and one call per primitive type.
+pub struct CollapsedLayer<'a> {
+    pub data: Cow<'a, GerberData>,
+    pub sublayers: PrimitiveValues<f32>, // Sublayer index per element, empty for a single sublayer
+}
+
+impl<'a> CollapsedLayer<'a> {
+    /// Merge `sublayers` in file order; a single sublayer is used as is
+    pub fn new(sublayers: &'a [GerberData]) -> Option<CollapsedLayer<'a>> {
+        let (first, rest) = sublayers.split_first()?;
+        if rest.is_empty() {
+            return Some(CollapsedLayer {
+                data: Cow::Borrowed(first),
+                sublayers: PrimitiveValues::default(),
+            });
+        }
+
+        let mut data = first.clone();
+        let mut indices = PrimitiveValues::default();
+        indices.resize(lengths(first), 0.0);
+        for (index, sublayer) in rest.iter().enumerate() {
+            append(&mut data, sublayer);
+            indices.resize(lengths(&data), (index + 1) as f32);
+        }
+        data.boundary = Boundary::combined(sublayers);
+        Some(CollapsedLayer {
+            data: Cow::Owned(data),
+            sublayers: indices,
+        })
+    }
+}
+
+/// Element count of each primitive type (triangle vertices, circles, arcs,
+/// thermals, obrounds)
+fn lengths(data: &GerberData) -> [usize; 5] {
+    [
+        data.triangles.vertices.len() / 2,
+        data.circles.x.len(),
+        data.arcs.x.len(),
+        data.thermals.x.len(),
+        data.obrounds.x.len(),
+    ]
+}
+
+/// Append the primitives, per-primitive values, flashes and draws of `other`
+fn append(data: &mut GerberData, other: &GerberData) {
+    let (data_lengths, other_lengths) = (lengths(data), lengths(other));
+
+    let t = &mut data.triangles;
+    let offset = (t.vertices.len() / 2) as u32;
+    t.vertices.extend_from_slice(&other.triangles.vertices);
+    t.indices
+        .extend(other.triangles.indices.iter().map(|i| i + offset));
+    t.hole_x.extend_from_slice(&other.triangles.hole_x);
+    t.hole_y.extend_from_slice(&other.triangles.hole_y);
+    t.hole_radius
+        .extend_from_slice(&other.triangles.hole_radius);
+
+    let c = &mut data.circles;
+    c.x.extend_from_slice(&other.circles.x);
+    c.y.extend_from_slice(&other.circles.y);
+    c.radius.extend_from_slice(&other.circles.radius);
+    c.hole_x.extend_from_slice(&other.circles.hole_x);
+    c.hole_y.extend_from_slice(&other.circles.hole_y);
+    c.hole_radius.extend_from_slice(&other.circles.hole_radius);
+
+    let a = &mut data.arcs;
+    a.x.extend_from_slice(&other.arcs.x);
+    a.y.extend_from_slice(&other.arcs.y);
+    a.radius.extend_from_slice(&other.arcs.radius);
+    a.start_angle.extend_from_slice(&other.arcs.start_angle);
+    a.sweep_angle.extend_from_slice(&other.arcs.sweep_angle);
+    a.thickness.extend_from_slice(&other.arcs.thickness);
+
+    let th = &mut data.thermals;
+    th.x.extend_from_slice(&other.thermals.x);
+    th.y.extend_from_slice(&other.thermals.y);
+    th.outer_diameter
+        .extend_from_slice(&other.thermals.outer_diameter);
+    th.inner_diameter
+        .extend_from_slice(&other.thermals.inner_diameter);
+    th.gap_thickness
+        .extend_from_slice(&other.thermals.gap_thickness);
+    th.rotation.extend_from_slice(&other.thermals.rotation);
+
+    let o = &mut data.obrounds;
+    o.x.extend_from_slice(&other.obrounds.x);
+    o.y.extend_from_slice(&other.obrounds.y);
+    o.width.extend_from_slice(&other.obrounds.width);
+    o.height.extend_from_slice(&other.obrounds.height);
+    o.rotation.extend_from_slice(&other.obrounds.rotation);
+    o.hole_x.extend_from_slice(&other.obrounds.hole_x);
+    o.hole_y.extend_from_slice(&other.obrounds.hole_y);
+    o.hole_radius.extend_from_slice(&other.obrounds.hole_radius);
+
+    append_values(
+        &mut data.functions,
+        &other.functions,
+        data_lengths,
+        other_lengths,
+    );
+    append_values(
+        &mut data.apertures,
+        &other.apertures,
+        data_lengths,
+        other_lengths,
+    );
+    append_values(&mut data.kinds, &other.kinds, data_lengths, other_lengths);
+    data.flashes.append(&mut other.flashes.clone());
+    data.traces.append(&mut other.traces.clone());
+}
+
+/// Append per-primitive values, padding with the default (none, region,
+/// unknown) where only one side has them
+fn append_values<T: Copy + Default>(
+    values: &mut PrimitiveValues<T>,
+    other: &PrimitiveValues<T>,
+    lengths: [usize; 5],
+    other_lengths: [usize; 5],
+) {
+    if values.is_empty() && other.is_empty() {
+        return;
+    }
+    let mut other = other.clone();
+    other.resize(other_lengths, T::default());
+    values.resize(lengths, T::default());
+    values.triangles.append(&mut other.triangles);
+    values.circles.append(&mut other.circles);
+    values.arcs.append(&mut other.arcs);
+    values.thermals.append(&mut other.thermals);
+    values.obrounds.append(&mut other.obrounds);
+}
+
+impl Renderer {
+    /// Create and bind a sublayer index buffer (one f32 per element)
+    ///
+    /// Returns None for a layer of one sublayer; the disabled attribute then
+    /// reads as 0, the first dark sublayer.
+    pub(super) fn create_sublayer_buffer(
+        gl: &WebGl2RenderingContext,
+        sublayers: &[f32],
+        count: usize,
+        program: &ShaderProgram,
+        divisor: u32,
+    ) -> Result<Option<WebGlBuffer>, JsValue> {
+        if sublayers.len() != count {
+            return Ok(None);
+        }
+        let buffer = gl
+            .create_buffer()
+            .ok_or_else(|| ViewerError::webgl("Failed to create sublayer buffer"))?;
+        gl.bind_buffer(ARRAY_BUFFER, Some(&buffer));
+        unsafe {
+            let array = Float32Array::view(sublayers);
+            gl.buffer_data_with_array_buffer_view(ARRAY_BUFFER, &array, STATIC_DRAW);
+        }
+        let loc = program
+            .attributes
+            .get("sublayer_instance")
+            .ok_or_else(|| ViewerError::webgl("Missing shader attribute: sublayer_instance"))?;
+        gl.enable_vertex_attrib_array(*loc);
+        gl.vertex_attrib_pointer_with_i32(*loc, 1, FLOAT, false, 0, 0);
+        gl.vertex_attrib_divisor(*loc, divisor);
+        Ok(Some(buffer))
+    }
+
+    /// Set the sublayer count and image polarity of all primitive programs
+    pub(super) fn set_sublayer_uniforms(&self, sublayer_count: usize, inverted: bool) {
+        for program in self.programs.primitives() {
+            self.gl.use_program(Some(&program.program));
+            self.gl.uniform1f(
+                program.uniforms.get("u_sublayer_count"),
+                sublayer_count as f32,
+            );
+            self.gl
+                .uniform1i(program.uniforms.get("u_inverted"), i32::from(inverted));
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use super::shader::{ARRAY_BUFFER, BLEND, COLOR_BUFFER_BIT, FLOAT, FUNC_ADD, ONE, TRIANGLES};
+use super::Renderer;
+use crate::error::ViewerError;
+use wasm_bindgen::prelude::*;
+use web_sys::WebGl2RenderingContext;
+
+// Default colors: A-only red, B-only green, common gray
+const DEFAULT_COMPARE_COLORS: [f32; 9] = [1.0, 0.2, 0.2, 0.2, 1.0, 0.2, 0.6, 0.6, 0.6];
+
+/// Two-layer comparison display state
+pub struct CompareMode {
+    pub layer_a: usize,
+    pub layer_b: usize,
+    pub colors: [f32; 9], // [A-only rgb, B-only rgb, common rgb]
+}
+
+impl Renderer {
+    /// Switch rendering to compare mode for two layers
+    ///
+    /// `colors` holds [A-only, B-only, common] as 9 RGB floats; empty uses defaults.
+    pub fn set_compare(
+        &mut self,
+        layer_a: usize,
+        layer_b: usize,
+        colors: &[f32],
+    ) -> Result<(), JsValue> {
+        self.get_layer(layer_a)?;
+        self.get_layer(layer_b)?;
+
+        let colors = if colors.is_empty() {
+            DEFAULT_COMPARE_COLORS
+        } else {
+            colors.try_into().map_err(|_| {
+                ViewerError::invalid_argument("Compare colors must contain 9 values (3 RGB colors)")
+            })?
+        };
+
+        self.compare = Some(CompareMode {
+            layer_a,
+            layer_b,
+            colors,
+        });
+        Ok(())
+    }
+
+    /// Leave compare mode and return to normal layer compositing
+    pub fn clear_compare(&mut self) {
+        self.compare = None;
+    }
+
+    /// Composite the two compared layer FBOs to the canvas
+    pub(super) fn composite_compare(&self, alpha: f32) -> Result<(), JsValue> {
+        let compare = match &self.compare {
+            Some(compare) => compare,
+            None => return Ok(()),
+        };
+        let (width, height) = self.get_canvas_size()?;
+        let texture_a = &self.get_layer(compare.layer_a)?.fbo.texture;
+        let texture_b = &self.get_layer(compare.layer_b)?.fbo.texture;
+
+        // Bind canvas (or back buffer) framebuffer
+        self.bind_output();
+        self.gl.viewport(0, 0, width as i32, height as i32);
+        self.gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        self.gl.clear(COLOR_BUFFER_BIT);
+
+        let program = &self.programs.compare;
+        self.gl.use_program(Some(&program.program));
+
+        self.gl.bind_buffer(ARRAY_BUFFER, Some(&self.quad_buffer));
+        let pos_loc = *program.attributes.get("position").unwrap();
+        self.gl.enable_vertex_attrib_array(pos_loc);
+        self.gl
+            .vertex_attrib_pointer_with_i32(pos_loc, 2, FLOAT, false, 0, 0);
+
+        self.gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        self.gl
+            .bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(texture_a));
+        self.gl.active_texture(WebGl2RenderingContext::TEXTURE1);
+        self.gl
+            .bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(texture_b));
+        self.gl.uniform1i(program.uniforms.get("u_texture_a"), 0);
+        self.gl.uniform1i(program.uniforms.get("u_texture_b"), 1);
+        self.gl
+            .uniform3fv_with_f32_array(program.uniforms.get("u_color_a"), &compare.colors[0..3]);
+        self.gl
+            .uniform3fv_with_f32_array(program.uniforms.get("u_color_b"), &compare.colors[3..6]);
+        self.gl.uniform3fv_with_f32_array(
+            program.uniforms.get("u_color_common"),
+            &compare.colors[6..9],
+        );
+        self.gl.uniform1f(program.uniforms.get("u_alpha"), alpha);
+
+        self.gl.enable(BLEND);
+        self.gl.blend_func(ONE, ONE);
+        self.gl.blend_equation(FUNC_ADD);
+        self.gl.draw_arrays(TRIANGLES, 0, 6);
+        self.gl.disable(BLEND);
+
+        // Leave texture unit 0 active for the other composite passes
+        self.gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+
+        Ok(())
+    }
+}
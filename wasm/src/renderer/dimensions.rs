@@ -0,0 +1,284 @@
+use super::shader::TRIANGLES;
+use super::Renderer;
+use crate::error::ViewerError;
+use crate::shape::Boundary;
+use wasm_bindgen::prelude::*;
+use web_sys::{WebGlBuffer, WebGlVertexArrayObject};
+
+// Dimension lines and text color (premultiplied white)
+const DIMENSION_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+// Sizes as fractions of the longer board side, so the annotation scales with the board:
+// dimension line offset from the board edge, extension line gap to the board edge
+// and overshoot past the dimension line, and the gap between line and text
+const DIMENSION_OFFSET: f32 = 0.08;
+const EXTENSION_GAP: f32 = 0.01;
+const EXTENSION_OVERSHOOT: f32 = 0.02;
+const TEXT_GAP: f32 = 0.015;
+const LINE_WIDTH: f32 = 0.003;
+const ARROW_LENGTH: f32 = 0.025;
+const ARROW_WIDTH: f32 = 0.016;
+const TEXT_HEIGHT: f32 = 0.035;
+// Glyph cell of the stroke font: 4 x 6 units, advancing 6 units per character
+const GLYPH_WIDTH: f32 = 4.0;
+const GLYPH_HEIGHT: f32 = 6.0;
+const GLYPH_ADVANCE: f32 = 6.0;
+
+/// Board width and height dimension lines uploaded as plain triangles
+pub struct DimensionOverlay {
+    pub layer_id: usize, // Outline layer the dimensions were measured on
+    pub vao: WebGlVertexArrayObject,
+    pub buffer: WebGlBuffer,
+    pub count: i32, // Vertex count
+}
+
+impl Renderer {
+    /// Annotate the board with its overall width and height
+    ///
+    /// The width is dimensioned below the board and the height to its right,
+    /// each with extension lines, arrowheads and the size in mm.
+    pub fn set_dimensions(&mut self, layer_id: usize, board: &Boundary) -> Result<(), JsValue> {
+        self.get_layer(layer_id)?;
+        self.clear_dimensions();
+
+        let vertices = dimension_vertices(board);
+        let program = &self.programs.triangle;
+        let vao = self
+            .gl
+            .create_vertex_array()
+            .ok_or_else(|| ViewerError::webgl("Failed to create VAO"))?;
+        self.gl.bind_vertex_array(Some(&vao));
+        let buffer = Self::create_instance_buffer_2d(&self.gl, &vertices, program, "position", 0)?;
+        self.gl.bind_vertex_array(None);
+
+        self.dimensions = Some(DimensionOverlay {
+            layer_id,
+            vao,
+            buffer,
+            count: (vertices.len() / 2) as i32,
+        });
+        Ok(())
+    }
+
+    /// Remove the board dimensions and free their buffer
+    pub fn clear_dimensions(&mut self) {
+        if let Some(dimensions) = self.dimensions.take() {
+            self.gl.delete_vertex_array(Some(&dimensions.vao));
+            self.gl.delete_buffer(Some(&dimensions.buffer));
+        }
+    }
+
+    /// Draw the board dimensions in one color
+    pub(super) fn draw_dimensions(&self, dimensions: &DimensionOverlay, transform: &[f32; 9]) {
+        let program = &self.programs.triangle;
+        self.gl.use_program(Some(&program.program));
+        self.gl.uniform_matrix3fv_with_f32_array(
+            program.uniforms.get("transform"),
+            false,
+            transform,
+        );
+        // Layer draws may leave function colors or a highlight on
+        self.gl.uniform1i(program.uniforms.get("u_by_function"), 0);
+        self.gl
+            .uniform1f(program.uniforms.get("u_highlight_code"), -1.0);
+        self.gl
+            .uniform4fv_with_f32_array(program.uniforms.get("color"), &DIMENSION_COLOR);
+
+        self.gl.bind_vertex_array(Some(&dimensions.vao));
+        self.gl.draw_arrays(TRIANGLES, 0, dimensions.count);
+        self.gl.bind_vertex_array(None);
+    }
+}
+
+/// Triangles of the width dimension below the board and the height
+/// dimension to its right, in world space
+fn dimension_vertices(board: &Boundary) -> Vec<f32> {
+    let width = board.max_x - board.min_x;
+    let height = board.max_y - board.min_y;
+    let size = width.max(height);
+    let line = LINE_WIDTH * size / 2.0;
+    let mut vertices = Vec::new();
+
+    // Width: extension lines down from the bottom corners, text below the line
+    let y = board.min_y - DIMENSION_OFFSET * size;
+    for x in [board.min_x, board.max_x] {
+        push_segment(
+            &mut vertices,
+            [x, board.min_y - EXTENSION_GAP * size],
+            [x, y - EXTENSION_OVERSHOOT * size],
+            line,
+        );
+    }
+    push_dimension_line(&mut vertices, [board.min_x, y], [board.max_x, y], size);
+    let text = format!("{:.2} mm", width);
+    let text_height = TEXT_HEIGHT * size;
+    let text_x = (board.min_x + board.max_x - text_width(&text, text_height)) / 2.0;
+    push_text(
+        &mut vertices,
+        &text,
+        [text_x, y - TEXT_GAP * size - text_height],
+        text_height,
+    );
+
+    // Height: extension lines right from the right corners, text beside the line
+    let x = board.max_x + DIMENSION_OFFSET * size;
+    for y in [board.min_y, board.max_y] {
+        push_segment(
+            &mut vertices,
+            [board.max_x + EXTENSION_GAP * size, y],
+            [x + EXTENSION_OVERSHOOT * size, y],
+            line,
+        );
+    }
+    push_dimension_line(&mut vertices, [x, board.min_y], [x, board.max_y], size);
+    let text = format!("{:.2} mm", height);
+    push_text(
+        &mut vertices,
+        &text,
+        [
+            x + TEXT_GAP * size,
+            (board.min_y + board.max_y - text_height) / 2.0,
+        ],
+        text_height,
+    );
+
+    vertices
+}
+
+/// Append a dimension line from `from` to `to` with an arrowhead at each end
+fn push_dimension_line(vertices: &mut Vec<f32>, from: [f32; 2], to: [f32; 2], size: f32) {
+    let length = (to[0] - from[0]).hypot(to[1] - from[1]).max(f32::EPSILON);
+    let direction = [(to[0] - from[0]) / length, (to[1] - from[1]) / length];
+    let arrow_length = (ARROW_LENGTH * size).min(length / 2.0);
+    let inset = |point: [f32; 2], sign: f32| {
+        [
+            point[0] + direction[0] * arrow_length * sign,
+            point[1] + direction[1] * arrow_length * sign,
+        ]
+    };
+    push_segment(
+        vertices,
+        inset(from, 1.0),
+        inset(to, -1.0),
+        LINE_WIDTH * size / 2.0,
+    );
+
+    let half_width = ARROW_WIDTH * size / 2.0;
+    let normal = [-direction[1] * half_width, direction[0] * half_width];
+    for (tip, sign) in [(from, 1.0), (to, -1.0)] {
+        let base = inset(tip, sign);
+        vertices.extend_from_slice(&[
+            tip[0],
+            tip[1],
+            base[0] + normal[0],
+            base[1] + normal[1],
+            base[0] - normal[0],
+            base[1] - normal[1],
+        ]);
+    }
+}
+
+/// Append a line of `half_width` from `from` to `to` with square caps
+fn push_segment(vertices: &mut Vec<f32>, from: [f32; 2], to: [f32; 2], half_width: f32) {
+    let length = (to[0] - from[0]).hypot(to[1] - from[1]);
+    let (dx, dy) = if length > 0.0 {
+        (
+            (to[0] - from[0]) / length * half_width,
+            (to[1] - from[1]) / length * half_width,
+        )
+    } else {
+        (half_width, 0.0)
+    };
+    // Caps reach past both ends so strokes meet without gaps at corners
+    let corners = [
+        [from[0] - dx - dy, from[1] - dy + dx],
+        [from[0] - dx + dy, from[1] - dy - dx],
+        [to[0] + dx + dy, to[1] + dy - dx],
+        [to[0] + dx - dy, to[1] + dy + dx],
+    ];
+    for index in [0, 1, 2, 0, 2, 3] {
+        vertices.extend_from_slice(&corners[index]);
+    }
+}
+
+/// Width of `text` drawn at `height`
+fn text_width(text: &str, height: f32) -> f32 {
+    let scale = height / GLYPH_HEIGHT;
+    (text.chars().count() as f32 * GLYPH_ADVANCE - (GLYPH_ADVANCE - GLYPH_WIDTH)) * scale
+}
+
+/// Append `text` in the stroke font with its lower left corner at `origin`
+fn push_text(vertices: &mut Vec<f32>, text: &str, origin: [f32; 2], height: f32) {
+    let scale = height / GLYPH_HEIGHT;
+    let half_width = scale * 0.4;
+    for (index, c) in text.chars().enumerate() {
+        let left = origin[0] + index as f32 * GLYPH_ADVANCE * scale;
+        for stroke in glyph(c) {
+            for pair in stroke.windows(2) {
+                let point = |p: [f32; 2]| [left + p[0] * scale, origin[1] + p[1] * scale];
+                push_segment(vertices, point(pair[0]), point(pair[1]), half_width);
+            }
+        }
+    }
+}
+
+/// Strokes of a character in the 4 x 6 glyph cell (digits, '.', 'm' and space)
+fn glyph(c: char) -> &'static [&'static [[f32; 2]]] {
+    match c {
+        '0' => &[&[[0.0, 0.0], [4.0, 0.0], [4.0, 6.0], [0.0, 6.0], [0.0, 0.0]]],
+        '1' => &[
+            &[[1.0, 5.0], [2.0, 6.0], [2.0, 0.0]],
+            &[[1.0, 0.0], [3.0, 0.0]],
+        ],
+        '2' => &[&[
+            [0.0, 6.0],
+            [4.0, 6.0],
+            [4.0, 3.0],
+            [0.0, 3.0],
+            [0.0, 0.0],
+            [4.0, 0.0],
+        ]],
+        '3' => &[
+            &[[0.0, 6.0], [4.0, 6.0], [4.0, 0.0], [0.0, 0.0]],
+            &[[1.0, 3.0], [4.0, 3.0]],
+        ],
+        '4' => &[
+            &[[0.0, 6.0], [0.0, 3.0], [4.0, 3.0]],
+            &[[4.0, 6.0], [4.0, 0.0]],
+        ],
+        '5' => &[&[
+            [4.0, 6.0],
+            [0.0, 6.0],
+            [0.0, 3.0],
+            [4.0, 3.0],
+            [4.0, 0.0],
+            [0.0, 0.0],
+        ]],
+        '6' => &[&[
+            [4.0, 6.0],
+            [0.0, 6.0],
+            [0.0, 0.0],
+            [4.0, 0.0],
+            [4.0, 3.0],
+            [0.0, 3.0],
+        ]],
+        '7' => &[&[[0.0, 6.0], [4.0, 6.0], [4.0, 0.0]]],
+        '8' => &[
+            &[[0.0, 0.0], [4.0, 0.0], [4.0, 6.0], [0.0, 6.0], [0.0, 0.0]],
+            &[[0.0, 3.0], [4.0, 3.0]],
+        ],
+        '9' => &[&[
+            [4.0, 3.0],
+            [0.0, 3.0],
+            [0.0, 6.0],
+            [4.0, 6.0],
+            [4.0, 0.0],
+            [0.0, 0.0],
+        ]],
+        '.' => &[&[[2.0, 0.0], [2.0, 0.0]]],
+        'm' => &[
+            &[[0.0, 0.0], [0.0, 4.0], [4.0, 4.0], [4.0, 0.0]],
+            &[[2.0, 4.0], [2.0, 0.0]],
+        ],
+        _ => &[],
+    }
+}
@@ -0,0 +1,103 @@
+use super::Renderer;
+use crate::store::LayerStore;
+use wasm_bindgen::prelude::*;
+use web_sys::WebGl2RenderingContext;
+
+// More dirty rectangles than this are merged into their bounding box
+const MAX_DIRTY_RECTS: usize = 16;
+
+impl Renderer {
+    /// Queue a canvas rectangle for render_dirty()
+    ///
+    /// `x` and `y` are the top-left corner in canvas pixels (y down, like the
+    /// DOM); the rectangle is clamped to the canvas.
+    pub fn mark_dirty(&mut self, x: f32, y: f32, width: f32, height: f32) -> Result<(), JsValue> {
+        let (canvas_width, canvas_height) = self.get_canvas_size()?;
+        let left = x.floor().clamp(0.0, canvas_width as f32) as i32;
+        let right = (x + width).ceil().clamp(0.0, canvas_width as f32) as i32;
+        let top = y.floor().clamp(0.0, canvas_height as f32) as i32;
+        let bottom = (y + height).ceil().clamp(0.0, canvas_height as f32) as i32;
+        if left >= right || top >= bottom {
+            return Ok(());
+        }
+        // GL scissor rectangles start at the bottom-left corner
+        self.dirty.push([
+            left,
+            canvas_height as i32 - bottom,
+            right - left,
+            bottom - top,
+        ]);
+        if self.dirty.len() > MAX_DIRTY_RECTS {
+            let merged = self.dirty.iter().fold([i32::MAX, i32::MAX, 0, 0], |m, r| {
+                [
+                    m[0].min(r[0]),
+                    m[1].min(r[1]),
+                    m[2].max(r[0] + r[2]),
+                    m[3].max(r[1] + r[3]),
+                ]
+            });
+            self.dirty = vec![[
+                merged[0],
+                merged[1],
+                merged[2] - merged[0],
+                merged[3] - merged[1],
+            ]];
+        }
+        Ok(())
+    }
+
+    /// Recomposite the queued dirty rectangles from the cached layer FBOs
+    ///
+    /// Layer geometry is not drawn again, so the FBOs must still match the
+    /// camera and layers of the last render(). Overlays are redrawn inside
+    /// the rectangles and the minimap on top. Only the back buffer of
+    /// double buffering keeps the rest of the frame; without it the whole
+    /// canvas is recomposited. Nothing is drawn in the 3D view.
+    pub fn render_dirty(
+        &mut self,
+        store: &LayerStore,
+        active_layer_ids: &[u32],
+        color_data: &[f32],
+        alpha: f32,
+    ) -> Result<(), JsValue> {
+        let dirty = std::mem::take(&mut self.dirty);
+        if dirty.is_empty() || self.view3d.is_some() {
+            return Ok(());
+        }
+        let (width, height) = self.get_canvas_size()?;
+        let transform = self.camera.get_transform_matrix(width, height);
+
+        let result = if self.frame.is_some() {
+            self.gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
+            let result = dirty.iter().try_for_each(|&[x, y, w, h]| {
+                self.gl.scissor(x, y, w, h);
+                self.composite(active_layer_ids, color_data, alpha, &transform)
+            });
+            self.gl.disable(WebGl2RenderingContext::SCISSOR_TEST);
+            result
+        } else {
+            self.composite(active_layer_ids, color_data, alpha, &transform)
+        };
+        result?;
+
+        self.draw_minimap(store, active_layer_ids, color_data, (width, height))?;
+        self.present(width, height);
+        Ok(())
+    }
+
+    /// Composite the layer FBOs and draw the overlays over them
+    pub(super) fn composite(
+        &mut self,
+        active_layer_ids: &[u32],
+        color_data: &[f32],
+        alpha: f32,
+        transform: &[f32; 9],
+    ) -> Result<(), JsValue> {
+        if self.compare.is_some() {
+            self.composite_compare(alpha)?;
+        } else {
+            self.composite_layers(active_layer_ids, color_data, alpha)?;
+        }
+        self.draw_overlays(transform)
+    }
+}
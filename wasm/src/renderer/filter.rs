@@ -0,0 +1,127 @@
+use super::shader::{ShaderProgram, ARRAY_BUFFER, STATIC_DRAW, UNSIGNED_BYTE};
+use super::Renderer;
+use crate::error::ViewerError;
+use crate::options::PrimitiveFilter;
+use crate::parser::{ApertureFunction, PrimitiveKind};
+use crate::shape::GerberData;
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+use web_sys::{WebGl2RenderingContext, WebGlBuffer};
+
+impl PrimitiveFilter {
+    /// Hidden flag (1 = hidden) of each of `count` elements of one primitive
+    /// type of `data`
+    ///
+    /// `kinds`, `d_codes` and `functions` are the per-element values of that
+    /// type and may be empty. Elements of unknown operation are only shown
+    /// while flashes, draws and regions all are; apertures of unknown size
+    /// pass the size limit.
+    pub(super) fn hidden(
+        &self,
+        data: &GerberData,
+        kinds: &[u8],
+        d_codes: &[u16],
+        functions: &[u8],
+        count: usize,
+    ) -> Vec<u8> {
+        let sizes = if self.min_aperture > 0.0 {
+            aperture_sizes(data)
+        } else {
+            HashMap::new()
+        };
+        (0..count)
+            .map(|i| {
+                let d_code = d_codes.get(i).copied();
+                let kind = match kinds.get(i).map(|&k| PrimitiveKind::from_u8(k)) {
+                    Some(PrimitiveKind::Unknown) | None if d_code == Some(0) => {
+                        PrimitiveKind::Region
+                    }
+                    Some(kind) => kind,
+                    None => PrimitiveKind::Unknown,
+                };
+                let shown_kind = match kind {
+                    PrimitiveKind::Region => self.regions,
+                    PrimitiveKind::Draw => self.draws,
+                    PrimitiveKind::Flash => self.flashes,
+                    PrimitiveKind::Unknown => self.regions && self.draws && self.flashes,
+                };
+                let large_enough = d_code
+                    .and_then(|code| sizes.get(&code))
+                    .is_none_or(|&size| size >= self.min_aperture);
+                let conductor = !self.conductors_only
+                    || functions.get(i) == Some(&(ApertureFunction::Conductor as u8));
+                u8::from(!(shown_kind && large_enough && conductor))
+            })
+            .collect()
+    }
+}
+
+/// Size of every aperture flashed or drawn in `data`: the larger side of a
+/// flashed aperture, the line width of a draw
+fn aperture_sizes(data: &GerberData) -> HashMap<u16, f32> {
+    let flashes = &data.flashes;
+    let traces = &data.traces;
+    let flashed = flashes
+        .d_code
+        .iter()
+        .zip(flashes.width.iter().zip(&flashes.height))
+        .map(|(&code, (&w, &h))| (code, w.max(h)));
+    let drawn = traces
+        .d_code
+        .iter()
+        .copied()
+        .zip(traces.width.iter().copied());
+    flashed.chain(drawn).collect()
+}
+
+impl Renderer {
+    /// Draw only the primitives of a layer that pass `filter`
+    ///
+    /// The layer's buffers are rebuilt from the stored geometry with a
+    /// one-byte hidden flag per element; nothing is parsed again.
+    pub fn set_filter(&mut self, layer_id: usize, filter: PrimitiveFilter) -> Result<(), JsValue> {
+        let layer = self
+            .layers
+            .get_mut(layer_id)
+            .and_then(Option::as_mut)
+            .ok_or_else(|| {
+                ViewerError::invalid_layer(
+                    format!("Invalid layer_id: {}", layer_id),
+                    layer_id as u32,
+                )
+            })?;
+        layer.filter = (filter != PrimitiveFilter::default()).then_some(filter);
+        self.reset_layer_buffers(layer_id)
+    }
+
+    /// Create and bind a hidden flag buffer (one byte per element)
+    ///
+    /// Returns None without a filter; the disabled attribute then reads as 0
+    /// and every element is drawn.
+    pub(super) fn create_hidden_buffer(
+        gl: &WebGl2RenderingContext,
+        hidden: Option<Vec<u8>>,
+        program: &ShaderProgram,
+        divisor: u32,
+    ) -> Result<Option<WebGlBuffer>, JsValue> {
+        let Some(hidden) = hidden else {
+            return Ok(None);
+        };
+        let buffer = gl
+            .create_buffer()
+            .ok_or_else(|| ViewerError::webgl("Failed to create hidden flag buffer"))?;
+        gl.bind_buffer(ARRAY_BUFFER, Some(&buffer));
+        unsafe {
+            let array = js_sys::Uint8Array::view(&hidden);
+            gl.buffer_data_with_array_buffer_view(ARRAY_BUFFER, &array, STATIC_DRAW);
+        }
+        let loc = program
+            .attributes
+            .get("hidden_instance")
+            .ok_or_else(|| ViewerError::webgl("Missing shader attribute: hidden_instance"))?;
+        gl.enable_vertex_attrib_array(*loc);
+        gl.vertex_attrib_pointer_with_i32(*loc, 1, UNSIGNED_BYTE, false, 0, 0);
+        gl.vertex_attrib_divisor(*loc, divisor);
+        Ok(Some(buffer))
+    }
+}
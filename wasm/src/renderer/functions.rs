@@ -0,0 +1,73 @@
+use super::shader::ShaderProgram;
+use super::Renderer;
+use crate::error::ViewerError;
+use crate::parser::APERTURE_FUNCTION_COUNT;
+use wasm_bindgen::prelude::*;
+
+// Default colors in ApertureFunction order: none gray, conductor copper,
+// via pad cyan, SMD pad green, BGA pad magenta, component pad yellow,
+// other violet, courtyard white, fiducial red, test pad orange
+const DEFAULT_FUNCTION_COLORS: [f32; APERTURE_FUNCTION_COUNT * 3] = [
+    0.55, 0.55, 0.55, 0.85, 0.55, 0.2, 0.2, 0.7, 0.9, 0.3, 0.85, 0.3, 0.9, 0.3, 0.8, 0.95, 0.85,
+    0.2, 0.6, 0.4, 0.9, 0.95, 0.95, 0.95, 1.0, 0.3, 0.25, 1.0, 0.6, 0.1,
+];
+
+/// Layer drawn in per-aperture-function colors
+pub struct FunctionColors {
+    pub layer_id: usize,
+    pub colors: [f32; APERTURE_FUNCTION_COUNT * 3], // RGB per ApertureFunction
+}
+
+impl Renderer {
+    /// Color the primitives of one layer by their X2 aperture function
+    ///
+    /// `colors` holds one RGB triple per ApertureFunction; empty uses defaults.
+    pub fn set_function_colors(&mut self, layer_id: usize, colors: &[f32]) -> Result<(), JsValue> {
+        self.get_layer(layer_id)?;
+
+        let colors = if colors.is_empty() {
+            DEFAULT_FUNCTION_COLORS
+        } else {
+            colors.try_into().map_err(|_| {
+                ViewerError::invalid_argument(format!(
+                    "Function colors must contain {} values ({} RGB colors)",
+                    APERTURE_FUNCTION_COUNT * 3,
+                    APERTURE_FUNCTION_COUNT
+                ))
+            })?
+        };
+
+        self.function_colors = Some(FunctionColors { layer_id, colors });
+        Ok(())
+    }
+
+    /// Return to drawing every layer in its single color
+    pub fn clear_function_colors(&mut self) {
+        self.function_colors = None;
+    }
+
+    /// Whether a layer is drawn in per-function colors
+    pub(super) fn colors_by_function(&self, layer_id: usize) -> bool {
+        self.function_colors
+            .as_ref()
+            .is_some_and(|f| f.layer_id == layer_id)
+    }
+
+    /// Set the function color uniforms of a primitive program for a layer
+    pub(super) fn set_function_uniforms(&self, program: &ShaderProgram, layer_id: usize) {
+        let function_colors = self
+            .function_colors
+            .as_ref()
+            .filter(|f| f.layer_id == layer_id);
+        self.gl.uniform1i(
+            program.uniforms.get("u_by_function"),
+            function_colors.is_some() as i32,
+        );
+        if let Some(function_colors) = function_colors {
+            self.gl.uniform3fv_with_f32_array(
+                program.uniforms.get("u_function_colors"),
+                &function_colors.colors,
+            );
+        }
+    }
+}
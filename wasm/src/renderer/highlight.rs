@@ -0,0 +1,39 @@
+use super::shader::ShaderProgram;
+use super::Renderer;
+use wasm_bindgen::prelude::*;
+
+/// Aperture whose features are emphasized in one layer
+pub struct ApertureHighlight {
+    pub layer_id: usize,
+    pub d_code: u16,
+}
+
+impl Renderer {
+    /// Emphasize the features a layer drew with aperture `d_code`
+    ///
+    /// Features of other apertures in that layer are drawn dimmed.
+    pub fn set_highlight(&mut self, layer_id: usize, d_code: u16) -> Result<(), JsValue> {
+        self.get_layer(layer_id)?;
+        self.highlight = Some(ApertureHighlight { layer_id, d_code });
+        Ok(())
+    }
+
+    /// Draw every feature at full strength again
+    pub fn clear_highlight(&mut self) {
+        self.highlight = None;
+    }
+
+    /// Set the highlighted D-code uniform of a primitive program for a layer
+    ///
+    /// Clear features are never dimmed, so they still cut the highlighted
+    /// features fully.
+    pub(super) fn set_highlight_uniform(&self, program: &ShaderProgram, layer_id: usize) {
+        let d_code = self
+            .highlight
+            .as_ref()
+            .filter(|h| h.layer_id == layer_id)
+            .map_or(-1.0, |h| h.d_code as f32);
+        self.gl
+            .uniform1f(program.uniforms.get("u_highlight_code"), d_code);
+    }
+}
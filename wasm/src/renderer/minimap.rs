@@ -0,0 +1,190 @@
+use super::buffer::Fbo;
+use super::shader::{BLEND, COLOR_BUFFER_BIT, FUNC_ADD, ONE, ONE_MINUS_SRC_ALPHA};
+use super::Renderer;
+use crate::shape::Boundary;
+use crate::store::LayerStore;
+use wasm_bindgen::prelude::*;
+use web_sys::WebGl2RenderingContext;
+
+// Minimap background (premultiplied dark gray)
+const MINIMAP_BACKGROUND: [f32; 4] = [0.05, 0.05, 0.05, 0.85];
+// Viewport frame color
+const FRAME_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+// Viewport frame line width in pixels
+const FRAME_WIDTH: i32 = 2;
+// Gap between the minimap and the canvas edges in pixels
+const MINIMAP_MARGIN: i32 = 8;
+// Smallest minimap side in pixels
+const MIN_MINIMAP_PIXELS: u32 = 16;
+// Border around the board inside the minimap, as a fraction of the board size
+const MINIMAP_PADDING: f32 = 0.05;
+
+/// Always-fit view of the whole board in a canvas corner
+pub struct Minimap {
+    pub corner: u32, // 0 bottom-left, 1 bottom-right, 2 top-left, 3 top-right
+    pub size: f32,   // Side length as a fraction of the shorter canvas side
+    image: Option<MinimapImage>,
+}
+
+/// Board image, redrawn only when what it shows changes
+struct MinimapImage {
+    fbo: Fbo,
+    key: MinimapKey,
+}
+
+#[derive(PartialEq)]
+struct MinimapKey {
+    layer_ids: Vec<u32>,
+    colors: Vec<f32>,
+    region: [f32; 4],
+    byte_size: usize, // Stored geometry size, changes when layers load more
+    pixels: u32,
+}
+
+impl Renderer {
+    /// Show the minimap in a canvas corner, or move and resize it
+    pub fn set_minimap(&mut self, corner: u32, size: f32) {
+        match &mut self.minimap {
+            Some(minimap) => {
+                minimap.corner = corner;
+                minimap.size = size;
+            }
+            None => {
+                self.minimap = Some(Minimap {
+                    corner,
+                    size,
+                    image: None,
+                })
+            }
+        }
+    }
+
+    /// Remove the minimap and free its image
+    pub fn clear_minimap(&mut self) {
+        if let Some(image) = self.minimap.take().and_then(|m| m.image) {
+            Self::delete_fbo(&self.gl, image.fbo);
+        }
+    }
+
+    /// Draw the minimap and the current viewport frame onto the canvas
+    pub(super) fn draw_minimap(
+        &mut self,
+        store: &LayerStore,
+        layer_ids: &[u32],
+        color_data: &[f32],
+        (width, height): (u32, u32),
+    ) -> Result<(), JsValue> {
+        let Some(mut minimap) = self.minimap.take() else {
+            return Ok(());
+        };
+        let result =
+            self.draw_minimap_into(&mut minimap, store, layer_ids, color_data, (width, height));
+        self.minimap = Some(minimap);
+
+        // Leave the canvas as the rest of the frame expects it
+        self.gl.disable(WebGl2RenderingContext::SCISSOR_TEST);
+        self.gl.disable(BLEND);
+        self.gl.viewport(0, 0, width as i32, height as i32);
+        result
+    }
+
+    fn draw_minimap_into(
+        &mut self,
+        minimap: &mut Minimap,
+        store: &LayerStore,
+        layer_ids: &[u32],
+        color_data: &[f32],
+        (width, height): (u32, u32),
+    ) -> Result<(), JsValue> {
+        if color_data.len() < layer_ids.len() * 3 {
+            return Ok(());
+        }
+        let side = ((width.min(height) as f32 * minimap.size) as u32).max(MIN_MINIMAP_PIXELS);
+        let left = match minimap.corner % 2 {
+            0 => MINIMAP_MARGIN,
+            _ => width as i32 - side as i32 - MINIMAP_MARGIN,
+        };
+        let bottom = match minimap.corner {
+            0 | 1 => MINIMAP_MARGIN,
+            _ => height as i32 - side as i32 - MINIMAP_MARGIN,
+        };
+
+        // Square window around the board so the minimap keeps the board aspect
+        let board = store.boundary();
+        let center = (
+            (board.min_x + board.max_x) / 2.0,
+            (board.min_y + board.max_y) / 2.0,
+        );
+        let half = ((board.max_x - board.min_x).max(board.max_y - board.min_y) / 2.0).max(0.5)
+            * (1.0 + MINIMAP_PADDING);
+        let region = Boundary::new(
+            center.0 - half,
+            center.0 + half,
+            center.1 - half,
+            center.1 + half,
+        );
+
+        // Redraw the board image only when layers, colors or size changed
+        let key = MinimapKey {
+            layer_ids: layer_ids.to_vec(),
+            colors: color_data[..layer_ids.len() * 3].to_vec(),
+            region: [region.min_x, region.min_y, region.max_x, region.max_y],
+            byte_size: store.byte_size(),
+            pixels: side,
+        };
+        if minimap.image.as_ref().is_none_or(|image| image.key != key) {
+            if let Some(image) = minimap.image.take() {
+                Self::delete_fbo(&self.gl, image.fbo);
+            }
+            let fbo = Self::create_fbo(&self.gl, side, side)?;
+            let drawn = self.draw_region(store, &region, (side, side), layer_ids, color_data, &fbo);
+            minimap.image = Some(MinimapImage { fbo, key });
+            drawn?;
+        }
+        let Some(image) = &minimap.image else {
+            return Ok(());
+        };
+
+        // Background, then the board image over it
+        self.bind_output();
+        self.gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
+        self.clear_rect(MINIMAP_BACKGROUND, left, bottom, side as i32, side as i32);
+        self.gl.viewport(left, bottom, side as i32, side as i32);
+        self.gl.enable(BLEND);
+        self.gl.blend_func(ONE, ONE_MINUS_SRC_ALPHA);
+        self.gl.blend_equation(FUNC_ADD);
+        self.draw_fbo_texture(&image.fbo.texture, &[1.0, 1.0, 1.0, 1.0], true)?;
+
+        // Visible board window from the camera, in minimap pixels
+        let transform = self.camera.get_transform_matrix(width, height);
+        let to_pixels = |world: f32, min: f32, origin: i32| {
+            origin as f32 + (world - min) / (2.0 * half) * side as f32
+        };
+        let x0 = to_pixels((-1.0 - transform[6]) / transform[0], region.min_x, left);
+        let x1 = to_pixels((1.0 - transform[6]) / transform[0], region.min_x, left);
+        let y0 = to_pixels((-1.0 - transform[7]) / transform[4], region.min_y, bottom);
+        let y1 = to_pixels((1.0 - transform[7]) / transform[4], region.min_y, bottom);
+
+        // Frame edges clipped to the minimap
+        let clamp_x = |x: f32| (x.round() as i32).clamp(left, left + side as i32);
+        let clamp_y = |y: f32| (y.round() as i32).clamp(bottom, bottom + side as i32);
+        let (x0, x1, y0, y1) = (clamp_x(x0), clamp_x(x1), clamp_y(y0), clamp_y(y1));
+        self.gl.scissor(left, bottom, side as i32, side as i32);
+        for (x, y, w, h) in [
+            (x0, y0, x1 - x0, FRAME_WIDTH),
+            (x0, y1 - FRAME_WIDTH, x1 - x0, FRAME_WIDTH),
+            (x0, y0, FRAME_WIDTH, y1 - y0),
+            (x1 - FRAME_WIDTH, y0, FRAME_WIDTH, y1 - y0),
+        ] {
+            self.clear_rect(FRAME_COLOR, x, y, w.max(1), h.max(1));
+        }
+        Ok(())
+    }
+
+    /// Fill a canvas rectangle with a color (scissor test enabled)
+    fn clear_rect(&self, color: [f32; 4], x: i32, y: i32, width: i32, height: i32) {
+        self.gl.scissor(x, y, width, height);
+        self.gl.clear_color(color[0], color[1], color[2], color[3]);
+        self.gl.clear(COLOR_BUFFER_BIT);
+    }
+}
@@ -0,0 +1,757 @@
+use super::shader::{
+    ARRAY_BUFFER, BLEND, FLOAT, FUNC_ADD, LINES, ONE, ONE_MINUS_SRC_ALPHA, TRIANGLES,
+};
+use super::Renderer;
+use crate::analysis::{
+    ComponentSide, Connectivity, CopperDensity, CourtyardLines, CrossSection, DrcMarkers,
+};
+use crate::error::ViewerError;
+use crate::parser::geometry::{triangulate_outline, Primitive};
+use crate::simd::interleave_xy;
+use crate::stackup::StackupKind;
+use wasm_bindgen::prelude::*;
+use web_sys::{WebGl2RenderingContext, WebGlBuffer, WebGlTexture, WebGlVertexArrayObject};
+
+// Opacity of the density heatmap drawn over the layers
+const HEATMAP_OPACITY: f32 = 0.45;
+// DRC marker color (magenta, stands out against the layer palette)
+const MARKER_COLOR: [f32; 4] = [1.0, 0.0, 1.0, 0.9];
+// Marker ring thickness as a fraction of its radius
+const MARKER_THICKNESS: f32 = 0.2;
+// Cross-section strip placement in normalized device coordinates
+const STRIP_LEFT: f32 = -0.95;
+const STRIP_RIGHT: f32 = 0.95;
+const STRIP_TOP: f32 = -0.55;
+const STRIP_BOTTOM: f32 = -0.95;
+// Thin layers are drawn at least this fraction of the strip height
+const STRIP_MIN_LAYER_FRACTION: f32 = 0.04;
+// Strip background and cut line colors (premultiplied)
+const STRIP_BACKGROUND: [f32; 4] = [0.08, 0.08, 0.08, 0.85];
+const CUT_LINE_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+// Cut line width as a fraction of its length
+const CUT_LINE_WIDTH: f32 = 0.004;
+// Highlighted net color (premultiplied translucent yellow)
+const NET_COLOR: [f32; 4] = [0.6, 0.6, 0.0, 0.6];
+// Via stitching colors: hits connecting both layers bright, the rest dimmed
+const VIA_CONNECTED_COLOR: [f32; 4] = [0.2, 1.0, 0.3, 1.0];
+const VIA_OTHER_COLOR: [f32; 4] = [0.5, 0.5, 0.5, 0.35];
+// Fiducial marker color (bright green) and ring thickness as a fraction of its radius
+const FIDUCIAL_COLOR: [f32; 4] = [0.2, 1.0, 0.4, 1.0];
+const FIDUCIAL_THICKNESS: f32 = 0.12;
+// Test point marker color (orange) and ring thickness as a fraction of its radius
+const TEST_POINT_COLOR: [f32; 4] = [1.0, 0.55, 0.1, 1.0];
+const TEST_POINT_THICKNESS: f32 = 0.12;
+// Inferred drill preview color (premultiplied translucent sky blue), drawn as filled holes
+const INFERRED_DRILL_COLOR: [f32; 4] = [0.0, 0.45, 0.6, 0.6];
+// Courtyard colors per ComponentSide: top light yellow, bottom light blue
+const COURTYARD_COLORS: [[f32; 4]; 2] = [[1.0, 0.95, 0.6, 1.0], [0.6, 0.8, 1.0, 1.0]];
+// Courtyard dash and gap length on screen
+const COURTYARD_DASH_PIXELS: f32 = 6.0;
+
+/// Copper density heatmap uploaded as a coarse texture
+pub struct Heatmap {
+    pub layer_id: usize, // Layer the density was computed for
+    pub texture: WebGlTexture,
+    pub bounds: [f32; 4], // min_x, min_y, max_x, max_y
+}
+
+/// DRC marker rings uploaded as instance buffers
+pub struct MarkerOverlay {
+    pub vao: WebGlVertexArrayObject,
+    pub center_buffer: WebGlBuffer,
+    pub radius_buffer: WebGlBuffer,
+    pub count: usize,
+}
+
+/// Cross-section strip and its cut line uploaded as plain triangles
+pub struct CrossSectionOverlay {
+    pub vao: WebGlVertexArrayObject,
+    pub buffer: WebGlBuffer,
+    pub strip_groups: Vec<([f32; 4], i32, i32)>, // Color, first vertex, vertex count (NDC)
+    pub cut_line: (i32, i32),                    // First vertex, vertex count (world space)
+}
+
+/// Copper of one net uploaded as plain triangles
+pub struct NetOverlay {
+    pub layer_ids: Vec<usize>, // Copper layers the net was computed for
+    pub vao: WebGlVertexArrayObject,
+    pub buffer: WebGlBuffer,
+    pub count: i32, // Vertex count
+}
+
+/// Courtyard outlines of one component side uploaded as dashed lines
+pub struct CourtyardOverlay {
+    pub layer_ids: Vec<usize>, // Component layers the outlines came from
+    pub vao: WebGlVertexArrayObject,
+    pub position_buffer: WebGlBuffer,
+    pub distance_buffer: WebGlBuffer,
+    pub count: i32, // Vertex count
+}
+
+/// Drill hits of a via stitching map, split by whether they connect both layers
+pub struct ViaOverlay {
+    pub layer_ids: [usize; 2], // Copper layers the map was computed for
+    pub connected: Option<MarkerOverlay>,
+    pub other: Option<MarkerOverlay>,
+}
+
+impl Renderer {
+    /// Upload a copper density grid and show it as a translucent overlay
+    pub fn set_heatmap(&mut self, layer_id: usize, density: &CopperDensity) -> Result<(), JsValue> {
+        let max_size = self
+            .gl
+            .get_parameter(WebGl2RenderingContext::MAX_TEXTURE_SIZE)?
+            .as_f64()
+            .unwrap_or(4096.0) as u32;
+        if density.columns > max_size || density.rows > max_size {
+            return Err(ViewerError::invalid_argument(format!(
+                "Heatmap grid must be at most {} cells per side",
+                max_size
+            ))
+            .into());
+        }
+        self.clear_heatmap();
+
+        // One texel per grid cell, density stored in the red channel
+        let mut pixels = Vec::with_capacity(density.values.len() * 4);
+        for value in &density.values {
+            pixels.push((value.clamp(0.0, 1.0) * 255.0).round() as u8);
+            pixels.push(0);
+            pixels.push(0);
+            pixels.push(255);
+        }
+
+        let texture = self
+            .gl
+            .create_texture()
+            .ok_or_else(|| ViewerError::webgl("Failed to create heatmap texture"))?;
+        self.gl
+            .bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&texture));
+        self.gl
+            .tex_image_2d_with_i32_and_i32_and_i32_and_format_and_type_and_opt_u8_array(
+                WebGl2RenderingContext::TEXTURE_2D,
+                0,
+                WebGl2RenderingContext::RGBA as i32,
+                density.columns as i32,
+                density.rows as i32,
+                0,
+                WebGl2RenderingContext::RGBA,
+                WebGl2RenderingContext::UNSIGNED_BYTE,
+                Some(&pixels),
+            )?;
+        // Nearest filtering keeps the grid cells crisp
+        for (param, value) in [
+            (
+                WebGl2RenderingContext::TEXTURE_MIN_FILTER,
+                WebGl2RenderingContext::NEAREST,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_MAG_FILTER,
+                WebGl2RenderingContext::NEAREST,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_S,
+                WebGl2RenderingContext::CLAMP_TO_EDGE,
+            ),
+            (
+                WebGl2RenderingContext::TEXTURE_WRAP_T,
+                WebGl2RenderingContext::CLAMP_TO_EDGE,
+            ),
+        ] {
+            self.gl
+                .tex_parameteri(WebGl2RenderingContext::TEXTURE_2D, param, value as i32);
+        }
+        self.gl
+            .bind_texture(WebGl2RenderingContext::TEXTURE_2D, None);
+
+        self.heatmap = Some(Heatmap {
+            layer_id,
+            texture,
+            bounds: [density.min_x, density.min_y, density.max_x, density.max_y],
+        });
+        Ok(())
+    }
+
+    /// Remove the heatmap overlay and free its texture
+    pub fn clear_heatmap(&mut self) {
+        if let Some(heatmap) = self.heatmap.take() {
+            self.gl.delete_texture(Some(&heatmap.texture));
+        }
+    }
+
+    /// Upload DRC marker rings and show them on top of the layers
+    pub fn set_markers(&mut self, markers: &DrcMarkers) -> Result<(), JsValue> {
+        self.clear_markers();
+        if markers.x.is_empty() {
+            return Ok(());
+        }
+        self.markers = Some(self.upload_rings(&markers.x, &markers.y, &markers.radius)?);
+        Ok(())
+    }
+
+    /// Remove the DRC markers and free their buffers
+    pub fn clear_markers(&mut self) {
+        if let Some(markers) = self.markers.take() {
+            self.delete_rings(markers);
+        }
+    }
+
+    /// Upload fiducial marker rings and show them on top of the layers
+    pub fn set_fiducials(&mut self, markers: &DrcMarkers) -> Result<(), JsValue> {
+        self.clear_fiducials();
+        if markers.x.is_empty() {
+            return Ok(());
+        }
+        self.fiducials = Some(self.upload_rings(&markers.x, &markers.y, &markers.radius)?);
+        Ok(())
+    }
+
+    /// Remove the fiducial markers and free their buffers
+    pub fn clear_fiducials(&mut self) {
+        if let Some(fiducials) = self.fiducials.take() {
+            self.delete_rings(fiducials);
+        }
+    }
+
+    /// Upload test point marker rings and show them on top of the layers
+    pub fn set_test_points(&mut self, markers: &DrcMarkers) -> Result<(), JsValue> {
+        self.clear_test_points();
+        if markers.x.is_empty() {
+            return Ok(());
+        }
+        self.test_points = Some(self.upload_rings(&markers.x, &markers.y, &markers.radius)?);
+        Ok(())
+    }
+
+    /// Remove the test point markers and free their buffers
+    pub fn clear_test_points(&mut self) {
+        if let Some(test_points) = self.test_points.take() {
+            self.delete_rings(test_points);
+        }
+    }
+
+    /// Upload inferred drill hits and show them as holes on top of the layers
+    pub fn set_inferred_drills(&mut self, markers: &DrcMarkers) -> Result<(), JsValue> {
+        self.clear_inferred_drills();
+        if markers.x.is_empty() {
+            return Ok(());
+        }
+        self.inferred_drills = Some(self.upload_rings(&markers.x, &markers.y, &markers.radius)?);
+        Ok(())
+    }
+
+    /// Remove the inferred drill preview and free its buffers
+    pub fn clear_inferred_drills(&mut self) {
+        if let Some(drills) = self.inferred_drills.take() {
+            self.delete_rings(drills);
+        }
+    }
+
+    /// Upload the drill hits of a via stitching map and show them on top of the layers
+    ///
+    /// `connected` flags the hits landing on copper of both layers.
+    pub fn set_via_stitching(
+        &mut self,
+        layer_ids: [usize; 2],
+        connectivity: &Connectivity,
+        connected: &[bool],
+    ) -> Result<(), JsValue> {
+        self.clear_via_stitching();
+
+        let mut groups = [
+            (Vec::new(), Vec::new(), Vec::new()),
+            (Vec::new(), Vec::new(), Vec::new()),
+        ];
+        for (hit, &connects) in connected.iter().enumerate() {
+            let (x, y, radius) = &mut groups[connects as usize];
+            x.push(connectivity.hit_x[hit]);
+            y.push(connectivity.hit_y[hit]);
+            radius.push(connectivity.hit_radius[hit]);
+        }
+        let mut overlays = Vec::with_capacity(2);
+        for (x, y, radius) in &groups {
+            overlays.push(if x.is_empty() {
+                None
+            } else {
+                Some(self.upload_rings(x, y, radius)?)
+            });
+        }
+        let connected = overlays.pop().flatten();
+        let other = overlays.pop().flatten();
+
+        self.vias = Some(ViaOverlay {
+            layer_ids,
+            connected,
+            other,
+        });
+        Ok(())
+    }
+
+    /// Remove the via stitching map and free its buffers
+    pub fn clear_via_stitching(&mut self) {
+        if let Some(vias) = self.vias.take() {
+            for rings in [vias.connected, vias.other].into_iter().flatten() {
+                self.delete_rings(rings);
+            }
+        }
+    }
+
+    /// Upload circles as instance buffers for the marker program
+    fn upload_rings(&self, x: &[f32], y: &[f32], radius: &[f32]) -> Result<MarkerOverlay, JsValue> {
+        let program = &self.programs.marker;
+        let vao = self
+            .gl
+            .create_vertex_array()
+            .ok_or_else(|| ViewerError::webgl("Failed to create VAO"))?;
+        self.gl.bind_vertex_array(Some(&vao));
+
+        // Bind shared quad buffer for position attribute
+        self.gl.bind_buffer(ARRAY_BUFFER, Some(&self.quad_buffer));
+        let position_loc = *program.attributes.get("position").unwrap();
+        self.gl.enable_vertex_attrib_array(position_loc);
+        self.gl
+            .vertex_attrib_pointer_with_i32(position_loc, 2, FLOAT, false, 0, 0);
+
+        let centers = interleave_xy(x, y);
+        let center_buffer =
+            Self::create_instance_buffer_2d(&self.gl, &centers, program, "center_instance", 1)?;
+        let radius_buffer =
+            Self::create_instance_buffer(&self.gl, radius, program, "radius_instance", 1)?;
+
+        // Unbind VAO
+        self.gl.bind_vertex_array(None);
+
+        Ok(MarkerOverlay {
+            vao,
+            center_buffer,
+            radius_buffer,
+            count: x.len(),
+        })
+    }
+
+    /// Free the buffers of uploaded circles
+    fn delete_rings(&self, rings: MarkerOverlay) {
+        self.gl.delete_vertex_array(Some(&rings.vao));
+        self.gl.delete_buffer(Some(&rings.center_buffer));
+        self.gl.delete_buffer(Some(&rings.radius_buffer));
+    }
+
+    /// Upload a cross-section and show it as a strip along the bottom of the canvas
+    ///
+    /// Layer bands are stretched to fill the strip height; the cut line is drawn
+    /// on the board in world space.
+    pub fn set_cross_section(&mut self, section: &CrossSection) -> Result<(), JsValue> {
+        self.clear_cross_section();
+
+        // Display band of each stackup layer, with a minimum height so copper stays visible
+        let total = section.total_thickness();
+        let heights: Vec<f32> = section
+            .layer_top
+            .iter()
+            .zip(&section.layer_bottom)
+            .map(|(top, bottom)| (bottom - top).max(total * STRIP_MIN_LAYER_FRACTION))
+            .collect();
+        let display_total: f32 = heights.iter().sum();
+        let mut band_top = Vec::with_capacity(heights.len());
+        let mut y = STRIP_TOP;
+        for height in &heights {
+            let band = height / display_total.max(f32::EPSILON) * (STRIP_TOP - STRIP_BOTTOM);
+            band_top.push((y, y - band));
+            y -= band;
+        }
+
+        let mut vertices = Vec::new();
+        let mut strip_groups = Vec::new();
+        push_rect(
+            &mut vertices,
+            [STRIP_LEFT, STRIP_BOTTOM],
+            [STRIP_RIGHT, STRIP_TOP],
+        );
+        strip_groups.push((STRIP_BACKGROUND, 0, 6));
+
+        // One draw per material kind
+        let length = section.length().max(f32::EPSILON);
+        let to_strip_x = |t: f32| STRIP_LEFT + t / length * (STRIP_RIGHT - STRIP_LEFT);
+        for kind in [
+            StackupKind::Dielectric,
+            StackupKind::Copper,
+            StackupKind::SolderMask,
+            StackupKind::Paste,
+            StackupKind::Legend,
+            StackupKind::Other,
+        ] {
+            let first = (vertices.len() / 2) as i32;
+            for i in 0..section.segment_layer.len() {
+                let layer = section.segment_layer[i] as usize;
+                if section.layer_kinds[layer] != kind {
+                    continue;
+                }
+                let (top, bottom) = band_top[layer];
+                push_rect(
+                    &mut vertices,
+                    [to_strip_x(section.segment_start[i]), bottom],
+                    [to_strip_x(section.segment_end[i]), top],
+                );
+            }
+            let count = (vertices.len() / 2) as i32 - first;
+            if count > 0 {
+                // Strip bands are drawn opaque
+                let [r, g, b, _] = kind.color();
+                strip_groups.push(([r, g, b, 1.0], first, count));
+            }
+        }
+
+        // Cut line as a thin quad around the line
+        let first = (vertices.len() / 2) as i32;
+        let (dx, dy) = (
+            section.end_x - section.start_x,
+            section.end_y - section.start_y,
+        );
+        let half_width = (length * CUT_LINE_WIDTH) / 2.0;
+        let (nx, ny) = (-dy / length * half_width, dx / length * half_width);
+        let corners = [
+            [section.start_x + nx, section.start_y + ny],
+            [section.start_x - nx, section.start_y - ny],
+            [section.end_x - nx, section.end_y - ny],
+            [section.end_x + nx, section.end_y + ny],
+        ];
+        for index in [0, 1, 2, 0, 2, 3] {
+            vertices.extend_from_slice(&corners[index]);
+        }
+
+        let program = &self.programs.triangle;
+        let vao = self
+            .gl
+            .create_vertex_array()
+            .ok_or_else(|| ViewerError::webgl("Failed to create VAO"))?;
+        self.gl.bind_vertex_array(Some(&vao));
+        let buffer = Self::create_instance_buffer_2d(&self.gl, &vertices, program, "position", 0)?;
+        self.gl.bind_vertex_array(None);
+
+        self.cross_section = Some(CrossSectionOverlay {
+            vao,
+            buffer,
+            strip_groups,
+            cut_line: (first, 6),
+        });
+        Ok(())
+    }
+
+    /// Remove the cross-section strip and free its buffers
+    pub fn clear_cross_section(&mut self) {
+        if let Some(section) = self.cross_section.take() {
+            self.gl.delete_vertex_array(Some(&section.vao));
+            self.gl.delete_buffer(Some(&section.buffer));
+        }
+    }
+
+    /// Upload the copper of one net and show it highlighted on top of the layers
+    pub fn set_net(&mut self, connectivity: &Connectivity, net: u32) -> Result<(), JsValue> {
+        self.clear_net();
+
+        let mut vertices = Vec::new();
+        for (features, nets) in connectivity.features.iter().zip(&connectivity.nets) {
+            for (feature, _) in features.iter().zip(nets).filter(|(_, &n)| n == net) {
+                for ring in &feature.rings {
+                    for triangle in triangulate_outline(ring, 1.0).unwrap_or_default() {
+                        if let Primitive::Triangle {
+                            vertices: corners, ..
+                        } = triangle
+                        {
+                            vertices.extend(corners.iter().flatten());
+                        }
+                    }
+                }
+            }
+        }
+        if vertices.is_empty() {
+            return Ok(());
+        }
+
+        let program = &self.programs.triangle;
+        let vao = self
+            .gl
+            .create_vertex_array()
+            .ok_or_else(|| ViewerError::webgl("Failed to create VAO"))?;
+        self.gl.bind_vertex_array(Some(&vao));
+        let buffer = Self::create_instance_buffer_2d(&self.gl, &vertices, program, "position", 0)?;
+        self.gl.bind_vertex_array(None);
+
+        self.net = Some(NetOverlay {
+            layer_ids: connectivity
+                .layer_ids
+                .iter()
+                .map(|&id| id as usize)
+                .collect(),
+            vao,
+            buffer,
+            count: (vertices.len() / 2) as i32,
+        });
+        Ok(())
+    }
+
+    /// Remove the net highlight and free its buffer
+    pub fn clear_net(&mut self) {
+        if let Some(net) = self.net.take() {
+            self.gl.delete_vertex_array(Some(&net.vao));
+            self.gl.delete_buffer(Some(&net.buffer));
+        }
+    }
+
+    /// Upload the courtyard outlines of one component side and show them dashed
+    pub fn set_courtyards(
+        &mut self,
+        side: ComponentSide,
+        layer_ids: &[usize],
+        lines: &CourtyardLines,
+    ) -> Result<(), JsValue> {
+        self.clear_courtyards(side);
+        if lines.is_empty() {
+            return Ok(());
+        }
+
+        let program = &self.programs.dash;
+        let vao = self
+            .gl
+            .create_vertex_array()
+            .ok_or_else(|| ViewerError::webgl("Failed to create VAO"))?;
+        self.gl.bind_vertex_array(Some(&vao));
+        let position_buffer =
+            Self::create_instance_buffer_2d(&self.gl, &lines.vertices, program, "position", 0)?;
+        let distance_buffer =
+            Self::create_instance_buffer(&self.gl, &lines.distances, program, "distance", 0)?;
+        self.gl.bind_vertex_array(None);
+
+        self.courtyards[side as usize] = Some(CourtyardOverlay {
+            layer_ids: layer_ids.to_vec(),
+            vao,
+            position_buffer,
+            distance_buffer,
+            count: lines.distances.len() as i32,
+        });
+        Ok(())
+    }
+
+    /// Hide the courtyards of one component side and free their buffers
+    pub fn clear_courtyards(&mut self, side: ComponentSide) {
+        if let Some(courtyards) = self.courtyards[side as usize].take() {
+            self.gl.delete_vertex_array(Some(&courtyards.vao));
+            self.gl.delete_buffer(Some(&courtyards.position_buffer));
+            self.gl.delete_buffer(Some(&courtyards.distance_buffer));
+        }
+    }
+
+    /// Draw all overlays on top of the composited layers (canvas framebuffer bound)
+    pub(super) fn draw_overlays(&self, transform: &[f32; 9]) -> Result<(), JsValue> {
+        if self.heatmap.is_none()
+            && self.markers.is_none()
+            && self.cross_section.is_none()
+            && self.net.is_none()
+            && self.vias.is_none()
+            && self.fiducials.is_none()
+            && self.test_points.is_none()
+            && self.inferred_drills.is_none()
+            && self.courtyards.iter().all(Option::is_none)
+            && self.dimensions.is_none()
+        {
+            return Ok(());
+        }
+
+        self.gl.enable(BLEND);
+        self.gl.blend_func(ONE, ONE_MINUS_SRC_ALPHA);
+        self.gl.blend_equation(FUNC_ADD);
+        if let Some(heatmap) = &self.heatmap {
+            self.draw_heatmap(heatmap, transform);
+        }
+        if let Some(net) = &self.net {
+            self.draw_net(net, transform);
+        }
+        for (courtyards, color) in self.courtyards.iter().zip(COURTYARD_COLORS) {
+            if let Some(courtyards) = courtyards {
+                self.draw_courtyards(courtyards, color, transform)?;
+            }
+        }
+        if let Some(drills) = &self.inferred_drills {
+            self.draw_rings(drills, INFERRED_DRILL_COLOR, 1.0, transform);
+        }
+        if let Some(vias) = &self.vias {
+            // Dimmed hits first so connecting vias stay on top
+            if let Some(other) = &vias.other {
+                self.draw_rings(other, VIA_OTHER_COLOR, 1.0, transform);
+            }
+            if let Some(connected) = &vias.connected {
+                self.draw_rings(connected, VIA_CONNECTED_COLOR, 1.0, transform);
+            }
+        }
+        if let Some(fiducials) = &self.fiducials {
+            self.draw_rings(fiducials, FIDUCIAL_COLOR, FIDUCIAL_THICKNESS, transform);
+        }
+        if let Some(test_points) = &self.test_points {
+            self.draw_rings(
+                test_points,
+                TEST_POINT_COLOR,
+                TEST_POINT_THICKNESS,
+                transform,
+            );
+        }
+        if let Some(markers) = &self.markers {
+            self.draw_rings(markers, MARKER_COLOR, MARKER_THICKNESS, transform);
+        }
+        if let Some(dimensions) = &self.dimensions {
+            self.draw_dimensions(dimensions, transform);
+        }
+        if let Some(section) = &self.cross_section {
+            self.draw_cross_section(section, transform);
+        }
+        self.gl.disable(BLEND);
+        Ok(())
+    }
+
+    /// Draw the heatmap texture stretched over its world-space bounds
+    fn draw_heatmap(&self, heatmap: &Heatmap, transform: &[f32; 9]) {
+        let program = &self.programs.heatmap;
+        self.gl.use_program(Some(&program.program));
+
+        self.gl.bind_buffer(ARRAY_BUFFER, Some(&self.quad_buffer));
+        let pos_loc = *program.attributes.get("position").unwrap();
+        self.gl.enable_vertex_attrib_array(pos_loc);
+        self.gl
+            .vertex_attrib_pointer_with_i32(pos_loc, 2, FLOAT, false, 0, 0);
+
+        self.gl.active_texture(WebGl2RenderingContext::TEXTURE0);
+        self.gl
+            .bind_texture(WebGl2RenderingContext::TEXTURE_2D, Some(&heatmap.texture));
+        self.gl.uniform1i(program.uniforms.get("u_texture"), 0);
+        self.gl.uniform_matrix3fv_with_f32_array(
+            program.uniforms.get("transform"),
+            false,
+            transform,
+        );
+        self.gl
+            .uniform4fv_with_f32_array(program.uniforms.get("u_bounds"), &heatmap.bounds);
+        self.gl
+            .uniform1f(program.uniforms.get("u_opacity"), HEATMAP_OPACITY);
+
+        self.gl.draw_arrays(TRIANGLES, 0, 6);
+    }
+
+    /// Draw the highlighted net in one color
+    fn draw_net(&self, net: &NetOverlay, transform: &[f32; 9]) {
+        let program = &self.programs.triangle;
+        self.gl.use_program(Some(&program.program));
+        self.gl.uniform_matrix3fv_with_f32_array(
+            program.uniforms.get("transform"),
+            false,
+            transform,
+        );
+        // Layer draws may leave function colors or a highlight on
+        self.gl.uniform1i(program.uniforms.get("u_by_function"), 0);
+        self.gl
+            .uniform1f(program.uniforms.get("u_highlight_code"), -1.0);
+        self.gl
+            .uniform4fv_with_f32_array(program.uniforms.get("color"), &NET_COLOR);
+
+        self.gl.bind_vertex_array(Some(&net.vao));
+        self.gl.draw_arrays(TRIANGLES, 0, net.count);
+        self.gl.bind_vertex_array(None);
+    }
+
+    /// Draw courtyard outlines as one-pixel dashed lines
+    fn draw_courtyards(
+        &self,
+        courtyards: &CourtyardOverlay,
+        color: [f32; 4],
+        transform: &[f32; 9],
+    ) -> Result<(), JsValue> {
+        // Dashes keep their screen length at every zoom
+        let (width, _) = self.get_canvas_size()?;
+        let dash_length = COURTYARD_DASH_PIXELS * 2.0 / (transform[0] * width as f32);
+
+        let program = &self.programs.dash;
+        self.gl.use_program(Some(&program.program));
+        self.gl.uniform_matrix3fv_with_f32_array(
+            program.uniforms.get("transform"),
+            false,
+            transform,
+        );
+        self.gl
+            .uniform4fv_with_f32_array(program.uniforms.get("color"), &color);
+        self.gl
+            .uniform1f(program.uniforms.get("u_dash_length"), dash_length);
+
+        self.gl.bind_vertex_array(Some(&courtyards.vao));
+        self.gl.draw_arrays(LINES, 0, courtyards.count);
+        self.gl.bind_vertex_array(None);
+        Ok(())
+    }
+
+    /// Draw uploaded circles as rings of `thickness` (fraction of the radius, 1.0 = filled)
+    fn draw_rings(
+        &self,
+        rings: &MarkerOverlay,
+        color: [f32; 4],
+        thickness: f32,
+        transform: &[f32; 9],
+    ) {
+        let program = &self.programs.marker;
+        self.gl.use_program(Some(&program.program));
+        self.gl.uniform_matrix3fv_with_f32_array(
+            program.uniforms.get("transform"),
+            false,
+            transform,
+        );
+        self.gl
+            .uniform4fv_with_f32_array(program.uniforms.get("color"), &color);
+        self.gl
+            .uniform1f(program.uniforms.get("u_thickness"), thickness);
+
+        self.gl.bind_vertex_array(Some(&rings.vao));
+        self.gl
+            .draw_arrays_instanced(TRIANGLES, 0, 6, rings.count as i32);
+        self.gl.bind_vertex_array(None);
+    }
+
+    /// Draw the cut line on the board, then the strip in screen space
+    fn draw_cross_section(&self, section: &CrossSectionOverlay, transform: &[f32; 9]) {
+        let program = &self.programs.triangle;
+        self.gl.use_program(Some(&program.program));
+        self.gl.bind_vertex_array(Some(&section.vao));
+
+        self.gl.uniform_matrix3fv_with_f32_array(
+            program.uniforms.get("transform"),
+            false,
+            transform,
+        );
+        // Layer draws may leave function colors or a highlight on
+        self.gl.uniform1i(program.uniforms.get("u_by_function"), 0);
+        self.gl
+            .uniform1f(program.uniforms.get("u_highlight_code"), -1.0);
+        self.gl
+            .uniform4fv_with_f32_array(program.uniforms.get("color"), &CUT_LINE_COLOR);
+        self.gl
+            .draw_arrays(TRIANGLES, section.cut_line.0, section.cut_line.1);
+
+        // Strip vertices are already in normalized device coordinates
+        let identity = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+        self.gl.uniform_matrix3fv_with_f32_array(
+            program.uniforms.get("transform"),
+            false,
+            &identity,
+        );
+        for (color, first, count) in &section.strip_groups {
+            self.gl
+                .uniform4fv_with_f32_array(program.uniforms.get("color"), color);
+            self.gl.draw_arrays(TRIANGLES, *first, *count);
+        }
+
+        self.gl.bind_vertex_array(None);
+    }
+}
+
+/// Append two triangles covering an axis-aligned rectangle
+fn push_rect(vertices: &mut Vec<f32>, min: [f32; 2], max: [f32; 2]) {
+    vertices.extend_from_slice(&[
+        min[0], min[1], max[0], min[1], min[0], max[1], min[0], max[1], max[0], min[1], max[0],
+        max[1],
+    ]);
+}
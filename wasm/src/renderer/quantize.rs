@@ -0,0 +1,119 @@
+// Largest rounding error (mm) accepted for 16-bit instance data, the 1 um
+// resolution of 4.6 format files; sublayers spanning more than about 130 mm
+// keep f32 buffers
+const MAX_QUANTIZE_ERROR: f32 = 0.001;
+// Sublayers with fewer instances keep f32 buffers, the saving is not worth it
+const MIN_QUANTIZED_INSTANCES: usize = 256;
+
+/// Range of 16-bit normalized instance values: value = offset + unorm * scale
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quantization {
+    pub offset: f32,
+    pub scale: f32,
+}
+
+impl Quantization {
+    /// Range covering all `values`, or None when 16 bits cannot hold them
+    /// within MAX_QUANTIZE_ERROR
+    fn fit<'a>(values: impl Iterator<Item = &'a f32>) -> Option<Quantization> {
+        let (mut min, mut max) = (f32::INFINITY, f32::NEG_INFINITY);
+        for &value in values {
+            if !value.is_finite() {
+                return None;
+            }
+            min = min.min(value);
+            max = max.max(value);
+        }
+        if min > max {
+            return Some(Quantization {
+                offset: 0.0,
+                scale: 0.0,
+            });
+        }
+        let scale = (max - min) / u16::MAX as f32;
+        (scale / 2.0 <= MAX_QUANTIZE_ERROR).then_some(Quantization { offset: min, scale })
+    }
+
+    fn encode(&self, value: f32) -> u16 {
+        if self.scale > 0.0 {
+            ((value - self.offset) / self.scale)
+                .round()
+                .clamp(0.0, u16::MAX as f32) as u16
+        } else {
+            0
+        }
+    }
+}
+
+/// Circle instances packed as 16-bit normalized values
+///
+/// Centers and hole centers share one range per axis, radii and hole radii
+/// another, so a dense drill or via layer uploads half the bytes of f32
+/// buffers. The shader maps the values back with the `u_center_range` and
+/// `u_radius_range` uniforms.
+pub struct QuantizedCircles {
+    pub x: Quantization,
+    pub y: Quantization,
+    pub radius: Quantization,
+    pub centers: Vec<u16>,      // Interleaved x, y
+    pub hole_centers: Vec<u16>, // Interleaved x, y
+    pub radii: Vec<u16>,
+    pub hole_radii: Vec<u16>,
+}
+
+impl QuantizedCircles {
+    /// Pack circle instances, or None when there are too few of them or
+    /// their range needs f32 precision
+    ///
+    /// Hole centers of circles without a hole are ignored for the ranges.
+    pub fn new(
+        x: &[f32],
+        y: &[f32],
+        radius: &[f32],
+        hole_x: &[f32],
+        hole_y: &[f32],
+        hole_radius: &[f32],
+    ) -> Option<QuantizedCircles> {
+        if x.len() < MIN_QUANTIZED_INSTANCES {
+            return None;
+        }
+        let qx = Quantization::fit(x.iter().chain(hole_coords(hole_x, hole_radius)))?;
+        let qy = Quantization::fit(y.iter().chain(hole_coords(hole_y, hole_radius)))?;
+        let qr = Quantization::fit(radius.iter().chain(hole_radius))?;
+
+        let interleave = |xs: &[f32], ys: &[f32]| {
+            xs.iter()
+                .zip(ys)
+                .flat_map(|(&x, &y)| [qx.encode(x), qy.encode(y)])
+                .collect()
+        };
+        Some(QuantizedCircles {
+            x: qx,
+            y: qy,
+            radius: qr,
+            centers: interleave(x, y),
+            hole_centers: interleave(hole_x, hole_y),
+            radii: radius.iter().map(|&r| qr.encode(r)).collect(),
+            hole_radii: hole_radius.iter().map(|&r| qr.encode(r)).collect(),
+        })
+    }
+
+    /// `u_center_range` uniform: x and y offsets, then x and y scales
+    pub fn center_range(&self) -> [f32; 4] {
+        [self.x.offset, self.y.offset, self.x.scale, self.y.scale]
+    }
+
+    /// `u_radius_range` uniform: offset, then scale
+    pub fn radius_range(&self) -> [f32; 2] {
+        [self.radius.offset, self.radius.scale]
+    }
+}
+
+/// Coordinates of the circles that have a hole
+fn hole_coords<'a>(coords: &'a [f32], hole_radius: &'a [f32]) -> impl Iterator<Item = &'a f32> {
+    coords
+        .iter()
+        .zip(hole_radius)
+        .filter(|(_, &r)| r > 0.0)
+        .map(|(c, _)| c)
+}
@@ -0,0 +1,175 @@
+use super::buffer::Fbo;
+use super::shader::{BLEND, COLOR_BUFFER_BIT, FUNC_ADD, ONE};
+use super::Renderer;
+use crate::error::ViewerError;
+use crate::shape::Boundary;
+use crate::store::LayerStore;
+use wasm_bindgen::prelude::*;
+use web_sys::WebGl2RenderingContext;
+
+impl Renderer {
+    /// Render a board window into offscreen framebuffers and read back RGBA pixels
+    ///
+    /// Layers are composited additively at full opacity like on the canvas.
+    /// Every feature is drawn regardless of the level-of-detail setting.
+    /// Rows are returned top row first with straight (not premultiplied) alpha,
+    /// ready for ImageData. The canvas and layer FBOs are left untouched.
+    pub fn render_region(
+        &mut self,
+        store: &LayerStore,
+        region: &Boundary,
+        width: u32,
+        height: u32,
+        layer_ids: &[u32],
+        color_data: &[f32],
+    ) -> Result<Vec<u8>, JsValue> {
+        for &layer_id in layer_ids {
+            self.get_layer(layer_id as usize)?;
+        }
+        if color_data.len() < layer_ids.len() * 3 {
+            return Err(
+                ViewerError::invalid_argument("Colors must contain 3 values per layer").into(),
+            );
+        }
+        let max_size = self
+            .gl
+            .get_parameter(WebGl2RenderingContext::MAX_TEXTURE_SIZE)?
+            .as_f64()
+            .unwrap_or(4096.0) as u32;
+        if width == 0 || height == 0 || width > max_size || height > max_size {
+            return Err(ViewerError::invalid_argument(format!(
+                "Image size must be between 1 and {} pixels per side",
+                max_size
+            ))
+            .into());
+        }
+        let region_width = region.max_x - region.min_x;
+        let region_height = region.max_y - region.min_y;
+        if region_width <= 0.0 || region_height <= 0.0 {
+            return Err(ViewerError::invalid_argument("Region must have a positive size").into());
+        }
+
+        let image_fbo = Self::create_fbo(&self.gl, width, height)?;
+        let mut result = self.draw_region(
+            store,
+            region,
+            (width, height),
+            layer_ids,
+            color_data,
+            &image_fbo,
+        );
+
+        let mut pixels = vec![0u8; width as usize * height as usize * 4];
+        if result.is_ok() {
+            self.gl.bind_framebuffer(
+                WebGl2RenderingContext::FRAMEBUFFER,
+                Some(&image_fbo.framebuffer),
+            );
+            result = self.gl.read_pixels_with_opt_u8_array(
+                0,
+                0,
+                width as i32,
+                height as i32,
+                WebGl2RenderingContext::RGBA,
+                WebGl2RenderingContext::UNSIGNED_BYTE,
+                Some(&mut pixels),
+            );
+        }
+
+        self.gl
+            .bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        Self::delete_fbo(&self.gl, image_fbo);
+        result?;
+
+        // GL rows run bottom to top; images run top to bottom
+        let row = width as usize * 4;
+        let mut image = Vec::with_capacity(pixels.len());
+        for line in pixels.chunks_exact(row).rev() {
+            image.extend_from_slice(line);
+        }
+        for pixel in image.chunks_exact_mut(4) {
+            let alpha = pixel[3] as u32;
+            if alpha > 0 && alpha < 255 {
+                for channel in &mut pixel[..3] {
+                    *channel = ((*channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+                }
+            }
+        }
+        Ok(image)
+    }
+
+    /// Composite layers showing a board window into `target` (premultiplied alpha)
+    ///
+    /// The region is mapped exactly onto the target, independent of the
+    /// canvas. Leaves `target` bound and its size as the viewport.
+    pub(super) fn draw_region(
+        &mut self,
+        store: &LayerStore,
+        region: &Boundary,
+        (width, height): (u32, u32),
+        layer_ids: &[u32],
+        color_data: &[f32],
+        target: &Fbo,
+    ) -> Result<(), JsValue> {
+        let region_width = region.max_x - region.min_x;
+        let region_height = region.max_y - region.min_y;
+        let transform = [
+            2.0 / region_width,
+            0.0,
+            0.0,
+            0.0,
+            2.0 / region_height,
+            0.0,
+            -(region.min_x + region.max_x) / region_width,
+            -(region.min_y + region.max_y) / region_height,
+            1.0,
+        ];
+
+        let layer_fbo = Self::create_geometry_fbo(&self.gl, width, height)?;
+        self.gl.viewport(0, 0, width as i32, height as i32);
+        self.gl.bind_framebuffer(
+            WebGl2RenderingContext::FRAMEBUFFER,
+            Some(&target.framebuffer),
+        );
+        self.gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        self.gl.clear(COLOR_BUFFER_BIT);
+
+        let mut result = Ok(());
+        for (index, &layer_id) in layer_ids.iter().enumerate() {
+            // Draw the layer alone, then add it to the image in its color
+            self.gl.bind_framebuffer(
+                WebGl2RenderingContext::FRAMEBUFFER,
+                Some(&layer_fbo.framebuffer),
+            );
+            self.gl.clear_color(0.0, 0.0, 0.0, 0.0);
+            self.gl.clear(COLOR_BUFFER_BIT);
+            result = self.render_layer_geometry(store, layer_id as usize, &transform, 0.0, 0.0);
+            if result.is_err() {
+                break;
+            }
+
+            self.gl.bind_framebuffer(
+                WebGl2RenderingContext::FRAMEBUFFER,
+                Some(&target.framebuffer),
+            );
+            self.gl.enable(BLEND);
+            self.gl.blend_func(ONE, ONE);
+            self.gl.blend_equation(FUNC_ADD);
+            let color = [
+                color_data[index * 3],
+                color_data[index * 3 + 1],
+                color_data[index * 3 + 2],
+                1.0,
+            ];
+            let by_function = self.colors_by_function(layer_id as usize);
+            result = self.draw_fbo_texture(&layer_fbo.texture, &color, by_function);
+            self.gl.disable(BLEND);
+            if result.is_err() {
+                break;
+            }
+        }
+
+        Self::delete_fbo(&self.gl, layer_fbo);
+        result
+    }
+}
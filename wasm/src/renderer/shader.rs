@@ -1,3 +1,4 @@
+use crate::error::{ErrorCode, ViewerError};
 use std::collections::HashMap;
 use wasm_bindgen::prelude::*;
 use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlShader, WebGlUniformLocation};
@@ -5,8 +6,11 @@ use web_sys::{WebGl2RenderingContext, WebGlProgram, WebGlShader, WebGlUniformLoc
 // WebGL constants
 pub const COLOR_BUFFER_BIT: u32 = WebGl2RenderingContext::COLOR_BUFFER_BIT;
 pub const TRIANGLES: u32 = WebGl2RenderingContext::TRIANGLES;
+pub const LINES: u32 = WebGl2RenderingContext::LINES;
 pub const FLOAT: u32 = WebGl2RenderingContext::FLOAT;
 pub const UNSIGNED_INT: u32 = WebGl2RenderingContext::UNSIGNED_INT;
+pub const UNSIGNED_BYTE: u32 = WebGl2RenderingContext::UNSIGNED_BYTE;
+pub const UNSIGNED_SHORT: u32 = WebGl2RenderingContext::UNSIGNED_SHORT;
 pub const ARRAY_BUFFER: u32 = WebGl2RenderingContext::ARRAY_BUFFER;
 pub const ELEMENT_ARRAY_BUFFER: u32 = WebGl2RenderingContext::ELEMENT_ARRAY_BUFFER;
 pub const STATIC_DRAW: u32 = WebGl2RenderingContext::STATIC_DRAW;
@@ -16,23 +20,71 @@ pub const BLEND: u32 = WebGl2RenderingContext::BLEND;
 pub const ONE_MINUS_SRC_ALPHA: u32 = WebGl2RenderingContext::ONE_MINUS_SRC_ALPHA;
 pub const ONE: u32 = WebGl2RenderingContext::ONE;
 pub const FUNC_ADD: u32 = WebGl2RenderingContext::FUNC_ADD;
-pub const ZERO: u32 = WebGl2RenderingContext::ZERO;
+pub const DEPTH_TEST: u32 = WebGl2RenderingContext::DEPTH_TEST;
+pub const DEPTH_BUFFER_BIT: u32 = WebGl2RenderingContext::DEPTH_BUFFER_BIT;
+pub const LESS: u32 = WebGl2RenderingContext::LESS;
+pub const LEQUAL: u32 = WebGl2RenderingContext::LEQUAL;
 
 // Shader sources
 pub const TRIANGLE_VERTEX_SHADER: &str = r#"#version 300 es
 in vec2 position;
 in vec2 hole_center_instance;
 in float hole_radius_instance;
+in float function_instance;
+in float aperture_instance;
+in float hidden_instance; // 1 = filtered out by set_filter()
+in float sublayer_instance; // Polarity sublayer: even = dark, odd = clear
+in vec4 corners_instance; // Other two corners of the vertex's triangle (with u_min_width)
 uniform mat3 transform;
+uniform vec4 color;
+uniform bool u_by_function;
+uniform vec3 u_function_colors[10]; // Per ApertureFunction
+uniform float u_highlight_code; // D-code to emphasize (-1 = off)
+uniform float u_sublayer_count;
+uniform bool u_inverted; // Negative image (%IPNEG): even sublayers clear
+uniform float u_min_width; // Thinner triangles are widened to this (0 = off)
 out lowp vec2 vPosition;
 out lowp vec2 vHoleCenter;
 out lowp float vHoleRadius;
+flat out vec4 vColor;
+// Vertex p of triangle (p, a, b) after stretching the triangle across its
+// longest edge to at least u_min_width; all three corners agree on the edge
+vec2 widened(vec2 p, vec2 a, vec2 b) {
+    vec2 origin = a;
+    vec2 edge = b - a;
+    if (dot(a - p, a - p) > dot(edge, edge)) { origin = p; edge = a - p; }
+    if (dot(b - p, b - p) > dot(edge, edge)) { origin = p; edge = b - p; }
+    float len = length(edge);
+    if (len == 0.0) return p;
+    vec2 normal = vec2(-edge.y, edge.x) / len;
+    float dp = dot(p - origin, normal);
+    float da = dot(a - origin, normal);
+    float db = dot(b - origin, normal);
+    float low = min(dp, min(da, db));
+    float high = max(dp, max(da, db));
+    float height = high - low;
+    if (height >= u_min_width || height == 0.0) return p;
+    float middle = (low + high) * 0.5;
+    return p + normal * ((dp - middle) * (u_min_width / height - 1.0));
+}
 void main() {
-    vec3 transformed = transform * vec3(position, 1.0);
-    gl_Position = vec4(transformed.xy, 0.0, 1.0);
-    vPosition = position;
+    if (hidden_instance > 0.0) {
+        gl_Position = vec4(0.0, 0.0, 2.0, 1.0);
+        return;
+    }
+    vec2 corner = u_min_width > 0.0 ? widened(position, corners_instance.xy, corners_instance.zw) : position;
+    vec3 transformed = transform * vec3(corner, 1.0);
+    // Later sublayers lie in front, so they win the depth test
+    float depth = 1.0 - 2.0 * (sublayer_instance + 1.0) / (u_sublayer_count + 1.0);
+    gl_Position = vec4(transformed.xy, depth, 1.0);
+    vPosition = corner;
     vHoleCenter = hole_center_instance;
     vHoleRadius = hole_radius_instance;
+    vColor = u_by_function ? vec4(u_function_colors[int(function_instance)], color.a) : color;
+    // Dim features of other apertures while one is highlighted
+    if (u_highlight_code >= 0.0 && aperture_instance != u_highlight_code) vColor *= 0.15;
+    // Clear features replace what earlier sublayers drew with nothing
+    if ((mod(sublayer_instance, 2.0) == 1.0) != u_inverted) vColor = vec4(0.0);
 }
 "#;
 
@@ -41,14 +93,14 @@ precision lowp float;
 in lowp vec2 vPosition;
 in lowp vec2 vHoleCenter;
 in lowp float vHoleRadius;
-uniform vec4 color;
+flat in vec4 vColor;
 out vec4 fragColor;
 void main() {
     if (vHoleRadius > 0.0) {
         vec2 diff = vPosition - vHoleCenter;
         if (dot(diff, diff) < vHoleRadius * vHoleRadius) discard;
     }
-    fragColor = color;
+    fragColor = vColor;
 }
 "#;
 
@@ -58,17 +110,53 @@ in vec2 center_instance;
 in float radius_instance;
 in vec2 hole_center_instance;
 in float hole_radius_instance;
+in float function_instance;
+in float aperture_instance;
+in float hidden_instance; // 1 = filtered out by set_filter()
+in float sublayer_instance; // Polarity sublayer: even = dark, odd = clear
 uniform mat3 transform;
+uniform vec4 color;
+uniform bool u_by_function;
+uniform vec3 u_function_colors[10]; // Per ApertureFunction
+uniform float u_highlight_code; // D-code to emphasize (-1 = off)
+uniform float u_sublayer_count;
+uniform bool u_inverted; // Negative image (%IPNEG): even sublayers clear
+uniform float u_min_size;
+uniform float u_min_width; // Narrower features are widened to this (0 = off)
+uniform vec4 u_center_range; // Offset xy and scale zw of 16-bit centers (0, 0, 1, 1 for f32)
+uniform vec2 u_radius_range; // Offset and scale of 16-bit radii (0, 1 for f32)
 out lowp vec2 vPosition;
 out lowp vec2 vHoleCenter;
 out lowp float vHoleRadius;
+flat out vec4 vColor;
 void main() {
-    vec2 scaledPos = position * radius_instance + center_instance;
+    if (hidden_instance > 0.0) {
+        gl_Position = vec4(0.0, 0.0, 2.0, 1.0);
+        return;
+    }
+    vec2 center = u_center_range.xy + center_instance * u_center_range.zw;
+    float radius = u_radius_range.x + radius_instance * u_radius_range.y;
+    vec2 holeCenter = u_center_range.xy + hole_center_instance * u_center_range.zw;
+    float holeRadius = u_radius_range.x + hole_radius_instance * u_radius_range.y;
+    // Level of detail: drop features smaller than the minimum size (clipped away)
+    if (2.0 * radius < u_min_size) {
+        gl_Position = vec4(0.0, 0.0, 2.0, 1.0);
+        return;
+    }
+    radius = max(radius, u_min_width * 0.5);
+    vec2 scaledPos = position * radius + center;
     vec3 transformed = transform * vec3(scaledPos, 1.0);
-    gl_Position = vec4(transformed.xy, 0.0, 1.0);
+    // Later sublayers lie in front, so they win the depth test
+    float depth = 1.0 - 2.0 * (sublayer_instance + 1.0) / (u_sublayer_count + 1.0);
+    gl_Position = vec4(transformed.xy, depth, 1.0);
     vPosition = position;
-    vHoleCenter = (hole_center_instance - center_instance) / radius_instance;
-    vHoleRadius = hole_radius_instance / radius_instance;
+    vHoleCenter = (holeCenter - center) / radius;
+    vHoleRadius = holeRadius / radius;
+    vColor = u_by_function ? vec4(u_function_colors[int(function_instance)], color.a) : color;
+    // Dim features of other apertures while one is highlighted
+    if (u_highlight_code >= 0.0 && aperture_instance != u_highlight_code) vColor *= 0.15;
+    // Clear features replace what earlier sublayers drew with nothing
+    if ((mod(sublayer_instance, 2.0) == 1.0) != u_inverted) vColor = vec4(0.0);
 }
 "#;
 
@@ -77,7 +165,7 @@ precision lowp float;
 in lowp vec2 vPosition;
 in lowp vec2 vHoleCenter;
 in lowp float vHoleRadius;
-uniform vec4 color;
+flat in vec4 vColor;
 out vec4 fragColor;
 void main() {
     float dist = dot(vPosition, vPosition);
@@ -86,7 +174,7 @@ void main() {
         vec2 diff = vPosition - vHoleCenter;
         if (dot(diff, diff) < vHoleRadius * vHoleRadius) discard;
     }
-    fragColor = color;
+    fragColor = vColor;
 }
 "#;
 
@@ -97,22 +185,52 @@ in float radius_instance;
 in float startAngle_instance;
 in float sweepAngle_instance;
 in float thickness_instance;
+in float function_instance;
+in float aperture_instance;
+in float hidden_instance; // 1 = filtered out by set_filter()
+in float sublayer_instance; // Polarity sublayer: even = dark, odd = clear
 uniform mat3 transform;
+uniform vec4 color;
+uniform bool u_by_function;
+uniform vec3 u_function_colors[10]; // Per ApertureFunction
+uniform float u_highlight_code; // D-code to emphasize (-1 = off)
+uniform float u_sublayer_count;
+uniform bool u_inverted; // Negative image (%IPNEG): even sublayers clear
+uniform float u_min_size;
+uniform float u_min_width; // Narrower features are widened to this (0 = off)
 out lowp vec2 vPosition;
 out lowp float vRadius;
-out lowp float vStartAngle;
-out lowp float vSweepAngle;
+out highp float vStartAngle; // lowp may not hold angles up to 2 PI
+out highp float vSweepAngle;
 out lowp float vThickness;
+flat out vec4 vColor;
 void main() {
-    float maxRadius = radius_instance + thickness_instance;
+    if (hidden_instance > 0.0) {
+        gl_Position = vec4(0.0, 0.0, 2.0, 1.0);
+        return;
+    }
+    // Level of detail: drop features smaller than the minimum size (clipped away)
+    if (2.0 * radius_instance + thickness_instance < u_min_size) {
+        gl_Position = vec4(0.0, 0.0, 2.0, 1.0);
+        return;
+    }
+    float thickness = max(thickness_instance, u_min_width);
+    float maxRadius = radius_instance + thickness;
     vec2 scaledPos = position * maxRadius + center_instance;
     vec3 transformed = transform * vec3(scaledPos, 1.0);
-    gl_Position = vec4(transformed.xy, 0.0, 1.0);
+    // Later sublayers lie in front, so they win the depth test
+    float depth = 1.0 - 2.0 * (sublayer_instance + 1.0) / (u_sublayer_count + 1.0);
+    gl_Position = vec4(transformed.xy, depth, 1.0);
     vPosition = position * maxRadius;
     vRadius = radius_instance;
     vStartAngle = startAngle_instance;
     vSweepAngle = sweepAngle_instance;
-    vThickness = thickness_instance;
+    vThickness = thickness;
+    vColor = u_by_function ? vec4(u_function_colors[int(function_instance)], color.a) : color;
+    // Dim features of other apertures while one is highlighted
+    if (u_highlight_code >= 0.0 && aperture_instance != u_highlight_code) vColor *= 0.15;
+    // Clear features replace what earlier sublayers drew with nothing
+    if ((mod(sublayer_instance, 2.0) == 1.0) != u_inverted) vColor = vec4(0.0);
 }
 "#;
 
@@ -120,10 +238,10 @@ pub const ARC_FRAGMENT_SHADER: &str = r#"#version 300 es
 precision lowp float;
 in lowp vec2 vPosition;
 in lowp float vRadius;
-in lowp float vStartAngle;
-in lowp float vSweepAngle;
+in highp float vStartAngle;
+in highp float vSweepAngle;
 in lowp float vThickness;
-uniform vec4 color;
+flat in vec4 vColor;
 out vec4 fragColor;
 
 const float PI = 3.14159265359;
@@ -153,7 +271,10 @@ void main() {
     }
 
     bool inRange;
-    if (vSweepAngle > 0.0) {
+    if (abs(vSweepAngle) >= TWO_PI - 0.001) {
+        // Full circle; its end angle wraps onto the start angle
+        inRange = true;
+    } else if (vSweepAngle > 0.0) {
         if (endAngle > startAngle) {
             inRange = angle >= startAngle && angle <= endAngle;
         } else {
@@ -171,7 +292,7 @@ void main() {
         discard;
     }
 
-    fragColor = color;
+    fragColor = vColor;
 }
 "#;
 
@@ -182,22 +303,50 @@ in float outer_diameter_instance;
 in float inner_diameter_instance;
 in float gap_thickness_instance;
 in float rotation_instance;
+in float function_instance;
+in float aperture_instance;
+in float hidden_instance; // 1 = filtered out by set_filter()
+in float sublayer_instance; // Polarity sublayer: even = dark, odd = clear
 uniform mat3 transform;
+uniform vec4 color;
+uniform bool u_by_function;
+uniform vec3 u_function_colors[10]; // Per ApertureFunction
+uniform float u_highlight_code; // D-code to emphasize (-1 = off)
+uniform float u_sublayer_count;
+uniform bool u_inverted; // Negative image (%IPNEG): even sublayers clear
+uniform float u_min_size;
 out lowp vec2 vPosition;
 out lowp float vInnerDiameter;
 out lowp float vOuterDiameter;
 out lowp float vGapThickness;
-out lowp float vRotation;
+out highp float vRotation; // Radians counterclockwise
+flat out vec4 vColor;
 void main() {
+    if (hidden_instance > 0.0) {
+        gl_Position = vec4(0.0, 0.0, 2.0, 1.0);
+        return;
+    }
+    // Level of detail: drop features smaller than the minimum size (clipped away)
+    if (outer_diameter_instance < u_min_size) {
+        gl_Position = vec4(0.0, 0.0, 2.0, 1.0);
+        return;
+    }
     float outer_radius = outer_diameter_instance / 2.0;
     vec2 scaledPos = position * outer_radius + center_instance;
     vec3 transformed = transform * vec3(scaledPos, 1.0);
-    gl_Position = vec4(transformed.xy, 0.0, 1.0);
+    // Later sublayers lie in front, so they win the depth test
+    float depth = 1.0 - 2.0 * (sublayer_instance + 1.0) / (u_sublayer_count + 1.0);
+    gl_Position = vec4(transformed.xy, depth, 1.0);
     vPosition = position;
     vInnerDiameter = inner_diameter_instance;
     vOuterDiameter = outer_diameter_instance;
     vGapThickness = gap_thickness_instance;
     vRotation = rotation_instance;
+    vColor = u_by_function ? vec4(u_function_colors[int(function_instance)], color.a) : color;
+    // Dim features of other apertures while one is highlighted
+    if (u_highlight_code >= 0.0 && aperture_instance != u_highlight_code) vColor *= 0.15;
+    // Clear features replace what earlier sublayers drew with nothing
+    if ((mod(sublayer_instance, 2.0) == 1.0) != u_inverted) vColor = vec4(0.0);
 }
 "#;
 
@@ -207,17 +356,18 @@ in lowp vec2 vPosition;
 in lowp float vInnerDiameter;
 in lowp float vOuterDiameter;
 in lowp float vGapThickness;
-in lowp float vRotation;
-uniform vec4 color;
+in highp float vRotation;
+flat in vec4 vColor;
 out vec4 fragColor;
 
 void main() {
-    // Apply rotation to vPosition
+    // Rotate vPosition back by the thermal's rotation, so the gaps lie
+    // along the axes
     float cosR = cos(vRotation);
     float sinR = sin(vRotation);
     vec2 rotated = vec2(
-        vPosition.x * cosR - vPosition.y * sinR,
-        vPosition.x * sinR + vPosition.y * cosR
+        vPosition.x * cosR + vPosition.y * sinR,
+        -vPosition.x * sinR + vPosition.y * cosR
     );
 
     float dist = length(rotated);
@@ -237,7 +387,87 @@ void main() {
         discard;
     }
 
-    fragColor = color;
+    fragColor = vColor;
+}
+"#;
+
+pub const OBROUND_VERTEX_SHADER: &str = r#"#version 300 es
+in vec2 position;
+in vec2 center_instance;
+in vec2 size_instance;
+in float rotation_instance;
+in vec2 hole_center_instance;
+in float hole_radius_instance;
+in float function_instance;
+in float aperture_instance;
+in float hidden_instance; // 1 = filtered out by set_filter()
+in float sublayer_instance; // Polarity sublayer: even = dark, odd = clear
+uniform mat3 transform;
+uniform vec4 color;
+uniform bool u_by_function;
+uniform vec3 u_function_colors[10]; // Per ApertureFunction
+uniform float u_highlight_code; // D-code to emphasize (-1 = off)
+uniform float u_sublayer_count;
+uniform bool u_inverted; // Negative image (%IPNEG): even sublayers clear
+uniform float u_min_size;
+uniform float u_min_width; // Narrower features are widened to this (0 = off)
+out vec2 vLocal;
+out vec2 vHalfSize;
+out vec2 vHoleCenter;
+out float vHoleRadius;
+flat out vec4 vColor;
+void main() {
+    if (hidden_instance > 0.0) {
+        gl_Position = vec4(0.0, 0.0, 2.0, 1.0);
+        return;
+    }
+    // Level of detail: drop features smaller than the minimum size (clipped away)
+    if (max(size_instance.x, size_instance.y) < u_min_size) {
+        gl_Position = vec4(0.0, 0.0, 2.0, 1.0);
+        return;
+    }
+    vec2 halfSize = max(size_instance, vec2(u_min_width)) * 0.5;
+    float cosR = cos(rotation_instance);
+    float sinR = sin(rotation_instance);
+    vec2 local = position * halfSize;
+    vec2 rotated = vec2(local.x * cosR - local.y * sinR, local.x * sinR + local.y * cosR);
+    vec3 transformed = transform * vec3(rotated + center_instance, 1.0);
+    // Later sublayers lie in front, so they win the depth test
+    float depth = 1.0 - 2.0 * (sublayer_instance + 1.0) / (u_sublayer_count + 1.0);
+    gl_Position = vec4(transformed.xy, depth, 1.0);
+    vLocal = local;
+    vHalfSize = halfSize;
+    // Hole center in the obround's unrotated local frame
+    vec2 hole = hole_center_instance - center_instance;
+    vHoleCenter = vec2(hole.x * cosR + hole.y * sinR, -hole.x * sinR + hole.y * cosR);
+    vHoleRadius = hole_radius_instance;
+    vColor = u_by_function ? vec4(u_function_colors[int(function_instance)], color.a) : color;
+    // Dim features of other apertures while one is highlighted
+    if (u_highlight_code >= 0.0 && aperture_instance != u_highlight_code) vColor *= 0.15;
+    // Clear features replace what earlier sublayers drew with nothing
+    if ((mod(sublayer_instance, 2.0) == 1.0) != u_inverted) vColor = vec4(0.0);
+}
+"#;
+
+pub const OBROUND_FRAGMENT_SHADER: &str = r#"#version 300 es
+precision mediump float;
+in vec2 vLocal;
+in vec2 vHalfSize;
+in vec2 vHoleCenter;
+in float vHoleRadius;
+flat in vec4 vColor;
+out vec4 fragColor;
+void main() {
+    // Distance to the straight segment between the two end cap centers
+    float radius = min(vHalfSize.x, vHalfSize.y);
+    vec2 segment = vHalfSize - vec2(radius);
+    vec2 q = max(abs(vLocal) - segment, 0.0);
+    if (dot(q, q) > radius * radius) discard;
+    if (vHoleRadius > 0.0) {
+        vec2 diff = vLocal - vHoleCenter;
+        if (dot(diff, diff) < vHoleRadius * vHoleRadius) discard;
+    }
+    fragColor = vColor;
 }
 "#;
 
@@ -255,12 +485,145 @@ precision lowp float;
 in vec2 v_uv;
 uniform sampler2D u_texture;
 uniform vec4 u_color;
+uniform bool u_by_function;
 out vec4 fragColor;
 void main() {
     vec4 texColor = texture(u_texture, v_uv);
     // Pre-multiply alpha: color * alpha for additive blending
     float finalAlpha = u_color.a * texColor.a;
-    fragColor = vec4(u_color.rgb * finalAlpha, finalAlpha);
+    // Layers colored by aperture function carry their color in the texture
+    vec3 rgb = u_by_function ? texColor.rgb : u_color.rgb;
+    fragColor = vec4(rgb * finalAlpha, finalAlpha);
+}
+"#;
+
+pub const COMPARE_FRAGMENT_SHADER: &str = r#"#version 300 es
+precision lowp float;
+in vec2 v_uv;
+uniform sampler2D u_texture_a;
+uniform sampler2D u_texture_b;
+uniform vec3 u_color_a;
+uniform vec3 u_color_b;
+uniform vec3 u_color_common;
+uniform float u_alpha;
+out vec4 fragColor;
+void main() {
+    float a = texture(u_texture_a, v_uv).a;
+    float b = texture(u_texture_b, v_uv).a;
+    // Split coverage into A-only, B-only and common parts
+    vec3 color = u_color_a * a * (1.0 - b) + u_color_b * b * (1.0 - a) + u_color_common * a * b;
+    float coverage = max(a, b) * u_alpha;
+    fragColor = vec4(color * u_alpha, coverage);
+}
+"#;
+
+pub const HEATMAP_VERTEX_SHADER: &str = r#"#version 300 es
+in vec2 position;
+uniform mat3 transform;
+uniform vec4 u_bounds;
+out vec2 v_uv;
+void main() {
+    v_uv = position * 0.5 + 0.5;
+    vec2 world = mix(u_bounds.xy, u_bounds.zw, v_uv);
+    vec3 transformed = transform * vec3(world, 1.0);
+    gl_Position = vec4(transformed.xy, 0.0, 1.0);
+}
+"#;
+
+pub const HEATMAP_FRAGMENT_SHADER: &str = r#"#version 300 es
+precision mediump float;
+in vec2 v_uv;
+uniform sampler2D u_texture;
+uniform float u_opacity;
+out vec4 fragColor;
+void main() {
+    float value = texture(u_texture, v_uv).r;
+    // Blue (bare) -> green -> red (full copper)
+    vec3 ramp = clamp(vec3(
+        1.5 - abs(4.0 * value - 3.0),
+        1.5 - abs(4.0 * value - 2.0),
+        1.5 - abs(4.0 * value - 1.0)
+    ), 0.0, 1.0);
+    // Pre-multiplied alpha for blending over the composited layers
+    fragColor = vec4(ramp * u_opacity, u_opacity);
+}
+"#;
+
+pub const MARKER_VERTEX_SHADER: &str = r#"#version 300 es
+in vec2 position;
+in vec2 center_instance;
+in float radius_instance;
+uniform mat3 transform;
+out vec2 vPosition;
+void main() {
+    vec2 scaledPos = position * radius_instance + center_instance;
+    vec3 transformed = transform * vec3(scaledPos, 1.0);
+    gl_Position = vec4(transformed.xy, 0.0, 1.0);
+    vPosition = position;
+}
+"#;
+
+pub const MARKER_FRAGMENT_SHADER: &str = r#"#version 300 es
+precision mediump float;
+in vec2 vPosition;
+uniform vec4 color;
+uniform float u_thickness;
+out vec4 fragColor;
+void main() {
+    float dist = length(vPosition);
+    // Ring outline so the marked feature stays visible underneath
+    if (dist > 1.0 || dist < 1.0 - u_thickness) discard;
+    fragColor = vec4(color.rgb * color.a, color.a);
+}
+"#;
+
+pub const DASH_VERTEX_SHADER: &str = r#"#version 300 es
+in vec2 position;
+in float distance; // Along the outline in mm
+uniform mat3 transform;
+out float vDistance;
+void main() {
+    vec3 transformed = transform * vec3(position, 1.0);
+    gl_Position = vec4(transformed.xy, 0.0, 1.0);
+    vDistance = distance;
+}
+"#;
+
+pub const DASH_FRAGMENT_SHADER: &str = r#"#version 300 es
+precision highp float;
+in float vDistance;
+uniform vec4 color;
+uniform float u_dash_length; // mm, dash and gap each
+out vec4 fragColor;
+void main() {
+    // Gaps at odd multiples of the dash length
+    if (mod(vDistance, 2.0 * u_dash_length) > u_dash_length) discard;
+    fragColor = vec4(color.rgb * color.a, color.a);
+}
+"#;
+
+pub const MESH_VERTEX_SHADER: &str = r#"#version 300 es
+in vec3 position;
+in vec3 normal;
+uniform mat4 u_view_projection;
+out vec3 vNormal;
+void main() {
+    gl_Position = u_view_projection * vec4(position, 1.0);
+    vNormal = normal;
+}
+"#;
+
+pub const MESH_FRAGMENT_SHADER: &str = r#"#version 300 es
+precision mediump float;
+in vec3 vNormal;
+uniform vec4 color;
+uniform vec3 u_light_dir;
+out vec4 fragColor;
+void main() {
+    // Two-sided diffuse light from the viewer plus ambient
+    float diffuse = abs(dot(normalize(vNormal), u_light_dir));
+    vec3 lit = color.rgb * (0.35 + 0.65 * diffuse);
+    fragColor = vec4(lit * color.a, color.a);
 }
 "#;
 
@@ -277,7 +640,13 @@ pub struct ShaderPrograms {
     pub circle: ShaderProgram,
     pub arc: ShaderProgram,
     pub thermal: ShaderProgram,
+    pub obround: ShaderProgram,
     pub texture: ShaderProgram,
+    pub compare: ShaderProgram,
+    pub heatmap: ShaderProgram,
+    pub marker: ShaderProgram,
+    pub dash: ShaderProgram,
+    pub mesh: ShaderProgram,
 }
 
 impl ShaderPrograms {
@@ -287,16 +656,56 @@ impl ShaderPrograms {
             gl,
             TRIANGLE_VERTEX_SHADER,
             TRIANGLE_FRAGMENT_SHADER,
-            &["position", "hole_center_instance", "hole_radius_instance"],
-            &["transform", "color"],
+            &[
+                "position",
+                "hole_center_instance",
+                "hole_radius_instance",
+                "function_instance",
+                "aperture_instance",
+                "hidden_instance",
+                "sublayer_instance",
+                "corners_instance",
+            ],
+            &[
+                "transform",
+                "color",
+                "u_by_function",
+                "u_function_colors",
+                "u_highlight_code",
+                "u_sublayer_count",
+                "u_inverted",
+                "u_min_width",
+            ],
         )?;
 
         let circle = compile_program(
             gl,
             CIRCLE_VERTEX_SHADER,
             CIRCLE_FRAGMENT_SHADER,
-            &["position", "center_instance", "radius_instance", "hole_center_instance", "hole_radius_instance"],
-            &["transform", "color"],
+            &[
+                "position",
+                "center_instance",
+                "radius_instance",
+                "hole_center_instance",
+                "hole_radius_instance",
+                "function_instance",
+                "aperture_instance",
+                "hidden_instance",
+                "sublayer_instance",
+            ],
+            &[
+                "transform",
+                "color",
+                "u_min_size",
+                "u_center_range",
+                "u_radius_range",
+                "u_by_function",
+                "u_function_colors",
+                "u_highlight_code",
+                "u_sublayer_count",
+                "u_inverted",
+                "u_min_width",
+            ],
         )?;
 
         let arc = compile_program(
@@ -310,8 +719,22 @@ impl ShaderPrograms {
                 "startAngle_instance",
                 "sweepAngle_instance",
                 "thickness_instance",
+                "function_instance",
+                "aperture_instance",
+                "hidden_instance",
+                "sublayer_instance",
+            ],
+            &[
+                "transform",
+                "color",
+                "u_min_size",
+                "u_by_function",
+                "u_function_colors",
+                "u_highlight_code",
+                "u_sublayer_count",
+                "u_inverted",
+                "u_min_width",
             ],
-            &["transform", "color"],
         )?;
 
         let thermal = compile_program(
@@ -325,8 +748,50 @@ impl ShaderPrograms {
                 "inner_diameter_instance",
                 "gap_thickness_instance",
                 "rotation_instance",
+                "function_instance",
+                "aperture_instance",
+                "hidden_instance",
+                "sublayer_instance",
+            ],
+            &[
+                "transform",
+                "color",
+                "u_min_size",
+                "u_by_function",
+                "u_function_colors",
+                "u_highlight_code",
+                "u_sublayer_count",
+                "u_inverted",
+            ],
+        )?;
+
+        let obround = compile_program(
+            gl,
+            OBROUND_VERTEX_SHADER,
+            OBROUND_FRAGMENT_SHADER,
+            &[
+                "position",
+                "center_instance",
+                "size_instance",
+                "rotation_instance",
+                "hole_center_instance",
+                "hole_radius_instance",
+                "function_instance",
+                "aperture_instance",
+                "hidden_instance",
+                "sublayer_instance",
+            ],
+            &[
+                "transform",
+                "color",
+                "u_min_size",
+                "u_by_function",
+                "u_function_colors",
+                "u_highlight_code",
+                "u_sublayer_count",
+                "u_inverted",
+                "u_min_width",
             ],
-            &["transform", "color"],
         )?;
 
         let texture = compile_program(
@@ -334,7 +799,54 @@ impl ShaderPrograms {
             TEXTURE_VERTEX_SHADER,
             TEXTURE_FRAGMENT_SHADER,
             &["position"],
-            &["u_texture", "u_color"],
+            &["u_texture", "u_color", "u_by_function"],
+        )?;
+
+        let compare = compile_program(
+            gl,
+            TEXTURE_VERTEX_SHADER,
+            COMPARE_FRAGMENT_SHADER,
+            &["position"],
+            &[
+                "u_texture_a",
+                "u_texture_b",
+                "u_color_a",
+                "u_color_b",
+                "u_color_common",
+                "u_alpha",
+            ],
+        )?;
+
+        let heatmap = compile_program(
+            gl,
+            HEATMAP_VERTEX_SHADER,
+            HEATMAP_FRAGMENT_SHADER,
+            &["position"],
+            &["transform", "u_bounds", "u_texture", "u_opacity"],
+        )?;
+
+        let marker = compile_program(
+            gl,
+            MARKER_VERTEX_SHADER,
+            MARKER_FRAGMENT_SHADER,
+            &["position", "center_instance", "radius_instance"],
+            &["transform", "color", "u_thickness"],
+        )?;
+
+        let dash = compile_program(
+            gl,
+            DASH_VERTEX_SHADER,
+            DASH_FRAGMENT_SHADER,
+            &["position", "distance"],
+            &["transform", "color", "u_dash_length"],
+        )?;
+
+        let mesh = compile_program(
+            gl,
+            MESH_VERTEX_SHADER,
+            MESH_FRAGMENT_SHADER,
+            &["position", "normal"],
+            &["u_view_projection", "color", "u_light_dir"],
         )?;
 
         Ok(ShaderPrograms {
@@ -342,9 +854,45 @@ impl ShaderPrograms {
             circle,
             arc,
             thermal,
+            obround,
             texture,
+            compare,
+            heatmap,
+            marker,
+            dash,
+            mesh,
         })
     }
+
+    /// Programs of the five primitive types
+    pub fn primitives(&self) -> [&ShaderProgram; 5] {
+        [
+            &self.triangle,
+            &self.circle,
+            &self.arc,
+            &self.thermal,
+            &self.obround,
+        ]
+    }
+
+    /// Delete all shader programs
+    pub fn delete(&self, gl: &WebGl2RenderingContext) {
+        for program in [
+            &self.triangle,
+            &self.circle,
+            &self.arc,
+            &self.thermal,
+            &self.obround,
+            &self.texture,
+            &self.compare,
+            &self.heatmap,
+            &self.marker,
+            &self.dash,
+            &self.mesh,
+        ] {
+            gl.delete_program(Some(&program.program));
+        }
+    }
 }
 
 /// Compile a shader program
@@ -360,7 +908,7 @@ fn compile_program(
 
     let program = gl
         .create_program()
-        .ok_or_else(|| JsValue::from_str("Unable to create shader program"))?;
+        .ok_or_else(|| ViewerError::webgl("Unable to create shader program"))?;
 
     gl.attach_shader(&program, &vert_shader);
     gl.attach_shader(&program, &frag_shader);
@@ -379,7 +927,11 @@ fn compile_program(
         let error = gl
             .get_program_info_log(&program)
             .unwrap_or_else(|| "Unknown error".to_string());
-        return Err(JsValue::from_str(&format!("Shader link error: {}", error)));
+        return Err(ViewerError::new(
+            ErrorCode::ShaderCompile,
+            format!("Shader link error: {}", error),
+        )
+        .into());
     }
 
     gl.delete_shader(Some(&vert_shader));
@@ -412,7 +964,7 @@ fn compile_shader(
 ) -> Result<WebGlShader, JsValue> {
     let shader = gl
         .create_shader(shader_type)
-        .ok_or_else(|| JsValue::from_str("Unable to create shader object"))?;
+        .ok_or_else(|| ViewerError::webgl("Unable to create shader object"))?;
 
     gl.shader_source(&shader, source);
     gl.compile_shader(&shader);
@@ -426,7 +978,11 @@ fn compile_shader(
             .get_shader_info_log(&shader)
             .unwrap_or_else(|| "Unknown error".to_string());
         gl.delete_shader(Some(&shader));
-        return Err(JsValue::from_str(&format!("Shader compile error: {}", error)));
+        return Err(ViewerError::new(
+            ErrorCode::ShaderCompile,
+            format!("Shader compile error: {}", error),
+        )
+        .into());
     }
 
     Ok(shader)
@@ -0,0 +1,327 @@
+use super::shader::{
+    ShaderProgram, ARRAY_BUFFER, BLEND, COLOR_BUFFER_BIT, DEPTH_BUFFER_BIT, DEPTH_TEST, FLOAT,
+    FUNC_ADD, ONE, ONE_MINUS_SRC_ALPHA, STATIC_DRAW, TRIANGLES,
+};
+use super::Renderer;
+use crate::error::ViewerError;
+use crate::extrude::LayerMesh;
+use crate::shape::Boundary;
+use js_sys::Float32Array;
+use wasm_bindgen::prelude::*;
+use web_sys::{WebGl2RenderingContext, WebGlBuffer, WebGlVertexArrayObject};
+
+// Vertical field of view of the perspective camera (degrees)
+const FIELD_OF_VIEW: f32 = 45.0;
+// Pitch is kept off the poles so the up vector stays valid (degrees)
+const MAX_PITCH: f32 = 89.0;
+// Background of the 3D view
+const CLEAR_COLOR: [f32; 4] = [0.12, 0.12, 0.14, 1.0];
+
+/// One extruded stackup layer uploaded to the GPU
+pub struct MeshBuffers {
+    vao: WebGlVertexArrayObject,
+    position_buffer: WebGlBuffer,
+    normal_buffer: WebGlBuffer,
+    vertex_count: i32,
+    color: [f32; 4],
+    layer_id: Option<u32>, // Hidden when this layer is not active
+}
+
+/// Perspective view of the extruded stackup, orbiting a target point
+pub struct View3d {
+    meshes: Vec<MeshBuffers>,
+    target: [f32; 3], // Orbit center in board coordinates (mm)
+    yaw: f32,         // Degrees around the board normal, 0 = eye on the +X side
+    pitch: f32,       // Eye elevation above the board plane, negative = from below
+    distance: f32,    // Eye distance from the target (mm)
+}
+
+impl Renderer {
+    /// Upload extruded layer meshes and switch to the 3D view
+    ///
+    /// The orbit starts above the front edge of the board, framing all of it.
+    pub fn set_3d_view(&mut self, meshes: &[LayerMesh], board: &Boundary) -> Result<(), JsValue> {
+        self.clear_3d_view();
+
+        let program = &self.programs.mesh;
+        let mut buffers = Vec::with_capacity(meshes.len());
+        let mut max_z = 0.0f32;
+        for mesh in meshes {
+            let vao = self
+                .gl
+                .create_vertex_array()
+                .ok_or_else(|| ViewerError::webgl("Failed to create VAO"))?;
+            self.gl.bind_vertex_array(Some(&vao));
+            let position_buffer =
+                Self::create_vertex_buffer_3d(&self.gl, &mesh.positions, program, "position")?;
+            let normal_buffer =
+                Self::create_vertex_buffer_3d(&self.gl, &mesh.normals, program, "normal")?;
+            self.gl.bind_vertex_array(None);
+
+            for z in mesh.positions.iter().skip(2).step_by(3) {
+                max_z = max_z.max(*z);
+            }
+            buffers.push(MeshBuffers {
+                vao,
+                position_buffer,
+                normal_buffer,
+                vertex_count: (mesh.positions.len() / 3) as i32,
+                color: mesh.kind.color(),
+                layer_id: mesh.layer_id,
+            });
+        }
+
+        let size = (board.max_x - board.min_x).max(board.max_y - board.min_y);
+        self.view3d = Some(View3d {
+            meshes: buffers,
+            target: [
+                (board.min_x + board.max_x) / 2.0,
+                (board.min_y + board.max_y) / 2.0,
+                max_z / 2.0,
+            ],
+            yaw: -90.0,
+            pitch: 45.0,
+            distance: size / (FIELD_OF_VIEW.to_radians() / 2.0).tan(),
+        });
+        Ok(())
+    }
+
+    /// Leave the 3D view and free its buffers
+    pub fn clear_3d_view(&mut self) {
+        if let Some(view) = self.view3d.take() {
+            for mesh in view.meshes {
+                self.gl.delete_vertex_array(Some(&mesh.vao));
+                self.gl.delete_buffer(Some(&mesh.position_buffer));
+                self.gl.delete_buffer(Some(&mesh.normal_buffer));
+            }
+        }
+    }
+
+    /// Whether render() currently draws the 3D view
+    pub fn is_3d_view(&self) -> bool {
+        self.view3d.is_some()
+    }
+
+    /// Move the 3D orbit camera
+    ///
+    /// Angles are in degrees; pitch is clamped just short of straight above or
+    /// below the board, the distance to a small positive value.
+    pub fn set_orbit(
+        &mut self,
+        yaw: f32,
+        pitch: f32,
+        distance: f32,
+        target_x: f32,
+        target_y: f32,
+    ) -> Result<(), JsValue> {
+        let view = self.view3d.as_mut().ok_or_else(|| {
+            ViewerError::invalid_state("3D view is not active. Call show_3d() first.")
+        })?;
+        view.yaw = yaw;
+        view.pitch = pitch.clamp(-MAX_PITCH, MAX_PITCH);
+        view.distance = distance.max(0.01);
+        view.target[0] = target_x;
+        view.target[1] = target_y;
+        Ok(())
+    }
+
+    /// Current orbit as [yaw, pitch, distance, target_x, target_y]
+    pub fn orbit(&self) -> Option<[f32; 5]> {
+        self.view3d
+            .as_ref()
+            .map(|v| [v.yaw, v.pitch, v.distance, v.target[0], v.target[1]])
+    }
+
+    /// Draw the extruded layers of active layers straight to the canvas
+    ///
+    /// Layers without a linked layer (board body, unlinked mask) are always drawn.
+    /// Opaque layers are drawn first, translucent ones after with depth writes off.
+    pub(super) fn render_3d(&self, active_layer_ids: &[u32], alpha: f32) -> Result<(), JsValue> {
+        let view = match &self.view3d {
+            Some(view) => view,
+            None => return Ok(()),
+        };
+        let (width, height) = self.get_canvas_size()?;
+
+        self.gl
+            .bind_framebuffer(WebGl2RenderingContext::FRAMEBUFFER, None);
+        self.gl.viewport(0, 0, width as i32, height as i32);
+        let [r, g, b, a] = CLEAR_COLOR;
+        self.gl.clear_color(r, g, b, a);
+        self.gl.clear(COLOR_BUFFER_BIT | DEPTH_BUFFER_BIT);
+
+        let (yaw, pitch) = (view.yaw.to_radians(), view.pitch.to_radians());
+        // Unit vector from the target towards the eye
+        let toward_eye = [
+            pitch.cos() * yaw.cos(),
+            pitch.cos() * yaw.sin(),
+            pitch.sin(),
+        ];
+        let eye = [
+            view.target[0] + toward_eye[0] * view.distance,
+            view.target[1] + toward_eye[1] * view.distance,
+            view.target[2] + toward_eye[2] * view.distance,
+        ];
+        let aspect = width as f32 / height.max(1) as f32;
+        let projection = perspective(
+            FIELD_OF_VIEW.to_radians(),
+            aspect,
+            view.distance * 0.01,
+            view.distance * 10.0,
+        );
+        let view_projection = multiply(&projection, &look_at(eye, view.target, [0.0, 0.0, 1.0]));
+
+        let program = &self.programs.mesh;
+        self.gl.use_program(Some(&program.program));
+        self.gl.uniform_matrix4fv_with_f32_array(
+            program.uniforms.get("u_view_projection"),
+            false,
+            &view_projection,
+        );
+        // Light comes from the viewer
+        self.gl.uniform3f(
+            program.uniforms.get("u_light_dir"),
+            toward_eye[0],
+            toward_eye[1],
+            toward_eye[2],
+        );
+
+        self.gl.enable(DEPTH_TEST);
+        self.gl.enable(BLEND);
+        self.gl.blend_func(ONE, ONE_MINUS_SRC_ALPHA);
+        self.gl.blend_equation(FUNC_ADD);
+        for translucent in [false, true] {
+            self.gl.depth_mask(!translucent);
+            for mesh in &view.meshes {
+                let color = [
+                    mesh.color[0],
+                    mesh.color[1],
+                    mesh.color[2],
+                    mesh.color[3] * alpha,
+                ];
+                if (color[3] < 1.0) != translucent {
+                    continue;
+                }
+                if mesh
+                    .layer_id
+                    .is_some_and(|id| !active_layer_ids.contains(&id))
+                {
+                    continue;
+                }
+                self.gl
+                    .uniform4fv_with_f32_array(program.uniforms.get("color"), &color);
+                self.gl.bind_vertex_array(Some(&mesh.vao));
+                self.gl.draw_arrays(TRIANGLES, 0, mesh.vertex_count);
+            }
+        }
+        self.gl.bind_vertex_array(None);
+        self.gl.depth_mask(true);
+        self.gl.disable(DEPTH_TEST);
+        self.gl.disable(BLEND);
+        Ok(())
+    }
+
+    /// Create and bind a per-vertex buffer with three components
+    fn create_vertex_buffer_3d(
+        gl: &WebGl2RenderingContext,
+        data: &[f32],
+        program: &ShaderProgram,
+        attr_name: &str,
+    ) -> Result<WebGlBuffer, JsValue> {
+        let buffer = gl
+            .create_buffer()
+            .ok_or_else(|| ViewerError::webgl("Failed to create buffer"))?;
+        gl.bind_buffer(ARRAY_BUFFER, Some(&buffer));
+        unsafe {
+            let array = Float32Array::view(data);
+            gl.buffer_data_with_array_buffer_view(ARRAY_BUFFER, &array, STATIC_DRAW);
+        }
+        let loc = program.attributes.get(attr_name).ok_or_else(|| {
+            ViewerError::webgl(format!("Missing shader attribute: {}", attr_name))
+        })?;
+        gl.enable_vertex_attrib_array(*loc);
+        gl.vertex_attrib_pointer_with_i32(*loc, 3, FLOAT, false, 0, 0);
+        Ok(buffer)
+    }
+}
+
+/// Column-major perspective projection
+fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> [f32; 16] {
+    let f = 1.0 / (fov_y / 2.0).tan();
+    let range = near - far;
+    [
+        f / aspect,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        f,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        (far + near) / range,
+        -1.0,
+        0.0,
+        0.0,
+        2.0 * far * near / range,
+        0.0,
+    ]
+}
+
+/// Column-major view matrix looking from `eye` at `target`
+fn look_at(eye: [f32; 3], target: [f32; 3], up: [f32; 3]) -> [f32; 16] {
+    let forward = normalize(sub(target, eye));
+    let side = normalize(cross(forward, up));
+    let up = cross(side, forward);
+    [
+        side[0],
+        up[0],
+        -forward[0],
+        0.0,
+        side[1],
+        up[1],
+        -forward[1],
+        0.0,
+        side[2],
+        up[2],
+        -forward[2],
+        0.0,
+        -dot(side, eye),
+        -dot(up, eye),
+        dot(forward, eye),
+        1.0,
+    ]
+}
+
+/// Product of two column-major 4x4 matrices (a * b)
+fn multiply(a: &[f32; 16], b: &[f32; 16]) -> [f32; 16] {
+    let mut result = [0.0; 16];
+    for column in 0..4 {
+        for row in 0..4 {
+            result[column * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[column * 4 + k]).sum();
+        }
+    }
+    result
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = dot(v, v).sqrt().max(f32::EPSILON);
+    [v[0] / length, v[1] / length, v[2] / length]
+}
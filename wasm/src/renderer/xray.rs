@@ -0,0 +1,89 @@
+use super::shader::{BLEND, FUNC_ADD, ONE, ONE_MINUS_SRC_ALPHA};
+use super::Renderer;
+use crate::error::ViewerError;
+use wasm_bindgen::prelude::*;
+
+/// Copper layers drawn translucently in stackup order
+pub struct XrayMode {
+    pub layer_ids: Vec<usize>, // Top to bottom
+    pub alpha: f32,
+}
+
+impl Renderer {
+    /// Composite `layer_ids` (top to bottom) translucently over each other
+    ///
+    /// Each layer is drawn at `alpha` with "over" blending, so a deeper
+    /// layer shows through the ones above it dimmed instead of adding up.
+    pub fn set_xray(&mut self, layer_ids: &[usize], alpha: f32) -> Result<(), JsValue> {
+        if !(alpha > 0.0 && alpha <= 1.0) {
+            return Err(
+                ViewerError::invalid_argument("X-ray alpha must be in the range (0, 1]").into(),
+            );
+        }
+        if layer_ids.is_empty() {
+            return Err(ViewerError::invalid_argument("X-ray view needs a copper layer").into());
+        }
+        for &layer_id in layer_ids {
+            self.get_layer(layer_id)?;
+        }
+        self.xray = Some(XrayMode {
+            layer_ids: layer_ids.to_vec(),
+            alpha,
+        });
+        Ok(())
+    }
+
+    /// Leave the X-ray view and composite every layer additively again
+    pub fn clear_xray(&mut self) {
+        self.xray = None;
+    }
+
+    /// Whether a layer is composited by composite_xray() instead of additively
+    pub(super) fn in_xray(&self, layer_id: usize) -> bool {
+        self.xray
+            .as_ref()
+            .is_some_and(|x| x.layer_ids.contains(&layer_id))
+    }
+
+    /// Draw the active X-ray layers from the bottom up over the output
+    ///
+    /// Expects the output framebuffer to be bound; leaves blending disabled.
+    pub(super) fn composite_xray(
+        &self,
+        active_layer_ids: &[u32],
+        color_data: &[f32],
+    ) -> Result<(), JsValue> {
+        let xray = match &self.xray {
+            Some(xray) => xray,
+            None => return Ok(()),
+        };
+
+        // Premultiplied "over": each layer covers alpha of what is below it
+        self.gl.enable(BLEND);
+        self.gl.blend_func(ONE, ONE_MINUS_SRC_ALPHA);
+        self.gl.blend_equation(FUNC_ADD);
+
+        for &layer_id in xray.layer_ids.iter().rev() {
+            // Colors are given per active layer; inactive layers stay hidden
+            let color_index = match active_layer_ids
+                .iter()
+                .position(|&id| id as usize == layer_id)
+            {
+                Some(index) => index,
+                None => continue,
+            };
+            let rgb = match color_data.get(color_index * 3..color_index * 3 + 3) {
+                Some(rgb) => rgb,
+                None => continue,
+            };
+            if let Some(layer) = &self.layers[layer_id] {
+                let color = [rgb[0], rgb[1], rgb[2], xray.alpha];
+                let by_function = self.colors_by_function(layer_id);
+                self.draw_fbo_texture(&layer.fbo.texture, &color, by_function)?;
+            }
+        }
+
+        self.gl.disable(BLEND);
+        Ok(())
+    }
+}
@@ -0,0 +1,690 @@
+mod bindings;
+mod shader;
+
+// Export only what's needed externally
+pub use bindings::{GpuCanvasContext, GpuDevice};
+
+// Internal use only
+use bindings::{
+    array, object, GpuBuffer, GpuQueue, GpuRenderPass, GpuTexture, BUFFER_COPY_DST, BUFFER_INDEX,
+    BUFFER_UNIFORM, BUFFER_VERTEX, STAGE_FRAGMENT, STAGE_VERTEX, TEXTURE_BINDING,
+    TEXTURE_RENDER_ATTACHMENT,
+};
+use shader::{
+    ARC_SHADER, CIRCLE_SHADER, COMPOSITE_SHADER, LAYER_UNIFORMS, OBROUND_SHADER, THERMAL_SHADER,
+    TRIANGLE_SHADER,
+};
+
+use crate::events::json_parse;
+use crate::parser::Polarity;
+use crate::renderer::camera::Camera;
+use crate::renderer::canvas_size;
+use crate::shape::{Boundary, GerberData};
+use crate::store::LayerStore;
+use crate::view::CameraView;
+use js_sys::Object;
+use serde_json::json;
+use wasm_bindgen::prelude::*;
+
+// Layer coverage textures (alpha = coverage, like the WebGL FBO textures)
+const LAYER_FORMAT: &str = "rgba8unorm";
+
+// Two triangles covering [-1, 1]², shared by instanced shapes and compositing
+const QUAD: [f32; 12] = [
+    -1.0, -1.0, 1.0, -1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0, 1.0,
+];
+
+/// Instance attributes of an instanced shape: (shader location, byte offset, format)
+type Attributes = &'static [(u32, u32, &'static str)];
+
+/// Instanced shapes in drawing order, after the triangles
+const INSTANCED: [(&str, Attributes); 4] = [
+    (
+        CIRCLE_SHADER,
+        &[
+            (1, 0, "float32x2"),
+            (2, 8, "float32"),
+            (3, 12, "float32x2"),
+            (4, 20, "float32"),
+        ],
+    ),
+    (
+        ARC_SHADER,
+        &[
+            (1, 0, "float32x2"),
+            (2, 8, "float32"),
+            (3, 12, "float32"),
+            (4, 16, "float32"),
+            (5, 20, "float32"),
+        ],
+    ),
+    (
+        THERMAL_SHADER,
+        &[
+            (1, 0, "float32x2"),
+            (2, 8, "float32"),
+            (3, 12, "float32"),
+            (4, 16, "float32"),
+            (5, 20, "float32"),
+        ],
+    ),
+    (
+        OBROUND_SHADER,
+        &[
+            (1, 0, "float32x2"),
+            (2, 8, "float32x2"),
+            (3, 16, "float32"),
+            (4, 20, "float32x2"),
+            (5, 28, "float32"),
+        ],
+    ),
+];
+
+/// Dark and clear polarity variants of a geometry pipeline
+struct PolarityPipelines {
+    dark: JsValue,
+    clear: JsValue,
+}
+
+impl PolarityPipelines {
+    fn get(&self, negative: bool) -> &JsValue {
+        if negative {
+            &self.clear
+        } else {
+            &self.dark
+        }
+    }
+}
+
+/// Triangle buffers of one polarity sublayer
+struct TriangleBuffers {
+    positions: GpuBuffer,
+    holes: GpuBuffer, // Per-vertex [hole_x, hole_y, hole_radius]
+    indices: GpuBuffer,
+    index_count: u32,
+}
+
+/// Instance buffer of one instanced shape kind
+struct InstanceBuffer {
+    kind: usize, // Index into INSTANCED
+    buffer: GpuBuffer,
+    count: u32,
+}
+
+/// GPU buffers of one polarity sublayer
+struct SublayerBuffers {
+    triangles: Option<TriangleBuffers>,
+    instances: Vec<InstanceBuffer>,
+}
+
+/// GPU resources of a single user layer
+struct GpuLayer {
+    texture: GpuTexture,
+    view: JsValue,
+    size: (u32, u32),
+    color: GpuBuffer,                        // Composite color uniform
+    composite_group: JsValue,                // Binds color, sampler and texture for compositing
+    sublayers: Option<Vec<SublayerBuffers>>, // None = upload from the store on next render
+}
+
+/// WebGPU renderer with the same layer and composite semantics as the WebGL one
+///
+/// Each layer is drawn white into its own coverage texture, dark polarity
+/// adding and clear polarity erasing coverage, and the textures are added to
+/// the canvas in the layer colors. Geometry is uploaded on first render.
+pub struct WebGpuRenderer {
+    device: GpuDevice,
+    queue: GpuQueue,
+    context: GpuCanvasContext,
+    frame: GpuBuffer,     // Transform and level of detail uniform
+    frame_group: JsValue, // Bind group of the frame uniform
+    triangle: PolarityPipelines,
+    instanced: Vec<PolarityPipelines>, // Same order as INSTANCED
+    composite: JsValue,
+    composite_layout: JsValue,
+    sampler: JsValue,
+    quad: GpuBuffer,
+    layers: Vec<Option<GpuLayer>>, // By layer ID, sparse like the layer store
+    camera: Camera,
+    min_feature_pixels: f32,
+}
+
+impl WebGpuRenderer {
+    /// Configure `context` for `device` and create pipelines
+    ///
+    /// `format` is the canvas format, navigator.gpu.getPreferredCanvasFormat().
+    pub fn new(
+        device: GpuDevice,
+        context: GpuCanvasContext,
+        format: &str,
+    ) -> Result<WebGpuRenderer, JsValue> {
+        context.configure(&object(&[
+            ("device", device.clone()),
+            ("format", format.into()),
+            ("alphaMode", "premultiplied".into()),
+        ]))?;
+
+        let frame_layout = device.create_bind_group_layout(&json(json!({
+            "entries": [{
+                "binding": 0,
+                "visibility": STAGE_VERTEX | STAGE_FRAGMENT,
+                "buffer": { "type": "uniform" },
+            }],
+        })));
+        let geometry_layout = device.create_pipeline_layout(&object(&[(
+            "bindGroupLayouts",
+            array([frame_layout.clone()]),
+        )]));
+        let composite_layout = device.create_bind_group_layout(&json(json!({
+            "entries": [
+                { "binding": 0, "visibility": STAGE_FRAGMENT, "buffer": {} },
+                { "binding": 1, "visibility": STAGE_FRAGMENT, "sampler": {} },
+                { "binding": 2, "visibility": STAGE_FRAGMENT, "texture": {} },
+            ],
+        })));
+
+        let quad_layout = json!({
+            "arrayStride": 8,
+            "attributes": [{ "shaderLocation": 0, "offset": 0, "format": "float32x2" }],
+        });
+        let triangle_layouts = json!([
+            quad_layout,
+            {
+                "arrayStride": 12,
+                "attributes": [
+                    { "shaderLocation": 1, "offset": 0, "format": "float32x2" },
+                    { "shaderLocation": 2, "offset": 8, "format": "float32" },
+                ],
+            },
+        ]);
+        let triangle =
+            Self::geometry_pipelines(&device, &geometry_layout, TRIANGLE_SHADER, triangle_layouts)?;
+        let instanced = INSTANCED
+            .iter()
+            .map(|(source, attributes)| {
+                let instance_layout = json!({
+                    "arrayStride": instance_stride(attributes) * 4,
+                    "stepMode": "instance",
+                    "attributes": attributes
+                        .iter()
+                        .map(|&(location, offset, format)| json!({
+                            "shaderLocation": location,
+                            "offset": offset,
+                            "format": format,
+                        }))
+                        .collect::<Vec<_>>(),
+                });
+                Self::geometry_pipelines(
+                    &device,
+                    &geometry_layout,
+                    source,
+                    json!([quad_layout, instance_layout]),
+                )
+            })
+            .collect::<Result<Vec<_>, JsValue>>()?;
+
+        let composite = device.create_render_pipeline(&Self::pipeline_descriptor(
+            &device.create_pipeline_layout(&object(&[(
+                "bindGroupLayouts",
+                array([composite_layout.clone()]),
+            )])),
+            &device.create_shader_module(&object(&[("code", COMPOSITE_SHADER.into())])),
+            json!([quad_layout]),
+            json!({
+                "format": format,
+                "blend": {
+                    "color": { "srcFactor": "one", "dstFactor": "one" },
+                    "alpha": { "srcFactor": "one", "dstFactor": "one" },
+                },
+            }),
+        ))?;
+
+        let queue = device.queue();
+        let frame = create_buffer(&device, 64, BUFFER_UNIFORM | BUFFER_COPY_DST);
+        let frame_group = device.create_bind_group(&object(&[
+            ("layout", frame_layout),
+            (
+                "entries",
+                array([binding(0, object(&[("buffer", frame.clone())]).into())]),
+            ),
+        ]));
+        let quad = create_buffer(&device, QUAD.len() * 4, BUFFER_VERTEX | BUFFER_COPY_DST);
+        queue.write_buffer_f32(&quad, 0.0, &QUAD);
+        let sampler = device.create_sampler(&json(json!({
+            "magFilter": "linear",
+            "minFilter": "linear",
+        })));
+
+        Ok(WebGpuRenderer {
+            device,
+            queue,
+            context,
+            frame,
+            frame_group,
+            triangle,
+            instanced,
+            composite,
+            composite_layout,
+            sampler,
+            quad,
+            layers: Vec::new(),
+            camera: Camera::new(),
+            min_feature_pixels: 0.0,
+        })
+    }
+
+    /// Dark and clear polarity pipelines drawing white into a layer texture
+    fn geometry_pipelines(
+        device: &GpuDevice,
+        layout: &JsValue,
+        source: &str,
+        buffers: serde_json::Value,
+    ) -> Result<PolarityPipelines, JsValue> {
+        let module = device.create_shader_module(&object(&[(
+            "code",
+            format!("{}{}", LAYER_UNIFORMS, source).into(),
+        )]));
+        // Dark polarity adds coverage, clear polarity erases it
+        let pipeline = |alpha: serde_json::Value| {
+            device.create_render_pipeline(&Self::pipeline_descriptor(
+                layout,
+                &module,
+                buffers.clone(),
+                json!({
+                    "format": LAYER_FORMAT,
+                    "blend": {
+                        "color": { "srcFactor": "zero", "dstFactor": "one" },
+                        "alpha": alpha,
+                    },
+                }),
+            ))
+        };
+        Ok(PolarityPipelines {
+            dark: pipeline(json!({ "srcFactor": "one", "dstFactor": "one" }))?,
+            clear: pipeline(json!({ "srcFactor": "zero", "dstFactor": "one-minus-src-alpha" }))?,
+        })
+    }
+
+    fn pipeline_descriptor(
+        layout: &JsValue,
+        module: &JsValue,
+        buffers: serde_json::Value,
+        target: serde_json::Value,
+    ) -> Object {
+        object(&[
+            ("layout", layout.clone()),
+            (
+                "vertex",
+                object(&[
+                    ("module", module.clone()),
+                    ("entryPoint", "vs_main".into()),
+                    ("buffers", json_parse(&buffers.to_string())),
+                ])
+                .into(),
+            ),
+            (
+                "fragment",
+                object(&[
+                    ("module", module.clone()),
+                    ("entryPoint", "fs_main".into()),
+                    ("targets", json_parse(&json!([target]).to_string())),
+                ])
+                .into(),
+            ),
+            (
+                "primitive",
+                json(json!({ "topology": "triangle-list" })).into(),
+            ),
+        ])
+    }
+
+    /// Pixel size of the canvas rendered to
+    pub fn canvas_size(&self) -> Result<(u32, u32), JsValue> {
+        canvas_size(&self.context.canvas())
+    }
+
+    /// Camera view fitting `bounds` into the canvas
+    pub fn view_for_bounds(&self, bounds: &Boundary) -> Result<CameraView, JsValue> {
+        let (width, height) = canvas_size(&self.context.canvas())?;
+        Ok(CameraView::fit(bounds, width, height))
+    }
+
+    /// Level of detail: smallest flash or arc drawn, in canvas pixels
+    pub fn set_min_feature_pixels(&mut self, pixels: f32) {
+        self.min_feature_pixels = pixels;
+    }
+
+    /// Free the GPU resources of a layer
+    pub fn remove_layer(&mut self, layer_id: usize) {
+        if let Some(layer) = self.layers.get_mut(layer_id).and_then(Option::take) {
+            Self::delete_layer(layer);
+        }
+    }
+
+    /// Re-upload a layer's geometry on the next render after it changed
+    pub fn reset_layer_buffers(&mut self, layer_id: usize) {
+        if let Some(layer) = self.layers.get_mut(layer_id).and_then(Option::as_mut) {
+            if let Some(sublayers) = layer.sublayers.take() {
+                Self::delete_sublayers(sublayers);
+            }
+        }
+    }
+
+    /// Free the GPU resources of all layers
+    pub fn clear_all(&mut self) {
+        for layer in self.layers.drain(..).flatten() {
+            Self::delete_layer(layer);
+        }
+    }
+
+    /// Free every GPU object owned by the renderer and release the canvas
+    pub fn dispose(mut self) {
+        self.clear_all();
+        self.frame.destroy();
+        self.quad.destroy();
+        self.context.unconfigure();
+    }
+
+    fn delete_layer(layer: GpuLayer) {
+        layer.texture.destroy_texture();
+        layer.color.destroy();
+        if let Some(sublayers) = layer.sublayers {
+            Self::delete_sublayers(sublayers);
+        }
+    }
+
+    fn delete_sublayers(sublayers: Vec<SublayerBuffers>) {
+        for sublayer in sublayers {
+            if let Some(triangles) = sublayer.triangles {
+                triangles.positions.destroy();
+                triangles.holes.destroy();
+                triangles.indices.destroy();
+            }
+            for instances in sublayer.instances {
+                instances.buffer.destroy();
+            }
+        }
+    }
+
+    /// Render layers to their textures and composite them to the canvas
+    #[allow(clippy::too_many_arguments)]
+    pub fn render(
+        &mut self,
+        store: &LayerStore,
+        active_layer_ids: &[u32],
+        color_data: &[f32],
+        zoom: f32,
+        offset_x: f32,
+        offset_y: f32,
+        alpha: f32,
+    ) -> Result<(), JsValue> {
+        self.camera.zoom = zoom;
+        self.camera.offset_x = offset_x;
+        self.camera.offset_y = offset_y;
+
+        let (width, height) = canvas_size(&self.context.canvas())?;
+        let (width, height) = (width.max(1), height.max(1));
+        let t = self.camera.get_transform_matrix(width, height);
+        // Level of detail threshold in board units (clip space spans 2 units)
+        let min_size = if self.min_feature_pixels > 0.0 {
+            self.min_feature_pixels * 2.0 / (t[0] * width as f32)
+        } else {
+            0.0
+        };
+        // mat3x3 columns are padded to 16 bytes in uniform buffers
+        let frame = [
+            t[0], t[1], t[2], 0.0, t[3], t[4], t[5], 0.0, t[6], t[7], t[8], 0.0, min_size, 0.0,
+            0.0, 0.0,
+        ];
+        self.queue.write_buffer_f32(&self.frame, 0.0, &frame);
+
+        // Create textures and upload geometry of the layers drawn this frame
+        for &layer_id in active_layer_ids {
+            let sublayers = store.get(layer_id as usize)?;
+            self.prepare_layer(layer_id as usize, sublayers, (width, height));
+        }
+
+        let encoder = self.device.create_command_encoder();
+
+        // STEP 1: Render each active layer's geometry to its texture (white)
+        for &layer_id in active_layer_ids {
+            let Some(layer) = self.layers[layer_id as usize].as_ref() else {
+                continue;
+            };
+            // A negative image (%IPNEG) starts fully dark and swaps the polarities
+            let inverted = store.image_polarity(layer_id as usize) == Polarity::Negative;
+            let pass = encoder.begin_render_pass(&render_pass(&layer.view, inverted));
+            pass.set_bind_group(0, &self.frame_group);
+            for (sublayer_idx, buffers) in layer.sublayers.iter().flatten().enumerate() {
+                // Even index = positive, odd index = negative polarity
+                self.draw_sublayer(&pass, buffers, (sublayer_idx % 2 == 1) != inverted);
+            }
+            pass.end();
+        }
+
+        // STEP 2: Add the layer textures to the canvas in their colors
+        let pass = encoder.begin_render_pass(&render_pass(
+            &self.context.get_current_texture().create_view(),
+            false,
+        ));
+        pass.set_pipeline(&self.composite);
+        pass.set_vertex_buffer(0, &self.quad);
+        for (color_index, &layer_id) in active_layer_ids.iter().enumerate() {
+            let Some(layer) = self.layers[layer_id as usize].as_ref() else {
+                continue;
+            };
+            if let Some(rgb) = color_data.get(color_index * 3..color_index * 3 + 3) {
+                let color = [rgb[0], rgb[1], rgb[2], alpha];
+                self.queue.write_buffer_f32(&layer.color, 0.0, &color);
+                pass.set_bind_group(0, &layer.composite_group);
+                pass.draw(6, 1);
+            }
+        }
+        pass.end();
+
+        self.queue.submit(&js_sys::Array::of1(&encoder.finish()));
+        Ok(())
+    }
+
+    /// Make sure a layer has a texture of the canvas size and uploaded geometry
+    fn prepare_layer(&mut self, layer_id: usize, sublayers: &[GerberData], size: (u32, u32)) {
+        if layer_id >= self.layers.len() {
+            self.layers.resize_with(layer_id + 1, || None);
+        }
+        if self.layers[layer_id]
+            .as_ref()
+            .is_some_and(|layer| layer.size != size)
+        {
+            // Canvas resized: keep the geometry, replace the texture
+            let mut layer = self.layers[layer_id].take().unwrap();
+            let sublayers = layer.sublayers.take();
+            Self::delete_layer(layer);
+            let mut layer = self.create_layer(size);
+            layer.sublayers = sublayers;
+            self.layers[layer_id] = Some(layer);
+        }
+        if self.layers[layer_id].is_none() {
+            self.layers[layer_id] = Some(self.create_layer(size));
+        }
+        let device = &self.device;
+        let queue = &self.queue;
+        let layer = self.layers[layer_id].as_mut().unwrap();
+        if layer.sublayers.is_none() {
+            layer.sublayers = Some(
+                sublayers
+                    .iter()
+                    .map(|data| upload_sublayer(device, queue, data))
+                    .collect(),
+            );
+        }
+    }
+
+    fn create_layer(&self, (width, height): (u32, u32)) -> GpuLayer {
+        let texture = self.device.create_texture(&object(&[
+            ("size", array([width.into(), height.into()])),
+            ("format", LAYER_FORMAT.into()),
+            (
+                "usage",
+                (TEXTURE_BINDING | TEXTURE_RENDER_ATTACHMENT).into(),
+            ),
+        ]));
+        let view = texture.create_view();
+        let color = create_buffer(&self.device, 16, BUFFER_UNIFORM | BUFFER_COPY_DST);
+        let composite_group = self.device.create_bind_group(&object(&[
+            ("layout", self.composite_layout.clone()),
+            (
+                "entries",
+                array([
+                    binding(0, object(&[("buffer", color.clone())]).into()),
+                    binding(1, self.sampler.clone()),
+                    binding(2, view.clone()),
+                ]),
+            ),
+        ]));
+        GpuLayer {
+            texture,
+            view,
+            size: (width, height),
+            color,
+            composite_group,
+            sublayers: None,
+        }
+    }
+
+    fn draw_sublayer(&self, pass: &GpuRenderPass, buffers: &SublayerBuffers, negative: bool) {
+        if let Some(triangles) = &buffers.triangles {
+            pass.set_pipeline(self.triangle.get(negative));
+            pass.set_vertex_buffer(0, &triangles.positions);
+            pass.set_vertex_buffer(1, &triangles.holes);
+            pass.set_index_buffer(&triangles.indices, "uint32");
+            pass.draw_indexed(triangles.index_count);
+        }
+        for instances in &buffers.instances {
+            pass.set_pipeline(self.instanced[instances.kind].get(negative));
+            pass.set_vertex_buffer(0, &self.quad);
+            pass.set_vertex_buffer(1, &instances.buffer);
+            pass.draw(6, instances.count);
+        }
+    }
+}
+
+/// Upload the geometry of one polarity sublayer
+fn upload_sublayer(device: &GpuDevice, queue: &GpuQueue, data: &GerberData) -> SublayerBuffers {
+    let vertex_buffer = |values: &[f32]| {
+        let buffer = create_buffer(device, values.len() * 4, BUFFER_VERTEX | BUFFER_COPY_DST);
+        queue.write_buffer_f32(&buffer, 0.0, values);
+        buffer
+    };
+
+    let t = &data.triangles;
+    let triangles = (!t.indices.is_empty()).then(|| {
+        let indices = create_buffer(device, t.indices.len() * 4, BUFFER_INDEX | BUFFER_COPY_DST);
+        queue.write_buffer_u32(&indices, 0.0, &t.indices);
+        TriangleBuffers {
+            positions: vertex_buffer(&t.vertices),
+            holes: vertex_buffer(&interleave(&[&t.hole_x, &t.hole_y, &t.hole_radius])),
+            indices,
+            index_count: t.indices.len() as u32,
+        }
+    });
+
+    let (c, a, th, o) = (&data.circles, &data.arcs, &data.thermals, &data.obrounds);
+    let columns: [&[&[f32]]; 4] = [
+        &[&c.x, &c.y, &c.radius, &c.hole_x, &c.hole_y, &c.hole_radius],
+        &[
+            &a.x,
+            &a.y,
+            &a.radius,
+            &a.start_angle,
+            &a.sweep_angle,
+            &a.thickness,
+        ],
+        &[
+            &th.x,
+            &th.y,
+            &th.outer_diameter,
+            &th.inner_diameter,
+            &th.gap_thickness,
+            &th.rotation,
+        ],
+        &[
+            &o.x,
+            &o.y,
+            &o.width,
+            &o.height,
+            &o.rotation,
+            &o.hole_x,
+            &o.hole_y,
+            &o.hole_radius,
+        ],
+    ];
+    let instances = columns
+        .iter()
+        .enumerate()
+        .filter(|(_, columns)| !columns[0].is_empty())
+        .map(|(kind, columns)| InstanceBuffer {
+            kind,
+            buffer: vertex_buffer(&interleave(columns)),
+            count: columns[0].len() as u32,
+        })
+        .collect();
+
+    SublayerBuffers {
+        triangles,
+        instances,
+    }
+}
+
+/// Floats per instance of an instanced shape
+fn instance_stride(attributes: Attributes) -> u32 {
+    attributes
+        .iter()
+        .map(|&(_, offset, format)| offset / 4 + if format == "float32x2" { 2 } else { 1 })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Interleave equally long attribute columns into one vertex array
+fn interleave(columns: &[&[f32]]) -> Vec<f32> {
+    let count = columns.first().map_or(0, |column| column.len());
+    let mut values = Vec::with_capacity(count * columns.len());
+    for i in 0..count {
+        values.extend(columns.iter().map(|column| column[i]));
+    }
+    values
+}
+
+fn create_buffer(device: &GpuDevice, size: usize, usage: u32) -> GpuBuffer {
+    device.create_buffer(&object(&[
+        ("size", (size.max(4) as u32).into()),
+        ("usage", usage.into()),
+    ]))
+}
+
+/// Bind group entry
+fn binding(index: u32, resource: JsValue) -> JsValue {
+    object(&[("binding", index.into()), ("resource", resource)]).into()
+}
+
+/// Render pass clearing `view` to transparent, or to opaque white when `filled`
+fn render_pass(view: &JsValue, filled: bool) -> Object {
+    let clear = if filled { 1.0 } else { 0.0 };
+    object(&[(
+        "colorAttachments",
+        array([object(&[
+            ("view", view.clone()),
+            (
+                "clearValue",
+                json(json!([clear, clear, clear, clear])).into(),
+            ),
+            ("loadOp", "clear".into()),
+            ("storeOp", "store".into()),
+        ])
+        .into()]),
+    )])
+}
+
+/// JS object from a JSON value
+fn json(value: serde_json::Value) -> Object {
+    json_parse(&value.to_string()).unchecked_into()
+}
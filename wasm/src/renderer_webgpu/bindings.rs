@@ -0,0 +1,122 @@
+//! Minimal WebGPU bindings for the calls the renderer makes
+//!
+//! web-sys only exposes WebGPU behind `--cfg=web_sys_unstable_apis`, so the
+//! few interfaces used here are declared directly. Descriptors are plain JS
+//! objects built with object().
+
+use js_sys::{Array, Object, Reflect};
+use wasm_bindgen::prelude::*;
+
+// GPUBufferUsage flags
+pub const BUFFER_INDEX: u32 = 0x10;
+pub const BUFFER_VERTEX: u32 = 0x20;
+pub const BUFFER_UNIFORM: u32 = 0x40;
+pub const BUFFER_COPY_DST: u32 = 0x08;
+
+// GPUTextureUsage flags
+pub const TEXTURE_BINDING: u32 = 0x04;
+pub const TEXTURE_RENDER_ATTACHMENT: u32 = 0x10;
+
+// GPUShaderStage flags
+pub const STAGE_VERTEX: u32 = 0x1;
+pub const STAGE_FRAGMENT: u32 = 0x2;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "GPUDevice")]
+    pub type GpuDevice;
+    #[wasm_bindgen(method, getter)]
+    pub fn queue(this: &GpuDevice) -> GpuQueue;
+    #[wasm_bindgen(method, js_name = createBuffer)]
+    pub fn create_buffer(this: &GpuDevice, descriptor: &Object) -> GpuBuffer;
+    #[wasm_bindgen(method, js_name = createTexture)]
+    pub fn create_texture(this: &GpuDevice, descriptor: &Object) -> GpuTexture;
+    #[wasm_bindgen(method, js_name = createSampler)]
+    pub fn create_sampler(this: &GpuDevice, descriptor: &Object) -> JsValue;
+    #[wasm_bindgen(method, js_name = createShaderModule)]
+    pub fn create_shader_module(this: &GpuDevice, descriptor: &Object) -> JsValue;
+    #[wasm_bindgen(method, js_name = createBindGroupLayout)]
+    pub fn create_bind_group_layout(this: &GpuDevice, descriptor: &Object) -> JsValue;
+    #[wasm_bindgen(method, js_name = createPipelineLayout)]
+    pub fn create_pipeline_layout(this: &GpuDevice, descriptor: &Object) -> JsValue;
+    #[wasm_bindgen(method, catch, js_name = createRenderPipeline)]
+    pub fn create_render_pipeline(
+        this: &GpuDevice,
+        descriptor: &Object,
+    ) -> Result<JsValue, JsValue>;
+    #[wasm_bindgen(method, js_name = createBindGroup)]
+    pub fn create_bind_group(this: &GpuDevice, descriptor: &Object) -> JsValue;
+    #[wasm_bindgen(method, js_name = createCommandEncoder)]
+    pub fn create_command_encoder(this: &GpuDevice) -> GpuCommandEncoder;
+
+    #[wasm_bindgen(typescript_type = "GPUQueue")]
+    pub type GpuQueue;
+    #[wasm_bindgen(method, js_name = writeBuffer)]
+    pub fn write_buffer_f32(this: &GpuQueue, buffer: &GpuBuffer, offset: f64, data: &[f32]);
+    #[wasm_bindgen(method, js_name = writeBuffer)]
+    pub fn write_buffer_u32(this: &GpuQueue, buffer: &GpuBuffer, offset: f64, data: &[u32]);
+    #[wasm_bindgen(method)]
+    pub fn submit(this: &GpuQueue, command_buffers: &Array);
+
+    #[wasm_bindgen(typescript_type = "GPUBuffer")]
+    pub type GpuBuffer;
+    #[wasm_bindgen(method)]
+    pub fn destroy(this: &GpuBuffer);
+
+    #[wasm_bindgen(typescript_type = "GPUTexture")]
+    pub type GpuTexture;
+    #[wasm_bindgen(method, js_name = createView)]
+    pub fn create_view(this: &GpuTexture) -> JsValue;
+    #[wasm_bindgen(method, js_name = destroy)]
+    pub fn destroy_texture(this: &GpuTexture);
+
+    #[wasm_bindgen(typescript_type = "GPUCanvasContext")]
+    pub type GpuCanvasContext;
+    #[wasm_bindgen(method, getter)]
+    pub fn canvas(this: &GpuCanvasContext) -> Object;
+    #[wasm_bindgen(method, catch)]
+    pub fn configure(this: &GpuCanvasContext, configuration: &Object) -> Result<(), JsValue>;
+    #[wasm_bindgen(method)]
+    pub fn unconfigure(this: &GpuCanvasContext);
+    #[wasm_bindgen(method, js_name = getCurrentTexture)]
+    pub fn get_current_texture(this: &GpuCanvasContext) -> GpuTexture;
+
+    #[wasm_bindgen(typescript_type = "GPUCommandEncoder")]
+    pub type GpuCommandEncoder;
+    #[wasm_bindgen(method, js_name = beginRenderPass)]
+    pub fn begin_render_pass(this: &GpuCommandEncoder, descriptor: &Object) -> GpuRenderPass;
+    #[wasm_bindgen(method)]
+    pub fn finish(this: &GpuCommandEncoder) -> JsValue;
+
+    #[wasm_bindgen(typescript_type = "GPURenderPassEncoder")]
+    pub type GpuRenderPass;
+    #[wasm_bindgen(method, js_name = setPipeline)]
+    pub fn set_pipeline(this: &GpuRenderPass, pipeline: &JsValue);
+    #[wasm_bindgen(method, js_name = setBindGroup)]
+    pub fn set_bind_group(this: &GpuRenderPass, index: u32, bind_group: &JsValue);
+    #[wasm_bindgen(method, js_name = setVertexBuffer)]
+    pub fn set_vertex_buffer(this: &GpuRenderPass, slot: u32, buffer: &GpuBuffer);
+    #[wasm_bindgen(method, js_name = setIndexBuffer)]
+    pub fn set_index_buffer(this: &GpuRenderPass, buffer: &GpuBuffer, format: &str);
+    #[wasm_bindgen(method)]
+    pub fn draw(this: &GpuRenderPass, vertex_count: u32, instance_count: u32);
+    #[wasm_bindgen(method, js_name = drawIndexed)]
+    pub fn draw_indexed(this: &GpuRenderPass, index_count: u32);
+    #[wasm_bindgen(method)]
+    pub fn end(this: &GpuRenderPass);
+}
+
+/// Plain JS object with the given properties
+pub fn object(properties: &[(&str, JsValue)]) -> Object {
+    let object = Object::new();
+    for (key, value) in properties {
+        // Setting a property on a fresh plain object cannot fail
+        let _ = Reflect::set(&object, &JsValue::from_str(key), value);
+    }
+    object
+}
+
+/// JS array of the given values
+pub fn array(values: impl IntoIterator<Item = JsValue>) -> JsValue {
+    values.into_iter().collect::<Array>().into()
+}
@@ -0,0 +1,302 @@
+// WGSL ports of the WebGL layer and composite shaders
+//
+// Layer geometry is drawn white into a per-layer texture; the polarity is
+// applied by the pipeline's blend state, exactly as in the WebGL renderer.
+
+/// Camera transform and level of detail, shared by all geometry shaders
+pub const LAYER_UNIFORMS: &str = r#"
+struct Frame {
+    transform: mat3x3<f32>,
+    min_size: f32,
+}
+@group(0) @binding(0) var<uniform> frame: Frame;
+
+fn to_clip(world: vec2<f32>) -> vec4<f32> {
+    let transformed = frame.transform * vec3<f32>(world, 1.0);
+    return vec4<f32>(transformed.xy, 0.0, 1.0);
+}
+
+// Outside the clip volume: drops features below the level of detail
+const CULLED = vec4<f32>(0.0, 0.0, 2.0, 1.0);
+"#;
+
+pub const TRIANGLE_SHADER: &str = r#"
+struct Varyings {
+    @builtin(position) clip: vec4<f32>,
+    @location(0) position: vec2<f32>,
+    @location(1) hole_center: vec2<f32>,
+    @location(2) hole_radius: f32,
+}
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) hole_center: vec2<f32>,
+    @location(2) hole_radius: f32,
+) -> Varyings {
+    return Varyings(to_clip(position), position, hole_center, hole_radius);
+}
+
+@fragment
+fn fs_main(in: Varyings) -> @location(0) vec4<f32> {
+    if (in.hole_radius > 0.0) {
+        let diff = in.position - in.hole_center;
+        if (dot(diff, diff) < in.hole_radius * in.hole_radius) {
+            discard;
+        }
+    }
+    return vec4<f32>(1.0);
+}
+"#;
+
+pub const CIRCLE_SHADER: &str = r#"
+struct Varyings {
+    @builtin(position) clip: vec4<f32>,
+    @location(0) position: vec2<f32>,
+    @location(1) hole_center: vec2<f32>,
+    @location(2) hole_radius: f32,
+}
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) center: vec2<f32>,
+    @location(2) radius: f32,
+    @location(3) hole_center: vec2<f32>,
+    @location(4) hole_radius: f32,
+) -> Varyings {
+    if (2.0 * radius < frame.min_size) {
+        return Varyings(CULLED, position, vec2<f32>(0.0), 0.0);
+    }
+    return Varyings(
+        to_clip(position * radius + center),
+        position,
+        (hole_center - center) / radius,
+        hole_radius / radius,
+    );
+}
+
+@fragment
+fn fs_main(in: Varyings) -> @location(0) vec4<f32> {
+    if (dot(in.position, in.position) > 1.0) {
+        discard;
+    }
+    if (in.hole_radius > 0.0) {
+        let diff = in.position - in.hole_center;
+        if (dot(diff, diff) < in.hole_radius * in.hole_radius) {
+            discard;
+        }
+    }
+    return vec4<f32>(1.0);
+}
+"#;
+
+pub const ARC_SHADER: &str = r#"
+const TWO_PI = 6.28318530718;
+
+struct Varyings {
+    @builtin(position) clip: vec4<f32>,
+    @location(0) position: vec2<f32>,
+    @location(1) radius: f32,
+    @location(2) start_angle: f32,
+    @location(3) sweep_angle: f32,
+    @location(4) thickness: f32,
+}
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) center: vec2<f32>,
+    @location(2) radius: f32,
+    @location(3) start_angle: f32,
+    @location(4) sweep_angle: f32,
+    @location(5) thickness: f32,
+) -> Varyings {
+    if (2.0 * radius + thickness < frame.min_size) {
+        return Varyings(CULLED, position, 0.0, 0.0, 0.0, 0.0);
+    }
+    let max_radius = radius + thickness;
+    return Varyings(
+        to_clip(position * max_radius + center),
+        position * max_radius,
+        radius,
+        start_angle,
+        sweep_angle,
+        thickness,
+    );
+}
+
+fn normalize_angle(angle: f32) -> f32 {
+    let normalized = angle - TWO_PI * floor(angle / TWO_PI);
+    return select(normalized, normalized + TWO_PI, normalized < 0.0);
+}
+
+@fragment
+fn fs_main(in: Varyings) -> @location(0) vec4<f32> {
+    let dist = length(in.position);
+    let angle = normalize_angle(atan2(in.position.y, in.position.x));
+    let start = normalize_angle(in.start_angle);
+    let end = normalize_angle(start + in.sweep_angle);
+
+    if (dist < in.radius - in.thickness * 0.5 || dist > in.radius + in.thickness * 0.5) {
+        discard;
+    }
+
+    var in_range: bool;
+    if (abs(in.sweep_angle) >= TWO_PI - 0.001) {
+        // Full circle; its end angle wraps onto the start angle
+        in_range = true;
+    } else if (in.sweep_angle > 0.0) {
+        if (end > start) {
+            in_range = angle >= start && angle <= end;
+        } else {
+            in_range = angle >= start || angle <= end;
+        }
+    } else {
+        if (end < start) {
+            in_range = angle <= start && angle >= end;
+        } else {
+            in_range = angle <= start || angle >= end;
+        }
+    }
+    if (!in_range) {
+        discard;
+    }
+    return vec4<f32>(1.0);
+}
+"#;
+
+pub const THERMAL_SHADER: &str = r#"
+struct Varyings {
+    @builtin(position) clip: vec4<f32>,
+    @location(0) position: vec2<f32>,
+    @location(1) outer_diameter: f32,
+    @location(2) inner_diameter: f32,
+    @location(3) gap_thickness: f32,
+    @location(4) rotation: f32,
+}
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) center: vec2<f32>,
+    @location(2) outer_diameter: f32,
+    @location(3) inner_diameter: f32,
+    @location(4) gap_thickness: f32,
+    @location(5) rotation: f32,
+) -> Varyings {
+    if (outer_diameter < frame.min_size) {
+        return Varyings(CULLED, position, 1.0, 0.0, 0.0, 0.0);
+    }
+    return Varyings(
+        to_clip(position * outer_diameter * 0.5 + center),
+        position,
+        outer_diameter,
+        inner_diameter,
+        gap_thickness,
+        rotation,
+    );
+}
+
+@fragment
+fn fs_main(in: Varyings) -> @location(0) vec4<f32> {
+    let c = cos(in.rotation);
+    let s = sin(in.rotation);
+    // Rotated back so the gaps lie along the axes
+    let rotated = vec2<f32>(
+        in.position.x * c + in.position.y * s,
+        -in.position.x * s + in.position.y * c,
+    );
+
+    let dist = length(rotated);
+    if (dist > 0.5 || dist < in.inner_diameter / (2.0 * in.outer_diameter)) {
+        discard;
+    }
+    let half_gap = in.gap_thickness / (2.0 * in.outer_diameter);
+    if (abs(rotated.x) < half_gap || abs(rotated.y) < half_gap) {
+        discard;
+    }
+    return vec4<f32>(1.0);
+}
+"#;
+
+pub const OBROUND_SHADER: &str = r#"
+struct Varyings {
+    @builtin(position) clip: vec4<f32>,
+    @location(0) local: vec2<f32>,
+    @location(1) half_size: vec2<f32>,
+    @location(2) hole_center: vec2<f32>,
+    @location(3) hole_radius: f32,
+}
+
+@vertex
+fn vs_main(
+    @location(0) position: vec2<f32>,
+    @location(1) center: vec2<f32>,
+    @location(2) size: vec2<f32>,
+    @location(3) rotation: f32,
+    @location(4) hole_center: vec2<f32>,
+    @location(5) hole_radius: f32,
+) -> Varyings {
+    if (max(size.x, size.y) < frame.min_size) {
+        return Varyings(CULLED, position, vec2<f32>(1.0), vec2<f32>(0.0), 0.0);
+    }
+    let half_size = size * 0.5;
+    let c = cos(rotation);
+    let s = sin(rotation);
+    let local = position * half_size;
+    let rotated = vec2<f32>(local.x * c - local.y * s, local.x * s + local.y * c);
+    // Hole center in the obround's unrotated local frame
+    let hole = hole_center - center;
+    return Varyings(
+        to_clip(rotated + center),
+        local,
+        half_size,
+        vec2<f32>(hole.x * c + hole.y * s, -hole.x * s + hole.y * c),
+        hole_radius,
+    );
+}
+
+@fragment
+fn fs_main(in: Varyings) -> @location(0) vec4<f32> {
+    let radius = min(in.half_size.x, in.half_size.y);
+    let segment = in.half_size - vec2<f32>(radius);
+    let q = max(abs(in.local) - segment, vec2<f32>(0.0));
+    if (dot(q, q) > radius * radius) {
+        discard;
+    }
+    if (in.hole_radius > 0.0) {
+        let diff = in.local - in.hole_center;
+        if (dot(diff, diff) < in.hole_radius * in.hole_radius) {
+            discard;
+        }
+    }
+    return vec4<f32>(1.0);
+}
+"#;
+
+/// Draws a layer texture over the whole canvas in the layer color
+pub const COMPOSITE_SHADER: &str = r#"
+@group(0) @binding(0) var<uniform> color: vec4<f32>;
+@group(0) @binding(1) var layer_sampler: sampler;
+@group(0) @binding(2) var layer_texture: texture_2d<f32>;
+
+struct Varyings {
+    @builtin(position) clip: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+}
+
+@vertex
+fn vs_main(@location(0) position: vec2<f32>) -> Varyings {
+    // Texture rows run top to bottom, clip space y bottom to top
+    return Varyings(vec4<f32>(position, 0.0, 1.0), vec2<f32>(0.5, -0.5) * position + 0.5);
+}
+
+@fragment
+fn fs_main(in: Varyings) -> @location(0) vec4<f32> {
+    let coverage = textureSample(layer_texture, layer_sampler, in.uv).a;
+    // Pre-multiplied alpha for additive blending
+    let alpha = color.a * coverage;
+    return vec4<f32>(color.rgb * alpha, alpha);
+}
+"#;
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Bumped when the session format changes incompatibly
+const SESSION_VERSION: u32 = 1;
+
+/// Saved review session: loaded layers, view settings and annotations
+#[derive(Serialize, Deserialize)]
+pub struct SessionState {
+    pub version: u32,
+    pub layers: Vec<SessionLayer>,
+    pub view: Option<ViewState>,
+    #[serde(default)]
+    pub annotations: serde_json::Value, // Opaque data owned by the page
+}
+
+/// A loaded layer identified by the hash of its source content
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SessionLayer {
+    pub id: u32,
+    pub hash: String,
+}
+
+/// Settings of the last render() call
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ViewState {
+    pub active_layer_ids: Vec<u32>, // Visible layers in draw order
+    pub colors: Vec<f32>,           // [r, g, b] per active layer
+    pub zoom_x: f32,
+    pub zoom_y: f32,
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub alpha: f32,
+}
+
+impl SessionState {
+    pub fn new(
+        layers: Vec<SessionLayer>,
+        view: Option<ViewState>,
+        annotations: serde_json::Value,
+    ) -> SessionState {
+        SessionState {
+            version: SESSION_VERSION,
+            layers,
+            view,
+            annotations,
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<SessionState, String> {
+        let state: SessionState =
+            serde_json::from_str(json).map_err(|e| format!("Invalid session state: {}", e))?;
+        if state.version != SESSION_VERSION {
+            return Err(format!(
+                "Unsupported session state version: {} (expected {})",
+                state.version, SESSION_VERSION
+            ));
+        }
+        Ok(state)
+    }
+
+    pub fn to_json(&self) -> String {
+        // Session state only holds JSON-safe values
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Map the saved layers onto currently loaded layers with the same content
+    ///
+    /// Returns the state with layer IDs rewritten to the loaded ones, or the
+    /// hashes of saved layers that are not loaded.
+    pub fn remap(self, loaded: &[SessionLayer]) -> Result<SessionState, Vec<String>> {
+        let mut available: Vec<&SessionLayer> = loaded.iter().collect();
+        let mut id_map = HashMap::new();
+        let mut missing = Vec::new();
+        for layer in &self.layers {
+            // Identical files loaded twice are matched one to one
+            match available.iter().position(|l| l.hash == layer.hash) {
+                Some(index) => {
+                    id_map.insert(layer.id, available.remove(index).id);
+                }
+                None => missing.push(layer.hash.clone()),
+            }
+        }
+        if !missing.is_empty() {
+            return Err(missing);
+        }
+
+        let layers = self
+            .layers
+            .iter()
+            .map(|layer| SessionLayer {
+                id: id_map[&layer.id],
+                hash: layer.hash.clone(),
+            })
+            .collect();
+
+        // Drop active layers that were not part of the saved layer list
+        let view = self.view.map(|view| {
+            let mut active_layer_ids = Vec::new();
+            let mut colors = Vec::new();
+            for (i, id) in view.active_layer_ids.iter().enumerate() {
+                if let Some(&new_id) = id_map.get(id) {
+                    active_layer_ids.push(new_id);
+                    colors.extend(view.colors.iter().skip(i * 3).take(3));
+                }
+            }
+            ViewState {
+                active_layer_ids,
+                colors,
+                ..view
+            }
+        });
+
+        Ok(SessionState::new(layers, view, self.annotations))
+    }
+}
+
+/// Stable 64-bit FNV-1a hash of layer content, as 16 hex digits
+pub fn content_hash(content: &[u8]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in content {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
@@ -1,6 +1,11 @@
+use crate::parser::geometry::{primitive_to_polygon, Primitive};
+use crate::parser::ApertureFunction;
+use crate::tessellation::arc_segments;
+use std::f32::consts::PI;
 use wasm_bindgen::prelude::*;
 
 /// Triangle mesh data structure
+#[derive(Clone, Default)]
 pub struct Triangles {
     pub(crate) vertices: Vec<f32>,
     pub(crate) indices: Vec<u32>,
@@ -28,6 +33,7 @@ impl Triangles {
 }
 
 /// Circle primitive data structure
+#[derive(Clone, Default)]
 pub struct Circles {
     pub(crate) x: Vec<f32>,
     pub(crate) y: Vec<f32>,
@@ -58,6 +64,7 @@ impl Circles {
 }
 
 /// Arc primitive data structure
+#[derive(Clone, Default)]
 pub struct Arcs {
     pub(crate) x: Vec<f32>,
     pub(crate) y: Vec<f32>,
@@ -88,13 +95,14 @@ impl Arcs {
 }
 
 /// Thermal primitive data structure
+#[derive(Clone, Default)]
 pub struct Thermals {
     pub(crate) x: Vec<f32>,
     pub(crate) y: Vec<f32>,
     pub(crate) outer_diameter: Vec<f32>,
     pub(crate) inner_diameter: Vec<f32>,
     pub(crate) gap_thickness: Vec<f32>,
-    pub(crate) rotation: Vec<f32>,
+    pub(crate) rotation: Vec<f32>, // Radians counterclockwise
 }
 
 impl Thermals {
@@ -117,8 +125,307 @@ impl Thermals {
     }
 }
 
+/// Obround (stadium) primitive data structure
+#[derive(Clone, Default)]
+pub struct Obrounds {
+    pub(crate) x: Vec<f32>,
+    pub(crate) y: Vec<f32>,
+    pub(crate) width: Vec<f32>,
+    pub(crate) height: Vec<f32>,
+    pub(crate) rotation: Vec<f32>,
+    pub(crate) hole_x: Vec<f32>,
+    pub(crate) hole_y: Vec<f32>,
+    pub(crate) hole_radius: Vec<f32>,
+}
+
+impl Obrounds {
+    pub fn new(
+        x: Vec<f32>,
+        y: Vec<f32>,
+        width: Vec<f32>,
+        height: Vec<f32>,
+        rotation: Vec<f32>,
+        hole_x: Vec<f32>,
+        hole_y: Vec<f32>,
+        hole_radius: Vec<f32>,
+    ) -> Obrounds {
+        Obrounds {
+            x,
+            y,
+            width,
+            height,
+            rotation,
+            hole_x,
+            hole_y,
+            hole_radius,
+        }
+    }
+}
+
+/// Aperture flashes (D03) recorded while parsing
+///
+/// One entry per flash and step-and-repeat copy, in file order, kept next to
+/// the primitives the flashes produced.
+#[derive(Clone, Default)]
+pub struct Flashes {
+    pub(crate) x: Vec<f32>,
+    pub(crate) y: Vec<f32>,
+    pub(crate) d_code: Vec<u16>,
+    pub(crate) shape: Vec<u8>, // ApertureShape
+    pub(crate) width: Vec<f32>,
+    pub(crate) height: Vec<f32>,
+    pub(crate) rotation: Vec<f32>, // Degrees counterclockwise
+}
+
+impl Flashes {
+    pub(crate) fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    /// Whether all arrays have one value per flash
+    pub(crate) fn is_consistent(&self) -> bool {
+        let len = self.x.len();
+        [&self.y, &self.width, &self.height, &self.rotation]
+            .iter()
+            .all(|a| a.len() == len)
+            && self.d_code.len() == len
+            && self.shape.len() == len
+    }
+
+    /// Move all flashes of `other` to the end of this list
+    pub(crate) fn append(&mut self, other: &mut Flashes) {
+        self.x.append(&mut other.x);
+        self.y.append(&mut other.y);
+        self.d_code.append(&mut other.d_code);
+        self.shape.append(&mut other.shape);
+        self.width.append(&mut other.width);
+        self.height.append(&mut other.height);
+        self.rotation.append(&mut other.rotation);
+    }
+
+    fn byte_size(&self) -> usize {
+        self.len() * (5 * 4 + 2 + 1)
+    }
+}
+
+/// Draw (D01) sequences recorded while parsing, as polylines
+///
+/// Consecutive draws with the same aperture, each starting where the previous
+/// one ended, form one polyline. Points of all polylines are stored back to
+/// back; polyline i runs from `start[i]` up to the next polyline's start.
+#[derive(Clone, Default)]
+pub struct Traces {
+    pub(crate) start: Vec<u32>, // First point of each polyline
+    pub(crate) d_code: Vec<u16>,
+    pub(crate) width: Vec<f32>,
+    pub(crate) points: Vec<f32>, // x, y of every point in mm
+    pub(crate) sweep: Vec<f32>,  // Arc sweep of the segment ending at each point (0.0 = straight)
+    pub(crate) center: Vec<f32>, // Arc center x, y of that segment (0.0 when straight)
+}
+
+impl Traces {
+    pub(crate) fn len(&self) -> usize {
+        self.start.len()
+    }
+
+    fn point_count(&self) -> usize {
+        self.sweep.len()
+    }
+
+    /// Whether the last polyline ends at `point` with the same aperture
+    fn continues(&self, point: [f32; 2], d_code: u16, width: f32) -> bool {
+        self.d_code.last() == Some(&d_code)
+            && self.width.last() == Some(&width)
+            && self.points[self.points.len() - 2..] == point
+    }
+
+    fn push_point(&mut self, point: [f32; 2], sweep: f32, center: [f32; 2]) {
+        self.points.extend_from_slice(&point);
+        self.sweep.push(sweep);
+        self.center.extend_from_slice(&center);
+    }
+
+    /// Append a draw from `from` to `to`, extending the last polyline if it
+    /// ends at `from`
+    ///
+    /// `arc` is the center and signed sweep (radians) of a circular draw.
+    pub(crate) fn push_segment(
+        &mut self,
+        from: [f32; 2],
+        to: [f32; 2],
+        arc: Option<([f32; 2], f32)>,
+        d_code: u16,
+        width: f32,
+    ) {
+        if !self.continues(from, d_code, width) {
+            self.start.push(self.point_count() as u32);
+            self.d_code.push(d_code);
+            self.width.push(width);
+            self.push_point(from, 0.0, [0.0, 0.0]);
+        }
+        let (center, sweep) = arc.unwrap_or(([0.0, 0.0], 0.0));
+        self.push_point(to, sweep, center);
+    }
+
+    /// Move all polylines of `other` to the end of this list
+    pub(crate) fn append(&mut self, other: &mut Traces) {
+        self.append_skipping(other, 0);
+    }
+
+    /// Move all polylines of `other` to the end of this list, joining its
+    /// first polyline to the last one here if it continues it
+    pub(crate) fn join(&mut self, other: &mut Traces) {
+        let continued = !other.start.is_empty()
+            && self.continues(
+                [other.points[0], other.points[1]],
+                other.d_code[0],
+                other.width[0],
+            );
+        self.append_skipping(other, usize::from(continued));
+    }
+
+    /// Append `other` without its first `skip` polylines' entries and points
+    /// (0, or 1 to continue the last polyline with the first one of `other`)
+    fn append_skipping(&mut self, other: &mut Traces, skip: usize) {
+        let offset = (self.point_count() - skip) as u32;
+        self.start
+            .extend(other.start[skip..].iter().map(|s| s + offset));
+        self.d_code.extend_from_slice(&other.d_code[skip..]);
+        self.width.extend_from_slice(&other.width[skip..]);
+        self.points.extend_from_slice(&other.points[skip * 2..]);
+        self.sweep.extend_from_slice(&other.sweep[skip..]);
+        self.center.extend_from_slice(&other.center[skip * 2..]);
+        *other = Traces::default();
+    }
+
+    /// Length of every polyline along its segments and arcs in mm
+    pub(crate) fn lengths(&self) -> Vec<f32> {
+        let point = |i: usize| [self.points[i * 2], self.points[i * 2 + 1]];
+        (0..self.len())
+            .map(|polyline| {
+                let end = self
+                    .start
+                    .get(polyline + 1)
+                    .map_or(self.point_count(), |&s| s as usize);
+                (self.start[polyline] as usize + 1..end)
+                    .map(|i| {
+                        let (from, to) = (point(i - 1), point(i));
+                        if self.sweep[i] == 0.0 {
+                            (to[0] - from[0]).hypot(to[1] - from[1])
+                        } else {
+                            let radius = (from[0] - self.center[i * 2])
+                                .hypot(from[1] - self.center[i * 2 + 1]);
+                            radius * self.sweep[i].abs()
+                        }
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Whether the arrays are parallel and every polyline has two or more points
+    pub(crate) fn is_consistent(&self) -> bool {
+        let points = self.point_count();
+        self.d_code.len() == self.start.len()
+            && self.width.len() == self.start.len()
+            && self.points.len() == points * 2
+            && self.center.len() == points * 2
+            && self
+                .start
+                .iter()
+                .zip(self.start.iter().skip(1).chain([&(points as u32)]))
+                .all(|(&start, &end)| start.checked_add(2).is_some_and(|s| s <= end))
+            && self.start.first().map_or(points == 0, |&s| s == 0)
+    }
+
+    fn byte_size(&self) -> usize {
+        self.start.len() * (4 + 2 + 4) + self.point_count() * 5 * 4
+    }
+}
+
+/// Per-primitive values parallel to the primitive arrays
+///
+/// One value per triangle vertex (like the hole data) and one per instance.
+/// Arrays are either empty or as long as the primitive arrays.
+#[derive(Clone, Default)]
+pub struct PrimitiveValues<T> {
+    pub(crate) triangles: Vec<T>,
+    pub(crate) circles: Vec<T>,
+    pub(crate) arcs: Vec<T>,
+    pub(crate) thermals: Vec<T>,
+    pub(crate) obrounds: Vec<T>,
+}
+
+impl<T: Copy> PrimitiveValues<T> {
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+            && self.circles.is_empty()
+            && self.arcs.is_empty()
+            && self.thermals.is_empty()
+            && self.obrounds.is_empty()
+    }
+
+    /// Append the value of one primitive
+    pub(crate) fn push(&mut self, primitive: &Primitive, value: T) {
+        match primitive {
+            Primitive::Triangle { .. } => self.triangles.extend_from_slice(&[value; 3]),
+            Primitive::Circle { .. } => self.circles.push(value),
+            Primitive::Arc { .. } => self.arcs.push(value),
+            Primitive::Thermal { .. } => self.thermals.push(value),
+            Primitive::Obround { .. } => self.obrounds.push(value),
+        }
+    }
+
+    /// Pad or cut the arrays to `lengths` (triangle vertices, circles, arcs,
+    /// thermals, obrounds)
+    pub(crate) fn resize(&mut self, lengths: [usize; 5], value: T) {
+        self.triangles.resize(lengths[0], value);
+        self.circles.resize(lengths[1], value);
+        self.arcs.resize(lengths[2], value);
+        self.thermals.resize(lengths[3], value);
+        self.obrounds.resize(lengths[4], value);
+    }
+
+    /// Whether any primitive has `value`
+    #[cfg(feature = "renderer")]
+    pub(crate) fn contains(&self, value: T) -> bool
+    where
+        T: PartialEq,
+    {
+        [
+            &self.triangles,
+            &self.circles,
+            &self.arcs,
+            &self.thermals,
+            &self.obrounds,
+        ]
+        .iter()
+        .any(|values| values.contains(&value))
+    }
+
+    /// Whether the arrays are empty or match the primitive arrays of `data`
+    pub(crate) fn fits(&self, data: &GerberData) -> bool {
+        self.is_empty()
+            || (self.triangles.len() == data.triangles.vertices.len() / 2
+                && self.circles.len() == data.circles.x.len()
+                && self.arcs.len() == data.arcs.x.len()
+                && self.thermals.len() == data.thermals.x.len()
+                && self.obrounds.len() == data.obrounds.x.len())
+    }
+
+    fn byte_size(&self) -> usize {
+        let count = self.triangles.len()
+            + self.circles.len()
+            + self.arcs.len()
+            + self.thermals.len()
+            + self.obrounds.len();
+        count * std::mem::size_of::<T>()
+    }
+}
+
 /// Boundary information for the entire Gerber layer
 #[wasm_bindgen]
+#[derive(Clone, Copy)]
 pub struct Boundary {
     pub(crate) min_x: f32,
     pub(crate) max_x: f32,
@@ -159,13 +466,40 @@ impl Boundary {
     }
 }
 
+impl Boundary {
+    /// Combined boundary of all polarity sublayers of a layer
+    pub(crate) fn combined(data: &[GerberData]) -> Boundary {
+        let mut min_x = f32::INFINITY;
+        let mut max_x = f32::NEG_INFINITY;
+        let mut min_y = f32::INFINITY;
+        let mut max_y = f32::NEG_INFINITY;
+
+        for layer in data {
+            let b = &layer.boundary;
+            min_x = min_x.min(b.min_x);
+            max_x = max_x.max(b.max_x);
+            min_y = min_y.min(b.min_y);
+            max_y = max_y.max(b.max_y);
+        }
+
+        Boundary::new(min_x, max_x, min_y, max_y)
+    }
+}
+
 /// Container for all parsed Gerber data
+#[derive(Clone)]
 pub struct GerberData {
     pub(crate) triangles: Triangles,
     pub(crate) circles: Circles,
     pub(crate) arcs: Arcs,
     pub(crate) thermals: Thermals,
+    pub(crate) obrounds: Obrounds,
     pub(crate) boundary: Boundary,
+    pub(crate) functions: PrimitiveValues<u8>, // ApertureFunction, empty without X2 attributes
+    pub(crate) apertures: PrimitiveValues<u16>, // Aperture D-code, 0 for regions
+    pub(crate) kinds: PrimitiveValues<u8>,     // PrimitiveKind, empty outside Gerber parses
+    pub(crate) flashes: Flashes,
+    pub(crate) traces: Traces,
 }
 
 impl GerberData {
@@ -174,6 +508,7 @@ impl GerberData {
         circles: Circles,
         arcs: Arcs,
         thermals: Thermals,
+        obrounds: Obrounds,
         boundary: Boundary,
     ) -> GerberData {
         GerberData {
@@ -181,15 +516,551 @@ impl GerberData {
             circles,
             arcs,
             thermals,
+            obrounds,
             boundary,
+            functions: PrimitiveValues::default(),
+            apertures: PrimitiveValues::default(),
+            kinds: PrimitiveValues::default(),
+            flashes: Flashes::default(),
+            traces: Traces::default(),
         }
     }
 
+    /// Attach the aperture function and D-code of every primitive
+    pub fn with_apertures(
+        mut self,
+        functions: PrimitiveValues<u8>,
+        apertures: PrimitiveValues<u16>,
+    ) -> GerberData {
+        self.functions = functions;
+        self.apertures = apertures;
+        self
+    }
+
+    /// Attach the operation (region, draw or flash) of every primitive
+    pub fn with_kinds(mut self, kinds: PrimitiveValues<u8>) -> GerberData {
+        self.kinds = kinds;
+        self
+    }
+
+    /// Attach the aperture flashes and draws that produced the geometry
+    pub fn with_flashes(mut self, flashes: Flashes, traces: Traces) -> GerberData {
+        self.flashes = flashes;
+        self.traces = traces;
+        self
+    }
+
+    /// D-codes of the apertures tagged with `function`
+    pub(crate) fn aperture_codes(&self, function: ApertureFunction) -> Vec<u16> {
+        let (functions, apertures) = (&self.functions, &self.apertures);
+        let mut d_codes = Vec::new();
+        for (functions, apertures) in [
+            (&functions.triangles, &apertures.triangles),
+            (&functions.circles, &apertures.circles),
+            (&functions.arcs, &apertures.arcs),
+            (&functions.thermals, &apertures.thermals),
+            (&functions.obrounds, &apertures.obrounds),
+        ] {
+            for (&tag, &d_code) in functions.iter().zip(apertures) {
+                // Regions carry no D-code
+                if tag == function as u8 && d_code != 0 && !d_codes.contains(&d_code) {
+                    d_codes.push(d_code);
+                }
+            }
+        }
+        d_codes
+    }
+
     /// Check if this GerberData contains any geometry
     pub fn has_geometry(&self) -> bool {
         !self.triangles.indices.is_empty()
             || !self.circles.x.is_empty()
             || !self.arcs.x.is_empty()
             || !self.thermals.x.is_empty()
+            || !self.obrounds.x.is_empty()
+    }
+
+    /// Memory held by the geometry arrays in bytes
+    pub fn byte_size(&self) -> usize {
+        let t = &self.triangles;
+        let values = t.vertices.len()
+            + t.indices.len()
+            + t.hole_x.len()
+            + t.hole_y.len()
+            + t.hole_radius.len()
+            + self.circles.x.len() * 6
+            + self.arcs.x.len() * 6
+            + self.thermals.x.len() * 6
+            + self.obrounds.x.len() * 8;
+        // f32 and u32 values, then the per-primitive aperture data, flashes and draws
+        values * 4
+            + self.functions.byte_size()
+            + self.apertures.byte_size()
+            + self.kinds.byte_size()
+            + self.flashes.byte_size()
+            + self.traces.byte_size()
+    }
+
+    /// Turn the geometry by `quarter_turns` × 90° counterclockwise about the
+    /// origin, then move it by (dx, dy) mm
+    pub(crate) fn transform(&mut self, quarter_turns: u32, dx: f32, dy: f32) {
+        let turns = quarter_turns % 4;
+        let place = |x: &mut f32, y: &mut f32| {
+            let (tx, ty) = match turns {
+                1 => (-*y, *x),
+                2 => (-*x, -*y),
+                3 => (*y, -*x),
+                _ => (*x, *y),
+            };
+            *x = tx + dx;
+            *y = ty + dy;
+        };
+        let place_all = |xs: &mut [f32], ys: &mut [f32]| {
+            for (x, y) in xs.iter_mut().zip(ys.iter_mut()) {
+                place(x, y);
+            }
+        };
+        let radians = turns as f32 * PI / 2.0;
+
+        let t = &mut self.triangles;
+        for vertex in t.vertices.chunks_exact_mut(2) {
+            let [x, y] = vertex else { continue };
+            place(x, y);
+        }
+        place_all(&mut t.hole_x, &mut t.hole_y);
+
+        let c = &mut self.circles;
+        place_all(&mut c.x, &mut c.y);
+        place_all(&mut c.hole_x, &mut c.hole_y);
+
+        let a = &mut self.arcs;
+        place_all(&mut a.x, &mut a.y);
+        a.start_angle.iter_mut().for_each(|angle| *angle += radians);
+
+        let th = &mut self.thermals;
+        place_all(&mut th.x, &mut th.y);
+        th.rotation.iter_mut().for_each(|angle| *angle += radians);
+
+        let o = &mut self.obrounds;
+        place_all(&mut o.x, &mut o.y);
+        place_all(&mut o.hole_x, &mut o.hole_y);
+        o.rotation.iter_mut().for_each(|angle| *angle += radians);
+
+        let f = &mut self.flashes;
+        place_all(&mut f.x, &mut f.y);
+        f.rotation
+            .iter_mut()
+            .for_each(|angle| *angle += turns as f32 * 90.0);
+
+        // Straight segments keep their (0, 0) arc center
+        let tr = &mut self.traces;
+        for point in tr.points.chunks_exact_mut(2) {
+            let [x, y] = point else { continue };
+            place(x, y);
+        }
+        for (center, &sweep) in tr.center.chunks_exact_mut(2).zip(&tr.sweep) {
+            if let ([x, y], true) = (center, sweep != 0.0) {
+                place(x, y);
+            }
+        }
+
+        // Quarter turns map the bounding box onto a bounding box
+        let b = &self.boundary;
+        let (mut x0, mut y0, mut x1, mut y1) = (b.min_x, b.min_y, b.max_x, b.max_y);
+        place(&mut x0, &mut y0);
+        place(&mut x1, &mut y1);
+        self.boundary = Boundary::new(x0.min(x1), x0.max(x1), y0.min(y1), y0.max(y1));
+    }
+
+    /// Mirror the geometry about the vertical line x = `axis_x`
+    pub(crate) fn mirror_x(&mut self, axis_x: f32) {
+        let mirror = |xs: &mut [f32]| xs.iter_mut().for_each(|x| *x = 2.0 * axis_x - *x);
+        // Angles measured from +x map to π - angle; degrees to 180° - angle
+        let mirror_angles = |angles: &mut [f32]| angles.iter_mut().for_each(|a| *a = PI - *a);
+
+        // Swapping two corners keeps the triangles counterclockwise
+        let t = &mut self.triangles;
+        t.vertices
+            .iter_mut()
+            .step_by(2)
+            .for_each(|x| *x = 2.0 * axis_x - *x);
+        t.indices
+            .chunks_exact_mut(3)
+            .for_each(|corners| corners.swap(1, 2));
+        mirror(&mut t.hole_x);
+
+        let c = &mut self.circles;
+        mirror(&mut c.x);
+        mirror(&mut c.hole_x);
+
+        // An arc keeps its sweep direction by starting from its mirrored end
+        let a = &mut self.arcs;
+        mirror(&mut a.x);
+        for (start, &sweep) in a.start_angle.iter_mut().zip(&a.sweep_angle) {
+            *start = PI - *start - sweep;
+        }
+
+        let th = &mut self.thermals;
+        mirror(&mut th.x);
+        mirror_angles(&mut th.rotation);
+
+        let o = &mut self.obrounds;
+        mirror(&mut o.x);
+        mirror(&mut o.hole_x);
+        mirror_angles(&mut o.rotation);
+
+        let f = &mut self.flashes;
+        mirror(&mut f.x);
+        f.rotation
+            .iter_mut()
+            .for_each(|angle| *angle = 180.0 - *angle);
+
+        // Arcs of draws turn the other way; straight segments keep their (0, 0) center
+        let tr = &mut self.traces;
+        tr.points
+            .iter_mut()
+            .step_by(2)
+            .for_each(|x| *x = 2.0 * axis_x - *x);
+        for (center, sweep) in tr.center.chunks_exact_mut(2).zip(tr.sweep.iter_mut()) {
+            if *sweep != 0.0 {
+                center[0] = 2.0 * axis_x - center[0];
+                *sweep = -*sweep;
+            }
+        }
+
+        let b = &self.boundary;
+        self.boundary = Boundary::new(
+            2.0 * axis_x - b.max_x,
+            2.0 * axis_x - b.min_x,
+            b.min_y,
+            b.max_y,
+        );
+    }
+
+    /// Scale positions and sizes by `factor` about the origin
+    pub(crate) fn scale(&mut self, factor: f32) {
+        let scale_all = |values: &mut [f32]| values.iter_mut().for_each(|v| *v *= factor);
+
+        let t = &mut self.triangles;
+        scale_all(&mut t.vertices);
+        scale_all(&mut t.hole_x);
+        scale_all(&mut t.hole_y);
+        scale_all(&mut t.hole_radius);
+
+        let c = &mut self.circles;
+        for values in [
+            &mut c.x,
+            &mut c.y,
+            &mut c.radius,
+            &mut c.hole_x,
+            &mut c.hole_y,
+            &mut c.hole_radius,
+        ] {
+            scale_all(values);
+        }
+
+        let a = &mut self.arcs;
+        for values in [&mut a.x, &mut a.y, &mut a.radius, &mut a.thickness] {
+            scale_all(values);
+        }
+
+        let th = &mut self.thermals;
+        for values in [
+            &mut th.x,
+            &mut th.y,
+            &mut th.outer_diameter,
+            &mut th.inner_diameter,
+            &mut th.gap_thickness,
+        ] {
+            scale_all(values);
+        }
+
+        let o = &mut self.obrounds;
+        for values in [
+            &mut o.x,
+            &mut o.y,
+            &mut o.width,
+            &mut o.height,
+            &mut o.hole_x,
+            &mut o.hole_y,
+            &mut o.hole_radius,
+        ] {
+            scale_all(values);
+        }
+
+        let f = &mut self.flashes;
+        for values in [&mut f.x, &mut f.y, &mut f.width, &mut f.height] {
+            scale_all(values);
+        }
+
+        let tr = &mut self.traces;
+        for values in [&mut tr.width, &mut tr.points, &mut tr.center] {
+            scale_all(values);
+        }
+
+        let b = &self.boundary;
+        self.boundary = Boundary::new(
+            b.min_x * factor,
+            b.max_x * factor,
+            b.min_y * factor,
+            b.max_y * factor,
+        );
+    }
+
+    /// Call `f` with the outline of every primitive, its kind and its index
+    /// within that kind
+    ///
+    /// Each shape is an outer contour (counterclockwise) followed by its hole
+    /// (clockwise) if it has one; thermals give one shape per segment. Curves
+    /// are flattened to within `chord_error` (mm).
+    pub(crate) fn for_each_outline(
+        &self,
+        chord_error: f32,
+        mut f: impl FnMut(&'static str, usize, Vec<Vec<Vec<[f32; 2]>>>),
+    ) {
+        let mut emit = |kind, index, primitive: Primitive| {
+            let shapes = outline_shapes(&primitive, chord_error);
+            if !shapes.is_empty() {
+                f(kind, index, shapes);
+            }
+        };
+
+        let t = &self.triangles;
+        for (index, corners) in t.indices.chunks_exact(3).enumerate() {
+            let vertex = |i: u32| [t.vertices[i as usize * 2], t.vertices[i as usize * 2 + 1]];
+            let v = corners[0] as usize;
+            emit(
+                "triangle",
+                index,
+                Primitive::Triangle {
+                    vertices: [vertex(corners[0]), vertex(corners[1]), vertex(corners[2])],
+                    exposure: 1.0,
+                    hole_x: t.hole_x[v],
+                    hole_y: t.hole_y[v],
+                    hole_radius: t.hole_radius[v],
+                },
+            );
+        }
+
+        let c = &self.circles;
+        for i in 0..c.x.len() {
+            emit(
+                "circle",
+                i,
+                Primitive::Circle {
+                    x: c.x[i],
+                    y: c.y[i],
+                    radius: c.radius[i],
+                    exposure: 1.0,
+                    hole_x: c.hole_x[i],
+                    hole_y: c.hole_y[i],
+                    hole_radius: c.hole_radius[i],
+                },
+            );
+        }
+
+        let a = &self.arcs;
+        for i in 0..a.x.len() {
+            emit(
+                "arc",
+                i,
+                Primitive::Arc {
+                    x: a.x[i],
+                    y: a.y[i],
+                    radius: a.radius[i],
+                    start_angle: a.start_angle[i],
+                    end_angle: a.start_angle[i] + a.sweep_angle[i],
+                    thickness: a.thickness[i],
+                    exposure: 1.0,
+                },
+            );
+        }
+
+        let th = &self.thermals;
+        for i in 0..th.x.len() {
+            emit(
+                "thermal",
+                i,
+                Primitive::Thermal {
+                    x: th.x[i],
+                    y: th.y[i],
+                    outer_diameter: th.outer_diameter[i],
+                    inner_diameter: th.inner_diameter[i],
+                    gap_thickness: th.gap_thickness[i],
+                    rotation: th.rotation[i],
+                    exposure: 1.0,
+                },
+            );
+        }
+
+        let o = &self.obrounds;
+        for i in 0..o.x.len() {
+            emit(
+                "obround",
+                i,
+                Primitive::Obround {
+                    x: o.x[i],
+                    y: o.y[i],
+                    width: o.width[i],
+                    height: o.height[i],
+                    rotation: o.rotation[i],
+                    exposure: 1.0,
+                    hole_x: o.hole_x[i],
+                    hole_y: o.hole_y[i],
+                    hole_radius: o.hole_radius[i],
+                },
+            );
+        }
+    }
+}
+
+/// Oriented outline shapes of a primitive, with its round hole if any
+fn outline_shapes(primitive: &Primitive, chord_error: f32) -> Vec<Vec<Vec<[f32; 2]>>> {
+    let hole = match *primitive {
+        Primitive::Triangle {
+            hole_x,
+            hole_y,
+            hole_radius,
+            ..
+        }
+        | Primitive::Circle {
+            hole_x,
+            hole_y,
+            hole_radius,
+            ..
+        }
+        | Primitive::Obround {
+            hole_x,
+            hole_y,
+            hole_radius,
+            ..
+        } if hole_radius > 0.0 => {
+            let segments = arc_segments(hole_radius, 2.0 * PI, chord_error);
+            let circle: Vec<[f32; 2]> = (0..segments)
+                .map(|i| {
+                    let angle = i as f32 * 2.0 * PI / segments as f32;
+                    [
+                        hole_x + hole_radius * angle.cos(),
+                        hole_y + hole_radius * angle.sin(),
+                    ]
+                })
+                .collect();
+            Some(circle)
+        }
+        _ => None,
+    };
+
+    let mut shapes: Vec<Vec<Vec<[f32; 2]>>> = primitive_to_polygon(primitive, chord_error)
+        .into_iter()
+        .filter(|contour| contour.len() >= 3)
+        .map(|contour| vec![oriented(contour, true)])
+        .collect();
+    if let (Some(shape), Some(hole)) = (shapes.first_mut(), hole) {
+        shape.push(oriented(hole, false));
+    }
+    shapes
+}
+
+/// Contour reversed if needed to run counterclockwise (or clockwise)
+fn oriented(mut points: Vec<[f32; 2]>, counterclockwise: bool) -> Vec<[f32; 2]> {
+    let area: f32 = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a[0] * b[1] - b[0] * a[1])
+        .sum();
+    if (area > 0.0) != counterclockwise {
+        points.reverse();
+    }
+    points
+}
+
+#[cfg(feature = "renderer")]
+impl GerberData {
+    /// Typed array views over the geometry arrays, keyed by primitive kind
+    ///
+    /// # Safety
+    /// The views alias WASM memory without copying. They are only valid
+    /// until this GerberData is dropped or the WASM memory grows, which any
+    /// allocation may cause.
+    pub(crate) unsafe fn js_views(&self) -> Result<js_sys::Object, JsValue> {
+        use js_sys::{Float32Array, Object, Reflect, Uint32Array};
+
+        fn group(arrays: &[(&str, JsValue)]) -> Result<Object, JsValue> {
+            let object = Object::new();
+            for (key, array) in arrays {
+                Reflect::set(&object, &JsValue::from_str(key), array)?;
+            }
+            Ok(object)
+        }
+        let view = |values: &[f32]| JsValue::from(Float32Array::view(values));
+
+        let t = &self.triangles;
+        let c = &self.circles;
+        let a = &self.arcs;
+        let th = &self.thermals;
+        let o = &self.obrounds;
+        group(&[
+            (
+                "triangles",
+                group(&[
+                    ("vertices", view(&t.vertices)),
+                    ("indices", Uint32Array::view(&t.indices).into()),
+                    ("hole_x", view(&t.hole_x)),
+                    ("hole_y", view(&t.hole_y)),
+                    ("hole_radius", view(&t.hole_radius)),
+                ])?
+                .into(),
+            ),
+            (
+                "circles",
+                group(&[
+                    ("x", view(&c.x)),
+                    ("y", view(&c.y)),
+                    ("radius", view(&c.radius)),
+                    ("hole_x", view(&c.hole_x)),
+                    ("hole_y", view(&c.hole_y)),
+                    ("hole_radius", view(&c.hole_radius)),
+                ])?
+                .into(),
+            ),
+            (
+                "arcs",
+                group(&[
+                    ("x", view(&a.x)),
+                    ("y", view(&a.y)),
+                    ("radius", view(&a.radius)),
+                    ("start_angle", view(&a.start_angle)),
+                    ("sweep_angle", view(&a.sweep_angle)),
+                    ("thickness", view(&a.thickness)),
+                ])?
+                .into(),
+            ),
+            (
+                "thermals",
+                group(&[
+                    ("x", view(&th.x)),
+                    ("y", view(&th.y)),
+                    ("outer_diameter", view(&th.outer_diameter)),
+                    ("inner_diameter", view(&th.inner_diameter)),
+                    ("gap_thickness", view(&th.gap_thickness)),
+                    ("rotation", view(&th.rotation)),
+                ])?
+                .into(),
+            ),
+            (
+                "obrounds",
+                group(&[
+                    ("x", view(&o.x)),
+                    ("y", view(&o.y)),
+                    ("width", view(&o.width)),
+                    ("height", view(&o.height)),
+                    ("rotation", view(&o.rotation)),
+                    ("hole_x", view(&o.hole_x)),
+                    ("hole_y", view(&o.hole_y)),
+                    ("hole_radius", view(&o.hole_radius)),
+                ])?
+                .into(),
+            ),
+        ])
     }
 }
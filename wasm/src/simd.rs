@@ -0,0 +1,16 @@
+pub mod scalar;
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+mod wasm128;
+
+// Export only what's needed externally
+#[cfg(not(all(target_arch = "wasm32", target_feature = "simd128")))]
+pub use scalar::{extend_disc_bounds, extend_point_bounds, interleave_xy, transform_points};
+#[cfg(all(target_arch = "wasm32", target_feature = "simd128"))]
+pub use wasm128::{extend_disc_bounds, extend_point_bounds, interleave_xy, transform_points};
+
+/// Whether the hot geometry loops use wasm SIMD (128-bit lanes) in this build
+///
+/// SIMD is on when compiling for wasm32 with `+simd128`, which
+/// `wasm/.cargo/config.toml` enables. All other builds use the scalar loops in
+/// `simd::scalar`, which produce identical results.
+pub const ENABLED: bool = cfg!(all(target_arch = "wasm32", target_feature = "simd128"));
@@ -0,0 +1,39 @@
+use crate::shape::Boundary;
+
+/// Scale then offset interleaved x,y coordinates in place
+pub fn transform_points(xy: &mut [f32], scale: f32, dx: f32, dy: f32) {
+    for point in xy.chunks_exact_mut(2) {
+        point[0] = point[0] * scale + dx;
+        point[1] = point[1] * scale + dy;
+    }
+}
+
+/// Interleave x,y arrays into a single flat array
+pub fn interleave_xy(x: &[f32], y: &[f32]) -> Vec<f32> {
+    let mut result = Vec::with_capacity(x.len() * 2);
+    for (&x, &y) in x.iter().zip(y) {
+        result.push(x);
+        result.push(y);
+    }
+    result
+}
+
+/// Grow a boundary to include interleaved x,y points
+pub fn extend_point_bounds(bounds: &mut Boundary, xy: &[f32]) {
+    for point in xy.chunks_exact(2) {
+        bounds.min_x = bounds.min_x.min(point[0]);
+        bounds.max_x = bounds.max_x.max(point[0]);
+        bounds.min_y = bounds.min_y.min(point[1]);
+        bounds.max_y = bounds.max_y.max(point[1]);
+    }
+}
+
+/// Grow a boundary to include discs (center +/- extent)
+pub fn extend_disc_bounds(bounds: &mut Boundary, x: &[f32], y: &[f32], extent: &[f32]) {
+    for ((&x, &y), &r) in x.iter().zip(y).zip(extent) {
+        bounds.min_x = bounds.min_x.min(x - r);
+        bounds.max_x = bounds.max_x.max(x + r);
+        bounds.min_y = bounds.min_y.min(y - r);
+        bounds.max_y = bounds.max_y.max(y + r);
+    }
+}
@@ -0,0 +1,115 @@
+use super::scalar;
+use crate::shape::Boundary;
+use core::arch::wasm32::*;
+
+// f32 lanes per v128
+const LANES: usize = 4;
+
+#[inline]
+fn load(values: &[f32]) -> v128 {
+    debug_assert!(values.len() >= LANES);
+    // SAFETY: at least 4 floats are readable; v128_load has no alignment requirement
+    unsafe { v128_load(values.as_ptr() as *const v128) }
+}
+
+#[inline]
+fn store(values: &mut [f32], vector: v128) {
+    debug_assert!(values.len() >= LANES);
+    // SAFETY: at least 4 floats are writable; v128_store has no alignment requirement
+    unsafe { v128_store(values.as_mut_ptr() as *mut v128, vector) }
+}
+
+#[inline]
+fn lane_min(v: v128) -> f32 {
+    f32x4_extract_lane::<0>(v)
+        .min(f32x4_extract_lane::<1>(v))
+        .min(f32x4_extract_lane::<2>(v).min(f32x4_extract_lane::<3>(v)))
+}
+
+#[inline]
+fn lane_max(v: v128) -> f32 {
+    f32x4_extract_lane::<0>(v)
+        .max(f32x4_extract_lane::<1>(v))
+        .max(f32x4_extract_lane::<2>(v).max(f32x4_extract_lane::<3>(v)))
+}
+
+/// Scale then offset interleaved x,y coordinates in place, two points per lane group
+pub fn transform_points(xy: &mut [f32], scale: f32, dx: f32, dy: f32) {
+    let scale_v = f32x4_splat(scale);
+    let offset_v = f32x4(dx, dy, dx, dy);
+    let mut chunks = xy.chunks_exact_mut(LANES);
+    for chunk in &mut chunks {
+        store(chunk, f32x4_add(f32x4_mul(load(chunk), scale_v), offset_v));
+    }
+    // An even lane count keeps the remainder starting on an x coordinate
+    scalar::transform_points(chunks.into_remainder(), scale, dx, dy);
+}
+
+/// Interleave x,y arrays into a single flat array, four points per lane group
+pub fn interleave_xy(x: &[f32], y: &[f32]) -> Vec<f32> {
+    let count = x.len().min(y.len());
+    let simd_count = count - count % LANES;
+    let mut result = vec![0.0; count * 2];
+    for (i, out) in result[..simd_count * 2]
+        .chunks_exact_mut(LANES * 2)
+        .enumerate()
+    {
+        let xs = load(&x[i * LANES..]);
+        let ys = load(&y[i * LANES..]);
+        store(&mut out[..LANES], i32x4_shuffle::<0, 4, 1, 5>(xs, ys));
+        store(&mut out[LANES..], i32x4_shuffle::<2, 6, 3, 7>(xs, ys));
+    }
+    for i in simd_count..count {
+        result[i * 2] = x[i];
+        result[i * 2 + 1] = y[i];
+    }
+    result
+}
+
+/// Grow a boundary to include interleaved x,y points, two points per lane group
+pub fn extend_point_bounds(bounds: &mut Boundary, xy: &[f32]) {
+    // pmin/pmax keep the accumulator on NaN input, like f32::min/max
+    let mut min_v = f32x4(bounds.min_x, bounds.min_y, bounds.min_x, bounds.min_y);
+    let mut max_v = f32x4(bounds.max_x, bounds.max_y, bounds.max_x, bounds.max_y);
+    let mut chunks = xy.chunks_exact(LANES);
+    for chunk in &mut chunks {
+        let v = load(chunk);
+        min_v = f32x4_pmin(min_v, v);
+        max_v = f32x4_pmax(max_v, v);
+    }
+    // Lanes 0 and 2 hold x, lanes 1 and 3 hold y
+    bounds.min_x = f32x4_extract_lane::<0>(min_v).min(f32x4_extract_lane::<2>(min_v));
+    bounds.min_y = f32x4_extract_lane::<1>(min_v).min(f32x4_extract_lane::<3>(min_v));
+    bounds.max_x = f32x4_extract_lane::<0>(max_v).max(f32x4_extract_lane::<2>(max_v));
+    bounds.max_y = f32x4_extract_lane::<1>(max_v).max(f32x4_extract_lane::<3>(max_v));
+    scalar::extend_point_bounds(bounds, chunks.remainder());
+}
+
+/// Grow a boundary to include discs (center +/- extent), four discs per lane group
+pub fn extend_disc_bounds(bounds: &mut Boundary, x: &[f32], y: &[f32], extent: &[f32]) {
+    let count = x.len().min(y.len()).min(extent.len());
+    let simd_count = count - count % LANES;
+    let mut min_x = f32x4_splat(bounds.min_x);
+    let mut max_x = f32x4_splat(bounds.max_x);
+    let mut min_y = f32x4_splat(bounds.min_y);
+    let mut max_y = f32x4_splat(bounds.max_y);
+    for i in (0..simd_count).step_by(LANES) {
+        let xs = load(&x[i..]);
+        let ys = load(&y[i..]);
+        let rs = load(&extent[i..]);
+        min_x = f32x4_pmin(min_x, f32x4_sub(xs, rs));
+        max_x = f32x4_pmax(max_x, f32x4_add(xs, rs));
+        min_y = f32x4_pmin(min_y, f32x4_sub(ys, rs));
+        max_y = f32x4_pmax(max_y, f32x4_add(ys, rs));
+    }
+    bounds.min_x = lane_min(min_x);
+    bounds.max_x = lane_max(max_x);
+    bounds.min_y = lane_min(min_y);
+    bounds.max_y = lane_max(max_y);
+    scalar::extend_disc_bounds(
+        bounds,
+        &x[simd_count..count],
+        &y[simd_count..count],
+        &extent[simd_count..count],
+    );
+}
@@ -0,0 +1,200 @@
+use crate::error::ViewerError;
+use crate::job::GerberJob;
+use crate::shape::GerberData;
+use wasm_bindgen::prelude::*;
+
+/// Material category of a stackup layer
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StackupKind {
+    Copper,
+    Dielectric,
+    SolderMask,
+    Legend,
+    Paste,
+    Other,
+}
+
+impl StackupKind {
+    /// Category for a stackup type name as used in Gerber job files
+    pub fn from_name(name: &str) -> StackupKind {
+        match name.to_ascii_lowercase().as_str() {
+            "copper" => StackupKind::Copper,
+            "dielectric" | "core" | "prepreg" => StackupKind::Dielectric,
+            "soldermask" => StackupKind::SolderMask,
+            "legend" | "silkscreen" => StackupKind::Legend,
+            "solderpaste" | "paste" => StackupKind::Paste,
+            _ => StackupKind::Other,
+        }
+    }
+
+    /// Display color (RGBA) used for this material in cut and 3D views
+    pub fn color(self) -> [f32; 4] {
+        match self {
+            StackupKind::Copper => [0.85, 0.55, 0.2, 1.0],
+            StackupKind::Dielectric => [0.45, 0.5, 0.3, 1.0],
+            StackupKind::SolderMask => [0.1, 0.45, 0.2, 0.85],
+            StackupKind::Legend => [0.95, 0.95, 0.95, 1.0],
+            StackupKind::Paste => [0.7, 0.7, 0.75, 1.0],
+            StackupKind::Other => [0.5, 0.5, 0.5, 1.0],
+        }
+    }
+}
+
+/// Sublayers of the loaded layer linked to a stackup layer (None = not linked)
+pub type LayerArtwork<'a> = Option<&'a [GerberData]>;
+
+/// One physical layer of the board stackup
+#[derive(Clone)]
+pub struct StackupLayer {
+    pub kind: StackupKind,
+    pub type_name: String,
+    pub thickness: f32, // mm
+    pub material: String,
+    pub name: String,
+    pub layer_id: Option<u32>, // Loaded layer holding this layer's artwork
+}
+
+/// Board stackup, ordered from the top side down
+///
+/// Built from a Gerber job file or entered manually. Layers can be linked to
+/// loaded Gerber layers so cross-sections show where copper, mask and legend
+/// are actually present.
+#[wasm_bindgen]
+#[derive(Default, Clone)]
+pub struct Stackup {
+    pub(crate) layers: Vec<StackupLayer>,
+}
+
+#[wasm_bindgen]
+impl Stackup {
+    /// Create an empty stackup for manual input
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Stackup {
+        Stackup::default()
+    }
+
+    /// Create a stackup from the material stackup of a Gerber job file
+    pub fn from_job(job: &GerberJob) -> Stackup {
+        let mut stackup = Stackup::default();
+        for entry in &job.stackup {
+            stackup.add_layer(&entry.kind, entry.thickness, &entry.material, &entry.name);
+        }
+        stackup
+    }
+
+    /// Append a layer below the current bottom layer
+    ///
+    /// # Arguments
+    /// * `type_name` - Copper, Dielectric, SolderMask, Legend or SolderPaste
+    /// * `thickness` - Layer thickness in mm
+    /// * `material` - Material name (e.g. "FR4"), may be empty
+    /// * `name` - Display name (e.g. "F.Cu"), may be empty
+    ///
+    /// # Returns
+    /// * Index of the new layer
+    pub fn add_layer(
+        &mut self,
+        type_name: &str,
+        thickness: f32,
+        material: &str,
+        name: &str,
+    ) -> usize {
+        self.layers.push(StackupLayer {
+            kind: StackupKind::from_name(type_name),
+            type_name: type_name.to_string(),
+            thickness: thickness.max(0.0),
+            material: material.to_string(),
+            name: name.to_string(),
+            layer_id: None,
+        });
+        self.layers.len() - 1
+    }
+
+    /// Link a stackup layer to the loaded layer holding its artwork
+    ///
+    /// # Arguments
+    /// * `index` - Stackup layer index
+    /// * `layer_id` - Layer ID returned from add_layer()
+    pub fn assign_layer(&mut self, index: usize, layer_id: u32) -> Result<(), JsValue> {
+        let layer = self.layers.get_mut(index).ok_or_else(|| {
+            ViewerError::invalid_argument(format!("Invalid stackup layer index: {}", index))
+        })?;
+        layer.layer_id = Some(layer_id);
+        Ok(())
+    }
+
+    /// Index of the stackup layer described by a .FileFunction value
+    ///
+    /// "Copper,L2,Inr" maps to the second copper layer; mask, legend and paste
+    /// functions map to the outermost layer of that kind on their side.
+    pub fn layer_for_function(&self, function: &str) -> Option<usize> {
+        let fields: Vec<&str> = function.split(',').map(str::trim).collect();
+        let bottom = fields.contains(&"Bot");
+        let kind = match fields[0].to_ascii_lowercase().as_str() {
+            "copper" => {
+                let number = fields
+                    .get(1)
+                    .and_then(|f| f.strip_prefix('L'))
+                    .and_then(|n| n.parse::<usize>().ok())?;
+                return self
+                    .layers
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, l)| l.kind == StackupKind::Copper)
+                    .nth(number.checked_sub(1)?)
+                    .map(|(i, _)| i);
+            }
+            "soldermask" => StackupKind::SolderMask,
+            "legend" => StackupKind::Legend,
+            "paste" => StackupKind::Paste,
+            _ => return None,
+        };
+        let mut matches = self
+            .layers
+            .iter()
+            .enumerate()
+            .filter(|(_, l)| l.kind == kind);
+        if bottom {
+            matches.next_back().map(|(i, _)| i)
+        } else {
+            matches.next().map(|(i, _)| i)
+        }
+    }
+
+    /// Number of layers in the stackup
+    #[wasm_bindgen(getter)]
+    pub fn count(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Sum of all layer thicknesses in mm
+    #[wasm_bindgen(getter)]
+    pub fn total_thickness(&self) -> f32 {
+        self.layers.iter().map(|l| l.thickness).sum()
+    }
+
+    /// Type name of the layer at `index`
+    pub fn layer_type(&self, index: usize) -> Option<String> {
+        self.layers.get(index).map(|l| l.type_name.clone())
+    }
+
+    /// Thickness of the layer at `index` in mm
+    pub fn layer_thickness(&self, index: usize) -> Option<f32> {
+        self.layers.get(index).map(|l| l.thickness)
+    }
+
+    /// Material of the layer at `index`
+    pub fn layer_material(&self, index: usize) -> Option<String> {
+        self.layers.get(index).map(|l| l.material.clone())
+    }
+
+    /// Display name of the layer at `index`
+    pub fn layer_name(&self, index: usize) -> Option<String> {
+        self.layers.get(index).map(|l| l.name.clone())
+    }
+
+    /// Loaded layer linked to the stackup layer at `index`, if any
+    pub fn layer_id(&self, index: usize) -> Option<u32> {
+        self.layers.get(index).and_then(|l| l.layer_id)
+    }
+}
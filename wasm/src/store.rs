@@ -0,0 +1,188 @@
+use crate::error::ViewerError;
+use crate::layer_info::LayerInfo;
+#[cfg(feature = "renderer")]
+use crate::parser::Polarity;
+use crate::shape::{Boundary, GerberData};
+
+/// Parsed geometry of one user layer
+struct StoredLayer {
+    sublayers: Vec<GerberData>, // Polarity sublayers for this layer
+    boundary: Boundary,         // Combined boundary
+    info: LayerInfo,            // Unit, format and image polarity of the file
+}
+
+/// Parsed layers by layer ID, independent of the renderer
+///
+/// Parsing, analysis and export only need this store, so they also work in
+/// builds without the `renderer` feature. The renderer keeps its GPU
+/// resources under the same layer IDs.
+#[derive(Default)]
+pub struct LayerStore {
+    layers: Vec<Option<StoredLayer>>, // Sparse vec (None = removed slot)
+}
+
+impl LayerStore {
+    /// Add a layer and return its layer ID (the first free slot)
+    pub fn add(&mut self, sublayers: Vec<GerberData>) -> usize {
+        let layer = StoredLayer {
+            boundary: Boundary::combined(&sublayers),
+            sublayers,
+            info: LayerInfo::default(),
+        };
+        if let Some(free_slot) = self.layers.iter().position(|layer| layer.is_none()) {
+            self.layers[free_slot] = Some(layer);
+            free_slot
+        } else {
+            self.layers.push(Some(layer));
+            self.layers.len() - 1
+        }
+    }
+
+    pub fn remove(&mut self, layer_id: usize) -> Result<(), ViewerError> {
+        match self.layers.get_mut(layer_id).and_then(Option::take) {
+            Some(_) => Ok(()),
+            None => Err(ViewerError::invalid_layer(
+                format!("Invalid layer_id: {}", layer_id),
+                layer_id as u32,
+            )),
+        }
+    }
+
+    /// Replace the sublayers of a layer, e.g. while it is loading progressively
+    pub fn replace(
+        &mut self,
+        layer_id: usize,
+        sublayers: Vec<GerberData>,
+    ) -> Result<(), ViewerError> {
+        match self.layers.get_mut(layer_id) {
+            Some(Some(layer)) => {
+                layer.boundary = Boundary::combined(&sublayers);
+                layer.sublayers = sublayers;
+                Ok(())
+            }
+            Some(None) => Err(ViewerError::invalid_layer(
+                "Layer deallocated",
+                layer_id as u32,
+            )),
+            None => Err(ViewerError::invalid_layer(
+                "Invalid layer index",
+                layer_id as u32,
+            )),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.layers.clear();
+    }
+
+    /// Set the unit, format and image polarity a layer's file was parsed with
+    pub fn set_info(&mut self, layer_id: usize, info: LayerInfo) -> Result<(), ViewerError> {
+        match self.layers.get_mut(layer_id) {
+            Some(Some(layer)) => {
+                layer.info = info;
+                Ok(())
+            }
+            _ => Err(ViewerError::invalid_layer(
+                format!("Invalid layer_id: {}", layer_id),
+                layer_id as u32,
+            )),
+        }
+    }
+
+    /// Unit, format and image polarity of a layer
+    pub fn info(&self, layer_id: usize) -> Result<&LayerInfo, ViewerError> {
+        match self.layers.get(layer_id) {
+            Some(Some(layer)) => Ok(&layer.info),
+            _ => Err(ViewerError::invalid_layer(
+                format!("Invalid layer_id: {}", layer_id),
+                layer_id as u32,
+            )),
+        }
+    }
+
+    /// Image polarity of a layer (Positive for unknown layers)
+    #[cfg(feature = "renderer")]
+    pub fn image_polarity(&self, layer_id: usize) -> Polarity {
+        match self.layers.get(layer_id) {
+            Some(Some(layer)) => layer.info.image_polarity,
+            _ => Polarity::Positive,
+        }
+    }
+
+    /// Polarity sublayers of a layer
+    pub fn get(&self, layer_id: usize) -> Result<&[GerberData], ViewerError> {
+        match self.layers.get(layer_id) {
+            Some(Some(layer)) => Ok(&layer.sublayers),
+            Some(None) => Err(ViewerError::invalid_layer(
+                "Layer deallocated",
+                layer_id as u32,
+            )),
+            None => Err(ViewerError::invalid_layer(
+                "Invalid layer index",
+                layer_id as u32,
+            )),
+        }
+    }
+
+    /// All stored layers with their layer IDs
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &[GerberData])> {
+        self.layers
+            .iter()
+            .enumerate()
+            .filter_map(|(id, layer)| layer.as_ref().map(|l| (id, l.sublayers.as_slice())))
+    }
+
+    /// Memory held by the geometry of all layers in bytes
+    pub fn byte_size(&self) -> usize {
+        self.layers
+            .iter()
+            .flatten()
+            .flat_map(|layer| &layer.sublayers)
+            .map(GerberData::byte_size)
+            .sum()
+    }
+
+    /// Combined boundary of all layers (all zero when empty)
+    pub fn boundary(&self) -> Boundary {
+        Self::union(self.layers.iter().flatten())
+    }
+
+    /// Combined boundary of the given layers (all zero when none has geometry)
+    pub fn boundary_of(&self, layer_ids: &[usize]) -> Result<Boundary, ViewerError> {
+        let layers = layer_ids
+            .iter()
+            .map(|&layer_id| match self.layers.get(layer_id) {
+                Some(Some(layer)) => Ok(layer),
+                _ => Err(ViewerError::invalid_layer(
+                    format!("Invalid layer_id: {}", layer_id),
+                    layer_id as u32,
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self::union(layers))
+    }
+
+    fn union<'a>(layers: impl IntoIterator<Item = &'a StoredLayer>) -> Boundary {
+        let mut min_x = f32::INFINITY;
+        let mut max_x = f32::NEG_INFINITY;
+        let mut min_y = f32::INFINITY;
+        let mut max_y = f32::NEG_INFINITY;
+
+        let mut any = false;
+        // Layers still waiting for their first geometry have no extent
+        for layer in layers.into_iter().filter(|l| !l.sublayers.is_empty()) {
+            let b = &layer.boundary;
+            min_x = min_x.min(b.min_x);
+            max_x = max_x.max(b.max_x);
+            min_y = min_y.min(b.min_y);
+            max_y = max_y.max(b.max_y);
+            any = true;
+        }
+
+        if any {
+            Boundary::new(min_x, max_x, min_y, max_y)
+        } else {
+            Boundary::new(0.0, 0.0, 0.0, 0.0)
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+/// Default maximum distance between a curve and its flattened outline (mm)
+pub const DEFAULT_CHORD_ERROR: f32 = 0.005;
+
+// Segment limits per full turn, so tiny curves stay round and tight
+// tolerances on huge curves cannot explode the vertex count
+const MIN_SEGMENTS_PER_TURN: f32 = 8.0;
+const MAX_SEGMENTS_PER_TURN: f32 = 1024.0;
+
+/// Number of straight segments for an arc so no chord strays more than
+/// `max_error` from the true curve
+///
+/// `sweep` is in radians (sign ignored). A full circle of radius r needs
+/// about pi / acos(1 - max_error / r) segments.
+pub fn arc_segments(radius: f32, sweep: f32, max_error: f32) -> usize {
+    let turns = sweep.abs() / (2.0 * PI);
+    let min = (turns * MIN_SEGMENTS_PER_TURN).ceil().max(1.0);
+    let max = (turns * MAX_SEGMENTS_PER_TURN).ceil().max(1.0);
+    if !(radius > max_error && max_error > 0.0) {
+        return min as usize;
+    }
+    let step = 2.0 * (1.0 - max_error / radius).acos();
+    (sweep.abs() / step).ceil().clamp(min, max) as usize
+}
+
+/// Number of cubic Bezier segments for an arc so that the curve stays within
+/// `max_error` of the true circle
+///
+/// Segments never span more than a quarter turn.
+pub fn bezier_segments(radius: f32, sweep: f32, max_error: f32) -> usize {
+    let sweep = sweep.abs();
+    let mut segments = (sweep / FRAC_PI_2).ceil().max(1.0);
+    let max = (sweep / (2.0 * PI) * MAX_SEGMENTS_PER_TURN)
+        .ceil()
+        .max(segments);
+    if max_error > 0.0 {
+        while segments < max && bezier_error(radius, sweep / segments) > max_error {
+            segments += 1.0;
+        }
+    }
+    segments as usize
+}
+
+/// Largest radial deviation of the standard cubic approximation of an arc
+fn bezier_error(radius: f32, step: f32) -> f32 {
+    let (sin, cos) = (step / 4.0).sin_cos();
+    radius * 2.0 / 27.0 * sin.powi(6) / (cos * cos)
+}
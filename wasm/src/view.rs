@@ -0,0 +1,249 @@
+use crate::shape::Boundary;
+use wasm_bindgen::prelude::*;
+
+// Fraction of the canvas a fitted window fills, leaving a small border
+const FIT_MARGIN: f32 = 0.9;
+// Smallest window width/height fitted in mm, so points don't zoom in without limit
+const MIN_FIT_EXTENT: f32 = 1.0;
+
+/// Camera placement for render(): zoom and pan offset
+///
+/// The shorter canvas side spans 2 / zoom mm; a board point (x, y) is drawn
+/// at the canvas center when offset = -(x, y) * zoom.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CameraView {
+    pub(crate) zoom: f32,
+    pub(crate) offset_x: f32,
+    pub(crate) offset_y: f32,
+}
+
+#[wasm_bindgen]
+impl CameraView {
+    #[wasm_bindgen(constructor)]
+    pub fn new(zoom: f32, offset_x: f32, offset_y: f32) -> CameraView {
+        CameraView {
+            zoom,
+            offset_x,
+            offset_y,
+        }
+    }
+
+    /// Zoom factor, passed to render() as zoom_x and zoom_y
+    #[wasm_bindgen(getter)]
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn offset_x(&self) -> f32 {
+        self.offset_x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn offset_y(&self) -> f32 {
+        self.offset_y
+    }
+}
+
+impl CameraView {
+    /// Board point at the canvas center
+    pub fn center(&self) -> (f32, f32) {
+        (-self.offset_x / self.zoom, -self.offset_y / self.zoom)
+    }
+
+    /// View with the board point (x, y) at the canvas center
+    pub fn centered_on(x: f32, y: f32, zoom: f32) -> CameraView {
+        CameraView::new(zoom, -x * zoom, -y * zoom)
+    }
+
+    /// Board-to-clip-space matrix (3x3, column-major) for a canvas of the
+    /// given pixel size
+    pub fn transform_matrix(&self, canvas_width: u32, canvas_height: u32) -> [f32; 9] {
+        let aspect = canvas_width as f32 / canvas_height as f32;
+
+        let (scale_x, scale_y) = if aspect > 1.0 {
+            (self.zoom / aspect, self.zoom)
+        } else {
+            (self.zoom, self.zoom * aspect)
+        };
+
+        let (offset_x, offset_y) = if aspect > 1.0 {
+            (self.offset_x / aspect, self.offset_y)
+        } else {
+            (self.offset_x, self.offset_y * aspect)
+        };
+
+        [
+            scale_x, 0.0, 0.0, 0.0, scale_y, 0.0, offset_x, offset_y, 1.0,
+        ]
+    }
+
+    /// View showing all of `bounds` centered in a canvas of the given pixel size
+    pub fn fit(bounds: &Boundary, canvas_width: u32, canvas_height: u32) -> CameraView {
+        let aspect = canvas_width.max(1) as f32 / canvas_height.max(1) as f32;
+        let width = (bounds.max_x - bounds.min_x).max(MIN_FIT_EXTENT);
+        let height = (bounds.max_y - bounds.min_y).max(MIN_FIT_EXTENT);
+
+        // The longer canvas side spans aspect times the shorter one
+        let zoom_x = 2.0 * aspect.max(1.0) / width;
+        let zoom_y = 2.0 * (1.0 / aspect).max(1.0) / height;
+        CameraView::centered_on(
+            (bounds.min_x + bounds.max_x) / 2.0,
+            (bounds.min_y + bounds.max_y) / 2.0,
+            zoom_x.min(zoom_y) * FIT_MARGIN,
+        )
+    }
+}
+
+/// Camera of the last render() with the matrices it drew with
+///
+/// The 2D view never rotates, so `rotation` is always 0; it is reported so
+/// hosts can store a complete view.
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug)]
+pub struct CameraState {
+    pub(crate) view: CameraView,
+    pub(crate) canvas_width: u32,
+    pub(crate) canvas_height: u32,
+}
+
+#[wasm_bindgen]
+impl CameraState {
+    #[wasm_bindgen(getter)]
+    pub fn zoom(&self) -> f32 {
+        self.view.zoom
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn offset_x(&self) -> f32 {
+        self.view.offset_x
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn offset_y(&self) -> f32 {
+        self.view.offset_y
+    }
+
+    /// View rotation in degrees counterclockwise
+    #[wasm_bindgen(getter)]
+    pub fn rotation(&self) -> f32 {
+        0.0
+    }
+
+    /// Canvas size in pixels the matrices are for
+    #[wasm_bindgen(getter)]
+    pub fn canvas_width(&self) -> u32 {
+        self.canvas_width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn canvas_height(&self) -> u32 {
+        self.canvas_height
+    }
+
+    /// Zoom and offsets as a CameraView for render() or animate_to()
+    #[wasm_bindgen(getter)]
+    pub fn view(&self) -> CameraView {
+        self.view
+    }
+
+    /// Board mm to clip space (-1..1), 3x3 column-major as in the shaders
+    #[wasm_bindgen(getter)]
+    pub fn matrix(&self) -> Vec<f32> {
+        self.view
+            .transform_matrix(self.canvas_width, self.canvas_height)
+            .to_vec()
+    }
+
+    /// Board mm to canvas pixels (origin top-left, y down), 3x3 column-major
+    ///
+    /// Multiply by the canvas's CSS size over its pixel size to place DOM
+    /// elements over the board.
+    #[wasm_bindgen(getter)]
+    pub fn pixel_matrix(&self) -> Vec<f32> {
+        let m = self
+            .view
+            .transform_matrix(self.canvas_width, self.canvas_height);
+        let (half_w, half_h) = (
+            self.canvas_width as f32 / 2.0,
+            self.canvas_height as f32 / 2.0,
+        );
+        vec![
+            m[0] * half_w,
+            0.0,
+            0.0,
+            0.0,
+            -m[4] * half_h,
+            0.0,
+            (m[6] + 1.0) * half_w,
+            (1.0 - m[7]) * half_h,
+            1.0,
+        ]
+    }
+}
+
+impl CameraState {
+    /// View showing the same board point at the center at the same pixels
+    /// per mm on a canvas of another size
+    pub fn view_for_canvas(&self, canvas_width: u32, canvas_height: u32) -> CameraView {
+        let shorter = |w: u32, h: u32| w.min(h).max(1) as f32;
+        let (x, y) = self.view.center();
+        let zoom = self.view.zoom * shorter(self.canvas_width, self.canvas_height)
+            / shorter(canvas_width, canvas_height);
+        CameraView::centered_on(x, y, zoom)
+    }
+}
+
+/// Tween from one camera view to another over a time span
+///
+/// Zoom changes geometrically and the view center moves in a straight line
+/// on the board, both eased in and out, so zooming out and back in looks
+/// even.
+#[cfg(feature = "renderer")]
+pub struct CameraAnimation {
+    from: CameraView,
+    to: CameraView,
+    start_ms: f64,
+    duration_ms: f64,
+}
+
+#[cfg(feature = "renderer")]
+impl CameraAnimation {
+    pub fn new(from: CameraView, to: CameraView, start_ms: f64, duration_ms: f64) -> Self {
+        CameraAnimation {
+            from,
+            to,
+            start_ms,
+            duration_ms,
+        }
+    }
+
+    /// View at time `now_ms` and whether the animation has arrived
+    pub fn view_at(&self, now_ms: f64) -> (CameraView, bool) {
+        let t = if self.duration_ms > 0.0 {
+            ((now_ms - self.start_ms) / self.duration_ms).clamp(0.0, 1.0) as f32
+        } else {
+            1.0
+        };
+        if t >= 1.0 || !(self.from.zoom > 0.0 && self.to.zoom > 0.0) {
+            return (self.to, true);
+        }
+
+        // Cubic ease in and out
+        let eased = if t < 0.5 {
+            4.0 * t * t * t
+        } else {
+            1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+        };
+        let zoom = self.from.zoom * (self.to.zoom / self.from.zoom).powf(eased);
+        let (from_x, from_y) = self.from.center();
+        let (to_x, to_y) = self.to.center();
+        let view = CameraView::centered_on(
+            from_x + (to_x - from_x) * eased,
+            from_y + (to_y - from_y) * eased,
+            zoom,
+        );
+        (view, false)
+    }
+}
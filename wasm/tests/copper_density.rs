@@ -0,0 +1,58 @@
+//! Copper density grid of a layer and its size limit
+
+use wasm_gerber_processor::GerberProcessor;
+
+// 1 mm square pad filling the left half of a 2 x 1 mm board
+const LAYER: &str = "%FSLAX26Y26*%
+%MOMM*%
+%ADD10R,1.0X1.0*%
+%ADD11C,0.01*%
+D10*
+X500000Y500000D03*
+D11*
+X2000000Y0D02*
+X2000000Y1000000D01*
+M02*
+";
+
+#[test]
+fn density_is_computed_per_cell() {
+    let mut processor = GerberProcessor::new();
+    let layer = processor.add_layer(LAYER.to_string()).unwrap();
+
+    let density = processor.compute_copper_density(layer, 2, 1).unwrap();
+    assert_eq!((density.columns(), density.rows()), (2, 1));
+    let values = density.values();
+    assert!(values[0] > 0.9);
+    assert!(values[1] < 0.1);
+}
+
+#[test]
+fn grid_up_to_the_limit_is_computed() {
+    let mut processor = GerberProcessor::new();
+    let layer = processor.add_layer(LAYER.to_string()).unwrap();
+
+    let density = processor.compute_copper_density(layer, 256, 256).unwrap();
+    assert_eq!(density.values().len(), 256 * 256);
+    // An empty axis still gets one cell
+    let density = processor.compute_copper_density(layer, 0, 3).unwrap();
+    assert_eq!((density.columns(), density.rows()), (1, 3));
+}
+
+// Errors are JS values, which cannot be built outside the wasm target, so
+// natively a rejected call panics while converting its error
+#[test]
+#[should_panic(expected = "cannot convert to JsValue")]
+fn oversized_grid_is_rejected() {
+    let mut processor = GerberProcessor::new();
+    let layer = processor.add_layer(LAYER.to_string()).unwrap();
+    let _ = processor.compute_copper_density(layer, 257, 256);
+}
+
+#[test]
+#[should_panic(expected = "cannot convert to JsValue")]
+fn grid_overflowing_a_cell_count_is_rejected() {
+    let mut processor = GerberProcessor::new();
+    let layer = processor.add_layer(LAYER.to_string()).unwrap();
+    let _ = processor.compute_copper_density(layer, u32::MAX, u32::MAX);
+}
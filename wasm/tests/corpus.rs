@@ -0,0 +1,193 @@
+//! Real-world CAM outputs and mutations of them through the parser
+//!
+//! Run with `cargo test --features fuzzing --test corpus`. The files in
+//! tests/corpus must parse to geometry; truncated and mutated copies may
+//! fail to parse but must never panic. Crashes found by the fuzz targets in
+//! fuzz/ belong here as regression inputs.
+
+use std::fs;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::PathBuf;
+use wasm_gerber_processor::fuzzing::{evaluate_expression, flash_macro, parse_gerber};
+
+const MUTATIONS_PER_FILE: usize = 400;
+
+// Characters that steer the parser into its different commands
+const ALPHABET: &[u8] = b"%*XYIJDGM0123456789-+.,$=LPCABSRFOT\n";
+
+fn corpus() -> Vec<(String, String)> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    files.sort();
+    files
+        .into_iter()
+        .map(|path| {
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, fs::read_to_string(&path).unwrap())
+        })
+        .collect()
+}
+
+/// Parse `data`, failing the test with the input if the parser panics
+fn parse_without_panic(name: &str, data: &str) {
+    if catch_unwind(AssertUnwindSafe(|| parse_gerber(data))).is_err() {
+        panic!("parser panicked on {}:\n{}", name, data);
+    }
+}
+
+/// Deterministic xorshift generator, so failures reproduce
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0 as usize
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        self.next() % n.max(1)
+    }
+}
+
+/// Replace, insert or delete a few bytes, or duplicate a line
+fn mutate(data: &str, rng: &mut Rng) -> String {
+    let mut bytes = data.as_bytes().to_vec();
+    for _ in 0..1 + rng.below(4) {
+        let at = rng.below(bytes.len());
+        let byte = ALPHABET[rng.below(ALPHABET.len())];
+        match rng.below(5) {
+            0 => bytes[at] = byte,
+            1 => bytes.insert(at, byte),
+            2 => {
+                bytes.remove(at);
+            }
+            3 => {
+                // Multi-byte character where the parser slices by byte index
+                bytes.splice(at..at, "µ".bytes());
+            }
+            _ => {
+                let start = bytes[..at]
+                    .iter()
+                    .rposition(|&b| b == b'\n')
+                    .map_or(0, |i| i + 1);
+                let end = bytes[at..]
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(bytes.len(), |i| at + i);
+                let line = bytes[start..end].to_vec();
+                bytes.splice(start..start, line.into_iter().chain([b'\n']));
+            }
+        }
+        if bytes.is_empty() {
+            break;
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[test]
+fn corpus_files_parse_to_geometry() {
+    let files = corpus();
+    assert!(!files.is_empty(), "tests/corpus is empty");
+    for (name, data) in files {
+        let sublayers = parse_gerber(&data).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        assert!(sublayers > 0, "{} has no geometry", name);
+    }
+}
+
+#[test]
+fn truncated_files_do_not_panic() {
+    for (name, data) in corpus() {
+        for (end, _) in data.match_indices('\n') {
+            parse_without_panic(&name, &data[..end]);
+            // Cut inside the line as well
+            parse_without_panic(&name, &data[..end.saturating_sub(3)]);
+        }
+    }
+}
+
+#[test]
+fn mutated_files_do_not_panic() {
+    for (index, (name, data)) in corpus().into_iter().enumerate() {
+        let mut rng = Rng(0x9E37_79B9_7F4A_7C15 ^ index as u64);
+        for _ in 0..MUTATIONS_PER_FILE {
+            parse_without_panic(&name, &mutate(&data, &mut rng));
+        }
+    }
+}
+
+#[test]
+fn malformed_macro_expressions_do_not_panic() {
+    let expressions = [
+        "",
+        "$",
+        "$$1",
+        "$0",
+        "$99",
+        "1/0",
+        "0/0",
+        "-",
+        "--1",
+        "1-",
+        "(",
+        ")",
+        "((1)",
+        "1)",
+        "()",
+        "1X",
+        "X1",
+        "1.2.3",
+        "$1X$2+($1-$3)/2",
+        "1e40X1e40",
+        "-$1",
+        "+",
+        "2X-3",
+        "$1=2",
+    ];
+    for expr in expressions {
+        let result = catch_unwind(|| evaluate_expression(expr, &[1.0, 2.0]));
+        assert!(result.is_ok(), "evaluator panicked on {:?}", expr);
+    }
+
+    let macros = [
+        ("1,1,$1,0,0*", ""),
+        ("1,1*", "1"),
+        ("4,1,3,0,0,1,0*", "1"),
+        ("4,1,-2,0,0*", ""),
+        ("4,1,100000000,0,0*", ""),
+        ("5,1,0,0,0,1,0*", ""),
+        ("5,1,1000000000,0,0,1,0*", ""),
+        ("7,0,0,1,2,0.1,0*", ""),
+        ("20,1,0.1,0,0,0,0,0*", ""),
+        ("21,1,0,0,0,0,0*", ""),
+        ("$1=*1,1,$1,0,0*", ""),
+        ("1,0,1,0,0*1,1,$1,0,0*", "X"),
+        ("22,1,1,1,0,0,0*", "1X2X3"),
+        ("1,1,$1,0,0*1,0,$2,0,0*", "1.0X2.0"),
+    ];
+    for (body, params) in macros {
+        let result = catch_unwind(|| flash_macro(body, params));
+        assert!(
+            result.is_ok(),
+            "macro {:?} with {:?} panicked",
+            body,
+            params
+        );
+    }
+}
+
+#[test]
+fn content_after_m02_is_rejected() {
+    let file = "%FSLAX26Y26*%\n%MOMM*%\n%ADD10C,1.0*%\nD10*\nX0Y0D03*\nM02*\n";
+    assert!(parse_gerber(file).is_ok());
+    // Blank lines and control padding after M02 are not content
+    assert!(parse_gerber(&format!("{}\n  \n\u{1a}\n", file)).is_ok());
+
+    let error = parse_gerber(&format!("{}X1000000Y0D03*\n", file)).unwrap_err();
+    assert_eq!(error.line(), Some(7));
+}
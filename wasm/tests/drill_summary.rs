@@ -0,0 +1,64 @@
+//! Drill tool summary of plated and non-plated drill layers
+
+use wasm_gerber_processor::GerberProcessor;
+
+// Two 0.3 mm vias, one 1.0 mm hole and a 1.0 mm slot
+const PLATED: &str = "%FSLAX26Y26*%
+%MOMM*%
+%TF.FileFunction,Plated,1,2,PTH*%
+%ADD10C,0.3*%
+%ADD11C,1.0*%
+D10*
+X0Y0D03*
+X1000000Y0D03*
+D11*
+X5000000Y0D03*
+X5000000Y5000000D02*
+X7000000Y5000000D01*
+M02*
+";
+
+// Two 3.2 mm mounting holes and one 1.0 mm hole
+const NON_PLATED: &str = "%FSLAX26Y26*%
+%MOMM*%
+%TF.FileFunction,NonPlated,1,2,NPTH*%
+%ADD10C,3.2*%
+%ADD11C,1.0*%
+D10*
+X0Y10000000D03*
+X10000000Y10000000D03*
+D11*
+X20000000Y0D03*
+M02*
+";
+
+#[test]
+fn tools_are_merged_by_diameter_and_plating() {
+    let mut processor = GerberProcessor::new();
+    let plated = processor.add_layer(PLATED.to_string()).unwrap();
+    let non_plated = processor.add_layer(NON_PLATED.to_string()).unwrap();
+
+    let summary = processor
+        .get_drill_summary(vec![plated, non_plated])
+        .unwrap();
+    assert_eq!(summary.tools(), 4);
+    assert_eq!(summary.diameter(), vec![0.3, 1.0, 1.0, 3.2]);
+    assert_eq!(summary.plated(), vec![1, 1, 0, 0]);
+    assert_eq!(summary.hits(), vec![2, 1, 1, 2]);
+    assert_eq!(summary.slots(), vec![0, 1, 0, 0]);
+    assert_eq!((summary.total_hits(), summary.total_slots()), (6, 1));
+}
+
+#[test]
+fn summary_exports_as_csv() {
+    let mut processor = GerberProcessor::new();
+    let plated = processor.add_layer(PLATED.to_string()).unwrap();
+
+    let summary = processor.get_drill_summary(vec![plated]).unwrap();
+    assert_eq!(
+        summary.to_csv(),
+        "Tool,Diameter (mm),Plated,Hits,Slots\n\
+         T1,0.300,PTH,2,0\n\
+         T2,1.000,PTH,1,1\n"
+    );
+}
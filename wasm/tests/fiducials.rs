@@ -0,0 +1,93 @@
+//! Fiducial detection on copper, mask and drill layers
+//!
+//! A round pad only counts as a fiducial by its shape when it sits in a
+//! wide mask opening, has no drill hit and no trace; pads flashed with a
+//! FiducialPad aperture count without any of that.
+
+use wasm_gerber_processor::GerberProcessor;
+
+// 1 mm pads at x = 0 (bare), 10 (drilled) and 20 mm (trace attached)
+const COPPER: &str = "%FSLAX26Y26*%
+%MOMM*%
+%ADD10C,1.0*%
+%ADD11C,0.2*%
+D10*
+X0Y0D03*
+X10000000Y0D03*
+X20000000Y0D03*
+D11*
+X20000000Y0D02*
+X25000000Y0D01*
+M02*
+";
+
+// 2 mm openings over all three pads
+const MASK: &str = "%FSLAX26Y26*%
+%MOMM*%
+%ADD10C,2.0*%
+D10*
+X0Y0D03*
+X10000000Y0D03*
+X20000000Y0D03*
+M02*
+";
+
+const DRILL: &str = "%FSLAX26Y26*%
+%MOMM*%
+%ADD10C,0.4*%
+D10*
+X10000000Y0D03*
+M02*
+";
+
+// Small rectangular pad marked as a fiducial, with a plain pad beside it
+const ATTRIBUTED: &str = "%FSLAX26Y26*%
+%MOMM*%
+%TA.AperFunction,FiducialPad,Local*%
+%ADD10R,0.3X0.3*%
+%TD*%
+%ADD11C,1.0*%
+D10*
+X5000000Y5000000D03*
+D11*
+X0Y0D03*
+M02*
+";
+
+#[test]
+fn bare_pad_in_wide_mask_opening_is_a_fiducial() {
+    let mut processor = GerberProcessor::new();
+    let copper = processor.add_layer(COPPER.to_string()).unwrap();
+    let mask = processor.add_layer(MASK.to_string()).unwrap();
+    let drill = processor.add_layer(DRILL.to_string()).unwrap();
+
+    let fiducials = processor
+        .get_fiducials(copper, Some(mask), Some(drill))
+        .unwrap();
+    assert_eq!(fiducials.count(), 1);
+    assert_eq!(fiducials.x(), vec![0.0]);
+    assert_eq!(fiducials.diameter(), vec![1.0]);
+    assert_eq!(fiducials.opening(), vec![2.0]);
+    assert_eq!(fiducials.attributed(), vec![0]);
+}
+
+#[test]
+fn pads_need_a_mask_layer_to_be_found_by_shape() {
+    let mut processor = GerberProcessor::new();
+    let copper = processor.add_layer(COPPER.to_string()).unwrap();
+    assert_eq!(
+        processor.get_fiducials(copper, None, None).unwrap().count(),
+        0
+    );
+}
+
+#[test]
+fn fiducial_pad_attribute_marks_a_fiducial() {
+    let mut processor = GerberProcessor::new();
+    let copper = processor.add_layer(ATTRIBUTED.to_string()).unwrap();
+
+    let fiducials = processor.get_fiducials(copper, None, None).unwrap();
+    assert_eq!(fiducials.count(), 1);
+    assert_eq!((fiducials.x()[0], fiducials.y()[0]), (5.0, 5.0));
+    assert_eq!(fiducials.attributed(), vec![1]);
+}
@@ -0,0 +1,262 @@
+//! Golden-file geometry regression tests
+//!
+//! Run with `cargo test --features fuzzing --test golden`. Every reference
+//! Gerber in tests/golden is parsed natively and its primitive counts,
+//! bounds and hashed vertex data are compared with the `.golden` file next
+//! to it, so parser refactors (tokenizer, step and repeat, polarity) can be
+//! checked without a browser.
+//!
+//! After an intended geometry change, regenerate the expectations with
+//! `UPDATE_GOLDEN=1 cargo test --features fuzzing --test golden` and review
+//! the diff. The reference files avoid regions and outline macros: their
+//! triangles come from i_triangle, and a dependency update should not show
+//! up as a parser regression.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use wasm_gerber_processor::fuzzing::{summarize_gerber, GeometrySummary};
+
+fn references() -> Vec<PathBuf> {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext != "golden"))
+        .collect();
+    files.sort();
+    files
+}
+
+/// Text form of a summary, one line per polarity sublayer
+fn render(summary: &GeometrySummary) -> String {
+    // Round so float noise below 0.1 µm does not show up, and drop -0
+    let bound = |value: f32| (value as f64 * 1e4).round() / 1e4 + 0.0;
+    let [min_x, max_x, min_y, max_y] = summary.bounds.map(bound);
+    let mut text = format!(
+        "bounds {:.4} {:.4} {:.4} {:.4}\n",
+        min_x, max_x, min_y, max_y
+    );
+    for sublayer in &summary.sublayers {
+        writeln!(
+            text,
+            "sublayer triangles={} circles={} arcs={} thermals={} obrounds={} hash={:016x}",
+            sublayer.triangles,
+            sublayer.circles,
+            sublayer.arcs,
+            sublayer.thermals,
+            sublayer.obrounds,
+            sublayer.hash
+        )
+        .unwrap();
+    }
+    text
+}
+
+fn golden_path(reference: &Path) -> PathBuf {
+    reference.with_extension("golden")
+}
+
+#[test]
+fn reference_files_match_golden_geometry() {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let files = references();
+    assert!(!files.is_empty(), "tests/golden has no reference files");
+
+    let mut failures = Vec::new();
+    for reference in files {
+        let name = reference
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        let data = fs::read_to_string(&reference).unwrap();
+        let summary = summarize_gerber(&data).unwrap_or_else(|e| panic!("{}: {}", name, e));
+        assert!(!summary.sublayers.is_empty(), "{} has no geometry", name);
+        let actual = render(&summary);
+
+        let golden = golden_path(&reference);
+        if update {
+            fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+        match fs::read_to_string(&golden) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{}:\n--- expected\n{}+++ actual\n{}",
+                name, expected, actual
+            )),
+            Err(_) => failures.push(format!(
+                "{}: no {} (run with UPDATE_GOLDEN=1)",
+                name,
+                golden.display()
+            )),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "geometry changed:\n{}",
+        failures.join("\n")
+    );
+}
+
+#[test]
+fn summaries_are_deterministic() {
+    for reference in references() {
+        let data = fs::read_to_string(&reference).unwrap();
+        assert_eq!(
+            summarize_gerber(&data).unwrap(),
+            summarize_gerber(&data).unwrap(),
+            "{}",
+            reference.display()
+        );
+    }
+}
+
+#[test]
+fn clear_polarity_starts_a_new_sublayer() {
+    let dark = "%FSLAX26Y26*%\n%MOMM*%\n%ADD10C,1.0*%\nD10*\nX0Y0D03*\nM02*\n";
+    let with_clear = "%FSLAX26Y26*%\n%MOMM*%\n%ADD10C,1.0*%\nD10*\nX0Y0D03*\n\
+                      %LPC*%\nX0Y0D03*\n%LPD*%\nX2000000Y0D03*\nM02*\n";
+    assert_eq!(summarize_gerber(dark).unwrap().sublayers.len(), 1);
+    let summary = summarize_gerber(with_clear).unwrap();
+    assert_eq!(summary.sublayers.len(), 3);
+    assert!(summary
+        .sublayers
+        .iter()
+        .all(|sublayer| sublayer.circles == 1));
+}
+
+#[test]
+fn aperture_selected_before_its_definition_is_used() {
+    let data = "%FSLAX26Y26*%\n%MOMM*%\nD10*\n%ADD10C,1.0*%\nX0Y0D03*\nM02*\n";
+    let summary = summarize_gerber(data).unwrap();
+    assert_eq!(summary.sublayers.len(), 1);
+    assert_eq!(summary.sublayers[0].circles, 1);
+}
+
+#[test]
+fn redefined_aperture_applies_to_later_objects() {
+    let data = "%FSLAX26Y26*%\n%MOMM*%\n%ADD10C,1.0*%\nD10*\nX0Y0D03*\n\
+                %ADD10R,1.0X1.0*%\nX2000000Y0D03*\nM02*\n";
+    let summary = summarize_gerber(data).unwrap();
+    assert_eq!(summary.sublayers.len(), 1);
+    assert_eq!(summary.sublayers[0].circles, 1);
+    assert_eq!(summary.sublayers[0].triangles, 2);
+}
+
+#[test]
+fn zero_padded_d_codes_name_the_same_aperture() {
+    let padded = "%FSLAX26Y26*%\n%MOMM*%\n%ADD010C,1.0*%\nD10*\nX0Y0D03*\n\
+                  D0010*\nX2000000Y0D03*\nM02*\n";
+    let summary = summarize_gerber(padded).unwrap();
+    assert_eq!(summary.sublayers[0].circles, 2);
+}
+
+#[test]
+fn trailing_zero_omission_pads_coordinates() {
+    // X15 is 15.0 and Y0015 is 0.15 in format 24 with trailing zeros omitted
+    let trailing = "%FSTAX24Y24*%\n%MOMM*%\n%ADD10C,1.0*%\nD10*\nX15Y0015D03*\nM02*\n";
+    let leading = "%FSLAX24Y24*%\n%MOMM*%\n%ADD10C,1.0*%\nD10*\nX150000Y1500D03*\nM02*\n";
+    let summary = summarize_gerber(trailing).unwrap();
+    assert_eq!(summary.bounds, summarize_gerber(leading).unwrap().bounds);
+    assert_eq!(summary.bounds, [14.5, 15.5, -0.35, 0.65]);
+}
+
+#[test]
+fn layer_scale_applies_once_to_apertures() {
+    // A 1 mm circle flashed under %LS0.5 is 0.5 mm wide, whether it was
+    // defined before or after the scale was set
+    let before = "%FSLAX26Y26*%\n%MOMM*%\n%ADD10C,1.0*%\n%LS0.5*%\nD10*\nX0Y0D03*\nM02*\n";
+    let after = "%FSLAX26Y26*%\n%MOMM*%\n%LS0.5*%\n%ADD10C,1.0*%\nD10*\nX0Y0D03*\nM02*\n";
+    let expected = [-0.25, 0.25, -0.25, 0.25];
+    assert_eq!(summarize_gerber(before).unwrap().bounds, expected);
+    assert_eq!(summarize_gerber(after).unwrap().bounds, expected);
+
+    // A scale set after a flash leaves it alone
+    let unscaled = "%FSLAX26Y26*%\n%MOMM*%\n%ADD10C,1.0*%\nD10*\nX0Y0D03*\n%LS0.5*%\nM02*\n";
+    assert_eq!(
+        summarize_gerber(unscaled).unwrap().bounds,
+        [-0.5, 0.5, -0.5, 0.5]
+    );
+}
+
+#[test]
+fn layer_scale_scales_macros_and_blocks_about_their_origin() {
+    // Macro circle of diameter 1 centered at (2, 0)
+    let macro_flash = "%FSLAX26Y26*%\n%MOMM*%\n%AMOFF*1,1,1.0,2.0,0*%\n%ADD10OFF*%\n\
+                       %LS0.5*%\nD10*\nX0Y0D03*\nM02*\n";
+    assert_eq!(
+        summarize_gerber(macro_flash).unwrap().bounds,
+        [0.75, 1.25, -0.25, 0.25]
+    );
+
+    // Block defined and flashed under the same scale
+    let block = "%FSLAX26Y26*%\n%MOMM*%\n%ADD10C,1.0*%\n%LS0.5*%\n%ABD20*%\nD10*\n\
+                 X0Y0D03*\n%AB*%\nD20*\nX0Y0D03*\nM02*\n";
+    assert_eq!(
+        summarize_gerber(block).unwrap().bounds,
+        [-0.25, 0.25, -0.25, 0.25]
+    );
+
+    // Block content at (1, 0) flashed at (2, 0): 0.5 mm from the block origin
+    let offset_block = "%FSLAX26Y26*%\n%MOMM*%\n%ADD10C,1.0*%\n%LS0.5*%\n%ABD20*%\nD10*\n\
+                        X1000000Y0D03*\n%AB*%\nD20*\nX2000000Y0D03*\nM02*\n";
+    assert_eq!(
+        summarize_gerber(offset_block).unwrap().bounds,
+        [2.25, 2.75, -0.25, 0.25]
+    );
+}
+
+#[test]
+fn layer_scale_leaves_coordinates_alone() {
+    // Flash at (1, 2): only the 1 mm circle shrinks
+    let flash = "%FSLAX26Y26*%\n%MOMM*%\n%LS0.5*%\n%ADD10C,1.0*%\nD10*\n\
+                 X1000000Y2000000D03*\nM02*\n";
+    assert_eq!(
+        summarize_gerber(flash).unwrap().bounds,
+        [0.75, 1.25, 1.75, 2.25]
+    );
+
+    // Draw from (1, 0) to (3, 0) with a 0.4 mm aperture, scale set in between
+    let draw = "%FSLAX26Y26*%\n%MOMM*%\n%ADD10C,0.4*%\nD10*\nX1000000Y0D02*\n\
+                %LS0.5*%\nX3000000Y0D01*\nM02*\n";
+    assert_eq!(
+        summarize_gerber(draw).unwrap().bounds,
+        [0.9, 3.1, -0.1, 0.1]
+    );
+
+    // Quarter arc of radius 2 around the origin; arc bounds cover the whole
+    // circle, so they show the unscaled radius and the scaled width
+    let arc = "%FSLAX26Y26*%\n%MOMM*%\n%LS0.5*%\n%ADD10C,0.4*%\nD10*\nG75*\n\
+               X2000000Y0D02*\nG03X0Y2000000I-2000000J0D01*\nM02*\n";
+    assert_eq!(
+        summarize_gerber(arc).unwrap().bounds,
+        [-2.1, 2.1, -2.1, 2.1]
+    );
+}
+
+#[test]
+fn polarity_change_splits_a_region() {
+    // The first triangle is dark, the second one clears part of it
+    let data = "%FSLAX26Y26*%\n%MOMM*%\nG36*\nX0Y0D02*\nX1000000Y0D01*\n\
+                X1000000Y1000000D01*\nX0Y0D01*\n%LPC*%\nX0Y0D02*\nX500000Y0D01*\n\
+                X500000Y500000D01*\nX0Y0D01*\nG37*\nM02*\n";
+    let summary = summarize_gerber(data).unwrap();
+    assert_eq!(summary.sublayers.len(), 2);
+    assert!(summary
+        .sublayers
+        .iter()
+        .all(|sublayer| sublayer.triangles == 1));
+}
+
+#[test]
+fn flash_inside_a_region_is_ignored() {
+    let data = "%FSLAX26Y26*%\n%MOMM*%\n%ADD10C,1.0*%\nD10*\nG36*\nX0Y0D02*\n\
+                X1000000Y0D01*\nX2000000Y2000000D03*\nX1000000Y1000000D01*\nX0Y0D01*\n\
+                G37*\nM02*\n";
+    let summary = summarize_gerber(data).unwrap();
+    assert_eq!(summary.sublayers.len(), 1);
+    assert_eq!(summary.sublayers[0].circles, 0);
+    assert_eq!(summary.sublayers[0].triangles, 1);
+}
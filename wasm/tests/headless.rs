@@ -0,0 +1,94 @@
+//! CPU rendering of features whose shape depends on a rotation or a hole
+//!
+//! Run with `cargo test --features headless --test headless`. The headless
+//! renderer samples the same primitives the shaders draw, with rotations in
+//! radians counterclockwise and holes cut from the per-vertex or
+//! per-instance hole data, so a unit or sign slip or a dropped hole shows up
+//! as pixels appearing in the wrong place.
+
+use wasm_gerber_processor::headless::HeadlessRenderer;
+use wasm_gerber_processor::Boundary;
+
+// 100 px per mm over -1..1 mm
+const SIZE: u32 = 200;
+
+/// Thermal of 2 mm outer, 1 mm inner diameter and 0.2 mm gaps at the origin
+fn thermal(degrees: f32) -> String {
+    format!(
+        "%FSLAX26Y26*%\n%MOMM*%\n%AMTHERMAL*7,0,0,2.0,1.0,0.2,{}*%\n\
+         %ADD10THERMAL*%\nD10*\nX0Y0D03*\nM02*\n",
+        degrees
+    )
+}
+
+/// Alpha of the pixel containing the point on the ring (radius 0.75 mm) at
+/// `degrees` counterclockwise from the X axis
+fn ring_alpha(image: &[u8], degrees: f32) -> u8 {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let column = ((0.75 * cos + 1.0) * 100.0) as usize;
+    let row = ((1.0 - 0.75 * sin) * 100.0) as usize;
+    image[(row * SIZE as usize + column) * 4 + 3]
+}
+
+/// Single 0.8 mm hole pad of aperture `definition` at the origin
+fn pad(definition: &str) -> String {
+    format!(
+        "%FSLAX26Y26*%\n%MOMM*%\n%ADD10{}*%\nD10*\nX0Y0D03*\nM02*\n",
+        definition
+    )
+}
+
+/// Alpha of the pixel containing the point (x, y) in mm
+fn alpha_at(image: &[u8], x: f32, y: f32) -> u8 {
+    let column = ((x + 1.0) * 100.0) as usize;
+    let row = ((1.0 - y) * 100.0) as usize;
+    image[(row * SIZE as usize + column) * 4 + 3]
+}
+
+fn render(content: &str) -> Vec<u8> {
+    let mut renderer = HeadlessRenderer::new();
+    let layer = renderer.add_layer(content).unwrap();
+    let region = Boundary::new(-1.0, 1.0, -1.0, 1.0);
+    renderer
+        .render(&region, SIZE, SIZE, &[layer], &[[1.0, 1.0, 1.0]])
+        .unwrap()
+}
+
+#[test]
+fn thermal_rotated_45_degrees_has_diagonal_gaps() {
+    let image = render(&thermal(45.0));
+    for gap in [45.0, 135.0, 225.0, 315.0] {
+        assert_eq!(ring_alpha(&image, gap), 0, "gap at {}°", gap);
+    }
+    for spoke in [0.0, 90.0, 180.0, 270.0] {
+        assert_eq!(ring_alpha(&image, spoke), 255, "copper at {}°", spoke);
+    }
+}
+
+#[test]
+fn thermal_rotation_is_counterclockwise() {
+    let image = render(&thermal(30.0));
+    assert_eq!(ring_alpha(&image, 30.0), 0);
+    assert_eq!(ring_alpha(&image, 120.0), 0);
+    assert_eq!(ring_alpha(&image, -30.0), 255);
+    assert_eq!(ring_alpha(&image, 60.0), 255);
+}
+
+#[test]
+fn rectangle_and_polygon_pads_keep_their_holes() {
+    for definition in ["R,1.6X1.6X0.8", "P,1.6X6X0X0.8"] {
+        let image = render(&pad(definition));
+        assert_eq!(alpha_at(&image, 0.0, 0.0), 0, "hole of {}", definition);
+        assert_eq!(alpha_at(&image, 0.3, 0.0), 0, "hole of {}", definition);
+        assert_eq!(alpha_at(&image, 0.55, 0.0), 255, "copper of {}", definition);
+    }
+}
+
+#[test]
+fn circle_pad_keeps_its_hole() {
+    let image = render(&pad("C,1.6X0.8"));
+    assert_eq!(alpha_at(&image, 0.0, 0.0), 0);
+    assert_eq!(alpha_at(&image, 0.0, -0.3), 0);
+    assert_eq!(alpha_at(&image, 0.0, 0.55), 255);
+    assert_eq!(alpha_at(&image, 0.85, 0.0), 0);
+}
@@ -0,0 +1,68 @@
+//! Drill hits inferred from coincident pads on both copper sides
+
+use wasm_gerber_processor::GerberProcessor;
+
+// Pad with a 0.8 mm hole at x = 0, plain pads at 5 mm and at 10 mm (top only)
+const TOP: &str = "%FSLAX26Y26*%
+%MOMM*%
+%ADD10C,1.6X0.8*%
+%ADD11C,1.0*%
+D10*
+X0Y0D03*
+D11*
+X5000000Y0D03*
+X10000000Y0D03*
+M02*
+";
+
+const BOTTOM: &str = "%FSLAX26Y26*%
+%MOMM*%
+%ADD10C,1.6X0.8*%
+%ADD11C,1.2*%
+D10*
+X0Y0D03*
+D11*
+X5000000Y0D03*
+M02*
+";
+
+#[test]
+fn coincident_pads_become_drill_hits() {
+    let mut processor = GerberProcessor::new();
+    let top = processor.add_layer(TOP.to_string()).unwrap();
+    let bottom = processor.add_layer(BOTTOM.to_string()).unwrap();
+
+    let drills = processor.infer_drills(top, bottom).unwrap();
+    assert_eq!(drills.count(), 2);
+    assert_eq!(drills.x(), vec![0.0, 5.0]);
+    // The hole of the pad aperture, then half the smaller pad
+    assert_eq!(drills.diameter(), vec![0.8, 0.5]);
+    assert_eq!(drills.estimated(), vec![0, 1]);
+}
+
+#[test]
+fn inferred_drills_export_as_marked_excellon() {
+    let mut processor = GerberProcessor::new();
+    let top = processor.add_layer(TOP.to_string()).unwrap();
+    let bottom = processor.add_layer(BOTTOM.to_string()).unwrap();
+
+    let excellon = processor.infer_drills(top, bottom).unwrap().to_excellon();
+    assert_eq!(
+        excellon,
+        "M48\n\
+         ; Inferred from coincident top and bottom copper pads\n\
+         ; Best effort only - not a fabrication drill file\n\
+         FMAT,2\n\
+         METRIC\n\
+         T1C0.500\n\
+         T2C0.800\n\
+         %\n\
+         G90\n\
+         G05\n\
+         T1\n\
+         X5.000Y0.000\n\
+         T2\n\
+         X0.000Y0.000\n\
+         M30\n"
+    );
+}
@@ -0,0 +1,79 @@
+//! Mirroring and rescaling loaded layers
+
+use wasm_gerber_processor::GerberProcessor;
+
+// Flash at (1, 2), a straight draw and a counterclockwise arc
+const LAYER: &str = "%FSLAX26Y26*%
+%MOMM*%
+%ADD10C,0.5*%
+%ADD11R,1.0X0.4*%
+D11*
+X1000000Y2000000D03*
+D10*
+X0Y0D02*
+X3000000Y0D01*
+G75*
+G03X0Y3000000I-3000000J0D01*
+M02*
+";
+
+#[test]
+fn flip_mirrors_about_the_axis() {
+    let mut processor = GerberProcessor::new();
+    let layer = processor.add_layer(LAYER.to_string()).unwrap();
+    let before = processor.get_boundary_for(&[layer]).unwrap();
+
+    assert_eq!(
+        processor.flip_layer_horizontal(layer, 5.0).unwrap(),
+        "layer_flip_done"
+    );
+    let flashes = processor.get_flashes(layer).unwrap();
+    assert_eq!((flashes.x(), flashes.y()), (vec![9.0], vec![2.0]));
+
+    let after = processor.get_boundary_for(&[layer]).unwrap();
+    assert_eq!(after.min_x(), 10.0 - before.max_x());
+    assert_eq!(after.max_x(), 10.0 - before.min_x());
+    assert_eq!(
+        (after.min_y(), after.max_y()),
+        (before.min_y(), before.max_y())
+    );
+
+    // The arc now turns clockwise around its mirrored center
+    let traces = processor.get_traces(layer).unwrap();
+    assert_eq!(traces.points()[..6], [10.0, 0.0, 7.0, 0.0, 10.0, 3.0]);
+    assert!(traces.sweep()[2] < 0.0);
+    assert_eq!(traces.center()[4..6], [10.0, 0.0]);
+}
+
+#[test]
+fn flipping_twice_restores_the_layer() {
+    let mut processor = GerberProcessor::new();
+    let layer = processor.add_layer(LAYER.to_string()).unwrap();
+    let original = processor.export_gerber(layer).unwrap();
+
+    processor.flip_layer_horizontal(layer, 5.0).unwrap();
+    processor.flip_layer_horizontal(layer, 5.0).unwrap();
+    assert_eq!(processor.export_gerber(layer).unwrap(), original);
+}
+
+#[test]
+fn rescale_converts_inch_values_read_as_mm() {
+    let mut processor = GerberProcessor::new();
+    let layer = processor
+        .add_layer(
+            "%FSLAX26Y26*%
+%MOMM*%
+%ADD10C,0.04*%
+D10*
+X1000000Y500000D03*
+M02*
+"
+            .to_string(),
+        )
+        .unwrap();
+
+    processor.rescale_layer(layer, 25.4).unwrap();
+    let flashes = processor.get_flashes(layer).unwrap();
+    assert_eq!((flashes.x(), flashes.y()), (vec![25.4], vec![12.7]));
+    assert!((flashes.width()[0] - 1.016).abs() < 1e-5);
+}
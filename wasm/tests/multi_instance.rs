@@ -0,0 +1,110 @@
+//! Several GerberProcessor instances used side by side
+//!
+//! Each processor owns its layers, renderer, caches and settings, so pages
+//! can show different jobs in separate viewers. These tests run the parsing
+//! side natively; the renderer is per instance in the same way.
+
+use wasm_gerber_processor::GerberProcessor;
+
+// Two traces and a pad inside 0..10 x 0..4 mm
+const BOARD_A: &str = "%FSLAX26Y26*%
+%MOMM*%
+%ADD10C,0.5*%
+%ADD11R,1.0X0.6*%
+D10*
+X0Y0D02*
+X10000000Y0D01*
+X10000000Y4000000D01*
+D11*
+X5000000Y2000000D03*
+M02*
+";
+
+// A row of round pads around (100, 50) mm, in inches
+const BOARD_B: &str = "%FSLAX24Y24*%
+%MOIN*%
+%ADD10C,0.0600*%
+D10*
+X39000Y19600D03*
+X39500Y19600D03*
+X40000Y19600D03*
+M02*
+";
+
+fn assert_bounds(processor: &GerberProcessor, min_x: f32, max_x: f32) {
+    let bounds = processor.get_boundary().unwrap();
+    assert!(
+        (bounds.min_x() - min_x).abs() < 0.01 && (bounds.max_x() - max_x).abs() < 0.01,
+        "x extent {}..{}, expected {}..{}",
+        bounds.min_x(),
+        bounds.max_x(),
+        min_x,
+        max_x
+    );
+}
+
+#[test]
+fn two_viewers_keep_separate_layers() {
+    let mut a = GerberProcessor::new();
+    let mut b = GerberProcessor::new();
+
+    let layer_a = a.add_layer(BOARD_A.to_string()).unwrap();
+    let layer_b = b.add_layer(BOARD_B.to_string()).unwrap();
+
+    // Layer IDs are allocated per processor
+    assert_eq!(layer_a, 0);
+    assert_eq!(layer_b, 0);
+    assert_ne!(
+        a.layer_hash(layer_a).unwrap(),
+        b.layer_hash(layer_b).unwrap()
+    );
+
+    // Pads at 99.06..101.6 mm with 0.06" (1.524 mm) diameter
+    assert_bounds(&a, -0.25, 10.25);
+    assert_bounds(&b, 98.298, 102.362);
+
+    // Duplicate detection only looks at the processor's own layers
+    assert_eq!(a.find_duplicate_layer(BOARD_B.to_string()), None);
+    assert_eq!(b.find_duplicate_layer(BOARD_B.to_string()), Some(layer_b));
+}
+
+#[test]
+fn changing_one_viewer_leaves_the_other_untouched() {
+    let mut a = GerberProcessor::new();
+    let mut b = GerberProcessor::new();
+    a.add_layer(BOARD_A.to_string()).unwrap();
+    let second = a.add_layer(BOARD_B.to_string()).unwrap();
+    b.add_layer(BOARD_B.to_string()).unwrap();
+
+    a.remove_layer(second).unwrap();
+    assert_bounds(&a, -0.25, 10.25);
+    assert_bounds(&b, 98.298, 102.362);
+
+    a.clear().unwrap();
+    assert_eq!(a.find_duplicate_layer(BOARD_A.to_string()), None);
+    assert_bounds(&a, 0.0, 0.0);
+    assert_bounds(&b, 98.298, 102.362);
+
+    a.dispose().unwrap();
+    assert!(b.layer_hash(0).is_ok());
+}
+
+#[test]
+fn aperture_caches_are_per_viewer() {
+    let mut a = GerberProcessor::new();
+    let mut b = GerberProcessor::new();
+    a.set_aperture_sharing(true);
+    b.set_aperture_sharing(true);
+
+    a.add_layer(BOARD_A.to_string()).unwrap();
+    a.add_layer(BOARD_A.replace("M02*", "X1000000Y1000000D03*\nM02*"))
+        .unwrap();
+    b.add_layer(BOARD_B.to_string()).unwrap();
+
+    // The second layer of viewer A reuses its apertures; viewer B only sees its own
+    let [a_distinct, a_reused] = a.aperture_sharing_stats()[..] else {
+        panic!("expected two counts");
+    };
+    assert_eq!((a_distinct, a_reused), (2, 2));
+    assert_eq!(b.aperture_sharing_stats(), vec![1, 0]);
+}
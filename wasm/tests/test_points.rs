@@ -0,0 +1,168 @@
+//! Test point detection on copper, mask and drill layers
+//!
+//! A round pad only counts as a test point by its shape when it is exposed
+//! by the mask, has a trace attached, no drill hit and room around it for
+//! a probe; pads flashed with a TestPad aperture count without any of that.
+
+use wasm_gerber_processor::GerberProcessor;
+
+// 1 mm pads with traces at x = 0 (free), 10 (drilled), 20 and 21.27 mm
+// (too close together), and a bare pad at 30 mm
+const COPPER: &str = "%FSLAX26Y26*%
+%MOMM*%
+%ADD10C,1.0*%
+%ADD11C,0.2*%
+D10*
+X0Y0D03*
+X10000000Y0D03*
+X20000000Y0D03*
+X21270000Y0D03*
+X30000000Y0D03*
+D11*
+X0Y0D02*
+X0Y5000000D01*
+X10000000Y0D02*
+X10000000Y5000000D01*
+X20000000Y0D02*
+X20000000Y5000000D01*
+X21270000Y0D02*
+X21270000Y5000000D01*
+M02*
+";
+
+// 1.2 mm openings over every pad
+const MASK: &str = "%FSLAX26Y26*%
+%MOMM*%
+%ADD10C,1.2*%
+D10*
+X0Y0D03*
+X10000000Y0D03*
+X20000000Y0D03*
+X21270000Y0D03*
+X30000000Y0D03*
+M02*
+";
+
+const DRILL: &str = "%FSLAX26Y26*%
+%MOMM*%
+%ADD10C,0.4*%
+D10*
+X10000000Y0D03*
+M02*
+";
+
+// Rectangular pad marked as a test pad
+const ATTRIBUTED: &str = "%FSLAX26Y26*%
+%MOMM*%
+%TA.AperFunction,TestPad*%
+%ADD10R,1.5X1.0*%
+%TD*%
+D10*
+X5000000Y5000000D03*
+M02*
+";
+
+/// Copper and mask text for round pads (x, diameter) on y = 0, each with a
+/// trace running up from it and a mask opening 0.2 mm wider than the pad;
+/// `function` is the .AperFunction of the pad apertures
+fn pads(pads: &[(f32, f32)], function: Option<&str>) -> (String, String) {
+    let header = "%FSLAX26Y26*%\n%MOMM*%\n";
+    let mut copper = header.to_string();
+    let mut mask = header.to_string();
+    if let Some(function) = function {
+        copper += &format!("%TA.AperFunction,{}*%\n", function);
+    }
+    for (i, (x, diameter)) in pads.iter().enumerate() {
+        let x = (x * 1e6).round() as i64;
+        copper += &format!(
+            "%ADD{}C,{}*%\nD{}*\nX{}Y0D03*\n",
+            10 + i,
+            diameter,
+            10 + i,
+            x
+        );
+        mask += &format!(
+            "%ADD{}C,{}*%\nD{}*\nX{}Y0D03*\n",
+            10 + i,
+            diameter + 0.2,
+            10 + i,
+            x
+        );
+    }
+    copper += "%TD*%\n%ADD99C,0.2*%\nD99*\n";
+    for (x, _) in pads {
+        let x = (x * 1e6).round() as i64;
+        copper += &format!("X{}Y0D02*\nX{}Y5000000D01*\n", x, x);
+    }
+    copper += "M02*\n";
+    mask += "M02*\n";
+    (copper, mask)
+}
+
+/// X positions of the test points found on `pads`
+fn test_point_x(pads: (String, String)) -> Vec<f32> {
+    let mut processor = GerberProcessor::new();
+    let copper = processor.add_layer(pads.0).unwrap();
+    let mask = processor.add_layer(pads.1).unwrap();
+    processor
+        .get_test_points(copper, Some(mask), None)
+        .unwrap()
+        .x()
+}
+
+#[test]
+fn probed_pad_with_trace_is_a_test_point() {
+    let mut processor = GerberProcessor::new();
+    let copper = processor.add_layer(COPPER.to_string()).unwrap();
+    let mask = processor.add_layer(MASK.to_string()).unwrap();
+    let drill = processor.add_layer(DRILL.to_string()).unwrap();
+
+    let test_points = processor
+        .get_test_points(copper, Some(mask), Some(drill))
+        .unwrap();
+    assert_eq!(test_points.count(), 1);
+    assert_eq!((test_points.x()[0], test_points.y()[0]), (0.0, 0.0));
+    assert_eq!(test_points.width(), vec![1.0]);
+    assert_eq!(test_points.height(), vec![1.0]);
+    assert_eq!(test_points.attributed(), vec![0]);
+}
+
+#[test]
+fn pads_need_1_mm_to_the_next_pad() {
+    // 1 mm pads with 0.9 mm and 1.1 mm between their edges
+    assert!(test_point_x(pads(&[(0.0, 1.0), (1.9, 1.0)], None)).is_empty());
+    assert_eq!(
+        test_point_x(pads(&[(0.0, 1.0), (2.1, 1.0)], None)),
+        vec![0.0, 2.1]
+    );
+}
+
+#[test]
+fn only_pads_from_0_6_to_2_mm_are_test_points() {
+    let found = test_point_x(pads(
+        &[(0.0, 0.5), (10.0, 0.6), (20.0, 2.0), (30.0, 2.1)],
+        None,
+    ));
+    assert_eq!(found, vec![10.0, 20.0]);
+}
+
+#[test]
+fn pads_with_another_function_are_not_test_points() {
+    let round_pad = [(0.0, 1.0)];
+    assert_eq!(test_point_x(pads(&round_pad, None)), vec![0.0]);
+    assert!(test_point_x(pads(&round_pad, Some("SMDPad,CuDef"))).is_empty());
+}
+
+#[test]
+fn test_pad_attribute_marks_a_test_point() {
+    let mut processor = GerberProcessor::new();
+    let copper = processor.add_layer(ATTRIBUTED.to_string()).unwrap();
+
+    // No mask layer needed
+    let test_points = processor.get_test_points(copper, None, None).unwrap();
+    assert_eq!(test_points.count(), 1);
+    assert_eq!((test_points.x()[0], test_points.y()[0]), (5.0, 5.0));
+    assert_eq!(test_points.width(), vec![1.5]);
+    assert_eq!(test_points.height(), vec![1.0]);
+    assert_eq!(test_points.attributed(), vec![1]);
+}